@@ -1,16 +1,33 @@
-//! Benchmark for GRC-20 serialization using city data.
+//! Size report for GRC-20 serialization using city data.
 //!
-//! Demonstrates the builder API with a large dataset (153k cities).
+//! Demonstrates the builder API with a large dataset (153k cities) and
+//! reports encoded sizes, op counts, coarse throughputs, and allocator
+//! stats. Precise timings live in the criterion suite of the library crate
+//! (`cargo bench -p grc-20`).
+//!
+//! `--json FILE` writes the results in machine-readable form, and
+//! `--baseline FILE` compares the run against an earlier report, exiting
+//! non-zero if any metric regressed past its threshold.
+
+mod alloc;
+mod report;
 
 use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
+use std::process;
 use std::time::Instant;
 
 use grc_20::{
-    DataType, EditBuilder, EncodeOptions, EntityBuilder, Id, Op, derived_uuid,
+    EditBuilder, EncodeOptions, EntityBuilder, Id, Op, derived_uuid,
 };
 
+use crate::alloc::CountingAlloc;
+use crate::report::{Allocations, BenchReport, OpCounts, Sizes, Thresholds, Throughput};
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
 /// Creates a deterministic relation ID from from+to+type (to maintain same behavior as removed unique mode).
 fn make_relation_id(from: Id, to: Id, rel_type: Id) -> Id {
     let mut input = [0u8; 48];
@@ -182,10 +199,10 @@ fn build_city_entity<'a>(city: &'a City) -> EntityBuilder<'a> {
         .text(props::NAME, city.name.as_str(), None);
 
     // Native name
-    if let Some(ref native) = city.native {
-        if !native.is_empty() {
-            builder = builder.text(props::NATIVE_NAME, native.as_str(), None);
-        }
+    if let Some(ref native) = city.native
+        && !native.is_empty()
+    {
+        builder = builder.text(props::NATIVE_NAME, native.as_str(), None);
     }
 
     // City type
@@ -233,18 +250,9 @@ fn convert_cities_to_edit<'a>(cities: &'a [City]) -> grc_20::Edit<'a> {
     let mut builder = EditBuilder::new(edit_id)
         .name("Cities Import")
         .author(author_id)
-        .created_at(1704067200_000_000);
+        .created_at(1_704_067_200_000_000);
 
-    // Create schema: properties
-    builder = builder
-        .create_property(props::NAME, DataType::Text)
-        .create_property(props::CODE, DataType::Text)
-        .create_property(props::NATIVE_NAME, DataType::Text)
-        .create_property(props::POPULATION, DataType::Int64)
-        .create_property(props::LOCATION, DataType::Point)
-        .create_property(props::TIMEZONE, DataType::Text)
-        .create_property(props::WIKIDATA_ID, DataType::Text)
-        .create_property(props::CITY_TYPE, DataType::Text);
+    // Property data types are declared per-edit from the values themselves
 
     // Create type entities
     builder = builder
@@ -311,11 +319,85 @@ fn convert_cities_to_edit<'a>(cities: &'a [City]) -> grc_20::Edit<'a> {
     builder.build()
 }
 
+const USAGE: &str = "usage: bench-countries [CITIES_JSON] [options]
+
+options:
+  --json FILE              write results as JSON
+  --baseline FILE          compare against a previous --json report; exits 1 on regression
+  --threshold PCT          allowed change for sizes and allocations (default 1)
+  --time-threshold PCT     allowed change for throughputs (default 10)
+  --iterations N           timed iterations per throughput (default 5)";
+
+struct Args {
+    data_path: String,
+    json: Option<String>,
+    baseline: Option<String>,
+    thresholds: Thresholds,
+    iterations: u32,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        data_path: "../../../out/cities.json".to_string(),
+        json: None,
+        baseline: None,
+        thresholds: Thresholds::default(),
+        iterations: 5,
+    };
+    let mut raw = std::env::args().skip(1);
+    while let Some(arg) = raw.next() {
+        let mut value = |name: &str| raw.next().ok_or(format!("{} requires a value", name));
+        match arg.as_str() {
+            "--json" => args.json = Some(value("--json")?),
+            "--baseline" => args.baseline = Some(value("--baseline")?),
+            "--threshold" => {
+                let v = value("--threshold")?;
+                args.thresholds.deterministic =
+                    v.parse().map_err(|_| format!("invalid threshold {}", v))?;
+            }
+            "--time-threshold" => {
+                let v = value("--time-threshold")?;
+                args.thresholds.noisy = v.parse().map_err(|_| format!("invalid threshold {}", v))?;
+            }
+            "--iterations" => {
+                let v = value("--iterations")?;
+                args.iterations = match v.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid iteration count {}", v)),
+                };
+            }
+            "--help" | "-h" => return Err(String::new()),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => args.data_path = arg,
+        }
+    }
+    Ok(args)
+}
+
+/// Runs `f` once as warmup, then `iterations` times, and returns MB/s over
+/// `bytes` per call.
+fn measure_throughput(bytes: usize, iterations: u32, mut f: impl FnMut()) -> f64 {
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    bytes as f64 * iterations as f64 / seconds / 1_000_000.0
+}
+
 fn main() {
-    // Find the data file (look in out/ directory)
-    let data_path = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "../../../out/cities.json".to_string());
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {}\n", message);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let data_path = args.data_path.clone();
 
     println!("Loading cities from: {}", data_path);
 
@@ -336,21 +418,15 @@ fn main() {
 
     let json_data = fs::read_to_string(&data_path).expect("Failed to read cities.json");
 
-    let parse_start = Instant::now();
     let cities: Vec<City> = serde_json::from_str(&json_data).expect("Failed to parse JSON");
-    let parse_time = parse_start.elapsed();
-
-    println!("Loaded {} cities in {:?}", cities.len(), parse_time);
+    println!("Loaded {} cities", cities.len());
 
     // Convert to GRC-20 using builder API
-    let convert_start = Instant::now();
     let edit = convert_cities_to_edit(&cities);
-    let convert_time = convert_start.elapsed();
 
     // Count statistics
     let mut entity_count = 0;
     let mut relation_count = 0;
-    let mut property_count = 0;
     let mut total_values = 0;
     for op in &edit.ops {
         match op {
@@ -359,160 +435,91 @@ fn main() {
                 total_values += e.values.len();
             }
             Op::CreateRelation(_) => relation_count += 1,
-            Op::CreateProperty(_) => property_count += 1,
             _ => {}
         }
     }
 
+    println!("Converted to {} operations", edit.ops.len());
     println!(
-        "Converted to {} operations in {:?}",
-        edit.ops.len(),
-        convert_time
-    );
-    println!(
-        "  - {} entities, {} relations, {} properties, {} total values",
-        entity_count, relation_count, property_count, total_values
+        "  - {} entities, {} relations, {} total values",
+        entity_count, relation_count, total_values
     );
 
-    // Benchmark encoding (uncompressed, fast mode)
-    let encode_start = Instant::now();
-    let encoded = grc_20::encode_edit(&edit).expect("Failed to encode");
-    let encode_time = encode_start.elapsed();
-
-    println!(
-        "\nUncompressed (fast): {} bytes in {:?}",
-        encoded.len(),
-        encode_time
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (encoded.len() as f64 / 1_000_000.0) / encode_time.as_secs_f64()
-    );
+    // Encode (uncompressed, fast mode)
+    let (encoded, encode_allocs) =
+        alloc::measure(|| grc_20::encode_edit(&edit).expect("Failed to encode"));
+    println!("\nUncompressed (fast): {} bytes", encoded.len());
 
-    // Benchmark encoding (uncompressed, canonical mode)
-    let canonical_start = Instant::now();
+    // Encode (uncompressed, canonical mode) and verify it is deterministic
     let canonical_encoded = grc_20::encode_edit_with_options(&edit, EncodeOptions::canonical())
         .expect("Failed to encode canonical");
-    let canonical_time = canonical_start.elapsed();
-
-    println!(
-        "\nUncompressed (canonical): {} bytes in {:?}",
-        canonical_encoded.len(),
-        canonical_time
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (canonical_encoded.len() as f64 / 1_000_000.0) / canonical_time.as_secs_f64()
-    );
-    println!(
-        "  Overhead vs fast: {:.1}x slower",
-        canonical_time.as_secs_f64() / encode_time.as_secs_f64()
-    );
-
-    // Verify canonical encoding is deterministic
     let canonical_encoded2 = grc_20::encode_edit_with_options(&edit, EncodeOptions::canonical())
         .expect("Failed to encode canonical");
     assert_eq!(
         canonical_encoded, canonical_encoded2,
         "Canonical encoding should be deterministic"
     );
+    println!("Uncompressed (canonical): {} bytes", canonical_encoded.len());
 
-    // Benchmark encoding (compressed)
-    let compress_start = Instant::now();
-    let compressed = grc_20::encode_edit_compressed(&edit, 3).expect("Failed to compress");
-    let compress_time = compress_start.elapsed();
-
-    println!(
-        "\nCompressed (level 3): {} bytes in {:?}",
-        compressed.len(),
-        compress_time
-    );
+    // Encode (compressed)
+    let (compressed, encode_compressed_allocs) = alloc::measure(|| {
+        grc_20::encode_edit_compressed(&edit, 3).expect("Failed to compress")
+    });
+    println!("\nCompressed (level 3): {} bytes", compressed.len());
     println!(
         "  Compression ratio: {:.1}x",
         encoded.len() as f64 / compressed.len() as f64
     );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / compress_time.as_secs_f64()
-    );
-
-    // Benchmark decoding (uncompressed)
-    const DECODE_ITERS: u32 = 10; // Fewer iterations due to larger data
-
-    // Warmup
-    for _ in 0..3 {
-        let _ = grc_20::decode_edit(&encoded).expect("Failed to decode");
-    }
-
-    let decode_start = Instant::now();
-    let mut decoded = None;
-    for _ in 0..DECODE_ITERS {
-        decoded = Some(grc_20::decode_edit(&encoded).expect("Failed to decode"));
-    }
-    let decode_time = decode_start.elapsed() / DECODE_ITERS;
-    let decoded = decoded.unwrap();
-
-    println!(
-        "\nDecode (uncompressed): {:?} (avg of {} iterations)",
-        decode_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s",
-        (encoded.len() as f64 / 1_000_000.0) / decode_time.as_secs_f64()
-    );
-    assert_eq!(decoded.ops.len(), edit.ops.len());
-
-    // Benchmark decoding (compressed, allocating)
-    for _ in 0..3 {
-        let _ = grc_20::decode_edit(&compressed).expect("Failed to decode compressed");
-    }
-
-    let decode_compressed_start = Instant::now();
-    let mut decoded_compressed = None;
-    for _ in 0..DECODE_ITERS {
-        decoded_compressed =
-            Some(grc_20::decode_edit(&compressed).expect("Failed to decode compressed"));
-    }
-    let decode_compressed_time = decode_compressed_start.elapsed() / DECODE_ITERS;
-    let decoded_compressed = decoded_compressed.unwrap();
-
-    println!(
-        "\nDecode (compressed, allocating): {:?} (avg of {} iterations)",
-        decode_compressed_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / decode_compressed_time.as_secs_f64()
-    );
-    assert_eq!(decoded_compressed.ops.len(), edit.ops.len());
-
-    // Benchmark decoding (compressed, zero-copy)
-    for _ in 0..3 {
-        let decompressed = grc_20::decompress(&compressed).expect("Failed to decompress");
-        let _ = grc_20::decode_edit(&decompressed).expect("Failed to decode");
-    }
 
-    let decode_zc_start = Instant::now();
-    for _ in 0..DECODE_ITERS {
-        let decompressed = grc_20::decompress(&compressed).expect("Failed to decompress");
-        let decoded = grc_20::decode_edit(&decompressed).expect("Failed to decode");
-        assert_eq!(decoded.ops.len(), edit.ops.len());
+    // Verify both encodings decode back to the same op count
+    let (decoded_ops, decode_allocs) = alloc::measure(|| {
+        grc_20::decode_edit(&encoded).expect("Failed to decode").ops.len()
+    });
+    assert_eq!(decoded_ops, edit.ops.len());
+    let (decoded_compressed_ops, decode_compressed_allocs) = alloc::measure(|| {
+        grc_20::decode_edit(&compressed).expect("Failed to decode compressed").ops.len()
+    });
+    assert_eq!(decoded_compressed_ops, edit.ops.len());
+
+    // Throughput, in MB/s of uncompressed edit bytes
+    let n = args.iterations;
+    let size = encoded.len();
+    let throughput = Throughput {
+        encode: measure_throughput(size, n, || drop(grc_20::encode_edit(&edit))),
+        encode_compressed: measure_throughput(size, n, || {
+            drop(grc_20::encode_edit_compressed(&edit, 3))
+        }),
+        decode: measure_throughput(size, n, || drop(grc_20::decode_edit(&encoded))),
+        decode_compressed: measure_throughput(size, n, || drop(grc_20::decode_edit(&compressed))),
+    };
+    println!("\n=== Throughput ({} iterations) ===", n);
+    println!("Encode:              {:>8.1} MB/s", throughput.encode);
+    println!("Encode (compressed): {:>8.1} MB/s", throughput.encode_compressed);
+    println!("Decode:              {:>8.1} MB/s", throughput.decode);
+    println!("Decode (compressed): {:>8.1} MB/s", throughput.decode_compressed);
+
+    let allocations = Allocations {
+        encode: encode_allocs,
+        encode_compressed: encode_compressed_allocs,
+        decode: decode_allocs,
+        decode_compressed: decode_compressed_allocs,
+    };
+    println!("\n=== Allocations (one pass) ===");
+    let phases = [
+        ("Encode", &allocations.encode),
+        ("Encode (compressed)", &allocations.encode_compressed),
+        ("Decode", &allocations.decode),
+        ("Decode (compressed)", &allocations.decode_compressed),
+    ];
+    for (phase, stats) in phases {
+        println!(
+            "{:<20} {:>9} allocs, {:>12} bytes, {:>12} peak",
+            format!("{}:", phase),
+            stats.allocations,
+            stats.bytes,
+            stats.peak_bytes
+        );
     }
-    let decode_zc_time = decode_zc_start.elapsed() / DECODE_ITERS;
-
-    println!(
-        "\nDecode (compressed, zero-copy): {:?} (avg of {} iterations)",
-        decode_zc_time, DECODE_ITERS
-    );
-    println!(
-        "  Throughput: {:.2} MB/s (uncompressed equivalent)",
-        (encoded.len() as f64 / 1_000_000.0) / decode_zc_time.as_secs_f64()
-    );
-    println!(
-        "  Speedup vs allocating: {:.1}%",
-        100.0 * (decode_compressed_time.as_secs_f64() - decode_zc_time.as_secs_f64())
-            / decode_compressed_time.as_secs_f64()
-    );
 
     // Write output files
     let input_path = Path::new(&data_path);
@@ -556,4 +563,46 @@ fn main() {
         100.0 * encoded.len() as f64 / json_data.len() as f64,
         100.0 * compressed.len() as f64 / json_data.len() as f64
     );
+
+    let report = BenchReport {
+        dataset: data_path.clone(),
+        cities: cities.len(),
+        json_bytes: json_data.len(),
+        iterations: args.iterations,
+        ops: OpCounts {
+            total: edit.ops.len(),
+            entities: entity_count,
+            relations: relation_count,
+            values: total_values,
+        },
+        sizes: Sizes {
+            uncompressed: encoded.len(),
+            canonical: canonical_encoded.len(),
+            compressed: compressed.len(),
+        },
+        throughput,
+        allocations,
+    };
+
+    if let Some(path) = &args.json {
+        let json = serde_json::to_string_pretty(&report).expect("Failed to serialize report");
+        fs::write(path, json).expect("Failed to write JSON report");
+        println!("\nWrote JSON report to {}", path);
+    }
+
+    if let Some(path) = &args.baseline {
+        let text = fs::read_to_string(path).expect("Failed to read baseline");
+        let baseline: BenchReport = serde_json::from_str(&text).expect("Failed to parse baseline");
+        let changes = report::compare(&baseline, &report, args.thresholds);
+        println!("\n=== Comparison with {} ===", path);
+        for change in &changes {
+            println!("{}", change);
+        }
+        let regressions = changes.iter().filter(|c| c.regressed).count();
+        if regressions > 0 {
+            println!("\n{} metric(s) regressed", regressions);
+            process::exit(1);
+        }
+        println!("\nNo regressions");
+    }
 }
@@ -0,0 +1,193 @@
+//! Machine-readable results and baseline comparison.
+//!
+//! A run can be written as JSON (`--json FILE`) and later passed back as
+//! `--baseline FILE`. Comparison flattens both reports into named metrics
+//! and flags any metric that moved in the wrong direction by more than its
+//! threshold. Sizes, op counts, and allocator stats are deterministic for a
+//! given dataset, so they get a tight threshold; throughputs are noisy and
+//! get a looser one.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alloc::AllocStats;
+
+/// Results of one benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Input file the run was made against.
+    pub dataset: String,
+    pub cities: usize,
+    pub json_bytes: usize,
+    /// Timed iterations per throughput measurement.
+    pub iterations: u32,
+    pub ops: OpCounts,
+    pub sizes: Sizes,
+    pub throughput: Throughput,
+    pub allocations: Allocations,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct OpCounts {
+    pub total: usize,
+    pub entities: usize,
+    pub relations: usize,
+    pub values: usize,
+}
+
+/// Encoded sizes in bytes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Sizes {
+    pub uncompressed: usize,
+    pub canonical: usize,
+    pub compressed: usize,
+}
+
+/// Throughputs in MB/s of uncompressed edit bytes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Throughput {
+    pub encode: f64,
+    pub encode_compressed: f64,
+    pub decode: f64,
+    pub decode_compressed: f64,
+}
+
+/// Allocator activity for a single pass of each phase.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Allocations {
+    pub encode: AllocStats,
+    pub encode_compressed: AllocStats,
+    pub decode: AllocStats,
+    pub decode_compressed: AllocStats,
+}
+
+/// Which direction of change is an improvement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Better {
+    Lower,
+    Higher,
+    /// Informational only; a change never counts as a regression.
+    Neither,
+}
+
+struct Metric {
+    name: String,
+    value: f64,
+    better: Better,
+    noisy: bool,
+}
+
+impl BenchReport {
+    fn metrics(&self) -> Vec<Metric> {
+        let mut out = Vec::new();
+        let mut push = |name: &str, value: f64, better: Better, noisy: bool| {
+            out.push(Metric { name: name.to_string(), value, better, noisy });
+        };
+
+        push("ops.total", self.ops.total as f64, Better::Neither, false);
+        push("ops.entities", self.ops.entities as f64, Better::Neither, false);
+        push("ops.relations", self.ops.relations as f64, Better::Neither, false);
+        push("ops.values", self.ops.values as f64, Better::Neither, false);
+
+        push("size.uncompressed", self.sizes.uncompressed as f64, Better::Lower, false);
+        push("size.canonical", self.sizes.canonical as f64, Better::Lower, false);
+        push("size.compressed", self.sizes.compressed as f64, Better::Lower, false);
+
+        push("throughput.encode", self.throughput.encode, Better::Higher, true);
+        let encode_compressed = self.throughput.encode_compressed;
+        push("throughput.encode_compressed", encode_compressed, Better::Higher, true);
+        push("throughput.decode", self.throughput.decode, Better::Higher, true);
+        let decode_compressed = self.throughput.decode_compressed;
+        push("throughput.decode_compressed", decode_compressed, Better::Higher, true);
+
+        let phases = [
+            ("encode", &self.allocations.encode),
+            ("encode_compressed", &self.allocations.encode_compressed),
+            ("decode", &self.allocations.decode),
+            ("decode_compressed", &self.allocations.decode_compressed),
+        ];
+        for (phase, stats) in phases {
+            let count = stats.allocations as f64;
+            push(&format!("alloc.{}.count", phase), count, Better::Lower, false);
+            push(&format!("alloc.{}.bytes", phase), stats.bytes as f64, Better::Lower, false);
+            let peak = stats.peak_bytes as f64;
+            push(&format!("alloc.{}.peak_bytes", phase), peak, Better::Lower, false);
+        }
+        out
+    }
+}
+
+/// Allowed relative change, in percent, before a metric counts as regressed.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// Sizes and allocator stats.
+    pub deterministic: f64,
+    /// Throughputs.
+    pub noisy: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self { deterministic: 1.0, noisy: 10.0 }
+    }
+}
+
+/// One metric present in both reports.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub name: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// Relative change in percent (positive means the value grew).
+    pub percent: f64,
+    pub regressed: bool,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<36} {:>14.2} -> {:>14.2}  {:>+8.2}%{}",
+            self.name,
+            self.baseline,
+            self.current,
+            self.percent,
+            if self.regressed { "  REGRESSION" } else { "" }
+        )
+    }
+}
+
+/// Compares `current` against `baseline`, metric by metric.
+pub fn compare(
+    baseline: &BenchReport,
+    current: &BenchReport,
+    thresholds: Thresholds,
+) -> Vec<Change> {
+    let old = baseline.metrics();
+    current
+        .metrics()
+        .into_iter()
+        .filter_map(|metric| {
+            let before = old.iter().find(|m| m.name == metric.name)?.value;
+            let percent = if before == 0.0 {
+                if metric.value == 0.0 { 0.0 } else { 100.0 }
+            } else {
+                100.0 * (metric.value - before) / before
+            };
+            let limit = if metric.noisy { thresholds.noisy } else { thresholds.deterministic };
+            let regressed = match metric.better {
+                Better::Lower => percent > limit,
+                Better::Higher => -percent > limit,
+                Better::Neither => false,
+            };
+            Some(Change {
+                name: metric.name,
+                baseline: before,
+                current: metric.value,
+                percent,
+                regressed,
+            })
+        })
+        .collect()
+}
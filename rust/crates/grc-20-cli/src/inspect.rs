@@ -0,0 +1,333 @@
+//! Summaries and diffs of encoded edits (`grc20 inspect`, `grc20 diff`).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use grc_20::codec::op::collect_op_ids;
+use grc_20::compression::{compression_algorithm, edit_dictionary_id, CompressionAlgorithm};
+use grc_20::model::timestamp::format_timestamp;
+use grc_20::{format_id, DictionaryBuilder, Edit, GraphState, Id, Op, Value};
+
+/// Describes the framing of an encoded edit from its magic bytes.
+pub fn wire_format(bytes: &[u8]) -> String {
+    if let Some(algorithm) = compression_algorithm(bytes) {
+        return match algorithm {
+            CompressionAlgorithm::None => "GRC2N (uncompressed envelope)".to_string(),
+            CompressionAlgorithm::Zstd => "GRC2Z (zstd)".to_string(),
+            CompressionAlgorithm::Lz4 => "GRC2L (lz4)".to_string(),
+        };
+    }
+    match edit_dictionary_id(bytes) {
+        Ok(Some(id)) => format!("GRC2D (zstd, dictionary {})", id),
+        _ if bytes.starts_with(b"GRC2B") => "GRC2B (batch)".to_string(),
+        _ if bytes.starts_with(b"GRC2T") => "GRC2T (transaction)".to_string(),
+        _ if bytes.starts_with(b"GRC2") => "GRC2 (uncompressed)".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Summarizes an edit: header, op counts by kind, value counts by type,
+/// and dictionary sizes.
+///
+/// Dictionary sizes are those the encoder would emit for this edit, which
+/// match the file for anything written by this crate.
+pub fn summary(bytes: &[u8], edit: &Edit<'_>) -> Vec<String> {
+    let mut lines = vec![
+        format!("format:     {} ({} bytes)", wire_format(bytes), bytes.len()),
+        format!("id:         {}", format_id(&edit.id)),
+        format!("name:       {:?}", edit.name),
+    ];
+    let created_at = match format_timestamp(edit.created_at, 0) {
+        Some(datetime) => format!("{} ({})", edit.created_at, datetime),
+        None => edit.created_at.to_string(),
+    };
+    lines.push(format!("created_at: {}", created_at));
+    let authors: Vec<String> = edit.authors.iter().map(format_id).collect();
+    let authors = if authors.is_empty() { "-".to_string() } else { authors.join(", ") };
+    lines.push(format!("authors:    {}", authors));
+
+    let mut ops: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut values: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut dicts = DictionaryBuilder::new();
+    for op in &edit.ops {
+        *ops.entry(op_kind(op)).or_default() += 1;
+        let set = match op {
+            Op::CreateEntity(ce) => ce.values.as_slice(),
+            Op::UpdateEntity(ue) => ue.set_properties.as_slice(),
+            _ => &[],
+        };
+        for pv in set {
+            *values.entry(value_kind(&pv.value)).or_default() += 1;
+        }
+        collect_op_ids(op, &mut dicts);
+    }
+
+    lines.push(format!("ops:        {}", edit.ops.len()));
+    lines.extend(ops.iter().map(|(kind, count)| format!("  {:<18} {}", kind, count)));
+    lines.push(format!("values:     {}", values.values().sum::<usize>()));
+    lines.extend(values.iter().map(|(kind, count)| format!("  {:<18} {}", kind, count)));
+
+    let dicts = dicts.build();
+    lines.push("dictionaries:".to_string());
+    lines.push(format!("  {:<18} {}", "properties", dicts.properties.len()));
+    lines.push(format!("  {:<18} {}", "relation types", dicts.relation_types.len()));
+    lines.push(format!("  {:<18} {}", "languages", dicts.languages.len()));
+    lines.push(format!("  {:<18} {}", "units", dicts.units.len()));
+    lines.push(format!("  {:<18} {}", "objects", dicts.objects.len()));
+    lines
+}
+
+/// Compares the states two edits produce when applied to an empty graph.
+///
+/// Reports header differences, then entities and relations that exist on
+/// only one side or whose contents differ. Returns no lines if the edits
+/// are equivalent.
+pub fn diff(left: &Edit<'_>, right: &Edit<'_>) -> Vec<String> {
+    let mut lines = Vec::new();
+    if left.id != right.id {
+        lines.push(format!("edit id: {} -> {}", format_id(&left.id), format_id(&right.id)));
+    }
+    if left.name != right.name {
+        lines.push(format!("name: {:?} -> {:?}", left.name, right.name));
+    }
+    if left.authors != right.authors {
+        let ids = |edit: &Edit<'_>| {
+            edit.authors.iter().map(format_id).collect::<Vec<_>>().join(", ")
+        };
+        lines.push(format!("authors: [{}] -> [{}]", ids(left), ids(right)));
+    }
+    if left.created_at != right.created_at {
+        lines.push(format!("created_at: {} -> {}", left.created_at, right.created_at));
+    }
+
+    let mut before = GraphState::new();
+    before.apply_edit(left);
+    let mut after = GraphState::new();
+    after.apply_edit(right);
+
+    let entity_ids: BTreeSet<_> =
+        before.entities().chain(after.entities()).map(|(id, _)| *id).collect();
+    for id in entity_ids {
+        let name = format_id(&id);
+        match (before.entity(&id), after.entity(&id)) {
+            (Some(_), None) => lines.push(format!("- entity {}", name)),
+            (None, Some(entity)) => {
+                lines.push(format!("+ entity {}", name));
+                for ((property, language), value) in &entity.values {
+                    lines.push(format!("    + {}", slot(property, language, value)));
+                }
+            }
+            (Some(old), Some(new)) if old != new => {
+                lines.push(format!("~ entity {}", name));
+                if old.deleted != new.deleted {
+                    lines.push(format!("    deleted: {} -> {}", old.deleted, new.deleted));
+                }
+                let keys: BTreeSet<_> = old.values.keys().chain(new.values.keys()).collect();
+                for key in keys {
+                    let (property, language) = key;
+                    match (old.values.get(key), new.values.get(key)) {
+                        (Some(value), None) => {
+                            lines.push(format!("    - {}", slot(property, language, value)))
+                        }
+                        (None, Some(value)) => {
+                            lines.push(format!("    + {}", slot(property, language, value)))
+                        }
+                        (Some(a), Some(b)) if a != b => lines.push(format!(
+                            "    ~ {} -> {}",
+                            slot(property, language, a),
+                            format_value(b)
+                        )),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let relation_ids: BTreeSet<_> =
+        before.relations().chain(after.relations()).map(|(id, _)| *id).collect();
+    for id in relation_ids {
+        let name = format_id(&id);
+        match (before.relation(&id), after.relation(&id)) {
+            (Some(_), None) => lines.push(format!("- relation {}", name)),
+            (None, Some(r)) => lines.push(format!(
+                "+ relation {}: {} -[{}]-> {}",
+                name,
+                format_id(&r.from),
+                format_id(&r.relation_type),
+                format_id(&r.to)
+            )),
+            (Some(old), Some(new)) if old != new => {
+                lines.push(format!("~ relation {}", name));
+                lines.push(format!("    - {:?}", old));
+                lines.push(format!("    + {:?}", new));
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Formats a value for display.
+pub fn format_value(value: &Value<'_>) -> String {
+    match value {
+        Value::Text { value, .. } => format!("{:?}", value),
+        Value::Int64 { value, .. } => value.to_string(),
+        Value::Float64 { value, .. } => value.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Date(s) => format!("DATE({})", s),
+        Value::Time(s) => format!("TIME({})", s),
+        Value::Datetime(s) => format!("DATETIME({})", s),
+        Value::Schedule(s) => format!("SCHEDULE({} bytes)", s.len()),
+        Value::Point { lon, lat, alt: Some(alt) } => format!("POINT({}, {}, {})", lon, lat, alt),
+        Value::Point { lon, lat, alt: None } => format!("POINT({}, {})", lon, lat),
+        Value::Bytes(b) => format!("BYTES[{}]", b.len()),
+        Value::Decimal { exponent, mantissa, .. } => {
+            format!("DECIMAL(e={}, m={:?})", exponent, mantissa)
+        }
+        Value::Embedding { sub_type, dims, .. } => {
+            format!("EMBEDDING({:?}, dims={})", sub_type, dims)
+        }
+    }
+}
+
+fn slot(property: &Id, language: &Option<Id>, value: &Value<'_>) -> String {
+    match language {
+        Some(language) => format!(
+            "{}@{} = {}",
+            format_id(property),
+            format_id(language),
+            format_value(value)
+        ),
+        None => format!("{} = {}", format_id(property), format_value(value)),
+    }
+}
+
+fn op_kind(op: &Op<'_>) -> &'static str {
+    match op {
+        Op::CreateEntity(_) => "CreateEntity",
+        Op::UpdateEntity(_) => "UpdateEntity",
+        Op::DeleteEntity(_) => "DeleteEntity",
+        Op::RestoreEntity(_) => "RestoreEntity",
+        Op::CreateRelation(_) => "CreateRelation",
+        Op::UpdateRelation(_) => "UpdateRelation",
+        Op::DeleteRelation(_) => "DeleteRelation",
+        Op::RestoreRelation(_) => "RestoreRelation",
+        Op::CreateValueRef(_) => "CreateValueRef",
+        Op::CreateSpace(_) => "CreateSpace",
+        Op::AddMember(_) => "AddMember",
+        Op::RemoveMember(_) => "RemoveMember",
+        Op::UpdateSpaceMetadata(_) => "UpdateSpaceMetadata",
+        Op::Unknown(_) => "Unknown",
+    }
+}
+
+fn value_kind(value: &Value<'_>) -> &'static str {
+    match value {
+        Value::Bool(_) => "BOOL",
+        Value::Int64 { .. } => "INT64",
+        Value::Float64 { .. } => "FLOAT64",
+        Value::Decimal { .. } => "DECIMAL",
+        Value::Text { .. } => "TEXT",
+        Value::Bytes(_) => "BYTES",
+        Value::Date(_) => "DATE",
+        Value::Time(_) => "TIME",
+        Value::Datetime(_) => "DATETIME",
+        Value::Schedule(_) => "SCHEDULE",
+        Value::Point { .. } => "POINT",
+        Value::Embedding { .. } => "EMBEDDING",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grc_20::{encode_edit, EditBuilder};
+
+    #[test]
+    fn test_diff_reports_value_changes() {
+        let entity = [1u8; 16];
+        let name = [2u8; 16];
+        let left = EditBuilder::new([9u8; 16])
+            .create_entity(entity, |e| e.text(name, "Alice", None))
+            .build();
+        let right = EditBuilder::new([9u8; 16])
+            .create_entity(entity, |e| e.text(name, "Alicia", None))
+            .build();
+
+        assert!(diff(&left, &left).is_empty());
+        let lines = diff(&left, &right);
+        assert_eq!(lines[0], format!("~ entity {}", format_id(&entity)));
+        assert!(lines[1].contains("\"Alice\" -> \"Alicia\""));
+
+        let bytes = encode_edit(&right).unwrap();
+        let summary = summary(&bytes, &right);
+        assert!(summary[0].starts_with("format:     GRC2 (uncompressed)"));
+        assert!(summary.iter().any(|l| l.trim() == "properties         1"));
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let (alice, bob, rel) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let (name, age, knows) = ([10u8; 16], [11u8; 16], [12u8; 16]);
+        let edit = EditBuilder::new([9u8; 16])
+            .name("People")
+            .author([8u8; 16])
+            .create_entity(alice, |e| e.text(name, "Alice", None).int64(age, 30, None))
+            .create_entity(bob, |e| e.text(name, "Bob", None))
+            .update_entity(alice, |u| u.set_int64(age, 31, None))
+            .create_relation_simple(rel, alice, bob, knows)
+            .delete_entity(bob)
+            .build();
+        let bytes = encode_edit(&edit).unwrap();
+        let summary = summary(&bytes, &edit);
+        let expected = [
+            "name:       \"People\"",
+            "created_at: 0 (1970-01-01T00:00:00Z)",
+            "ops:        5",
+            "  CreateEntity       2",
+            "  CreateRelation     1",
+            "  DeleteEntity       1",
+            "  UpdateEntity       1",
+            "values:     4",
+            "  INT64              2",
+            "  TEXT               2",
+            "dictionaries:",
+            "  properties         2",
+            "  relation types     1",
+        ];
+        for line in expected {
+            assert!(summary.iter().any(|l| l == line), "missing {:?} in {:#?}", line, summary);
+        }
+        assert_eq!(summary[4], format!("authors:    {}", format_id(&[8u8; 16])));
+    }
+
+    #[test]
+    fn test_diff_entities_and_relations() {
+        let (alice, bob, carol, rel) = ([1u8; 16], [2u8; 16], [4u8; 16], [3u8; 16]);
+        let (name, knows) = ([10u8; 16], [12u8; 16]);
+        let left = EditBuilder::new([9u8; 16])
+            .create_entity(alice, |e| e.text(name, "Alice", None))
+            .create_entity(bob, |e| e.text(name, "Bob", None))
+            .build();
+        let right = EditBuilder::new([9u8; 16])
+            .name("renamed")
+            .create_entity(alice, |e| e.text(name, "Alice", None))
+            .create_entity(carol, |e| e.text(name, "Carol", None))
+            .create_relation_simple(rel, alice, carol, knows)
+            .delete_entity(alice)
+            .build();
+
+        let lines = diff(&left, &right);
+        let id = format_id;
+        assert_eq!(lines[0], "name: \"\" -> \"renamed\"");
+        assert!(lines.contains(&format!("~ entity {}", id(&alice))));
+        assert!(lines.contains(&"    deleted: false -> true".to_string()));
+        assert!(lines.contains(&format!("- entity {}", id(&bob))));
+        assert!(lines.contains(&format!("+ entity {}", id(&carol))));
+        assert!(lines.contains(&format!("    + {} = \"Carol\"", id(&name))));
+        let relation =
+            format!("+ relation {}: {} -[{}]-> {}", id(&rel), id(&alice), id(&knows), id(&carol));
+        assert!(lines.contains(&relation));
+    }
+}
@@ -0,0 +1,307 @@
+//! JSON rendering of a decoded edit (`grc20 dump --json`).
+//!
+//! One object per op, with IDs as lowercase hex and values tagged by data
+//! type. Byte payloads (BYTES, big decimal mantissas, embeddings) are hex
+//! strings. The output mirrors the in-memory model rather than the wire
+//! layout, so dictionary indices never appear.
+
+use grc_20::{
+    format_id, DecimalMantissa, Edit, Id, MemberRole, Op, PropertyValue, UnsetLanguage,
+    UnsetRelationField, Value,
+};
+
+/// Renders an edit as a pretty-printed JSON object.
+pub fn edit_json(edit: &Edit<'_>) -> String {
+    let ops: Vec<String> = edit.ops.iter().map(|op| format!("    {}", op_json(op))).collect();
+    let ops = if ops.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", ops.join(",\n"))
+    };
+    format!(
+        "{{\n  \"id\": {},\n  \"name\": {},\n  \"authors\": {},\n  \"created_at\": {},\n  \
+         \"ops\": {}\n}}\n",
+        id(&edit.id),
+        string(&edit.name),
+        id_array(&edit.authors),
+        edit.created_at,
+        ops
+    )
+}
+
+fn op_json(op: &Op<'_>) -> String {
+    let fields = match op {
+        Op::CreateEntity(ce) => vec![
+            kind("create_entity"),
+            field("id", id(&ce.id)),
+            field("values", values(&ce.values)),
+        ],
+        Op::UpdateEntity(ue) => {
+            let unset: Vec<String> = ue
+                .unset_values
+                .iter()
+                .map(|u| {
+                    let language = match &u.language {
+                        UnsetLanguage::All => "\"all\"".to_string(),
+                        UnsetLanguage::English => "\"english\"".to_string(),
+                        UnsetLanguage::Specific(language) => id(language),
+                    };
+                    format!("{{\"property\": {}, \"language\": {}}}", id(&u.property), language)
+                })
+                .collect();
+            vec![
+                kind("update_entity"),
+                field("id", id(&ue.id)),
+                field("set", values(&ue.set_properties)),
+                field("unset", format!("[{}]", unset.join(", "))),
+                field("remove", id_array(&ue.remove_values)),
+            ]
+        }
+        Op::DeleteEntity(de) => vec![kind("delete_entity"), field("id", id(&de.id))],
+        Op::RestoreEntity(re) => vec![kind("restore_entity"), field("id", id(&re.id))],
+        Op::CreateRelation(cr) => vec![
+            kind("create_relation"),
+            field("id", id(&cr.id)),
+            field("relation_type", id(&cr.relation_type)),
+            field("from", id(&cr.from)),
+            field("from_is_value_ref", cr.from_is_value_ref.to_string()),
+            field("from_space", optional_id(&cr.from_space)),
+            field("from_version", optional_id(&cr.from_version)),
+            field("to", id(&cr.to)),
+            field("to_is_value_ref", cr.to_is_value_ref.to_string()),
+            field("to_space", optional_id(&cr.to_space)),
+            field("to_version", optional_id(&cr.to_version)),
+            field("entity", optional_id(&cr.entity)),
+            field("position", cr.position.as_deref().map_or("null".to_string(), string)),
+        ],
+        Op::UpdateRelation(ur) => {
+            let unset: Vec<String> = ur
+                .unset
+                .iter()
+                .map(|f| {
+                    let name = match f {
+                        UnsetRelationField::FromSpace => "from_space",
+                        UnsetRelationField::FromVersion => "from_version",
+                        UnsetRelationField::ToSpace => "to_space",
+                        UnsetRelationField::ToVersion => "to_version",
+                        UnsetRelationField::Position => "position",
+                    };
+                    format!("\"{}\"", name)
+                })
+                .collect();
+            vec![
+                kind("update_relation"),
+                field("id", id(&ur.id)),
+                field("from_space", optional_id(&ur.from_space)),
+                field("from_version", optional_id(&ur.from_version)),
+                field("to_space", optional_id(&ur.to_space)),
+                field("to_version", optional_id(&ur.to_version)),
+                field("position", ur.position.as_deref().map_or("null".to_string(), string)),
+                field("unset", format!("[{}]", unset.join(", "))),
+            ]
+        }
+        Op::DeleteRelation(dr) => vec![kind("delete_relation"), field("id", id(&dr.id))],
+        Op::RestoreRelation(rr) => vec![kind("restore_relation"), field("id", id(&rr.id))],
+        Op::CreateValueRef(vr) => vec![
+            kind("create_value_ref"),
+            field("id", id(&vr.id)),
+            field("entity", id(&vr.entity)),
+            field("property", id(&vr.property)),
+            field("language", optional_id(&vr.language)),
+            field("space", optional_id(&vr.space)),
+        ],
+        Op::CreateSpace(cs) => vec![
+            kind("create_space"),
+            field("id", id(&cs.id)),
+            field("name", string(&cs.name)),
+        ],
+        Op::AddMember(am) => vec![
+            kind("add_member"),
+            field("space", id(&am.space)),
+            field("member", id(&am.member)),
+            field("role", role(am.role)),
+        ],
+        Op::RemoveMember(rm) => vec![
+            kind("remove_member"),
+            field("space", id(&rm.space)),
+            field("member", id(&rm.member)),
+            field("role", role(rm.role)),
+        ],
+        Op::UpdateSpaceMetadata(usm) => vec![
+            kind("update_space_metadata"),
+            field("space", id(&usm.space)),
+            field("name", usm.name.as_deref().map_or("null".to_string(), string)),
+            field("description", usm.description.as_deref().map_or("null".to_string(), string)),
+        ],
+        Op::Unknown(op) => vec![
+            kind("unknown"),
+            field("op_type", op.tag.to_string()),
+            field("payload", format!("\"{}\"", hex(&op.bytes))),
+        ],
+    };
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn values(values: &[PropertyValue<'_>]) -> String {
+    let items: Vec<String> = values
+        .iter()
+        .map(|pv| format!("{{\"property\": {}, {}}}", id(&pv.property), value_fields(&pv.value)))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Renders a value as `"type": ..., "value": ...` plus any qualifiers.
+fn value_fields(value: &Value<'_>) -> String {
+    let (data_type, mut fields) = match value {
+        Value::Bool(b) => ("bool", vec![field("value", b.to_string())]),
+        Value::Int64 { value, unit } => (
+            "int64",
+            vec![field("value", value.to_string()), field("unit", optional_id(unit))],
+        ),
+        Value::Float64 { value, unit } => (
+            "float64",
+            vec![field("value", number(*value)), field("unit", optional_id(unit))],
+        ),
+        Value::Decimal { exponent, mantissa, unit } => {
+            let mantissa = match mantissa {
+                DecimalMantissa::I64(m) => m.to_string(),
+                DecimalMantissa::Big(bytes) => format!("\"0x{}\"", hex(bytes)),
+            };
+            (
+                "decimal",
+                vec![
+                    field("exponent", exponent.to_string()),
+                    field("mantissa", mantissa),
+                    field("unit", optional_id(unit)),
+                ],
+            )
+        }
+        Value::Text { value, language } => (
+            "text",
+            vec![field("value", string(value)), field("language", optional_id(language))],
+        ),
+        Value::Bytes(bytes) => ("bytes", vec![field("value", format!("\"{}\"", hex(bytes)))]),
+        Value::Date(s) => ("date", vec![field("value", string(s))]),
+        Value::Time(s) => ("time", vec![field("value", string(s))]),
+        Value::Datetime(s) => ("datetime", vec![field("value", string(s))]),
+        Value::Schedule(s) => ("schedule", vec![field("value", string(s))]),
+        Value::Point { lon, lat, alt } => (
+            "point",
+            vec![
+                field("lon", number(*lon)),
+                field("lat", number(*lat)),
+                field("alt", alt.map_or("null".to_string(), number)),
+            ],
+        ),
+        Value::Embedding { sub_type, dims, data } => (
+            "embedding",
+            vec![
+                field("sub_type", format!("\"{:?}\"", sub_type)),
+                field("dims", dims.to_string()),
+                field("data", format!("\"{}\"", hex(data))),
+            ],
+        ),
+    };
+    fields.insert(0, field("type", format!("\"{}\"", data_type)));
+    fields.join(", ")
+}
+
+fn kind(name: &str) -> String {
+    field("op", format!("\"{}\"", name))
+}
+
+fn field(name: &str, json: String) -> String {
+    format!("\"{}\": {}", name, json)
+}
+
+fn role(role: MemberRole) -> String {
+    match role {
+        MemberRole::Member => "\"member\"".to_string(),
+        MemberRole::Editor => "\"editor\"".to_string(),
+    }
+}
+
+fn id(id: &Id) -> String {
+    format!("\"{}\"", format_id(id))
+}
+
+fn optional_id(value: &Option<Id>) -> String {
+    value.as_ref().map_or("null".to_string(), id)
+}
+
+fn id_array(ids: &[Id]) -> String {
+    let items: Vec<String> = ids.iter().map(id).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// JSON has no NaN or infinities, so those become strings.
+fn number(value: f64) -> String {
+    if value.is_finite() { format!("{:?}", value) } else { format!("\"{}\"", value) }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grc_20::EditBuilder;
+
+    #[test]
+    fn test_edit_json() {
+        let (entity, property) = ([1u8; 16], [2u8; 16]);
+        let edit = EditBuilder::new([9u8; 16])
+            .name("Quote \"test\"")
+            .created_at(42)
+            .create_entity(entity, |e| e.text(property, "a\nb", None).float64(property, 1.5, None))
+            .delete_entity(entity)
+            .build();
+        let (e, p) = (format_id(&entity), format_id(&property));
+        let text =
+            format!(r#"{{"property": "{p}", "type": "text", "value": "a\nb", "language": null}}"#);
+        let float =
+            format!(r#"{{"property": "{p}", "type": "float64", "value": 1.5, "unit": null}}"#);
+        let expected = [
+            "{".to_string(),
+            format!(r#"  "id": "{}","#, format_id(&[9u8; 16])),
+            r#"  "name": "Quote \"test\"","#.to_string(),
+            r#"  "authors": [],"#.to_string(),
+            r#"  "created_at": 42,"#.to_string(),
+            r#"  "ops": ["#.to_string(),
+            format!(r#"    {{"op": "create_entity", "id": "{e}", "values": [{text}, {float}]}},"#),
+            format!(r#"    {{"op": "delete_entity", "id": "{e}"}}"#),
+            "  ]".to_string(),
+            "}\n".to_string(),
+        ]
+        .join("\n");
+        assert_eq!(edit_json(&edit), expected);
+    }
+
+    #[test]
+    fn test_non_finite_numbers_and_control_chars() {
+        assert_eq!(number(f64::NAN), "\"NaN\"");
+        assert_eq!(number(-0.25), "-0.25");
+        assert_eq!(string("\u{1}\t"), "\"\\u0001\\t\"");
+        let empty = EditBuilder::new([0u8; 16]).build();
+        assert!(edit_json(&empty).contains("\"ops\": []"));
+    }
+}
@@ -0,0 +1,392 @@
+//! `grc20`: command-line tools for GRC-20 edits.
+//!
+//! Usage:
+//!
+//! ```text
+//! grc20 inspect FILE...                   summarize edits
+//! grc20 dump [--json] FILE                 print every op
+//! grc20 validate PATH...                   decode, validate, and lint; exit status reports errors
+//! grc20 compress FILE -o OUT [--lz4]       re-encode compressed (zstd by default)
+//! grc20 decompress FILE -o OUT             re-encode as uncompressed GRC2
+//! grc20 diff OLD NEW                       compare the states two edits produce
+//! grc20 tui FILE [--aliases FILE]          browse the ops of one edit
+//! grc20 tui --state FILE... [--aliases F]  browse the state after applying edits in order
+//! grc20 watch PATH... [--interval MS]      recheck edits as they change
+//! grc20 watch --once PATH...               check once; exit status reports errors
+//! ```
+//!
+//! IDs are shown by alias where known: genesis IDs always, plus any from an
+//! alias file (`.json` for JSON, anything else for TOML). `validate` and
+//! `watch` check edits against a schema file given with `--schema`, in the
+//! same formats; `watch` reloads it when it changes. There is no importer,
+//! so `--mapping` is rejected: convert source data to edit files and watch
+//! those. The `tui` command requires the `tui` feature.
+
+mod inspect;
+mod json;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "tui")]
+mod view;
+mod watch;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[cfg(feature = "tui")]
+use grc_20::AliasTable;
+use grc_20::compression::compression_algorithm;
+use grc_20::{decode_edit, decompress, encode_edit_with_compression, CompressionOptions, Edit};
+
+const USAGE: &str = "usage: grc20 <command> [args]
+
+commands:
+  inspect FILE...          summarize format, header, op counts, and dictionary sizes
+  dump FILE                print every op (--json for machine-readable output)
+  validate PATH...         decode, validate, and lint edit files once
+  compress FILE            re-encode with zstd (or --lz4), writing to -o or stdout
+  decompress FILE          re-encode as uncompressed GRC2, writing to -o or stdout
+  diff OLD NEW             compare the states two edits produce
+  tui FILE                 browse the ops of an edit
+  tui --state FILE...      browse the state after applying edits in order
+  watch PATH...            decode, validate, and lint edit files as they change
+
+options:
+  -o, --output FILE        output file for compress and decompress
+  --level N                zstd compression level (default 3)
+  --lz4                    compress with LZ4 instead of zstd
+  --json                   dump as JSON
+  --aliases FILE           show IDs by alias (TOML, or JSON if FILE ends in .json)
+  --schema FILE            validate against a schema file (TOML, or JSON if .json)
+  --interval MS            watch polling interval (default 500)
+  --once                   check once and exit instead of watching";
+
+/// Parsed command-line options shared by all commands.
+struct Args {
+    command: String,
+    files: Vec<String>,
+    aliases: Option<String>,
+    schema: Option<String>,
+    state: bool,
+    interval: Option<u64>,
+    once: bool,
+    output: Option<String>,
+    level: Option<i32>,
+    lz4: bool,
+    json: bool,
+}
+
+fn parse_args(mut raw: impl Iterator<Item = String>) -> Result<Args, String> {
+    let command = raw.next().ok_or("missing command")?;
+    let mut args = Args {
+        command,
+        files: Vec::new(),
+        aliases: None,
+        schema: None,
+        state: false,
+        interval: None,
+        once: false,
+        output: None,
+        level: None,
+        lz4: false,
+        json: false,
+    };
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--aliases" => args.aliases = Some(raw.next().ok_or("--aliases requires a file")?),
+            "--schema" => args.schema = Some(raw.next().ok_or("--schema requires a file")?),
+            "--mapping" => {
+                return Err("--mapping is not supported: grc20 has no importer; convert the \
+                    data to edit files and watch those"
+                    .into())
+            }
+            "--state" => args.state = true,
+            "--once" => args.once = true,
+            "--lz4" => args.lz4 = true,
+            "--json" => args.json = true,
+            "-o" | "--output" => args.output = Some(raw.next().ok_or("-o requires a file")?),
+            "--level" => {
+                let value = raw.next().ok_or("--level requires a number")?;
+                let level = value.parse().map_err(|_| format!("invalid level {}", value))?;
+                args.level = Some(level);
+            }
+            "--interval" => {
+                let value = raw.next().ok_or("--interval requires milliseconds")?;
+                let ms = value.parse().map_err(|_| format!("invalid interval {}", value))?;
+                args.interval = Some(ms);
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => args.files.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(feature = "tui")]
+fn load_aliases(path: Option<&str>) -> Result<AliasTable, String> {
+    let mut aliases = AliasTable::genesis();
+    if let Some(path) = path {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let extra = if path.ends_with(".json") {
+            AliasTable::from_json_str(&text)
+        } else {
+            AliasTable::from_toml_str(&text)
+        };
+        let extra = extra.map_err(|e| format!("{}: {}", path, e))?;
+        aliases.extend(&extra).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    Ok(aliases)
+}
+
+fn watcher(args: &Args) -> Result<watch::Watcher, String> {
+    let roots = args.files.iter().map(PathBuf::from).collect();
+    watch::Watcher::new(roots, args.schema.as_ref().map(PathBuf::from))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn decode<'a>(path: &str, bytes: &'a [u8]) -> Result<Edit<'a>, String> {
+    decode_edit(bytes).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Writes to `-o FILE` if given, otherwise to stdout.
+fn write_output(args: &Args, bytes: &[u8]) -> Result<(), String> {
+    match &args.output {
+        Some(path) => fs::write(path, bytes).map_err(|e| format!("{}: {}", path, e)),
+        None => io::stdout().write_all(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+fn single_file(args: &Args) -> Result<&str, String> {
+    match args.files.as_slice() {
+        [path] => Ok(path),
+        _ => Err(format!("{} takes exactly one file", args.command)),
+    }
+}
+
+fn cmd_inspect(args: &Args) -> Result<(), String> {
+    if args.files.is_empty() {
+        return Err("inspect requires at least one file".into());
+    }
+    for (i, path) in args.files.iter().enumerate() {
+        let bytes = read(path)?;
+        let edit = decode(path, &bytes)?;
+        if i > 0 {
+            println!();
+        }
+        println!("{}", path);
+        for line in inspect::summary(&bytes, &edit) {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_dump(args: &Args) -> Result<(), String> {
+    let path = single_file(args)?;
+    let bytes = read(path)?;
+    let edit = decode(path, &bytes)?;
+    if args.json {
+        print!("{}", json::edit_json(&edit));
+    } else {
+        for (index, op) in edit.ops.iter().enumerate() {
+            println!("#{} {:?}", index, op);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_validate(args: &Args) -> Result<(), String> {
+    if args.files.is_empty() {
+        return Err("validate requires at least one file or directory".into());
+    }
+    if watch::run(watcher(args)?, watch::DEFAULT_INTERVAL, true) {
+        Ok(())
+    } else {
+        Err("errors found".into())
+    }
+}
+
+fn cmd_compress(args: &Args) -> Result<(), String> {
+    let path = single_file(args)?;
+    let bytes = read(path)?;
+    let edit = decode(path, &bytes)?;
+    let options = if args.lz4 {
+        CompressionOptions::lz4()
+    } else {
+        CompressionOptions::zstd(args.level.unwrap_or(3))
+    };
+    let encoded = encode_edit_with_compression(&edit, options).map_err(|e| e.to_string())?;
+    write_output(args, &encoded)
+}
+
+fn cmd_decompress(args: &Args) -> Result<(), String> {
+    let path = single_file(args)?;
+    let bytes = read(path)?;
+    if compression_algorithm(&bytes).is_some() {
+        let decompressed = decompress(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+        return write_output(args, &decompressed);
+    }
+    // Already uncompressed (or GRC2D, which fails here): check it decodes,
+    // then pass it through unchanged.
+    decode(path, &bytes)?;
+    write_output(args, &bytes)
+}
+
+fn cmd_diff(args: &Args) -> Result<(), String> {
+    let [old, new] = args.files.as_slice() else {
+        return Err("diff takes exactly two files".into());
+    };
+    let (old_bytes, new_bytes) = (read(old)?, read(new)?);
+    let lines = inspect::diff(&decode(old, &old_bytes)?, &decode(new, &new_bytes)?);
+    if lines.is_empty() {
+        println!("no differences");
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn cmd_tui(args: &Args) -> Result<(), String> {
+    use grc_20::GraphState;
+
+    let aliases = load_aliases(args.aliases.as_deref())?;
+
+    let (title, rows) = if args.state {
+        if args.files.is_empty() {
+            return Err("tui --state requires at least one file".into());
+        }
+        let mut state = GraphState::new();
+        for path in &args.files {
+            let bytes = read(path)?;
+            let edit = decode(path, &bytes)?;
+            state.apply_edit(&edit);
+        }
+        let title = format!("State of {} edits", args.files.len());
+        (title, view::state_rows(&state, &aliases))
+    } else {
+        let [path] = args.files.as_slice() else {
+            return Err("tui takes exactly one edit file (use --state for several)".into());
+        };
+        let bytes = read(path)?;
+        let edit = decode(path, &bytes)?;
+        let title = if edit.name.is_empty() { path.clone() } else { edit.name.to_string() };
+        (title, view::edit_rows(&edit, &aliases))
+    };
+
+    tui::run(title, rows, &aliases).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "tui"))]
+fn cmd_tui(_args: &Args) -> Result<(), String> {
+    Err("grc20 was built without the `tui` feature".into())
+}
+
+fn cmd_watch(args: &Args) -> Result<(), String> {
+    if args.files.is_empty() {
+        return Err("watch requires at least one file or directory".into());
+    }
+    let interval = args.interval.map_or(watch::DEFAULT_INTERVAL, Duration::from_millis);
+    if watch::run(watcher(args)?, interval, args.once) {
+        Ok(())
+    } else {
+        Err("errors found".into())
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match args.command.as_str() {
+        "inspect" => cmd_inspect(&args),
+        "dump" => cmd_dump(&args),
+        "validate" => cmd_validate(&args),
+        "compress" => cmd_compress(&args),
+        "decompress" => cmd_decompress(&args),
+        "diff" => cmd_diff(&args),
+        "tui" => cmd_tui(&args),
+        "watch" => cmd_watch(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("unknown command {}\n\n{}", other, USAGE)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grc_20::{encode_edit, EditBuilder};
+
+    fn args(command: &str, file: &str, output: &str) -> Args {
+        Args {
+            command: command.to_string(),
+            files: vec![file.to_string()],
+            aliases: None,
+            schema: None,
+            state: false,
+            interval: None,
+            once: false,
+            output: Some(output.to_string()),
+            level: None,
+            lz4: false,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_compress_decompress_round_trip() {
+        let dir = std::env::temp_dir().join(format!("grc20-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        let edit = EditBuilder::new([9u8; 16])
+            .name("round trip")
+            .create_entity([1u8; 16], |e| e.text([2u8; 16], "x".repeat(500), None))
+            .build();
+        let original = encode_edit(&edit).unwrap();
+        fs::write(path("edit.grc2"), &original).unwrap();
+
+        cmd_compress(&args("compress", &path("edit.grc2"), &path("edit.grc2z"))).unwrap();
+        let compressed = fs::read(path("edit.grc2z")).unwrap();
+        assert!(compressed.starts_with(b"GRC2Z"));
+        assert!(compressed.len() < original.len());
+
+        cmd_decompress(&args("decompress", &path("edit.grc2z"), &path("out.grc2"))).unwrap();
+        assert_eq!(fs::read(path("out.grc2")).unwrap(), original);
+        // Uncompressed input passes through unchanged
+        cmd_decompress(&args("decompress", &path("out.grc2"), &path("again.grc2"))).unwrap();
+        assert_eq!(fs::read(path("again.grc2")).unwrap(), original);
+
+        fs::write(path("bad.grc2"), b"GRC2Zjunk").unwrap();
+        assert!(cmd_decompress(&args("decompress", &path("bad.grc2"), &path("x"))).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_args_rejects_mapping() {
+        let raw = ["watch", "--mapping", "map.yaml", "data/"].map(String::from);
+        let err = parse_args(raw.into_iter()).err().unwrap();
+        assert!(err.contains("--mapping is not supported"), "{}", err);
+    }
+}
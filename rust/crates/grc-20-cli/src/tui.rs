@@ -0,0 +1,361 @@
+//! Interactive inspector (`grc20 tui`).
+//!
+//! Layout: the row list on the left, the selected row's details and links
+//! on the top right, and a hex view of its encoded values below.
+//!
+//! Keys: `j`/`k` or arrows move, `/` edits the filter, `Tab` cycles the
+//! focused pane, `Enter` on a link jumps to its target, `Backspace` goes
+//! back, `q` quits.
+
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use grc_20::AliasTable;
+
+use crate::view::{hex_dump, Row};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Rows,
+    Links,
+    Hex,
+}
+
+struct App<'a> {
+    rows: Vec<Row>,
+    aliases: &'a AliasTable,
+    title: String,
+    filter: String,
+    editing_filter: bool,
+    /// Indices into `rows` that pass the filter.
+    visible: Vec<usize>,
+    rows_state: ListState,
+    links_state: ListState,
+    hex_scroll: u16,
+    focus: Pane,
+    /// Previously selected rows, for `Backspace`.
+    history: Vec<usize>,
+    status: String,
+}
+
+/// Runs the inspector over prepared rows until the user quits.
+pub fn run(title: String, rows: Vec<Row>, aliases: &AliasTable) -> io::Result<()> {
+    let mut app = App::new(title, rows, aliases);
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> App<'a> {
+    fn new(title: String, rows: Vec<Row>, aliases: &'a AliasTable) -> Self {
+        App {
+            visible: (0..rows.len()).collect(),
+            rows,
+            aliases,
+            title,
+            filter: String::new(),
+            editing_filter: false,
+            rows_state: ListState::default().with_selected(Some(0)),
+            links_state: ListState::default(),
+            hex_scroll: 0,
+            focus: Pane::Rows,
+            history: Vec::new(),
+            status: String::new(),
+        }
+    }
+}
+
+impl App<'_> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.editing_filter {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.editing_filter = false,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                        self.refilter();
+                    }
+                    KeyCode::Char(c) => {
+                        self.filter.push(c);
+                        self.refilter();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('/') => self.editing_filter = true,
+                KeyCode::Tab => {
+                    self.focus = match self.focus {
+                        Pane::Rows => Pane::Links,
+                        Pane::Links => Pane::Hex,
+                        Pane::Hex => Pane::Rows,
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(20),
+                KeyCode::PageUp => self.move_selection(-20),
+                KeyCode::Enter => self.follow_link(),
+                KeyCode::Backspace => self.go_back(),
+                _ => {}
+            }
+        }
+    }
+
+    fn selected_row(&self) -> Option<usize> {
+        self.rows_state.selected().and_then(|i| self.visible.get(i)).copied()
+    }
+
+    fn refilter(&mut self) {
+        let selected = self.selected_row();
+        self.visible =
+            (0..self.rows.len()).filter(|&i| self.rows[i].matches(&self.filter)).collect();
+        let position = selected.and_then(|row| self.visible.iter().position(|&i| i == row));
+        self.select_visible(position.or((!self.visible.is_empty()).then_some(0)));
+    }
+
+    fn select_visible(&mut self, index: Option<usize>) {
+        self.rows_state.select(index);
+        self.links_state.select(None);
+        self.hex_scroll = 0;
+    }
+
+    fn move_selection(&mut self, delta: i64) {
+        match self.focus {
+            Pane::Rows => {
+                if self.visible.is_empty() {
+                    return;
+                }
+                let current = self.rows_state.selected().unwrap_or(0) as i64;
+                let next = (current + delta).clamp(0, self.visible.len() as i64 - 1);
+                self.select_visible(Some(next as usize));
+            }
+            Pane::Links => {
+                let count = self.selected_row().map_or(0, |i| self.rows[i].links.len());
+                if count == 0 {
+                    return;
+                }
+                let current = self.links_state.selected().unwrap_or(0) as i64;
+                let next = (current + delta).clamp(0, count as i64 - 1);
+                self.links_state.select(Some(next as usize));
+            }
+            Pane::Hex => {
+                self.hex_scroll = (self.hex_scroll as i64 + delta).max(0) as u16;
+            }
+        }
+    }
+
+    /// Jumps to the first row about the selected link's target, clearing
+    /// the filter if it hides that row.
+    fn follow_link(&mut self) {
+        let Some(current) = self.selected_row() else {
+            return;
+        };
+        let link_index = match self.focus {
+            Pane::Links => self.links_state.selected().unwrap_or(0),
+            _ => 0,
+        };
+        let Some(link) = self.rows[current].links.get(link_index) else {
+            return;
+        };
+        let target = link.target;
+        let Some(row) = self.rows.iter().position(|r| r.subject == target) else {
+            self.status = format!("{} is not in this view", self.aliases.display(&target));
+            return;
+        };
+        if !self.visible.contains(&row) {
+            self.filter.clear();
+            self.visible = (0..self.rows.len()).collect();
+        }
+        self.history.push(current);
+        self.status.clear();
+        self.focus = Pane::Rows;
+        let position = self.visible.iter().position(|&i| i == row);
+        self.select_visible(position);
+    }
+
+    fn go_back(&mut self) {
+        let Some(row) = self.history.pop() else {
+            return;
+        };
+        if !self.visible.contains(&row) {
+            self.filter.clear();
+            self.visible = (0..self.rows.len()).collect();
+        }
+        let position = self.visible.iter().position(|&i| i == row);
+        self.select_visible(position);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+        let [detail, links, hex] = Layout::vertical([
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+        ])
+        .areas(right);
+
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+        let pane = |title: String, focused: bool| {
+            let block = Block::bordered().title(title);
+            if focused {
+                block.border_style(Style::default().add_modifier(Modifier::BOLD))
+            } else {
+                block
+            }
+        };
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| ListItem::new(format!("{:<16} {}", self.rows[i].kind, self.rows[i].title)))
+            .collect();
+        let list_title = format!("{} ({}/{})", self.title, self.visible.len(), self.rows.len());
+        let list = List::new(items)
+            .block(pane(list_title, self.focus == Pane::Rows))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(list, left, &mut self.rows_state);
+
+        let row = self.selected_row().map(|i| &self.rows[i]);
+
+        let detail_lines: Vec<Line> = row
+            .map(|r| {
+                std::iter::once(Line::from(r.title.clone()))
+                    .chain(r.details.iter().map(|d| Line::from(d.clone())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let detail_view = Paragraph::new(detail_lines).block(pane("Detail".into(), false));
+        frame.render_widget(detail_view, detail);
+
+        let link_items: Vec<ListItem> = row
+            .map(|r| {
+                r.links
+                    .iter()
+                    .map(|l| format!("{} {}", l.label, self.aliases.display(&l.target)))
+                    .map(ListItem::new)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let link_list = List::new(link_items)
+            .block(pane("Links".into(), self.focus == Pane::Links))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(link_list, links, &mut self.links_state);
+
+        let mut hex_lines = Vec::new();
+        for raw in row.map(|r| r.raw_values.as_slice()).unwrap_or_default() {
+            hex_lines.push(Line::from(format!("{} ({} bytes)", raw.label, raw.bytes.len())));
+            hex_lines.extend(hex_dump(&raw.bytes).into_iter().map(Line::from));
+        }
+        let hex_view = Paragraph::new(hex_lines)
+            .block(pane("Value bytes".into(), self.focus == Pane::Hex))
+            .scroll((self.hex_scroll, 0));
+        frame.render_widget(hex_view, hex);
+
+        let footer_text = if self.editing_filter {
+            format!("filter: {}_", self.filter)
+        } else if !self.status.is_empty() {
+            self.status.clone()
+        } else {
+            format!(
+                "[/] filter{}  [tab] pane  [enter] follow  [backspace] back  [q] quit",
+                if self.filter.is_empty() { String::new() } else { format!(" ({})", self.filter) }
+            )
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::edit_rows;
+    use grc_20::{EditBuilder, Id};
+
+    const ALICE: Id = [1u8; 16];
+    const BOB: Id = [2u8; 16];
+    const NAME: Id = [10u8; 16];
+    const KNOWS: Id = [11u8; 16];
+
+    /// Rows: 0 CreateEntity alice, 1 CreateEntity bob, 2 CreateRelation alice -> bob.
+    fn app(aliases: &AliasTable) -> App<'_> {
+        let edit = EditBuilder::new([9u8; 16])
+            .create_entity(ALICE, |e| e.text(NAME, "Alice", None))
+            .create_entity(BOB, |e| e.text(NAME, "Bob", None))
+            .create_relation_simple([3u8; 16], ALICE, BOB, KNOWS)
+            .build();
+        App::new("test".to_string(), edit_rows(&edit, aliases), aliases)
+    }
+
+    #[test]
+    fn test_refilter_keeps_selection() {
+        let aliases = AliasTable::new();
+        let mut app = app(&aliases);
+        app.select_visible(Some(1));
+        app.filter = "createentity".to_string();
+        app.refilter();
+        assert_eq!(app.visible, [0, 1]);
+        assert_eq!(app.selected_row(), Some(1));
+
+        // The selected row is filtered out, so the first visible one is selected
+        app.filter = "relation".to_string();
+        app.refilter();
+        assert_eq!(app.visible, [2]);
+        assert_eq!(app.selected_row(), Some(2));
+
+        app.filter = "no such row".to_string();
+        app.refilter();
+        assert!(app.visible.is_empty());
+        assert_eq!(app.selected_row(), None);
+    }
+
+    #[test]
+    fn test_follow_link_and_go_back() {
+        let aliases = AliasTable::new();
+        let mut app = app(&aliases);
+        app.filter = "relation".to_string();
+        app.refilter();
+
+        // Following "to" clears the filter that hides bob's row
+        app.focus = Pane::Links;
+        app.links_state.select(Some(1));
+        app.follow_link();
+        assert_eq!(app.selected_row(), Some(1));
+        assert!(app.filter.is_empty());
+        assert_eq!(app.visible, [0, 1, 2]);
+        assert_eq!(app.focus, Pane::Rows);
+
+        app.go_back();
+        assert_eq!(app.selected_row(), Some(2));
+        app.go_back();
+        assert_eq!(app.selected_row(), Some(2));
+
+        // The relation type has no row, so the selection stays put
+        app.focus = Pane::Links;
+        app.links_state.select(Some(2));
+        app.follow_link();
+        assert_eq!(app.selected_row(), Some(2));
+        assert!(app.status.ends_with("is not in this view"));
+        assert!(app.history.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! Browsable rows for an edit or an applied state.
+//!
+//! Both inspector modes flatten their input into [`Row`]s: one per op when
+//! browsing an edit, one per entity and relation when browsing a state.
+//! Rows carry their own detail lines, navigable links, and raw value bytes,
+//! so front ends only have to lay them out.
+
+use grc_20::codec::value::encode_value;
+use grc_20::codec::Writer;
+use grc_20::{
+    AliasTable, DictionaryBuilder, Edit, GraphState, Id, Op, PropertyValue, Value, NIL_ID,
+};
+
+use crate::inspect::format_value;
+
+/// One browsable item.
+#[derive(Debug, Clone)]
+pub struct Row {
+    /// Short kind label, e.g. "CreateEntity" or "entity".
+    pub kind: &'static str,
+    /// The entity or relation the row is about.
+    pub subject: Id,
+    /// One-line summary.
+    pub title: String,
+    /// Detail lines for the detail pane.
+    pub details: Vec<String>,
+    /// Objects reachable from this row.
+    pub links: Vec<Link>,
+    /// Encoded value payloads, for the hex pane.
+    pub raw_values: Vec<RawValue>,
+}
+
+/// A navigable reference to another object.
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// How the target relates to the row.
+    pub label: String,
+    /// Target entity or relation.
+    pub target: Id,
+}
+
+/// The wire payload of one value.
+#[derive(Debug, Clone)]
+pub struct RawValue {
+    /// Property the value belongs to.
+    pub label: String,
+    /// Value payload as encoded on the wire.
+    pub bytes: Vec<u8>,
+}
+
+impl Row {
+    /// Returns true if the row matches a case-insensitive filter.
+    pub fn matches(&self, filter: &str) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+        let filter = filter.to_lowercase();
+        self.kind.to_lowercase().contains(&filter)
+            || self.title.to_lowercase().contains(&filter)
+            || self.details.iter().any(|d| d.to_lowercase().contains(&filter))
+    }
+}
+
+/// Builds one row per op of an edit.
+pub fn edit_rows(edit: &Edit<'_>, aliases: &AliasTable) -> Vec<Row> {
+    let name = |id: &Id| aliases.display(id);
+    edit.ops
+        .iter()
+        .enumerate()
+        .map(|(index, op)| {
+            let mut row = match op {
+                Op::CreateEntity(ce) => {
+                    let mut row = Row::new("CreateEntity", ce.id, name(&ce.id));
+                    add_values(&mut row, &ce.values, aliases);
+                    row
+                }
+                Op::UpdateEntity(ue) => {
+                    let mut row = Row::new("UpdateEntity", ue.id, name(&ue.id));
+                    add_values(&mut row, &ue.set_properties, aliases);
+                    for unset in &ue.unset_values {
+                        row.details.push(format!(
+                            "unset {} ({:?})",
+                            name(&unset.property),
+                            unset.language
+                        ));
+                    }
+                    for value_id in &ue.remove_values {
+                        row.details.push(format!("remove value {}", name(value_id)));
+                    }
+                    row
+                }
+                Op::DeleteEntity(de) => Row::new("DeleteEntity", de.id, name(&de.id)),
+                Op::RestoreEntity(re) => Row::new("RestoreEntity", re.id, name(&re.id)),
+                Op::CreateRelation(cr) => {
+                    let mut row = Row::new(
+                        "CreateRelation",
+                        cr.id,
+                        format!(
+                            "{} -[{}]-> {}",
+                            name(&cr.from),
+                            name(&cr.relation_type),
+                            name(&cr.to)
+                        ),
+                    );
+                    row.details.push(format!("id: {}", name(&cr.id)));
+                    if let Some(position) = &cr.position {
+                        row.details.push(format!("position: {}", position));
+                    }
+                    row.link("from", cr.from);
+                    row.link("to", cr.to);
+                    row.link("type", cr.relation_type);
+                    if let Some(entity) = cr.entity {
+                        row.link("entity", entity);
+                    }
+                    row
+                }
+                Op::UpdateRelation(ur) => {
+                    let mut row = Row::new("UpdateRelation", ur.id, name(&ur.id));
+                    if let Some(position) = &ur.position {
+                        row.details.push(format!("position: {}", position));
+                    }
+                    for field in &ur.unset {
+                        row.details.push(format!("unset {:?}", field));
+                    }
+                    row
+                }
+                Op::DeleteRelation(dr) => Row::new("DeleteRelation", dr.id, name(&dr.id)),
+                Op::RestoreRelation(rr) => Row::new("RestoreRelation", rr.id, name(&rr.id)),
+                Op::CreateValueRef(vr) => {
+                    let mut row = Row::new(
+                        "CreateValueRef",
+                        vr.id,
+                        format!("{}.{}", name(&vr.entity), name(&vr.property)),
+                    );
+                    row.link("entity", vr.entity);
+                    row
+                }
+                Op::CreateSpace(cs) => {
+                    let mut row = Row::new("CreateSpace", cs.id, name(&cs.id));
+                    row.details.push(format!("name: {}", cs.name));
+                    row
+                }
+                Op::AddMember(am) => {
+                    let mut row = Row::new("AddMember", am.space, name(&am.member));
+                    row.details.push(format!("role: {:?}", am.role));
+                    row.link("space", am.space);
+                    row.link("member", am.member);
+                    row
+                }
+                Op::RemoveMember(rm) => {
+                    let mut row = Row::new("RemoveMember", rm.space, name(&rm.member));
+                    row.details.push(format!("role: {:?}", rm.role));
+                    row.link("space", rm.space);
+                    row.link("member", rm.member);
+                    row
+                }
+                Op::UpdateSpaceMetadata(usm) => {
+                    let mut row = Row::new("UpdateSpaceMetadata", usm.space, name(&usm.space));
+                    if let Some(space_name) = &usm.name {
+                        row.details.push(format!("name: {}", space_name));
+                    }
+                    if let Some(description) = &usm.description {
+                        row.details.push(format!("description: {}", description));
+                    }
+                    row
+                }
+                Op::Unknown(op) => Row::new(
+                    "Unknown",
+                    NIL_ID,
+                    format!("type 0x{:02x}, {} bytes", op.tag, op.bytes.len()),
+                ),
+            };
+            row.details.insert(0, format!("op #{}", index));
+            row
+        })
+        .collect()
+}
+
+/// Builds one row per entity, then one per relation, of an applied state.
+///
+/// Entity rows link to the other end of every relation touching them.
+pub fn state_rows(state: &GraphState, aliases: &AliasTable) -> Vec<Row> {
+    let name = |id: &Id| aliases.display(id);
+
+    let mut entities: Vec<_> = state.entities().collect();
+    entities.sort_unstable_by_key(|(id, _)| **id);
+    let mut relations: Vec<_> = state.relations().collect();
+    relations.sort_unstable_by_key(|(id, _)| **id);
+
+    let mut rows = Vec::with_capacity(entities.len() + relations.len());
+    for (id, entity) in entities {
+        let kind = if entity.deleted { "entity (deleted)" } else { "entity" };
+        let mut row = Row::new(kind, *id, name(id));
+        let values: Vec<PropertyValue<'_>> = entity
+            .values
+            .iter()
+            .map(|((property, _), value)| PropertyValue {
+                property: *property,
+                value: value.clone(),
+                clock: None,
+            })
+            .collect();
+        add_values(&mut row, &values, aliases);
+        for (relation_id, relation) in &relations {
+            if relation.from == *id {
+                row.link(&format!("-[{}]->", name(&relation.relation_type)), relation.to);
+            } else if relation.to == *id {
+                row.link(&format!("<-[{}]-", name(&relation.relation_type)), relation.from);
+            } else {
+                continue;
+            }
+            row.link("relation", **relation_id);
+        }
+        rows.push(row);
+    }
+    for (id, relation) in relations {
+        let kind = if relation.deleted { "relation (deleted)" } else { "relation" };
+        let mut row = Row::new(
+            kind,
+            *id,
+            format!(
+                "{} -[{}]-> {}",
+                name(&relation.from),
+                name(&relation.relation_type),
+                name(&relation.to)
+            ),
+        );
+        if let Some(position) = &relation.position {
+            row.details.push(format!("position: {}", position));
+        }
+        row.link("from", relation.from);
+        row.link("to", relation.to);
+        row.link("entity", relation.entity);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Formats bytes as `offset  hex  ascii` lines of 16 bytes.
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  {}", i * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
+
+impl Row {
+    fn new(kind: &'static str, subject: Id, title: String) -> Self {
+        Self {
+            kind,
+            subject,
+            title,
+            details: Vec::new(),
+            links: Vec::new(),
+            raw_values: Vec::new(),
+        }
+    }
+
+    fn link(&mut self, label: &str, target: Id) {
+        self.links.push(Link { label: label.to_string(), target });
+    }
+}
+
+fn add_values(row: &mut Row, values: &[PropertyValue<'_>], aliases: &AliasTable) {
+    for pv in values {
+        let mut label = aliases.display(&pv.property);
+        if let Value::Text { language: Some(language), .. } = &pv.value {
+            label = format!("{}@{}", label, aliases.display(language));
+        }
+        row.details.push(format!("{} = {}", label, format_value(&pv.value)));
+
+        let mut writer = Writer::new();
+        let mut dicts = DictionaryBuilder::new();
+        if encode_value(&mut writer, &pv.value, &mut dicts).is_ok() {
+            row.raw_values.push(RawValue { label, bytes: writer.into_bytes() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grc_20::EditBuilder;
+
+    const ALICE: Id = [1u8; 16];
+    const BOB: Id = [2u8; 16];
+    const REL: Id = [3u8; 16];
+    const NAME: Id = [10u8; 16];
+    const KNOWS: Id = [11u8; 16];
+
+    fn aliases() -> AliasTable {
+        let mut aliases = AliasTable::new();
+        for (id, alias) in [(ALICE, "alice"), (BOB, "bob"), (NAME, "name"), (KNOWS, "knows")] {
+            aliases.insert(id, alias).unwrap();
+        }
+        aliases
+    }
+
+    fn edit() -> Edit<'static> {
+        EditBuilder::new([9u8; 16])
+            .create_entity(ALICE, |e| e.text(NAME, "Alice", None))
+            .create_entity(BOB, |e| e.text(NAME, "Bob", None))
+            .create_relation_simple(REL, ALICE, BOB, KNOWS)
+            .delete_entity(BOB)
+            .build()
+    }
+
+    #[test]
+    fn test_edit_rows() {
+        let rows = edit_rows(&edit(), &aliases());
+        let kinds: Vec<_> = rows.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, ["CreateEntity", "CreateEntity", "CreateRelation", "DeleteEntity"]);
+
+        assert_eq!(rows[0].title, "alice");
+        assert_eq!(rows[0].details, ["op #0", "name = \"Alice\""]);
+        assert_eq!(rows[0].raw_values.len(), 1);
+        assert_eq!(rows[0].raw_values[0].label, "name");
+        // Length-prefixed text, then language index 0 (none)
+        assert_eq!(rows[0].raw_values[0].bytes, b"\x05Alice\x00");
+
+        assert_eq!(rows[2].subject, REL);
+        assert_eq!(rows[2].title, "alice -[knows]-> bob");
+        let links: Vec<_> = rows[2].links.iter().map(|l| (l.label.as_str(), l.target)).collect();
+        assert_eq!(links, [("from", ALICE), ("to", BOB), ("type", KNOWS)]);
+        assert_eq!(rows[3].details, ["op #3"]);
+    }
+
+    #[test]
+    fn test_state_rows() {
+        let mut state = GraphState::new();
+        state.apply_edit(&edit());
+        let rows = state_rows(&state, &aliases());
+
+        // Entities sorted by ID (including the relation's entity), then relations
+        let kinds: Vec<_> = rows.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, ["entity", "entity (deleted)", "entity", "relation"]);
+        let alice = &rows[0];
+        assert_eq!(alice.details, ["name = \"Alice\""]);
+        let links: Vec<_> = alice.links.iter().map(|l| (l.label.as_str(), l.target)).collect();
+        assert_eq!(links, [("-[knows]->", BOB), ("relation", REL)]);
+        let bob = &rows[1];
+        assert_eq!(bob.links[0].label, "<-[knows]-");
+        assert_eq!(bob.links[0].target, ALICE);
+        assert_eq!(rows[3].title, "alice -[knows]-> bob");
+    }
+
+    #[test]
+    fn test_row_matches() {
+        let rows = edit_rows(&edit(), &aliases());
+        assert!(rows[0].matches(""));
+        assert!(rows[0].matches("createentity"));
+        assert!(rows[0].matches("ALI"));
+        // Detail lines are searched too
+        assert!(rows[0].matches("\"alice\""));
+        assert!(!rows[0].matches("bob"));
+        assert!(rows[2].matches("KNOWS"));
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let bytes: Vec<u8> = b"GRC2 edit\x00\x01\xff".iter().copied().chain(0..6).collect();
+        let lines = hex_dump(&bytes);
+        assert_eq!(
+            lines,
+            [
+                "00000000  47 52 43 32 20 65 64 69 74 00 01 ff 00 01 02 03  GRC2 edit.......",
+                "00000010  04 05                                            ..",
+            ]
+        );
+        assert!(hex_dump(&[]).is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! Watch mode (`grc20 watch`).
+//!
+//! Polls a set of edit files and directories, and whenever a file changes
+//! re-runs decoding, validation, and value lints on it. IDs that are not
+//! valid UUIDs are reported as warnings. Only files whose diagnostics
+//! changed are reported, so a long session prints one block per save
+//! rather than the whole tree each time.
+//!
+//! Edits are validated against a schema file if one is given (see
+//! [`SchemaContext::from_toml_str`]). The schema file is polled too: when
+//! it changes it is reloaded and every edit is rechecked. Deprecation
+//! markers are collected across every watched edit on top of it, so writing
+//! a property deprecated in another file is reported as well.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use grc_20::model::bytes::BytesPolicy;
+use grc_20::{
+    decode_edit, validate_edit_all, validate_ids, validate_value, IdPolicy, Op, SchemaContext,
+    Severity,
+};
+
+/// Default polling interval.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Diagnostics for one file, in op order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileReport {
+    /// Number of error-level diagnostics.
+    pub errors: usize,
+    /// Formatted diagnostics.
+    pub lines: Vec<String>,
+}
+
+struct Watched {
+    modified: Option<SystemTime>,
+    bytes: Vec<u8>,
+    /// Last printed report (None until first checked).
+    report: Option<FileReport>,
+}
+
+/// The schema file edits are validated against.
+struct SchemaFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+/// Polls edit files and a schema file, keeping each file's diagnostics.
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    schema_file: Option<SchemaFile>,
+    base: SchemaContext,
+    files: BTreeMap<PathBuf, Watched>,
+}
+
+impl Watcher {
+    /// Creates a watcher over `roots`, validating against the schema file
+    /// at `schema` if given.
+    ///
+    /// Fails if the schema file cannot be loaded. Later changes that fail
+    /// to load are printed and the previous schema is kept.
+    pub fn new(roots: Vec<PathBuf>, schema: Option<PathBuf>) -> Result<Self, String> {
+        let (base, schema_file) = match schema {
+            Some(path) => {
+                let modified = modified(&path);
+                (load_schema(&path)?, Some(SchemaFile { path, modified }))
+            }
+            None => (SchemaContext::new(), None),
+        };
+        Ok(Self { roots, schema_file, base, files: BTreeMap::new() })
+    }
+
+    /// Checks the schema file and edit files for changes once, rechecking
+    /// edits and printing the reports that changed.
+    ///
+    /// Returns whether anything changed.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = self.reload_schema();
+        let current = collect_files(&self.roots);
+
+        let removed: Vec<PathBuf> =
+            self.files.keys().filter(|path| !current.contains(*path)).cloned().collect();
+        for path in removed {
+            self.files.remove(&path);
+            println!("{}: removed", path.display());
+            changed = true;
+        }
+        for path in current {
+            let modified = modified(&path);
+            let unchanged = |w: &Watched| w.modified == modified && modified.is_some();
+            if self.files.get(&path).is_some_and(unchanged) {
+                continue;
+            }
+            let bytes = fs::read(&path).unwrap_or_default();
+            self.files.insert(path, Watched { modified, bytes, report: None });
+            changed = true;
+        }
+
+        if changed {
+            // Deprecations may be declared in any watched edit, so every
+            // file is rechecked, but only changed reports are printed.
+            let mut schema = self.base.clone();
+            for watched in self.files.values() {
+                if let Ok(edit) = decode_edit(&watched.bytes) {
+                    schema.apply_deprecations(&edit);
+                }
+            }
+            for (path, watched) in self.files.iter_mut() {
+                let report = check(&watched.bytes, &schema);
+                if watched.report.as_ref() != Some(&report) {
+                    print_report(path, &report);
+                }
+                watched.report = Some(report);
+            }
+        }
+        changed
+    }
+
+    /// Returns the latest report for a watched file.
+    #[cfg(test)]
+    fn report(&self, path: &Path) -> Option<&FileReport> {
+        self.files.get(path).and_then(|w| w.report.as_ref())
+    }
+
+    /// Returns whether every watched file was checked and has no errors.
+    pub fn is_clean(&self) -> bool {
+        self.files.values().all(|w| w.report.as_ref().is_some_and(|r| r.errors == 0))
+    }
+
+    /// Reloads the schema file if it changed, returning whether it did.
+    fn reload_schema(&mut self) -> bool {
+        let Some(file) = &mut self.schema_file else {
+            return false;
+        };
+        let modified = modified(&file.path);
+        if file.modified == modified {
+            return false;
+        }
+        file.modified = modified;
+        match load_schema(&file.path) {
+            Ok(schema) => {
+                println!("{}: reloaded", file.path.display());
+                self.base = schema;
+                true
+            }
+            Err(e) => {
+                println!("error: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Watches until interrupted, printing diagnostics as files change.
+///
+/// With `once`, checks every file a single time and returns whether all of
+/// them are free of errors.
+pub fn run(mut watcher: Watcher, interval: Duration, once: bool) -> bool {
+    loop {
+        watcher.poll();
+        if once {
+            return watcher.is_clean();
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Loads a schema file: JSON if the path ends in `.json`, TOML otherwise.
+pub fn load_schema(path: &Path) -> Result<SchemaContext, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let schema = if path.extension().is_some_and(|ext| ext == "json") {
+        SchemaContext::from_json_str(&text)
+    } else {
+        SchemaContext::from_toml_str(&text)
+    };
+    schema.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Decodes, validates, and lints one encoded edit.
+pub fn check(bytes: &[u8], schema: &SchemaContext) -> FileReport {
+    let edit = match decode_edit(bytes) {
+        Ok(edit) => edit,
+        Err(e) => return FileReport { errors: 1, lines: vec![format!("error: {}", e)] },
+    };
+
+    let mut report = FileReport::default();
+    let mut issues = validate_edit_all(&edit, schema);
+    issues.extend(validate_ids(&edit, IdPolicy::Warn));
+    issues.sort_by_key(|issue| issue.op_index);
+    let mut issues = issues.into_iter().peekable();
+    let policy = BytesPolicy::default();
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        while let Some(issue) = issues.next_if(|i| i.op_index == op_index) {
+            let level = match issue.severity {
+                Severity::Error => {
+                    report.errors += 1;
+                    "error"
+                }
+                Severity::Warning => "warning",
+            };
+            report.lines.push(format!("op #{}: {}: {}", op_index, level, issue.error));
+        }
+        let values = match op {
+            Op::CreateEntity(ce) => &ce.values,
+            Op::UpdateEntity(ue) => &ue.set_properties,
+            _ => continue,
+        };
+        for pv in values {
+            let problem = validate_value(&pv.value).or_else(|| pv.value.lint_bytes(&policy));
+            if let Some(problem) = problem {
+                report.lines.push(format!("op #{}: warning: {}", op_index, problem));
+            }
+        }
+    }
+    report
+}
+
+fn print_report(path: &Path, report: &FileReport) {
+    if report.lines.is_empty() {
+        println!("{}: ok", path.display());
+        return;
+    }
+    println!("{}: {} diagnostic(s)", path.display(), report.lines.len());
+    for line in &report.lines {
+        println!("  {}", line);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Lists regular files under `roots`, skipping hidden entries.
+fn collect_files(roots: &[PathBuf]) -> Vec<PathBuf> {
+    fn walk(path: &Path, out: &mut Vec<PathBuf>) {
+        if path.is_dir() {
+            let Ok(entries) = fs::read_dir(path) else {
+                return;
+            };
+            let mut children: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            children.sort();
+            let visible = |p: &PathBuf| {
+                !p.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'))
+            };
+            for child in children.iter().filter(|p| visible(p)) {
+                walk(child, out);
+            }
+        } else if path.is_file() {
+            out.push(path.to_path_buf());
+        }
+    }
+
+    let mut out = Vec::new();
+    for root in roots {
+        walk(root, &mut out);
+    }
+    out.sort();
+    out.dedup();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grc_20::{encode_edit, EditBuilder};
+
+    #[test]
+    fn test_check_uses_schema() {
+        let edit = EditBuilder::new([9u8; 16])
+            .create_entity([1u8; 16], |e| e.text([2u8; 16], "thirty", None))
+            .build();
+        let bytes = encode_edit(&edit).unwrap();
+        assert_eq!(check(&bytes, &SchemaContext::new()).errors, 0);
+
+        let toml = format!("[[properties]]\nid = \"{}\"\ndata_type = \"int64\"\n", "02".repeat(16));
+        let schema = SchemaContext::from_toml_str(&toml).unwrap();
+        let report = check(&bytes, &schema);
+        assert_eq!(report.errors, 1, "{:?}", report.lines);
+    }
+
+    #[test]
+    fn test_poll_reloads_schema() {
+        let dir = std::env::temp_dir().join(format!("grc20-watch-{}", std::process::id()));
+        let edits = dir.join("edits");
+        fs::create_dir_all(&edits).unwrap();
+        let edit_path = edits.join("edit.grc2");
+        let schema_path = dir.join("schema.toml");
+
+        let edit = EditBuilder::new([9u8; 16])
+            .create_entity([1u8; 16], |e| e.text([2u8; 16], "thirty", None))
+            .build();
+        fs::write(&edit_path, encode_edit(&edit).unwrap()).unwrap();
+        let schema = |data_type: &str| {
+            format!("[[properties]]\nid = \"{}\"\ndata_type = \"{}\"\n", "02".repeat(16), data_type)
+        };
+        fs::write(&schema_path, schema("text")).unwrap();
+
+        let mut watcher = Watcher::new(vec![edits], Some(schema_path.clone())).unwrap();
+        assert!(watcher.poll());
+        assert_eq!(watcher.report(&edit_path).unwrap().errors, 0);
+        assert!(!watcher.poll());
+
+        // Changing only the schema rechecks the unchanged edit
+        fs::write(&schema_path, schema("int64")).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(1);
+        fs::File::options().write(true).open(&schema_path).unwrap().set_modified(later).unwrap();
+        assert!(watcher.poll());
+        assert_eq!(watcher.report(&edit_path).unwrap().errors, 1);
+        assert!(!watcher.is_clean());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
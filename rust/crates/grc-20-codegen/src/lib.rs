@@ -0,0 +1,465 @@
+//! Rust code generation from GRC-20 schema files.
+//!
+//! [`generate`] turns a [`SchemaContext`] into Rust source with one struct
+//! per type that declares properties (the `properties` key of a `[[types]]`
+//! entry in the schema file). Each struct has an `id` field and one
+//! `Option` field per property, ID constants, `to_ops()` to create the
+//! entity, and `from_entity()` to read it back from resolved state:
+//!
+//! ```text
+//! pub struct City {
+//!     pub id: grc_20::Id,
+//!     pub name: Option<String>,
+//!     pub population: Option<i64>,
+//! }
+//!
+//! impl City {
+//!     pub const TYPE_ID: grc_20::Id = [...];
+//!     pub const NAME: grc_20::Id = [...];
+//!     pub const POPULATION: grc_20::Id = [...];
+//!     pub fn to_ops(&self) -> Vec<grc_20::Op<'static>> { ... }
+//!     pub fn from_entity(id: grc_20::Id, entity: &grc_20::EntityState) -> Option<Self> { ... }
+//! }
+//! ```
+//!
+//! From a build script, [`generate_file`] reads a `.toml` or `.json` schema
+//! file and writes the generated module for `include!`:
+//!
+//! ```text
+//! // build.rs
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("schema.rs");
+//! grc_20_codegen::generate_file("schema.toml", out).unwrap();
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/schema.rs"));
+//! ```
+//!
+//! Struct and field names come from the `name` keys of the schema file, in
+//! PascalCase and snake_case. Field types follow the property's data type:
+//!
+//! | Data type                            | Field type               |
+//! |--------------------------------------|--------------------------|
+//! | BOOL                                 | `bool`                   |
+//! | INT64, FLOAT64                       | `i64`, `f64`             |
+//! | TEXT, DATE, TIME, DATETIME, SCHEDULE | `String`                 |
+//! | BYTES                                | `Vec<u8>`                |
+//! | DECIMAL, POINT, EMBEDDING            | `grc_20::Value<'static>` |
+//!
+//! Numeric properties with a dimension also keep the whole value, so that
+//! their unit survives. TEXT fields hold the value without a language.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use grc_20::{format_id, DataType, Id, SchemaContext, SchemaFileError};
+use thiserror::Error;
+
+/// Error while generating code from a schema.
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    #[error("failed to read or write {path}: {source}")]
+    Io { path: PathBuf, source: std::io::Error },
+
+    #[error("schema file {path}: {source}")]
+    Schema { path: PathBuf, source: SchemaFileError },
+
+    #[error("schema file {path} is neither .toml nor .json")]
+    UnknownFormat { path: PathBuf },
+
+    #[error("ID {} has no name", format_id(.id))]
+    MissingName { id: Id },
+
+    #[error("name {name:?} does not make a Rust identifier")]
+    InvalidName { name: String },
+
+    #[error("{name} is generated more than once in {scope}")]
+    DuplicateName { scope: String, name: String },
+
+    #[error("type {type_name} lists property {} which is not in the schema", format_id(.property))]
+    UnknownProperty { type_name: String, property: Id },
+}
+
+/// Generates Rust source for every type with declared properties, in ID
+/// order.
+pub fn generate(schema: &SchemaContext) -> Result<String, CodegenError> {
+    let mut types: Vec<(&Id, &[Id])> = schema.type_properties().collect();
+    types.sort_unstable_by_key(|(id, _)| **id);
+
+    let mut out = String::from("// @generated by grc-20-codegen. Do not edit.\n");
+    let mut struct_names = Vec::new();
+    for (type_id, properties) in types {
+        let name = name_of(schema, type_id)?;
+        let struct_name = pascal_case(name)?;
+        if struct_names.contains(&struct_name) {
+            return Err(duplicate("the module", struct_name));
+        }
+        struct_names.push(struct_name.clone());
+
+        let fields = properties
+            .iter()
+            .map(|property| Field::new(schema, &struct_name, property))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, field) in fields.iter().enumerate() {
+            if ["id", "type_id"].contains(&field.name.as_str())
+                || fields[..i].iter().any(|f| f.name == field.name)
+            {
+                return Err(duplicate(&struct_name, field.name.clone()));
+            }
+        }
+        out.push('\n');
+        write_type(&mut out, &struct_name, name, type_id, &fields);
+    }
+    Ok(out)
+}
+
+/// Reads a `.toml` or `.json` schema file and writes the generated source
+/// to `out`.
+///
+/// Meant for build scripts: it also tells Cargo to rerun the build script
+/// when the schema file changes.
+pub fn generate_file(schema: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<(), CodegenError> {
+    let (schema, out) = (schema.as_ref(), out.as_ref());
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| CodegenError::Io { path, source }
+    };
+    let text = fs::read_to_string(schema).map_err(io_error(schema))?;
+    let parsed = match schema.extension().and_then(|e| e.to_str()) {
+        Some("toml") => SchemaContext::from_toml_str(&text),
+        Some("json") => SchemaContext::from_json_str(&text),
+        _ => return Err(CodegenError::UnknownFormat { path: schema.to_path_buf() }),
+    };
+    let parsed =
+        parsed.map_err(|source| CodegenError::Schema { path: schema.to_path_buf(), source })?;
+    fs::write(out, generate(&parsed)?).map_err(io_error(out))?;
+    println!("cargo:rerun-if-changed={}", schema.display());
+    Ok(())
+}
+
+/// How a property maps to a Rust field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int64,
+    Float64,
+    Text,
+    /// A string-valued variant other than TEXT, e.g. `Date`.
+    Str(&'static str),
+    Bytes,
+    /// The whole value, matched by a pattern such as `Decimal { .. }`.
+    Value(&'static str),
+}
+
+impl Kind {
+    fn new(data_type: DataType, has_dimension: bool) -> Self {
+        match data_type {
+            DataType::Int64 if has_dimension => Kind::Value("Int64 { .. }"),
+            DataType::Float64 if has_dimension => Kind::Value("Float64 { .. }"),
+            DataType::Decimal => Kind::Value("Decimal { .. }"),
+            DataType::Point => Kind::Value("Point { .. }"),
+            DataType::Embedding => Kind::Value("Embedding { .. }"),
+            DataType::Bool => Kind::Bool,
+            DataType::Int64 => Kind::Int64,
+            DataType::Float64 => Kind::Float64,
+            DataType::Text => Kind::Text,
+            DataType::Date => Kind::Str("Date"),
+            DataType::Time => Kind::Str("Time"),
+            DataType::Datetime => Kind::Str("Datetime"),
+            DataType::Schedule => Kind::Str("Schedule"),
+            DataType::Bytes => Kind::Bytes,
+        }
+    }
+
+    fn rust_type(self) -> &'static str {
+        match self {
+            Kind::Bool => "bool",
+            Kind::Int64 => "i64",
+            Kind::Float64 => "f64",
+            Kind::Text | Kind::Str(_) => "String",
+            Kind::Bytes => "Vec<u8>",
+            Kind::Value(_) => "grc_20::Value<'static>",
+        }
+    }
+
+    /// Expression converting `v: &T` into a value.
+    fn value_expr(self) -> String {
+        match self {
+            Kind::Bool => "grc_20::Value::Bool(*v)".to_string(),
+            Kind::Int64 => "grc_20::Value::Int64 { value: *v, unit: None }".to_string(),
+            Kind::Float64 => "grc_20::Value::Float64 { value: *v, unit: None }".to_string(),
+            Kind::Text => {
+                "grc_20::Value::Text { value: v.clone().into(), language: None }".to_string()
+            }
+            Kind::Str(variant) => format!("grc_20::Value::{variant}(v.clone().into())"),
+            Kind::Bytes => "grc_20::Value::Bytes(v.clone().into())".to_string(),
+            Kind::Value(_) => "v.clone()".to_string(),
+        }
+    }
+
+    /// Match arm converting a `Some(&Value)` into `Some(T)`.
+    fn read_arm(self) -> String {
+        match self {
+            Kind::Bool => "Some(grc_20::Value::Bool(v)) => Some(*v)".to_string(),
+            Kind::Int64 => "Some(grc_20::Value::Int64 { value, .. }) => Some(*value)".to_string(),
+            Kind::Float64 => {
+                "Some(grc_20::Value::Float64 { value, .. }) => Some(*value)".to_string()
+            }
+            Kind::Text => {
+                "Some(grc_20::Value::Text { value, .. }) => Some(value.to_string())".to_string()
+            }
+            Kind::Str(variant) => {
+                format!("Some(grc_20::Value::{variant}(v)) => Some(v.to_string())")
+            }
+            Kind::Bytes => "Some(grc_20::Value::Bytes(v)) => Some(v.to_vec())".to_string(),
+            Kind::Value(pattern) => {
+                format!("Some(v @ grc_20::Value::{pattern}) => Some(v.clone())")
+            }
+        }
+    }
+}
+
+/// A generated struct field.
+struct Field {
+    property: Id,
+    /// Property name from the schema, for doc comments.
+    label: String,
+    /// snake_case field name.
+    name: String,
+    /// SCREAMING_SNAKE_CASE name of the property ID constant.
+    constant: String,
+    kind: Kind,
+}
+
+impl Field {
+    fn new(schema: &SchemaContext, type_name: &str, property: &Id) -> Result<Self, CodegenError> {
+        let property_schema = schema.get_property_schema(property).ok_or_else(|| {
+            CodegenError::UnknownProperty { type_name: type_name.to_string(), property: *property }
+        })?;
+        let label = name_of(schema, property)?;
+        Ok(Self {
+            property: *property,
+            label: label.to_string(),
+            name: snake_case(label)?,
+            constant: words(label).join("_").to_uppercase(),
+            kind: Kind::new(property_schema.data_type, property_schema.dimension.is_some()),
+        })
+    }
+}
+
+fn write_type(out: &mut String, struct_name: &str, label: &str, type_id: &Id, fields: &[Field]) {
+    // Writing to a String cannot fail
+    let _ = writeln!(out, "/// {label} (type `{}`).", format_id(type_id));
+    out.push_str("#[derive(Debug, Clone, PartialEq, Default)]\n");
+    let _ = writeln!(out, "pub struct {struct_name} {{");
+    out.push_str("    /// Entity ID.\n    pub id: grc_20::Id,\n");
+    for field in fields {
+        let _ = writeln!(out, "    /// {}.", field.label);
+        let _ = writeln!(out, "    pub {}: Option<{}>,", field.name, field.kind.rust_type());
+    }
+    out.push_str("}\n\n");
+
+    let _ = writeln!(out, "impl {struct_name} {{");
+    write_id_const(out, &format!("{label} type"), "TYPE_ID", type_id);
+    for field in fields {
+        write_id_const(out, &format!("{} property", field.label), &field.constant, &field.property);
+    }
+
+    out.push_str(
+        "    /// Returns ops creating the entity with its set fields, and its type\n\
+         \x20   /// relation.\n\
+         \x20   pub fn to_ops(&self) -> Vec<grc_20::Op<'static>> {\n\
+         \x20       let mut values = Vec::new();\n",
+    );
+    for field in fields {
+        let _ = write!(
+            out,
+            "        if let Some(v) = &self.{} {{\n\
+             \x20           values.push(grc_20::PropertyValue {{\n\
+             \x20               property: Self::{},\n\
+             \x20               value: {},\n\
+             \x20               clock: None,\n\
+             \x20           }});\n\
+             \x20       }}\n",
+            field.name,
+            field.constant,
+            field.kind.value_expr(),
+        );
+    }
+    out.push_str(
+        "        let types = grc_20::genesis::relation_types::types();\n\
+         \x20       vec![\n\
+         \x20           grc_20::Op::CreateEntity(grc_20::CreateEntity { id: self.id, values }),\n\
+         \x20           grc_20::Op::CreateRelation(grc_20::CreateRelation {\n\
+         \x20               id: grc_20::model::id::unique_relation_id(&self.id, &Self::TYPE_ID, &types),\n\
+         \x20               relation_type: types,\n\
+         \x20               from: self.id,\n\
+         \x20               from_is_value_ref: false,\n\
+         \x20               from_space: None,\n\
+         \x20               from_version: None,\n\
+         \x20               to: Self::TYPE_ID,\n\
+         \x20               to_is_value_ref: false,\n\
+         \x20               to_space: None,\n\
+         \x20               to_version: None,\n\
+         \x20               entity: None,\n\
+         \x20               position: None,\n\
+         \x20           }),\n\
+         \x20       ]\n\
+         \x20   }\n\n",
+    );
+
+    out.push_str(
+        "    /// Reads the fields from resolved state. Values of another data type\n\
+         \x20   /// are skipped. Returns None if the entity is tombstoned.\n\
+         \x20   pub fn from_entity(id: grc_20::Id, entity: &grc_20::EntityState) -> Option<Self> {\n\
+         \x20       if entity.deleted {\n\
+         \x20           return None;\n\
+         \x20       }\n\
+         \x20       Some(Self {\n\
+         \x20           id,\n",
+    );
+    for field in fields {
+        let _ = write!(
+            out,
+            "            {}: match entity.value(&Self::{}, None) {{\n\
+             \x20               {},\n\
+             \x20               _ => None,\n\
+             \x20           }},\n",
+            field.name,
+            field.constant,
+            field.kind.read_arm(),
+        );
+    }
+    out.push_str("        })\n    }\n}\n");
+}
+
+fn write_id_const(out: &mut String, label: &str, name: &str, id: &Id) {
+    let bytes: Vec<String> = id.iter().map(|b| format!("0x{b:02x}")).collect();
+    let _ = write!(
+        out,
+        "    /// {label} ID (`{}`).\n\
+         \x20   pub const {name}: grc_20::Id = [\n\
+         \x20       {},\n\
+         \x20       {},\n\
+         \x20   ];\n\n",
+        format_id(id),
+        bytes[..8].join(", "),
+        bytes[8..].join(", "),
+    );
+}
+
+fn name_of<'a>(schema: &'a SchemaContext, id: &Id) -> Result<&'a str, CodegenError> {
+    schema.get_name(id).ok_or(CodegenError::MissingName { id: *id })
+}
+
+fn duplicate(scope: &str, name: String) -> CodegenError {
+    CodegenError::DuplicateName { scope: scope.to_string(), name }
+}
+
+/// Splits a name into words at non-alphanumeric characters and at
+/// lowercase-to-uppercase boundaries ("wikidataID" is "wikidata", "ID").
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            words.extend((!current.is_empty()).then(|| std::mem::take(&mut current)));
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        current.push(c);
+    }
+    words.extend((!current.is_empty()).then_some(current));
+    words
+}
+
+/// Returns the words of `name` if they make an identifier.
+fn checked_words(name: &str) -> Result<Vec<String>, CodegenError> {
+    let words = words(name);
+    match words.first().and_then(|w| w.chars().next()) {
+        Some(c) if !c.is_numeric() => Ok(words),
+        _ => Err(CodegenError::InvalidName { name: name.to_string() }),
+    }
+}
+
+fn pascal_case(name: &str) -> Result<String, CodegenError> {
+    let mut out: String = checked_words(name)?
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().into_iter().flat_map(char::to_uppercase);
+            first.chain(chars.flat_map(char::to_lowercase)).collect::<String>()
+        })
+        .collect();
+    if out == "Self" {
+        out.push('_');
+    }
+    Ok(out)
+}
+
+fn snake_case(name: &str) -> Result<String, CodegenError> {
+    let mut out = checked_words(name)?.join("_").to_lowercase();
+    if KEYWORDS.contains(&out.as_str()) {
+        out.push('_');
+    }
+    Ok(out)
+}
+
+/// Rust keywords, which cannot be field names.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(pascal_case("city").unwrap(), "City");
+        assert_eq!(pascal_case("Sports team").unwrap(), "SportsTeam");
+        assert_eq!(pascal_case("self").unwrap(), "Self_");
+        assert_eq!(snake_case("Wikidata ID").unwrap(), "wikidata_id");
+        assert_eq!(snake_case("nativeName").unwrap(), "native_name");
+        assert_eq!(snake_case("Type").unwrap(), "type_");
+        assert_eq!(snake_case("Population (2020)").unwrap(), "population_2020");
+        assert!(matches!(snake_case("2nd name"), Err(CodegenError::InvalidName { .. })));
+        assert!(matches!(snake_case("--"), Err(CodegenError::InvalidName { .. })));
+    }
+
+    #[test]
+    fn test_generate_errors() {
+        let mut schema = SchemaContext::new();
+        schema.add_type_property([1u8; 16], [2u8; 16]);
+        assert!(
+            matches!(generate(&schema), Err(CodegenError::MissingName { id }) if id == [1u8; 16])
+        );
+
+        schema.set_name([1u8; 16], "City");
+        assert!(matches!(generate(&schema), Err(CodegenError::UnknownProperty { .. })));
+
+        schema.add_property([2u8; 16], DataType::Text);
+        schema.set_name([2u8; 16], "ID");
+        assert!(matches!(generate(&schema), Err(CodegenError::DuplicateName { .. })));
+
+        schema.set_name([2u8; 16], "Name");
+        schema.add_type_property([3u8; 16], [2u8; 16]);
+        schema.set_name([3u8; 16], "city");
+        assert!(matches!(generate(&schema), Err(CodegenError::DuplicateName { .. })));
+
+        schema.set_name([3u8; 16], "Town");
+        let code = generate(&schema).unwrap();
+        assert!(code.contains("pub struct City {"));
+        assert!(code.contains("pub struct Town {"));
+        assert!(code.contains("pub name: Option<String>,"));
+    }
+}
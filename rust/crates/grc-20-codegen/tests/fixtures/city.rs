@@ -0,0 +1,215 @@
+// @generated by grc-20-codegen. Do not edit.
+
+/// City (type `b1b2c3d4e5f6071829304050a1b2c3d4`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct City {
+    /// Entity ID.
+    pub id: grc_20::Id,
+    /// Name.
+    pub name: Option<String>,
+    /// Population.
+    pub population: Option<i64>,
+    /// Location.
+    pub location: Option<grc_20::Value<'static>>,
+    /// Wikidata ID.
+    pub wikidata_id: Option<String>,
+}
+
+impl City {
+    /// City type ID (`b1b2c3d4e5f6071829304050a1b2c3d4`).
+    pub const TYPE_ID: grc_20::Id = [
+        0xb1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd4,
+    ];
+
+    /// Name property ID (`a1b2c3d4e5f6071829304050a1b2c3d4`).
+    pub const NAME: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd4,
+    ];
+
+    /// Population property ID (`a1b2c3d4e5f6071829304050a1b2c3d7`).
+    pub const POPULATION: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd7,
+    ];
+
+    /// Location property ID (`a1b2c3d4e5f6071829304050a1b2c3d8`).
+    pub const LOCATION: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd8,
+    ];
+
+    /// Wikidata ID property ID (`a1b2c3d4e5f6071829304050a1b2c3da`).
+    pub const WIKIDATA_ID: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xda,
+    ];
+
+    /// Returns ops creating the entity with its set fields, and its type
+    /// relation.
+    pub fn to_ops(&self) -> Vec<grc_20::Op<'static>> {
+        let mut values = Vec::new();
+        if let Some(v) = &self.name {
+            values.push(grc_20::PropertyValue {
+                property: Self::NAME,
+                value: grc_20::Value::Text { value: v.clone().into(), language: None },
+                clock: None,
+            });
+        }
+        if let Some(v) = &self.population {
+            values.push(grc_20::PropertyValue {
+                property: Self::POPULATION,
+                value: grc_20::Value::Int64 { value: *v, unit: None },
+                clock: None,
+            });
+        }
+        if let Some(v) = &self.location {
+            values.push(grc_20::PropertyValue {
+                property: Self::LOCATION,
+                value: v.clone(),
+                clock: None,
+            });
+        }
+        if let Some(v) = &self.wikidata_id {
+            values.push(grc_20::PropertyValue {
+                property: Self::WIKIDATA_ID,
+                value: grc_20::Value::Text { value: v.clone().into(), language: None },
+                clock: None,
+            });
+        }
+        let types = grc_20::genesis::relation_types::types();
+        vec![
+            grc_20::Op::CreateEntity(grc_20::CreateEntity { id: self.id, values }),
+            grc_20::Op::CreateRelation(grc_20::CreateRelation {
+                id: grc_20::model::id::unique_relation_id(&self.id, &Self::TYPE_ID, &types),
+                relation_type: types,
+                from: self.id,
+                from_is_value_ref: false,
+                from_space: None,
+                from_version: None,
+                to: Self::TYPE_ID,
+                to_is_value_ref: false,
+                to_space: None,
+                to_version: None,
+                entity: None,
+                position: None,
+            }),
+        ]
+    }
+
+    /// Reads the fields from resolved state. Values of another data type
+    /// are skipped. Returns None if the entity is tombstoned.
+    pub fn from_entity(id: grc_20::Id, entity: &grc_20::EntityState) -> Option<Self> {
+        if entity.deleted {
+            return None;
+        }
+        Some(Self {
+            id,
+            name: match entity.value(&Self::NAME, None) {
+                Some(grc_20::Value::Text { value, .. }) => Some(value.to_string()),
+                _ => None,
+            },
+            population: match entity.value(&Self::POPULATION, None) {
+                Some(grc_20::Value::Int64 { value, .. }) => Some(*value),
+                _ => None,
+            },
+            location: match entity.value(&Self::LOCATION, None) {
+                Some(v @ grc_20::Value::Point { .. }) => Some(v.clone()),
+                _ => None,
+            },
+            wikidata_id: match entity.value(&Self::WIKIDATA_ID, None) {
+                Some(grc_20::Value::Text { value, .. }) => Some(value.to_string()),
+                _ => None,
+            },
+        })
+    }
+}
+
+/// Country (type `b1b2c3d4e5f6071829304050a1b2c3d6`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Country {
+    /// Entity ID.
+    pub id: grc_20::Id,
+    /// Name.
+    pub name: Option<String>,
+    /// Code.
+    pub code: Option<String>,
+}
+
+impl Country {
+    /// Country type ID (`b1b2c3d4e5f6071829304050a1b2c3d6`).
+    pub const TYPE_ID: grc_20::Id = [
+        0xb1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd6,
+    ];
+
+    /// Name property ID (`a1b2c3d4e5f6071829304050a1b2c3d4`).
+    pub const NAME: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd4,
+    ];
+
+    /// Code property ID (`a1b2c3d4e5f6071829304050a1b2c3d5`).
+    pub const CODE: grc_20::Id = [
+        0xa1, 0xb2, 0xc3, 0xd4, 0xe5, 0xf6, 0x07, 0x18,
+        0x29, 0x30, 0x40, 0x50, 0xa1, 0xb2, 0xc3, 0xd5,
+    ];
+
+    /// Returns ops creating the entity with its set fields, and its type
+    /// relation.
+    pub fn to_ops(&self) -> Vec<grc_20::Op<'static>> {
+        let mut values = Vec::new();
+        if let Some(v) = &self.name {
+            values.push(grc_20::PropertyValue {
+                property: Self::NAME,
+                value: grc_20::Value::Text { value: v.clone().into(), language: None },
+                clock: None,
+            });
+        }
+        if let Some(v) = &self.code {
+            values.push(grc_20::PropertyValue {
+                property: Self::CODE,
+                value: grc_20::Value::Text { value: v.clone().into(), language: None },
+                clock: None,
+            });
+        }
+        let types = grc_20::genesis::relation_types::types();
+        vec![
+            grc_20::Op::CreateEntity(grc_20::CreateEntity { id: self.id, values }),
+            grc_20::Op::CreateRelation(grc_20::CreateRelation {
+                id: grc_20::model::id::unique_relation_id(&self.id, &Self::TYPE_ID, &types),
+                relation_type: types,
+                from: self.id,
+                from_is_value_ref: false,
+                from_space: None,
+                from_version: None,
+                to: Self::TYPE_ID,
+                to_is_value_ref: false,
+                to_space: None,
+                to_version: None,
+                entity: None,
+                position: None,
+            }),
+        ]
+    }
+
+    /// Reads the fields from resolved state. Values of another data type
+    /// are skipped. Returns None if the entity is tombstoned.
+    pub fn from_entity(id: grc_20::Id, entity: &grc_20::EntityState) -> Option<Self> {
+        if entity.deleted {
+            return None;
+        }
+        Some(Self {
+            id,
+            name: match entity.value(&Self::NAME, None) {
+                Some(grc_20::Value::Text { value, .. }) => Some(value.to_string()),
+                _ => None,
+            },
+            code: match entity.value(&Self::CODE, None) {
+                Some(grc_20::Value::Text { value, .. }) => Some(value.to_string()),
+                _ => None,
+            },
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//! Checks that `fixtures/city.rs` is what `fixtures/city.toml` generates,
+//! and that the generated structs roundtrip through resolved state.
+
+use std::fs;
+use std::path::Path;
+
+use grc_20::{GraphState, Value};
+
+mod generated {
+    include!("fixtures/city.rs");
+}
+
+use generated::{City, Country};
+
+#[test]
+fn test_fixture_is_up_to_date() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let out = std::env::temp_dir().join(format!("grc-20-codegen-{}.rs", std::process::id()));
+    grc_20_codegen::generate_file(dir.join("city.toml"), &out).unwrap();
+    let generated = fs::read_to_string(&out).unwrap();
+    fs::remove_file(&out).unwrap();
+    assert_eq!(
+        generated,
+        fs::read_to_string(dir.join("city.rs")).unwrap(),
+        "regenerate tests/fixtures/city.rs from tests/fixtures/city.toml"
+    );
+}
+
+#[test]
+fn test_to_ops_and_from_entity() {
+    let city = City {
+        id: [1u8; 16],
+        name: Some("Lisbon".to_string()),
+        population: Some(545_796),
+        location: Some(Value::Point { lon: -9.14, lat: 38.72, alt: None }),
+        wikidata_id: None,
+    };
+    let country = Country { id: [2u8; 16], name: Some("Portugal".to_string()), code: None };
+
+    let mut state = GraphState::new();
+    for op in city.to_ops().iter().chain(&country.to_ops()) {
+        state.apply_op(op);
+    }
+    assert!(state.is_instance_of(&city.id, &City::TYPE_ID));
+    assert!(state.is_instance_of(&country.id, &Country::TYPE_ID));
+    assert_eq!(City::from_entity(city.id, state.entity(&city.id).unwrap()), Some(city.clone()));
+    assert_eq!(Country::from_entity(country.id, state.entity(&country.id).unwrap()), Some(country));
+
+    // A value of the wrong data type reads as unset
+    let mut entity = state.entity(&city.id).unwrap().clone();
+    entity.values.insert((City::POPULATION, None), Value::Bool(true));
+    assert_eq!(City::from_entity(city.id, &entity).unwrap().population, None);
+    entity.deleted = true;
+    assert_eq!(City::from_entity(city.id, &entity), None);
+}
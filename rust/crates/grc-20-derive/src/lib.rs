@@ -0,0 +1,462 @@
+//! `#[derive(Grc20Entity)]`: implements `grc_20::Grc20Entity` for a
+//! struct with named fields.
+//!
+//! ```text
+//! #[derive(Grc20Entity)]
+//! #[grc20(type_id = "b1b2c3d4e5f6071829304050a1b2c3d4")]   // optional
+//! struct City {
+//!     #[grc20(id)]                                          // or a field named `id`
+//!     id: grc_20::Id,
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d4")]
+//!     name: String,                                         // required
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d7")]
+//!     population: Option<i64>,                              // optional
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d8", data_type = "Point")]
+//!     location: Option<grc_20::Value<'static>>,
+//!     #[grc20(skip)]                                        // Default on read
+//!     cached: u32,
+//! }
+//! ```
+//!
+//! `data_type` defaults from the field type: `bool` is BOOL, `i64` INT64,
+//! `f64` FLOAT64, `String` TEXT, and `Vec<u8>` BYTES. `String` fields may
+//! also be DATE, TIME, DATETIME, or SCHEDULE. A `grc_20::Value<'static>`
+//! field holds the whole value of any data type (and is required for
+//! DECIMAL, POINT, and EMBEDDING), keeping units and structure.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitStr,
+    PathArguments, Result, Type,
+};
+
+/// Derives `grc_20::Grc20Entity`. See the crate docs for the attributes.
+#[proc_macro_derive(Grc20Entity, attributes(grc20))]
+pub fn derive_grc20_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "Grc20Entity needs named fields")),
+        },
+        _ => return Err(Error::new_spanned(input, "Grc20Entity can only be derived for structs")),
+    };
+
+    let mut type_id = None;
+    for attr in grc20_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type_id") {
+                type_id = Some(parse_id(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `type_id = \"...\"`"))
+            }
+        })?;
+    }
+
+    let mut id_field = None;
+    let mut properties = Vec::new();
+    let mut skipped = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().expect("named field");
+        match FieldRole::parse(field.attrs.as_slice(), &ident, &field.ty)? {
+            FieldRole::Id => {
+                if id_field.replace(ident.clone()).is_some() {
+                    return Err(Error::new_spanned(field, "more than one ID field"));
+                }
+            }
+            FieldRole::Skip => skipped.push(ident),
+            FieldRole::Property(property) => properties.push(property),
+        }
+    }
+    let id_field = id_field.ok_or_else(|| {
+        Error::new_spanned(&input.ident, "no ID field: add a field `id` or mark one #[grc20(id)]")
+    })?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let type_id = match type_id {
+        Some(id) => quote!(::core::option::Option::Some([#(#id),*])),
+        None => quote!(::core::option::Option::None),
+    };
+    let property_ids = properties.iter().map(|p| {
+        let id = p.id;
+        quote!([#(#id),*])
+    });
+    let pushes = properties.iter().map(Property::push_value);
+    let reads = properties.iter().map(Property::read);
+    let property_fields = properties.iter().map(|p| &p.field);
+
+    Ok(quote! {
+        impl #impl_generics ::grc_20::Grc20Entity for #name #ty_generics #where_clause {
+            const TYPE_ID: ::core::option::Option<::grc_20::Id> = #type_id;
+            const PROPERTIES: &'static [::grc_20::Id] = &[#(#property_ids),*];
+
+            fn entity_id(&self) -> ::grc_20::Id {
+                self.#id_field
+            }
+
+            fn to_values(&self) -> ::std::vec::Vec<::grc_20::PropertyValue<'static>> {
+                let mut values = ::std::vec::Vec::new();
+                #(#pushes)*
+                values
+            }
+
+            fn from_entity(
+                id: ::grc_20::Id,
+                entity: &::grc_20::EntityState,
+            ) -> ::core::option::Option<Self> {
+                #(#reads)*
+                ::core::option::Option::Some(Self {
+                    #id_field: id,
+                    #(#property_fields,)*
+                    #(#skipped: ::core::default::Default::default(),)*
+                })
+            }
+        }
+    })
+}
+
+fn grc20_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|a| a.path().is_ident("grc20"))
+}
+
+/// What a struct field maps to.
+enum FieldRole {
+    Id,
+    Skip,
+    Property(Property),
+}
+
+impl FieldRole {
+    fn parse(attrs: &[Attribute], ident: &Ident, ty: &Type) -> Result<Self> {
+        let mut is_id = false;
+        let mut skip = false;
+        let mut property = None;
+        let mut data_type = None;
+        let mut has_attr = false;
+        for attr in grc20_attrs(attrs) {
+            has_attr = true;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("id") {
+                    is_id = true;
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("property") {
+                    property = Some(parse_id(&meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("data_type") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    data_type = Some(DataType::parse(&lit.value()).ok_or_else(|| {
+                        Error::new(lit.span(), "unknown data type, e.g. \"Text\" or \"Int64\"")
+                    })?);
+                } else {
+                    return Err(meta.error("expected `id`, `skip`, `property`, or `data_type`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        match (is_id, skip, property) {
+            (true, false, None) => Ok(FieldRole::Id),
+            (false, true, None) => Ok(FieldRole::Skip),
+            (false, false, Some(id)) => {
+                let (inner, optional) = match option_inner(ty) {
+                    Some(inner) => (inner, true),
+                    None => (ty, false),
+                };
+                let kind = Kind::new(inner, data_type)?;
+                Ok(FieldRole::Property(Property { field: ident.clone(), id, optional, kind }))
+            }
+            (false, false, None) if !has_attr && ident == "id" => Ok(FieldRole::Id),
+            (false, false, None) => Err(Error::new_spanned(
+                ident,
+                "field needs #[grc20(property = \"...\")], #[grc20(id)], or #[grc20(skip)]",
+            )),
+            _ => Err(Error::new_spanned(ident, "`id`, `skip`, and `property` exclude each other")),
+        }
+    }
+}
+
+/// A field mapped to a property.
+struct Property {
+    field: Ident,
+    id: [u8; 16],
+    optional: bool,
+    kind: Kind,
+}
+
+impl Property {
+    /// Statement pushing the field's value onto `values`, if set.
+    fn push_value(&self) -> TokenStream2 {
+        let field = &self.field;
+        let id = self.id;
+        let value = self.kind.to_value();
+        let push = quote! {
+            values.push(::grc_20::PropertyValue {
+                property: [#(#id),*],
+                value: #value,
+                clock: ::core::option::Option::None,
+            });
+        };
+        if self.optional {
+            quote!(if let ::core::option::Option::Some(v) = &self.#field { #push })
+        } else {
+            quote!({ let v = &self.#field; #push })
+        }
+    }
+
+    /// Statement binding the field's value read from `entity`.
+    fn read(&self) -> TokenStream2 {
+        let field = &self.field;
+        let id = self.id;
+        let arm = self.kind.read_arm();
+        let read = quote! {
+            match entity.value(&[#(#id),*], ::core::option::Option::None) {
+                #arm,
+                _ => ::core::option::Option::None,
+            }
+        };
+        if self.optional {
+            quote!(let #field = #read;)
+        } else {
+            quote!(let #field = #read?;)
+        }
+    }
+}
+
+/// GRC-20 data types, as named in `data_type = "..."`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataType {
+    Bool,
+    Int64,
+    Float64,
+    Decimal,
+    Text,
+    Bytes,
+    Date,
+    Time,
+    Datetime,
+    Schedule,
+    Point,
+    Embedding,
+}
+
+impl DataType {
+    const ALL: [DataType; 12] = [
+        DataType::Bool,
+        DataType::Int64,
+        DataType::Float64,
+        DataType::Decimal,
+        DataType::Text,
+        DataType::Bytes,
+        DataType::Date,
+        DataType::Time,
+        DataType::Datetime,
+        DataType::Schedule,
+        DataType::Point,
+        DataType::Embedding,
+    ];
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| format!("{t:?}").eq_ignore_ascii_case(name))
+    }
+
+    fn variant(self) -> Ident {
+        Ident::new(&format!("{self:?}"), Span::call_site())
+    }
+}
+
+/// How a field's Rust type converts to and from a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int64,
+    Float64,
+    /// A `String` of TEXT or of a string-valued data type such as DATE.
+    Str(DataType),
+    Bytes,
+    /// A `grc_20::Value<'static>` of the data type, if given.
+    Value(Option<DataType>),
+}
+
+impl Kind {
+    /// Picks the conversion from the field type (without `Option`) and the
+    /// `data_type` attribute.
+    fn new(ty: &Type, data_type: Option<DataType>) -> Result<Self> {
+        let name = last_segment(ty).map(|ident| ident.to_string()).unwrap_or_default();
+        let kind = match (name.as_str(), data_type) {
+            ("Value", data_type) => Kind::Value(data_type),
+            ("bool", None | Some(DataType::Bool)) => Kind::Bool,
+            ("i64", None | Some(DataType::Int64)) => Kind::Int64,
+            ("f64", None | Some(DataType::Float64)) => Kind::Float64,
+            ("String", None) => Kind::Str(DataType::Text),
+            (
+                "String",
+                Some(
+                    t @ (DataType::Text
+                    | DataType::Date
+                    | DataType::Time
+                    | DataType::Datetime
+                    | DataType::Schedule),
+                ),
+            ) => Kind::Str(t),
+            ("Vec", None | Some(DataType::Bytes)) => Kind::Bytes,
+            (_, Some(DataType::Decimal | DataType::Point | DataType::Embedding)) => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "DECIMAL, POINT, and EMBEDDING fields must be grc_20::Value<'static>",
+                ));
+            }
+            (_, Some(_)) => {
+                return Err(Error::new_spanned(ty, "field type does not hold this data type"));
+            }
+            (_, None) => {
+                return Err(Error::new_spanned(ty, "cannot infer data_type from this type"));
+            }
+        };
+        Ok(kind)
+    }
+
+    /// Expression converting `v: &T` into a `Value<'static>`.
+    fn to_value(self) -> TokenStream2 {
+        match self {
+            Kind::Bool => quote!(::grc_20::Value::Bool(*v)),
+            Kind::Int64 => {
+                quote!(::grc_20::Value::Int64 { value: *v, unit: ::core::option::Option::None })
+            }
+            Kind::Float64 => {
+                quote!(::grc_20::Value::Float64 { value: *v, unit: ::core::option::Option::None })
+            }
+            Kind::Str(DataType::Text) => quote! {
+                ::grc_20::Value::Text {
+                    value: ::std::borrow::Cow::Owned(::core::clone::Clone::clone(v)),
+                    language: ::core::option::Option::None,
+                }
+            },
+            Kind::Str(data_type) => {
+                let variant = data_type.variant();
+                quote! {
+                    ::grc_20::Value::#variant(
+                        ::std::borrow::Cow::Owned(::core::clone::Clone::clone(v)),
+                    )
+                }
+            }
+            Kind::Bytes => {
+                quote!(::grc_20::Value::Bytes(::std::borrow::Cow::Owned(
+                    ::core::clone::Clone::clone(v)
+                )))
+            }
+            Kind::Value(_) => quote!(::core::clone::Clone::clone(v)),
+        }
+    }
+
+    /// Match arm turning a `Some(&Value)` into `Some(T)`.
+    fn read_arm(self) -> TokenStream2 {
+        let some = quote!(::core::option::Option::Some);
+        match self {
+            Kind::Bool => quote!(#some(::grc_20::Value::Bool(v)) => #some(*v)),
+            Kind::Int64 => quote!(#some(::grc_20::Value::Int64 { value, .. }) => #some(*value)),
+            Kind::Float64 => {
+                quote!(#some(::grc_20::Value::Float64 { value, .. }) => #some(*value))
+            }
+            Kind::Str(DataType::Text) => quote! {
+                #some(::grc_20::Value::Text { value, .. }) => #some(value.to_string())
+            },
+            Kind::Str(data_type) => {
+                let variant = data_type.variant();
+                quote!(#some(::grc_20::Value::#variant(v)) => #some(v.to_string()))
+            }
+            Kind::Bytes => quote!(#some(::grc_20::Value::Bytes(v)) => #some(v.to_vec())),
+            Kind::Value(None) => quote!(#some(v) => #some(::core::clone::Clone::clone(v))),
+            Kind::Value(Some(data_type)) => {
+                let variant = data_type.variant();
+                quote! {
+                    #some(v @ ::grc_20::Value::#variant { .. }) => {
+                        #some(::core::clone::Clone::clone(v))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn last_segment(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
+}
+
+/// Parses a hex ID, with or without dashes.
+fn parse_id(lit: &LitStr) -> Result<[u8; 16]> {
+    let hex: Vec<u8> = lit.value().bytes().filter(|b| *b != b'-').collect();
+    let digit = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let mut id = [0u8; 16];
+    if hex.len() != 32 {
+        return Err(Error::new(lit.span(), "expected a 32-digit hex ID"));
+    }
+    for (byte, pair) in id.iter_mut().zip(hex.chunks(2)) {
+        match (digit(pair[0]), digit(pair[1])) {
+            (Some(hi), Some(lo)) => *byte = hi << 4 | lo,
+            _ => return Err(Error::new(lit.span(), "expected a 32-digit hex ID")),
+        }
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id() {
+        let lit = |s| LitStr::new(s, Span::call_site());
+        let id = parse_id(&lit("01234567-89ab-cdef-0123-456789ABCDEF")).unwrap();
+        assert_eq!(id[..4], [0x01, 0x23, 0x45, 0x67]);
+        assert_eq!(id[15], 0xef);
+        assert!(parse_id(&lit("0123")).is_err());
+        assert!(parse_id(&lit("zz234567890123456789012345678901")).is_err());
+    }
+
+    #[test]
+    fn test_kind_inference() {
+        let ty = |s: &str| syn::parse_str::<Type>(s).unwrap();
+        assert_eq!(Kind::new(&ty("i64"), None).unwrap(), Kind::Int64);
+        assert_eq!(Kind::new(&ty("String"), None).unwrap(), Kind::Str(DataType::Text));
+        assert_eq!(
+            Kind::new(&ty("String"), DataType::parse("datetime")).unwrap(),
+            Kind::Str(DataType::Datetime)
+        );
+        assert_eq!(
+            Kind::new(&ty("grc_20::Value<'static>"), DataType::parse("Point")).unwrap(),
+            Kind::Value(Some(DataType::Point))
+        );
+        assert!(Kind::new(&ty("String"), DataType::parse("Point")).is_err());
+        assert!(Kind::new(&ty("i64"), DataType::parse("Text")).is_err());
+        assert!(Kind::new(&ty("u32"), None).is_err());
+        let optional = ty("Option<i64>");
+        let inner = option_inner(&optional).and_then(last_segment);
+        assert!(inner.is_some_and(|ident| ident == "i64"));
+        assert!(option_inner(&ty("i64")).is_none());
+    }
+}
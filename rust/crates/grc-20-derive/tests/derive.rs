@@ -0,0 +1,100 @@
+//! `#[derive(Grc20Entity)]` roundtrips through ops and resolved state.
+
+use grc_20::{EditBuilder, GraphState, Grc20Entity, Id, Op, UnsetValue, Value};
+
+const CITY: Id = [0xB1; 16];
+const NAME: Id = [0xA1; 16];
+const POPULATION: Id = [0xA2; 16];
+const LOCATION: Id = [0xA3; 16];
+const FOUNDED: Id = [0xA4; 16];
+const CAPITAL: Id = [0xA5; 16];
+
+#[derive(Debug, Clone, PartialEq, Grc20Entity)]
+#[grc20(type_id = "b1b1b1b1-b1b1-b1b1-b1b1-b1b1b1b1b1b1")]
+struct City {
+    id: Id,
+    #[grc20(property = "a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1")]
+    name: String,
+    #[grc20(property = "a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2")]
+    population: Option<i64>,
+    #[grc20(property = "a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3a3", data_type = "Point")]
+    location: Option<Value<'static>>,
+    #[grc20(property = "a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4a4", data_type = "date")]
+    founded: Option<String>,
+    #[grc20(property = "a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5")]
+    capital: Option<bool>,
+    #[grc20(skip)]
+    visits: u32,
+}
+
+/// No type, and the ID field is not called `id`.
+#[derive(Debug, Clone, PartialEq, Grc20Entity)]
+struct Label {
+    #[grc20(id)]
+    entity: Id,
+    #[grc20(property = "a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1a1")]
+    name: String,
+}
+
+fn lisbon() -> City {
+    City {
+        id: [1u8; 16],
+        name: "Lisbon".to_string(),
+        population: Some(545_796),
+        location: Some(Value::Point { lon: -9.14, lat: 38.72, alt: None }),
+        founded: Some("1179".to_string()),
+        capital: None,
+        visits: 0,
+    }
+}
+
+#[test]
+fn test_constants_and_values() {
+    assert_eq!(City::TYPE_ID, Some(CITY));
+    assert_eq!(City::PROPERTIES, [NAME, POPULATION, LOCATION, FOUNDED, CAPITAL]);
+    assert_eq!(Label::TYPE_ID, None);
+
+    let values = lisbon().to_values();
+    let properties: Vec<Id> = values.iter().map(|v| v.property).collect();
+    assert_eq!(properties, [NAME, POPULATION, LOCATION, FOUNDED]);
+    assert_eq!(values[3].value, Value::Date("1179".into()));
+}
+
+#[test]
+fn test_roundtrip_through_state() {
+    let city = lisbon();
+    let mut state = GraphState::new();
+    for op in city.to_ops() {
+        state.apply_op(&op);
+    }
+    assert!(state.is_instance_of(&city.id, &CITY));
+    assert_eq!(state.load::<City>(&city.id), Some(city.clone()));
+    assert_eq!(
+        state.load::<Label>(&city.id),
+        Some(Label { entity: city.id, name: "Lisbon".to_string() })
+    );
+
+    let update = City { population: None, capital: Some(true), ..city.clone() }.to_update();
+    assert_eq!(update.unset_values, [UnsetValue::all(POPULATION)]);
+    state.apply_op(&Op::UpdateEntity(update));
+    let updated = state.load::<City>(&city.id).unwrap();
+    assert_eq!((updated.population, updated.capital), (None, Some(true)));
+
+    // An untyped entity with a name is a Label but not a City
+    let edit = EditBuilder::new([0xE1; 16])
+        .create_entity([2u8; 16], |e| e.text(NAME, "Porto", None))
+        .build();
+    state.apply_edit(&edit);
+    assert_eq!(state.load_all::<City>().len(), 1);
+    assert_eq!(state.load_all::<Label>().len(), 2);
+}
+
+#[test]
+fn test_missing_required_field() {
+    let edit = EditBuilder::new([0xE1; 16])
+        .create_entity([3u8; 16], |e| e.int64(POPULATION, 10, None))
+        .build();
+    let mut state = GraphState::new();
+    state.apply_edit(&edit);
+    assert_eq!(state.load::<Label>(&[3u8; 16]), None);
+}
@@ -0,0 +1,8 @@
+fn main() {
+    // Use the bundled protoc so that building doesn't need one installed
+    let mut config = tonic_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path().expect("bundled protoc"));
+    tonic_build::configure()
+        .compile_protos_with_config(config, &["proto/grc20_rpc.proto"], &["proto"])
+        .expect("Failed to compile protos");
+}
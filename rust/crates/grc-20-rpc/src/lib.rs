@@ -0,0 +1,66 @@
+//! gRPC transport for GRC-20 edits.
+//!
+//! Defines the `EditService` (`proto/grc20_rpc.proto`) with three calls:
+//!
+//! - `PublishEdit`: submit an encoded edit, get back its ID and sequence
+//!   number in the service's log
+//! - `GetEdit`: fetch a published edit by ID
+//! - `StreamEdits`: stream published edits in order from a sequence number
+//!
+//! Payloads are edits in the binary format, in any framing
+//! [`grc_20::decode_edit`] accepts, so what goes over the wire is what would
+//! be written to a file. The generated client ([`EditServiceClient`]) and
+//! server ([`EditServiceServer`] around an [`EditService`]) are re-exported;
+//! [`MemoryEditService`] is an in-memory reference server.
+//!
+//! ```text
+//! // Server
+//! Server::builder()
+//!     .add_service(EditServiceServer::new(MemoryEditService::new()))
+//!     .serve(addr)
+//!     .await?;
+//!
+//! // Client
+//! let mut client = EditServiceClient::connect("http://[::1]:50051").await?;
+//! let published = client.publish_edit(PublishEditRequest::from_edit(&edit)?).await?;
+//! ```
+
+pub mod server;
+
+/// Messages and client and server stubs generated from
+/// `proto/grc20_rpc.proto`.
+pub mod proto {
+    tonic::include_proto!("grc20.rpc.v1");
+}
+
+pub use proto::edit_service_client::EditServiceClient;
+pub use proto::edit_service_server::{EditService, EditServiceServer};
+pub use proto::{
+    EditEnvelope, GetEditRequest, GetEditResponse, PublishEditRequest, PublishEditResponse,
+    StreamEditsRequest,
+};
+pub use server::MemoryEditService;
+
+use grc_20::{DecodeError, Edit, EncodeError, Id};
+
+impl PublishEditRequest {
+    /// Encodes an edit with the default compression for publishing.
+    pub fn from_edit(edit: &Edit) -> Result<Self, EncodeError> {
+        let options = grc_20::CompressionOptions::default();
+        Ok(Self { edit: grc_20::encode_edit_with_compression(edit, options)? })
+    }
+}
+
+impl GetEditRequest {
+    /// Requests the edit with the given ID.
+    pub fn new(edit_id: Id) -> Self {
+        Self { edit_id: edit_id.to_vec() }
+    }
+}
+
+impl EditEnvelope {
+    /// Decodes the edit.
+    pub fn decode_edit(&self) -> Result<Edit<'_>, DecodeError> {
+        grc_20::decode_edit(&self.edit)
+    }
+}
@@ -0,0 +1,187 @@
+//! In-memory reference implementation of the edit service.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use grc_20::Id;
+use tonic::{Request, Response, Status};
+
+use crate::proto::edit_service_server::EditService;
+use crate::proto::{
+    EditEnvelope, GetEditRequest, GetEditResponse, PublishEditRequest, PublishEditResponse,
+    StreamEditsRequest,
+};
+
+/// An edit service that keeps published edits in memory.
+///
+/// Every edit is decoded before it is accepted, so the log only holds
+/// well-formed edits. An edit ID can be published once; republishing the
+/// same bytes returns the original sequence number, and other bytes under a
+/// published ID fail with `ALREADY_EXISTS`. `StreamEdits` streams the edits
+/// published when it is called.
+#[derive(Debug, Default)]
+pub struct MemoryEditService {
+    log: RwLock<Log>,
+}
+
+#[derive(Debug, Default)]
+struct Log {
+    edits: Vec<EditEnvelope>,
+    by_id: HashMap<Id, usize>,
+}
+
+impl MemoryEditService {
+    /// Creates an empty service.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of published edits.
+    pub fn len(&self) -> usize {
+        self.log.read().expect("log lock poisoned").edits.len()
+    }
+
+    /// Returns true if no edits have been published.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[tonic::async_trait]
+impl EditService for MemoryEditService {
+    async fn publish_edit(
+        &self,
+        request: Request<PublishEditRequest>,
+    ) -> Result<Response<PublishEditResponse>, Status> {
+        let bytes = request.into_inner().edit;
+        let edit = grc_20::decode_edit(&bytes)
+            .map_err(|e| Status::invalid_argument(format!("invalid edit: {}", e)))?;
+        let edit_id = edit.id;
+
+        let mut log = self.log.write().expect("log lock poisoned");
+        let sequence = match log.by_id.get(&edit_id) {
+            Some(&sequence) if log.edits[sequence].edit == bytes => sequence,
+            Some(_) => {
+                return Err(Status::already_exists(format!(
+                    "edit {} is already published",
+                    grc_20::format_id(&edit_id)
+                )));
+            }
+            None => {
+                let sequence = log.edits.len();
+                log.edits.push(EditEnvelope {
+                    sequence: sequence as u64,
+                    edit_id: edit_id.to_vec(),
+                    edit: bytes,
+                });
+                log.by_id.insert(edit_id, sequence);
+                sequence
+            }
+        };
+        Ok(Response::new(PublishEditResponse {
+            edit_id: edit_id.to_vec(),
+            sequence: sequence as u64,
+        }))
+    }
+
+    async fn get_edit(
+        &self,
+        request: Request<GetEditRequest>,
+    ) -> Result<Response<GetEditResponse>, Status> {
+        let bytes = request.into_inner().edit_id;
+        let edit_id: Id = bytes.as_slice().try_into().map_err(|_| {
+            Status::invalid_argument(format!("edit ID must be 16 bytes, got {}", bytes.len()))
+        })?;
+        let log = self.log.read().expect("log lock poisoned");
+        let sequence = log.by_id.get(&edit_id).ok_or_else(|| {
+            Status::not_found(format!("edit {} not found", grc_20::format_id(&edit_id)))
+        })?;
+        Ok(Response::new(GetEditResponse { edit: Some(log.edits[*sequence].clone()) }))
+    }
+
+    type StreamEditsStream = tonic::codegen::BoxStream<EditEnvelope>;
+
+    async fn stream_edits(
+        &self,
+        request: Request<StreamEditsRequest>,
+    ) -> Result<Response<Self::StreamEditsStream>, Status> {
+        let from = request.into_inner().from_sequence;
+        let log = self.log.read().expect("log lock poisoned");
+        let edits: Vec<_> =
+            log.edits.iter().skip(from.try_into().unwrap_or(usize::MAX)).cloned().collect();
+        Ok(Response::new(Box::pin(tokio_stream::iter(edits.into_iter().map(Ok)))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::edit_service_client::EditServiceClient;
+    use crate::proto::edit_service_server::EditServiceServer;
+    use grc_20::{Edit, EditBuilder};
+    use tokio_stream::StreamExt;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Code;
+    use tonic::transport::{Channel, Server};
+
+    fn edit(n: u8) -> Edit<'static> {
+        EditBuilder::new([n; 16])
+            .name("rpc test")
+            .create_entity([0xA0 + n; 16], |e| e.text([1u8; 16], format!("Entity {}", n), None))
+            .build()
+    }
+
+    async fn serve() -> EditServiceClient<Channel> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(EditServiceServer::new(MemoryEditService::new()))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        EditServiceClient::connect(format!("http://{}", addr)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_publish_get_and_stream() {
+        let mut client = serve().await;
+        for n in 0..3 {
+            let request = PublishEditRequest::from_edit(&edit(n)).unwrap();
+            let published = client.publish_edit(request).await.unwrap().into_inner();
+            assert_eq!(published, PublishEditResponse { edit_id: vec![n; 16], sequence: n as u64 });
+        }
+
+        // Republishing is idempotent; other bytes under the same ID are not
+        let request = PublishEditRequest::from_edit(&edit(1)).unwrap();
+        assert_eq!(client.publish_edit(request).await.unwrap().into_inner().sequence, 1);
+        let mut changed = edit(1);
+        changed.name = "changed".into();
+        let request = PublishEditRequest::from_edit(&changed).unwrap();
+        assert_eq!(client.publish_edit(request).await.unwrap_err().code(), Code::AlreadyExists);
+
+        let got = client.get_edit(GetEditRequest::new([2u8; 16])).await.unwrap().into_inner();
+        assert_eq!(got.edit.unwrap().decode_edit().unwrap(), edit(2));
+
+        let stream = client
+            .stream_edits(StreamEditsRequest { from_sequence: 1 })
+            .await
+            .unwrap()
+            .into_inner();
+        let streamed: Vec<_> = stream.map(|e| e.unwrap().sequence).collect().await;
+        assert_eq!(streamed, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests() {
+        let service = MemoryEditService::new();
+        let publish =
+            service.publish_edit(Request::new(PublishEditRequest { edit: b"GRC2".to_vec() })).await;
+        assert_eq!(publish.unwrap_err().code(), Code::InvalidArgument);
+        assert!(service.is_empty());
+
+        let get = service.get_edit(Request::new(GetEditRequest { edit_id: vec![1, 2] })).await;
+        assert_eq!(get.unwrap_err().code(), Code::InvalidArgument);
+        let get = service.get_edit(Request::new(GetEditRequest::new([7u8; 16]))).await;
+        assert_eq!(get.unwrap_err().code(), Code::NotFound);
+    }
+}
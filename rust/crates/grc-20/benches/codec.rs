@@ -0,0 +1,246 @@
+//! Criterion benchmarks for the GRC-20 codec.
+//!
+//! Run with `cargo bench -p grc-20`. Criterion stores results under
+//! `target/criterion/` and reports changes against the previous run, so
+//! regressions show up with confidence intervals rather than single timings.
+//!
+//! The workload is a synthetic edit shaped like a typical import: entities
+//! with a mix of value types, localized names, and typed relations.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use grc_20::codec::{has_f32_nan, Reader, Writer};
+use grc_20::{
+    decode_edit, decompress, derived_uuid, encode_edit, encode_edit_compressed,
+    encode_edit_with_options, validate_edit, DataType, DictionaryBuilder, Edit, EditBuilder,
+    EncodeOptions, Id, Op, SchemaContext, Value,
+};
+
+/// Edit sizes (entity count) to benchmark.
+const SIZES: &[usize] = &[100, 10_000];
+
+fn id(kind: &str, n: usize) -> Id {
+    derived_uuid(format!("bench:{}:{}", kind, n).as_bytes())
+}
+
+fn build_edit(entities: usize) -> Edit<'static> {
+    let name = id("prop", 0);
+    let population = id("prop", 1);
+    let location = id("prop", 2);
+    let founded = id("prop", 3);
+    let in_region = id("rel", 0);
+    let languages = [id("lang", 0), id("lang", 1), id("lang", 2)];
+
+    let mut builder = EditBuilder::new(id("edit", entities))
+        .name("Benchmark Import")
+        .author(id("author", 0))
+        .created_at(1_704_067_200_000_000);
+
+    for i in 0..entities {
+        let entity = id("entity", i);
+        builder = builder
+            .create_entity(entity, |e| {
+                let mut e = e
+                    .text(name, format!("Entity {}", i), None)
+                    .int64(population, (i as i64) * 1_000, None)
+                    .point(location, (i % 360) as f64 - 180.0, (i % 180) as f64 - 90.0, None)
+                    .date(founded, "1850-06-01");
+                for (j, lang) in languages.iter().enumerate() {
+                    e = e.text(name, format!("Entity {} ({})", i, j), Some(*lang));
+                }
+                e
+            })
+            .create_relation_simple(id("relation", i), entity, id("region", i % 50), in_region);
+    }
+
+    builder.build()
+}
+
+fn schema() -> SchemaContext {
+    let mut schema = SchemaContext::new();
+    schema.add_property(id("prop", 0), DataType::Text);
+    schema.add_property(id("prop", 1), DataType::Int64);
+    schema.add_property(id("prop", 2), DataType::Point);
+    schema.add_property(id("prop", 3), DataType::Date);
+    schema
+}
+
+/// Builds the wire dictionaries for an edit, mirroring the encoder's first pass.
+fn build_dictionaries(edit: &Edit) -> Vec<u8> {
+    let mut dicts = DictionaryBuilder::with_capacity(edit.ops.len());
+    for op in &edit.ops {
+        match op {
+            Op::CreateEntity(ce) => {
+                dicts.add_object(ce.id);
+                for pv in &ce.values {
+                    dicts.add_property(pv.property, pv.value.data_type());
+                    if let Value::Text { language, .. } = &pv.value {
+                        dicts.add_language(*language);
+                    }
+                }
+            }
+            Op::CreateRelation(cr) => {
+                dicts.add_relation_type(cr.relation_type);
+                dicts.add_object(cr.from);
+                dicts.add_object(cr.to);
+            }
+            _ => {}
+        }
+    }
+    let mut writer = Writer::new();
+    dicts.into_sorted().write_dictionaries(&mut writer);
+    writer.into_bytes()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for &size in SIZES {
+        let edit = build_edit(size);
+        let len = encode_edit(&edit).unwrap().len();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("fast", size), &edit, |b, edit| {
+            b.iter(|| encode_edit(black_box(edit)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("canonical", size), &edit, |b, edit| {
+            b.iter(|| encode_edit_with_options(black_box(edit), EncodeOptions::canonical()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for &size in SIZES {
+        let bytes = encode_edit(&build_edit(size)).unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("uncompressed", size), &bytes, |b, bytes| {
+            b.iter(|| decode_edit(black_box(bytes)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_compressed_roundtrip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compressed");
+    for &size in SIZES {
+        let edit = build_edit(size);
+        let uncompressed_len = encode_edit(&edit).unwrap().len();
+        let compressed = encode_edit_compressed(&edit, 3).unwrap();
+        // Throughput is reported in uncompressed bytes for comparability
+        group.throughput(Throughput::Bytes(uncompressed_len as u64));
+        group.bench_with_input(BenchmarkId::new("encode", size), &edit, |b, edit| {
+            b.iter(|| encode_edit_compressed(black_box(edit), 3).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode", size), &compressed, |b, bytes| {
+            b.iter(|| decode_edit(black_box(bytes)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode_zero_copy", size), &compressed, |b, bytes| {
+            b.iter(|| {
+                let decompressed = decompress(black_box(bytes)).unwrap();
+                decode_edit(&decompressed).unwrap().ops.len()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_validate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("validate");
+    let schema = schema();
+    for &size in SIZES {
+        let edit = build_edit(size);
+        group.throughput(Throughput::Elements(edit.ops.len() as u64));
+        group.bench_with_input(BenchmarkId::new("validate_edit", size), &edit, |b, edit| {
+            b.iter(|| validate_edit(black_box(edit), &schema).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_dictionaries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dictionaries");
+    for &size in SIZES {
+        let edit = build_edit(size);
+        group.throughput(Throughput::Elements(edit.ops.len() as u64));
+        group.bench_with_input(BenchmarkId::new("build_sorted", size), &edit, |b, edit| {
+            b.iter(|| build_dictionaries(black_box(edit)))
+        });
+    }
+    group.finish();
+}
+
+/// Byte-at-a-time varint decoding, the baseline for the fast paths.
+fn read_varint_bytewise(data: &[u8], pos: &mut usize) -> u64 {
+    let mut result = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        result |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return result;
+        }
+        shift += 7;
+    }
+}
+
+fn bench_primitives(c: &mut Criterion) {
+    let mut group = c.benchmark_group("primitives");
+
+    // Dictionary indices (mostly one byte) and timestamps (seven bytes)
+    let count = 65_536u64;
+    let workloads = [
+        ("indices", (0..count).map(|i| if i % 100 == 0 { i } else { i % 100 }).collect::<Vec<_>>()),
+        ("timestamps", (0..count).map(|i| 1_704_067_200_000_000 + i * 997).collect()),
+    ];
+    group.throughput(Throughput::Elements(count));
+    for (name, values) in workloads {
+        let mut writer = Writer::new();
+        for &v in &values {
+            writer.write_varint(v);
+        }
+        let varints = writer.into_bytes();
+        group.bench_function(BenchmarkId::new("varint/bytewise", name), |b| {
+            b.iter(|| {
+                let mut pos = 0;
+                let data = black_box(&varints);
+                (0..count).fold(0u64, |acc, _| acc ^ read_varint_bytewise(data, &mut pos))
+            })
+        });
+        group.bench_function(BenchmarkId::new("varint/read_varint", name), |b| {
+            b.iter(|| {
+                let mut reader = Reader::new(black_box(&varints));
+                (0..count).fold(0u64, |acc, _| acc ^ reader.read_varint("bench").unwrap())
+            })
+        });
+        let mut out = vec![0u64; values.len()];
+        group.bench_function(BenchmarkId::new("varint/read_varints", name), |b| {
+            b.iter(|| Reader::new(black_box(&varints)).read_varints(&mut out, "bench").unwrap())
+        });
+    }
+
+    // A batch of 1536-dimension float32 embeddings
+    let embedding: Vec<u8> =
+        (0..64 * 1536).flat_map(|i| (i as f32 * 0.001).to_le_bytes()).collect();
+    group.throughput(Throughput::Bytes(embedding.len() as u64));
+    group.bench_function("f32_nan/scalar", |b| {
+        b.iter(|| {
+            black_box(&embedding)
+                .chunks_exact(4)
+                .any(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]).is_nan())
+        })
+    });
+    group.bench_function("f32_nan/has_f32_nan", |b| b.iter(|| has_f32_nan(black_box(&embedding))));
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_encode,
+    bench_decode,
+    bench_compressed_roundtrip,
+    bench_validate,
+    bench_dictionaries,
+    bench_primitives
+);
+criterion_main!(benches);
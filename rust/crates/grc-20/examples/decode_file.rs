@@ -1,14 +1,27 @@
 //! Simple decoder to inspect GRC-20 files.
+//!
+//! Usage: `decode_file [PATH] [--aliases FILE]`
+//!
+//! IDs are shown by alias where known: genesis IDs always, plus any from an
+//! alias file (`.json` for JSON, anything else for TOML).
 
 use std::fs;
-use grc_20::{decode_edit, Op, Value, CreateEntity, UpdateEntity, CreateRelation, DeleteEntity};
+use grc_20::{decode_edit, AliasTable, Op, Value, CreateEntity, UpdateEntity, CreateRelation, DeleteEntity};
 
-fn format_id(id: &[u8; 16]) -> String {
-    format!(
-        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
-        id[0], id[1], id[2], id[3], id[4], id[5], id[6], id[7],
-        id[8], id[9], id[10], id[11], id[12], id[13], id[14], id[15]
-    )
+fn load_aliases(path: Option<&str>) -> AliasTable {
+    let mut aliases = AliasTable::genesis();
+    if let Some(path) = path {
+        let text = fs::read_to_string(path).expect("Failed to read alias file");
+        let extra = if path.ends_with(".json") {
+            AliasTable::from_json_str(&text)
+        } else {
+            AliasTable::from_toml_str(&text)
+        };
+        aliases
+            .extend(&extra.expect("Failed to parse alias file"))
+            .expect("Alias file conflicts with genesis aliases");
+    }
+    aliases
 }
 
 fn format_value(v: &Value) -> String {
@@ -42,9 +55,18 @@ fn format_value(v: &Value) -> String {
 }
 
 fn main() {
-    let path = std::env::args()
-        .nth(1)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let alias_path = args
+        .iter()
+        .position(|a| a == "--aliases")
+        .map(|i| args.get(i + 1).expect("--aliases requires a file").as_str());
+    let path = args
+        .first()
+        .filter(|a| *a != "--aliases")
+        .cloned()
         .unwrap_or_else(|| "../../data/podcast_data.grc20z".to_string());
+    let aliases = load_aliases(alias_path);
+    let format_id = |id: &[u8; 16]| aliases.display(id);
 
     println!("Reading: {}", path);
 
@@ -78,6 +100,8 @@ fn main() {
     let mut restore_relation_count = 0;
 
     let mut create_value_ref_count = 0;
+    let mut space_count = 0;
+    let mut unknown_count = 0;
     for op in &edit.ops {
         match op {
             Op::CreateEntity(_) => create_entity_count += 1,
@@ -89,6 +113,11 @@ fn main() {
             Op::DeleteRelation(_) => delete_relation_count += 1,
             Op::RestoreRelation(_) => restore_relation_count += 1,
             Op::CreateValueRef(_) => create_value_ref_count += 1,
+            Op::CreateSpace(_)
+            | Op::AddMember(_)
+            | Op::RemoveMember(_)
+            | Op::UpdateSpaceMetadata(_) => space_count += 1,
+            Op::Unknown(_) => unknown_count += 1,
         }
     }
     println!("  CreateEntity: {}", create_entity_count);
@@ -100,6 +129,12 @@ fn main() {
     println!("  DeleteRelation: {}", delete_relation_count);
     println!("  RestoreRelation: {}", restore_relation_count);
     println!("  CreateValueRef: {}", create_value_ref_count);
+    if space_count > 0 {
+        println!("  Space: {}", space_count);
+    }
+    if unknown_count > 0 {
+        println!("  Unknown: {}", unknown_count);
+    }
 
     // Show first few operations in detail
     println!("\n=== First 20 Operations (detail) ===");
@@ -114,7 +149,7 @@ fn main() {
                     println!("      ... and {} more values", values.len() - 5);
                 }
             }
-            Op::UpdateEntity(UpdateEntity { id, set_properties, unset_values }) => {
+            Op::UpdateEntity(UpdateEntity { id, set_properties, unset_values, remove_values }) => {
                 println!("[{}] UpdateEntity {}", i, format_id(id));
                 for pv in set_properties.iter().take(3) {
                     println!("      SET {} = {}", format_id(&pv.property), format_value(&pv.value));
@@ -125,6 +160,9 @@ fn main() {
                 if !unset_values.is_empty() {
                     println!("      UNSET {} values", unset_values.len());
                 }
+                if !remove_values.is_empty() {
+                    println!("      REMOVE {} values", remove_values.len());
+                }
             }
             Op::CreateRelation(CreateRelation { id, relation_type, from, to, .. }) => {
                 println!("[{}] CreateRelation {} ({} -> {})",
@@ -0,0 +1,340 @@
+//! End-to-end import of a cities dataset into a GRC-20 edit.
+//!
+//! Usage: `import_cities [CITIES_CSV] [--out FILE]`
+//!
+//! Reads cities in the CSV format of `data/cities.csv.gz` (decompress it
+//! first with `gunzip -k`), or a built-in sample of a dozen cities when no
+//! file is given. Each city becomes a Place entity with its name, native
+//! name, population, location, and timezone, linked to its state and
+//! country, which are created the first time a city refers to them.
+//!
+//! Well-known properties, types, and relation types come from the Genesis
+//! Space; the rest of the schema and every entity ID is derived from
+//! stable names, so re-running the import produces the same IDs. The edit
+//! is validated, encoded, decoded, and replayed into a [`GraphState`]
+//! before it is written out.
+
+use std::collections::HashSet;
+use std::fs;
+
+use grc_20::genesis::{properties, relation_types, types};
+use grc_20::{
+    decode_edit, derived_uuid, encode_edit_compressed, validate_edit, DataType, Edit, EditBuilder,
+    EntityBuilder, GraphState, Id, SchemaContext,
+};
+
+/// Cities in the dataset's CSV format, covering shared states and
+/// countries as well as quoted fields.
+const SAMPLE: &str = "\
+id,name,state_id,state_code,state_name,country_id,country_code,country_name,latitude,longitude,native,type,level,parent_id,population,timezone,wikiDataId
+16785,Ottawa,866,ON,Ontario,39,CA,Canada,45.41117000,-75.69812000,Ottawa,capital,,,1017449,America/Toronto,Q1930
+17121,Toronto,866,ON,Ontario,39,CA,Canada,43.70011000,-79.41630000,Toronto,section,,,2794356,America/Toronto,Q172
+16796,Paris,866,ON,Ontario,39,CA,Canada,43.20000000,-80.38333000,Paris,city,,,13448,America/Toronto,Q3305306
+44856,Paris,4796,IDF,Île-de-France,75,FR,France,48.85340000,2.34860000,Paris,capital,,,2113705,Europe/Paris,Q90
+43678,Lyon,4798,ARA,Auvergne-Rhône-Alpes,75,FR,France,45.74846000,4.84671000,Lyon,adm1,,,520774,Europe/Paris,Q456
+24053,Berlin,3010,BE,Berlin,82,DE,Germany,52.52437000,13.41053000,Berlin,capital,,,3426354,Europe/Berlin,Q64
+27540,Munich,3009,BY,Bavaria,82,DE,Germany,48.13743000,11.57549000,München,adm1,,,1510378,Europe/Berlin,Q1726
+64500,Tokyo,823,13,Tokyo,109,JP,Japan,35.68950000,139.69171000,東京,capital,,,9733276,Asia/Tokyo,Q207309
+64214,Osaka,859,27,Ōsaka,109,JP,Japan,34.69374000,135.50218000,大阪,adm1,,,2751862,Asia/Tokyo,Q35765
+63890,Kyoto,834,26,Kyōto,109,JP,Japan,35.02107000,135.75385000,京都,adm1,,,1448964,Asia/Tokyo,Q34600
+64845,Nairobi,191,30,\"Nairobi City\",113,KE,Kenya,-1.28333000,36.81667000,Nairobi,capital,,,4397073,Africa/Nairobi,Q3870
+123829,Paris,1444,AR,Arkansas,233,US,\"United States\",35.29203000,-93.72992000,Paris,city,,,3443,America/Chicago,Q79917
+";
+
+// =============================================================================
+// SCHEMA
+// =============================================================================
+
+/// Derives an ID for this importer from a stable name.
+fn import_id(name: &str) -> Id {
+    derived_uuid(format!("import_cities:{}", name).as_bytes())
+}
+
+/// Properties and types beyond the Genesis Space.
+struct Schema {
+    code: Id,
+    native_name: Id,
+    population: Id,
+    wikidata_id: Id,
+    city: Id,
+    state: Id,
+    country: Id,
+    in_state: Id,
+    in_country: Id,
+}
+
+impl Schema {
+    fn new() -> Self {
+        Self {
+            code: import_id("property:Code"),
+            native_name: import_id("property:NativeName"),
+            population: import_id("property:Population"),
+            wikidata_id: import_id("property:WikidataId"),
+            city: import_id("type:City"),
+            state: import_id("type:State"),
+            country: import_id("type:Country"),
+            in_state: import_id("relation_type:InState"),
+            in_country: import_id("relation_type:InCountry"),
+        }
+    }
+
+    /// Returns the expected data type of every property the import writes.
+    fn context(&self) -> SchemaContext {
+        let mut context = SchemaContext::new();
+        context.add_property(properties::name(), DataType::Text);
+        context.add_property(properties::location(), DataType::Point);
+        context.add_property(properties::timezone(), DataType::Text);
+        context.add_property(self.code, DataType::Text);
+        context.add_property(self.native_name, DataType::Text);
+        context.add_property(self.population, DataType::Int64);
+        context.add_property(self.wikidata_id, DataType::Text);
+        context
+    }
+}
+
+// =============================================================================
+// CSV INPUT
+// =============================================================================
+
+struct City {
+    id: String,
+    name: String,
+    state_id: String,
+    state_code: String,
+    state_name: String,
+    country_id: String,
+    country_code: String,
+    country_name: String,
+    latitude: String,
+    longitude: String,
+    native: String,
+    population: String,
+    timezone: String,
+    wikidata_id: String,
+}
+
+/// Splits a CSV line into fields, handling quoted fields and `""` escapes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+fn parse_cities(csv: &str) -> Vec<City> {
+    let mut lines = csv.lines();
+    let header = split_csv_line(lines.next().expect("CSV has no header"));
+    let column = |name: &str| {
+        header.iter().position(|h| h == name).unwrap_or_else(|| panic!("missing column {}", name))
+    };
+    let columns = [
+        column("id"),
+        column("name"),
+        column("state_id"),
+        column("state_code"),
+        column("state_name"),
+        column("country_id"),
+        column("country_code"),
+        column("country_name"),
+        column("latitude"),
+        column("longitude"),
+        column("native"),
+        column("population"),
+        column("timezone"),
+        column("wikiDataId"),
+    ];
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut fields = split_csv_line(line);
+            let mut take =
+                |i: usize| fields.get_mut(columns[i]).map(std::mem::take).unwrap_or_default();
+            City {
+                id: take(0),
+                name: take(1),
+                state_id: take(2),
+                state_code: take(3),
+                state_name: take(4),
+                country_id: take(5),
+                country_code: take(6),
+                country_name: take(7),
+                latitude: take(8),
+                longitude: take(9),
+                native: take(10),
+                population: take(11),
+                timezone: take(12),
+                wikidata_id: take(13),
+            }
+        })
+        .collect()
+}
+
+// =============================================================================
+// CONVERSION
+// =============================================================================
+
+/// Builds edits from cities, creating each state and country once.
+struct Importer<'a> {
+    schema: &'a Schema,
+    builder: EditBuilder<'a>,
+    states: HashSet<&'a str>,
+    countries: HashSet<&'a str>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(schema: &'a Schema) -> Self {
+        let builder = EditBuilder::new(import_id("edit:1"))
+            .name("Cities Import")
+            .author(import_id("author"))
+            .created_at(1_704_067_200_000_000)
+            .create_entity(schema.city, |e| e.text(properties::name(), "City", None))
+            .create_entity(schema.state, |e| e.text(properties::name(), "State", None))
+            .create_entity(schema.country, |e| e.text(properties::name(), "Country", None));
+        Self { schema, builder, states: HashSet::new(), countries: HashSet::new() }
+    }
+
+    /// Creates the country on first sight and returns its ID.
+    fn ensure_country(&mut self, city: &'a City) -> Id {
+        let id = import_id(&format!("country:{}", city.country_id));
+        if self.countries.insert(&city.country_id) {
+            let schema = self.schema;
+            self.add_entity(id, schema.country, |e| {
+                e.text(properties::name(), city.country_name.as_str(), None).text(
+                    schema.code,
+                    city.country_code.as_str(),
+                    None,
+                )
+            });
+        }
+        id
+    }
+
+    /// Creates the state, and its country, on first sight and returns its ID.
+    fn ensure_state(&mut self, city: &'a City) -> Id {
+        let id = import_id(&format!("state:{}", city.state_id));
+        if self.states.insert(&city.state_id) {
+            let schema = self.schema;
+            let country = self.ensure_country(city);
+            self.add_entity(id, schema.state, |e| {
+                e.text(properties::name(), city.state_name.as_str(), None).text(
+                    schema.code,
+                    city.state_code.as_str(),
+                    None,
+                )
+            });
+            self.relate(id, country, schema.in_country);
+        }
+        id
+    }
+
+    fn add_city(&mut self, city: &'a City) {
+        let state = self.ensure_state(city);
+        let country = self.ensure_country(city);
+        let id = import_id(&format!("city:{}", city.id));
+        let schema = self.schema;
+        self.add_entity(id, schema.city, |mut e| {
+            e = e.text(properties::name(), city.name.as_str(), None);
+            if !city.native.is_empty() && city.native != city.name {
+                e = e.text(schema.native_name, city.native.as_str(), None);
+            }
+            if let Ok(population) = city.population.parse() {
+                e = e.int64(schema.population, population, None);
+            }
+            if let (Ok(lat), Ok(lon)) = (city.latitude.parse(), city.longitude.parse()) {
+                e = e.point(properties::location(), lon, lat, None);
+            }
+            if !city.timezone.is_empty() {
+                e = e.text(properties::timezone(), city.timezone.as_str(), None);
+            }
+            if !city.wikidata_id.is_empty() {
+                e = e.text(schema.wikidata_id, city.wikidata_id.as_str(), None);
+            }
+            e
+        });
+        self.relate(id, state, schema.in_state);
+        self.relate(id, country, schema.in_country);
+    }
+
+    /// Creates an entity typed as both `ty` and a genesis Place.
+    fn add_entity(
+        &mut self,
+        id: Id,
+        ty: Id,
+        f: impl FnOnce(EntityBuilder<'a>) -> EntityBuilder<'a>,
+    ) {
+        self.replace_builder(|b| b.create_entity(id, f));
+        self.relate(id, ty, relation_types::types());
+        self.relate(id, types::place(), relation_types::types());
+    }
+
+    /// Adds a unique relation, whose ID is derived from its endpoints.
+    fn relate(&mut self, from: Id, to: Id, relation_type: Id) {
+        self.replace_builder(|b| b.create_unique_relation(from, to, relation_type));
+    }
+
+    fn replace_builder(&mut self, f: impl FnOnce(EditBuilder<'a>) -> EditBuilder<'a>) {
+        let builder = std::mem::replace(&mut self.builder, EditBuilder::new([0u8; 16]));
+        self.builder = f(builder);
+    }
+
+    fn build(self) -> Edit<'a> {
+        self.builder.build()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let out = args
+        .iter()
+        .position(|a| a == "--out")
+        .map(|i| args.get(i + 1).expect("--out requires a file").clone());
+    let path = args.first().filter(|a| *a != "--out");
+
+    let csv = match path {
+        Some(path) => {
+            println!("Reading: {}", path);
+            fs::read_to_string(path).expect("Failed to read cities CSV")
+        }
+        None => {
+            println!("Using the built-in sample (pass a CSV path to import a full dataset)");
+            SAMPLE.to_string()
+        }
+    };
+    let cities = parse_cities(&csv);
+    println!("Cities: {}", cities.len());
+
+    let schema = Schema::new();
+    let mut importer = Importer::new(&schema);
+    for city in &cities {
+        importer.add_city(city);
+    }
+    let (states, countries) = (importer.states.len(), importer.countries.len());
+    let edit = importer.build();
+    println!("States: {}, countries: {}, ops: {}", states, countries, edit.ops.len());
+
+    validate_edit(&edit, &schema.context()).expect("Edit failed validation");
+    let bytes = encode_edit_compressed(&edit, 3).expect("Failed to encode");
+    println!("Encoded: {} bytes", bytes.len());
+
+    let decoded = decode_edit(&bytes).expect("Failed to decode");
+    assert_eq!(decoded, edit);
+    let mut state = GraphState::new();
+    state.apply_edit(&decoded);
+    println!("Graph: {} entities, {} relations", state.entity_count(), state.relation_count());
+
+    if let Some(out) = out {
+        fs::write(&out, &bytes).expect("Failed to write edit");
+        println!("Wrote: {}", out);
+    }
+}
@@ -0,0 +1,30 @@
+//! Decodes arbitrary bytes as an edit.
+//!
+//! Covers every framing `decode_edit` dispatches on (GRC2, GRC2Z, GRC2L,
+//! GRC2N), so compressed inputs exercise the decompression size checks too.
+//! Decoding must return an error rather than panic or over-allocate.
+//!
+//! Uncompressed input is also walked with `RawEditReader`: whenever the
+//! full decoder accepts an edit, the lazy reader must locate the same ops.
+
+#![no_main]
+
+use grc_20::{decode_edit, decode_edit_with_options, DecodeOptions, RawEditReader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let decoded = decode_edit(data);
+    let _ = decode_edit_with_options(data, DecodeOptions::strict_ids());
+
+    let Ok(reader) = RawEditReader::new(data) else {
+        return;
+    };
+    let raw: Result<Vec<_>, _> = reader.clone().collect();
+    if let Ok(edit) = decoded {
+        let raw = raw.expect("raw reader must accept a decodable edit");
+        assert_eq!(raw.len(), edit.ops.len());
+        for (raw, op) in raw.iter().zip(&edit.ops) {
+            assert_eq!(&reader.decode(raw).expect("located op must decode"), op);
+        }
+    }
+});
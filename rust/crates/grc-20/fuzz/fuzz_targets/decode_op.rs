@@ -0,0 +1,31 @@
+//! Decodes arbitrary bytes as a sequence of ops.
+//!
+//! Dictionaries hold one property of every data type plus a few relation
+//! types, languages, units, and objects, so all op and value decoders are
+//! reachable without the fuzzer having to build a valid edit header.
+
+#![no_main]
+
+use grc_20::codec::op::decode_op;
+use grc_20::codec::primitives::Reader;
+use grc_20::{DataType, WireDictionaries};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let properties = (1..=12u8)
+        .filter_map(|tag| DataType::from_u8(tag).map(|data_type| ([tag; 16], data_type)))
+        .collect();
+    let dicts = WireDictionaries {
+        properties,
+        relation_types: vec![[20; 16], [21; 16]],
+        languages: vec![[30; 16], [31; 16]],
+        units: vec![[40; 16]],
+        objects: vec![[50; 16], [51; 16], [52; 16]],
+    };
+    let mut reader = Reader::new(data);
+    while !reader.is_empty() {
+        if decode_op(&mut reader, &dicts).is_err() {
+            break;
+        }
+    }
+});
@@ -0,0 +1,27 @@
+//! Decodes arbitrary bytes as a single value.
+//!
+//! The first byte picks the data type; the rest is the value payload. Unit
+//! and language indices resolve against small fixed dictionaries, so both
+//! in-range and out-of-range indices are reachable.
+
+#![no_main]
+
+use grc_20::codec::primitives::Reader;
+use grc_20::codec::value::decode_value;
+use grc_20::{DataType, WireDictionaries};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&tag, payload)) = data.split_first() else {
+        return;
+    };
+    let Some(data_type) = DataType::from_u8(tag % 12 + 1) else {
+        return;
+    };
+    let dicts = WireDictionaries {
+        languages: vec![[1; 16], [2; 16]],
+        units: vec![[3; 16]],
+        ..Default::default()
+    };
+    let _ = decode_value(&mut Reader::new(payload), data_type, &dicts);
+});
@@ -0,0 +1,184 @@
+//! Builds a structured edit from fuzz input and checks that it roundtrips.
+//!
+//! Any edit the encoder accepts must decode back to an equal edit, and
+//! canonical encoding must be idempotent. IDs come from a pool of eight so
+//! that properties, languages, and objects are shared across ops, which is
+//! where dictionary bookkeeping can go wrong.
+
+#![no_main]
+
+use arbitrary::{Result, Unstructured};
+use grc_20::model::{
+    CreateEntity, CreateRelation, CreateValueRef, DecimalMantissa, DeleteEntity, DeleteRelation,
+    Edit, EmbeddingSubType, Id, Op, PropertyValue, RestoreEntity, RestoreRelation, UnsetLanguage,
+    UnsetRelationField, UnsetValue, UpdateEntity, UpdateRelation, Value,
+};
+use grc_20::{decode_edit, encode_edit, encode_edit_with_options, EncodeOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(edit) = edit(&mut Unstructured::new(data)) else {
+        return;
+    };
+
+    // The encoder rejects some inputs (NaN floats, unnormalized decimals,
+    // conflicting property types); those are fine as long as it doesn't panic.
+    if let Ok(encoded) = encode_edit(&edit) {
+        let decoded = decode_edit(&encoded).expect("encoder output must decode");
+        assert_eq!(decoded, edit);
+    }
+
+    if let Ok(canonical) = encode_edit_with_options(&edit, EncodeOptions::canonical()) {
+        let decoded = decode_edit(&canonical).expect("canonical output must decode");
+        let again = encode_edit_with_options(&decoded, EncodeOptions::canonical())
+            .expect("decoded canonical edit must re-encode");
+        assert_eq!(again, canonical);
+    }
+});
+
+fn edit<'a>(u: &mut Unstructured<'a>) -> Result<Edit<'a>> {
+    let mut edit = Edit::new(id(u)?);
+    edit.name = u.arbitrary::<&str>()?.into();
+    edit.created_at = u.arbitrary()?;
+    for _ in 0..u.int_in_range(0..=3)? {
+        edit.authors.push(id(u)?);
+    }
+    for _ in 0..u.int_in_range(0..=32)? {
+        edit.ops.push(op(u)?);
+    }
+    Ok(edit)
+}
+
+fn id(u: &mut Unstructured<'_>) -> Result<Id> {
+    Ok([u.int_in_range(0..=7)?; 16])
+}
+
+fn optional_id(u: &mut Unstructured<'_>) -> Result<Option<Id>> {
+    Ok(if u.arbitrary()? { Some(id(u)?) } else { None })
+}
+
+fn op<'a>(u: &mut Unstructured<'a>) -> Result<Op<'a>> {
+    Ok(match u.int_in_range(0..=8)? {
+        0 => Op::CreateEntity(CreateEntity { id: id(u)?, values: values(u)? }),
+        1 => {
+            let mut unset_values = Vec::new();
+            for _ in 0..u.int_in_range(0..=3)? {
+                let language = match u.int_in_range(0..=2)? {
+                    0 => UnsetLanguage::All,
+                    1 => UnsetLanguage::English,
+                    _ => UnsetLanguage::Specific(id(u)?),
+                };
+                unset_values.push(UnsetValue { property: id(u)?, language });
+            }
+            let mut remove_values = Vec::new();
+            for _ in 0..u.int_in_range(0..=2)? {
+                remove_values.push(id(u)?);
+            }
+            Op::UpdateEntity(UpdateEntity {
+                id: id(u)?,
+                set_properties: values(u)?,
+                unset_values,
+                remove_values,
+            })
+        }
+        2 => Op::DeleteEntity(DeleteEntity { id: id(u)? }),
+        3 => Op::RestoreEntity(RestoreEntity { id: id(u)? }),
+        4 => Op::CreateRelation(CreateRelation {
+            id: id(u)?,
+            relation_type: id(u)?,
+            from: id(u)?,
+            from_is_value_ref: u.arbitrary()?,
+            from_space: optional_id(u)?,
+            from_version: optional_id(u)?,
+            to: id(u)?,
+            to_is_value_ref: u.arbitrary()?,
+            to_space: optional_id(u)?,
+            to_version: optional_id(u)?,
+            entity: optional_id(u)?,
+            position: position(u)?,
+        }),
+        5 => {
+            // Unset fields travel as a bitmask, so only a duplicate-free
+            // list in wire order roundtrips unchanged.
+            let mut unset = Vec::new();
+            for field in [
+                UnsetRelationField::FromSpace,
+                UnsetRelationField::FromVersion,
+                UnsetRelationField::ToSpace,
+                UnsetRelationField::ToVersion,
+                UnsetRelationField::Position,
+            ] {
+                if u.ratio(1, 4)? {
+                    unset.push(field);
+                }
+            }
+            Op::UpdateRelation(UpdateRelation {
+                id: id(u)?,
+                from_space: optional_id(u)?,
+                from_version: optional_id(u)?,
+                to_space: optional_id(u)?,
+                to_version: optional_id(u)?,
+                position: position(u)?,
+                unset,
+            })
+        }
+        6 => Op::DeleteRelation(DeleteRelation { id: id(u)? }),
+        7 => Op::RestoreRelation(RestoreRelation { id: id(u)? }),
+        _ => Op::CreateValueRef(CreateValueRef {
+            id: id(u)?,
+            entity: id(u)?,
+            property: id(u)?,
+            language: optional_id(u)?,
+            space: optional_id(u)?,
+        }),
+    })
+}
+
+fn position<'a>(u: &mut Unstructured<'a>) -> Result<Option<std::borrow::Cow<'a, str>>> {
+    Ok(if u.arbitrary()? { Some(u.arbitrary::<&str>()?.into()) } else { None })
+}
+
+fn values<'a>(u: &mut Unstructured<'a>) -> Result<Vec<PropertyValue<'a>>> {
+    let mut values = Vec::new();
+    for _ in 0..u.int_in_range(0..=4)? {
+        values.push(PropertyValue { property: id(u)?, value: value(u)?, clock: None });
+    }
+    Ok(values)
+}
+
+fn value<'a>(u: &mut Unstructured<'a>) -> Result<Value<'a>> {
+    Ok(match u.int_in_range(0..=11)? {
+        0 => Value::Bool(u.arbitrary()?),
+        1 => Value::Int64 { value: u.arbitrary()?, unit: optional_id(u)? },
+        2 => Value::Float64 { value: u.arbitrary()?, unit: optional_id(u)? },
+        3 => {
+            let mantissa = if u.arbitrary()? {
+                DecimalMantissa::I64(u.arbitrary()?)
+            } else {
+                DecimalMantissa::Big(u.arbitrary::<&[u8]>()?.into())
+            };
+            Value::Decimal { exponent: u.arbitrary()?, mantissa, unit: optional_id(u)? }
+        }
+        4 => Value::Text { value: u.arbitrary::<&str>()?.into(), language: optional_id(u)? },
+        5 => Value::Bytes(u.arbitrary::<&[u8]>()?.into()),
+        6 => Value::Date(u.arbitrary::<&str>()?.into()),
+        7 => Value::Time(u.arbitrary::<&str>()?.into()),
+        8 => Value::Datetime(u.arbitrary::<&str>()?.into()),
+        9 => Value::Schedule(u.arbitrary::<&str>()?.into()),
+        10 => Value::Point {
+            lon: u.arbitrary()?,
+            lat: u.arbitrary()?,
+            alt: u.arbitrary()?,
+        },
+        _ => {
+            let sub_type = match u.int_in_range(0..=2)? {
+                0 => EmbeddingSubType::Float32,
+                1 => EmbeddingSubType::Int8,
+                _ => EmbeddingSubType::Binary,
+            };
+            let dims = u.int_in_range(0..=64)?;
+            let data = u.bytes(sub_type.bytes_for_dims(dims))?;
+            Value::Embedding { sub_type, dims, data: data.into() }
+        }
+    })
+}
@@ -0,0 +1,266 @@
+//! Author IDs for wallet-based identities.
+//!
+//! Edit authors are IDs. So that the same wallet maps to the same author ID
+//! in every implementation, author IDs are derived from the identity (spec
+//! Section 4.1):
+//!
+//! ```text
+//! did:key           author_id = derived_uuid("grc20:author:" || did)
+//! Ethereum address  author_id = derived_uuid("grc20:author:eth:" || lowercase_hex_address)
+//! ```
+//!
+//! This module parses identities ([`AuthorIdentity`]), derives their IDs
+//! ([`author_id`]), and maps IDs back to identities ([`AuthorRegistry`]),
+//! which a hash cannot do on its own.
+//!
+//! ```
+//! use grc_20::authors::author_id;
+//!
+//! let checksummed = author_id("0x52908400098527886E0F7030069857D2E4169EE7").unwrap();
+//! let did = author_id("did:pkh:eip155:137:0x52908400098527886e0f7030069857d2e4169ee7").unwrap();
+//! assert_eq!(checksummed, did);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::AuthorError;
+use crate::model::id::{derived_uuid, BASE58_ALPHABET};
+use crate::model::Id;
+
+/// A wallet-based author identity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AuthorIdentity {
+    /// A `did:key` DID, without any path, query, or fragment.
+    DidKey(String),
+    /// A 20-byte Ethereum address.
+    Ethereum([u8; 20]),
+}
+
+impl AuthorIdentity {
+    /// Parses an identity.
+    ///
+    /// Accepts a `did:key` DID or DID URL (the path, query, and fragment
+    /// are dropped), a `0x`-prefixed Ethereum address in any case, and a
+    /// `did:pkh:eip155:<chain_id>:<address>` DID, which is the same
+    /// identity on every chain. EIP-55 checksums are not verified.
+    pub fn parse(s: &str) -> Result<Self, AuthorError> {
+        let invalid = |reason| AuthorError::Invalid { identity: s.to_string(), reason };
+        if let Some(key) = s.strip_prefix("did:key:") {
+            let key = key.split(['/', '?', '#']).next().unwrap_or_default();
+            let encoded = key.strip_prefix('z').ok_or(invalid("did:key must be base58btc (z)"))?;
+            if decode_base58(encoded).is_none_or(|key| key.len() < 2) {
+                return Err(invalid("did:key is not a base58btc multicodec key"));
+            }
+            return Ok(Self::DidKey(format!("did:key:{}", key)));
+        }
+        if let Some(account) = s.strip_prefix("did:pkh:eip155:") {
+            let (chain_id, address) = account.split_once(':').ok_or(invalid("missing address"))?;
+            if chain_id.is_empty() || !chain_id.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid("eip155 chain ID must be decimal"));
+            }
+            return parse_address(address).map(Self::Ethereum).ok_or(invalid("invalid address"));
+        }
+        if s.starts_with("0x") {
+            return parse_address(s).map(Self::Ethereum).ok_or(invalid("invalid address"));
+        }
+        match s.strip_prefix("did:").and_then(|rest| rest.split_once(':')) {
+            Some((method, _)) => Err(AuthorError::UnsupportedMethod { method: method.to_string() }),
+            None => Err(invalid("expected a did:key, did:pkh, or 0x address")),
+        }
+    }
+
+    /// Returns the string the author ID is derived from, after
+    /// `grc20:author:`.
+    pub fn id_key(&self) -> String {
+        match self {
+            Self::DidKey(did) => did.clone(),
+            Self::Ethereum(_) => format!("eth:{}", self),
+        }
+    }
+
+    /// Derives the author ID for this identity.
+    pub fn id(&self) -> Id {
+        derived_uuid(format!("grc20:author:{}", self.id_key()).as_bytes())
+    }
+}
+
+impl fmt::Display for AuthorIdentity {
+    /// Writes the DID, or the address as `0x` and lowercase hex.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DidKey(did) => f.write_str(did),
+            Self::Ethereum(address) => {
+                f.write_str("0x")?;
+                address.iter().try_for_each(|b| write!(f, "{:02x}", b))
+            }
+        }
+    }
+}
+
+impl FromStr for AuthorIdentity {
+    type Err = AuthorError;
+
+    fn from_str(s: &str) -> Result<Self, AuthorError> {
+        Self::parse(s)
+    }
+}
+
+/// Derives the author ID for an identity (see [`AuthorIdentity::parse`]).
+pub fn author_id(identity: &str) -> Result<Id, AuthorError> {
+    AuthorIdentity::parse(identity).map(|identity| identity.id())
+}
+
+/// Derives the author ID for an Ethereum address.
+pub fn ethereum_author_id(address: &[u8; 20]) -> Id {
+    AuthorIdentity::Ethereum(*address).id()
+}
+
+/// Maps author IDs back to the identities they were derived from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorRegistry {
+    identities: HashMap<Id, AuthorIdentity>,
+}
+
+impl AuthorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an identity, returning its author ID.
+    pub fn add(&mut self, identity: &str) -> Result<Id, AuthorError> {
+        let identity = AuthorIdentity::parse(identity)?;
+        let id = identity.id();
+        self.identities.insert(id, identity);
+        Ok(id)
+    }
+
+    /// Gets the identity for an author ID, if registered.
+    pub fn identity(&self, id: &Id) -> Option<&AuthorIdentity> {
+        self.identities.get(id)
+    }
+
+    /// Returns true if the author ID is registered.
+    pub fn contains(&self, id: &Id) -> bool {
+        self.identities.contains_key(id)
+    }
+
+    /// Returns the number of registered identities.
+    pub fn len(&self) -> usize {
+        self.identities.len()
+    }
+
+    /// Returns true if no identities are registered.
+    pub fn is_empty(&self) -> bool {
+        self.identities.is_empty()
+    }
+}
+
+/// Parses `0x` followed by 40 hex digits in any case.
+fn parse_address(s: &str) -> Option<[u8; 20]> {
+    let hex = s.strip_prefix("0x")?;
+    if hex.len() != 40 {
+        return None;
+    }
+    let mut address = [0u8; 20];
+    for (byte, chunk) in address.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    }
+    Some(address)
+}
+
+/// Decodes base58 (Bitcoin alphabet) of any length.
+fn decode_base58(s: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = s.bytes().take_while(|c| *c == b'1').count();
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.into_iter().skip_while(|b| *b == 0));
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::id::format_id;
+
+    const DID_KEY: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+    const ADDRESS: &str = "0x52908400098527886E0F7030069857D2E4169EE7";
+
+    #[test]
+    fn test_parse() {
+        let did = AuthorIdentity::parse(&format!("{}#key-1", DID_KEY)).unwrap();
+        assert_eq!(did, AuthorIdentity::DidKey(DID_KEY.to_string()));
+        assert_eq!(did.to_string(), DID_KEY);
+
+        let eth: AuthorIdentity = ADDRESS.parse().unwrap();
+        assert_eq!(eth.to_string(), ADDRESS.to_ascii_lowercase());
+        assert_eq!(eth.id_key(), format!("eth:{}", ADDRESS.to_ascii_lowercase()));
+        let pkh = format!("did:pkh:eip155:1:{}", ADDRESS);
+        assert_eq!(AuthorIdentity::parse(&pkh).unwrap(), eth);
+
+        assert_eq!(
+            AuthorIdentity::parse("did:web:example.com"),
+            Err(AuthorError::UnsupportedMethod { method: "web".to_string() })
+        );
+        for bad in [
+            "",
+            "did:key:6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            "did:key:z0OIl",
+            "did:key:z",
+            "0x5290",
+            "0x52908400098527886E0F7030069857D2E4169EEG",
+            "did:pkh:eip155:0x52908400098527886E0F7030069857D2E4169EE7",
+            "did:pkh:eip155:x:0x52908400098527886E0F7030069857D2E4169EE7",
+        ] {
+            assert!(
+                matches!(AuthorIdentity::parse(bad), Err(AuthorError::Invalid { .. })),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_derivation_vectors() {
+        assert_eq!(format_id(&author_id(DID_KEY).unwrap()), "d99c3a60967a8cc5a7ca9b064ffcc73e");
+        assert_eq!(format_id(&author_id(ADDRESS).unwrap()), "c9c145b350d0847db67f374a203b2a53");
+        let eth = AuthorIdentity::parse(ADDRESS).unwrap();
+        let AuthorIdentity::Ethereum(address) = eth else { unreachable!() };
+        assert_eq!(ethereum_author_id(&address), eth.id());
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        let mut registry = AuthorRegistry::new();
+        assert!(registry.is_empty());
+        let id = registry.add(&ADDRESS.to_ascii_lowercase()).unwrap();
+        assert_eq!(registry.add(ADDRESS).unwrap(), id);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.identity(&id).unwrap().to_string(), ADDRESS.to_ascii_lowercase());
+        assert!(!registry.contains(&author_id(DID_KEY).unwrap()));
+    }
+
+    #[test]
+    fn test_base58() {
+        assert_eq!(decode_base58(""), Some(vec![]));
+        assert_eq!(decode_base58("11"), Some(vec![0, 0]));
+        assert_eq!(decode_base58("1z"), Some(vec![0, 57]));
+        assert_eq!(decode_base58("5R"), Some(vec![1, 0]));
+        // ed25519-pub multicodec header
+        let key = decode_base58(&DID_KEY["did:key:z".len()..]).unwrap();
+        assert_eq!((key.len(), &key[..2]), (34, &[0xed, 0x01][..]));
+    }
+}
@@ -0,0 +1,51 @@
+//! CRC32C (Castagnoli) checksums for edit integrity trailers.
+//!
+//! Edits encoded with [`EncodeOptions::checksum`](crate::EncodeOptions)
+//! end with the CRC32C of every preceding byte, little-endian (spec
+//! Section 6.3). CRC32C detects all burst errors up to 32 bits, which is
+//! what transport corruption looks like; it is not a defense against
+//! tampering (sign canonical bytes for that).
+
+const POLY: u32 = 0x82F6_3B78;
+
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC32C of `data`.
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_append(0, data)
+}
+
+/// Extends `crc`, the CRC32C of some bytes, to cover `data` following them.
+///
+/// `crc32c_append(crc32c(a), b)` equals the CRC32C of `a` then `b`, so
+/// data written in pieces can be checksummed as it goes.
+pub fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |crc, &b| TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c_check_values() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(&[0u8; 32]), 0x8A91_36AA);
+        assert_eq!(crc32c_append(crc32c(b"1234"), b"56789"), 0xE306_9283);
+    }
+}
@@ -0,0 +1,264 @@
+//! Encoding and decoding edits over async I/O (tokio).
+//!
+//! [`decode_edit_from`] reads an edit in any framing from an
+//! [`AsyncRead`] until end of stream. `GRC2Z` input is decompressed as it
+//! arrives, so a service receiving an edit over the network holds the
+//! decompressed edit but never the whole compressed one alongside it.
+//! [`encode_edit_to`] writes an edit to an [`AsyncWrite`]; with zstd, only
+//! the compressed bytes are buffered (see
+//! [`encode_edit_compressed_to_writer`]).
+//!
+//! ```ignore
+//! let edit = decode_edit_from(&mut socket).await?;
+//! encode_edit_to(&edit, &mut socket).await?;
+//! ```
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::codec::edit::{decode_uncompressed_owned, decompress, DecodeOptions};
+#[cfg(feature = "zstd")]
+use crate::codec::primitives::Reader;
+use crate::compression::{
+    compression_algorithm, edit_dictionary_id, encode_edit_with_compression, CompressionOptions,
+};
+#[cfg(feature = "zstd")]
+use crate::compression::{encode_edit_compressed_to_writer, CompressionAlgorithm};
+use crate::error::{AioError, DecodeError};
+use crate::limits::MAX_EDIT_SIZE;
+use crate::model::Edit;
+
+/// Size of the reads [`decode_edit_from`] decompresses `GRC2Z` input in.
+#[cfg(feature = "zstd")]
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Reads and decodes an edit in any framing, until end of stream.
+///
+/// Dictionary-compressed (`GRC2D`) edits fail with
+/// [`DecodeError::UnknownDictionary`], as with
+/// [`decode_edit`](crate::codec::decode_edit).
+pub async fn decode_edit_from<R: AsyncRead + Unpin>(reader: R) -> Result<Edit<'static>, AioError> {
+    decode_edit_from_with_options(reader, DecodeOptions::new()).await
+}
+
+/// Reads and decodes an edit in any framing with decoding options.
+pub async fn decode_edit_from_with_options<R: AsyncRead + Unpin>(
+    reader: R,
+    options: DecodeOptions,
+) -> Result<Edit<'static>, AioError> {
+    // No framing is larger than the edit it carries by more than a few bytes
+    let mut reader = reader.take(MAX_EDIT_SIZE as u64 + 64);
+    let mut magic = [0u8; 5];
+    reader.read_exact(&mut magic).await.map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => {
+            AioError::Decode(DecodeError::UnexpectedEof { context: "magic" })
+        }
+        _ => AioError::Io(e),
+    })?;
+
+    #[cfg(feature = "zstd")]
+    if compression_algorithm(&magic) == Some(CompressionAlgorithm::Zstd) {
+        let declared_size = read_uncompressed_size(&mut reader).await?;
+        let data = decompress_zstd(&mut reader, declared_size).await?;
+        return Ok(decode_uncompressed_owned(&data, options)?);
+    }
+
+    let mut input = magic.to_vec();
+    reader.read_to_end(&mut input).await?;
+    if input.len() > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "edit",
+            len: input.len(),
+            max: MAX_EDIT_SIZE,
+        }
+        .into());
+    }
+    if compression_algorithm(&input).is_some() {
+        let data = decompress(&input)?;
+        return Ok(decode_uncompressed_owned(&data, options)?);
+    }
+    if let Some(id) = edit_dictionary_id(&input)? {
+        return Err(DecodeError::UnknownDictionary { id }.into());
+    }
+    Ok(decode_uncompressed_owned(&input, options)?)
+}
+
+/// Reads the `uncompressed_size` varint after a compressed edit's magic.
+#[cfg(feature = "zstd")]
+async fn read_uncompressed_size<R: AsyncRead + Unpin>(reader: &mut R) -> Result<usize, AioError> {
+    let mut bytes = Vec::with_capacity(10);
+    loop {
+        let byte = reader.read_u8().await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => {
+                AioError::Decode(DecodeError::UnexpectedEof { context: "uncompressed_size" })
+            }
+            _ => AioError::Io(e),
+        })?;
+        bytes.push(byte);
+        if byte & 0x80 == 0 || bytes.len() == 10 {
+            break;
+        }
+    }
+    let declared_size = Reader::new(&bytes).read_varint("uncompressed_size")? as usize;
+    if declared_size > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "uncompressed_size",
+            len: declared_size,
+            max: MAX_EDIT_SIZE,
+        }
+        .into());
+    }
+    Ok(declared_size)
+}
+
+/// Decompresses zstd data as it is read, up to one chunk past
+/// `declared_size` so a decompression bomb fails the size check instead of
+/// exhausting memory.
+#[cfg(feature = "zstd")]
+async fn decompress_zstd<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    declared_size: usize,
+) -> Result<Vec<u8>, AioError> {
+    use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
+
+    let failed = |e: std::io::Error| DecodeError::DecompressionFailed(e.to_string());
+    let mut decoder = Decoder::new().map_err(failed)?;
+    let mut input = vec![0u8; READ_CHUNK_SIZE];
+    let mut output = vec![0u8; READ_CHUNK_SIZE];
+    let mut data = Vec::with_capacity(declared_size);
+    loop {
+        let read = reader.read(&mut input).await?;
+        if read == 0 {
+            break;
+        }
+        let mut in_buf = InBuffer::around(&input[..read]);
+        // Drain the decoder: it may hold more output than one buffer
+        loop {
+            let written = {
+                let mut out_buf = OutBuffer::around(&mut output[..]);
+                decoder.run(&mut in_buf, &mut out_buf).map_err(failed)?;
+                out_buf.pos()
+            };
+            data.extend_from_slice(&output[..written]);
+            if data.len() > declared_size {
+                return Err(DecodeError::UncompressedSizeMismatch {
+                    declared: declared_size,
+                    actual: data.len(),
+                }
+                .into());
+            }
+            if in_buf.pos() == read && written < output.len() {
+                break;
+            }
+        }
+    }
+    if data.len() != declared_size {
+        return Err(DecodeError::UncompressedSizeMismatch {
+            declared: declared_size,
+            actual: data.len(),
+        }
+        .into());
+    }
+    Ok(data)
+}
+
+/// Encodes an edit with the default compression and writes it to `writer`.
+pub async fn encode_edit_to<W: AsyncWrite + Unpin>(
+    edit: &Edit<'_>,
+    writer: W,
+) -> Result<(), AioError> {
+    encode_edit_to_with_options(edit, CompressionOptions::default(), writer).await
+}
+
+/// Encodes an edit with the given compression and writes it to `writer`,
+/// then flushes it.
+pub async fn encode_edit_to_with_options<W: AsyncWrite + Unpin>(
+    edit: &Edit<'_>,
+    options: CompressionOptions,
+    mut writer: W,
+) -> Result<(), AioError> {
+    let bytes = match options.algorithm {
+        #[cfg(feature = "zstd")]
+        CompressionAlgorithm::Zstd => {
+            encode_edit_compressed_to_writer(edit, options.level, options.encode, Vec::new())?
+        }
+        _ => encode_edit_with_compression(edit, options)?,
+    };
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::{encode_edit, EncodeOptions};
+    use crate::compression::CompressionAlgorithm;
+    use crate::model::EditBuilder;
+
+    fn edit(entities: u32) -> Edit<'static> {
+        let mut builder = EditBuilder::new([9u8; 16]).name("import");
+        for i in 0..entities {
+            let mut id = [0xA0; 16];
+            id[..4].copy_from_slice(&i.to_be_bytes());
+            builder = builder.create_entity(id, |e| {
+                e.text([1u8; 16], format!("Entity {}", i), None).int64([2u8; 16], i as i64, None)
+            });
+        }
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_through_pipe() {
+        let edit = edit(5000);
+        for algorithm in
+            [CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4, CompressionAlgorithm::None]
+        {
+            if !algorithm.is_available() {
+                continue;
+            }
+            // A small pipe delivers the edit in many short reads
+            let (writer, reader) = tokio::io::duplex(256);
+            let options = CompressionOptions::new(algorithm)
+                .with_encode_options(EncodeOptions::new().with_checksum());
+            let (written, decoded) = tokio::join!(
+                encode_edit_to_with_options(&edit, options, writer),
+                decode_edit_from(reader)
+            );
+            written.unwrap();
+            assert_eq!(decoded.unwrap(), edit);
+        }
+
+        let plain = encode_edit(&edit).unwrap();
+        assert_eq!(decode_edit_from(plain.as_slice()).await.unwrap(), edit);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_input() {
+        let decode = |input: Vec<u8>| async move { decode_edit_from(input.as_slice()).await };
+        assert!(matches!(
+            decode(b"GRC".to_vec()).await,
+            Err(AioError::Decode(DecodeError::UnexpectedEof { context: "magic" }))
+        ));
+        assert!(matches!(
+            decode(b"XXXX\x01".to_vec()).await,
+            Err(AioError::Decode(DecodeError::InvalidMagic { .. }))
+        ));
+
+        let mut compressed = Vec::new();
+        encode_edit_to(&edit(10), &mut compressed).await.unwrap();
+        // Truncated zstd data decompresses to less than declared
+        let truncated = compressed[..compressed.len() - 8].to_vec();
+        assert!(matches!(
+            decode(truncated).await,
+            Err(AioError::Decode(DecodeError::UncompressedSizeMismatch { .. }))
+        ));
+        // Understated size: decompression stops past it
+        let mut understated = compressed.clone();
+        understated[5] = 1;
+        understated.remove(6);
+        assert!(matches!(
+            decode(understated).await,
+            Err(AioError::Decode(DecodeError::UncompressedSizeMismatch { declared: 1, .. }))
+        ));
+    }
+}
@@ -0,0 +1,355 @@
+//! Edit batches with a shared dictionary.
+//!
+//! Many small edits tend to repeat the same property, language, and unit
+//! IDs, and each standalone edit spends 17 bytes per dictionary entry
+//! re-declaring them. A batch container emits a shared dictionary once and
+//! stores every edit with only its local entries; local indices continue
+//! after the shared ones.
+//!
+//! Container layout:
+//!
+//! ```text
+//! "GRC2B" version
+//! shared dictionaries (same layout as an edit's dictionaries)
+//! edit_count: varint
+//! edit_count × (length: varint, edit: GRC2 edit with local dictionaries)
+//! ```
+//!
+//! Edits inside a batch are always non-canonical; use standalone canonical
+//! edits when hashing or signing.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::codec::edit::{decode_edit_borrowed, write_edit_header, DecodeOptions};
+use crate::codec::op::{collect_op_ids, encode_op};
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{
+    FORMAT_VERSION, MAGIC_BATCH, MAGIC_UNCOMPRESSED, MAX_DICT_SIZE, MAX_EDITS_PER_BATCH,
+    MAX_EDIT_SIZE, MIN_FORMAT_VERSION,
+};
+use crate::model::{DataType, DictionaryBuilder, Edit, Id, Op, WireDictionaries};
+
+/// Builds a shared dictionary from the entries used by at least `min_edits`
+/// of the given edits.
+///
+/// Entries keep their first-seen order. Properties used with different data
+/// types across edits are left out, since a dictionary entry fixes the type.
+pub fn shared_dictionary(edits: &[Edit<'_>], min_edits: usize) -> WireDictionaries {
+    #[derive(Default)]
+    struct Counter {
+        order: Vec<Id>,
+        counts: FxHashMap<Id, usize>,
+    }
+
+    impl Counter {
+        fn add(&mut self, ids: &[Id]) {
+            for id in ids {
+                let count = self.counts.entry(*id).or_insert(0);
+                if *count == 0 {
+                    self.order.push(*id);
+                }
+                *count += 1;
+            }
+        }
+
+        fn shared(&self, min_edits: usize) -> Vec<Id> {
+            self.order.iter().copied().filter(|id| self.counts[id] >= min_edits).collect()
+        }
+    }
+
+    let mut properties = Counter::default();
+    let mut property_types: FxHashMap<Id, Option<DataType>> = FxHashMap::default();
+    let mut relation_types = Counter::default();
+    let mut languages = Counter::default();
+    let mut units = Counter::default();
+    let mut objects = Counter::default();
+
+    for edit in edits {
+        let mut builder = DictionaryBuilder::with_capacity(edit.ops.len());
+        for op in &edit.ops {
+            collect_op_ids(op, &mut builder);
+        }
+        let dicts = builder.build();
+
+        let ids: Vec<Id> = dicts.properties.iter().map(|(id, _)| *id).collect();
+        properties.add(&ids);
+        for (id, data_type) in &dicts.properties {
+            let entry = property_types.entry(*id).or_insert(Some(*data_type));
+            if *entry != Some(*data_type) {
+                *entry = None;
+            }
+        }
+        relation_types.add(&dicts.relation_types);
+        languages.add(&dicts.languages);
+        units.add(&dicts.units);
+        objects.add(&dicts.objects);
+    }
+
+    WireDictionaries {
+        properties: properties
+            .shared(min_edits)
+            .into_iter()
+            .filter_map(|id| property_types[&id].map(|data_type| (id, data_type)))
+            .collect(),
+        relation_types: relation_types.shared(min_edits),
+        languages: languages.shared(min_edits),
+        units: units.shared(min_edits),
+        objects: objects.shared(min_edits),
+        value_clocks: false,
+        batch_records: false,
+        remove_values: false,
+        strings: None,
+    }
+}
+
+/// Encodes edits into a batch container that emits `shared` once.
+///
+/// Any entry an edit needs beyond `shared` is stored in that edit. Fails
+/// with [`EncodeError::SharedPropertyTypeMismatch`] if an edit sets a value
+/// whose type differs from a shared property's declared type.
+pub fn encode_batch(
+    edits: &[Edit<'_>],
+    shared: &WireDictionaries,
+) -> Result<Vec<u8>, EncodeError> {
+    if edits.len() > MAX_EDITS_PER_BATCH {
+        return Err(EncodeError::LengthExceedsLimit {
+            field: "edits",
+            len: edits.len(),
+            max: MAX_EDITS_PER_BATCH,
+        });
+    }
+
+    let shared_types: FxHashMap<Id, DataType> = shared.properties.iter().copied().collect();
+    let seed = DictionaryBuilder::with_seed(shared);
+
+    let mut writer = Writer::new();
+    writer.write_bytes(MAGIC_BATCH);
+    writer.write_byte(FORMAT_VERSION);
+    seed.write_dictionaries(&mut writer);
+    writer.write_varint(edits.len() as u64);
+
+    for edit in edits {
+        check_shared_types(edit, &shared_types)?;
+        let bytes = encode_seeded_edit(edit, seed.clone())?;
+        writer.write_bytes_prefixed(&bytes);
+    }
+
+    Ok(writer.into_bytes())
+}
+
+/// Decodes a batch container produced by [`encode_batch`].
+///
+/// Edits borrow from `input` (zero-copy).
+pub fn decode_batch(input: &[u8]) -> Result<Vec<Edit<'_>>, DecodeError> {
+    if input.len() < MAGIC_BATCH.len() {
+        return Err(DecodeError::UnexpectedEof { context: "magic" });
+    }
+    if &input[0..MAGIC_BATCH.len()] != MAGIC_BATCH {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&input[0..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+
+    let mut reader = Reader::new(&input[MAGIC_BATCH.len()..]);
+    let version = reader.read_byte("version")?;
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+
+    let shared = read_dictionaries(&mut reader)?;
+
+    let count = reader.read_varint("edit_count")? as usize;
+    if count > MAX_EDITS_PER_BATCH {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "edits",
+            len: count,
+            max: MAX_EDITS_PER_BATCH,
+        });
+    }
+
+    let mut edits = Vec::with_capacity(count.min(reader.remaining_len()));
+    for _ in 0..count {
+        let len = reader.read_varint("edit_len")? as usize;
+        if len > MAX_EDIT_SIZE {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "edit",
+                len,
+                max: MAX_EDIT_SIZE,
+            });
+        }
+        let bytes = reader.read_bytes(len, "edit")?;
+        if bytes.len() < 4 || &bytes[0..4] != MAGIC_UNCOMPRESSED {
+            return Err(DecodeError::MalformedEncoding { context: "batch edit magic" });
+        }
+        edits.push(decode_edit_borrowed(bytes, Some(&shared), DecodeOptions::new())?);
+    }
+
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes after batch" });
+    }
+    Ok(edits)
+}
+
+/// Encodes one edit against a seeded builder, writing only local entries.
+fn encode_seeded_edit(
+    edit: &Edit<'_>,
+    mut dict_builder: DictionaryBuilder,
+) -> Result<Vec<u8>, EncodeError> {
+    // Property types are determined from values themselves (per-edit typing)
+    let property_types = FxHashMap::default();
+    dict_builder.set_value_clocks(edit.has_value_clocks());
+
+    let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
+    for op in &edit.ops {
+        encode_op(&mut ops_writer, op, &mut dict_builder, &property_types)?;
+    }
+    let ops_bytes = ops_writer.into_bytes();
+
+    let mut writer = Writer::with_capacity(256 + ops_bytes.len());
+    write_edit_header(&mut writer, edit, &edit.authors, edit.provenance.as_ref(), 0);
+    dict_builder.write_local_dictionaries(&mut writer);
+    writer.write_varint(edit.ops.len() as u64);
+    writer.write_bytes(&ops_bytes);
+    Ok(writer.into_bytes())
+}
+
+/// Checks that values set on shared properties match the shared data type.
+fn check_shared_types(
+    edit: &Edit<'_>,
+    shared_types: &FxHashMap<Id, DataType>,
+) -> Result<(), EncodeError> {
+    for op in &edit.ops {
+        let values = match op {
+            Op::CreateEntity(ce) => &ce.values,
+            Op::UpdateEntity(ue) => &ue.set_properties,
+            _ => continue,
+        };
+        for pv in values {
+            let actual = pv.value.data_type();
+            match shared_types.get(&pv.property) {
+                Some(&shared) if shared != actual => {
+                    return Err(EncodeError::SharedPropertyTypeMismatch {
+                        property: pv.property,
+                        shared,
+                        actual,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads a dictionary section (same layout as an edit's dictionaries).
+fn read_dictionaries(reader: &mut Reader<'_>) -> Result<WireDictionaries, DecodeError> {
+    let property_count = reader.read_varint("property_count")? as usize;
+    if property_count > MAX_DICT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "properties",
+            len: property_count,
+            max: MAX_DICT_SIZE,
+        });
+    }
+    let mut properties = Vec::with_capacity(property_count.min(reader.remaining_len() / 17));
+    let mut seen = FxHashSet::default();
+    for _ in 0..property_count {
+        let id = reader.read_id("property_id")?;
+        if !seen.insert(id) {
+            return Err(DecodeError::DuplicateDictionaryEntry { dict: "properties", id });
+        }
+        let dt_byte = reader.read_byte("data_type")?;
+        let data_type = DataType::from_u8(dt_byte)
+            .ok_or(DecodeError::InvalidDataType { data_type: dt_byte })?;
+        properties.push((id, data_type));
+    }
+
+    let local = WireDictionaries {
+        properties,
+        relation_types: reader.read_id_vec(MAX_DICT_SIZE, "relation_types")?,
+        languages: reader.read_id_vec(MAX_DICT_SIZE, "languages")?,
+        units: reader.read_id_vec(MAX_DICT_SIZE, "units")?,
+        objects: reader.read_id_vec(MAX_DICT_SIZE, "objects")?,
+        value_clocks: false,
+        batch_records: false,
+        remove_values: false,
+        strings: None,
+    };
+    // Merging into an empty seed runs the duplicate checks for the ID lists
+    WireDictionaries::default().extended_with(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::codec::edit::encode_edit;
+    use crate::model::{CreateEntity, PropertyValue, Value};
+
+    fn small_edit(n: u8) -> Edit<'static> {
+        let mut edit = Edit::new([n; 16]);
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: [100 + n; 16],
+            values: vec![
+                PropertyValue {
+                    property: [1u8; 16],
+                    value: Value::Text {
+                        value: Cow::Owned(format!("Name {}", n)),
+                        language: Some([2u8; 16]),
+                    },
+                    clock: None,
+                },
+                PropertyValue {
+                    property: [3u8; 16],
+                    value: Value::Int64 { value: n as i64, unit: Some([4u8; 16]) },
+                    clock: None,
+                },
+                PropertyValue {
+                    property: [50 + n; 16],
+                    value: Value::Bool(true),
+                    clock: None,
+                },
+            ],
+        }));
+        edit
+    }
+
+    #[test]
+    fn test_batch_roundtrip_and_savings() {
+        let edits: Vec<_> = (0..10).map(small_edit).collect();
+        let shared = shared_dictionary(&edits, 2);
+        assert_eq!(
+            shared.properties,
+            vec![([1u8; 16], DataType::Text), ([3u8; 16], DataType::Int64)]
+        );
+        assert_eq!(shared.languages, vec![[2u8; 16]]);
+        assert_eq!(shared.units, vec![[4u8; 16]]);
+
+        let batch = encode_batch(&edits, &shared).unwrap();
+        let standalone: usize = edits.iter().map(|e| encode_edit(e).unwrap().len()).sum();
+        assert!(batch.len() < standalone);
+
+        let decoded = decode_batch(&batch).unwrap();
+        assert_eq!(decoded, edits);
+    }
+
+    #[test]
+    fn test_batch_rejects_type_mismatch_and_bad_input() {
+        let edits = vec![small_edit(1)];
+        let shared = WireDictionaries {
+            properties: vec![([3u8; 16], DataType::Float64)],
+            ..WireDictionaries::default()
+        };
+        assert!(matches!(
+            encode_batch(&edits, &shared),
+            Err(EncodeError::SharedPropertyTypeMismatch { .. })
+        ));
+
+        let mut batch = encode_batch(&edits, &WireDictionaries::default()).unwrap();
+        batch.push(0);
+        assert!(decode_batch(&batch).is_err());
+        assert!(matches!(decode_batch(b"GRC2X"), Err(DecodeError::InvalidMagic { .. })));
+    }
+}
@@ -0,0 +1,378 @@
+//! Delta edits: an edit stored as a patch against a base edit.
+//!
+//! Consecutive published versions of an edit (re-imports, corrections)
+//! usually share almost all of their ops. A delta carries the new edit's
+//! header and the ops the base does not have, plus a script of runs that
+//! says where to splice in ranges of the base's ops.
+//!
+//! Container layout:
+//!
+//! ```text
+//! "GRC2P" version
+//! base_id: 16 bytes
+//! base_op_count: varint
+//! edit_len: varint, edit: GRC2 edit (new header, inserted ops only)
+//! run_count: varint
+//! run_count × run:
+//!   0x00 start: varint, len: varint   // copy base ops start..start+len
+//!   0x01 len: varint                  // take the next len inserted ops
+//! ```
+//!
+//! The base is identified by ID and op count only; applying a delta to a
+//! different base with the same shape produces garbage, so sync protocols
+//! should pin the base by hash.
+
+use crate::codec::edit::{decode_edit, encode_edit};
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{
+    FORMAT_VERSION, MAGIC_DELTA, MAX_DELTA_DISTANCE, MAX_EDIT_SIZE, MAX_OPS_PER_EDIT,
+    MIN_FORMAT_VERSION,
+};
+use crate::model::{Edit, Op};
+
+const RUN_COPY: u8 = 0;
+const RUN_INSERT: u8 = 1;
+
+/// One step of a delta's op script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Run {
+    /// Ops `start..start + len` of the base.
+    Copy { start: usize, len: usize },
+    /// The next `len` ops carried in the delta.
+    Insert { len: usize },
+}
+
+/// Returns true if `input` starts with the delta magic.
+pub fn is_delta(input: &[u8]) -> bool {
+    input.starts_with(MAGIC_DELTA)
+}
+
+/// Encodes `new` as a delta against `base`.
+///
+/// Ops are matched by equality, so an op that changed in any way is carried
+/// in full. The header (name, authors, provenance) always comes from `new`.
+pub fn encode_edit_delta(base: &Edit<'_>, new: &Edit<'_>) -> Result<Vec<u8>, EncodeError> {
+    let runs = diff_ops(&base.ops, &new.ops);
+
+    let mut inserted = Vec::new();
+    let mut position = 0;
+    for run in &runs {
+        match *run {
+            Run::Copy { len, .. } => position += len,
+            Run::Insert { len } => {
+                inserted.extend(new.ops[position..position + len].iter().cloned());
+                position += len;
+            }
+        }
+    }
+    let patch = Edit {
+        id: new.id,
+        name: new.name.clone(),
+        authors: new.authors.clone(),
+        created_at: new.created_at,
+        provenance: new.provenance.clone(),
+        ops: inserted,
+    };
+
+    let mut writer = Writer::new();
+    writer.write_bytes(MAGIC_DELTA);
+    writer.write_byte(FORMAT_VERSION);
+    writer.write_id(&base.id);
+    writer.write_varint(base.ops.len() as u64);
+    writer.write_bytes_prefixed(&encode_edit(&patch)?);
+    writer.write_varint(runs.len() as u64);
+    for run in runs {
+        match run {
+            Run::Copy { start, len } => {
+                writer.write_byte(RUN_COPY);
+                writer.write_varint(start as u64);
+                writer.write_varint(len as u64);
+            }
+            Run::Insert { len } => {
+                writer.write_byte(RUN_INSERT);
+                writer.write_varint(len as u64);
+            }
+        }
+    }
+    Ok(writer.into_bytes())
+}
+
+/// Rebuilds an edit from its base and a delta made by [`encode_edit_delta`].
+///
+/// Fails with [`DecodeError::DeltaBaseMismatch`] if `base` is not the edit
+/// the delta was made against.
+pub fn apply_delta<'a>(base: &Edit<'a>, patch: &'a [u8]) -> Result<Edit<'a>, DecodeError> {
+    if patch.len() < MAGIC_DELTA.len() {
+        return Err(DecodeError::UnexpectedEof { context: "magic" });
+    }
+    if !is_delta(patch) {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&patch[0..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+
+    let mut reader = Reader::new(&patch[MAGIC_DELTA.len()..]);
+    let version = reader.read_byte("version")?;
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+    let base_id = reader.read_id("base_id")?;
+    let base_op_count = reader.read_varint("base_op_count")?;
+    if base_id != base.id || base_op_count != base.ops.len() as u64 {
+        return Err(DecodeError::DeltaBaseMismatch { base: base_id });
+    }
+
+    let len = reader.read_varint("edit_len")? as usize;
+    if len > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit { field: "edit", len, max: MAX_EDIT_SIZE });
+    }
+    let mut edit = decode_edit(reader.read_bytes(len, "edit")?)?;
+    let mut inserted = std::mem::take(&mut edit.ops).into_iter();
+
+    let run_count = reader.read_varint("run_count")? as usize;
+    if run_count > MAX_OPS_PER_EDIT {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "runs",
+            len: run_count,
+            max: MAX_OPS_PER_EDIT,
+        });
+    }
+    for _ in 0..run_count {
+        match reader.read_byte("run")? {
+            RUN_COPY => {
+                let start = reader.read_varint("copy_start")? as usize;
+                let len = reader.read_varint("copy_len")? as usize;
+                let ops = start
+                    .checked_add(len)
+                    .and_then(|end| base.ops.get(start..end))
+                    .ok_or(DecodeError::MalformedEncoding {
+                        context: "copy run outside base ops",
+                    })?;
+                edit.ops.extend(ops.iter().cloned());
+            }
+            RUN_INSERT => {
+                let len = reader.read_varint("insert_len")? as usize;
+                if len > inserted.len() {
+                    return Err(DecodeError::MalformedEncoding {
+                        context: "insert run past inserted ops",
+                    });
+                }
+                edit.ops.extend(inserted.by_ref().take(len));
+            }
+            _ => return Err(DecodeError::MalformedEncoding { context: "unknown delta run" }),
+        }
+        if edit.ops.len() > MAX_OPS_PER_EDIT {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "ops",
+                len: edit.ops.len(),
+                max: MAX_OPS_PER_EDIT,
+            });
+        }
+    }
+
+    if inserted.len() != 0 {
+        return Err(DecodeError::MalformedEncoding { context: "unused inserted ops in delta" });
+    }
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding { context: "trailing bytes after delta" });
+    }
+    Ok(edit)
+}
+
+/// Computes the runs that build `new` from `base`.
+///
+/// The common prefix and suffix are matched directly; the rest with Myers'
+/// diff, which is cheap when the two differ by a few ops. Past
+/// [`MAX_DELTA_DISTANCE`] the middle is carried in full.
+fn diff_ops(base: &[Op<'_>], new: &[Op<'_>]) -> Vec<Run> {
+    let prefix = base.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let middle = (&base[prefix..base.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut runs = Vec::new();
+    push_run(&mut runs, Run::Copy { start: 0, len: prefix });
+    let mut next = 0;
+    for (x, y) in myers_matches(middle.0, middle.1, MAX_DELTA_DISTANCE).unwrap_or_default() {
+        push_run(&mut runs, Run::Insert { len: y - next });
+        push_run(&mut runs, Run::Copy { start: prefix + x, len: 1 });
+        next = y + 1;
+    }
+    push_run(&mut runs, Run::Insert { len: middle.1.len() - next });
+    push_run(&mut runs, Run::Copy { start: base.len() - suffix, len: suffix });
+    runs
+}
+
+/// Appends a run, merging it into the previous one where possible.
+fn push_run(runs: &mut Vec<Run>, run: Run) {
+    match (runs.last_mut(), run) {
+        (_, Run::Copy { len: 0, .. } | Run::Insert { len: 0 }) => {}
+        (Some(Run::Copy { start, len }), Run::Copy { start: next, len: more })
+            if *start + *len == next =>
+        {
+            *len += more;
+        }
+        (Some(Run::Insert { len }), Run::Insert { len: more }) => *len += more,
+        _ => runs.push(run),
+    }
+}
+
+/// Returns the matched (base, new) index pairs of a shortest edit script
+/// between `a` and `b`, or None if it takes more than `max_distance` edits.
+fn myers_matches(
+    a: &[Op<'_>],
+    b: &[Op<'_>],
+    max_distance: usize,
+) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m).min(max_distance as isize);
+    let offset = max + 1;
+    // v[offset + k] is the furthest x reached on diagonal k = x - y
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+/// Walks the recorded frontiers back from (n, m), collecting the diagonals.
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (n, m);
+    let mut matches = Vec::new();
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d) as usize];
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let k = x - y;
+            let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+            (at(prev_k), at(prev_k) - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    matches.reverse();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DeleteEntity, EditBuilder};
+
+    fn delete(n: u8) -> Op<'static> {
+        Op::DeleteEntity(DeleteEntity { id: [n; 16] })
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let mut builder = EditBuilder::new([1u8; 16]).name("Import");
+        for n in 0..50u8 {
+            builder = builder.create_entity([n; 16], |e| e.text([200u8; 16], "Entity", None));
+        }
+        let base = builder.build();
+
+        let mut new = base.clone();
+        new.name = "Import (fixed)".into();
+        new.ops.remove(10);
+        new.ops[20] = delete(20);
+        new.ops.insert(35, delete(99));
+        new.ops.push(delete(100));
+
+        let delta = encode_edit_delta(&base, &new).unwrap();
+        assert!(is_delta(&delta));
+        assert!(delta.len() * 5 < encode_edit(&new).unwrap().len());
+        assert_eq!(apply_delta(&base, &delta).unwrap(), new);
+
+        // Identical and disjoint edits
+        let same = encode_edit_delta(&base, &base).unwrap();
+        assert_eq!(apply_delta(&base, &same).unwrap(), base);
+        let empty = Edit::new([1u8; 16]);
+        let all_new = encode_edit_delta(&empty, &new).unwrap();
+        assert_eq!(apply_delta(&empty, &all_new).unwrap(), new);
+
+        // The base must match
+        assert!(matches!(
+            apply_delta(&new, &delta),
+            Err(DecodeError::DeltaBaseMismatch { base }) if base == [1u8; 16]
+        ));
+    }
+
+    #[test]
+    fn test_diff_ops_shortest() {
+        // Small pseudo-random op lists over a tiny alphabet, so that many
+        // ops repeat and the diff has real choices to make
+        let mut state = 0x2545_F491u32;
+        let mut next = |bound: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % bound
+        };
+        for _ in 0..200 {
+            let a: Vec<Op> = (0..next(12)).map(|_| delete(next(4) as u8)).collect();
+            let b: Vec<Op> = (0..next(12)).map(|_| delete(next(4) as u8)).collect();
+
+            let runs = diff_ops(&a, &b);
+            let mut rebuilt = Vec::new();
+            let mut copied = 0;
+            let mut inserted = 0;
+            for run in runs {
+                match run {
+                    Run::Copy { start, len } => {
+                        rebuilt.extend(a[start..start + len].iter().cloned());
+                        copied += len;
+                    }
+                    Run::Insert { len } => {
+                        rebuilt.extend(b[rebuilt.len()..rebuilt.len() + len].iter().cloned());
+                        inserted += len;
+                    }
+                }
+            }
+            assert_eq!(rebuilt, b);
+            assert_eq!(copied + inserted, b.len());
+
+            // Every op of the longest common subsequence is copied
+            let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    lcs[i][j] = if a[i] == b[j] {
+                        lcs[i + 1][j + 1] + 1
+                    } else {
+                        lcs[i + 1][j].max(lcs[i][j + 1])
+                    };
+                }
+            }
+            assert_eq!(copied, lcs[0][0]);
+        }
+    }
+}
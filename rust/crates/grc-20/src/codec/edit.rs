@@ -2,25 +2,41 @@
 //!
 //! Implements the wire format for edits (spec Section 6.3).
 
-use std::borrow::Cow;
-use std::io::Read;
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
-use rustc_hash::{FxHashMap, FxHashSet};
-
-use crate::codec::op::{decode_op, encode_op};
+use crate::codec::op::collect_op_ids;
+use crate::codec::op::{
+    create_entity_run, decode_ops_with, encode_batch_create_entities, encode_op,
+};
 use crate::codec::primitives::{Reader, Writer};
+use crate::compression::{
+    compression_algorithm, decompress_payload, edit_dictionary_id, encode_edit_with_compression,
+    CompressionOptions,
+};
+#[cfg(feature = "bumpalo")]
+use crate::compression::{decompress_into, read_uncompressed_size};
 use crate::error::{DecodeError, EncodeError};
+use crate::hash::{FxHashMap, FxHashSet};
+use crate::checksum::crc32c;
+use crate::codec::version::FormatVersion;
 use crate::limits::{
-    FORMAT_VERSION, MAGIC_COMPRESSED, MAGIC_UNCOMPRESSED, MAX_AUTHORS, MAX_DICT_SIZE,
-    MAX_EDIT_SIZE, MAX_OPS_PER_EDIT, MAX_STRING_LEN, MIN_FORMAT_VERSION,
+    MAGIC_UNCOMPRESSED, MAX_AUTHORS, MAX_DICT_SIZE, MAX_EDIT_SIZE, MAX_METADATA_ENTRIES,
+    MAX_OPS_PER_EDIT, MAX_PARENT_EDITS, MAX_STRING_LEN,
+};
+use crate::model::{
+    DataType, DictionaryBuilder, Edit, Id, Op, Provenance, WireDictionaries, NIL_ID,
 };
-use crate::model::{DataType, DictionaryBuilder, Edit, Id, Op, WireDictionaries};
+use crate::validate::ids::{first_invalid_id, IdPolicy};
+use crate::validate::ValidationProfile;
 
 // =============================================================================
 // DECODING
 // =============================================================================
 
-/// Decompresses a GRC2Z compressed edit, returning the uncompressed bytes.
+/// Decompresses a compressed edit (GRC2Z, GRC2L, or GRC2N), returning the
+/// uncompressed bytes.
 ///
 /// Use this with [`decode_edit`] for zero-copy decoding of compressed data:
 ///
@@ -33,17 +49,18 @@ pub fn decompress(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
     if input.len() < 5 {
         return Err(DecodeError::UnexpectedEof { context: "magic" });
     }
-    if &input[0..5] != MAGIC_COMPRESSED {
+    let Some(algorithm) = compression_algorithm(input) else {
         let mut found = [0u8; 4];
         found.copy_from_slice(&input[0..4]);
         return Err(DecodeError::InvalidMagic { found });
-    }
-    decompress_zstd(&input[5..])
+    };
+    decompress_payload(&input[5..], algorithm)
 }
 
 /// Decodes an Edit from binary data with zero-copy borrowing.
 ///
-/// Handles both compressed (GRC2Z) and uncompressed (GRC2) formats.
+/// Handles both compressed (GRC2Z, GRC2L, GRC2N) and uncompressed (GRC2)
+/// formats.
 /// For true zero-copy with compressed data, use [`decompress`] first:
 ///
 /// ```ignore
@@ -57,16 +74,46 @@ pub fn decompress(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
 ///
 /// If you pass compressed data directly, it will decompress internally
 /// and allocate owned strings (no zero-copy benefit).
+///
+/// Dictionary-compressed (GRC2D) input fails with
+/// [`DecodeError::UnknownDictionary`]; decode it with
+/// [`decode_edit_with_dict`](crate::compression::decode_edit_with_dict).
 pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
+    decode_any(input).map(|(_, edit)| edit)
+}
+
+/// Decodes an Edit of any supported format version, returning the version
+/// it was written in.
+///
+/// Accepts the same framings as [`decode_edit`]. Older versions are
+/// upgraded to the current model: fields they cannot carry are left empty.
+pub fn decode_any(input: &[u8]) -> Result<(FormatVersion, Edit<'_>), DecodeError> {
+    decode_any_with(input, DecodeOptions::new())
+}
+
+/// [`decode_any`] with decoding options.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "decode_edit",
+        level = "debug",
+        skip_all,
+        fields(bytes = input.len())
+    )
+)]
+fn decode_any_with(
+    input: &[u8],
+    options: DecodeOptions,
+) -> Result<(FormatVersion, Edit<'_>), DecodeError> {
     if input.len() < 4 {
         return Err(DecodeError::UnexpectedEof { context: "magic" });
     }
 
     // Detect compression
-    if input.len() >= 5 && &input[0..5] == MAGIC_COMPRESSED {
+    if let Some(algorithm) = compression_algorithm(input) {
         // Compressed: decompress and decode with allocations
         // (for zero-copy, caller should use decompress() first)
-        let decompressed = decompress_zstd(&input[5..])?;
+        let decompressed = decompress_payload(&input[5..], algorithm)?;
         if decompressed.len() > MAX_EDIT_SIZE {
             return Err(DecodeError::LengthExceedsLimit {
                 field: "edit",
@@ -74,7 +121,12 @@ pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
                 max: MAX_EDIT_SIZE,
             });
         }
-        decode_edit_owned(&decompressed)
+        let edit = decode_edit_owned(&decompressed, options)?;
+        Ok((decoded_version(&decompressed), edit))
+    } else if let Some(id) = edit_dictionary_id(input)? {
+        // Dictionary-compressed (GRC2D shares the GRC2 prefix, so check first):
+        // the caller must supply the dictionary
+        Err(DecodeError::UnknownDictionary { id })
     } else if &input[0..4] == MAGIC_UNCOMPRESSED {
         // Uncompressed: decode with zero-copy borrowing
         if input.len() > MAX_EDIT_SIZE {
@@ -84,7 +136,8 @@ pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
                 max: MAX_EDIT_SIZE,
             });
         }
-        decode_edit_borrowed(input)
+        let edit = decode_edit_borrowed(input, None, options)?;
+        Ok((decoded_version(input), edit))
     } else {
         let mut found = [0u8; 4];
         found.copy_from_slice(&input[0..4]);
@@ -92,25 +145,277 @@ pub fn decode_edit(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
     }
 }
 
-/// Decodes an Edit with zero-copy borrowing from the input.
-fn decode_edit_borrowed(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
-    let mut reader = Reader::new(input);
+/// Reads the version of an uncompressed edit that already decoded.
+fn decoded_version(data: &[u8]) -> FormatVersion {
+    FormatVersion::from_u8(data[4]).expect("decoding checked the version")
+}
 
-    // Skip magic (already validated)
-    reader.read_bytes(4, "magic")?;
+/// Options for decoding edits.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeOptions {
+    /// How op IDs that are not valid UUIDs are treated.
+    ///
+    /// Only [`IdPolicy::Strict`] changes decoding: the first malformed ID
+    /// fails with [`DecodeError::InvalidId`]. For warnings, decode normally
+    /// and run [`validate_ids`](crate::validate::ids::validate_ids).
+    pub id_policy: IdPolicy,
+    /// Keep extension ops (spec Section 6.4) as [`Op::Unknown`] instead of
+    /// failing with [`DecodeError::InvalidOpType`].
+    ///
+    /// Indexers that store edits should set this so that ops written by
+    /// newer clients survive a decode and re-encode unchanged. Unknown op
+    /// types below the extension range, and unknown data types, still fail:
+    /// their length isn't encoded, so they can't be skipped.
+    pub preserve_unknown_ops: bool,
+    /// Scan float32 embeddings for NaN (on by default).
+    ///
+    /// The scan is the main cost of decoding embedding-heavy edits. Trusted
+    /// pipelines that decode edits they encoded themselves can turn it off;
+    /// embeddings can then be checked when needed with [`Value::validate`].
+    /// The other embedding checks (sub-type, length, binary padding) still
+    /// run.
+    ///
+    /// [`Value::validate`]: crate::model::Value::validate
+    pub validate_embeddings: bool,
+    /// Which value checks are hard errors (see [`ValidationProfile`]).
+    pub profile: ValidationProfile,
+}
 
-    // Version
-    let version = reader.read_byte("version")?;
-    if version < MIN_FORMAT_VERSION || version > FORMAT_VERSION {
-        return Err(DecodeError::UnsupportedVersion { version });
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            id_policy: IdPolicy::default(),
+            preserve_unknown_ops: false,
+            validate_embeddings: true,
+            profile: ValidationProfile::default(),
+        }
     }
+}
 
-    // Header
-    let edit_id = reader.read_id("edit_id")?;
-    let name = Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "name")?);
-    let authors = reader.read_id_vec(MAX_AUTHORS, "authors")?;
-    let created_at = reader.read_signed_varint("created_at")?;
+impl DecodeOptions {
+    /// Creates default (permissive) decoding options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates options that reject IDs which are not RFC 4122 UUIDs.
+    pub fn strict_ids() -> Self {
+        Self { id_policy: IdPolicy::Strict, ..Self::default() }
+    }
+
+    /// Creates options that keep extension ops as [`Op::Unknown`].
+    pub fn lenient() -> Self {
+        Self { preserve_unknown_ops: true, ..Self::default() }
+    }
+
+    /// Sets which value checks are hard errors.
+    pub fn with_profile(mut self, profile: ValidationProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+}
+
+/// Decodes an Edit with the given options.
+///
+/// Accepts the same framings as [`decode_edit`].
+pub fn decode_edit_with_options(
+    input: &[u8],
+    options: DecodeOptions,
+) -> Result<Edit<'_>, DecodeError> {
+    let (_, edit) = decode_any_with(input, options)?;
+    if options.id_policy == IdPolicy::Strict {
+        if let Some((field, id, reason)) = first_invalid_id(&edit) {
+            return Err(DecodeError::InvalidId { field, id, reason });
+        }
+    }
+    Ok(edit)
+}
+
+/// Decodes as much of an edit as possible, for triaging corrupt input.
+///
+/// Returns the ops decoded before the first structural failure, along with
+/// every error met. A checksum mismatch is reported but decoding goes on;
+/// any other error ends it, since op boundaries can't be found past a
+/// malformed op. If the framing, header, or dictionaries can't be read,
+/// the edit is empty (with a nil ID if the header is unreadable). The
+/// errors are empty exactly when [`decode_edit`] would succeed.
+pub fn decode_edit_lossy(input: &[u8]) -> (Edit<'static>, Vec<DecodeError>) {
+    let mut errors = Vec::new();
+    let empty = || Edit::new(NIL_ID);
+    let data: Cow<'_, [u8]> = if input.len() < 4 {
+        errors.push(DecodeError::UnexpectedEof { context: "magic" });
+        return (empty(), errors);
+    } else if let Some(algorithm) = compression_algorithm(input) {
+        match decompress_payload(&input[5..], algorithm) {
+            Ok(data) => Cow::Owned(data),
+            Err(e) => {
+                errors.push(e);
+                return (empty(), errors);
+            }
+        }
+    } else {
+        match edit_dictionary_id(input) {
+            Ok(None) if &input[0..4] == MAGIC_UNCOMPRESSED => Cow::Borrowed(input),
+            Ok(None) => {
+                let mut found = [0u8; 4];
+                found.copy_from_slice(&input[0..4]);
+                errors.push(DecodeError::InvalidMagic { found });
+                return (empty(), errors);
+            }
+            Ok(Some(id)) => {
+                errors.push(DecodeError::UnknownDictionary { id });
+                return (empty(), errors);
+            }
+            Err(e) => {
+                errors.push(e);
+                return (empty(), errors);
+            }
+        }
+    };
+    if data.len() > MAX_EDIT_SIZE {
+        errors.push(DecodeError::LengthExceedsLimit {
+            field: "edit",
+            len: data.len(),
+            max: MAX_EDIT_SIZE,
+        });
+        return (empty(), errors);
+    }
+
+    let mut reader = Reader::new(&data);
+    let header = match read_edit_header(&mut reader) {
+        Err(e) if matches!(e.kind(), DecodeError::ChecksumMismatch { .. }) => {
+            errors.push(e);
+            reader = Reader::new(&data);
+            reader.set_verify_checksum(false);
+            read_edit_header(&mut reader)
+        }
+        header => header,
+    };
+    let EditHeader { id, name, authors, created_at, provenance, dicts } = match header {
+        Ok(header) => header,
+        Err(e) => {
+            errors.push(e);
+            return (empty(), errors);
+        }
+    };
+    let mut edit = Edit {
+        id,
+        name: Cow::Owned(name.to_string()),
+        authors,
+        created_at,
+        provenance: provenance.map(Provenance::into_owned),
+        ops: Vec::new(),
+    };
 
+    let op_count = match read_op_count(&mut reader) {
+        Ok(op_count) => op_count,
+        Err(e) => {
+            errors.push(e);
+            return (edit, errors);
+        }
+    };
+    let mut record = Vec::new();
+    while edit.ops.len() < op_count {
+        let op_index = edit.ops.len();
+        if let Err(e) = decode_ops_with(&mut reader, &dicts, false, &mut record) {
+            errors.push(e.at("ops", reader.position(), Some(op_index)));
+            break;
+        }
+        edit.ops.extend(record.drain(..).map(op_to_owned));
+    }
+    if edit.ops.len() > op_count {
+        errors.push(OP_COUNT_EXCEEDED.at("ops", reader.position(), None));
+    }
+    (edit, errors)
+}
+
+/// Decodes an Edit whose strings and byte payloads live in `arena`.
+///
+/// Compressed input is decompressed straight into the arena, and
+/// uncompressed input is copied there. The edit is then decoded zero-copy
+/// from that buffer, so names, text values, and bytes borrow from the
+/// arena and the edit does not borrow `input`. Op and value lists are
+/// ordinary `Vec`s. An indexer decoding many small edits can reuse one
+/// arena and [`reset`](bumpalo::Bump::reset) it between batches:
+///
+/// ```ignore
+/// let mut arena = Bump::new();
+/// for batch in log.chunks(1024) {
+///     for entry in batch {
+///         let edit = decode_edit_in(&arena, entry)?;
+///         state.apply_edit(&edit);
+///     }
+///     arena.reset();
+/// }
+/// ```
+///
+/// Accepts the same framings as [`decode_edit`].
+#[cfg(feature = "bumpalo")]
+pub fn decode_edit_in<'b>(
+    arena: &'b bumpalo::Bump,
+    input: &[u8],
+) -> Result<Edit<'b>, DecodeError> {
+    let data: &'b [u8] = match compression_algorithm(input) {
+        Some(algorithm) => {
+            let (size, compressed) = read_uncompressed_size(&input[5..])?;
+            let out = arena.alloc_slice_fill_copy(size, 0u8);
+            decompress_into(compressed, algorithm, out)?;
+            out
+        }
+        None => {
+            if input.len() > MAX_EDIT_SIZE {
+                return Err(DecodeError::LengthExceedsLimit {
+                    field: "edit",
+                    len: input.len(),
+                    max: MAX_EDIT_SIZE,
+                });
+            }
+            arena.alloc_slice_copy(input)
+        }
+    };
+    decode_edit(data)
+}
+
+/// Header fields and dictionaries of an uncompressed edit.
+pub(crate) struct EditHeader<'a> {
+    pub id: Id,
+    pub name: &'a str,
+    pub authors: Vec<Id>,
+    pub created_at: i64,
+    pub provenance: Option<Provenance<'a>>,
+    pub dicts: WireDictionaries,
+}
+
+/// Header flag bits (format version 2).
+const HEADER_HAS_SOURCE: u8 = 0x01;
+const HEADER_HAS_PARENTS: u8 = 0x02;
+const HEADER_HAS_METADATA: u8 = 0x04;
+const HEADER_HAS_CHECKSUM: u8 = 0x08;
+const HEADER_HAS_STRINGS: u8 = 0x10;
+const HEADER_HAS_BATCH_RECORDS: u8 = 0x20;
+const HEADER_HAS_REMOVE_VALUES: u8 = 0x40;
+const LAYOUT_FLAGS: u8 = HEADER_HAS_STRINGS | HEADER_HAS_BATCH_RECORDS | HEADER_HAS_REMOVE_VALUES;
+const PROVENANCE_FLAGS: u8 = HEADER_HAS_SOURCE | HEADER_HAS_PARENTS | HEADER_HAS_METADATA;
+
+/// Reads everything before the op count of an uncompressed edit.
+///
+/// The magic is skipped without being checked; callers validate it first.
+pub(crate) fn read_edit_header<'a>(
+    reader: &mut Reader<'a>,
+) -> Result<EditHeader<'a>, DecodeError> {
+    let EditHeader { id, name, authors, created_at, provenance, dicts: fields } =
+        read_edit_fields(reader).map_err(|e| e.at("header", reader.position(), None))?;
+    let dicts = read_dictionaries(reader, fields)
+        .map_err(|e| e.at("dictionaries", reader.position(), None))?;
+    Ok(EditHeader { id, name, authors, created_at, provenance, dicts })
+}
+
+/// Reads the dictionaries and string table, given the value encoding read
+/// from the header.
+fn read_dictionaries(
+    reader: &mut Reader<'_>,
+    fields: WireDictionaries,
+) -> Result<WireDictionaries, DecodeError> {
     // Schema dictionaries (with duplicate detection)
     let property_count = reader.read_varint("property_count")? as usize;
     if property_count > MAX_DICT_SIZE {
@@ -120,8 +425,10 @@ fn decode_edit_borrowed(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
             max: MAX_DICT_SIZE,
         });
     }
-    let mut properties = Vec::with_capacity(property_count);
-    let mut seen_props = FxHashSet::with_capacity_and_hasher(property_count, Default::default());
+    // Each entry is at least 17 bytes; don't trust the count for allocation
+    let capacity = property_count.min(reader.remaining_len() / 17);
+    let mut properties = Vec::with_capacity(capacity);
+    let mut seen_props = FxHashSet::with_capacity_and_hasher(capacity, Default::default());
     for _ in 0..property_count {
         let id = reader.read_id("property_id")?;
         if !seen_props.insert(id) {
@@ -133,130 +440,317 @@ fn decode_edit_borrowed(input: &[u8]) -> Result<Edit<'_>, DecodeError> {
         properties.push((id, data_type));
     }
 
-    let relation_types = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "relation_types")?;
-    let languages = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "languages")?;
-    let units = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "units")?;
-    let objects = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "objects")?;
+    let relation_types = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "relation_types")?;
+    let languages = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "languages")?;
+    let units = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "units")?;
+    let objects = read_id_vec_no_duplicates(reader, MAX_DICT_SIZE, "objects")?;
+    let strings = match fields.strings {
+        Some(_) => Some(read_string_table(reader)?),
+        None => None,
+    };
 
-    let dicts = WireDictionaries {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        properties = properties.len(),
+        relation_types = relation_types.len(),
+        languages = languages.len(),
+        units = units.len(),
+        objects = objects.len(),
+        strings = strings.as_ref().map_or(0, Vec::len),
+        "decoded dictionaries"
+    );
+    Ok(WireDictionaries {
         properties,
         relation_types,
         languages,
         units,
         objects,
-    };
+        value_clocks: fields.value_clocks,
+        batch_records: fields.batch_records,
+        remove_values: fields.remove_values,
+        strings,
+    })
+}
 
-    // Operations
-    let op_count = reader.read_varint("op_count")? as usize;
-    if op_count > MAX_OPS_PER_EDIT {
+/// Reads the string table, returning the byte range of each entry.
+fn read_string_table(reader: &mut Reader<'_>) -> Result<Vec<core::ops::Range<usize>>, DecodeError> {
+    let count = reader.read_varint("string_count")? as usize;
+    if count > MAX_DICT_SIZE {
         return Err(DecodeError::LengthExceedsLimit {
-            field: "ops",
-            len: op_count,
-            max: MAX_OPS_PER_EDIT,
+            field: "strings",
+            len: count,
+            max: MAX_DICT_SIZE,
         });
     }
-
-    let mut ops = Vec::with_capacity(op_count);
-    for _ in 0..op_count {
-        ops.push(decode_op(&mut reader, &dicts)?);
+    // Each entry is at least 1 byte; don't trust the count for allocation
+    let mut strings = Vec::with_capacity(count.min(reader.remaining_len()));
+    for _ in 0..count {
+        let value = reader.read_str(MAX_STRING_LEN, "string")?;
+        let end = reader.position();
+        strings.push(end - value.len()..end);
     }
-
-    Ok(Edit {
-        id: edit_id,
-        name,
-        authors,
-        created_at,
-        ops,
-    })
+    Ok(strings)
 }
 
-/// Decodes an Edit with allocations (for decompressed data).
-fn decode_edit_owned(data: &[u8]) -> Result<Edit<'static>, DecodeError> {
-    let mut reader = Reader::new(data);
+/// Reads the magic, version, and header fields of an uncompressed edit,
+/// stopping where the dictionaries begin. The returned dictionaries are
+/// empty apart from the value encoding (clocks, and an empty string table
+/// if the header announces one).
+///
+/// The reader must be at the start of the edit. The magic is skipped
+/// without being checked; callers validate it first. A checksum trailer is
+/// verified here and split off, so the reader stops before it.
+pub(crate) fn read_edit_fields<'a>(
+    reader: &mut Reader<'a>,
+) -> Result<EditHeader<'a>, DecodeError> {
+    let input = reader.remaining();
 
-    // Skip magic (already validated in decompress)
+    // Skip magic (already validated)
     reader.read_bytes(4, "magic")?;
 
     // Version
-    let version = reader.read_byte("version")?;
-    if version < MIN_FORMAT_VERSION || version > FORMAT_VERSION {
-        return Err(DecodeError::UnsupportedVersion { version });
+    let version = FormatVersion::try_from(reader.read_byte("version")?)?;
+
+    // Header, read by the path for its version
+    match version {
+        FormatVersion::V0 | FormatVersion::V1 => read_header_v1(reader),
+        FormatVersion::V2 => read_header_v2(reader, input),
+        FormatVersion::V3 => {
+            let mut header = read_header_v2(reader, input)?;
+            header.dicts.value_clocks = true;
+            Ok(header)
+        }
     }
+}
 
-    // Header - use allocating reads
-    let edit_id = reader.read_id("edit_id")?;
-    let name = Cow::Owned(reader.read_string(MAX_STRING_LEN, "name")?);
+/// Reads a version 1 header.
+fn read_header_v1<'a>(reader: &mut Reader<'a>) -> Result<EditHeader<'a>, DecodeError> {
+    let id = reader.read_id("edit_id")?;
+    let name = reader.read_str(MAX_STRING_LEN, "name")?;
     let authors = reader.read_id_vec(MAX_AUTHORS, "authors")?;
     let created_at = reader.read_signed_varint("created_at")?;
 
-    // Schema dictionaries (with duplicate detection)
-    let property_count = reader.read_varint("property_count")? as usize;
-    if property_count > MAX_DICT_SIZE {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "properties",
-            len: property_count,
-            max: MAX_DICT_SIZE,
-        });
+    Ok(EditHeader {
+        id,
+        name,
+        authors,
+        created_at,
+        provenance: None,
+        dicts: WireDictionaries::default(),
+    })
+}
+
+/// Reads a version 2 header: version 1 followed by the header flags and
+/// the provenance block. `input` is the whole edit, for the checksum.
+fn read_header_v2<'a>(
+    reader: &mut Reader<'a>,
+    input: &[u8],
+) -> Result<EditHeader<'a>, DecodeError> {
+    let EditHeader { id, name, authors, created_at, .. } = read_header_v1(reader)?;
+    let flags = reader.read_byte("header_flags")?;
+    if flags & !(PROVENANCE_FLAGS | HEADER_HAS_CHECKSUM | LAYOUT_FLAGS) != 0 {
+        return Err(DecodeError::ReservedBitsSet { context: "header_flags" });
     }
-    let mut properties = Vec::with_capacity(property_count);
-    let mut seen_props = FxHashSet::with_capacity_and_hasher(property_count, Default::default());
-    for _ in 0..property_count {
-        let id = reader.read_id("property_id")?;
-        if !seen_props.insert(id) {
-            return Err(DecodeError::DuplicateDictionaryEntry { dict: "properties", id });
+    // Check integrity before reading anything else
+    if flags & HEADER_HAS_CHECKSUM != 0 {
+        if reader.verifies_checksum() {
+            verify_checksum(reader, input)?;
+        } else {
+            reader.split_trailer(4, "checksum")?;
         }
-        let dt_byte = reader.read_byte("data_type")?;
-        let data_type = DataType::from_u8(dt_byte)
-            .ok_or(DecodeError::InvalidDataType { data_type: dt_byte })?;
-        properties.push((id, data_type));
     }
-
-    let relation_types = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "relation_types")?;
-    let languages = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "languages")?;
-    let units = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "units")?;
-    let objects = read_id_vec_no_duplicates(&mut reader, MAX_DICT_SIZE, "objects")?;
-
+    let provenance = match flags & PROVENANCE_FLAGS {
+        0 => None,
+        _ => Some(read_provenance(reader, flags)?),
+    };
     let dicts = WireDictionaries {
-        properties,
-        relation_types,
-        languages,
-        units,
-        objects,
+        strings: (flags & HEADER_HAS_STRINGS != 0).then(Vec::new),
+        batch_records: flags & HEADER_HAS_BATCH_RECORDS != 0,
+        remove_values: flags & HEADER_HAS_REMOVE_VALUES != 0,
+        ..WireDictionaries::default()
     };
 
-    // Operations - use allocating decode
-    let op_count = reader.read_varint("op_count")? as usize;
+    Ok(EditHeader { id, name, authors, created_at, provenance, dicts })
+}
+
+/// Splits off the checksum trailer and checks it against the rest of the
+/// edit.
+fn verify_checksum(reader: &mut Reader<'_>, input: &[u8]) -> Result<(), DecodeError> {
+    let trailer = reader.split_trailer(4, "checksum")?;
+    let expected = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let actual = crc32c(&input[..input.len() - 4]);
+    if expected != actual {
+        return Err(DecodeError::ChecksumMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// Reads the provenance fields announced by the header flags.
+fn read_provenance<'a>(reader: &mut Reader<'a>, flags: u8) -> Result<Provenance<'a>, DecodeError> {
+    let mut provenance = Provenance::default();
+    if flags & HEADER_HAS_SOURCE != 0 {
+        provenance.source = Some(reader.read_id("provenance_source")?);
+    }
+    if flags & HEADER_HAS_PARENTS != 0 {
+        provenance.parents = read_id_vec_no_duplicates(reader, MAX_PARENT_EDITS, "parents")?;
+    }
+    if flags & HEADER_HAS_METADATA != 0 {
+        let count = reader.read_varint("metadata_count")? as usize;
+        if count > MAX_METADATA_ENTRIES {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "metadata",
+                len: count,
+                max: MAX_METADATA_ENTRIES,
+            });
+        }
+        // Each entry is at least 2 bytes; don't trust the count for allocation
+        provenance.metadata = Vec::with_capacity(count.min(reader.remaining_len() / 2));
+        for _ in 0..count {
+            let key = reader.read_str(MAX_STRING_LEN, "metadata_key")?;
+            let value = reader.read_str(MAX_STRING_LEN, "metadata_value")?;
+            provenance.metadata.push((Cow::Borrowed(key), Cow::Borrowed(value)));
+        }
+    }
+    Ok(provenance)
+}
+
+/// Reads and checks the op count.
+fn read_op_count(reader: &mut Reader<'_>) -> Result<usize, DecodeError> {
+    let op_count = reader
+        .read_varint("op_count")
+        .map_err(|e| e.at("ops", reader.position(), None))? as usize;
     if op_count > MAX_OPS_PER_EDIT {
         return Err(DecodeError::LengthExceedsLimit {
             field: "ops",
             len: op_count,
             max: MAX_OPS_PER_EDIT,
-        });
+        }
+        .at("ops", reader.position(), None));
     }
+    Ok(op_count)
+}
+
+/// Decodes an Edit with zero-copy borrowing from the input.
+///
+/// With a `seed`, the edit's dictionaries hold only entries beyond the seed
+/// (see [`DictionaryBuilder::with_seed`]).
+pub(crate) fn decode_edit_borrowed<'a>(
+    input: &'a [u8],
+    seed: Option<&WireDictionaries>,
+    options: DecodeOptions,
+) -> Result<Edit<'a>, DecodeError> {
+    let mut reader = Reader::new(input);
+    reader.set_validate_embeddings(options.validate_embeddings);
+    reader.set_profile(options.profile);
+    let EditHeader { id: edit_id, name, authors, created_at, provenance, dicts } =
+        read_edit_header(&mut reader)?;
+    let name = Cow::Borrowed(name);
+    let dicts = match seed {
+        Some(seed) => seed.extended_with(dicts)?,
+        None => dicts,
+    };
 
-    let mut ops = Vec::with_capacity(op_count);
-    for _ in 0..op_count {
-        ops.push(decode_op_owned(&mut reader, &dicts)?);
+    // Operations
+    let op_count = read_op_count(&mut reader)?;
+    let mut ops = Vec::with_capacity(op_count.min(reader.remaining_len()));
+    while ops.len() < op_count {
+        let op_index = ops.len();
+        decode_ops_with(&mut reader, &dicts, options.preserve_unknown_ops, &mut ops)
+            .map_err(|e| e.at("ops", reader.position(), Some(op_index)))?;
+    }
+    if ops.len() > op_count {
+        return Err(OP_COUNT_EXCEEDED.at("ops", reader.position(), None));
     }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(ops = ops.len(), bytes = input.len(), "decoded ops");
 
     Ok(Edit {
         id: edit_id,
         name,
         authors,
         created_at,
+        provenance,
         ops,
     })
 }
 
-/// Decodes an Op with allocations (for decompressed data).
-fn decode_op_owned(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<Op<'static>, DecodeError> {
-    // Decode normally, then convert to owned
-    let op = decode_op(reader, dicts)?;
-    Ok(op_to_owned(op))
+/// Decodes an Edit with allocations (for decompressed data).
+pub(crate) fn decode_edit_owned(
+    data: &[u8],
+    options: DecodeOptions,
+) -> Result<Edit<'static>, DecodeError> {
+    let mut reader = Reader::new(data);
+    reader.set_validate_embeddings(options.validate_embeddings);
+    reader.set_profile(options.profile);
+    let EditHeader { id: edit_id, name, authors, created_at, provenance, dicts } =
+        read_edit_header(&mut reader)?;
+    let name = Cow::Owned(name.to_string());
+    let provenance = provenance.map(Provenance::into_owned);
+
+    // Operations - use allocating decode
+    let op_count = read_op_count(&mut reader)?;
+    let mut ops = Vec::with_capacity(op_count.min(reader.remaining_len()));
+    let mut record = Vec::new();
+    while ops.len() < op_count {
+        let op_index = ops.len();
+        decode_ops_with(&mut reader, &dicts, options.preserve_unknown_ops, &mut record)
+            .map_err(|e| e.at("ops", reader.position(), Some(op_index)))?;
+        ops.extend(record.drain(..).map(op_to_owned));
+    }
+    if ops.len() > op_count {
+        return Err(OP_COUNT_EXCEEDED.at("ops", reader.position(), None));
+    }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(ops = ops.len(), bytes = data.len(), "decoded ops");
+
+    Ok(Edit {
+        id: edit_id,
+        name,
+        authors,
+        created_at,
+        provenance,
+        ops,
+    })
 }
 
+/// Decodes uncompressed `GRC2` bytes into an owned Edit, with the checks
+/// [`decode_edit_with_options`] makes.
+#[cfg(feature = "tokio")]
+pub(crate) fn decode_uncompressed_owned(
+    data: &[u8],
+    options: DecodeOptions,
+) -> Result<Edit<'static>, DecodeError> {
+    if data.len() < 4 {
+        return Err(DecodeError::UnexpectedEof { context: "magic" });
+    }
+    if &data[0..4] != MAGIC_UNCOMPRESSED {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&data[0..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+    if data.len() > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "edit",
+            len: data.len(),
+            max: MAX_EDIT_SIZE,
+        });
+    }
+    let edit = decode_edit_owned(data, options)?;
+    if options.id_policy == IdPolicy::Strict {
+        if let Some((field, id, reason)) = first_invalid_id(&edit) {
+            return Err(DecodeError::InvalidId { field, id, reason });
+        }
+    }
+    Ok(edit)
+}
+
+/// Error for op records holding more ops than the declared op count; a
+/// BatchCreateEntities record must not run past it.
+pub(crate) const OP_COUNT_EXCEEDED: DecodeError =
+    DecodeError::MalformedEncoding { context: "op records exceed op_count" };
+
 /// Converts an Op with borrowed data to owned data.
-fn op_to_owned(op: Op<'_>) -> Op<'static> {
+pub(crate) fn op_to_owned(op: Op<'_>) -> Op<'static> {
     match op {
         Op::CreateEntity(ce) => Op::CreateEntity(crate::model::CreateEntity {
             id: ce.id,
@@ -266,6 +760,7 @@ fn op_to_owned(op: Op<'_>) -> Op<'static> {
             id: ue.id,
             set_properties: ue.set_properties.into_iter().map(pv_to_owned).collect(),
             unset_values: ue.unset_values,
+            remove_values: ue.remove_values,
         }),
         Op::DeleteEntity(de) => Op::DeleteEntity(de),
         Op::RestoreEntity(re) => Op::RestoreEntity(re),
@@ -295,6 +790,23 @@ fn op_to_owned(op: Op<'_>) -> Op<'static> {
         Op::DeleteRelation(dr) => Op::DeleteRelation(dr),
         Op::RestoreRelation(rr) => Op::RestoreRelation(rr),
         Op::CreateValueRef(cvr) => Op::CreateValueRef(cvr),
+        Op::CreateSpace(cs) => Op::CreateSpace(crate::model::CreateSpace {
+            id: cs.id,
+            name: Cow::Owned(cs.name.into_owned()),
+        }),
+        Op::AddMember(am) => Op::AddMember(am),
+        Op::RemoveMember(rm) => Op::RemoveMember(rm),
+        Op::UpdateSpaceMetadata(usm) => {
+            Op::UpdateSpaceMetadata(crate::model::UpdateSpaceMetadata {
+                space: usm.space,
+                name: usm.name.map(|n| Cow::Owned(n.into_owned())),
+                description: usm.description.map(|d| Cow::Owned(d.into_owned())),
+            })
+        }
+        Op::Unknown(op) => Op::Unknown(crate::model::UnknownOp {
+            tag: op.tag,
+            bytes: Cow::Owned(op.bytes.into_owned()),
+        }),
     }
 }
 
@@ -303,6 +815,7 @@ fn pv_to_owned(pv: crate::model::PropertyValue<'_>) -> crate::model::PropertyVal
     crate::model::PropertyValue {
         property: pv.property,
         value: value_to_owned(pv.value),
+        clock: pv.clock,
     }
 }
 
@@ -354,8 +867,9 @@ fn read_id_vec_no_duplicates(
         });
     }
 
-    let mut ids = Vec::with_capacity(count);
-    let mut seen = FxHashSet::with_capacity_and_hasher(count, Default::default());
+    let capacity = count.min(reader.remaining_len() / 16);
+    let mut ids = Vec::with_capacity(capacity);
+    let mut seen = FxHashSet::with_capacity_and_hasher(capacity, Default::default());
 
     for _ in 0..count {
         let id = reader.read_id(field)?;
@@ -368,42 +882,124 @@ fn read_id_vec_no_duplicates(
     Ok(ids)
 }
 
-fn decompress_zstd(compressed: &[u8]) -> Result<Vec<u8>, DecodeError> {
-    // Read uncompressed size
-    let mut reader = Reader::new(compressed);
-    let declared_size = reader.read_varint("uncompressed_size")? as usize;
+// =============================================================================
+// ENCODING
+// =============================================================================
 
-    if declared_size > MAX_EDIT_SIZE {
-        return Err(DecodeError::LengthExceedsLimit {
-            field: "uncompressed_size",
-            len: declared_size,
-            max: MAX_EDIT_SIZE,
-        });
+/// Writes the magic, version, and header fields of an uncompressed edit.
+///
+/// Edits without provenance, value clocks, or layout flags are written as
+/// format version 1, so they stay readable by decoders that predate
+/// version 2 (see [`FormatVersion::required_for`]). Empty provenance counts
+/// as none. `layout` holds the flags from [`layout_flags`]; the flag for
+/// remove lists is added here.
+pub(crate) fn write_edit_header(
+    writer: &mut Writer,
+    edit: &Edit<'_>,
+    authors: &[Id],
+    provenance: Option<&Provenance<'_>>,
+    mut layout: u8,
+) {
+    if edit.has_remove_values() {
+        layout |= HEADER_HAS_REMOVE_VALUES;
     }
+    let provenance = provenance.filter(|p| !p.is_empty());
+    let version = match provenance {
+        _ if edit.has_value_clocks() => FormatVersion::V3,
+        Some(_) => FormatVersion::V2,
+        None if layout != 0 => FormatVersion::V2,
+        None => FormatVersion::V1,
+    };
+    writer.write_bytes(MAGIC_UNCOMPRESSED);
+    writer.write_byte(version.as_u8());
+    writer.write_id(&edit.id);
+    writer.write_string(&edit.name);
+    writer.write_id_vec(authors);
+    writer.write_signed_varint(edit.created_at);
 
-    let compressed_data = reader.remaining();
-
-    let mut decoder = zstd::Decoder::new(compressed_data)
-        .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?;
+    if !version.has_header_flags() {
+        return;
+    }
+    let Some(provenance) = provenance else {
+        writer.write_byte(layout);
+        return;
+    };
+    let mut flags = layout;
+    if provenance.source.is_some() {
+        flags |= HEADER_HAS_SOURCE;
+    }
+    if !provenance.parents.is_empty() {
+        flags |= HEADER_HAS_PARENTS;
+    }
+    if !provenance.metadata.is_empty() {
+        flags |= HEADER_HAS_METADATA;
+    }
+    writer.write_byte(flags);
+    if let Some(source) = &provenance.source {
+        writer.write_id(source);
+    }
+    if !provenance.parents.is_empty() {
+        writer.write_id_vec(&provenance.parents);
+    }
+    if !provenance.metadata.is_empty() {
+        writer.write_varint(provenance.metadata.len() as u64);
+        for (key, value) in &provenance.metadata {
+            writer.write_string(key);
+            writer.write_string(value);
+        }
+    }
+}
 
-    let mut decompressed = Vec::with_capacity(declared_size);
-    decoder
-        .read_to_end(&mut decompressed)
-        .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?;
+/// Sets the checksum flag on an encoded edit and appends the trailer.
+fn append_checksum(mut data: Vec<u8>) -> Vec<u8> {
+    set_checksum_flag(&mut data);
+    let checksum = crc32c(&data);
+    data.extend_from_slice(&checksum.to_le_bytes());
+    data
+}
 
-    if decompressed.len() != declared_size {
-        return Err(DecodeError::UncompressedSizeMismatch {
-            declared: declared_size,
-            actual: decompressed.len(),
-        });
+/// Sets the checksum flag in the header at the start of `data`, moving
+/// version 1 output to version 2 for the header flags.
+fn set_checksum_flag(data: &mut Vec<u8>) {
+    let flags_at = {
+        let mut reader = Reader::new(&data[5..]);
+        read_header_v1(&mut reader).expect("encoder output has a valid header");
+        5 + reader.position()
+    };
+    if decoded_version(data).has_header_flags() {
+        data[flags_at] |= HEADER_HAS_CHECKSUM;
+    } else {
+        data[4] = FormatVersion::V2.as_u8();
+        data.insert(flags_at, HEADER_HAS_CHECKSUM);
     }
+}
 
-    Ok(decompressed)
+/// Sorts authors for canonical mode, rejecting duplicates.
+fn canonical_authors(authors: &[Id]) -> Result<Vec<Id>, EncodeError> {
+    let mut sorted = authors.to_vec();
+    sorted.sort();
+    if let Some(pair) = sorted.windows(2).find(|w| w[0] == w[1]) {
+        return Err(EncodeError::DuplicateAuthor { id: pair[0] });
+    }
+    Ok(sorted)
 }
 
-// =============================================================================
-// ENCODING
-// =============================================================================
+/// Sorts provenance for canonical mode: parents by ID bytes and metadata by
+/// key, rejecting duplicates of either.
+fn canonical_provenance<'a>(
+    provenance: &Provenance<'a>,
+) -> Result<Provenance<'a>, EncodeError> {
+    let mut sorted = provenance.clone();
+    sorted.parents.sort();
+    if let Some(pair) = sorted.parents.windows(2).find(|w| w[0] == w[1]) {
+        return Err(EncodeError::DuplicateParent { id: pair[0] });
+    }
+    sorted.metadata.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(pair) = sorted.metadata.windows(2).find(|w| w[0].0 == w[1].0) {
+        return Err(EncodeError::DuplicateMetadataKey { key: pair[0].0.to_string() });
+    }
+    Ok(sorted)
+}
 
 /// Options for encoding edits.
 #[derive(Debug, Clone, Copy, Default)]
@@ -422,6 +1018,45 @@ pub struct EncodeOptions {
     /// Note: Canonical mode requires two passes over the ops and is slower
     /// than non-canonical encoding.
     pub canonical: bool,
+
+    /// Append a CRC32C trailer covering the whole edit.
+    ///
+    /// Decoders verify it before reading any ops, so corruption in transit
+    /// or storage fails with [`DecodeError::ChecksumMismatch`] instead of
+    /// yielding a partial or wrong edit. Adds 4 bytes (5 for edits that
+    /// would otherwise be written as format version 1).
+    pub checksum: bool,
+
+    /// Store TEXT values that repeat within the edit once, in a string
+    /// table after the dictionaries, and write references to them.
+    ///
+    /// Decoding is transparent. The table moves edits that would be format
+    /// version 1 to version 2. Ignored in canonical mode, whose output must
+    /// not depend on encoder heuristics.
+    pub intern_strings: bool,
+
+    /// Write runs of CreateEntity ops that set the same properties as
+    /// BatchCreateEntities records, which store the property layout once
+    /// and the values column by column.
+    ///
+    /// Decoding is transparent: a record decodes to the CreateEntity ops it
+    /// was built from. Ignored in canonical mode.
+    pub columnar: bool,
+
+    /// Keep dictionary entries in first-seen order.
+    ///
+    /// By default entries are ordered by how often the ops reference them,
+    /// so the most used properties and objects get 1-byte indices. That
+    /// takes an extra pass over the ops; first-seen order encodes in a
+    /// single pass, at some cost in size for edits with more than 127
+    /// entries in a dictionary. Ignored in canonical mode, which sorts
+    /// entries by ID.
+    pub insertion_order: bool,
+
+    /// Which value checks are hard errors (see [`ValidationProfile`]).
+    /// Ignored in canonical mode, which always encodes under
+    /// [`ValidationProfile::Strict`].
+    pub profile: ValidationProfile,
 }
 
 impl EncodeOptions {
@@ -432,53 +1067,193 @@ impl EncodeOptions {
 
     /// Creates canonical encoding options.
     pub fn canonical() -> Self {
-        Self { canonical: true }
+        Self { canonical: true, ..Self::default() }
+    }
+
+    /// Enables the checksum trailer.
+    pub fn with_checksum(mut self) -> Self {
+        self.checksum = true;
+        self
+    }
+
+    /// Enables the string table for repeated TEXT values.
+    pub fn with_interned_strings(mut self) -> Self {
+        self.intern_strings = true;
+        self
+    }
+
+    /// Enables columnar records for runs of CreateEntity ops.
+    pub fn with_columnar(mut self) -> Self {
+        self.columnar = true;
+        self
+    }
+
+    /// Keeps dictionary entries in first-seen order.
+    pub fn with_insertion_order(mut self) -> Self {
+        self.insertion_order = true;
+        self
+    }
+
+    /// Sets which value checks are hard errors.
+    pub fn with_profile(mut self, profile: ValidationProfile) -> Self {
+        self.profile = profile;
+        self
     }
 }
 
 /// Encodes an Edit to binary format (uncompressed).
 ///
-/// Uses single-pass encoding: ops are encoded to a buffer while building
-/// dictionaries, then the final output is assembled.
+/// Collects the dictionaries in a first pass and orders their entries by
+/// reference count, so the most used get the shortest indices, then encodes
+/// the ops. See [`EncodeOptions::insertion_order`] for single-pass encoding.
 pub fn encode_edit(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
     encode_edit_with_options(edit, EncodeOptions::default())
 }
 
 /// Encodes an Edit to binary format with the given options.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "encode_edit",
+        level = "debug",
+        skip_all,
+        fields(ops = edit.ops.len(), canonical = options.canonical, bytes = tracing::field::Empty)
+    )
+)]
 pub fn encode_edit_with_options(edit: &Edit, options: EncodeOptions) -> Result<Vec<u8>, EncodeError> {
-    if options.canonical {
-        encode_edit_canonical(edit)
+    let data = if options.canonical {
+        encode_edit_canonical(edit)?
     } else {
-        encode_edit_fast(edit)
+        encode_edit_fast(edit, options)?
+    };
+    let data = if options.checksum { append_checksum(data) } else { data };
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("bytes", data.len());
+    Ok(data)
+}
+
+/// Minimum op count at which non-canonical encoding is split across threads
+/// (requires the `parallel` feature).
+#[cfg(feature = "parallel")]
+pub const PARALLEL_MIN_OPS: usize = 4096;
+
+/// Returns the TEXT values worth storing in a string table: those of at
+/// least 2 bytes set more than once, most frequent first so they get the
+/// shortest references.
+pub(crate) fn repeated_strings(edit: &Edit) -> Vec<String> {
+    let mut counts: FxHashMap<&str, usize> = FxHashMap::default();
+    let mut order = Vec::new();
+    for op in &edit.ops {
+        let values = match op {
+            Op::CreateEntity(ce) => &ce.values,
+            Op::UpdateEntity(ue) => &ue.set_properties,
+            _ => continue,
+        };
+        for pv in values {
+            if let crate::model::Value::Text { value, .. } = &pv.value {
+                if value.len() < 2 {
+                    continue;
+                }
+                let count = counts.entry(value).or_insert(0);
+                if *count == 0 {
+                    order.push(&**value);
+                }
+                *count += 1;
+            }
+        }
+    }
+    order.retain(|value| counts[value] > 1);
+    // Stable, so ties keep first-seen order
+    order.sort_by_key(|value| core::cmp::Reverse(counts[value]));
+    order.truncate(MAX_DICT_SIZE);
+    order.into_iter().map(String::from).collect()
+}
+
+/// Creates the dictionary builder for a non-canonical encoding, set up for
+/// the edit's value clocks and the string table `options` ask for.
+fn edit_dictionary_builder(edit: &Edit, options: EncodeOptions) -> DictionaryBuilder {
+    let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+    dict_builder.set_value_clocks(edit.has_value_clocks());
+    dict_builder.set_profile(options.profile);
+    if options.intern_strings {
+        dict_builder.set_strings(repeated_strings(edit));
+    }
+    dict_builder
+}
+
+/// Splits ops into the records they are written as: single ops, or with
+/// `columnar`, runs of CreateEntity ops sharing a property layout.
+fn op_records<'e, 'a>(
+    mut ops: &'e [Op<'a>],
+    columnar: bool,
+) -> impl Iterator<Item = &'e [Op<'a>]> {
+    core::iter::from_fn(move || {
+        if ops.is_empty() {
+            return None;
+        }
+        let len = if columnar { create_entity_run(ops).max(1) } else { 1 };
+        let (record, rest) = ops.split_at(len);
+        ops = rest;
+        Some(record)
+    })
+}
+
+/// Returns the header flags announcing the string table and, with
+/// `columnar`, BatchCreateEntities records, if the edit has them.
+fn layout_flags(dict_builder: &DictionaryBuilder, ops: &[Op<'_>], columnar: bool) -> u8 {
+    let mut flags = 0;
+    if dict_builder.has_strings() {
+        flags |= HEADER_HAS_STRINGS;
+    }
+    if op_records(ops, columnar).any(|record| record.len() > 1) {
+        flags |= HEADER_HAS_BATCH_RECORDS;
     }
+    flags
 }
 
-/// Fast single-pass encoding (non-canonical).
-fn encode_edit_fast(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
+/// Encodes one record from [`op_records`].
+fn encode_record(
+    writer: &mut Writer,
+    record: &[Op<'_>],
+    dict_builder: &mut DictionaryBuilder,
+    property_types: &FxHashMap<Id, DataType>,
+) -> Result<(), EncodeError> {
+    match record {
+        [op] => encode_op(writer, op, dict_builder, property_types),
+        _ => encode_batch_create_entities(writer, record, dict_builder, property_types),
+    }
+}
+
+/// Fast non-canonical encoding: single-pass with
+/// [`EncodeOptions::insertion_order`], two-pass otherwise.
+fn encode_edit_fast(edit: &Edit, options: EncodeOptions) -> Result<Vec<u8>, EncodeError> {
+    #[cfg(feature = "parallel")]
+    if edit.ops.len() >= PARALLEL_MIN_OPS {
+        let chunk_size = edit.ops.len().div_ceil(rayon::current_num_threads());
+        return encode_edit_chunked(edit, chunk_size, options);
+    }
+    if !options.insertion_order {
+        return encode_edit_chunked(edit, edit.ops.len(), options);
+    }
+
     // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
+    let property_types = crate::hash::FxHashMap::default();
 
     // Single pass: encode ops while building dictionaries
-    let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+    let mut dict_builder = edit_dictionary_builder(edit, options);
     let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
 
-    for op in &edit.ops {
-        encode_op(&mut ops_writer, op, &mut dict_builder, &property_types)?;
+    for record in op_records(&edit.ops, options.columnar) {
+        encode_record(&mut ops_writer, record, &mut dict_builder, &property_types)?;
     }
 
     // Now assemble final output: header + dictionaries + ops
     let ops_bytes = ops_writer.into_bytes();
     let mut writer = Writer::with_capacity(256 + ops_bytes.len());
 
-    // Magic and version
-    writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
-
-    // Header
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&edit.authors);
-    writer.write_signed_varint(edit.created_at);
+    // Magic, version, and header
+    let layout = layout_flags(&dict_builder, &edit.ops, options.columnar);
+    write_edit_header(&mut writer, edit, &edit.authors, edit.provenance.as_ref(), layout);
 
     // Dictionaries
     dict_builder.write_dictionaries(&mut writer);
@@ -490,7 +1265,86 @@ fn encode_edit_fast(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
     Ok(writer.into_bytes())
 }
 
-/// Canonical two-pass encoding with sorted dictionaries, authors, values, and unsets.
+/// Encodes an Edit using all rayon worker threads (non-canonical).
+///
+/// A sequential pass collects the dictionaries in the same order as
+/// [`encode_edit`]; op payloads are then encoded in parallel chunks and
+/// concatenated. The output is byte-identical to [`encode_edit`].
+///
+/// [`encode_edit`] already takes this path for edits with at least
+/// [`PARALLEL_MIN_OPS`] ops when the `parallel` feature is enabled.
+#[cfg(feature = "parallel")]
+pub fn encode_edit_parallel(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
+    let chunk_size = edit.ops.len().div_ceil(rayon::current_num_threads());
+    encode_edit_chunked(edit, chunk_size, EncodeOptions::new())
+}
+
+/// Two-pass encoding: collect dictionaries, then encode ops in independent chunks.
+fn encode_edit_chunked(
+    edit: &Edit,
+    chunk_size: usize,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    // Property types are determined from values themselves (per-edit typing)
+    let property_types = crate::hash::FxHashMap::default();
+
+    // Pass 1: collect dictionaries in encode order so indices match encode_edit
+    let mut dict_builder = edit_dictionary_builder(edit, options);
+    for op in &edit.ops {
+        collect_op_ids(op, &mut dict_builder);
+    }
+    if !options.insertion_order {
+        dict_builder = dict_builder.into_frequency_order();
+    }
+
+    // Pass 2: each chunk of records encodes against its own copy of the
+    // complete dictionary, so no new entries are added and indices are
+    // stable. Chunks split between records, never inside a columnar run.
+    let records: Vec<_> = op_records(&edit.ops, options.columnar).collect();
+    let encode_chunk = |chunk: &[&[Op<'_>]]| -> Result<Vec<u8>, EncodeError> {
+        let mut builder = dict_builder.clone();
+        let mut writer = Writer::with_capacity(chunk.len() * 50);
+        for record in chunk {
+            encode_record(&mut writer, record, &mut builder, &property_types)?;
+        }
+        Ok(writer.into_bytes())
+    };
+    let chunk_size = chunk_size.max(1);
+    #[cfg(feature = "parallel")]
+    let chunks: Vec<_> = {
+        use rayon::prelude::*;
+        records.par_chunks(chunk_size).map(encode_chunk).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let chunks: Vec<_> = records.chunks(chunk_size).map(encode_chunk).collect();
+
+    // Report the error from the earliest op, as sequential encoding would
+    let chunks = chunks.into_iter().collect::<Result<Vec<_>, _>>()?;
+    let ops_len: usize = chunks.iter().map(Vec::len).sum();
+    let mut writer = Writer::with_capacity(256 + ops_len);
+
+    // Magic, version, and header
+    let layout = layout_flags(&dict_builder, &edit.ops, options.columnar);
+    write_edit_header(&mut writer, edit, &edit.authors, edit.provenance.as_ref(), layout);
+
+    // Dictionaries
+    dict_builder.write_dictionaries(&mut writer);
+
+    // Operations (already encoded)
+    writer.write_varint(edit.ops.len() as u64);
+    for chunk in &chunks {
+        writer.write_bytes(chunk);
+    }
+
+    Ok(writer.into_bytes())
+}
+
+/// Encodes an Edit in canonical mode (spec Section 4.4).
+///
+/// Two edits that differ only in dictionary order, author order, or the order
+/// of values and unsets within an op encode to identical bytes, so the output
+/// is suitable for signatures and content addressing. Op order is preserved:
+/// ops are applied in sequence, so reordering them changes the edit.
 ///
 /// Pass 1: Collect all dictionary entries
 /// Pass 2: Sort dictionaries, encode with stable indices and sorted values
@@ -500,12 +1354,17 @@ fn encode_edit_fast(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
 /// - Authors sorted by ID bytes, no duplicates
 /// - Values sorted by (propertyRef, languageRef), no duplicate (property, language)
 /// - Unset values sorted by (propertyRef, language), no duplicates
-fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
+/// - Provenance parents sorted by ID bytes and metadata sorted by key, no
+///   duplicates
+///
+/// Equivalent to `encode_edit_with_options(edit, EncodeOptions::canonical())`.
+pub fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
     // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
+    let property_types = crate::hash::FxHashMap::default();
 
     // Pass 1: Collect all dictionary entries by doing a dry run
     let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+    dict_builder.set_value_clocks(edit.has_value_clocks());
     let mut temp_writer = Writer::with_capacity(edit.ops.len() * 50);
     for op in &edit.ops {
         encode_op(&mut temp_writer, op, &mut dict_builder, &property_types)?;
@@ -515,14 +1374,9 @@ fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
     let sorted_builder = dict_builder.into_sorted();
 
     // Sort authors by ID bytes and check for duplicates
-    let mut sorted_authors = edit.authors.clone();
-    sorted_authors.sort();
-    // Check for duplicate authors
-    for i in 1..sorted_authors.len() {
-        if sorted_authors[i] == sorted_authors[i - 1] {
-            return Err(EncodeError::DuplicateAuthor { id: sorted_authors[i] });
-        }
-    }
+    let sorted_authors = canonical_authors(&edit.authors)?;
+
+    let sorted_provenance = edit.provenance.as_ref().map(canonical_provenance).transpose()?;
 
     // Pass 2: Encode ops with sorted dictionary indices and sorted values
     let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
@@ -535,15 +1389,8 @@ fn encode_edit_canonical(edit: &Edit) -> Result<Vec<u8>, EncodeError> {
     let ops_bytes = ops_writer.into_bytes();
     let mut writer = Writer::with_capacity(256 + ops_bytes.len());
 
-    // Magic and version
-    writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
-
-    // Header
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&sorted_authors);
-    writer.write_signed_varint(edit.created_at);
+    // Magic, version, and header
+    write_edit_header(&mut writer, edit, &sorted_authors, sorted_provenance.as_ref(), 0);
 
     // Dictionaries (sorted)
     sorted_builder.write_dictionaries(&mut writer);
@@ -580,9 +1427,14 @@ fn encode_op_canonical(
             Ok(())
         }
         Op::UpdateEntity(ue) => {
-            // Sort set_properties and unset_values, check for duplicates
+            // Sort set_properties, unset_values, and remove_values, check for duplicates
             let sorted_set = sort_and_check_values(&ue.set_properties, dict_builder)?;
             let sorted_unset = sort_and_check_unsets(&ue.unset_values, dict_builder)?;
+            let mut sorted_remove = ue.remove_values.clone();
+            sorted_remove.sort_unstable();
+            if let Some(pair) = sorted_remove.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(EncodeError::DuplicateRemoveValue { value_id: pair[0] });
+            }
 
             writer.write_byte(2); // OP_UPDATE_ENTITY
             let id_index = dict_builder.add_object(ue.id);
@@ -595,6 +1447,9 @@ fn encode_op_canonical(
             if !sorted_unset.is_empty() {
                 flags |= 0x02; // FLAG_HAS_UNSET_VALUES
             }
+            if !sorted_remove.is_empty() {
+                flags |= 0x04; // FLAG_HAS_REMOVE_VALUES
+            }
             writer.write_byte(flags);
 
             if !sorted_set.is_empty() {
@@ -611,7 +1466,7 @@ fn encode_op_canonical(
                 use crate::model::UnsetLanguage;
                 writer.write_varint(sorted_unset.len() as u64);
                 for unset in &sorted_unset {
-                    let prop_idx = dict_builder.add_property(unset.property, DataType::Bool);
+                    let prop_idx = dict_builder.add_property_placeholder(unset.property);
                     writer.write_varint(prop_idx as u64);
                     let lang_value: u32 = match &unset.language {
                         UnsetLanguage::All => 0xFFFFFFFF,
@@ -623,6 +1478,10 @@ fn encode_op_canonical(
                     writer.write_varint(lang_value as u64);
                 }
             }
+
+            if !sorted_remove.is_empty() {
+                writer.write_id_vec(&sorted_remove);
+            }
             Ok(())
         }
         // Other ops don't have values to sort, delegate to regular encode
@@ -656,7 +1515,7 @@ fn sort_and_check_values<'a>(
         .collect();
 
     // Sort by (property_index, language_index)
-    indexed.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    indexed.sort_by_key(|a| (a.0, a.1));
 
     // Check for duplicates (adjacent entries with same property_index and language_index)
     for i in 1..indexed.len() {
@@ -706,7 +1565,7 @@ fn sort_and_check_unsets(
         .collect();
 
     // Sort by (property_index, language_key)
-    indexed.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    indexed.sort_by_key(|a| (a.0, a.1));
 
     // Check for duplicates
     for i in 1..indexed.len() {
@@ -735,12 +1594,15 @@ fn encode_property_value_canonical(
     data_type: DataType,
 ) -> Result<(), EncodeError> {
     let prop_index = dict_builder.add_property(pv.property, data_type);
+    crate::codec::value::check_property_type(pv, dict_builder, prop_index)?;
     writer.write_varint(prop_index as u64);
+    crate::codec::value::write_value_clock(writer, pv, dict_builder)?;
     crate::codec::value::encode_value(writer, &pv.value, dict_builder)?;
     Ok(())
 }
 
 /// Encodes an Edit with profiling output (two-pass for comparison).
+#[cfg(feature = "std")]
 pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, EncodeError> {
     if !profile {
         return encode_edit(edit);
@@ -751,11 +1613,12 @@ pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, Encod
     let t0 = Instant::now();
 
     // Property types are determined from values themselves (per-edit typing)
-    let property_types = rustc_hash::FxHashMap::default();
+    let property_types = crate::hash::FxHashMap::default();
     let t1 = Instant::now();
 
     // Single pass: encode ops while building dictionaries
     let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+    dict_builder.set_value_clocks(edit.has_value_clocks());
     let mut ops_writer = Writer::with_capacity(edit.ops.len() * 50);
 
     for op in &edit.ops {
@@ -767,12 +1630,7 @@ pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, Encod
     let ops_bytes = ops_writer.into_bytes();
     let mut writer = Writer::with_capacity(256 + ops_bytes.len());
 
-    writer.write_bytes(MAGIC_UNCOMPRESSED);
-    writer.write_byte(FORMAT_VERSION);
-    writer.write_id(&edit.id);
-    writer.write_string(&edit.name);
-    writer.write_id_vec(&edit.authors);
-    writer.write_signed_varint(edit.created_at);
+    write_edit_header(&mut writer, edit, &edit.authors, edit.provenance.as_ref(), 0);
     dict_builder.write_dictionaries(&mut writer);
     writer.write_varint(edit.ops.len() as u64);
     writer.write_bytes(&ops_bytes);
@@ -790,7 +1648,130 @@ pub fn encode_edit_profiled(edit: &Edit, profile: bool) -> Result<Vec<u8>, Encod
     Ok(result)
 }
 
+/// Size of the pieces [`EditStream`] hands encoded ops on in.
+#[cfg(feature = "zstd")]
+pub(crate) const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// An edit prepared for encoding in pieces, so its encoding is never held
+/// in memory at once.
+///
+/// The header and dictionaries are encoded up front. The ops are encoded
+/// on demand, once in a dry run that measures the total length (which
+/// framings such as `GRC2Z` write before the edit) and once for output.
+/// The bytes are identical to [`encode_edit_with_options`].
+#[cfg(feature = "zstd")]
+pub(crate) struct EditStream<'e, 'a> {
+    edit: &'e Edit<'a>,
+    options: EncodeOptions,
+    /// Header, dictionaries, and op count.
+    prefix: Vec<u8>,
+    dict_builder: DictionaryBuilder,
+    len: usize,
+}
+
+#[cfg(feature = "zstd")]
+impl<'e, 'a> EditStream<'e, 'a> {
+    /// Collects the dictionaries, encodes the prefix, and measures the ops.
+    pub(crate) fn new(edit: &'e Edit<'a>, options: EncodeOptions) -> Result<Self, EncodeError> {
+        let mut writer = Writer::new();
+        let dict_builder = if options.canonical {
+            // Same dry run as encode_edit_canonical
+            let property_types = FxHashMap::default();
+            let mut dict_builder = DictionaryBuilder::with_capacity(edit.ops.len());
+            dict_builder.set_value_clocks(edit.has_value_clocks());
+            let mut scratch = Writer::new();
+            for op in &edit.ops {
+                encode_op(&mut scratch, op, &mut dict_builder, &property_types)?;
+                scratch.clear();
+            }
+            let authors = canonical_authors(&edit.authors)?;
+            let provenance = edit.provenance.as_ref().map(canonical_provenance).transpose()?;
+            write_edit_header(&mut writer, edit, &authors, provenance.as_ref(), 0);
+            dict_builder.into_sorted()
+        } else {
+            let mut dict_builder = edit_dictionary_builder(edit, options);
+            for op in &edit.ops {
+                collect_op_ids(op, &mut dict_builder);
+            }
+            if !options.insertion_order {
+                dict_builder = dict_builder.into_frequency_order();
+            }
+            let layout = layout_flags(&dict_builder, &edit.ops, options.columnar);
+            write_edit_header(&mut writer, edit, &edit.authors, edit.provenance.as_ref(), layout);
+            dict_builder
+        };
+        dict_builder.write_dictionaries(&mut writer);
+        writer.write_varint(edit.ops.len() as u64);
+        let mut prefix = writer.into_bytes();
+        if options.checksum {
+            set_checksum_flag(&mut prefix);
+        }
+
+        let mut stream = Self { edit, options, prefix, dict_builder, len: 0 };
+        let mut ops_len = 0;
+        stream.encode_ops(|chunk| {
+            ops_len += chunk.len();
+            Ok(())
+        })?;
+        stream.len = stream.prefix.len() + ops_len + if options.checksum { 4 } else { 0 };
+        Ok(stream)
+    }
+
+    /// Returns the length of the encoded edit.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Encodes the edit, handing the bytes to `emit` in order.
+    pub(crate) fn write(
+        &self,
+        mut emit: impl FnMut(&[u8]) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError> {
+        emit(&self.prefix)?;
+        let mut crc = crc32c(&self.prefix);
+        self.encode_ops(|chunk| {
+            crc = crate::checksum::crc32c_append(crc, chunk);
+            emit(chunk)
+        })?;
+        if self.options.checksum {
+            emit(&crc.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Encodes the ops in pieces of about [`STREAM_CHUNK_SIZE`] bytes.
+    fn encode_ops(
+        &self,
+        mut emit: impl FnMut(&[u8]) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError> {
+        let property_types = FxHashMap::default();
+        let mut dict_builder = self.dict_builder.clone();
+        let mut writer = Writer::with_capacity(STREAM_CHUNK_SIZE + 1024);
+        let columnar = self.options.columnar && !self.options.canonical;
+        for record in op_records(&self.edit.ops, columnar) {
+            if self.options.canonical {
+                encode_op_canonical(&mut writer, &record[0], &mut dict_builder, &property_types)?;
+            } else {
+                encode_record(&mut writer, record, &mut dict_builder, &property_types)?;
+            }
+            if writer.len() >= STREAM_CHUNK_SIZE {
+                emit(writer.as_bytes())?;
+                writer.clear();
+            }
+        }
+        if !writer.is_empty() {
+            emit(writer.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
 /// Encodes an Edit to binary format with zstd compression.
+///
+/// Fails with [`EncodeError::CompressionUnavailable`] without the `zstd`
+/// feature; see [`encode_edit_with_compression`] for other backends, and
+/// [`encode_edit_compressed_to_writer`](crate::compression::encode_edit_compressed_to_writer)
+/// to stream large edits.
 pub fn encode_edit_compressed(edit: &Edit, level: i32) -> Result<Vec<u8>, EncodeError> {
     encode_edit_compressed_with_options(edit, level, EncodeOptions::default())
 }
@@ -801,23 +1782,13 @@ pub fn encode_edit_compressed_with_options(
     level: i32,
     options: EncodeOptions,
 ) -> Result<Vec<u8>, EncodeError> {
-    let uncompressed = encode_edit_with_options(edit, options)?;
-
-    let compressed = zstd::encode_all(uncompressed.as_slice(), level)
-        .map_err(|e| EncodeError::CompressionFailed(e.to_string()))?;
-
-    let mut writer = Writer::with_capacity(5 + 10 + compressed.len());
-    writer.write_bytes(MAGIC_COMPRESSED);
-    writer.write_varint(uncompressed.len() as u64);
-    writer.write_bytes(&compressed);
-
-    Ok(writer.into_bytes())
+    encode_edit_with_compression(edit, CompressionOptions::zstd(level).with_encode_options(options))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{CreateEntity, PropertyValue, Value};
+    use crate::model::{CreateEntity, OpKind, PropertyValue, Value};
 
     fn make_test_edit() -> Edit<'static> {
         Edit {
@@ -825,6 +1796,7 @@ mod tests {
             name: Cow::Owned("Test Edit".to_string()),
             authors: vec![[2u8; 16]],
             created_at: 1234567890,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [3u8; 16],
@@ -834,6 +1806,7 @@ mod tests {
                             value: Cow::Owned("Hello".to_string()),
                             language: None,
                         },
+                        clock: None,
                     }],
                 }),
             ],
@@ -855,6 +1828,30 @@ mod tests {
     }
 
     #[test]
+    fn test_dictionaries_ordered_by_frequency() {
+        // 200 objects referenced once, then one referenced 50 times
+        let mut builder = crate::model::EditBuilder::new([1u8; 16]);
+        for n in 0..200u16 {
+            let mut id = [0u8; 16];
+            id[..2].copy_from_slice(&n.to_be_bytes());
+            builder = builder.delete_entity(id);
+        }
+        for _ in 0..50 {
+            builder = builder.delete_entity([0xFF; 16]);
+        }
+        let edit = builder.build();
+
+        let frequency = encode_edit(&edit).unwrap();
+        let insertion =
+            encode_edit_with_options(&edit, EncodeOptions::new().with_insertion_order()).unwrap();
+        // The hot object's 50 indices shrink to one byte; one singleton grows to two
+        assert_eq!(insertion.len() - frequency.len(), 49);
+        assert_eq!(decode_edit(&frequency).unwrap().ops, edit.ops);
+        assert_eq!(decode_edit(&insertion).unwrap().ops, edit.ops);
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
     fn test_edit_compressed_roundtrip() {
         let edit = make_test_edit();
 
@@ -868,7 +1865,88 @@ mod tests {
         assert_eq!(edit.ops.len(), decoded.ops.len());
     }
 
+    #[cfg(feature = "bumpalo")]
     #[test]
+    fn test_decode_edit_in_arena() {
+        let edit = make_test_edit();
+        let arena = bumpalo::Bump::new();
+
+        let mut input = encode_edit(&edit).unwrap();
+        let decoded = decode_edit_in(&arena, &input).unwrap();
+        input.clear();
+        assert_eq!(decoded, edit);
+        assert!(matches!(decoded.name, Cow::Borrowed(_)));
+
+        let compressed = encode_edit_compressed(&edit, 3).unwrap();
+        let decoded = decode_edit_in(&arena, &compressed).unwrap();
+        assert_eq!(decoded, edit);
+        assert!(matches!(decoded.name, Cow::Borrowed(_)));
+
+        // Passthrough framing whose declared size overstates the payload
+        let raw = encode_edit(&edit).unwrap();
+        let mut framed = b"GRC2N".to_vec();
+        let mut writer = Writer::new();
+        writer.write_varint(raw.len() as u64 + 1);
+        framed.extend_from_slice(writer.as_bytes());
+        framed.extend_from_slice(&raw);
+        assert!(matches!(
+            decode_edit_in(&arena, &framed),
+            Err(DecodeError::UncompressedSizeMismatch { actual, .. }) if actual == raw.len()
+        ));
+    }
+
+    #[test]
+    fn test_chunked_encoding_is_byte_identical() {
+        use crate::model::{DeleteEntity, UnsetValue, UpdateEntity};
+
+        let mut edit = make_test_edit();
+        for i in 0..20u8 {
+            edit.ops.push(Op::CreateEntity(CreateEntity {
+                id: [100 + i; 16],
+                values: vec![
+                    PropertyValue {
+                        property: [10 + i % 3; 16],
+                        value: Value::Text {
+                            value: Cow::Owned(format!("Entity {}", i)),
+                            language: Some([50 + i % 4; 16]),
+                        },
+                        clock: None,
+                    },
+                    PropertyValue {
+                        property: [20u8; 16],
+                        value: Value::Int64 { value: i as i64, unit: Some([60 + i % 2; 16]) },
+                        clock: None,
+                    },
+                ],
+            }));
+            edit.ops.push(Op::CreateRelation(crate::model::CreateRelation {
+                id: [200 - i; 16],
+                relation_type: [30 + i % 2; 16],
+                from: [100 + i; 16],
+                from_is_value_ref: false,
+                from_space: None,
+                from_version: None,
+                to: [100 + (i + 1) % 20; 16],
+                to_is_value_ref: false,
+                to_space: None,
+                to_version: None,
+                entity: None,
+                position: None,
+            }));
+        }
+        let mut update = UpdateEntity::new([3u8; 16]);
+        update.unset_values.push(UnsetValue::language([40u8; 16], [70u8; 16]));
+        edit.ops.push(Op::UpdateEntity(update));
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [250u8; 16] }));
+
+        let expected = encode_edit(&edit).unwrap();
+        for chunk_size in [1, 2, 7, edit.ops.len()] {
+            assert_eq!(encode_edit_chunked(&edit, chunk_size, EncodeOptions::new()).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
     fn test_compression_magic() {
         let edit = make_test_edit();
 
@@ -886,6 +1964,76 @@ mod tests {
         assert!(matches!(result, Err(DecodeError::InvalidMagic { .. })));
     }
 
+    #[test]
+    fn test_error_location() {
+        let edit = crate::model::EditBuilder::new([0u8; 16])
+            .create_entity([1u8; 16], |e| e.text([9u8; 16], "first", None))
+            .create_entity([2u8; 16], |e| e.text([9u8; 16], "second", None))
+            .create_entity([3u8; 16], |e| e.text([9u8; 16], "third", None))
+            .build();
+        let encoded = encode_edit(&edit).unwrap();
+
+        // Truncated inside the last op
+        let truncated = &encoded[..encoded.len() - 2];
+        let err = decode_edit(truncated).unwrap_err();
+        assert!(matches!(err.kind(), DecodeError::UnexpectedEof { .. }));
+        assert_eq!(err.op_index(), Some(2));
+        assert!(err.offset().unwrap() <= truncated.len());
+        let chain = err.context_chain();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0], format!("op 2, byte {}", err.offset().unwrap()));
+        assert!(err.to_string().starts_with(&chain[1]));
+        assert_eq!(err.code(), crate::error::ErrorCode::MalformedEncoding);
+
+        // Truncated inside the dictionaries
+        let err = decode_edit(&encoded[..30]).unwrap_err();
+        assert_eq!(err.op_index(), None);
+        assert!(err.offset().unwrap() <= 30);
+        assert!(err.context_chain()[0].starts_with("dictionaries, byte "));
+    }
+
+    #[test]
+    fn test_decode_lossy() {
+        let mut builder = crate::model::EditBuilder::new([0u8; 16]).name("lossy");
+        for n in 1..=5u8 {
+            let text = format!("text {}", n);
+            builder = builder.create_entity([n; 16], |e| e.text([9u8; 16], text, None));
+        }
+        let edit = builder.build();
+        let encoded =
+            encode_edit_with_options(&edit, EncodeOptions::new().with_checksum()).unwrap();
+        assert_eq!(decode_edit_lossy(&encoded), (edit.clone(), vec![]));
+
+        // A changed byte in the last op's text: reported, but every op survives
+        let mut corrupt = encoded.clone();
+        let at = corrupt.len() - 7;
+        corrupt[at] = b'_';
+        let (salvaged, errors) = decode_edit_lossy(&corrupt);
+        assert_eq!(salvaged.ops[..4], edit.ops[..4]);
+        assert!(matches!(&salvaged.ops[4], Op::CreateEntity(ce) if matches!(
+            &ce.values[0].value,
+            crate::model::Value::Text { value, .. } if value == "text_5"
+        )));
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].kind(), DecodeError::ChecksumMismatch { .. }));
+
+        // Truncated in the fourth op: the first three survive
+        let plain = encode_edit(&edit).unwrap();
+        let mut shorter = edit.clone();
+        shorter.ops.pop();
+        let op_len = plain.len() - encode_edit(&shorter).unwrap().len();
+        let truncated = &plain[..plain.len() - op_len - op_len / 2];
+        let (salvaged, errors) = decode_edit_lossy(truncated);
+        assert_eq!((salvaged.name.as_ref(), &salvaged.ops[..]), ("lossy", &edit.ops[..3]));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].op_index(), Some(3));
+        assert_eq!(errors[0], decode_edit(truncated).unwrap_err());
+
+        let (salvaged, errors) = decode_edit_lossy(b"XXXX\x01");
+        assert_eq!((salvaged.id, salvaged.ops.len()), (NIL_ID, 0));
+        assert!(matches!(errors[..], [DecodeError::InvalidMagic { .. }]));
+    }
+
     #[test]
     fn test_unsupported_version() {
         let mut data = Vec::new();
@@ -895,7 +2043,62 @@ mod tests {
         data.extend_from_slice(&[0u8; 100]);
 
         let result = decode_edit(&data);
-        assert!(matches!(result, Err(DecodeError::UnsupportedVersion { version: 99 })));
+        assert!(matches!(
+            result.unwrap_err().kind(),
+            DecodeError::UnsupportedVersion { version: 99 }
+        ));
+    }
+
+    #[test]
+    fn test_decode_any_versions() {
+        let mut edit = make_test_edit();
+        let v1 = encode_edit(&edit).unwrap();
+        let (version, decoded) = decode_any(&v1).unwrap();
+        assert_eq!((version, decoded.provenance), (FormatVersion::V1, None));
+
+        // Pre-release version 0 shares the version 1 layout
+        let mut v0 = v1.clone();
+        v0[4] = 0;
+        assert_eq!(decode_any(&v0).unwrap(), (FormatVersion::V0, edit.clone()));
+
+        edit.provenance = Some(Provenance { source: Some([9u8; 16]), ..Default::default() });
+        let v2 = encode_edit(&edit).unwrap();
+        assert_eq!(decode_any(&v2).unwrap(), (FormatVersion::V2, edit));
+
+        let mut future = v2;
+        future[4] = FormatVersion::LATEST.as_u8() + 1;
+        assert!(matches!(
+            decode_any(&future).unwrap_err().kind(),
+            DecodeError::UnsupportedVersion { .. }
+        ));
+    }
+
+    #[test]
+    fn test_checksum_trailer() {
+        let mut edit = make_test_edit();
+        let plain = encode_edit(&edit).unwrap();
+        let options = EncodeOptions::new().with_checksum();
+        let encoded = encode_edit_with_options(&edit, options).unwrap();
+        // Version 2 adds a flags byte, plus the 4-byte trailer
+        assert_eq!(encoded.len(), plain.len() + 5);
+        assert_eq!(decode_any(&encoded).unwrap(), (FormatVersion::V2, edit.clone()));
+
+        for i in [0, 20, encoded.len() / 2, encoded.len() - 1] {
+            let mut corrupt = encoded.clone();
+            corrupt[i] ^= 0x10;
+            assert!(decode_edit(&corrupt).is_err(), "byte {i}");
+        }
+        let mut corrupt = encoded.clone();
+        *corrupt.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(
+            decode_edit(&corrupt).unwrap_err().kind(),
+            DecodeError::ChecksumMismatch { .. }
+        ));
+
+        edit.provenance = Some(Provenance { source: Some([9u8; 16]), ..Default::default() });
+        let options = EncodeOptions::canonical().with_checksum();
+        let encoded = encode_edit_with_options(&edit, options).unwrap();
+        assert_eq!(decode_edit(&encoded).unwrap(), edit);
     }
 
     #[test]
@@ -905,6 +2108,7 @@ mod tests {
             name: Cow::Borrowed(""),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![],
         };
 
@@ -917,6 +2121,263 @@ mod tests {
         assert!(decoded.ops.is_empty());
     }
 
+    #[test]
+    fn test_provenance_roundtrip() {
+        let mut edit = make_test_edit();
+        // Edits without provenance stay on version 1
+        assert_eq!(encode_edit(&edit).unwrap()[4], FormatVersion::V1.as_u8());
+
+        edit.provenance = Some(Provenance {
+            source: Some([9u8; 16]),
+            parents: vec![[8u8; 16], [7u8; 16]],
+            metadata: vec![
+                (Cow::Borrowed("tool"), Cow::Borrowed("importer")),
+                (Cow::Borrowed("batch"), Cow::Borrowed("3")),
+            ],
+        });
+        let encoded = encode_edit(&edit).unwrap();
+        assert_eq!(encoded[4], FormatVersion::V2.as_u8());
+        assert_eq!(decode_edit(&encoded).unwrap(), edit);
+
+        let canonical = encode_edit_canonical(&edit).unwrap();
+        let provenance = decode_edit(&canonical).unwrap().provenance.unwrap();
+        assert_eq!(provenance.parents, vec![[7u8; 16], [8u8; 16]]);
+        assert_eq!(provenance.get("batch"), Some("3"));
+        assert_eq!(provenance.metadata[0].0, "batch");
+
+        // Unknown flag bits are rejected
+        // magic, version, id, name, authors, created_at
+        let flags_at = 4 + 1 + 16 + 10 + 17 + 5;
+        let mut bad = encoded.clone();
+        bad[flags_at] |= 0x80;
+        assert!(matches!(
+            decode_edit(&bad).unwrap_err().kind(),
+            DecodeError::ReservedBitsSet { .. }
+        ));
+
+        edit.provenance.as_mut().unwrap().parents.push([8u8; 16]);
+        assert!(matches!(
+            encode_edit_canonical(&edit),
+            Err(EncodeError::DuplicateParent { .. })
+        ));
+    }
+
+    #[test]
+    fn test_value_clock_roundtrip() {
+        let mut edit = make_test_edit();
+        edit.ops = vec![Op::UpdateEntity(crate::model::UpdateEntity {
+            id: [5u8; 16],
+            set_properties: vec![
+                PropertyValue {
+                    property: [1u8; 16],
+                    value: Value::Int64 { value: 1, unit: None },
+                    clock: Some(7),
+                },
+                PropertyValue { property: [2u8; 16], value: Value::Bool(true), clock: None },
+            ],
+            unset_values: vec![],
+            remove_values: vec![],
+        })];
+
+        // Any clock moves the edit to version 3
+        let encoded = encode_edit(&edit).unwrap();
+        assert_eq!(encoded[4], FormatVersion::V3.as_u8());
+        assert_eq!(decode_edit(&encoded).unwrap(), edit);
+        let canonical = encode_edit_canonical(&edit).unwrap();
+        assert_eq!(decode_edit(&canonical).unwrap(), edit);
+
+        edit.provenance = Some(Provenance { source: Some([9u8; 16]), ..Default::default() });
+        assert_eq!(decode_edit(&encode_edit(&edit).unwrap()).unwrap(), edit);
+
+        if let Op::UpdateEntity(ue) = &mut edit.ops[0] {
+            ue.set_properties[0].clock = Some(0);
+        }
+        assert!(matches!(encode_edit(&edit), Err(EncodeError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn test_remove_values_roundtrip() {
+        let embedding = Value::Embedding {
+            sub_type: crate::model::EmbeddingSubType::Int8,
+            dims: 4096,
+            data: Cow::Owned(vec![1; 4096]),
+        };
+        let (first, second) = (embedding.value_id(&[1u8; 16]), [2u8; 16]);
+        let edit = crate::model::EditBuilder::new([100u8; 16])
+            .update_entity([5u8; 16], |u| u.remove_value(first).remove_value(second))
+            .build();
+
+        let encoded = encode_edit(&edit).unwrap();
+        assert!(encoded.len() < 100);
+        assert_eq!(decode_edit(&encoded).unwrap(), edit);
+
+        // Remove lists need version 2 and the header flag that announces them
+        assert_eq!(decode_any(&encoded).unwrap().0, FormatVersion::V2);
+        let mut unflagged = encoded.clone();
+        assert_eq!(unflagged[24], HEADER_HAS_REMOVE_VALUES);
+        unflagged[24] = 0;
+        assert!(matches!(
+            decode_edit(&unflagged).unwrap_err().kind(),
+            DecodeError::ReservedBitsSet { context: "UpdateEntity flags" }
+        ));
+        let reader = crate::codec::raw::RawEditReader::new(&unflagged).unwrap();
+        assert!(reader.into_iter().any(|raw| raw.is_err()));
+
+        // Canonical mode sorts the IDs and rejects duplicates
+        let canonical = encode_edit_canonical(&edit).unwrap();
+        let canonical = decode_edit(&canonical).unwrap();
+        let Op::UpdateEntity(ue) = &canonical.ops[0] else { panic!("expected UpdateEntity") };
+        let mut sorted = vec![first, second];
+        sorted.sort();
+        assert_eq!(ue.remove_values, sorted);
+        let duplicated = crate::model::EditBuilder::new([100u8; 16])
+            .update_entity([5u8; 16], |u| u.remove_value(second).remove_value(second))
+            .build();
+        assert!(matches!(
+            encode_edit_canonical(&duplicated),
+            Err(EncodeError::DuplicateRemoveValue { value_id }) if value_id == second
+        ));
+    }
+
+    #[test]
+    fn test_interned_strings_roundtrip() {
+        let (name, kind, lang) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let mut builder = crate::model::EditBuilder::new([100u8; 16]).name("cities");
+        for i in 0..40u8 {
+            builder = builder.create_entity([i; 16], |e| {
+                e.text(name, format!("city {i}"), None)
+                    .text(kind, "city", None)
+                    .text(kind, "ville", Some(lang))
+                    .text([4u8; 16], "x", None)
+            });
+        }
+        let edit = builder.update_entity([0u8; 16], |u| u.set_text(kind, "city", None)).build();
+        assert_eq!(repeated_strings(&edit), vec!["city", "ville"]);
+
+        let plain = encode_edit(&edit).unwrap();
+        let options = EncodeOptions::new().with_interned_strings();
+        let interned = encode_edit_with_options(&edit, options).unwrap();
+        assert!(interned.len() + 40 * 4 < plain.len());
+        assert_eq!(interned[4], FormatVersion::V2.as_u8());
+        assert_eq!(decode_edit(&interned).unwrap(), edit);
+
+        // Other encoders and readers agree on the table
+        let chunked = encode_edit_chunked(&edit, 7, options).unwrap();
+        assert_eq!(chunked, interned);
+        let reader = crate::codec::raw::RawEditReader::new(&interned).unwrap();
+        let ops: Vec<_> = reader.map(|raw| raw.unwrap()).collect();
+        let reader = crate::codec::raw::RawEditReader::new(&interned).unwrap();
+        assert_eq!(reader.decode(&ops[3]).unwrap(), edit.ops[3]);
+        let stats = crate::codec::stats::EditStats::compute(&interned).unwrap();
+        assert_eq!(stats.values_by_type[&DataType::Text].count, 161);
+
+        let checked = encode_edit_with_options(&edit, options.with_checksum()).unwrap();
+        assert_eq!(decode_edit(&checked).unwrap(), edit);
+        let compressed = encode_edit_compressed_with_options(&edit, 3, options).unwrap();
+        assert_eq!(decode_edit(&compressed).unwrap(), edit);
+
+        // Canonical output does not depend on the option
+        let canonical = EncodeOptions { intern_strings: true, ..EncodeOptions::canonical() };
+        assert_eq!(
+            encode_edit_with_options(&edit, canonical).unwrap(),
+            encode_edit_canonical(&edit).unwrap(),
+        );
+
+        // References past the table are rejected
+        let edit = crate::model::EditBuilder::new([100u8; 16])
+            .create_entity([1u8; 16], |e| e.text(name, "city", None))
+            .create_entity([2u8; 16], |e| e.text(name, "city", None))
+            .build();
+        let mut bad = encode_edit_with_options(&edit, options).unwrap();
+        let last = bad.len() - 2;
+        bad[last] = 2;
+        assert!(matches!(
+            decode_edit(&bad).unwrap_err().kind(),
+            DecodeError::IndexOutOfBounds { dict: "strings", index: 2, size: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_columnar_roundtrip() {
+        use crate::codec::raw::{decode_edit_filtered, RawEditReader};
+
+        let (name, population, lang, km) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]);
+        let mut builder = crate::model::EditBuilder::new([100u8; 16]);
+        for i in 0..30u8 {
+            builder = builder.create_entity([i; 16], |e| {
+                e.text(name, format!("city {i}"), None)
+                    .text(name, "ville", Some(lang))
+                    .int64(population, i64::from(i) * 1000, Some(km))
+            });
+        }
+        let edit = builder
+            .create_relation_simple([200u8; 16], [0u8; 16], [1u8; 16], [5u8; 16])
+            .create_entity([201u8; 16], |e| e.int64(population, 1, None))
+            .create_entity([202u8; 16], |e| e.int64(population, 2, None))
+            .create_entity([203u8; 16], |e| e.text(name, "many", None))
+            .build();
+
+        let plain = encode_edit(&edit).unwrap();
+        let options = EncodeOptions::new().with_columnar();
+        let columnar = encode_edit_with_options(&edit, options).unwrap();
+        assert!(columnar.len() + 30 * 4 < plain.len());
+        assert_eq!(decode_edit(&columnar).unwrap(), edit);
+
+        // Runs: 30 cities, the relation, 2 entities, and the last entity
+        let reader = RawEditReader::new(&columnar).unwrap();
+        let records: Vec<_> = reader.clone().map(|raw| raw.unwrap()).collect();
+        let kinds: Vec<_> = records.iter().map(|raw| (raw.kind, raw.ops)).collect();
+        assert_eq!(kinds, vec![
+            (OpKind::BatchCreateEntities, 30),
+            (OpKind::CreateRelation, 1),
+            (OpKind::BatchCreateEntities, 2),
+            (OpKind::CreateEntity, 1),
+        ]);
+        assert_eq!(reader.decode_ops(&records[2]).unwrap(), edit.ops[31..33]);
+        assert!(reader.decode(&records[0]).is_err());
+
+        let stats = crate::codec::stats::EditStats::compute(&columnar).unwrap();
+        assert_eq!(stats.op_count(), edit.ops.len());
+        assert_eq!(stats.values_by_type[&DataType::Text].count, 61);
+        let filter: crate::model::IdSet = [[3u8; 16], [202u8; 16]].into_iter().collect();
+        let filtered = decode_edit_filtered(&columnar, &filter).unwrap();
+        assert_eq!(filtered.ops, vec![edit.ops[3].clone(), edit.ops[32].clone()]);
+
+        // Chunks split between records, never inside one
+        for chunk_size in [1, 2, 3] {
+            assert_eq!(encode_edit_chunked(&edit, chunk_size, options).unwrap(), columnar);
+        }
+        let both = options.with_interned_strings();
+        assert_eq!(decode_edit(&encode_edit_with_options(&edit, both).unwrap()).unwrap(), edit);
+        let compressed = encode_edit_compressed_with_options(&edit, 3, options).unwrap();
+        assert_eq!(decode_edit(&compressed).unwrap(), edit);
+
+        // A record may not hold more ops than the edit declares
+        let mut bad = columnar.clone();
+        bad[reader.ops_offset() - 1] = 10;
+        assert!(matches!(
+            decode_edit(&bad).unwrap_err().kind(),
+            DecodeError::MalformedEncoding { .. }
+        ));
+        assert!(RawEditReader::new(&bad).unwrap().any(|raw| raw.is_err()));
+
+        // Records need version 2 and the header flag that announces them
+        assert_eq!(plain[4], FormatVersion::V1.as_u8());
+        assert_eq!(decode_any(&columnar).unwrap().0, FormatVersion::V2);
+        let mut unflagged = columnar.clone();
+        let flags_at = 5 + 16 + 1 + edit.name.len() + 1 + 1;
+        assert_eq!(unflagged[flags_at], HEADER_HAS_BATCH_RECORDS);
+        unflagged[flags_at] = 0;
+        assert!(matches!(
+            decode_edit(&unflagged).unwrap_err().kind(),
+            DecodeError::InvalidOpType { op_type: 14 }
+        ));
+        assert!(RawEditReader::new(&unflagged).unwrap().any(|raw| raw.is_err()));
+        let single = Edit { ops: edit.ops[30..31].to_vec(), ..edit.clone() };
+        let single = encode_edit_with_options(&single, options).unwrap();
+        assert_eq!(single[4], FormatVersion::V1.as_u8());
+    }
+
     #[test]
     fn test_canonical_encoding_deterministic() {
         // Two edits with values in different order should produce
@@ -931,6 +2392,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [3u8; 16],
@@ -941,10 +2403,12 @@ mod tests {
                                 value: Cow::Owned("Hello".to_string()),
                                 language: None,
                             },
+                            clock: None,
                         },
                         PropertyValue {
                             property: prop_b,
                             value: Value::Int64 { value: 42, unit: None },
+                            clock: None,
                         },
                     ],
                 }),
@@ -957,6 +2421,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [3u8; 16],
@@ -965,6 +2430,7 @@ mod tests {
                         PropertyValue {
                             property: prop_b,
                             value: Value::Int64 { value: 42, unit: None },
+                            clock: None,
                         },
                         PropertyValue {
                             property: prop_a,
@@ -972,6 +2438,7 @@ mod tests {
                                 value: Cow::Owned("Hello".to_string()),
                                 language: None,
                             },
+                            clock: None,
                         },
                     ],
                 }),
@@ -1023,6 +2490,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "zstd")]
     fn test_canonical_encoding_compressed() {
         let edit = make_test_edit();
 
@@ -1042,6 +2510,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![author1, author1], // Duplicate!
             created_at: 0,
+            provenance: None,
             ops: vec![],
         };
 
@@ -1063,6 +2532,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [1u8; 16],
@@ -1073,6 +2543,7 @@ mod tests {
                                 value: Cow::Owned("First".to_string()),
                                 language: None,
                             },
+                            clock: None,
                         },
                         PropertyValue {
                             property: prop,
@@ -1080,6 +2551,7 @@ mod tests {
                                 value: Cow::Owned("Second".to_string()),
                                 language: None,
                             },
+                            clock: None,
                         },
                     ],
                 }),
@@ -1102,6 +2574,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [1u8; 16],
@@ -1112,6 +2585,7 @@ mod tests {
                                 value: Cow::Owned("Hello".to_string()),
                                 language: Some(lang_en),
                             },
+                            clock: None,
                         },
                         PropertyValue {
                             property: prop,
@@ -1119,6 +2593,7 @@ mod tests {
                                 value: Cow::Owned("Hola".to_string()),
                                 language: Some(lang_es),
                             },
+                            clock: None,
                         },
                     ],
                 }),
@@ -1141,6 +2616,7 @@ mod tests {
             name: Cow::Owned("Test".to_string()),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![
                 Op::CreateEntity(CreateEntity {
                     id: [3u8; 16],
@@ -1148,6 +2624,7 @@ mod tests {
                         PropertyValue {
                             property: prop_b, // B first
                             value: Value::Int64 { value: 42, unit: None },
+                            clock: None,
                         },
                         PropertyValue {
                             property: prop_a, // A second
@@ -1155,6 +2632,7 @@ mod tests {
                                 value: Cow::Owned("Hello".to_string()),
                                 language: None,
                             },
+                            clock: None,
                         },
                     ],
                 }),
@@ -1170,4 +2648,94 @@ mod tests {
         let decoded = decode_edit(&encoded1).unwrap();
         assert_eq!(decoded.ops.len(), 1);
     }
+
+    #[test]
+    fn test_decode_strict_ids() {
+        use crate::model::DeleteEntity;
+
+        let valid = crate::model::derived_uuid(b"entity");
+        let mut edit: Edit<'static> = Edit::new([1u8; 16]);
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: valid }));
+        let encoded = encode_edit(&edit).unwrap();
+        assert!(decode_edit_with_options(&encoded, DecodeOptions::strict_ids()).is_ok());
+
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [3u8; 16] }));
+        let encoded = encode_edit(&edit).unwrap();
+        assert!(decode_edit_with_options(&encoded, DecodeOptions::new()).is_ok());
+        assert!(matches!(
+            decode_edit_with_options(&encoded, DecodeOptions::strict_ids()),
+            Err(DecodeError::InvalidId { field: "entity", id, .. }) if id == [3u8; 16]
+        ));
+    }
+
+    #[test]
+    fn test_preserve_unknown_ops() {
+        use crate::model::{DeleteEntity, UnknownOp};
+
+        let mut edit: Edit<'static> = Edit::new([1u8; 16]);
+        edit.ops.push(Op::Unknown(UnknownOp { tag: 0x80, bytes: Cow::Owned(vec![1, 2, 3]) }));
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [3u8; 16] }));
+        let encoded = encode_edit(&edit).unwrap();
+
+        assert!(matches!(
+            decode_edit(&encoded).unwrap_err().kind(),
+            DecodeError::InvalidOpType { op_type: 0x80 }
+        ));
+        let decoded = decode_edit_with_options(&encoded, DecodeOptions::lenient()).unwrap();
+        assert_eq!(decoded.ops, edit.ops);
+        assert_eq!(encode_edit(&decoded).unwrap(), encoded);
+
+        // Core op types can't be smuggled in as extensions
+        edit.ops[0] = Op::Unknown(UnknownOp { tag: 0x01, bytes: Cow::Owned(vec![]) });
+        assert!(encode_edit(&edit).is_err());
+    }
+
+    #[test]
+    fn test_skip_embedding_validation() {
+        use crate::model::EditBuilder;
+
+        let edit = EditBuilder::new([9u8; 16])
+            .create_entity([1u8; 16], |e| e.embedding_f32([2u8; 16], &[1.5, 2.5, 3.5]))
+            .build();
+        let mut encoded = encode_edit(&edit).unwrap();
+        let at = encoded.windows(4).position(|w| w == 2.5f32.to_le_bytes()).unwrap();
+        encoded[at..at + 4].copy_from_slice(&f32::NAN.to_le_bytes());
+
+        assert!(matches!(decode_edit(&encoded).unwrap_err().kind(), DecodeError::FloatIsNan));
+        let trusted = DecodeOptions { validate_embeddings: false, ..DecodeOptions::new() };
+        let decoded = decode_edit_with_options(&encoded, trusted).unwrap();
+        let Op::CreateEntity(ce) = &decoded.ops[0] else { panic!("expected CreateEntity") };
+        assert_eq!(ce.values[0].value.validate(), Some("NaN is not allowed in float32 embedding"));
+    }
+
+    #[test]
+    fn test_property_types_across_ops() {
+        use crate::model::EditBuilder;
+
+        let entity = [1u8; 16];
+        let age = [2u8; 16];
+
+        // An unset before the first value must not fix the property's type.
+        let edit = EditBuilder::new([9u8; 16])
+            .update_entity(entity, |u| u.unset_all(age))
+            .update_entity(entity, |u| u.set_int64(age, 42, None))
+            .build();
+        let canonical = encode_edit_with_options(&edit, EncodeOptions::canonical()).unwrap();
+        for encoded in [encode_edit(&edit).unwrap(), canonical] {
+            assert_eq!(decode_edit(&encoded).unwrap(), edit);
+        }
+
+        let edit = EditBuilder::new([9u8; 16])
+            .update_entity(entity, |u| u.set_int64(age, 42, None))
+            .update_entity(entity, |u| u.set_text(age, "42", None))
+            .build();
+        assert!(matches!(
+            encode_edit(&edit),
+            Err(EncodeError::PropertyTypeConflict {
+                declared: DataType::Int64,
+                actual: DataType::Text,
+                ..
+            })
+        ));
+    }
 }
@@ -0,0 +1,370 @@
+//! Encoded size estimation without encoding.
+//!
+//! [`estimate_size`] returns an upper bound on the size of an edit's
+//! uncompressed encoding, so publishers can check it against a payload
+//! limit before encoding. The header and dictionaries are sized exactly:
+//! the dictionaries are projected by collecting the IDs the ops reference,
+//! which needs no value encoding. Ops are sized from their fields, with
+//! every dictionary index counted at the width of the largest index. With
+//! fewer than 128 entries in each dictionary that width is one byte and the
+//! estimate is exact.
+//!
+//! The bound holds for edits that encode successfully; invalid values are
+//! sized but not checked.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::codec::edit::{repeated_strings, EncodeOptions};
+use crate::codec::op::create_entity_run;
+use crate::codec::primitives::{varint_len, zigzag_encode};
+use crate::hash::FxHashSet;
+use crate::model::{CreateEntity, DecimalMantissa, Edit, Op, PropertyValue, UnsetLanguage, Value};
+
+/// Upper bound on an edit's encoded size, by section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeEstimate {
+    /// Magic, version, and header fields (exact).
+    pub header: usize,
+    /// Dictionaries and string table (exact).
+    pub dictionaries: usize,
+    /// Op count and ops (upper bound).
+    pub ops: usize,
+    /// Checksum trailer (exact).
+    pub trailer: usize,
+    /// Sum of the sections.
+    pub total: usize,
+}
+
+impl SizeEstimate {
+    /// Returns true if the edit may encode to more than `limit` bytes.
+    pub fn exceeds(&self, limit: usize) -> bool {
+        self.total > limit
+    }
+}
+
+/// Estimates the size of [`encode_edit`](crate::codec::encode_edit) output.
+pub fn estimate_size(edit: &Edit) -> SizeEstimate {
+    estimate_size_with_options(edit, EncodeOptions::default())
+}
+
+/// Estimates the size of
+/// [`encode_edit_with_options`](crate::codec::encode_edit_with_options)
+/// output.
+pub fn estimate_size_with_options(edit: &Edit, options: EncodeOptions) -> SizeEstimate {
+    let value_clocks = edit.has_value_clocks();
+    let strings = if options.intern_strings && !options.canonical {
+        repeated_strings(edit)
+    } else {
+        Vec::new()
+    };
+
+    let dicts = edit.build_dictionaries().build();
+    let mut dictionaries = varint_len(dicts.properties.len() as u64) + dicts.properties.len() * 17;
+    for ids in [&dicts.relation_types, &dicts.languages, &dicts.units, &dicts.objects] {
+        dictionaries += id_vec_len(ids.len());
+    }
+    if !strings.is_empty() {
+        dictionaries += varint_len(strings.len() as u64);
+        dictionaries += strings.iter().map(|s| string_len(s)).sum::<usize>();
+    }
+
+    let sizer = OpSizer {
+        property: max_index_len(dicts.properties.len()),
+        relation_type: max_index_len(dicts.relation_types.len()),
+        language: varint_len(dicts.languages.len() as u64),
+        unit: varint_len(dicts.units.len() as u64),
+        object: max_index_len(dicts.objects.len()),
+        value_clocks,
+        strings: strings.iter().map(String::as_str).collect(),
+        string_ref: varint_len(strings.len() as u64),
+    };
+    let columnar = options.columnar && !options.canonical;
+    let mut ops = varint_len(edit.ops.len() as u64);
+    let mut rest = &edit.ops[..];
+    let mut batch_records = false;
+    while !rest.is_empty() {
+        let run = if columnar { create_entity_run(rest) } else { 0 };
+        if run > 1 {
+            batch_records = true;
+            ops += sizer.batch_len(&rest[..run]);
+            rest = &rest[run..];
+        } else {
+            ops += sizer.op_len(&rest[0]);
+            rest = &rest[1..];
+        }
+    }
+
+    let mut header = 4 + 1 + 16 + string_len(&edit.name) + id_vec_len(edit.authors.len());
+    header += varint_len(zigzag_encode(edit.created_at));
+    let provenance = edit.provenance.as_ref().filter(|p| !p.is_empty());
+    let header_flags = value_clocks
+        || batch_records
+        || edit.has_remove_values()
+        || provenance.is_some()
+        || !strings.is_empty();
+    if header_flags || options.checksum {
+        header += 1;
+    }
+    if let Some(provenance) = provenance {
+        header += if provenance.source.is_some() { 16 } else { 0 };
+        if !provenance.parents.is_empty() {
+            header += id_vec_len(provenance.parents.len());
+        }
+        if !provenance.metadata.is_empty() {
+            header += varint_len(provenance.metadata.len() as u64);
+            for (key, value) in &provenance.metadata {
+                header += string_len(key) + string_len(value);
+            }
+        }
+    }
+
+    let trailer = if options.checksum { 4 } else { 0 };
+    SizeEstimate {
+        header,
+        dictionaries,
+        ops,
+        trailer,
+        total: header + dictionaries + ops + trailer,
+    }
+}
+
+/// Sizes ops given the widths of their dictionary indices.
+struct OpSizer<'e> {
+    property: usize,
+    relation_type: usize,
+    language: usize,
+    unit: usize,
+    object: usize,
+    value_clocks: bool,
+    strings: FxHashSet<&'e str>,
+    string_ref: usize,
+}
+
+impl OpSizer<'_> {
+    fn op_len(&self, op: &Op<'_>) -> usize {
+        let optional_ids = |ids: &[bool]| 16 * ids.iter().filter(|id| **id).count();
+        match op {
+            Op::CreateEntity(ce) => 1 + 16 + self.values_len(&ce.values),
+            Op::UpdateEntity(ue) => {
+                let mut len = 1 + self.object + 1;
+                if !ue.set_properties.is_empty() {
+                    len += self.values_len(&ue.set_properties);
+                }
+                if !ue.unset_values.is_empty() {
+                    len += varint_len(ue.unset_values.len() as u64);
+                    for unset in &ue.unset_values {
+                        len += self.property;
+                        len += match unset.language {
+                            UnsetLanguage::All => varint_len(0xFFFF_FFFF),
+                            UnsetLanguage::English => 1,
+                            UnsetLanguage::Specific(_) => self.language,
+                        };
+                    }
+                }
+                if !ue.remove_values.is_empty() {
+                    len += id_vec_len(ue.remove_values.len());
+                }
+                len
+            }
+            Op::DeleteEntity(_)
+            | Op::RestoreEntity(_)
+            | Op::DeleteRelation(_)
+            | Op::RestoreRelation(_) => 1 + self.object,
+            Op::CreateRelation(cr) => {
+                let endpoint = |is_value_ref| if is_value_ref { 16 } else { self.object };
+                1 + 16
+                    + self.relation_type
+                    + 1
+                    + endpoint(cr.from_is_value_ref)
+                    + endpoint(cr.to_is_value_ref)
+                    + optional_ids(&[
+                        cr.from_space.is_some(),
+                        cr.from_version.is_some(),
+                        cr.to_space.is_some(),
+                        cr.to_version.is_some(),
+                        cr.entity.is_some(),
+                    ])
+                    + optional_string_len(cr.position.as_deref())
+            }
+            Op::UpdateRelation(ur) => {
+                1 + self.object
+                    + 2
+                    + optional_ids(&[
+                        ur.from_space.is_some(),
+                        ur.from_version.is_some(),
+                        ur.to_space.is_some(),
+                        ur.to_version.is_some(),
+                    ])
+                    + optional_string_len(ur.position.as_deref())
+            }
+            Op::CreateValueRef(cvr) => {
+                1 + 16
+                    + self.object
+                    + self.property
+                    + 1
+                    + if cvr.language.is_some() { self.language } else { 0 }
+                    + optional_ids(&[cvr.space.is_some()])
+            }
+            Op::CreateSpace(cs) => 1 + 16 + string_len(&cs.name),
+            Op::AddMember(_) | Op::RemoveMember(_) => 1 + 16 + 16 + 1,
+            Op::UpdateSpaceMetadata(usm) => {
+                1 + 16
+                    + 1
+                    + optional_string_len(usm.name.as_deref())
+                    + optional_string_len(usm.description.as_deref())
+            }
+            Op::Unknown(op) => 1 + varint_len(op.bytes.len() as u64) + op.bytes.len(),
+        }
+    }
+
+    /// Sizes a BatchCreateEntities record for a run of CreateEntity ops.
+    fn batch_len(&self, ops: &[Op<'_>]) -> usize {
+        let entities: Vec<&CreateEntity<'_>> = ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::CreateEntity(ce) => Some(ce),
+                _ => None,
+            })
+            .collect();
+        let layout = entities[0].values.len();
+        let mut len = 1 + varint_len(entities.len() as u64) + varint_len(layout as u64);
+        len += layout * self.property + entities.len() * 16;
+        for ce in &entities {
+            len += ce.values.iter().map(|pv| self.value_len(pv)).sum::<usize>();
+        }
+        len
+    }
+
+    /// Sizes a count-prefixed list of property values.
+    fn values_len(&self, values: &[PropertyValue<'_>]) -> usize {
+        let values_len: usize = values.iter().map(|pv| self.property + self.value_len(pv)).sum();
+        varint_len(values.len() as u64) + values_len
+    }
+
+    /// Sizes a value and its clock, without the property index.
+    fn value_len(&self, pv: &PropertyValue<'_>) -> usize {
+        let clock = if self.value_clocks { varint_len(pv.clock.unwrap_or(0)) } else { 0 };
+        let payload = match &pv.value {
+            Value::Bool(_) => 1,
+            Value::Int64 { value, .. } => varint_len(zigzag_encode(*value)) + self.unit,
+            Value::Float64 { .. } => 8 + self.unit,
+            Value::Decimal { exponent, mantissa, .. } => {
+                let mantissa = match mantissa {
+                    DecimalMantissa::I64(v) => varint_len(zigzag_encode(*v)),
+                    DecimalMantissa::Big(bytes) => varint_len(bytes.len() as u64) + bytes.len(),
+                };
+                varint_len(zigzag_encode(i64::from(*exponent))) + 1 + mantissa + self.unit
+            }
+            Value::Text { value, .. } => {
+                let text = match self.strings.contains(&**value) {
+                    true => self.string_ref,
+                    false if self.strings.is_empty() => string_len(value),
+                    false => 1 + string_len(value),
+                };
+                text + self.language
+            }
+            Value::Bytes(bytes) => varint_len(bytes.len() as u64) + bytes.len(),
+            Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => {
+                string_len(s)
+            }
+            Value::Point { alt, .. } => 1 + if alt.is_some() { 24 } else { 16 },
+            Value::Embedding { dims, data, .. } => 1 + varint_len(*dims as u64) + data.len(),
+        };
+        clock + payload
+    }
+}
+
+/// Width of the largest index into a dictionary of `len` entries.
+fn max_index_len(len: usize) -> usize {
+    varint_len(len.saturating_sub(1) as u64)
+}
+
+fn string_len(s: &str) -> usize {
+    varint_len(s.len() as u64) + s.len()
+}
+
+fn optional_string_len(s: Option<&str>) -> usize {
+    s.map_or(0, string_len)
+}
+
+fn id_vec_len(count: usize) -> usize {
+    varint_len(count as u64) + count * 16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::encode_edit_with_options;
+    use crate::model::{EditBuilder, Provenance};
+
+    const NAME: [u8; 16] = [0xA0; 16];
+    const AGE: [u8; 16] = [0xA1; 16];
+    const KNOWS: [u8; 16] = [0xA2; 16];
+
+    fn edit(entities: u8) -> Edit<'static> {
+        let mut edit = EditBuilder::new([0xE1; 16]).name("People").author([0xA5; 16]);
+        for n in 0..entities {
+            edit = edit.create_entity([n; 16], |e| {
+                e.text(NAME, if n % 2 == 0 { "Alice" } else { "Bob" }, None).int64(
+                    AGE,
+                    i64::from(n) * 1000,
+                    Some([0xC0; 16]),
+                )
+            });
+        }
+        edit.create_relation_simple([0xF0; 16], [0u8; 16], [1u8; 16], KNOWS)
+            .update_entity([1u8; 16], |u| {
+                u.set_text(NAME, "Bob B.", Some([0xD0; 16])).unset_all(AGE).remove_value([9u8; 16])
+            })
+            .update_relation_position([0xF0; 16], Some("a".into()))
+            .delete_entity([0u8; 16])
+            .build()
+    }
+
+    fn all_options() -> Vec<EncodeOptions> {
+        vec![
+            EncodeOptions::new(),
+            EncodeOptions::canonical(),
+            EncodeOptions::new().with_checksum(),
+            EncodeOptions::new().with_interned_strings().with_columnar(),
+        ]
+    }
+
+    #[test]
+    fn test_exact_for_small_dictionaries() {
+        let mut edit = edit(4);
+        for options in all_options() {
+            let actual = encode_edit_with_options(&edit, options).unwrap().len();
+            assert_eq!(estimate_size_with_options(&edit, options).total, actual, "{options:?}");
+        }
+
+        edit.provenance = Some(Provenance {
+            source: Some([0xB0; 16]),
+            parents: vec![[0xB1; 16]],
+            metadata: vec![("app".into(), "test".into())],
+        });
+        let estimate = estimate_size(&edit);
+        assert_eq!(estimate.total, crate::codec::encode_edit(&edit).unwrap().len());
+        assert!(!estimate.exceeds(estimate.total));
+        assert!(estimate.exceeds(estimate.total - 1));
+    }
+
+    #[test]
+    fn test_upper_bound_for_large_dictionaries() {
+        let mut edit = edit(4);
+        for n in 0..200u8 {
+            let mut id = [0xDE; 16];
+            id[0] = n;
+            edit.ops.push(Op::DeleteEntity(crate::model::DeleteEntity { id }));
+        }
+        for options in all_options() {
+            let actual = encode_edit_with_options(&edit, options).unwrap().len();
+            let estimate = estimate_size_with_options(&edit, options);
+            assert!(estimate.total >= actual, "{options:?}");
+            // Only references to the first 128 objects are overcounted, by
+            // a byte each
+            assert!(estimate.total - actual < 140, "{options:?}");
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Extraction of large BYTES/TEXT payloads from encoded edits.
+//!
+//! Archiving pipelines often want to store bulk payloads (images, long
+//! documents) separately from graph structure. [`extract_large_values`]
+//! writes every oversized value to its own file, named by the SHA-256 of
+//! its content, and returns a slimmed edit where those values are replaced
+//! by blob references. [`restore_large_values`] reverses the process.
+//!
+//! A blob reference keeps the value's data type (and language, for TEXT):
+//! TEXT values become the string `grc20-blob:sha256:<hex>`, BYTES values
+//! become the same string as UTF-8 bytes.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::codec::edit::{decode_edit, encode_edit};
+use crate::compression::{compression_algorithm, encode_edit_with_compression, CompressionOptions};
+use crate::error::ExtractError;
+use crate::model::bytes::{content_hash, format_hash, HashAlgorithm};
+use crate::model::{DataType, Id, Op, PropertyValue, Value};
+
+/// Prefix of a blob reference; followed by 64 lowercase hex digits.
+pub const BLOB_REF_PREFIX: &str = "grc20-blob:sha256:";
+
+/// zstd level used when re-encoding zstd-compressed input.
+const RECOMPRESS_LEVEL: i32 = 3;
+
+/// A value written out by [`extract_large_values`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedValue {
+    /// Entity the value belongs to.
+    pub entity: Id,
+    /// Property of the value.
+    pub property: Id,
+    /// BYTES or TEXT.
+    pub data_type: DataType,
+    /// SHA-256 of the payload (UTF-8 bytes for TEXT).
+    pub hash: [u8; 32],
+    /// File the payload was written to.
+    pub path: PathBuf,
+    /// Payload size in bytes.
+    pub size: usize,
+}
+
+/// Result of [`extract_large_values`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extraction {
+    /// The slimmed edit, encoded with the same framing as the input
+    /// (compressed input is re-compressed).
+    pub edit: Vec<u8>,
+    /// Every value that was replaced, in op order.
+    pub extracted: Vec<ExtractedValue>,
+}
+
+/// Moves BYTES and TEXT values of at least `min_size` bytes out of an
+/// encoded edit into files under `dir`.
+///
+/// Each payload is written to `dir/<sha256 hex>` as it is encountered, so
+/// only one payload is held beyond the decoded edit at a time. Identical
+/// payloads share a file. The directory is created if needed.
+pub fn extract_large_values(
+    bytes: &[u8],
+    min_size: usize,
+    dir: impl AsRef<Path>,
+) -> Result<Extraction, ExtractError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut edit = decode_edit(bytes)?;
+    let mut extracted = Vec::new();
+
+    for op in &mut edit.ops {
+        let (entity, values) = match op {
+            Op::CreateEntity(ce) => (ce.id, &mut ce.values),
+            Op::UpdateEntity(ue) => (ue.id, &mut ue.set_properties),
+            _ => continue,
+        };
+        for pv in values.iter_mut() {
+            if let Some(value) = extract_value(entity, pv, min_size, dir)? {
+                extracted.push(value);
+            }
+        }
+    }
+
+    let edit = reencode(bytes, &edit)?;
+    Ok(Extraction { edit, extracted })
+}
+
+/// Replaces blob references in an encoded edit with the payloads stored
+/// under `dir` by [`extract_large_values`].
+///
+/// Payloads are verified against their hash before being inlined.
+pub fn restore_large_values(
+    bytes: &[u8],
+    dir: impl AsRef<Path>,
+) -> Result<Vec<u8>, ExtractError> {
+    let dir = dir.as_ref();
+    let mut edit = decode_edit(bytes)?;
+
+    for op in &mut edit.ops {
+        let values = match op {
+            Op::CreateEntity(ce) => &mut ce.values,
+            Op::UpdateEntity(ue) => &mut ue.set_properties,
+            _ => continue,
+        };
+        for pv in values.iter_mut() {
+            let Some(hash) = blob_ref_hash(&pv.value) else {
+                continue;
+            };
+            let path = dir.join(format_hash(&hash));
+            let data = fs::read(&path)?;
+            if content_hash(&data, HashAlgorithm::Sha256) != hash {
+                return Err(ExtractError::HashMismatch { path });
+            }
+            pv.value = match &pv.value {
+                Value::Text { language, .. } => {
+                    let text = String::from_utf8(data)
+                        .map_err(|_| ExtractError::InvalidUtf8 { path })?;
+                    Value::Text { value: Cow::Owned(text), language: *language }
+                }
+                _ => Value::Bytes(Cow::Owned(data)),
+            };
+        }
+    }
+
+    reencode(bytes, &edit)
+}
+
+/// Returns the payload hash if the value is a blob reference.
+pub fn blob_ref_hash(value: &Value) -> Option<[u8; 32]> {
+    let reference = match value {
+        Value::Text { value, .. } => value.as_bytes(),
+        Value::Bytes(data) => data,
+        _ => return None,
+    };
+    let hex = reference.strip_prefix(BLOB_REF_PREFIX.as_bytes())?;
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut hash = [0u8; 32];
+    for (i, pair) in hex.chunks_exact(2).enumerate() {
+        let hi = hex_digit(pair[0])?;
+        let lo = hex_digit(pair[1])?;
+        hash[i] = (hi << 4) | lo;
+    }
+    Some(hash)
+}
+
+/// Writes one value out if it is large enough, replacing it with a reference.
+fn extract_value(
+    entity: Id,
+    pv: &mut PropertyValue,
+    min_size: usize,
+    dir: &Path,
+) -> Result<Option<ExtractedValue>, ExtractError> {
+    let (payload, data_type): (&[u8], DataType) = match &pv.value {
+        Value::Bytes(data) => (data, DataType::Bytes),
+        Value::Text { value, .. } => (value.as_bytes(), DataType::Text),
+        _ => return Ok(None),
+    };
+    if payload.len() < min_size || blob_ref_hash(&pv.value).is_some() {
+        return Ok(None);
+    }
+
+    let hash = content_hash(payload, HashAlgorithm::Sha256);
+    let path = dir.join(format_hash(&hash));
+    if !path.exists() {
+        // Write to a temporary name first so a crash never leaves a
+        // truncated file under a valid hash name.
+        let tmp = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(payload)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+    }
+    let size = payload.len();
+
+    let reference = format!("{}{}", BLOB_REF_PREFIX, format_hash(&hash));
+    pv.value = match &pv.value {
+        Value::Text { language, .. } => Value::Text {
+            value: Cow::Owned(reference),
+            language: *language,
+        },
+        _ => Value::Bytes(Cow::Owned(reference.into_bytes())),
+    };
+
+    Ok(Some(ExtractedValue {
+        entity,
+        property: pv.property,
+        data_type,
+        hash,
+        path,
+        size,
+    }))
+}
+
+/// Encodes an edit with the same framing (compression backend or none) as
+/// `original`.
+fn reencode(original: &[u8], edit: &crate::model::Edit) -> Result<Vec<u8>, ExtractError> {
+    let bytes = match compression_algorithm(original) {
+        Some(algorithm) => encode_edit_with_compression(
+            edit,
+            CompressionOptions::new(algorithm).with_level(RECOMPRESS_LEVEL),
+        )?,
+        None => encode_edit(edit)?,
+    };
+    Ok(bytes)
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateEntity, Edit};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("grc20-extract-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sample_edit() -> Edit<'static> {
+        let mut edit = Edit::new([1u8; 16]);
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: [2u8; 16],
+            values: vec![
+                PropertyValue {
+                    property: [3u8; 16],
+                    value: Value::Bytes(Cow::Owned(vec![7u8; 4096])),
+                    clock: None,
+                },
+                PropertyValue {
+                    property: [4u8; 16],
+                    value: Value::Text {
+                        value: Cow::Owned("long text ".repeat(100)),
+                        language: Some([5u8; 16]),
+                    },
+                    clock: None,
+                },
+                PropertyValue {
+                    property: [6u8; 16],
+                    value: Value::Text { value: Cow::Borrowed("short"), language: None },
+                    clock: None,
+                },
+            ],
+        }));
+        edit
+    }
+
+    #[test]
+    fn test_extract_and_restore_roundtrip() {
+        let dir = temp_dir("roundtrip");
+        let original = encode_edit(&sample_edit()).unwrap();
+
+        let extraction = extract_large_values(&original, 256, &dir).unwrap();
+        assert_eq!(extraction.extracted.len(), 2);
+        assert!(extraction.edit.len() < original.len() / 4);
+        for value in &extraction.extracted {
+            assert_eq!(fs::metadata(&value.path).unwrap().len() as usize, value.size);
+        }
+
+        let slim = decode_edit(&extraction.edit).unwrap();
+        let Op::CreateEntity(ce) = &slim.ops[0] else { panic!("expected CreateEntity") };
+        assert_eq!(blob_ref_hash(&ce.values[0].value), Some(extraction.extracted[0].hash));
+        assert!(matches!(
+            &ce.values[1].value,
+            Value::Text { language: Some(l), .. } if *l == [5u8; 16]
+        ));
+        assert_eq!(blob_ref_hash(&ce.values[2].value), None);
+
+        let restored = restore_large_values(&extraction.edit, &dir).unwrap();
+        assert_eq!(decode_edit(&restored).unwrap(), decode_edit(&original).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_extract_preserves_compression_and_detects_tampering() {
+        use crate::codec::edit::encode_edit_compressed;
+
+        let dir = temp_dir("compressed");
+        let original = encode_edit_compressed(&sample_edit(), 3).unwrap();
+
+        let extraction = extract_large_values(&original, 256, &dir).unwrap();
+        assert_eq!(&extraction.edit[0..5], crate::limits::MAGIC_COMPRESSED);
+
+        fs::write(&extraction.extracted[0].path, b"tampered").unwrap();
+        let result = restore_large_values(&extraction.edit, &dir);
+        assert!(matches!(result, Err(ExtractError::HashMismatch { .. })));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,15 +2,66 @@
 //!
 //! This module implements the GRC-20 v2 binary format (spec Section 6).
 
+#[cfg(feature = "tokio")]
+pub mod aio;
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod compact;
+#[cfg(feature = "std")]
+pub mod delta;
 pub mod edit;
+pub mod estimate;
+#[cfg(feature = "std")]
+pub mod extract;
 pub mod op;
 pub mod primitives;
+pub mod raw;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod transaction;
+#[cfg(feature = "std")]
+pub mod usage;
 pub mod value;
+pub mod version;
 
+#[cfg(feature = "tokio")]
+pub use aio::{
+    decode_edit_from, decode_edit_from_with_options, encode_edit_to, encode_edit_to_with_options,
+};
+#[cfg(feature = "std")]
+pub use audit::{audit_trail, audit_trail_to_json, AuditAction, AuditEntry, RelationDirection};
+#[cfg(feature = "std")]
+pub use batch::{decode_batch, encode_batch, shared_dictionary};
+#[cfg(feature = "std")]
+pub use compact::{compact_log, CompactionReport};
+#[cfg(feature = "std")]
+pub use delta::{apply_delta, encode_edit_delta, is_delta};
 pub use edit::{
-    decode_edit, decompress, encode_edit, encode_edit_compressed,
-    encode_edit_compressed_with_options, encode_edit_profiled, encode_edit_with_options,
-    EncodeOptions,
+    decode_any, decode_edit, decode_edit_lossy, decode_edit_with_options, decompress, encode_edit,
+    encode_edit_canonical, encode_edit_compressed, encode_edit_compressed_with_options,
+    encode_edit_with_options, DecodeOptions, EncodeOptions,
+};
+#[cfg(feature = "bumpalo")]
+pub use edit::decode_edit_in;
+#[cfg(feature = "std")]
+pub use edit::encode_edit_profiled;
+#[cfg(feature = "parallel")]
+pub use edit::{encode_edit_parallel, PARALLEL_MIN_OPS};
+pub use estimate::{estimate_size, estimate_size_with_options, SizeEstimate};
+#[cfg(feature = "std")]
+pub use extract::{extract_large_values, restore_large_values, Extraction, ExtractedValue};
+pub use primitives::{has_f32_nan, varint_len, Reader, Writer, zigzag_decode, zigzag_encode};
+pub use raw::{decode_edit_filtered, RawEditReader, RawOp};
+pub use stats::{EditStats, OpSize, SizeStats};
+#[cfg(feature = "std")]
+pub use transaction::{
+    decode_log_entry, decode_transaction, encode_transaction, encode_transaction_raw,
+    is_transaction, Transaction,
 };
-pub use primitives::{Reader, Writer, zigzag_decode, zigzag_encode};
+#[cfg(feature = "std")]
+pub use usage::{find_usages, PropertyUsage, UsageKind};
 pub use value::{decode_value, encode_value};
+pub use version::FormatVersion;
@@ -2,14 +2,22 @@
 //!
 //! Implements the wire format for operations (spec Section 6.4).
 
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
 use crate::codec::primitives::{Reader, Writer};
-use crate::codec::value::{decode_position, decode_property_value, validate_position};
+use crate::codec::value::{
+    check_position, check_property_type, decode_position, decode_property_value, decode_value,
+    encode_value, read_value_clock, skip_property_value, skip_value_in, write_value_clock,
+};
 use crate::error::{DecodeError, EncodeError};
-use crate::limits::MAX_VALUES_PER_ENTITY;
+use crate::limits::{MAX_BYTES_LEN, MAX_OPS_PER_EDIT, MAX_STRING_LEN, MAX_VALUES_PER_ENTITY};
 use crate::model::{
-    CreateEntity, CreateRelation, CreateValueRef, DataType, DeleteEntity, DeleteRelation,
-    DictionaryBuilder, Op, PropertyValue, RestoreEntity, RestoreRelation,
-    UnsetLanguage, UnsetValue, UnsetRelationField, UpdateEntity, UpdateRelation, WireDictionaries,
+    AddMember, CreateEntity, CreateRelation, CreateSpace, CreateValueRef, DataType, DeleteEntity,
+    DeleteRelation, DictionaryBuilder, Id, MemberRole, Op, OpKind, PropertyValue, RemoveMember,
+    RestoreEntity, RestoreRelation, UnknownOp, UnsetLanguage, UnsetValue, UnsetRelationField,
+    UpdateEntity, UpdateRelation, UpdateSpaceMetadata, Value, WireDictionaries,
+    MIN_EXTENSION_OP_TYPE,
 };
 
 // Op type constants (grouped by lifecycle: Create, Update, Delete, Restore)
@@ -22,11 +30,17 @@ const OP_UPDATE_RELATION: u8 = 6;
 const OP_DELETE_RELATION: u8 = 7;
 const OP_RESTORE_RELATION: u8 = 8;
 const OP_CREATE_VALUE_REF: u8 = 9;
+const OP_CREATE_SPACE: u8 = 10;
+const OP_ADD_MEMBER: u8 = 11;
+const OP_REMOVE_MEMBER: u8 = 12;
+const OP_UPDATE_SPACE_METADATA: u8 = 13;
+const OP_BATCH_CREATE_ENTITIES: u8 = 14;
 
 // UpdateEntity flags
 const FLAG_HAS_SET_PROPERTIES: u8 = 0x01;
 const FLAG_HAS_UNSET_VALUES: u8 = 0x02;
-const UPDATE_ENTITY_RESERVED_MASK: u8 = 0xFC;
+const FLAG_HAS_REMOVE_VALUES: u8 = 0x04;
+const UPDATE_ENTITY_RESERVED_MASK: u8 = 0xF8;
 
 // CreateRelation flags (bit order matches field order in spec Section 6.4)
 const FLAG_HAS_FROM_SPACE: u8 = 0x01;
@@ -43,6 +57,11 @@ const FLAG_HAS_LANGUAGE: u8 = 0x01;
 const FLAG_HAS_SPACE: u8 = 0x02;
 const CREATE_VALUE_REF_RESERVED_MASK: u8 = 0xFC;
 
+// UpdateSpaceMetadata flags
+const FLAG_HAS_NAME: u8 = 0x01;
+const FLAG_HAS_DESCRIPTION: u8 = 0x02;
+const UPDATE_SPACE_RESERVED_MASK: u8 = 0xFC;
+
 // UpdateRelation set flags (bit order matches field order in spec Section 6.4)
 const UPDATE_SET_FROM_SPACE: u8 = 0x01;
 const UPDATE_SET_FROM_VERSION: u8 = 0x02;
@@ -64,7 +83,20 @@ const UPDATE_UNSET_RESERVED_MASK: u8 = 0xE0;
 // =============================================================================
 
 /// Decodes an Op from the reader (zero-copy).
+///
+/// Extension ops fail with [`DecodeError::InvalidOpType`]; see
+/// [`decode_op_with`] to keep them.
 pub fn decode_op<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Op<'a>, DecodeError> {
+    decode_op_with(reader, dicts, false)
+}
+
+/// Decodes an Op, keeping extension ops as [`Op::Unknown`] if
+/// `preserve_unknown` is set.
+pub fn decode_op_with<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries,
+    preserve_unknown: bool,
+) -> Result<Op<'a>, DecodeError> {
     let op_type = reader.read_byte("op_type")?;
 
     match op_type {
@@ -77,10 +109,124 @@ pub fn decode_op<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resul
         OP_DELETE_RELATION => decode_delete_relation(reader, dicts),
         OP_RESTORE_RELATION => decode_restore_relation(reader, dicts),
         OP_CREATE_VALUE_REF => decode_create_value_ref(reader, dicts),
+        OP_CREATE_SPACE => decode_create_space(reader),
+        OP_ADD_MEMBER => {
+            let (space, member, role) = decode_membership(reader)?;
+            Ok(Op::AddMember(AddMember { space, member, role }))
+        }
+        OP_REMOVE_MEMBER => {
+            let (space, member, role) = decode_membership(reader)?;
+            Ok(Op::RemoveMember(RemoveMember { space, member, role }))
+        }
+        OP_UPDATE_SPACE_METADATA => decode_update_space_metadata(reader),
+        OP_BATCH_CREATE_ENTITIES => Err(DecodeError::MalformedEncoding {
+            context: "BatchCreateEntities holds several ops; use decode_ops_with",
+        }),
+        MIN_EXTENSION_OP_TYPE..=u8::MAX if preserve_unknown => {
+            let len = reader.read_varint("extension_len")? as usize;
+            if len > MAX_BYTES_LEN {
+                return Err(DecodeError::LengthExceedsLimit {
+                    field: "extension",
+                    len,
+                    max: MAX_BYTES_LEN,
+                });
+            }
+            let bytes = reader.read_bytes(len, "extension")?;
+            Ok(Op::Unknown(UnknownOp { tag: op_type, bytes: Cow::Borrowed(bytes) }))
+        }
         _ => Err(DecodeError::InvalidOpType { op_type }),
     }
 }
 
+/// Decodes one op record, appending the ops it holds to `ops`: one op, or
+/// several for a BatchCreateEntities record.
+pub fn decode_ops_with<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries,
+    preserve_unknown: bool,
+    ops: &mut Vec<Op<'a>>,
+) -> Result<(), DecodeError> {
+    if reader.remaining().first() == Some(&OP_BATCH_CREATE_ENTITIES) {
+        let op_type = reader.read_byte("op_type")?;
+        if !dicts.batch_records {
+            return Err(DecodeError::InvalidOpType { op_type });
+        }
+        return decode_batch_create_entities(reader, dicts, ops);
+    }
+    ops.push(decode_op_with(reader, dicts, preserve_unknown)?);
+    Ok(())
+}
+
+fn decode_batch_create_entities<'a>(
+    reader: &mut Reader<'a>,
+    dicts: &WireDictionaries,
+    ops: &mut Vec<Op<'a>>,
+) -> Result<(), DecodeError> {
+    let entity_count = read_entity_count(reader)?;
+    let value_count = read_value_count(reader, "values")?;
+    let layout = read_layout(reader, dicts, value_count)?;
+
+    // Every value takes at least a byte; don't trust the counts for allocation
+    let capacity = match value_count.saturating_mul(entity_count) <= reader.remaining_len() {
+        true => value_count,
+        false => 0,
+    };
+    let mut entities = Vec::with_capacity(entity_count.min(reader.remaining_len() / 16));
+    for _ in 0..entity_count {
+        let id = reader.read_id("entity_id")?;
+        entities.push(CreateEntity { id, values: Vec::with_capacity(capacity) });
+    }
+
+    // Values are stored column by column
+    for &(property, data_type) in &layout {
+        for entity in &mut entities {
+            let clock = read_value_clock(reader, dicts)?;
+            let value = decode_value(reader, data_type, dicts)?;
+            entity.values.push(PropertyValue { property, value, clock });
+        }
+    }
+
+    ops.extend(entities.into_iter().map(Op::CreateEntity));
+    Ok(())
+}
+
+/// Reads the entity count of a BatchCreateEntities record.
+fn read_entity_count(reader: &mut Reader<'_>) -> Result<usize, DecodeError> {
+    let count = reader.read_varint("entity_count")? as usize;
+    if count == 0 {
+        return Err(DecodeError::MalformedEncoding { context: "empty BatchCreateEntities" });
+    }
+    if count > MAX_OPS_PER_EDIT {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "entities",
+            len: count,
+            max: MAX_OPS_PER_EDIT,
+        });
+    }
+    Ok(count)
+}
+
+/// Reads the property layout of a BatchCreateEntities record.
+fn read_layout(
+    reader: &mut Reader<'_>,
+    dicts: &WireDictionaries,
+    value_count: usize,
+) -> Result<Vec<(Id, DataType)>, DecodeError> {
+    let mut layout = Vec::with_capacity(value_count.min(reader.remaining_len()));
+    for _ in 0..value_count {
+        let index = reader.read_varint("property")? as usize;
+        let Some(&entry) = dicts.properties.get(index) else {
+            return Err(DecodeError::IndexOutOfBounds {
+                dict: "properties",
+                index,
+                size: dicts.properties.len(),
+            });
+        };
+        layout.push(entry);
+    }
+    Ok(layout)
+}
+
 fn decode_create_entity<'a>(
     reader: &mut Reader<'a>,
     dicts: &WireDictionaries,
@@ -96,7 +242,7 @@ fn decode_create_entity<'a>(
         });
     }
 
-    let mut values = Vec::with_capacity(value_count);
+    let mut values = Vec::with_capacity(value_count.min(reader.remaining_len()));
     for _ in 0..value_count {
         values.push(decode_property_value(reader, dicts)?);
     }
@@ -104,6 +250,16 @@ fn decode_create_entity<'a>(
     Ok(Op::CreateEntity(CreateEntity { id, values }))
 }
 
+/// Returns the UpdateEntity flag bits that must be 0: remove lists are only
+/// allowed when the edit header announces them.
+fn update_entity_reserved_mask(dicts: &WireDictionaries) -> u8 {
+    if dicts.remove_values {
+        UPDATE_ENTITY_RESERVED_MASK
+    } else {
+        UPDATE_ENTITY_RESERVED_MASK | FLAG_HAS_REMOVE_VALUES
+    }
+}
+
 fn decode_update_entity<'a>(
     reader: &mut Reader<'a>,
     dicts: &WireDictionaries,
@@ -121,7 +277,7 @@ fn decode_update_entity<'a>(
     let flags = reader.read_byte("update_flags")?;
 
     // Check reserved bits
-    if flags & UPDATE_ENTITY_RESERVED_MASK != 0 {
+    if flags & update_entity_reserved_mask(dicts) != 0 {
         return Err(DecodeError::ReservedBitsSet {
             context: "UpdateEntity flags",
         });
@@ -185,6 +341,10 @@ fn decode_update_entity<'a>(
         }
     }
 
+    if flags & FLAG_HAS_REMOVE_VALUES != 0 {
+        update.remove_values = reader.read_id_vec(MAX_VALUES_PER_ENTITY, "remove_values")?;
+    }
+
     Ok(Op::UpdateEntity(update))
 }
 
@@ -521,6 +681,208 @@ fn decode_create_value_ref<'a>(
     }))
 }
 
+fn decode_create_space<'a>(reader: &mut Reader<'a>) -> Result<Op<'a>, DecodeError> {
+    let id = reader.read_id("space_id")?;
+    let name = reader.read_str(MAX_STRING_LEN, "space_name")?;
+    Ok(Op::CreateSpace(CreateSpace { id, name: Cow::Borrowed(name) }))
+}
+
+/// Decodes the shared body of AddMember and RemoveMember.
+fn decode_membership(reader: &mut Reader<'_>) -> Result<(Id, Id, MemberRole), DecodeError> {
+    let space = reader.read_id("space_id")?;
+    let member = reader.read_id("member_id")?;
+    let role = reader.read_byte("member_role")?;
+    let role = MemberRole::from_u8(role)
+        .ok_or(DecodeError::MalformedEncoding { context: "unknown member role" })?;
+    Ok((space, member, role))
+}
+
+fn decode_update_space_metadata<'a>(reader: &mut Reader<'a>) -> Result<Op<'a>, DecodeError> {
+    let space = reader.read_id("space_id")?;
+    let flags = reader.read_byte("space_flags")?;
+    if flags & UPDATE_SPACE_RESERVED_MASK != 0 {
+        return Err(DecodeError::ReservedBitsSet {
+            context: "UpdateSpaceMetadata flags",
+        });
+    }
+    let name = if flags & FLAG_HAS_NAME != 0 {
+        Some(Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "space_name")?))
+    } else {
+        None
+    };
+    let description = if flags & FLAG_HAS_DESCRIPTION != 0 {
+        Some(Cow::Borrowed(reader.read_str(MAX_STRING_LEN, "space_description")?))
+    } else {
+        None
+    };
+    Ok(Op::UpdateSpaceMetadata(UpdateSpaceMetadata { space, name, description }))
+}
+
+/// Skips over an op without decoding it, returning its kind.
+///
+/// Checks only what is needed to find the op's end: the op type, reserved
+/// flag bits, length limits, and the property indices that select each
+/// value's layout. Everything else is left to [`decode_op`].
+pub fn skip_op(reader: &mut Reader<'_>, dicts: &WireDictionaries) -> Result<OpKind, DecodeError> {
+    skip_op_record(reader, dicts).map(|(kind, _)| kind)
+}
+
+/// Skips over an op record, returning its kind and the number of ops it
+/// holds (more than one only for BatchCreateEntities).
+pub(crate) fn skip_op_record(
+    reader: &mut Reader<'_>,
+    dicts: &WireDictionaries,
+) -> Result<(OpKind, usize), DecodeError> {
+    let op_type = reader.read_byte("op_type")?;
+    let kind = OpKind::from_u8(op_type)
+        .filter(|&kind| kind != OpKind::BatchCreateEntities || dicts.batch_records)
+        .ok_or(DecodeError::InvalidOpType { op_type })?;
+
+    match kind {
+        OpKind::CreateEntity => {
+            reader.read_id("entity_id")?;
+            let count = read_value_count(reader, "values")?;
+            for _ in 0..count {
+                skip_property_value(reader, dicts)?;
+            }
+        }
+        OpKind::UpdateEntity => {
+            reader.read_varint("entity_id")?;
+            let flags = reader.read_byte("update_flags")?;
+            if flags & update_entity_reserved_mask(dicts) != 0 {
+                return Err(DecodeError::ReservedBitsSet {
+                    context: "UpdateEntity flags",
+                });
+            }
+            if flags & FLAG_HAS_SET_PROPERTIES != 0 {
+                for _ in 0..read_value_count(reader, "set_properties")? {
+                    skip_property_value(reader, dicts)?;
+                }
+            }
+            if flags & FLAG_HAS_UNSET_VALUES != 0 {
+                for _ in 0..read_value_count(reader, "unset_values")? {
+                    reader.read_varint("property")?;
+                    reader.read_varint("unset.language")?;
+                }
+            }
+            if flags & FLAG_HAS_REMOVE_VALUES != 0 {
+                let count = read_value_count(reader, "remove_values")?;
+                reader.read_bytes(count * 16, "remove_values")?;
+            }
+        }
+        OpKind::DeleteEntity | OpKind::RestoreEntity => {
+            reader.read_varint("entity_id")?;
+        }
+        OpKind::DeleteRelation | OpKind::RestoreRelation => {
+            reader.read_varint("relation_id")?;
+        }
+        OpKind::CreateRelation => {
+            reader.read_id("relation_id")?;
+            reader.read_varint("relation_type")?;
+            let flags = reader.read_byte("relation_flags")?;
+            if flags & FLAG_FROM_IS_VALUE_REF != 0 {
+                reader.read_id("from")?;
+            } else {
+                reader.read_varint("from")?;
+            }
+            if flags & FLAG_TO_IS_VALUE_REF != 0 {
+                reader.read_id("to")?;
+            } else {
+                reader.read_varint("to")?;
+            }
+            let optional_ids = flags
+                & (FLAG_HAS_FROM_SPACE
+                    | FLAG_HAS_FROM_VERSION
+                    | FLAG_HAS_TO_SPACE
+                    | FLAG_HAS_TO_VERSION
+                    | FLAG_HAS_ENTITY);
+            reader.read_bytes(16 * optional_ids.count_ones() as usize, "relation_ids")?;
+            if flags & FLAG_HAS_POSITION != 0 {
+                decode_position(reader)?;
+            }
+        }
+        OpKind::UpdateRelation => {
+            reader.read_varint("relation_id")?;
+            let set_flags = reader.read_byte("set_flags")?;
+            let unset_flags = reader.read_byte("unset_flags")?;
+            if set_flags & UPDATE_SET_RESERVED_MASK != 0 {
+                return Err(DecodeError::ReservedBitsSet {
+                    context: "UpdateRelation set_flags",
+                });
+            }
+            if unset_flags & UPDATE_UNSET_RESERVED_MASK != 0 {
+                return Err(DecodeError::ReservedBitsSet {
+                    context: "UpdateRelation unset_flags",
+                });
+            }
+            let set_ids = set_flags & !UPDATE_SET_POSITION;
+            reader.read_bytes(16 * set_ids.count_ones() as usize, "relation_ids")?;
+            if set_flags & UPDATE_SET_POSITION != 0 {
+                decode_position(reader)?;
+            }
+        }
+        OpKind::CreateSpace => {
+            decode_create_space(reader)?;
+        }
+        OpKind::AddMember | OpKind::RemoveMember => {
+            decode_membership(reader)?;
+        }
+        OpKind::UpdateSpaceMetadata => {
+            decode_update_space_metadata(reader)?;
+        }
+        OpKind::BatchCreateEntities => {
+            let entity_count = read_entity_count(reader)?;
+            let value_count = read_value_count(reader, "values")?;
+            let layout = read_layout(reader, dicts, value_count)?;
+            reader.read_bytes(entity_count.saturating_mul(16), "entity_ids")?;
+            let string_table = dicts.strings.is_some();
+            for &(_, data_type) in &layout {
+                for _ in 0..entity_count {
+                    read_value_clock(reader, dicts)?;
+                    skip_value_in(reader, data_type, string_table)?;
+                }
+            }
+            return Ok((kind, entity_count));
+        }
+        OpKind::Extension => {
+            let len = reader.read_varint("extension_len")? as usize;
+            reader.read_bytes(len, "extension")?;
+        }
+        OpKind::CreateValueRef => {
+            reader.read_id("value_ref_id")?;
+            reader.read_varint("entity")?;
+            reader.read_varint("property")?;
+            let flags = reader.read_byte("value_ref_flags")?;
+            if flags & CREATE_VALUE_REF_RESERVED_MASK != 0 {
+                return Err(DecodeError::ReservedBitsSet {
+                    context: "CreateValueRef flags",
+                });
+            }
+            if flags & FLAG_HAS_LANGUAGE != 0 {
+                reader.read_varint("language")?;
+            }
+            if flags & FLAG_HAS_SPACE != 0 {
+                reader.read_id("space")?;
+            }
+        }
+    }
+
+    Ok((kind, 1))
+}
+
+/// Reads a value or unset count, enforcing [`MAX_VALUES_PER_ENTITY`].
+fn read_value_count(reader: &mut Reader<'_>, field: &'static str) -> Result<usize, DecodeError> {
+    let count = reader.read_varint(field)? as usize;
+    if count > MAX_VALUES_PER_ENTITY {
+        return Err(DecodeError::LengthExceedsLimit {
+            field,
+            len: count,
+            max: MAX_VALUES_PER_ENTITY,
+        });
+    }
+    Ok(count)
+}
+
 // =============================================================================
 // ENCODING
 // =============================================================================
@@ -533,7 +895,7 @@ pub fn encode_op(
     writer: &mut Writer,
     op: &Op<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::hash::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     match op {
         Op::CreateEntity(ce) => encode_create_entity(writer, ce, dict_builder, property_types),
@@ -545,14 +907,227 @@ pub fn encode_op(
         Op::DeleteRelation(dr) => encode_delete_relation(writer, dr, dict_builder),
         Op::RestoreRelation(rr) => encode_restore_relation(writer, rr, dict_builder),
         Op::CreateValueRef(cvr) => encode_create_value_ref(writer, cvr, dict_builder),
+        Op::CreateSpace(cs) => {
+            writer.write_byte(OP_CREATE_SPACE);
+            writer.write_id(&cs.id);
+            writer.write_string(&cs.name);
+            Ok(())
+        }
+        Op::AddMember(AddMember { space, member, role }) => {
+            encode_membership(writer, OP_ADD_MEMBER, space, member, *role);
+            Ok(())
+        }
+        Op::RemoveMember(RemoveMember { space, member, role }) => {
+            encode_membership(writer, OP_REMOVE_MEMBER, space, member, *role);
+            Ok(())
+        }
+        Op::UpdateSpaceMetadata(usm) => {
+            encode_update_space_metadata(writer, usm);
+            Ok(())
+        }
+        Op::Unknown(op) => encode_unknown(writer, op),
     }
 }
 
+fn encode_membership(
+    writer: &mut Writer,
+    op_type: u8,
+    space: &Id,
+    member: &Id,
+    role: MemberRole,
+) {
+    writer.write_byte(op_type);
+    writer.write_id(space);
+    writer.write_id(member);
+    writer.write_byte(role as u8);
+}
+
+fn encode_update_space_metadata(writer: &mut Writer, usm: &UpdateSpaceMetadata<'_>) {
+    writer.write_byte(OP_UPDATE_SPACE_METADATA);
+    writer.write_id(&usm.space);
+    let mut flags = 0u8;
+    if usm.name.is_some() {
+        flags |= FLAG_HAS_NAME;
+    }
+    if usm.description.is_some() {
+        flags |= FLAG_HAS_DESCRIPTION;
+    }
+    writer.write_byte(flags);
+    if let Some(name) = &usm.name {
+        writer.write_string(name);
+    }
+    if let Some(description) = &usm.description {
+        writer.write_string(description);
+    }
+}
+
+fn encode_unknown(writer: &mut Writer, op: &UnknownOp<'_>) -> Result<(), EncodeError> {
+    if op.tag < MIN_EXTENSION_OP_TYPE {
+        return Err(EncodeError::InvalidInput {
+            context: "unknown op type below extension range",
+        });
+    }
+    writer.write_byte(op.tag);
+    writer.write_bytes_prefixed(&op.bytes);
+    Ok(())
+}
+
+/// Registers every dictionary entry an op will reference, in the same order
+/// `encode_op` would add them.
+///
+/// Running this over all ops first yields a complete builder whose indices
+/// match single-pass encoding, so ops can then be encoded independently.
+pub fn collect_op_ids(op: &Op<'_>, dict_builder: &mut DictionaryBuilder) {
+    fn collect_value(pv: &PropertyValue<'_>, dict_builder: &mut DictionaryBuilder) {
+        dict_builder.add_property(pv.property, pv.value.data_type());
+        match &pv.value {
+            Value::Int64 { unit, .. }
+            | Value::Float64 { unit, .. }
+            | Value::Decimal { unit, .. } => {
+                dict_builder.add_unit(*unit);
+            }
+            Value::Text { language, .. } => {
+                dict_builder.add_language(*language);
+            }
+            _ => {}
+        }
+    }
+
+    match op {
+        Op::CreateEntity(ce) => {
+            for pv in &ce.values {
+                collect_value(pv, dict_builder);
+            }
+        }
+        Op::UpdateEntity(ue) => {
+            dict_builder.add_object(ue.id);
+            for pv in &ue.set_properties {
+                collect_value(pv, dict_builder);
+            }
+            for unset in &ue.unset_values {
+                dict_builder.add_property_placeholder(unset.property);
+                if let UnsetLanguage::Specific(lang_id) = &unset.language {
+                    dict_builder.add_language(Some(*lang_id));
+                }
+            }
+        }
+        Op::CreateRelation(cr) => {
+            dict_builder.add_relation_type(cr.relation_type);
+            if !cr.from_is_value_ref {
+                dict_builder.add_object(cr.from);
+            }
+            if !cr.to_is_value_ref {
+                dict_builder.add_object(cr.to);
+            }
+        }
+        Op::DeleteEntity(DeleteEntity { id })
+        | Op::RestoreEntity(RestoreEntity { id })
+        | Op::DeleteRelation(DeleteRelation { id })
+        | Op::RestoreRelation(RestoreRelation { id }) => {
+            dict_builder.add_object(*id);
+        }
+        Op::UpdateRelation(ur) => {
+            dict_builder.add_object(ur.id);
+        }
+        Op::CreateValueRef(cvr) => {
+            dict_builder.add_object(cvr.entity);
+            if cvr.language.is_some() {
+                dict_builder.add_property(cvr.property, DataType::Text);
+            } else {
+                dict_builder.add_property_placeholder(cvr.property);
+            }
+            if let Some(lang_id) = cvr.language {
+                dict_builder.add_language(Some(lang_id));
+            }
+        }
+        // Space ops carry raw IDs, and extension payloads never reference
+        // the dictionaries
+        Op::CreateSpace(_)
+        | Op::AddMember(_)
+        | Op::RemoveMember(_)
+        | Op::UpdateSpaceMetadata(_)
+        | Op::Unknown(_) => {}
+    }
+}
+
+/// Returns how many ops at the start of `ops` can be written as one
+/// BatchCreateEntities record: CreateEntity ops whose values set the same
+/// properties, with the same types, in the same order. Returns 0 if the
+/// first op is not a CreateEntity.
+pub(crate) fn create_entity_run(ops: &[Op<'_>]) -> usize {
+    let Some(Op::CreateEntity(first)) = ops.first() else {
+        return 0;
+    };
+    let same_layout = |ce: &CreateEntity<'_>| {
+        ce.values.len() == first.values.len()
+            && ce.values.iter().zip(&first.values).all(|(a, b)| {
+                a.property == b.property && a.value.data_type() == b.value.data_type()
+            })
+    };
+    let rest = ops[1..]
+        .iter()
+        .take_while(|op| matches!(op, Op::CreateEntity(ce) if same_layout(ce)))
+        .count();
+    1 + rest
+}
+
+/// Encodes a run of CreateEntity ops found by [`create_entity_run`] as one
+/// BatchCreateEntities record: the property layout once, the entity IDs,
+/// then the values column by column.
+pub(crate) fn encode_batch_create_entities(
+    writer: &mut Writer,
+    ops: &[Op<'_>],
+    dict_builder: &mut DictionaryBuilder,
+    property_types: &crate::hash::FxHashMap<crate::model::Id, DataType>,
+) -> Result<(), EncodeError> {
+    let entities: Vec<&CreateEntity<'_>> = ops
+        .iter()
+        .filter_map(|op| match op {
+            Op::CreateEntity(ce) => Some(ce),
+            _ => None,
+        })
+        .collect();
+    debug_assert_eq!(entities.len(), ops.len());
+
+    // Add dictionary entries in op order, as collect_op_ids does, so that
+    // chunked encoding assigns the same indices
+    for op in ops {
+        collect_op_ids(op, dict_builder);
+    }
+
+    writer.write_byte(OP_BATCH_CREATE_ENTITIES);
+    writer.write_varint(entities.len() as u64);
+    let layout = &entities[0].values;
+    writer.write_varint(layout.len() as u64);
+    let mut indices = Vec::with_capacity(layout.len());
+    for pv in layout {
+        let data_type = property_types.get(&pv.property)
+            .copied()
+            .unwrap_or_else(|| pv.value.data_type());
+        let prop_index = dict_builder.add_property(pv.property, data_type);
+        writer.write_varint(prop_index as u64);
+        indices.push(prop_index);
+    }
+    for ce in &entities {
+        writer.write_id(&ce.id);
+    }
+    for (column, &prop_index) in indices.iter().enumerate() {
+        for ce in &entities {
+            let pv = &ce.values[column];
+            check_property_type(pv, dict_builder, prop_index)?;
+            write_value_clock(writer, pv, dict_builder)?;
+            encode_value(writer, &pv.value, dict_builder)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn encode_create_entity(
     writer: &mut Writer,
     ce: &CreateEntity<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::hash::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     writer.write_byte(OP_CREATE_ENTITY);
     writer.write_id(&ce.id);
@@ -572,7 +1147,7 @@ fn encode_update_entity(
     writer: &mut Writer,
     ue: &UpdateEntity<'_>,
     dict_builder: &mut DictionaryBuilder,
-    property_types: &rustc_hash::FxHashMap<crate::model::Id, DataType>,
+    property_types: &crate::hash::FxHashMap<crate::model::Id, DataType>,
 ) -> Result<(), EncodeError> {
     writer.write_byte(OP_UPDATE_ENTITY);
 
@@ -586,6 +1161,9 @@ fn encode_update_entity(
     if !ue.unset_values.is_empty() {
         flags |= FLAG_HAS_UNSET_VALUES;
     }
+    if !ue.remove_values.is_empty() {
+        flags |= FLAG_HAS_REMOVE_VALUES;
+    }
     writer.write_byte(flags);
 
     if !ue.set_properties.is_empty() {
@@ -602,7 +1180,7 @@ fn encode_update_entity(
         writer.write_varint(ue.unset_values.len() as u64);
         for unset in &ue.unset_values {
             // We need the data type to add to dictionary, use a placeholder
-            let idx = dict_builder.add_property(unset.property, DataType::Bool);
+            let idx = dict_builder.add_property_placeholder(unset.property);
             writer.write_varint(idx as u64);
             // Language encoding: 0xFFFFFFFF = all, 0 = English, 1+ = specific language
             let lang_value: u32 = match &unset.language {
@@ -617,6 +1195,10 @@ fn encode_update_entity(
         }
     }
 
+    if !ue.remove_values.is_empty() {
+        writer.write_id_vec(&ue.remove_values);
+    }
+
     Ok(())
 }
 
@@ -719,7 +1301,7 @@ fn encode_create_relation(
     }
 
     if let Some(pos) = &cr.position {
-        validate_position(pos)?;
+        check_position(pos, dict_builder.profile())?;
         writer.write_string(pos);
     }
 
@@ -782,7 +1364,7 @@ fn encode_update_relation(
         writer.write_id(version);
     }
     if let Some(pos) = &ur.position {
-        validate_position(pos)?;
+        check_position(pos, dict_builder.profile())?;
         writer.write_string(pos);
     }
 
@@ -822,11 +1404,23 @@ fn encode_create_value_ref(
     let entity_index = dict_builder.add_object(cvr.entity);
     writer.write_varint(entity_index as u64);
 
-    // For CreateValueRef, we need to add the property to the dictionary.
-    // Use DataType::Text as a placeholder if language is present, otherwise Bool.
-    // The actual data type will be determined by the property's declaration elsewhere.
-    let data_type = if cvr.language.is_some() { DataType::Text } else { DataType::Bool };
-    let property_index = dict_builder.add_property(cvr.property, data_type);
+    // A language implies a TEXT property. Otherwise the type comes from any
+    // value of the property in the same edit, falling back to a placeholder.
+    let property_index = if cvr.language.is_some() {
+        let index = dict_builder.add_property(cvr.property, DataType::Text);
+        match dict_builder.property_type(index) {
+            Some(declared) if declared != DataType::Text => {
+                return Err(EncodeError::PropertyTypeConflict {
+                    property: cvr.property,
+                    declared,
+                    actual: DataType::Text,
+                });
+            }
+            _ => index,
+        }
+    } else {
+        dict_builder.add_property_placeholder(cvr.property)
+    };
     writer.write_varint(property_index as u64);
 
     let mut flags = 0u8;
@@ -857,7 +1451,9 @@ fn encode_property_value(
     data_type: DataType,
 ) -> Result<(), EncodeError> {
     let prop_index = dict_builder.add_property(pv.property, data_type);
+    crate::codec::value::check_property_type(pv, dict_builder, prop_index)?;
     writer.write_varint(prop_index as u64);
+    crate::codec::value::write_value_clock(writer, pv, dict_builder)?;
     crate::codec::value::encode_value(writer, &pv.value, dict_builder)?;
     Ok(())
 }
@@ -879,11 +1475,12 @@ mod tests {
                     value: Cow::Owned("test".to_string()),
                     language: None,
                 },
+                clock: None,
             }],
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let mut property_types = rustc_hash::FxHashMap::default();
+        let mut property_types = crate::hash::FxHashMap::default();
         property_types.insert([2u8; 16], DataType::Text);
 
         let mut writer = Writer::new();
@@ -932,7 +1529,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -980,7 +1577,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1023,7 +1620,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1069,7 +1666,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1103,7 +1700,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1135,7 +1732,7 @@ mod tests {
         });
 
         let mut dict_builder = DictionaryBuilder::new();
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1171,7 +1768,7 @@ mod tests {
 
         let mut dict_builder = DictionaryBuilder::new();
         dict_builder.add_object([1u8; 16]); // Pre-add the relation ID
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1217,7 +1814,7 @@ mod tests {
 
         let mut dict_builder = DictionaryBuilder::new();
         dict_builder.add_object([1u8; 16]); // Pre-add the relation ID
-        let property_types = rustc_hash::FxHashMap::default();
+        let property_types = crate::hash::FxHashMap::default();
 
         let mut writer = Writer::new();
         encode_op(&mut writer, &op, &mut dict_builder, &property_types).unwrap();
@@ -1239,4 +1836,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_space_ops_roundtrip() {
+        let space = [1u8; 16];
+        let ops = [
+            Op::CreateSpace(CreateSpace { id: space, name: Cow::Borrowed("Crypto") }),
+            Op::AddMember(AddMember { space, member: [2u8; 16], role: MemberRole::Editor }),
+            Op::RemoveMember(RemoveMember { space, member: [3u8; 16], role: MemberRole::Member }),
+            Op::UpdateSpaceMetadata(UpdateSpaceMetadata {
+                space,
+                name: None,
+                description: Some(Cow::Borrowed("News")),
+            }),
+        ];
+
+        let mut dict_builder = DictionaryBuilder::new();
+        let property_types = crate::hash::FxHashMap::default();
+        let mut writer = Writer::new();
+        for op in &ops {
+            encode_op(&mut writer, op, &mut dict_builder, &property_types).unwrap();
+        }
+
+        let dicts = dict_builder.build();
+        let mut reader = Reader::new(writer.as_bytes());
+        for op in &ops {
+            assert_eq!(&decode_op(&mut reader, &dicts).unwrap(), op);
+        }
+
+        let mut reader = Reader::new(writer.as_bytes());
+        for op in &ops {
+            assert_eq!(skip_op(&mut reader, &dicts).unwrap(), op.kind());
+        }
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_space_ops_reject_bad_encodings() {
+        let dicts = WireDictionaries::default();
+
+        let mut bytes = vec![OP_ADD_MEMBER];
+        bytes.extend_from_slice(&[1u8; 32]);
+        bytes.push(2);
+        let result = decode_op(&mut Reader::new(&bytes), &dicts);
+        assert!(matches!(result, Err(DecodeError::MalformedEncoding { .. })));
+
+        let mut bytes = vec![OP_UPDATE_SPACE_METADATA];
+        bytes.extend_from_slice(&[1u8; 16]);
+        bytes.push(0x04);
+        let result = decode_op(&mut Reader::new(&bytes), &dicts);
+        assert!(matches!(result, Err(DecodeError::ReservedBitsSet { .. })));
+    }
 }
@@ -1,10 +1,18 @@
 //! Primitive encoding/decoding for GRC-20 binary format.
 //!
 //! Implements varint, signed varint (zigzag), and basic types.
+//!
+//! Batched varint decoding and the float32 NaN scan have SSE2 fast paths
+//! on x86_64; other targets use word-at-a-time scalar versions.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
 
 use crate::error::DecodeError;
 use crate::limits::MAX_VARINT_BYTES;
 use crate::model::Id;
+use crate::validate::ValidationProfile;
 
 // =============================================================================
 // DECODING
@@ -18,12 +26,52 @@ use crate::model::Id;
 pub struct Reader<'a> {
     data: &'a [u8],
     pos: usize,
+    validate_embeddings: bool,
+    verify_checksum: bool,
+    profile: ValidationProfile,
 }
 
 impl<'a> Reader<'a> {
     /// Creates a new reader from a byte slice.
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data, pos: 0 }
+        Self {
+            data,
+            pos: 0,
+            validate_embeddings: true,
+            verify_checksum: true,
+            profile: ValidationProfile::default(),
+        }
+    }
+
+    /// Sets whether float32 embeddings are scanned for NaN (on by default).
+    pub fn set_validate_embeddings(&mut self, validate: bool) {
+        self.validate_embeddings = validate;
+    }
+
+    /// Returns true if float32 embeddings are scanned for NaN.
+    pub fn validates_embeddings(&self) -> bool {
+        self.validate_embeddings
+    }
+
+    /// Sets whether an edit's checksum trailer is verified (on by default).
+    /// The trailer is split off either way.
+    pub fn set_verify_checksum(&mut self, verify: bool) {
+        self.verify_checksum = verify;
+    }
+
+    /// Returns true if an edit's checksum trailer is verified.
+    pub fn verifies_checksum(&self) -> bool {
+        self.verify_checksum
+    }
+
+    /// Sets which value checks are hard errors.
+    pub fn set_profile(&mut self, profile: ValidationProfile) {
+        self.profile = profile;
+    }
+
+    /// Returns the validation profile values are decoded under.
+    pub fn profile(&self) -> ValidationProfile {
+        self.profile
     }
 
     /// Returns the current position in the data.
@@ -41,6 +89,21 @@ impl<'a> Reader<'a> {
         self.data.len() - self.pos
     }
 
+    /// Splits the last `n` bytes off the data, returning them. The reader
+    /// then stops before them.
+    pub fn split_trailer(
+        &mut self,
+        n: usize,
+        context: &'static str,
+    ) -> Result<&'a [u8], DecodeError> {
+        if self.remaining_len() < n {
+            return Err(DecodeError::UnexpectedEof { context });
+        }
+        let (data, trailer) = self.data.split_at(self.data.len() - n);
+        self.data = data;
+        Ok(trailer)
+    }
+
     /// Returns true if all data has been consumed.
     pub fn is_empty(&self) -> bool {
         self.pos >= self.data.len()
@@ -79,6 +142,47 @@ impl<'a> Reader<'a> {
     /// Reads an unsigned varint (LEB128).
     #[inline]
     pub fn read_varint(&mut self, context: &'static str) -> Result<u64, DecodeError> {
+        // Fast path: a single byte (most indices and lengths)
+        if let Some(&byte) = self.data.get(self.pos) {
+            if byte & 0x80 == 0 {
+                self.pos += 1;
+                return Ok(byte as u64);
+            }
+        }
+        self.read_varint_slow(context)
+    }
+
+    /// Reads `out.len()` consecutive unsigned varints.
+    ///
+    /// Runs of 16 single-byte varints (small indices and counts) are
+    /// checked and widened together.
+    pub fn read_varints(
+        &mut self,
+        out: &mut [u64],
+        context: &'static str,
+    ) -> Result<(), DecodeError> {
+        let mut i = 0;
+        while i < out.len() {
+            if out.len() - i >= 16 {
+                if let Some(bytes) = self.data.get(self.pos..self.pos + 16) {
+                    if all_single_byte(bytes) {
+                        for (slot, &byte) in out[i..i + 16].iter_mut().zip(bytes) {
+                            *slot = byte as u64;
+                        }
+                        self.pos += 16;
+                        i += 16;
+                        continue;
+                    }
+                }
+            }
+            out[i] = self.read_varint(context)?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Reads a varint byte by byte, checking length and overflow.
+    fn read_varint_slow(&mut self, context: &'static str) -> Result<u64, DecodeError> {
         let mut result: u64 = 0;
         let mut shift = 0;
 
@@ -129,7 +233,7 @@ impl<'a> Reader<'a> {
         }
         let bytes = self.read_bytes(len, field)?;
         // Validate UTF-8 on borrowed slice, then allocate once (avoids intermediate Vec)
-        std::str::from_utf8(bytes)
+        core::str::from_utf8(bytes)
             .map(|s| s.to_string())
             .map_err(|_| DecodeError::InvalidUtf8 { field })
     }
@@ -150,7 +254,18 @@ impl<'a> Reader<'a> {
             });
         }
         let bytes = self.read_bytes(len, field)?;
-        std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { field })
+        core::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { field })
+    }
+
+    /// Returns the UTF-8 string at `range` of the data, independent of the
+    /// read position. Used for string table entries recorded earlier.
+    pub(crate) fn str_at(
+        &self,
+        range: Range<usize>,
+        field: &'static str,
+    ) -> Result<&'a str, DecodeError> {
+        let bytes = self.data.get(range).ok_or(DecodeError::UnexpectedEof { context: field })?;
+        core::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8 { field })
     }
 
     /// Reads a length-prefixed byte array.
@@ -205,7 +320,7 @@ impl<'a> Reader<'a> {
                 max: max_len,
             });
         }
-        let mut ids = Vec::with_capacity(count);
+        let mut ids = Vec::with_capacity(count.min(self.remaining_len() / 16));
         for _ in 0..count {
             ids.push(self.read_id(field)?);
         }
@@ -256,6 +371,11 @@ impl Writer {
         self.buf.is_empty()
     }
 
+    /// Discards the written bytes, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
     /// Writes a single byte.
     #[inline]
     pub fn write_byte(&mut self, byte: u8) {
@@ -326,10 +446,99 @@ impl Writer {
     }
 }
 
+// =============================================================================
+// BULK SCANS
+// =============================================================================
+
+/// Returns true if none of the 16 bytes has its continuation bit set.
+#[inline]
+fn all_single_byte(bytes: &[u8]) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        sse2::all_below_0x80(bytes)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let (low, high) = bytes.split_at(8);
+        let low = u64::from_le_bytes(low.try_into().unwrap());
+        let high = u64::from_le_bytes(high.try_into().unwrap());
+        (low | high) & 0x8080_8080_8080_8080 == 0
+    }
+}
+
+/// Returns true if any little-endian f32 in `data` is NaN.
+///
+/// Trailing bytes that don't form a whole f32 are ignored. The scan does
+/// not stop early, so it takes the same time whether or not a NaN is found.
+pub fn has_f32_nan(data: &[u8]) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    let (found, data) = {
+        let split = data.len() - data.len() % 16;
+        (sse2::has_f32_nan(&data[..split]), &data[split..])
+    };
+    #[cfg(not(target_arch = "x86_64"))]
+    let found = false;
+
+    // Two lanes per u64: a lane is NaN if its magnitude is above infinity
+    // (0x7F80_0000), which carries into the lane's top bit when
+    // 0x007F_FFFF is added
+    let mut chunks = data.chunks_exact(8);
+    let mut lanes = 0u64;
+    for chunk in &mut chunks {
+        let magnitude = u64::from_le_bytes(chunk.try_into().unwrap()) & 0x7FFF_FFFF_7FFF_FFFF;
+        lanes |= magnitude + 0x007F_FFFF_007F_FFFF;
+    }
+    found
+        || lanes & 0x8000_0000_8000_0000 != 0
+        || chunks
+            .remainder()
+            .chunks_exact(4)
+            .any(|c| f32::from_le_bytes(c.try_into().unwrap()).is_nan())
+}
+
+#[cfg(target_arch = "x86_64")]
+mod sse2 {
+    use core::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_cmpgt_epi32, _mm_loadu_si128, _mm_movemask_epi8,
+        _mm_or_si128, _mm_set1_epi32, _mm_setzero_si128,
+    };
+
+    /// See [`super::has_f32_nan`]; `data.len()` must be a multiple of 16.
+    pub(super) fn has_f32_nan(data: &[u8]) -> bool {
+        debug_assert_eq!(data.len() % 16, 0);
+        // SAFETY: SSE2 is part of the x86_64 baseline, and each load reads
+        // a 16-byte chunk
+        unsafe {
+            let magnitude = _mm_set1_epi32(0x7FFF_FFFF);
+            let infinity = _mm_set1_epi32(0x7F80_0000);
+            let mut found = _mm_setzero_si128();
+            for chunk in data.chunks_exact(16) {
+                let lanes = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+                let nan = _mm_cmpgt_epi32(_mm_and_si128(lanes, magnitude), infinity);
+                found = _mm_or_si128(found, nan);
+            }
+            _mm_movemask_epi8(found) != 0
+        }
+    }
+
+    /// Returns true if none of the 16 bytes has its top bit set.
+    pub(super) fn all_below_0x80(bytes: &[u8]) -> bool {
+        assert_eq!(bytes.len(), 16);
+        // SAFETY: SSE2 is part of the x86_64 baseline, and `bytes` is 16 long
+        unsafe { _mm_movemask_epi8(_mm_loadu_si128(bytes.as_ptr() as *const __m128i)) == 0 }
+    }
+}
+
 // =============================================================================
 // ZIGZAG ENCODING
 // =============================================================================
 
+/// Returns the number of bytes [`Writer::write_varint`] writes for `value`.
+#[inline]
+pub fn varint_len(value: u64) -> usize {
+    (64 - value.max(1).leading_zeros() as usize).div_ceil(7)
+}
+
 /// Encodes a signed integer using zigzag encoding.
 ///
 /// Maps negative numbers to odd positive numbers:
@@ -420,6 +629,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_f64_roundtrip() {
         let test_values = [0.0, 1.0, -1.0, f64::INFINITY, f64::NEG_INFINITY, 3.14159];
 
@@ -433,6 +643,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_varint_fast_paths() {
+        // Every length, with and without enough trailing bytes for a 16-byte run
+        let mut values = vec![0u64, u64::MAX];
+        values.extend((1..64).map(|shift| (1u64 << shift) - 1));
+        values.extend((1..64).map(|shift| 1u64 << shift));
+        for v in values {
+            let mut writer = Writer::new();
+            writer.write_varint(v);
+            let len = writer.len();
+            for padding in [0, 16] {
+                let mut bytes = writer.as_bytes().to_vec();
+                bytes.resize(len + padding, 0xFF);
+                let mut reader = Reader::new(&bytes);
+                assert_eq!(reader.read_varint("test").unwrap(), v);
+                assert_eq!(reader.position(), len);
+                let mut out = [0u64];
+                let mut reader = Reader::new(&bytes);
+                reader.read_varints(&mut out, "test").unwrap();
+                assert_eq!((out[0], reader.position()), (v, len));
+            }
+        }
+
+        // Non-minimal encodings decode as before
+        let mut out = [0u64];
+        let mut reader = Reader::new(&[0x81, 0x80, 0x00, 0, 0, 0, 0, 0, 0]);
+        reader.read_varints(&mut out, "test").unwrap();
+        assert_eq!((out[0], reader.position()), (1, 3));
+
+        let mut writer = Writer::new();
+        let values: Vec<u64> =
+            (0..100).map(|i| if i % 37 == 0 { 1 << (i % 60) } else { i }).collect();
+        for &v in &values {
+            writer.write_varint(v);
+        }
+        let mut out = vec![0u64; values.len()];
+        let mut reader = Reader::new(writer.as_bytes());
+        reader.read_varints(&mut out, "test").unwrap();
+        assert_eq!(out, values);
+        assert!(reader.is_empty());
+        assert!(reader.read_varints(&mut [0u64; 1], "test").is_err());
+    }
+
+    #[test]
+    fn test_has_f32_nan() {
+        for len in [0, 1, 3, 4, 5, 8, 16, 17, 31] {
+            let mut data: Vec<f32> = (0..len).map(|i| i as f32 - 8.0).collect();
+            if len > 1 {
+                data[1] = f32::INFINITY;
+                data[len - 1] = f32::NEG_INFINITY;
+            }
+            let bytes =
+                |data: &[f32]| data.iter().flat_map(|f| f.to_le_bytes()).collect::<Vec<_>>();
+            assert!(!has_f32_nan(&bytes(&data)));
+
+            let nans = [f32::NAN, -f32::NAN, f32::from_bits(0x7F80_0001)];
+            for (i, nan) in nans.into_iter().cycle().take(len).enumerate() {
+                let mut with_nan = data.clone();
+                with_nan[i] = nan;
+                let with_nan = bytes(&with_nan);
+                assert!(has_f32_nan(&with_nan), "NaN at {} of {}", i, len);
+                // A partial trailing f32 is ignored
+                assert_eq!(has_f32_nan(&with_nan[..with_nan.len() - 1]), i + 1 < len);
+            }
+        }
+    }
+
     #[test]
     fn test_f64_nan_rejected() {
         let mut writer = Writer::new();
@@ -0,0 +1,402 @@
+//! Lazy op iteration over an encoded edit.
+//!
+//! [`RawEditReader`] parses an edit's header and dictionaries up front but
+//! yields ops as undecoded byte ranges tagged with their [`OpKind`].
+//! Consumers that only care about some ops (e.g. counting relation types)
+//! skip the rest without decoding their values, and decode the ones they
+//! want with [`RawEditReader::decode`].
+//!
+//! Each item is one op record. A BatchCreateEntities record (see
+//! [`EncodeOptions::columnar`]) holds several CreateEntity ops; decode it
+//! with [`RawEditReader::decode_ops`].
+//!
+//! ```ignore
+//! let reader = RawEditReader::new(&bytes)?;
+//! for raw in reader.clone() {
+//!     let raw = raw?;
+//!     if raw.kind == OpKind::CreateRelation {
+//!         let op = reader.decode(&raw)?;
+//!         // ...
+//!     }
+//! }
+//! ```
+//!
+//! [`decode_edit_filtered`] builds on the reader to decode only the ops
+//! about a given set of entities.
+//!
+//! [`EncodeOptions::columnar`]: crate::codec::edit::EncodeOptions::columnar
+
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::codec::edit::{
+    decompress, op_to_owned, read_edit_header, EditHeader, OP_COUNT_EXCEEDED,
+};
+use crate::codec::op::{decode_op, decode_ops_with, skip_op_record};
+use crate::codec::primitives::Reader;
+use crate::compression::{compression_algorithm, edit_dictionary_id};
+use crate::error::DecodeError;
+use crate::limits::{MAGIC_UNCOMPRESSED, MAX_EDIT_SIZE, MAX_OPS_PER_EDIT};
+use crate::model::{
+    relation_entity_id, Edit, Id, IdSet, Op, OpKind, Provenance, WireDictionaries,
+};
+
+/// An op that has been located but not decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawOp<'a> {
+    /// The op's kind.
+    pub kind: OpKind,
+    /// Byte offset of the op within the edit.
+    pub offset: usize,
+    /// The op's encoding, starting with its type byte.
+    pub bytes: &'a [u8],
+    /// Number of ops in the record: 1, or the entity count of a
+    /// BatchCreateEntities record.
+    pub ops: usize,
+}
+
+/// Reads an uncompressed edit, yielding its ops without decoding them.
+///
+/// Compressed edits must be decompressed first with
+/// [`decompress`](crate::codec::edit::decompress). Iteration stops after
+/// the first error.
+#[derive(Debug, Clone)]
+pub struct RawEditReader<'a> {
+    input: &'a [u8],
+    reader: Reader<'a>,
+    id: Id,
+    name: &'a str,
+    authors: Vec<Id>,
+    created_at: i64,
+    provenance: Option<Provenance<'a>>,
+    dicts: WireDictionaries,
+    op_count: usize,
+    ops_offset: usize,
+    remaining: usize,
+}
+
+impl<'a> RawEditReader<'a> {
+    /// Parses the header and dictionaries of an uncompressed (GRC2) edit.
+    pub fn new(input: &'a [u8]) -> Result<Self, DecodeError> {
+        if input.len() < 4 {
+            return Err(DecodeError::UnexpectedEof { context: "magic" });
+        }
+        if compression_algorithm(input).is_some() {
+            return Err(DecodeError::MalformedEncoding {
+                context: "raw edit reader needs uncompressed input; decompress first",
+            });
+        }
+        if let Some(id) = edit_dictionary_id(input)? {
+            return Err(DecodeError::UnknownDictionary { id });
+        }
+        if &input[0..4] != MAGIC_UNCOMPRESSED {
+            let mut found = [0u8; 4];
+            found.copy_from_slice(&input[0..4]);
+            return Err(DecodeError::InvalidMagic { found });
+        }
+        if input.len() > MAX_EDIT_SIZE {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "edit",
+                len: input.len(),
+                max: MAX_EDIT_SIZE,
+            });
+        }
+
+        let mut reader = Reader::new(input);
+        let EditHeader { id, name, authors, created_at, provenance, dicts } =
+            read_edit_header(&mut reader)?;
+        let op_count = reader.read_varint("op_count")? as usize;
+        if op_count > MAX_OPS_PER_EDIT {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "ops",
+                len: op_count,
+                max: MAX_OPS_PER_EDIT,
+            });
+        }
+        let ops_offset = reader.position();
+
+        Ok(Self {
+            input,
+            reader,
+            id,
+            name,
+            authors,
+            created_at,
+            provenance,
+            dicts,
+            op_count,
+            ops_offset,
+            remaining: op_count,
+        })
+    }
+
+    /// Returns the edit's ID.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the edit's name.
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the edit's author IDs.
+    pub fn authors(&self) -> &[Id] {
+        &self.authors
+    }
+
+    /// Returns the edit's creation timestamp.
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    /// Returns the edit's provenance (format version 2 only).
+    pub fn provenance(&self) -> Option<&Provenance<'a>> {
+        self.provenance.as_ref()
+    }
+
+    /// Returns the edit's wire dictionaries.
+    pub fn dictionaries(&self) -> &WireDictionaries {
+        &self.dicts
+    }
+
+    /// Returns the number of ops declared in the header.
+    pub fn op_count(&self) -> usize {
+        self.op_count
+    }
+
+    /// Returns the byte offset of the first op, just past the op count.
+    pub fn ops_offset(&self) -> usize {
+        self.ops_offset
+    }
+
+    /// Fully decodes an op yielded by this reader.
+    ///
+    /// Fails for BatchCreateEntities records; use
+    /// [`decode_ops`](Self::decode_ops) for those.
+    pub fn decode(&self, raw: &RawOp<'a>) -> Result<Op<'a>, DecodeError> {
+        decode_op(&mut self.op_reader(raw)?, &self.dicts)
+    }
+
+    /// Fully decodes an op record yielded by this reader into the ops it
+    /// holds.
+    pub fn decode_ops(&self, raw: &RawOp<'a>) -> Result<Vec<Op<'a>>, DecodeError> {
+        let mut ops = Vec::with_capacity(raw.ops.min(raw.bytes.len()));
+        decode_ops_with(&mut self.op_reader(raw)?, &self.dicts, false, &mut ops)?;
+        Ok(ops)
+    }
+
+    /// Returns a reader positioned at `raw`. It reads from the edit's start
+    /// so that string table ranges resolve.
+    fn op_reader(&self, raw: &RawOp<'a>) -> Result<Reader<'a>, DecodeError> {
+        let edit = self.input.get(..raw.offset + raw.bytes.len());
+        let mut reader = Reader::new(edit.ok_or(DecodeError::UnexpectedEof { context: "op" })?);
+        reader.read_bytes(raw.offset, "op")?;
+        Ok(reader)
+    }
+}
+
+impl<'a> Iterator for RawEditReader<'a> {
+    type Item = Result<RawOp<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let offset = self.reader.position();
+        let record = skip_op_record(&mut self.reader, &self.dicts).and_then(|(kind, ops)| {
+            // A columnar record must not run past the op count
+            match ops <= self.remaining {
+                true => Ok((kind, ops)),
+                false => Err(OP_COUNT_EXCEEDED),
+            }
+        });
+        match record {
+            Ok((kind, ops)) => {
+                self.remaining -= ops;
+                let bytes = &self.input[offset..self.reader.position()];
+                Some(Ok(RawOp { kind, offset, bytes, ops }))
+            }
+            Err(err) => {
+                self.remaining = 0;
+                Some(Err(err))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+/// Decodes an edit, keeping only the ops whose subject is in `filter`.
+///
+/// The subject of an op is:
+/// - for entity ops, the entity;
+/// - for CreateRelation, the relation, either endpoint, or the reified
+///   entity (any of them matching keeps the op);
+/// - for UpdateRelation, DeleteRelation, and RestoreRelation, the relation;
+/// - for CreateValueRef, the entity holding the value;
+/// - for space ops (CreateSpace, AddMember, RemoveMember, and
+///   UpdateSpaceMetadata), the space.
+///
+/// Extension ops have no known subject and are always kept.
+///
+/// Ops that name their subject through the objects dictionary are pruned
+/// with a single lookup per dictionary entry, and pruned ops are skipped
+/// without decoding their values. Because of that, a malformed value in a
+/// pruned op goes unnoticed here even though [`decode_edit`] rejects it.
+///
+/// Accepts the same framings as [`decode_edit`]; compressed input is
+/// decompressed and the result owns its data.
+///
+/// [`decode_edit`]: crate::codec::edit::decode_edit
+pub fn decode_edit_filtered<'a>(
+    input: &'a [u8],
+    filter: &IdSet,
+) -> Result<Edit<'a>, DecodeError> {
+    if compression_algorithm(input).is_none() {
+        return filter_edit(input, filter);
+    }
+    let decompressed = decompress(input)?;
+    let edit = filter_edit(&decompressed, filter)?;
+    Ok(Edit {
+        id: edit.id,
+        name: Cow::Owned(edit.name.to_string()),
+        authors: edit.authors,
+        created_at: edit.created_at,
+        provenance: edit.provenance.map(Provenance::into_owned),
+        ops: edit.ops.into_iter().map(op_to_owned).collect(),
+    })
+}
+
+fn filter_edit<'a>(input: &'a [u8], filter: &IdSet) -> Result<Edit<'a>, DecodeError> {
+    let mut reader = RawEditReader::new(input)?;
+    let wanted: Vec<bool> = reader.dicts.objects.iter().map(|id| filter.contains(id)).collect();
+
+    let mut ops = Vec::new();
+    while let Some(raw) = reader.next() {
+        let raw = raw?;
+        let mut subject = Reader::new(&raw.bytes[1..]);
+        let keep = match raw.kind {
+            OpKind::CreateEntity => filter.contains(&subject.read_id("entity_id")?),
+            OpKind::BatchCreateEntities => {
+                let entities = reader.decode_ops(&raw)?;
+                ops.extend(entities.into_iter().filter(|op| match op {
+                    Op::CreateEntity(ce) => filter.contains(&ce.id),
+                    _ => true,
+                }));
+                continue;
+            }
+            OpKind::CreateRelation | OpKind::CreateValueRef | OpKind::Extension => true,
+            // Space ops start with the raw space ID, not an object index
+            OpKind::CreateSpace
+            | OpKind::AddMember
+            | OpKind::RemoveMember
+            | OpKind::UpdateSpaceMetadata => filter.contains(&subject.read_id("space_id")?),
+            // Out-of-range indices are kept so that decoding reports them
+            _ => {
+                let index = subject.read_varint("object")? as usize;
+                wanted.get(index).copied().unwrap_or(true)
+            }
+        };
+        if !keep {
+            continue;
+        }
+        let op = reader.decode(&raw)?;
+        let keep = match &op {
+            Op::CreateRelation(cr) => {
+                let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+                [cr.id, cr.from, cr.to, entity].iter().any(|id| filter.contains(id))
+            }
+            Op::CreateValueRef(cvr) => filter.contains(&cvr.entity),
+            _ => true,
+        };
+        if keep {
+            ops.push(op);
+        }
+    }
+
+    Ok(Edit {
+        id: reader.id,
+        name: Cow::Borrowed(reader.name),
+        authors: reader.authors,
+        created_at: reader.created_at,
+        provenance: reader.provenance,
+        ops,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::{decode_edit, encode_edit};
+    use crate::model::{AddMember, CreateSpace, EditBuilder, MemberRole, UpdateSpaceMetadata};
+
+    #[test]
+    fn test_raw_reader_matches_decode() {
+        let (name, alice, bob) = ([1u8; 16], [10u8; 16], [11u8; 16]);
+        let edit = EditBuilder::new([100u8; 16])
+            .name("raw")
+            .create_entity(alice, |e| e.text(name, "Alice", None).int64([2u8; 16], 30, None))
+            .create_entity(bob, |e| e.text(name, "Bob", None))
+            .create_relation_simple([20u8; 16], alice, bob, [3u8; 16])
+            .update_entity(bob, |u| u.set_text(name, "Robert", None))
+            .delete_relation([20u8; 16])
+            .build();
+        let bytes = encode_edit(&edit).unwrap();
+
+        let reader = RawEditReader::new(&bytes).unwrap();
+        assert_eq!((reader.id(), reader.name(), reader.op_count()), (edit.id, "raw", 5));
+        let raw: Vec<RawOp<'_>> = reader.clone().collect::<Result<_, _>>().unwrap();
+        let kinds: Vec<OpKind> = raw.iter().map(|op| op.kind).collect();
+        assert_eq!(kinds, edit.ops.iter().map(Op::kind).collect::<Vec<_>>());
+
+        // Ops tile the op section and decode to the same ops
+        let last = raw.last().unwrap();
+        assert_eq!(last.offset + last.bytes.len(), bytes.len());
+        for (raw, op) in raw.iter().zip(&decode_edit(&bytes).unwrap().ops) {
+            assert_eq!(&reader.decode(raw).unwrap(), op);
+        }
+
+        // Filtering keeps alice's ops and the relation touching her
+        let filter: IdSet = [alice].into_iter().collect();
+        let filtered = decode_edit_filtered(&bytes, &filter).unwrap();
+        assert_eq!(filtered.ops, edit.ops[..3].iter().step_by(2).cloned().collect::<Vec<_>>());
+
+        // Truncation surfaces as an error once, then iteration stops
+        let mut truncated = RawEditReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(truncated.by_ref().filter(Result::is_err).count(), 1);
+        assert!(truncated.next().is_none());
+    }
+
+    #[test]
+    fn test_filtered_space_ops() {
+        let alice = [10u8; 16];
+        // The space IDs' first bytes would read as in-range object indices
+        let spaces = [[0u8; 16], [1u8; 16], [7u8; 16]];
+        let mut builder = EditBuilder::new([100u8; 16]).create_empty_entity(alice);
+        for space in spaces {
+            builder = builder
+                .op(Op::CreateSpace(CreateSpace { id: space, name: "space".into() }))
+                .op(Op::AddMember(AddMember { space, member: alice, role: MemberRole::Editor }))
+                .op(Op::UpdateSpaceMetadata(UpdateSpaceMetadata {
+                    space,
+                    name: None,
+                    description: Some("about".into()),
+                }));
+        }
+        let edit = builder.build();
+        let bytes = encode_edit(&edit).unwrap();
+
+        // Space ops are kept by space, whatever their IDs start with
+        let filter: IdSet = [alice].into_iter().collect();
+        assert_eq!(decode_edit_filtered(&bytes, &filter).unwrap().ops, edit.ops[..1]);
+        for (i, space) in spaces.into_iter().enumerate() {
+            let filter: IdSet = [space].into_iter().collect();
+            let filtered = decode_edit_filtered(&bytes, &filter).unwrap();
+            assert_eq!(filtered.ops, edit.ops[1 + 3 * i..4 + 3 * i]);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Size accounting for encoded edits.
+//!
+//! [`EditStats::compute`] breaks an edit's encoded size down by section, op
+//! kind, and value data type, and lists its largest ops. It walks the ops
+//! with [`RawEditReader`] and never builds an [`Edit`](crate::model::Edit),
+//! so it is cheap enough to run over every published edit.
+//!
+//! Sizes are of the uncompressed encoding. A value's size includes its
+//! property index, so the per-type totals add up to the bytes the values
+//! take in their ops. Values in a BatchCreateEntities record share one
+//! property layout, which is counted with the record but not the values.
+
+use alloc::collections::{BTreeMap, BinaryHeap};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::codec::edit::{decompress, read_edit_fields, read_edit_header};
+use crate::codec::primitives::Reader;
+use crate::codec::raw::{RawEditReader, RawOp};
+use crate::codec::value::{read_value_clock, skip_value_in};
+use crate::compression::compression_algorithm;
+use crate::error::DecodeError;
+use crate::model::{DataType, OpKind, WireDictionaries};
+
+/// Number of largest ops reported by [`EditStats::compute`].
+pub const DEFAULT_TOP_OPS: usize = 10;
+
+/// A count of items and the bytes they take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeStats {
+    /// Number of items.
+    pub count: usize,
+    /// Total encoded bytes.
+    pub bytes: usize,
+}
+
+impl SizeStats {
+    fn add(&mut self, bytes: usize) {
+        self.add_many(1, bytes);
+    }
+
+    fn add_many(&mut self, count: usize, bytes: usize) {
+        self.count += count;
+        self.bytes += bytes;
+    }
+}
+
+/// The encoded size of one op.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpSize {
+    /// Index of the op within the edit (of the first op, for a
+    /// BatchCreateEntities record).
+    pub index: usize,
+    /// The op's kind.
+    pub kind: OpKind,
+    /// Byte offset of the op within the uncompressed edit.
+    pub offset: usize,
+    /// Encoded size of the op.
+    pub bytes: usize,
+}
+
+/// Size breakdown of an encoded edit.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EditStats {
+    /// Size of the input as given, compressed or not.
+    pub total_bytes: usize,
+    /// Size of the uncompressed encoding.
+    pub uncompressed_bytes: usize,
+    /// Magic, version, edit ID, name, authors, timestamp, and op count.
+    pub header_bytes: usize,
+    /// Dictionaries, including their counts.
+    pub dictionary_bytes: usize,
+    /// Ops, by kind. BatchCreateEntities records count each op they hold.
+    pub ops_by_kind: BTreeMap<OpKind, SizeStats>,
+    /// Values set by CreateEntity and UpdateEntity ops, by data type.
+    pub values_by_type: BTreeMap<DataType, SizeStats>,
+    /// The largest ops, largest first (earlier ops first among equals).
+    pub largest_ops: Vec<OpSize>,
+}
+
+impl EditStats {
+    /// Computes statistics for an encoded edit, reporting the
+    /// [`DEFAULT_TOP_OPS`] largest ops.
+    ///
+    /// Accepts the same framings as [`decode_edit`]. Ops are checked only
+    /// as far as needed to find their extent (see [`RawEditReader`]).
+    ///
+    /// [`decode_edit`]: crate::codec::edit::decode_edit
+    pub fn compute(input: &[u8]) -> Result<Self, DecodeError> {
+        Self::compute_with_top(input, DEFAULT_TOP_OPS)
+    }
+
+    /// Computes statistics, reporting the `top` largest ops.
+    pub fn compute_with_top(input: &[u8], top: usize) -> Result<Self, DecodeError> {
+        if compression_algorithm(input).is_none() {
+            return Self::compute_uncompressed(input, input.len(), top);
+        }
+        let decompressed = decompress(input)?;
+        Self::compute_uncompressed(&decompressed, input.len(), top)
+    }
+
+    /// Total bytes taken by ops.
+    pub fn op_bytes(&self) -> usize {
+        self.ops_by_kind.values().map(|s| s.bytes).sum()
+    }
+
+    /// Number of ops.
+    pub fn op_count(&self) -> usize {
+        self.ops_by_kind.values().map(|s| s.count).sum()
+    }
+
+    fn compute_uncompressed(
+        data: &[u8],
+        total_bytes: usize,
+        top: usize,
+    ) -> Result<Self, DecodeError> {
+        let mut ops = RawEditReader::new(data)?;
+
+        // Header fields end where the dictionaries begin
+        let mut reader = Reader::new(data);
+        read_edit_fields(&mut reader)?;
+        let fields_end = reader.position();
+        let mut reader = Reader::new(data);
+        read_edit_header(&mut reader)?;
+        let dictionaries_end = reader.position();
+
+        let mut stats = EditStats {
+            total_bytes,
+            uncompressed_bytes: data.len(),
+            header_bytes: fields_end + (ops.ops_offset() - dictionaries_end),
+            dictionary_bytes: dictionaries_end - fields_end,
+            ..Default::default()
+        };
+
+        let mut largest = BinaryHeap::new();
+        let mut index = 0;
+        while let Some(raw) = ops.next() {
+            let raw = raw?;
+            let size = raw.bytes.len();
+            stats.ops_by_kind.entry(raw.kind).or_default().add_many(raw.ops, size);
+            count_values(&raw, ops.dictionaries(), &mut stats.values_by_type)?;
+
+            largest.push(Reverse((size, Reverse(index), raw.offset, raw.kind)));
+            if largest.len() > top {
+                largest.pop();
+            }
+            index += raw.ops;
+        }
+
+        stats.largest_ops = largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((bytes, Reverse(index), offset, kind))| OpSize {
+                index,
+                kind,
+                offset,
+                bytes,
+            })
+            .collect();
+        Ok(stats)
+    }
+}
+
+/// Adds the values set by an entity op to `by_type`.
+fn count_values(
+    raw: &RawOp<'_>,
+    dicts: &WireDictionaries,
+    by_type: &mut BTreeMap<DataType, SizeStats>,
+) -> Result<(), DecodeError> {
+    let mut reader = Reader::new(&raw.bytes[1..]);
+    let string_table = dicts.strings.is_some();
+    match raw.kind {
+        OpKind::CreateEntity => {
+            reader.read_id("entity_id")?;
+        }
+        OpKind::UpdateEntity => {
+            reader.read_varint("entity_id")?;
+            if reader.read_byte("update_flags")? & 0x01 == 0 {
+                // No FLAG_HAS_SET_PROPERTIES
+                return Ok(());
+            }
+        }
+        OpKind::BatchCreateEntities => {
+            let entity_count = reader.read_varint("entity_count")? as usize;
+            let value_count = reader.read_varint("value_count")?;
+            // The raw reader has already checked the layout
+            let layout: Vec<DataType> = (0..value_count)
+                .map(|_| Ok(dicts.properties[reader.read_varint("property")? as usize].1))
+                .collect::<Result<_, DecodeError>>()?;
+            reader.read_bytes(entity_count * 16, "entity_ids")?;
+            for data_type in layout {
+                for _ in 0..entity_count {
+                    let start = reader.position();
+                    read_value_clock(&mut reader, dicts)?;
+                    skip_value_in(&mut reader, data_type, string_table)?;
+                    by_type.entry(data_type).or_default().add(reader.position() - start);
+                }
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+
+    let count = reader.read_varint("value_count")?;
+    for _ in 0..count {
+        let start = reader.position();
+        let prop_index = reader.read_varint("property")? as usize;
+        // The raw reader has already checked the index
+        let data_type = dicts.properties[prop_index].1;
+        read_value_clock(&mut reader, dicts)?;
+        skip_value_in(&mut reader, data_type, string_table)?;
+        by_type.entry(data_type).or_default().add(reader.position() - start);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::encode_edit;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_edit_stats() {
+        let (name, alice, bob) = ([1u8; 16], [10u8; 16], [11u8; 16]);
+        let edit = EditBuilder::new([100u8; 16])
+            .name("stats")
+            .create_entity(alice, |e| e.text(name, "Alice", None).int64([2u8; 16], 30, None))
+            .create_entity(bob, |e| e.text(name, "b".repeat(200), None))
+            .create_relation_simple([20u8; 16], alice, bob, [3u8; 16])
+            .update_entity(bob, |u| u.set_text(name, "Bob", None))
+            .build();
+        let bytes = encode_edit(&edit).unwrap();
+        let stats = EditStats::compute_with_top(&bytes, 2).unwrap();
+
+        // Sections tile the encoding
+        assert_eq!(stats.total_bytes, bytes.len());
+        assert_eq!(stats.header_bytes + stats.dictionary_bytes + stats.op_bytes(), bytes.len());
+        assert_eq!(stats.op_count(), 4);
+        assert_eq!(stats.ops_by_kind[&OpKind::CreateEntity].count, 2);
+
+        assert_eq!(stats.values_by_type[&DataType::Text].count, 3);
+        assert_eq!(stats.values_by_type[&DataType::Int64].count, 1);
+        // Property index, zigzag 30, and no unit
+        assert_eq!(stats.values_by_type[&DataType::Int64].bytes, 3);
+
+        assert_eq!(stats.largest_ops.len(), 2);
+        let largest = stats.largest_ops[0];
+        assert_eq!((largest.index, largest.kind), (1, OpKind::CreateEntity));
+        assert!(largest.bytes > 200);
+        assert!(stats.largest_ops[1].bytes <= stats.largest_ops[0].bytes);
+    }
+
+    #[test]
+    fn test_edit_stats_with_clocks() {
+        let mut edit = EditBuilder::new([100u8; 16])
+            .create_entity([10u8; 16], |e| e.int64([2u8; 16], 30, None))
+            .build();
+        if let crate::model::Op::CreateEntity(ce) = &mut edit.ops[0] {
+            ce.values[0].clock = Some(7);
+        }
+        let bytes = encode_edit(&edit).unwrap();
+        let stats = EditStats::compute(&bytes).unwrap();
+
+        // Property index, clock, zigzag 30, and no unit
+        assert_eq!(stats.values_by_type[&DataType::Int64].bytes, 4);
+    }
+}
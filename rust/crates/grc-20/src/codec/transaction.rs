@@ -0,0 +1,181 @@
+//! Transactions: groups of edits that apply all-or-nothing.
+//!
+//! A change often spans several edits, e.g. a schema edit followed by a
+//! data edit that relies on it. Appending them to a log separately lets a
+//! reader apply the first and then stop, leaving the space in a state no
+//! author intended. A transaction container puts the group in a single log
+//! entry, so it is read as a whole or not at all, and
+//! [`GraphState::apply_transaction`] applies it atomically.
+//!
+//! Container layout:
+//!
+//! ```text
+//! "GRC2T" version
+//! transaction_id: 16 bytes
+//! edit_count: varint
+//! edit_count × (length: varint, edit: standalone edit in any framing)
+//! ```
+//!
+//! Member edits are stored exactly as encoded on their own, so hashes and
+//! signatures computed over them stay valid inside the container.
+//!
+//! [`GraphState::apply_transaction`]: crate::state::GraphState::apply_transaction
+
+use crate::codec::edit::{decode_edit, encode_edit};
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError};
+use crate::limits::{
+    FORMAT_VERSION, MAGIC_TRANSACTION, MAX_EDITS_PER_TRANSACTION, MAX_EDIT_SIZE,
+    MIN_FORMAT_VERSION,
+};
+use crate::model::{Edit, Id};
+
+/// A decoded transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction<'a> {
+    /// The transaction's unique identifier.
+    pub id: Id,
+    /// Member edits, in apply order.
+    pub edits: Vec<Edit<'a>>,
+}
+
+/// Returns true if `input` starts with the transaction magic.
+pub fn is_transaction(input: &[u8]) -> bool {
+    input.starts_with(MAGIC_TRANSACTION)
+}
+
+/// Encodes edits into a transaction container, each as an uncompressed
+/// standalone edit.
+pub fn encode_transaction(id: Id, edits: &[Edit<'_>]) -> Result<Vec<u8>, EncodeError> {
+    let encoded = edits.iter().map(encode_edit).collect::<Result<Vec<_>, _>>()?;
+    encode_transaction_raw(id, &encoded)
+}
+
+/// Wraps already-encoded edits in a transaction container.
+///
+/// The bytes are copied through unchanged, which keeps existing hashes and
+/// signatures valid. Each must be a standalone edit (GRC2, GRC2Z, GRC2L,
+/// or GRC2N); this is checked when the container is decoded.
+pub fn encode_transaction_raw<B: AsRef<[u8]>>(
+    id: Id,
+    edits: &[B],
+) -> Result<Vec<u8>, EncodeError> {
+    if edits.len() > MAX_EDITS_PER_TRANSACTION {
+        return Err(EncodeError::LengthExceedsLimit {
+            field: "edits",
+            len: edits.len(),
+            max: MAX_EDITS_PER_TRANSACTION,
+        });
+    }
+
+    let mut writer = Writer::new();
+    writer.write_bytes(MAGIC_TRANSACTION);
+    writer.write_byte(FORMAT_VERSION);
+    writer.write_id(&id);
+    writer.write_varint(edits.len() as u64);
+    for bytes in edits {
+        writer.write_bytes_prefixed(bytes.as_ref());
+    }
+    Ok(writer.into_bytes())
+}
+
+/// Decodes a transaction container.
+///
+/// Fails if any member edit fails to decode, so a transaction is never
+/// returned partially.
+pub fn decode_transaction(input: &[u8]) -> Result<Transaction<'_>, DecodeError> {
+    if input.len() < MAGIC_TRANSACTION.len() {
+        return Err(DecodeError::UnexpectedEof { context: "magic" });
+    }
+    if !is_transaction(input) {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&input[0..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+
+    let mut reader = Reader::new(&input[MAGIC_TRANSACTION.len()..]);
+    let version = reader.read_byte("version")?;
+    if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+        return Err(DecodeError::UnsupportedVersion { version });
+    }
+    let id = reader.read_id("transaction_id")?;
+
+    let count = reader.read_varint("edit_count")? as usize;
+    if count > MAX_EDITS_PER_TRANSACTION {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "edits",
+            len: count,
+            max: MAX_EDITS_PER_TRANSACTION,
+        });
+    }
+
+    let mut edits = Vec::with_capacity(count.min(reader.remaining_len()));
+    for _ in 0..count {
+        let len = reader.read_varint("edit_len")? as usize;
+        if len > MAX_EDIT_SIZE {
+            return Err(DecodeError::LengthExceedsLimit {
+                field: "edit",
+                len,
+                max: MAX_EDIT_SIZE,
+            });
+        }
+        edits.push(decode_edit(reader.read_bytes(len, "edit")?)?);
+    }
+
+    if !reader.is_empty() {
+        return Err(DecodeError::MalformedEncoding {
+            context: "trailing bytes after transaction",
+        });
+    }
+    Ok(Transaction { id, edits })
+}
+
+/// Decodes one log entry: a standalone edit or a transaction container.
+///
+/// Returns the entry's edits in apply order; a standalone edit yields one.
+pub fn decode_log_entry(input: &[u8]) -> Result<Vec<Edit<'_>>, DecodeError> {
+    if is_transaction(input) {
+        Ok(decode_transaction(input)?.edits)
+    } else {
+        Ok(vec![decode_edit(input)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::{encode_edit_with_compression, CompressionOptions};
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_transaction_roundtrip() {
+        let schema = EditBuilder::new([1u8; 16])
+            .create_entity([10u8; 16], |e| e.text([2u8; 16], "Person", None))
+            .build();
+        let data = EditBuilder::new([3u8; 16])
+            .create_entity([11u8; 16], |e| e.int64([4u8; 16], 30, None))
+            .build();
+        let edits = vec![schema, data];
+
+        let bytes = encode_transaction([9u8; 16], &edits).unwrap();
+        assert!(is_transaction(&bytes));
+        let tx = decode_transaction(&bytes).unwrap();
+        assert_eq!(tx.id, [9u8; 16]);
+        assert_eq!(tx.edits, edits);
+        assert_eq!(decode_log_entry(&bytes).unwrap(), edits);
+
+        // Mixed framings are allowed
+        let raw = [
+            encode_edit_with_compression(&edits[0], CompressionOptions::default()).unwrap(),
+            encode_edit(&edits[1]).unwrap(),
+        ];
+        let bytes = encode_transaction_raw([9u8; 16], &raw).unwrap();
+        assert_eq!(decode_transaction(&bytes).unwrap().edits, edits);
+
+        // A damaged member fails the whole transaction
+        let mut damaged = raw.clone();
+        damaged[1].truncate(damaged[1].len() - 1);
+        let bytes = encode_transaction_raw([9u8; 16], &damaged).unwrap();
+        assert!(decode_transaction(&bytes).is_err());
+    }
+}
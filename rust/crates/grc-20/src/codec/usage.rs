@@ -0,0 +1,178 @@
+//! Property usage search across an edit log.
+//!
+//! Before deprecating or retyping a property in a live space, every edit
+//! that touches it has to be found. [`find_usages`] scans a log of encoded
+//! edits and reports each op that sets, unsets, or references a property.
+//!
+//! Filtering happens at the wire level: an edit can only use a property if
+//! it appears in the edit's properties dictionary, so only the header and
+//! that dictionary are read for edits that don't mention it. Ops are
+//! decoded only for edits that pass the filter.
+
+use std::borrow::Cow;
+
+use crate::codec::edit::{decode_edit, decompress, read_edit_fields};
+use crate::compression::compression_algorithm;
+use crate::codec::primitives::Reader;
+use crate::error::DecodeError;
+use crate::limits::{MAGIC_UNCOMPRESSED, MAX_DICT_SIZE};
+use crate::model::{Id, Op};
+
+/// How an op uses a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsageKind {
+    /// A value is set (CreateEntity or UpdateEntity).
+    Set,
+    /// A value is unset (UpdateEntity).
+    Unset,
+    /// A value reference points at the property (CreateValueRef).
+    ValueRef,
+}
+
+/// One use of a property in an edit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyUsage {
+    /// Position of the edit in the log.
+    pub edit_index: usize,
+    /// ID of the edit.
+    pub edit_id: Id,
+    /// Index of the op within the edit.
+    pub op_index: usize,
+    /// Entity whose value is set, unset, or referenced.
+    pub entity: Id,
+    /// How the property is used.
+    pub kind: UsageKind,
+}
+
+/// Finds every use of `property` in a log of encoded edits.
+///
+/// Edits may be compressed or uncompressed. Usages are returned in log
+/// order, then op order. Decoding stops at the first malformed edit.
+pub fn find_usages<I, B>(log: I, property: Id) -> Result<Vec<PropertyUsage>, DecodeError>
+where
+    I: IntoIterator<Item = B>,
+    B: AsRef<[u8]>,
+{
+    let mut usages = Vec::new();
+
+    for (edit_index, bytes) in log.into_iter().enumerate() {
+        let bytes = bytes.as_ref();
+        let data = if compression_algorithm(bytes).is_some() {
+            Cow::Owned(decompress(bytes)?)
+        } else {
+            Cow::Borrowed(bytes)
+        };
+        if !dictionary_contains_property(&data, &property)? {
+            continue;
+        }
+
+        let edit = decode_edit(&data)?;
+        for (op_index, op) in edit.ops.iter().enumerate() {
+            let mut report = |entity: Id, kind: UsageKind| {
+                usages.push(PropertyUsage {
+                    edit_index,
+                    edit_id: edit.id,
+                    op_index,
+                    entity,
+                    kind,
+                });
+            };
+            match op {
+                Op::CreateEntity(ce) if ce.values.iter().any(|pv| pv.property == property) => {
+                    report(ce.id, UsageKind::Set);
+                }
+                Op::UpdateEntity(ue) => {
+                    if ue.set_properties.iter().any(|pv| pv.property == property) {
+                        report(ue.id, UsageKind::Set);
+                    }
+                    if ue.unset_values.iter().any(|u| u.property == property) {
+                        report(ue.id, UsageKind::Unset);
+                    }
+                }
+                Op::CreateValueRef(cvr) if cvr.property == property => {
+                    report(cvr.entity, UsageKind::ValueRef);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(usages)
+}
+
+/// Reads an uncompressed edit's header and properties dictionary and
+/// reports whether `property` is listed.
+fn dictionary_contains_property(data: &[u8], property: &Id) -> Result<bool, DecodeError> {
+    let mut reader = Reader::new(data);
+
+    let magic = reader.read_bytes(4, "magic")?;
+    if magic != MAGIC_UNCOMPRESSED {
+        let mut found = [0u8; 4];
+        found.copy_from_slice(magic);
+        return Err(DecodeError::InvalidMagic { found });
+    }
+    // Start over: read_edit_fields checks the version and skips the header
+    let mut reader = Reader::new(data);
+    read_edit_fields(&mut reader)?;
+
+    let property_count = reader.read_varint("property_count")? as usize;
+    if property_count > MAX_DICT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "properties",
+            len: property_count,
+            max: MAX_DICT_SIZE,
+        });
+    }
+    for _ in 0..property_count {
+        if reader.read_id("property_id")? == *property {
+            return Ok(true);
+        }
+        reader.read_byte("data_type")?;
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::edit::encode_edit;
+    use crate::compression::{encode_edit_with_compression, CompressionOptions};
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_find_usages_across_log() {
+        let name = [1u8; 16];
+        let other = [2u8; 16];
+
+        let first = EditBuilder::new([10u8; 16])
+            .create_entity([20u8; 16], |e| e.text(name, "Alice", None).int64(other, 1, None))
+            .build();
+        let second = EditBuilder::new([11u8; 16])
+            .create_entity([21u8; 16], |e| e.int64(other, 2, None))
+            .build();
+        let third = EditBuilder::new([12u8; 16])
+            .update_entity([20u8; 16], |u| u.set_text(name, "Alicia", None).unset_all(name))
+            .build();
+
+        let log = vec![
+            encode_edit(&first).unwrap(),
+            encode_edit(&second).unwrap(),
+            encode_edit_with_compression(&third, CompressionOptions::default()).unwrap(),
+        ];
+
+        let usages = find_usages(&log, name).unwrap();
+        let summary: Vec<_> = usages.iter().map(|u| (u.edit_index, u.op_index, u.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![(0, 0, UsageKind::Set), (2, 0, UsageKind::Set), (2, 0, UsageKind::Unset)]
+        );
+        assert_eq!(usages[1].edit_id, [12u8; 16]);
+        assert_eq!(usages[1].entity, [20u8; 16]);
+
+        assert!(find_usages(&log, [99u8; 16]).unwrap().is_empty());
+        assert!(matches!(
+            find_usages([b"XXXX".as_slice()], name),
+            Err(DecodeError::InvalidMagic { .. })
+        ));
+    }
+}
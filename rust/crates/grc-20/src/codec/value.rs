@@ -2,15 +2,21 @@
 //!
 //! Implements the wire format for property values (spec Section 6.5).
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
-use crate::codec::primitives::{Reader, Writer};
+use crate::codec::primitives::{has_f32_nan, Reader, Writer};
 use crate::error::{DecodeError, EncodeError};
 use crate::limits::{MAX_BYTES_LEN, MAX_EMBEDDING_BYTES, MAX_EMBEDDING_DIMS, MAX_POSITION_LEN, MAX_STRING_LEN};
+use crate::model::timestamp::validate_iso8601_date;
 use crate::model::{
     DataType, DecimalMantissa, DictionaryBuilder, EmbeddingSubType, PropertyValue, Value,
     WireDictionaries,
 };
+use crate::validate::schedule::validate_schedule;
+use crate::validate::ValidationProfile;
 
 // =============================================================================
 // DECODING
@@ -67,7 +73,11 @@ fn decode_int64<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result
 }
 
 fn decode_float64<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Value<'a>, DecodeError> {
-    let value = reader.read_f64("float64")?;
+    let value = if reader.profile().allows_nan() {
+        reader.read_f64_unchecked("float64")?
+    } else {
+        reader.read_f64("float64")?
+    };
     let unit_index = reader.read_varint("float64.unit")? as usize;
     let unit = if unit_index == 0 {
         None
@@ -88,6 +98,7 @@ fn decode_float64<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resu
 fn decode_decimal<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Value<'a>, DecodeError> {
     let exponent = reader.read_signed_varint("decimal.exponent")? as i32;
     let mantissa_type = reader.read_byte("decimal.mantissa_type")?;
+    let mut normalize = false;
 
     let mantissa = match mantissa_type {
         0x00 => {
@@ -106,7 +117,10 @@ fn decode_decimal<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resu
                     let second = bytes[1];
                     if (first == 0x00 && (second & 0x80) == 0)
                         || (first == 0xFF && (second & 0x80) != 0) {
-                        return Err(DecodeError::DecimalMantissaNotMinimal);
+                        if !reader.profile().normalizes_decimals() {
+                            return Err(DecodeError::DecimalMantissaNotMinimal);
+                        }
+                        normalize = true;
                     }
                 }
             }
@@ -121,26 +135,16 @@ fn decode_decimal<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resu
     };
 
     // Validate normalization
-    match &mantissa {
-        DecimalMantissa::I64(v) => {
-            if *v == 0 {
-                if exponent != 0 {
-                    return Err(DecodeError::DecimalNotNormalized);
-                }
-            } else if *v % 10 == 0 {
-                return Err(DecodeError::DecimalNotNormalized);
-            }
-        }
-        DecimalMantissa::Big(bytes) => {
-            if is_big_mantissa_zero(bytes) {
-                if exponent != 0 {
-                    return Err(DecodeError::DecimalNotNormalized);
-                }
-            } else if is_big_mantissa_divisible_by_10(bytes) {
-                return Err(DecodeError::DecimalNotNormalized);
-            }
+    if normalize || !is_decimal_normalized(exponent, &mantissa) {
+        if !reader.profile().normalizes_decimals() {
+            return Err(DecodeError::DecimalNotNormalized);
         }
+        normalize = true;
     }
+    let (exponent, mantissa) = match normalize {
+        true => mantissa.normalized(exponent),
+        false => (exponent, mantissa),
+    };
 
     let unit_index = reader.read_varint("decimal.unit")? as usize;
     let unit = if unit_index == 0 {
@@ -160,6 +164,17 @@ fn decode_decimal<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Resu
     Ok(Value::Decimal { exponent, mantissa, unit })
 }
 
+/// Checks that a DECIMAL is in normal form: zero as `0e0`, otherwise no
+/// trailing decimal zeros in the mantissa. Sign extension is not checked.
+fn is_decimal_normalized(exponent: i32, mantissa: &DecimalMantissa<'_>) -> bool {
+    match mantissa {
+        DecimalMantissa::I64(0) => exponent == 0,
+        DecimalMantissa::I64(v) => v % 10 != 0,
+        DecimalMantissa::Big(bytes) if is_big_mantissa_zero(bytes) => exponent == 0,
+        DecimalMantissa::Big(bytes) => !is_big_mantissa_divisible_by_10(bytes),
+    }
+}
+
 /// Checks if a big-endian two's complement mantissa represents zero.
 fn is_big_mantissa_zero(bytes: &[u8]) -> bool {
     bytes.iter().all(|&b| b == 0)
@@ -220,7 +235,23 @@ fn twos_complement_abs_mod_10(bytes: &[u8]) -> u32 {
 }
 
 fn decode_text<'a>(reader: &mut Reader<'a>, dicts: &WireDictionaries) -> Result<Value<'a>, DecodeError> {
-    let value = reader.read_str(MAX_STRING_LEN, "text")?;
+    // With a string table, a reference precedes the text; 0 means inline
+    let value = match &dicts.strings {
+        None => reader.read_str(MAX_STRING_LEN, "text")?,
+        Some(strings) => match reader.read_varint("text.string")? as usize {
+            0 => reader.read_str(MAX_STRING_LEN, "text")?,
+            index => {
+                let Some(range) = strings.get(index - 1) else {
+                    return Err(DecodeError::IndexOutOfBounds {
+                        dict: "strings",
+                        index,
+                        size: strings.len() + 1, // +1 for index 0
+                    });
+                };
+                reader.str_at(range.clone(), "text")?
+            }
+        },
+    };
     let lang_index = reader.read_varint("text.language")? as usize;
 
     let language = if lang_index == 0 {
@@ -255,12 +286,8 @@ fn decode_bytes<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError> {
 
 fn decode_date<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError> {
     let value = reader.read_str(MAX_STRING_LEN, "date")?;
-    // Basic validation: DATE should not contain 'T' (that's DATETIME)
-    if value.contains('T') {
-        return Err(DecodeError::MalformedEncoding {
-            context: "DATE should not contain 'T' separator (use DATETIME instead)",
-        });
-    }
+    validate_iso8601_date(value, reader.profile().strict_dates())
+        .map_err(|context| DecodeError::MalformedEncoding { context })?;
     Ok(Value::Date(Cow::Borrowed(value)))
 }
 
@@ -302,8 +329,9 @@ fn decode_datetime<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError
 
 fn decode_schedule<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError> {
     let value = reader.read_str(MAX_STRING_LEN, "schedule")?;
-    // RFC 5545 iCalendar format - basic validation
-    // Full validation would require a complete iCalendar parser
+    // RFC 5545 iCalendar format
+    validate_schedule(value, true)
+        .map_err(|issue| DecodeError::InvalidSchedule { line: issue.line, reason: issue.reason })?;
     Ok(Value::Schedule(Cow::Borrowed(value)))
 }
 
@@ -316,30 +344,26 @@ fn decode_point<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeError> {
         });
     }
 
-    // Read in wire order: longitude, latitude, altitude (optional)
-    let lon = reader.read_f64("point.lon")?;
-    let lat = reader.read_f64("point.lat")?;
+    // Read in wire order: longitude, latitude, altitude (optional);
+    // NaN is checked after the bounds
+    let lon = reader.read_f64_unchecked("point.lon")?;
+    let lat = reader.read_f64_unchecked("point.lat")?;
     let alt = if ordinate_count == 3 {
-        Some(reader.read_f64("point.alt")?)
+        Some(reader.read_f64_unchecked("point.alt")?)
     } else {
         None
     };
 
     // Validate bounds
-    if !(-180.0..=180.0).contains(&lon) {
+    if lon.abs() > 180.0 {
         return Err(DecodeError::LongitudeOutOfRange { lon });
     }
-    if !(-90.0..=90.0).contains(&lat) {
+    if lat.abs() > 90.0 {
         return Err(DecodeError::LatitudeOutOfRange { lat });
     }
-    if lon.is_nan() || lat.is_nan() {
+    if !reader.profile().allows_nan() && (lon.is_nan() || lat.is_nan() || alt.is_some_and(f64::is_nan)) {
         return Err(DecodeError::FloatIsNan);
     }
-    if let Some(a) = alt {
-        if a.is_nan() {
-            return Err(DecodeError::FloatIsNan);
-        }
-    }
 
     Ok(Value::Point { lon, lat, alt })
 }
@@ -370,13 +394,12 @@ fn decode_embedding<'a>(reader: &mut Reader<'a>) -> Result<Value<'a>, DecodeErro
     let data = reader.read_bytes(expected_bytes, "embedding.data")?;
 
     // Validate no NaN in float32 embeddings
-    if sub_type == EmbeddingSubType::Float32 {
-        for chunk in data.chunks_exact(4) {
-            let f = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-            if f.is_nan() {
-                return Err(DecodeError::FloatIsNan);
-            }
-        }
+    if sub_type == EmbeddingSubType::Float32
+        && reader.validates_embeddings()
+        && !reader.profile().allows_nan()
+        && has_f32_nan(data)
+    {
+        return Err(DecodeError::FloatIsNan);
     }
 
     // Validate binary embedding has zeros in unused bits
@@ -409,9 +432,140 @@ pub fn decode_property_value<'a>(
     }
 
     let (property, data_type) = dicts.properties[prop_index];
+    let clock = read_value_clock(reader, dicts)?;
     let value = decode_value(reader, data_type, dicts)?;
 
-    Ok(PropertyValue { property, value })
+    Ok(PropertyValue { property, value, clock })
+}
+
+/// Reads the clock of a property value, if the edit's format version has
+/// one. A clock of 0 means none.
+pub(crate) fn read_value_clock(
+    reader: &mut Reader<'_>,
+    dicts: &WireDictionaries,
+) -> Result<Option<u64>, DecodeError> {
+    if !dicts.value_clocks {
+        return Ok(None);
+    }
+    let clock = reader.read_varint("clock")?;
+    Ok((clock != 0).then_some(clock))
+}
+
+/// Skips over a value of the given type without decoding it.
+///
+/// Checks only what is needed to find the value's end (length limits,
+/// mantissa and point layouts, embedding sub-types); content, dictionary
+/// indices, and normalization are left to [`decode_value`].
+///
+/// TEXT values are expected inline, as in edits without a string table.
+pub fn skip_value(reader: &mut Reader<'_>, data_type: DataType) -> Result<(), DecodeError> {
+    skip_value_in(reader, data_type, false)
+}
+
+/// Skips over a value, reading TEXT as a string table reference if
+/// `string_table` is set.
+pub(crate) fn skip_value_in(
+    reader: &mut Reader<'_>,
+    data_type: DataType,
+    string_table: bool,
+) -> Result<(), DecodeError> {
+    match data_type {
+        DataType::Bool => {
+            reader.read_byte("bool")?;
+        }
+        DataType::Int64 => {
+            reader.read_varint("int64")?;
+            reader.read_varint("int64.unit")?;
+        }
+        DataType::Float64 => {
+            reader.read_bytes(8, "float64")?;
+            reader.read_varint("float64.unit")?;
+        }
+        DataType::Decimal => {
+            reader.read_varint("decimal.exponent")?;
+            match reader.read_byte("decimal.mantissa_type")? {
+                0x00 => {
+                    reader.read_varint("decimal.mantissa")?;
+                }
+                0x01 => {
+                    let len = reader.read_varint("decimal.mantissa_len")? as usize;
+                    reader.read_bytes(len, "decimal.mantissa_bytes")?;
+                }
+                _ => {
+                    return Err(DecodeError::MalformedEncoding {
+                        context: "invalid decimal mantissa type"
+                    });
+                }
+            }
+            reader.read_varint("decimal.unit")?;
+        }
+        DataType::Text => {
+            if !string_table || reader.read_varint("text.string")? == 0 {
+                skip_prefixed(reader, MAX_STRING_LEN, "text")?;
+            }
+            reader.read_varint("text.language")?;
+        }
+        DataType::Bytes => skip_prefixed(reader, MAX_BYTES_LEN, "bytes")?,
+        DataType::Date => skip_prefixed(reader, MAX_STRING_LEN, "date")?,
+        DataType::Time => skip_prefixed(reader, MAX_STRING_LEN, "time")?,
+        DataType::Datetime => skip_prefixed(reader, MAX_STRING_LEN, "datetime")?,
+        DataType::Schedule => skip_prefixed(reader, MAX_STRING_LEN, "schedule")?,
+        DataType::Point => {
+            let ordinate_count = reader.read_byte("point.ordinate_count")?;
+            if ordinate_count != 2 && ordinate_count != 3 {
+                return Err(DecodeError::MalformedEncoding {
+                    context: "POINT ordinate_count must be 2 or 3",
+                });
+            }
+            reader.read_bytes(8 * ordinate_count as usize, "point")?;
+        }
+        DataType::Embedding => {
+            let sub_type_byte = reader.read_byte("embedding.sub_type")?;
+            let sub_type = EmbeddingSubType::from_u8(sub_type_byte)
+                .ok_or(DecodeError::InvalidEmbeddingSubType { sub_type: sub_type_byte })?;
+            let dims = reader.read_varint("embedding.dims")? as usize;
+            if dims > MAX_EMBEDDING_DIMS {
+                return Err(DecodeError::LengthExceedsLimit {
+                    field: "embedding.dims",
+                    len: dims,
+                    max: MAX_EMBEDDING_DIMS,
+                });
+            }
+            reader.read_bytes(sub_type.bytes_for_dims(dims), "embedding.data")?;
+        }
+    }
+    Ok(())
+}
+
+/// Skips a property value, checking only that its property index exists.
+pub fn skip_property_value(
+    reader: &mut Reader<'_>,
+    dicts: &WireDictionaries,
+) -> Result<(), DecodeError> {
+    let prop_index = reader.read_varint("property")? as usize;
+    let Some(&(_, data_type)) = dicts.properties.get(prop_index) else {
+        return Err(DecodeError::IndexOutOfBounds {
+            dict: "properties",
+            index: prop_index,
+            size: dicts.properties.len(),
+        });
+    };
+    read_value_clock(reader, dicts)?;
+    skip_value_in(reader, data_type, dicts.strings.is_some())
+}
+
+/// Skips a length-prefixed field of at most `max_len` bytes.
+fn skip_prefixed(
+    reader: &mut Reader<'_>,
+    max_len: usize,
+    field: &'static str,
+) -> Result<(), DecodeError> {
+    let len = reader.read_varint(field)? as usize;
+    if len > max_len {
+        return Err(DecodeError::LengthExceedsLimit { field, len, max: max_len });
+    }
+    reader.read_bytes(len, field)?;
+    Ok(())
 }
 
 // =============================================================================
@@ -434,7 +588,7 @@ pub fn encode_value(
             writer.write_varint(unit_index as u64);
         }
         Value::Float64 { value, unit } => {
-            if value.is_nan() {
+            if value.is_nan() && !dict_builder.profile().allows_nan() {
                 return Err(EncodeError::FloatIsNan);
             }
             writer.write_f64(*value);
@@ -442,12 +596,22 @@ pub fn encode_value(
             writer.write_varint(unit_index as u64);
         }
         Value::Decimal { exponent, mantissa, unit } => {
-            encode_decimal(writer, *exponent, mantissa)?;
+            encode_decimal(writer, *exponent, mantissa, dict_builder.profile())?;
             let unit_index = dict_builder.add_unit(*unit);
             writer.write_varint(unit_index as u64);
         }
         Value::Text { value, language } => {
-            writer.write_string(value);
+            if dict_builder.has_strings() {
+                match dict_builder.get_string_index(value) {
+                    Some(index) => writer.write_varint(index as u64 + 1),
+                    None => {
+                        writer.write_varint(0);
+                        writer.write_string(value);
+                    }
+                }
+            } else {
+                writer.write_string(value);
+            }
             let lang_index = dict_builder.add_language(*language);
             writer.write_varint(lang_index as u64);
         }
@@ -455,12 +619,8 @@ pub fn encode_value(
             writer.write_bytes_prefixed(bytes);
         }
         Value::Date(value) => {
-            // DATE should not contain 'T' (that's DATETIME)
-            if value.contains('T') {
-                return Err(EncodeError::InvalidDate {
-                    reason: "DATE should not contain 'T' separator (use DATETIME instead)",
-                });
-            }
+            validate_iso8601_date(value, dict_builder.profile().strict_dates())
+                .map_err(|reason| EncodeError::InvalidDate { reason })?;
             writer.write_string(value);
         }
         Value::Time(value) => {
@@ -493,6 +653,10 @@ pub fn encode_value(
         }
         Value::Schedule(s) => {
             // RFC 5545 iCalendar format
+            validate_schedule(s, true).map_err(|issue| EncodeError::InvalidSchedule {
+                line: issue.line,
+                reason: issue.reason,
+            })?;
             writer.write_string(s);
         }
         Value::Point { lon, lat, alt } => {
@@ -502,14 +666,10 @@ pub fn encode_value(
             if *lat < -90.0 || *lat > 90.0 {
                 return Err(EncodeError::LatitudeOutOfRange { lat: *lat });
             }
-            if lat.is_nan() || lon.is_nan() {
+            let nan = lat.is_nan() || lon.is_nan() || alt.is_some_and(f64::is_nan);
+            if nan && !dict_builder.profile().allows_nan() {
                 return Err(EncodeError::FloatIsNan);
             }
-            if let Some(a) = alt {
-                if a.is_nan() {
-                    return Err(EncodeError::FloatIsNan);
-                }
-            }
             // Write ordinate_count: 2 for 2D, 3 for 3D
             let ordinate_count = if alt.is_some() { 3u8 } else { 2u8 };
             writer.write_byte(ordinate_count);
@@ -530,12 +690,19 @@ pub fn encode_value(
                 });
             }
             // Check for NaN in float32
-            if *sub_type == EmbeddingSubType::Float32 {
-                for chunk in data.chunks_exact(4) {
-                    let f = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-                    if f.is_nan() {
-                        return Err(EncodeError::FloatIsNan);
-                    }
+            if *sub_type == EmbeddingSubType::Float32
+                && !dict_builder.profile().allows_nan()
+                && has_f32_nan(data)
+            {
+                return Err(EncodeError::FloatIsNan);
+            }
+            // Binary padding bits past `dims` must be zero
+            if *sub_type == EmbeddingSubType::Binary && dims % 8 != 0 {
+                let mask = !((1u8 << (dims % 8)) - 1);
+                if data[data.len() - 1] & mask != 0 {
+                    return Err(EncodeError::InvalidInput {
+                        context: "binary embedding has non-zero unused bits",
+                    });
                 }
             }
             writer.write_byte(*sub_type as u8);
@@ -550,27 +717,28 @@ fn encode_decimal(
     writer: &mut Writer,
     exponent: i32,
     mantissa: &DecimalMantissa<'_>,
+    profile: ValidationProfile,
 ) -> Result<(), EncodeError> {
     // Validate normalization
-    match mantissa {
-        DecimalMantissa::I64(v) => {
-            if *v == 0 {
-                if exponent != 0 {
-                    return Err(EncodeError::DecimalNotNormalized);
-                }
-            } else if *v % 10 == 0 {
-                return Err(EncodeError::DecimalNotNormalized);
-            }
-        }
-        DecimalMantissa::Big(bytes) => {
-            if is_big_mantissa_zero(bytes) {
-                if exponent != 0 {
-                    return Err(EncodeError::DecimalNotNormalized);
-                }
-            } else if is_big_mantissa_divisible_by_10(bytes) {
-                return Err(EncodeError::DecimalNotNormalized);
+    let minimal = match mantissa {
+        // No redundant sign-extension byte
+        DecimalMantissa::Big(bytes) => match bytes[..] {
+            [first, second, ..] => {
+                !((first == 0x00 && second & 0x80 == 0) || (first == 0xFF && second & 0x80 != 0))
             }
+            _ => true,
+        },
+        DecimalMantissa::I64(_) => true,
+    };
+    if !minimal || !is_decimal_normalized(exponent, mantissa) {
+        if !profile.normalizes_decimals() {
+            return Err(match minimal {
+                true => EncodeError::DecimalNotNormalized,
+                false => EncodeError::DecimalMantissaNotMinimal,
+            });
         }
+        let (exponent, mantissa) = mantissa.normalized(exponent);
+        return encode_decimal(writer, exponent, &mantissa, ValidationProfile::Strict);
     }
 
     writer.write_signed_varint(exponent as i64);
@@ -598,20 +766,65 @@ pub fn encode_property_value(
     data_type: DataType,
 ) -> Result<(), EncodeError> {
     let prop_index = dict_builder.add_property(pv.property, data_type);
+    check_property_type(pv, dict_builder, prop_index)?;
     writer.write_varint(prop_index as u64);
+    write_value_clock(writer, pv, dict_builder)?;
     encode_value(writer, &pv.value, dict_builder)?;
     Ok(())
 }
 
+/// Writes the clock of a property value if the builder is set to write
+/// clocks (0 for none). Clocks start at 1.
+pub(crate) fn write_value_clock(
+    writer: &mut Writer,
+    pv: &PropertyValue<'_>,
+    dict_builder: &DictionaryBuilder,
+) -> Result<(), EncodeError> {
+    if !dict_builder.value_clocks() {
+        return Ok(());
+    }
+    match pv.clock {
+        Some(0) => Err(EncodeError::InvalidInput { context: "value clock must be at least 1" }),
+        clock => {
+            writer.write_varint(clock.unwrap_or(0));
+            Ok(())
+        }
+    }
+}
+
+/// Checks that the type registered for a property matches its value.
+///
+/// Value bytes are written for the value's own type, so a property used
+/// with two types in one edit would decode wrongly or not at all.
+pub(crate) fn check_property_type(
+    pv: &PropertyValue<'_>,
+    dict_builder: &DictionaryBuilder,
+    prop_index: usize,
+) -> Result<(), EncodeError> {
+    let actual = pv.value.data_type();
+    match dict_builder.property_type(prop_index) {
+        Some(declared) if declared != actual => Err(EncodeError::PropertyTypeConflict {
+            property: pv.property,
+            declared,
+            actual,
+        }),
+        _ => Ok(()),
+    }
+}
+
 /// Validates a position string according to spec rules.
 pub fn validate_position(pos: &str) -> Result<(), EncodeError> {
+    check_position(pos, ValidationProfile::Strict)
+}
+
+/// Validates a position string, checking its characters unless `profile`
+/// allows any.
+pub(crate) fn check_position(pos: &str, profile: ValidationProfile) -> Result<(), EncodeError> {
     if pos.len() > MAX_POSITION_LEN {
         return Err(EncodeError::PositionTooLong);
     }
-    for c in pos.chars() {
-        if !c.is_ascii_alphanumeric() {
-            return Err(EncodeError::InvalidPositionChar);
-        }
+    if !profile.allows_any_position_chars() && !pos.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(EncodeError::InvalidPositionChar);
     }
     Ok(())
 }
@@ -619,14 +832,74 @@ pub fn validate_position(pos: &str) -> Result<(), EncodeError> {
 /// Decodes a position string with validation (zero-copy).
 pub fn decode_position<'a>(reader: &mut Reader<'a>) -> Result<Cow<'a, str>, DecodeError> {
     let pos = reader.read_str(MAX_POSITION_LEN, "position")?;
-    for c in pos.chars() {
-        if !c.is_ascii_alphanumeric() {
+    if !reader.profile().allows_any_position_chars() {
+        if let Some(c) = pos.chars().find(|c| !c.is_ascii_alphanumeric()) {
             return Err(DecodeError::InvalidPositionChar { char: c });
         }
     }
     Ok(Cow::Borrowed(pos))
 }
 
+// =============================================================================
+// DATE HELPERS
+// =============================================================================
+
+/// Extracts the date part from an ISO 8601 date or datetime string.
+///
+/// Examples:
+/// - "2024-03-15T14:30:00Z" -> "2024-03-15"
+/// - "2024-03-15" -> "2024-03-15"
+/// - "2024-03" -> "2024-03"
+/// - "2024" -> "2024"
+pub fn extract_date_part(s: &str) -> &str {
+    if let Some(t_pos) = s.find('T') {
+        &s[..t_pos]
+    } else {
+        s
+    }
+}
+
+/// Converts an ISO 8601 date string to a full datetime string at midnight UTC.
+///
+/// If the input already contains a time component, returns it unchanged.
+///
+/// Examples:
+/// - "2024-03-15" -> "2024-03-15T00:00:00.000Z"
+/// - "2024-03" -> "2024-03-01T00:00:00.000Z"
+/// - "2024" -> "2024-01-01T00:00:00.000Z"
+/// - "2024-03-15T14:30:00Z" -> "2024-03-15T14:30:00Z" (unchanged)
+pub fn date_to_datetime(s: &str) -> String {
+    // If already has time component, return as-is
+    if s.contains('T') {
+        return s.to_string();
+    }
+
+    // Handle BCE dates (with leading -)
+    let (prefix, date_part) = if let Some(rest) = s.strip_prefix('-') {
+        ("-", rest)
+    } else {
+        ("", s)
+    };
+
+    let parts: Vec<&str> = date_part.split('-').collect();
+
+    match parts.len() {
+        1 => {
+            // Year only -> YYYY-01-01T00:00:00.000Z
+            format!("{}{}-01-01T00:00:00.000Z", prefix, parts[0])
+        }
+        2 => {
+            // Year-month -> YYYY-MM-01T00:00:00.000Z
+            format!("{}{}-{}-01T00:00:00.000Z", prefix, parts[0], parts[1])
+        }
+        3 => {
+            // Full date -> YYYY-MM-DDT00:00:00.000Z
+            format!("{}{}-{}-{}T00:00:00.000Z", prefix, parts[0], parts[1], parts[2])
+        }
+        _ => s.to_string(), // Invalid, return as-is
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -666,6 +939,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_float64_roundtrip() {
         for v in [0.0, 1.0, -1.0, f64::INFINITY, f64::NEG_INFINITY, 3.14159] {
             let value = Value::Float64 { value: v, unit: None };
@@ -939,6 +1213,11 @@ mod tests {
         let invalid = Value::Date(Cow::Borrowed("2024-03-15T14:30:00Z"));
         let mut writer = Writer::new();
         assert!(encode_value(&mut writer, &invalid, &mut dict_builder).is_err());
+
+        // DATE should reject February 29 outside leap years
+        let invalid = Value::Date(Cow::Borrowed("2023-02-29"));
+        let mut writer = Writer::new();
+        assert!(encode_value(&mut writer, &invalid, &mut dict_builder).is_err());
     }
 
     #[test]
@@ -1059,63 +1338,3 @@ mod tests {
         assert_eq!(date_to_datetime("2024-03-15T14:30:00Z"), "2024-03-15T14:30:00Z");
     }
 }
-
-// =============================================================================
-// DATE HELPERS
-// =============================================================================
-
-/// Extracts the date part from an ISO 8601 date or datetime string.
-///
-/// Examples:
-/// - "2024-03-15T14:30:00Z" -> "2024-03-15"
-/// - "2024-03-15" -> "2024-03-15"
-/// - "2024-03" -> "2024-03"
-/// - "2024" -> "2024"
-pub fn extract_date_part(s: &str) -> &str {
-    if let Some(t_pos) = s.find('T') {
-        &s[..t_pos]
-    } else {
-        s
-    }
-}
-
-/// Converts an ISO 8601 date string to a full datetime string at midnight UTC.
-///
-/// If the input already contains a time component, returns it unchanged.
-///
-/// Examples:
-/// - "2024-03-15" -> "2024-03-15T00:00:00.000Z"
-/// - "2024-03" -> "2024-03-01T00:00:00.000Z"
-/// - "2024" -> "2024-01-01T00:00:00.000Z"
-/// - "2024-03-15T14:30:00Z" -> "2024-03-15T14:30:00Z" (unchanged)
-pub fn date_to_datetime(s: &str) -> String {
-    // If already has time component, return as-is
-    if s.contains('T') {
-        return s.to_string();
-    }
-
-    // Handle BCE dates (with leading -)
-    let (prefix, date_part) = if let Some(rest) = s.strip_prefix('-') {
-        ("-", rest)
-    } else {
-        ("", s)
-    };
-
-    let parts: Vec<&str> = date_part.split('-').collect();
-
-    match parts.len() {
-        1 => {
-            // Year only -> YYYY-01-01T00:00:00.000Z
-            format!("{}{}-01-01T00:00:00.000Z", prefix, parts[0])
-        }
-        2 => {
-            // Year-month -> YYYY-MM-01T00:00:00.000Z
-            format!("{}{}-{}-01T00:00:00.000Z", prefix, parts[0], parts[1])
-        }
-        3 => {
-            // Full date -> YYYY-MM-DDT00:00:00.000Z
-            format!("{}{}-{}-{}T00:00:00.000Z", prefix, parts[0], parts[1], parts[2])
-        }
-        _ => s.to_string(), // Invalid, return as-is
-    }
-}
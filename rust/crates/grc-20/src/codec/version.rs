@@ -0,0 +1,143 @@
+//! Edit format versions.
+//!
+//! Every uncompressed edit starts with `GRC2` and a version byte. Each
+//! version has its own header layout, and decoding any of them yields the
+//! current [`Edit`](crate::model::Edit) model; fields an older version
+//! cannot carry are left empty (a version 1 edit decodes with no
+//! provenance). Encoders write the oldest version that can represent the
+//! edit, so edits that don't use newer fields stay readable by older
+//! decoders.
+//!
+//! To add a version: add a variant, give it a header path in
+//! `read_edit_fields`, and bump [`FormatVersion::LATEST`].
+
+use crate::error::DecodeError;
+use crate::limits::{
+    FORMAT_VERSION, FORMAT_VERSION_PROVENANCE, FORMAT_VERSION_VALUE_CLOCKS, MIN_FORMAT_VERSION,
+};
+use crate::model::Edit;
+
+/// A supported edit format version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum FormatVersion {
+    /// Pre-release edits; laid out exactly like version 1.
+    V0 = MIN_FORMAT_VERSION,
+    /// The original layout.
+    V1 = FORMAT_VERSION,
+    /// Adds header flags after `created_at`, announcing the provenance
+    /// block and the checksum trailer.
+    V2 = FORMAT_VERSION_PROVENANCE,
+    /// Version 2 with a clock after the property of every property value.
+    V3 = FORMAT_VERSION_VALUE_CLOCKS,
+}
+
+impl FormatVersion {
+    /// The newest version this crate decodes.
+    pub const LATEST: FormatVersion = FormatVersion::V3;
+
+    /// Converts a version byte, returning None for unsupported versions.
+    pub fn from_u8(version: u8) -> Option<Self> {
+        match version {
+            MIN_FORMAT_VERSION => Some(FormatVersion::V0),
+            FORMAT_VERSION => Some(FormatVersion::V1),
+            FORMAT_VERSION_PROVENANCE => Some(FormatVersion::V2),
+            FORMAT_VERSION_VALUE_CLOCKS => Some(FormatVersion::V3),
+            _ => None,
+        }
+    }
+
+    /// Returns the version byte.
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns true if the header has flags (provenance and checksum).
+    pub fn has_header_flags(self) -> bool {
+        self >= FormatVersion::V2
+    }
+
+    /// Returns true if property values carry a clock.
+    pub fn has_value_clocks(self) -> bool {
+        self >= FormatVersion::V3
+    }
+
+    /// Returns the version the encoders write for an edit: the oldest one
+    /// that can represent it. Remove lists need version 2 for the header
+    /// flag that announces them. Encoding with a checksum always writes at
+    /// least version 2, as does encoding with interned strings or columnar
+    /// records when the edit gets a string table or a BatchCreateEntities
+    /// record: their header flags make older decoders reject what they
+    /// cannot read.
+    pub fn required_for(edit: &Edit<'_>) -> Self {
+        if edit.has_value_clocks() {
+            FormatVersion::V3
+        } else if edit.has_remove_values()
+            || edit.provenance.as_ref().is_some_and(|p| !p.is_empty())
+        {
+            FormatVersion::V2
+        } else {
+            FormatVersion::V1
+        }
+    }
+}
+
+impl TryFrom<u8> for FormatVersion {
+    type Error = DecodeError;
+
+    fn try_from(version: u8) -> Result<Self, DecodeError> {
+        Self::from_u8(version).ok_or(DecodeError::UnsupportedVersion { version })
+    }
+}
+
+impl From<FormatVersion> for u8 {
+    fn from(version: FormatVersion) -> u8 {
+        version.as_u8()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_version_bytes() {
+        for byte in 0..=u8::MAX {
+            match FormatVersion::from_u8(byte) {
+                Some(version) => assert_eq!(version.as_u8(), byte),
+                None => assert_eq!(
+                    FormatVersion::try_from(byte),
+                    Err(DecodeError::UnsupportedVersion { version: byte })
+                ),
+            }
+        }
+        assert_eq!(FormatVersion::LATEST.as_u8(), crate::limits::MAX_FORMAT_VERSION);
+        assert!(!FormatVersion::V1.has_header_flags());
+        assert!(FormatVersion::V2.has_header_flags());
+        assert!(!FormatVersion::V2.has_value_clocks());
+        assert!(FormatVersion::V3.has_header_flags() && FormatVersion::V3.has_value_clocks());
+
+        let mut edit = Edit::new([1u8; 16]);
+        assert_eq!(FormatVersion::required_for(&edit), FormatVersion::V1);
+        edit.provenance = Some(Default::default());
+        assert_eq!(FormatVersion::required_for(&edit), FormatVersion::V1);
+        edit.provenance.as_mut().unwrap().parents.push([2u8; 16]);
+        assert_eq!(FormatVersion::required_for(&edit), FormatVersion::V2);
+
+        let mut update = crate::model::UpdateEntity::new([5u8; 16]);
+        update.remove_values.push([6u8; 16]);
+        let mut removal = Edit::new([1u8; 16]);
+        removal.ops.push(crate::model::Op::UpdateEntity(update));
+        assert_eq!(FormatVersion::required_for(&removal), FormatVersion::V2);
+
+        edit.ops.push(crate::model::Op::CreateEntity(crate::model::CreateEntity {
+            id: [3u8; 16],
+            values: vec![crate::model::PropertyValue {
+                property: [4u8; 16],
+                value: crate::model::Value::Bool(true),
+                clock: Some(1),
+            }],
+        }));
+        assert_eq!(FormatVersion::required_for(&edit), FormatVersion::V3);
+    }
+}
@@ -0,0 +1,689 @@
+//! Compression backends and dictionary compression for edits.
+//!
+//! A compressed edit carries its algorithm in the fifth magic byte, so
+//! decoders dispatch on the magic alone:
+//!
+//! ```text
+//! GRC2Z | uncompressed_size: varint | zstd data
+//! GRC2L | uncompressed_size: varint | LZ4 block
+//! GRC2N | uncompressed_size: varint | raw GRC2 bytes
+//! GRC2D | dictionary_id: varint | uncompressed_size: varint | zstd data
+//! ```
+//!
+//! zstd (the default) and LZ4 are behind the `zstd` and `lz4` cargo
+//! features; the no-op passthrough is always available, for targets that
+//! can carry neither but still want a uniform framing. Encoding or
+//! decoding with a backend that isn't compiled in fails with a
+//! `CompressionUnavailable` error. Choose a backend with
+//! [`CompressionOptions`].
+//!
+//! # Dictionaries
+//!
+//! Typical app writes encode to well under 2 KB, where plain zstd spends
+//! most of its output on frame overhead and never warms up. A dictionary
+//! trained on a sample of representative edits primes the compressor with
+//! the IDs, strings, and byte patterns those edits share, so even a
+//! single small edit compresses well.
+//!
+//! Dictionary-compressed edits use their own magic (`GRC2D`) so that a
+//! decoder without the dictionary fails clearly instead of producing
+//! garbage. The dictionary itself is never sent with the edit; producers
+//! and consumers distribute it out of band and look it up by ID.
+//! Dictionary support requires the `zstd` feature.
+
+#[cfg(feature = "zstd")]
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "zstd")]
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "zstd")]
+use crate::codec::edit::{decode_edit_owned, DecodeOptions, EditStream};
+use crate::codec::edit::{encode_edit_with_options, EncodeOptions};
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError};
+#[cfg(feature = "zstd")]
+use crate::limits::MAX_COMPRESSION_DICT_SIZE;
+use crate::limits::{MAGIC_COMPRESSED_DICT, MAGIC_UNCOMPRESSED, MAX_EDIT_SIZE};
+use crate::model::Edit;
+
+// =============================================================================
+// BACKENDS
+// =============================================================================
+
+/// Compression algorithm of a compressed edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionAlgorithm {
+    /// No compression; the payload is the raw `GRC2` edit.
+    None,
+    /// zstd (requires the `zstd` feature).
+    Zstd,
+    /// LZ4 block format (requires the `lz4` feature).
+    Lz4,
+}
+
+impl CompressionAlgorithm {
+    /// Returns the fifth magic byte identifying this algorithm.
+    pub const fn magic_byte(self) -> u8 {
+        match self {
+            CompressionAlgorithm::None => b'N',
+            CompressionAlgorithm::Zstd => b'Z',
+            CompressionAlgorithm::Lz4 => b'L',
+        }
+    }
+
+    /// Returns the algorithm for a fifth magic byte.
+    pub fn from_magic_byte(byte: u8) -> Option<Self> {
+        match byte {
+            b'N' => Some(CompressionAlgorithm::None),
+            b'Z' => Some(CompressionAlgorithm::Zstd),
+            b'L' => Some(CompressionAlgorithm::Lz4),
+            _ => None,
+        }
+    }
+
+    /// Returns the full five-byte magic for this algorithm.
+    pub const fn magic(self) -> [u8; 5] {
+        [b'G', b'R', b'C', b'2', self.magic_byte()]
+    }
+
+    /// Returns true if this backend is compiled in.
+    pub const fn is_available(self) -> bool {
+        match self {
+            CompressionAlgorithm::None => true,
+            CompressionAlgorithm::Zstd => cfg!(feature = "zstd"),
+            CompressionAlgorithm::Lz4 => cfg!(feature = "lz4"),
+        }
+    }
+}
+
+/// Options for compressing edits.
+#[derive(Debug, Clone, Copy)]
+pub struct CompressionOptions {
+    /// Backend to compress with.
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level; only zstd uses it.
+    pub level: i32,
+    /// Options for encoding the edit before compression.
+    pub encode: EncodeOptions,
+}
+
+impl Default for CompressionOptions {
+    /// zstd at level 3 if available, otherwise LZ4, otherwise passthrough.
+    fn default() -> Self {
+        let algorithm = if CompressionAlgorithm::Zstd.is_available() {
+            CompressionAlgorithm::Zstd
+        } else if CompressionAlgorithm::Lz4.is_available() {
+            CompressionAlgorithm::Lz4
+        } else {
+            CompressionAlgorithm::None
+        };
+        Self { algorithm, level: DEFAULT_ZSTD_LEVEL, encode: EncodeOptions::default() }
+    }
+}
+
+impl CompressionOptions {
+    /// Creates options for the given backend with default level and encoding.
+    pub fn new(algorithm: CompressionAlgorithm) -> Self {
+        Self { algorithm, ..Self::default() }
+    }
+
+    /// Creates zstd options at the given level.
+    pub fn zstd(level: i32) -> Self {
+        Self { algorithm: CompressionAlgorithm::Zstd, level, ..Self::default() }
+    }
+
+    /// Creates LZ4 options.
+    pub fn lz4() -> Self {
+        Self::new(CompressionAlgorithm::Lz4)
+    }
+
+    /// Creates passthrough options.
+    pub fn none() -> Self {
+        Self::new(CompressionAlgorithm::None)
+    }
+
+    /// Sets the compression level.
+    pub fn with_level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the encoding options.
+    pub fn with_encode_options(mut self, encode: EncodeOptions) -> Self {
+        self.encode = encode;
+        self
+    }
+}
+
+/// zstd level used by [`CompressionOptions::default`].
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Encodes an edit and compresses it with the configured backend.
+pub fn encode_edit_with_compression(
+    edit: &Edit,
+    options: CompressionOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    let uncompressed = encode_edit_with_options(edit, options.encode)?;
+    compress(&uncompressed, options.algorithm, options.level)
+}
+
+/// Encodes an edit as `GRC2Z` straight into `out`, returning `out`.
+///
+/// The output matches [`encode_edit_with_compression`] with zstd, but the
+/// ops are encoded in pieces and piped through zstd's stream encoder
+/// instead of being encoded into one buffer and compressed from there, so
+/// peak memory no longer holds the whole uncompressed edit. The ops are
+/// encoded twice: the uncompressed size precedes the zstd data.
+#[cfg(feature = "zstd")]
+pub fn encode_edit_compressed_to_writer<W: std::io::Write>(
+    edit: &Edit,
+    level: i32,
+    options: EncodeOptions,
+    mut out: W,
+) -> Result<W, EncodeError> {
+    use std::io::Write;
+
+    let io_error = |e: std::io::Error| EncodeError::CompressionFailed(e.to_string());
+    let stream = EditStream::new(edit, options)?;
+
+    let mut header = Writer::with_capacity(5 + 10);
+    header.write_bytes(&CompressionAlgorithm::Zstd.magic());
+    header.write_varint(stream.len() as u64);
+    out.write_all(header.as_bytes()).map_err(io_error)?;
+
+    let mut encoder = zstd::stream::Encoder::new(out, level).map_err(io_error)?;
+    encoder.set_pledged_src_size(Some(stream.len() as u64)).map_err(io_error)?;
+    stream.write(|chunk| encoder.write_all(chunk).map_err(io_error))?;
+    encoder.finish().map_err(io_error)
+}
+
+/// Frames already-encoded `GRC2` bytes with the given backend.
+pub fn compress(
+    uncompressed: &[u8],
+    algorithm: CompressionAlgorithm,
+    level: i32,
+) -> Result<Vec<u8>, EncodeError> {
+    let compressed = compress_payload(uncompressed, algorithm, level)?;
+
+    let mut writer = Writer::with_capacity(5 + 10 + compressed.len());
+    writer.write_bytes(&algorithm.magic());
+    writer.write_varint(uncompressed.len() as u64);
+    writer.write_bytes(&compressed);
+
+    Ok(writer.into_bytes())
+}
+
+/// Returns the algorithm of a compressed edit, or `None` if `input` isn't
+/// a `GRC2Z`/`GRC2L`/`GRC2N` edit.
+pub fn compression_algorithm(input: &[u8]) -> Option<CompressionAlgorithm> {
+    if input.len() < 5 || &input[0..4] != MAGIC_UNCOMPRESSED {
+        return None;
+    }
+    CompressionAlgorithm::from_magic_byte(input[4])
+}
+
+/// Decompresses a `GRC2Z`/`GRC2L`/`GRC2N` payload (everything after the
+/// magic) with the given backend.
+pub(crate) fn decompress_payload(
+    payload: &[u8],
+    algorithm: CompressionAlgorithm,
+) -> Result<Vec<u8>, DecodeError> {
+    let (declared_size, data) = read_uncompressed_size(payload)?;
+    let mut decompressed = vec![0; declared_size];
+    decompress_into(data, algorithm, &mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Splits a `GRC2Z`/`GRC2L`/`GRC2N` payload into its declared uncompressed
+/// size and the compressed data.
+pub(crate) fn read_uncompressed_size(payload: &[u8]) -> Result<(usize, &[u8]), DecodeError> {
+    let mut reader = Reader::new(payload);
+    let declared_size = reader.read_varint("uncompressed_size")? as usize;
+
+    if declared_size > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "uncompressed_size",
+            len: declared_size,
+            max: MAX_EDIT_SIZE,
+        });
+    }
+    Ok((declared_size, reader.remaining()))
+}
+
+/// Decompresses `data` into `out`, which is sized to the declared
+/// uncompressed size.
+pub(crate) fn decompress_into(
+    data: &[u8],
+    algorithm: CompressionAlgorithm,
+    out: &mut [u8],
+) -> Result<(), DecodeError> {
+    let actual = match algorithm {
+        CompressionAlgorithm::None => {
+            if data.len() == out.len() {
+                out.copy_from_slice(data);
+            }
+            data.len()
+        }
+        #[cfg(feature = "zstd")]
+        CompressionAlgorithm::Zstd => {
+            use std::io::Read;
+
+            let failed = |e: std::io::Error| DecodeError::DecompressionFailed(e.to_string());
+            let mut decoder = zstd::Decoder::new(data).map_err(failed)?;
+            let mut filled = 0;
+            while filled < out.len() {
+                match decoder.read(&mut out[filled..]).map_err(failed)? {
+                    0 => break,
+                    n => filled += n,
+                }
+            }
+            // Read one byte past the declared size so a decompression bomb
+            // fails the size check instead of being silently truncated
+            filled + decoder.read(&mut [0u8]).map_err(failed)?
+        }
+        #[cfg(feature = "lz4")]
+        CompressionAlgorithm::Lz4 => lz4_flex::block::decompress_into(data, out)
+            .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?,
+        #[allow(unreachable_patterns)]
+        _ => return Err(DecodeError::CompressionUnavailable { algorithm }),
+    };
+
+    if actual != out.len() {
+        return Err(DecodeError::UncompressedSizeMismatch { declared: out.len(), actual });
+    }
+    Ok(())
+}
+
+fn compress_payload(
+    uncompressed: &[u8],
+    algorithm: CompressionAlgorithm,
+    #[allow(unused_variables)] level: i32,
+) -> Result<Vec<u8>, EncodeError> {
+    match algorithm {
+        CompressionAlgorithm::None => Ok(uncompressed.to_vec()),
+        #[cfg(feature = "zstd")]
+        CompressionAlgorithm::Zstd => zstd::encode_all(uncompressed, level)
+            .map_err(|e| EncodeError::CompressionFailed(e.to_string())),
+        #[cfg(feature = "lz4")]
+        CompressionAlgorithm::Lz4 => Ok(lz4_flex::block::compress(uncompressed)),
+        #[allow(unreachable_patterns)]
+        _ => Err(EncodeError::CompressionUnavailable { algorithm }),
+    }
+}
+
+// =============================================================================
+// DICTIONARIES
+// =============================================================================
+
+/// Default maximum size of a trained dictionary (16 KB).
+#[cfg(feature = "zstd")]
+pub const DEFAULT_DICT_SIZE: usize = 16 * 1024;
+
+/// Magic number at the start of a zstd dictionary with a header.
+#[cfg(feature = "zstd")]
+const ZSTD_DICT_MAGIC: u32 = 0xEC30_A437;
+
+/// Trains a zstd dictionary of up to [`DEFAULT_DICT_SIZE`] bytes from
+/// encoded edits.
+///
+/// Samples should be uncompressed (`GRC2`) edits representative of the
+/// traffic the dictionary will be used for. zstd needs a reasonable number
+/// of samples (hundreds or more) and fails if given too few.
+#[cfg(feature = "zstd")]
+pub fn train_dictionary(samples: &[&[u8]]) -> Result<Vec<u8>, EncodeError> {
+    train_dictionary_with_size(samples, DEFAULT_DICT_SIZE)
+}
+
+/// Trains a zstd dictionary of up to `max_size` bytes from encoded edits.
+#[cfg(feature = "zstd")]
+pub fn train_dictionary_with_size(
+    samples: &[&[u8]],
+    max_size: usize,
+) -> Result<Vec<u8>, EncodeError> {
+    if max_size > MAX_COMPRESSION_DICT_SIZE {
+        return Err(EncodeError::LengthExceedsLimit {
+            field: "dictionary",
+            len: max_size,
+            max: MAX_COMPRESSION_DICT_SIZE,
+        });
+    }
+    zstd::dict::from_samples(samples, max_size)
+        .map_err(|e| EncodeError::DictionaryTrainingFailed(e.to_string()))
+}
+
+/// A trained zstd dictionary and the ID edits use to refer to it.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionDictionary {
+    id: u32,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "zstd")]
+impl CompressionDictionary {
+    /// Wraps dictionary bytes, taking the ID from the zstd dictionary header.
+    ///
+    /// Raw-content dictionaries (no header, or a header ID of 0) get an ID
+    /// derived from the first four bytes of the SHA-256 of their content.
+    pub fn new(data: Vec<u8>) -> Self {
+        let id = header_id(&data).unwrap_or_else(|| content_id(&data));
+        Self { id, data }
+    }
+
+    /// Wraps dictionary bytes under an explicit ID.
+    pub fn with_id(id: u32, data: Vec<u8>) -> Self {
+        Self { id, data }
+    }
+
+    /// Returns the dictionary ID.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the dictionary bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Encodes an edit and compresses it with a trained dictionary.
+///
+/// The output starts with `GRC2D` and carries the dictionary ID; it can
+/// only be decoded by [`decode_edit_with_dict`] given the same dictionary.
+#[cfg(feature = "zstd")]
+pub fn encode_edit_compressed_with_dict(
+    edit: &Edit,
+    level: i32,
+    dict: &CompressionDictionary,
+) -> Result<Vec<u8>, EncodeError> {
+    encode_edit_compressed_with_dict_options(edit, level, dict, EncodeOptions::default())
+}
+
+/// Encodes an edit with options and compresses it with a trained dictionary.
+#[cfg(feature = "zstd")]
+pub fn encode_edit_compressed_with_dict_options(
+    edit: &Edit,
+    level: i32,
+    dict: &CompressionDictionary,
+    options: EncodeOptions,
+) -> Result<Vec<u8>, EncodeError> {
+    let uncompressed = encode_edit_with_options(edit, options)?;
+
+    let compressed = zstd::bulk::Compressor::with_dictionary(level, dict.as_bytes())
+        .and_then(|mut c| c.compress(&uncompressed))
+        .map_err(|e| EncodeError::CompressionFailed(e.to_string()))?;
+
+    let mut writer = Writer::with_capacity(5 + 5 + 10 + compressed.len());
+    writer.write_bytes(MAGIC_COMPRESSED_DICT);
+    writer.write_varint(dict.id() as u64);
+    writer.write_varint(uncompressed.len() as u64);
+    writer.write_bytes(&compressed);
+
+    Ok(writer.into_bytes())
+}
+
+/// Returns the dictionary ID of a `GRC2D` edit, or `None` for any other
+/// framing.
+pub fn edit_dictionary_id(input: &[u8]) -> Result<Option<u32>, DecodeError> {
+    if input.len() < 5 || &input[0..5] != MAGIC_COMPRESSED_DICT {
+        return Ok(None);
+    }
+    let mut reader = Reader::new(&input[5..]);
+    let id = reader.read_varint("dictionary_id")?;
+    let id = u32::try_from(id).map_err(|_| DecodeError::MalformedEncoding {
+        context: "dictionary_id exceeds u32",
+    })?;
+    Ok(Some(id))
+}
+
+/// Decompresses a `GRC2D` edit using whichever of `dicts` it was
+/// compressed with, returning the uncompressed `GRC2` bytes.
+#[cfg(feature = "zstd")]
+pub fn decompress_with_dict(
+    input: &[u8],
+    dicts: &[CompressionDictionary],
+) -> Result<Vec<u8>, DecodeError> {
+    let Some(id) = edit_dictionary_id(input)? else {
+        if input.len() < 4 {
+            return Err(DecodeError::UnexpectedEof { context: "magic" });
+        }
+        let mut found = [0u8; 4];
+        found.copy_from_slice(&input[0..4]);
+        return Err(DecodeError::InvalidMagic { found });
+    };
+    let dict = dicts
+        .iter()
+        .find(|d| d.id() == id)
+        .ok_or(DecodeError::UnknownDictionary { id })?;
+
+    let mut reader = Reader::new(&input[5..]);
+    reader.read_varint("dictionary_id")?;
+    let declared_size = reader.read_varint("uncompressed_size")? as usize;
+    if declared_size > MAX_EDIT_SIZE {
+        return Err(DecodeError::LengthExceedsLimit {
+            field: "uncompressed_size",
+            len: declared_size,
+            max: MAX_EDIT_SIZE,
+        });
+    }
+
+    let decompressed = zstd::bulk::Decompressor::with_dictionary(dict.as_bytes())
+        .and_then(|mut d| d.decompress(reader.remaining(), declared_size))
+        .map_err(|e| DecodeError::DecompressionFailed(e.to_string()))?;
+
+    if decompressed.len() != declared_size {
+        return Err(DecodeError::UncompressedSizeMismatch {
+            declared: declared_size,
+            actual: decompressed.len(),
+        });
+    }
+
+    Ok(decompressed)
+}
+
+/// Decodes an edit in any framing, using `dicts` for `GRC2D` edits.
+///
+/// `GRC2` and `GRC2Z` input is passed through to
+/// [`decode_edit`](crate::codec::decode_edit). For zero-copy decoding of
+/// dictionary-compressed data, call [`decompress_with_dict`] first.
+#[cfg(feature = "zstd")]
+pub fn decode_edit_with_dict<'a>(
+    input: &'a [u8],
+    dicts: &[CompressionDictionary],
+) -> Result<Edit<'a>, DecodeError> {
+    if edit_dictionary_id(input)?.is_none() {
+        return crate::codec::decode_edit(input);
+    }
+    let decompressed = decompress_with_dict(input, dicts)?;
+    if &decompressed[0..4.min(decompressed.len())] != MAGIC_UNCOMPRESSED {
+        return Err(DecodeError::MalformedEncoding { context: "decompressed edit magic" });
+    }
+    decode_edit_owned(&decompressed, DecodeOptions::new())
+}
+
+/// Reads the ID from a zstd dictionary header; 0 means "no ID".
+#[cfg(feature = "zstd")]
+fn header_id(data: &[u8]) -> Option<u32> {
+    if data.len() < 8 || u32::from_le_bytes(data[0..4].try_into().unwrap()) != ZSTD_DICT_MAGIC {
+        return None;
+    }
+    let id = u32::from_le_bytes(data[4..8].try_into().unwrap());
+    (id != 0).then_some(id)
+}
+
+#[cfg(feature = "zstd")]
+fn content_id(data: &[u8]) -> u32 {
+    let hash = Sha256::digest(data);
+    u32::from_le_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, decompress, encode_edit};
+    use crate::model::EditBuilder;
+
+    fn small_edit(i: u8) -> Edit<'static> {
+        EditBuilder::new([i; 16])
+            .name("app write")
+            .create_entity([i.wrapping_add(1); 16], |e| {
+                e.text([1u8; 16], format!("Entity {}", i), None).int64([2u8; 16], i as i64, None)
+            })
+            .build()
+    }
+
+    #[test]
+    fn test_backend_roundtrips() {
+        let edit = small_edit(1);
+        let plain = encode_edit(&edit).unwrap();
+
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Lz4,
+        ] {
+            if !algorithm.is_available() {
+                continue;
+            }
+            let bytes =
+                encode_edit_with_compression(&edit, CompressionOptions::new(algorithm)).unwrap();
+            assert_eq!(bytes[0..5], algorithm.magic());
+            assert_eq!(compression_algorithm(&bytes), Some(algorithm));
+            assert_eq!(decode_edit(&bytes).unwrap(), edit);
+            assert_eq!(decompress(&bytes).unwrap(), plain);
+        }
+
+        assert_eq!(compression_algorithm(&plain), None);
+        let passthrough = compress(&plain, CompressionAlgorithm::None, 0).unwrap();
+        assert_eq!(passthrough.len(), plain.len() + 5 + 1);
+    }
+
+    #[test]
+    fn test_unavailable_backend_is_reported() {
+        let edit = small_edit(2);
+        for algorithm in [CompressionAlgorithm::Zstd, CompressionAlgorithm::Lz4] {
+            if algorithm.is_available() {
+                continue;
+            }
+            assert_eq!(
+                encode_edit_with_compression(&edit, CompressionOptions::new(algorithm)),
+                Err(EncodeError::CompressionUnavailable { algorithm })
+            );
+            let mut bytes = algorithm.magic().to_vec();
+            bytes.extend_from_slice(&[0]);
+            assert_eq!(decode_edit(&bytes), Err(DecodeError::CompressionUnavailable { algorithm }));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_streaming_compression() {
+        use std::io::Write;
+
+        // Enough ops for several stream chunks, with repeated strings
+        let mut builder = EditBuilder::new([9u8; 16]).name("bulk import").author([7u8; 16]);
+        for i in 0..4000u32 {
+            let mut id = [0xA0; 16];
+            id[..4].copy_from_slice(&i.to_be_bytes());
+            builder = builder.create_entity(id, |e| {
+                e.text([1u8; 16], format!("Entity number {} of the import", i), None)
+                    .text([3u8; 16], "imported", None)
+                    .int64([2u8; 16], i as i64, None)
+            });
+        }
+        let edit = builder.build();
+
+        for options in [
+            EncodeOptions::new(),
+            EncodeOptions::new().with_insertion_order(),
+            EncodeOptions::new().with_interned_strings().with_columnar().with_checksum(),
+            EncodeOptions::canonical().with_checksum(),
+        ] {
+            let mut out = b"prefix".to_vec();
+            out = encode_edit_compressed_to_writer(&edit, 3, options, out).unwrap();
+            let bytes = &out[6..];
+            assert_eq!(compression_algorithm(bytes), Some(CompressionAlgorithm::Zstd));
+            let plain = encode_edit_with_options(&edit, options).unwrap();
+            assert!(plain.len() > 2 * crate::codec::edit::STREAM_CHUNK_SIZE);
+            assert_eq!(decompress(bytes).unwrap(), plain);
+            assert_eq!(decode_edit(bytes).unwrap(), decode_edit(&plain).unwrap());
+        }
+
+        // Write errors are reported
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(matches!(
+            encode_edit_compressed_to_writer(&edit, 3, EncodeOptions::new(), Full),
+            Err(EncodeError::CompressionFailed(_))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    fn trained() -> CompressionDictionary {
+        let samples: Vec<Vec<u8>> = (0..64).map(|i| encode_edit(&small_edit(i)).unwrap()).collect();
+        let refs: Vec<&[u8]> = samples.iter().map(Vec::as_slice).collect();
+        CompressionDictionary::new(train_dictionary(&refs).unwrap())
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_dictionary_roundtrip() {
+        let dict = trained();
+        let edit = small_edit(200);
+
+        let bytes = encode_edit_compressed_with_dict(&edit, 3, &dict).unwrap();
+        assert_eq!(&bytes[0..5], MAGIC_COMPRESSED_DICT);
+        assert_eq!(edit_dictionary_id(&bytes).unwrap(), Some(dict.id()));
+
+        let other = CompressionDictionary::with_id(dict.id().wrapping_add(1), vec![0u8; 64]);
+        let decoded = decode_edit_with_dict(&bytes, &[other.clone(), dict.clone()]).unwrap();
+        assert_eq!(decoded, edit);
+
+        let uncompressed = decompress_with_dict(&bytes, &[dict]).unwrap();
+        assert_eq!(decode_edit(&uncompressed).unwrap(), edit);
+
+        // Other framings pass through
+        let plain = crate::codec::encode_edit_compressed(&edit, 3).unwrap();
+        assert_eq!(decode_edit_with_dict(&plain, &[]).unwrap(), edit);
+        assert_eq!(edit_dictionary_id(&plain).unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_missing_dictionary_is_reported() {
+        let dict = trained();
+        let bytes = encode_edit_compressed_with_dict(&small_edit(7), 3, &dict).unwrap();
+
+        assert_eq!(decode_edit(&bytes), Err(DecodeError::UnknownDictionary { id: dict.id() }));
+        assert_eq!(
+            decode_edit_with_dict(&bytes, &[]),
+            Err(DecodeError::UnknownDictionary { id: dict.id() })
+        );
+        assert!(matches!(
+            train_dictionary(&[b"GRC2".as_slice()]),
+            Err(EncodeError::DictionaryTrainingFailed(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_dictionary_id_from_header() {
+        let mut data = ZSTD_DICT_MAGIC.to_le_bytes().to_vec();
+        data.extend_from_slice(&42u32.to_le_bytes());
+        data.extend_from_slice(b"content");
+        assert_eq!(CompressionDictionary::new(data).id(), 42);
+
+        let raw = CompressionDictionary::new(b"raw content".to_vec());
+        assert_eq!(raw.id(), content_id(b"raw content"));
+    }
+}
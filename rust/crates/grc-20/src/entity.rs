@@ -0,0 +1,207 @@
+//! Mapping between application structs and entities.
+//!
+//! [`Grc20Entity`] maps the fields of a struct to property values. It is
+//! usually derived with `#[derive(Grc20Entity)]` from the `grc-20-derive`
+//! crate (re-exported here with the `derive` feature):
+//!
+//! ```text
+//! #[derive(Grc20Entity)]
+//! #[grc20(type_id = "b1b2c3d4e5f6071829304050a1b2c3d4")]
+//! struct City {
+//!     #[grc20(id)]
+//!     id: Id,
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d4")]
+//!     name: String,
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d7")]
+//!     population: Option<i64>,
+//!     #[grc20(property = "a1b2c3d4e5f6071829304050a1b2c3d9", data_type = "Datetime")]
+//!     founded: Option<String>,
+//! }
+//! ```
+//!
+//! The provided methods turn a struct into [`CreateEntity`] and
+//! [`UpdateEntity`] ops, and [`GraphState::load`] and
+//! [`GraphState::load_all`] read structs back from resolved state.
+
+use crate::genesis::relation_types;
+use crate::model::id::unique_relation_id;
+use crate::model::{CreateEntity, CreateRelation, Id, Op, PropertyValue, UnsetValue, UpdateEntity};
+use crate::state::{EntityState, GraphState};
+
+#[cfg(feature = "derive")]
+pub use grc_20_derive::Grc20Entity;
+
+/// A struct whose fields map to the property values of an entity.
+///
+/// Only values without a language are mapped: TEXT fields read and write
+/// the default-language slot.
+pub trait Grc20Entity: Sized {
+    /// Type that [`to_ops`](Self::to_ops) assigns and
+    /// [`GraphState::load_all`] looks for, if any.
+    const TYPE_ID: Option<Id>;
+
+    /// Mapped properties, in field order.
+    const PROPERTIES: &'static [Id];
+
+    /// Returns the entity ID.
+    fn entity_id(&self) -> Id;
+
+    /// Returns the values of the fields that are set, in field order.
+    fn to_values(&self) -> Vec<PropertyValue<'static>>;
+
+    /// Reads the fields from resolved state.
+    ///
+    /// Returns None if a required field is missing or has another data
+    /// type. Optional fields with another data type read as unset.
+    fn from_entity(id: Id, entity: &EntityState) -> Option<Self>;
+
+    /// Returns a CreateEntity op with the set fields.
+    fn to_create(&self) -> CreateEntity<'static> {
+        CreateEntity { id: self.entity_id(), values: self.to_values() }
+    }
+
+    /// Returns ops creating the entity and, if [`TYPE_ID`](Self::TYPE_ID)
+    /// is set, its Types relation.
+    fn to_ops(&self) -> Vec<Op<'static>> {
+        let mut ops = vec![Op::CreateEntity(self.to_create())];
+        if let Some(type_id) = Self::TYPE_ID {
+            let id = self.entity_id();
+            let types = relation_types::types();
+            ops.push(Op::CreateRelation(CreateRelation {
+                id: unique_relation_id(&id, &type_id, &types),
+                relation_type: types,
+                from: id,
+                from_is_value_ref: false,
+                from_space: None,
+                from_version: None,
+                to: type_id,
+                to_is_value_ref: false,
+                to_space: None,
+                to_version: None,
+                entity: None,
+                position: None,
+            }));
+        }
+        ops
+    }
+
+    /// Returns an UpdateEntity op that sets the set fields and unsets (in
+    /// every language) the mapped properties of unset fields.
+    fn to_update(&self) -> UpdateEntity<'static> {
+        let set_properties = self.to_values();
+        let unset_values = Self::PROPERTIES
+            .iter()
+            .filter(|p| !set_properties.iter().any(|v| v.property == **p))
+            .map(|p| UnsetValue::all(*p))
+            .collect();
+        UpdateEntity {
+            id: self.entity_id(),
+            set_properties,
+            unset_values,
+            remove_values: Vec::new(),
+        }
+    }
+}
+
+impl GraphState {
+    /// Reads a live entity as `T`.
+    ///
+    /// Returns None if the entity is unknown or tombstoned, or does not
+    /// have `T`'s required fields.
+    pub fn load<T: Grc20Entity>(&self, id: &Id) -> Option<T> {
+        self.entity(id).filter(|e| !e.deleted).and_then(|e| T::from_entity(*id, e))
+    }
+
+    /// Reads every live entity that maps to `T`, in ID order.
+    ///
+    /// If `T` has a type, only its instances (including those of its
+    /// subtypes) are read; otherwise every entity with `T`'s required
+    /// fields is.
+    pub fn load_all<T: Grc20Entity>(&self) -> Vec<T> {
+        let mut ids: Vec<Id> = match T::TYPE_ID {
+            Some(type_id) => self.query().of_type(type_id).ids().collect(),
+            None => self.entities().map(|(id, _)| *id).collect(),
+        };
+        ids.sort_unstable();
+        ids.iter().filter_map(|id| self.load(id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EditBuilder, Value};
+
+    const NAME: Id = [1u8; 16];
+    const AGE: Id = [2u8; 16];
+    const PERSON: Id = [3u8; 16];
+
+    /// A hand-written mapping, as `#[derive(Grc20Entity)]` would generate.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Person {
+        id: Id,
+        name: String,
+        age: Option<i64>,
+    }
+
+    impl Grc20Entity for Person {
+        const TYPE_ID: Option<Id> = Some(PERSON);
+        const PROPERTIES: &'static [Id] = &[NAME, AGE];
+
+        fn entity_id(&self) -> Id {
+            self.id
+        }
+
+        fn to_values(&self) -> Vec<PropertyValue<'static>> {
+            let mut values = vec![PropertyValue {
+                property: NAME,
+                value: Value::Text { value: self.name.clone().into(), language: None },
+                clock: None,
+            }];
+            if let Some(age) = self.age {
+                values.push(PropertyValue {
+                    property: AGE,
+                    value: Value::Int64 { value: age, unit: None },
+                    clock: None,
+                });
+            }
+            values
+        }
+
+        fn from_entity(id: Id, entity: &EntityState) -> Option<Self> {
+            let name = match entity.value(&NAME, None)? {
+                Value::Text { value, .. } => value.to_string(),
+                _ => return None,
+            };
+            let age = match entity.value(&AGE, None) {
+                Some(Value::Int64 { value, .. }) => Some(*value),
+                _ => None,
+            };
+            Some(Self { id, name, age })
+        }
+    }
+
+    #[test]
+    fn test_create_update_and_load() {
+        let alice = Person { id: [0xA1; 16], name: "Alice".to_string(), age: Some(30) };
+        let mut state = GraphState::new();
+        for op in alice.to_ops() {
+            state.apply_op(&op);
+        }
+        // Not a Person: no type, and no name
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_entity([0xB0; 16], |e| e.int64(AGE, 5, None))
+            .build();
+        state.apply_edit(&edit);
+
+        assert_eq!(state.load::<Person>(&alice.id), Some(alice.clone()));
+        assert_eq!(state.load::<Person>(&[0xB0; 16]), None);
+        assert_eq!(state.load_all::<Person>(), vec![alice.clone()]);
+
+        let older = Person { age: None, name: "Alice B.".to_string(), ..alice };
+        let update = older.to_update();
+        assert_eq!(update.unset_values, vec![UnsetValue::all(AGE)]);
+        state.apply_op(&Op::UpdateEntity(update));
+        assert_eq!(state.load::<Person>(&older.id), Some(older));
+    }
+}
@@ -1,8 +1,23 @@
 //! Error types for GRC-20 encoding/decoding and validation.
 
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
 use thiserror::Error;
 
+use crate::compression::CompressionAlgorithm;
+#[cfg(feature = "ipfs")]
+use crate::ipfs::Cid;
+#[cfg(feature = "chrono")]
+use crate::model::timestamp::DatePrecision;
 use crate::model::{DataType, Id};
+#[cfg(feature = "std")]
+use crate::validate::schedule::ScheduleIssue;
+use crate::validate::Dimension;
 
 /// Error codes as defined in spec Section 8.3.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -117,29 +132,126 @@ pub enum DecodeError {
     #[error("[E005] malformed encoding: {context}")]
     MalformedEncoding { context: &'static str },
 
+    #[error("[E005] invalid SCHEDULE at line {line}: {reason}")]
+    InvalidSchedule { line: usize, reason: &'static str },
+
     // === Compression errors ===
-    #[error("[E005] zstd decompression failed: {0}")]
+    #[error("[E001] edit is compressed with {algorithm:?}, which is not compiled in")]
+    CompressionUnavailable { algorithm: CompressionAlgorithm },
+
+    #[error("[E005] decompression failed: {0}")]
     DecompressionFailed(String),
 
+    #[error("[E005] edit is compressed with dictionary {id}, which was not provided")]
+    UnknownDictionary { id: u32 },
+
     #[error("[E005] decompressed size {actual} doesn't match declared {declared}")]
     UncompressedSizeMismatch { declared: usize, actual: usize },
 
+    #[error("[E005] checksum mismatch: trailer is {expected:#010x}, content is {actual:#010x}")]
+    ChecksumMismatch { expected: u32, actual: u32 },
+
     #[error("[E005] duplicate ID in {dict} dictionary: {id:?}")]
     DuplicateDictionaryEntry { dict: &'static str, id: Id },
+
+    #[error("[E005] {field} ID {id:?} is not a valid UUID: {reason}")]
+    InvalidId {
+        field: &'static str,
+        id: Id,
+        reason: &'static str,
+    },
+
+    #[error("[E005] delta was made against a different version of edit {base:?}")]
+    DeltaBaseMismatch { base: Id },
+
+    // === Location ===
+    /// Where in an edit another error occurred. Use [`kind`](Self::kind)
+    /// to match on the underlying error.
+    #[error("{source} ({})", location(.context, .op_index, .offset))]
+    At {
+        /// Section of the edit: `"header"`, `"dictionaries"`, or `"ops"`.
+        context: &'static str,
+        /// Byte offset into the uncompressed edit where the error was
+        /// detected.
+        offset: usize,
+        /// Index of the op (or first op of a batch record) being decoded.
+        op_index: Option<usize>,
+        source: Box<DecodeError>,
+    },
+}
+
+/// Formats the location of a [`DecodeError::At`].
+fn location(context: &str, op_index: &Option<usize>, offset: &usize) -> String {
+    match op_index {
+        Some(index) => format!("op {}, byte {}", index, offset),
+        None => format!("{}, byte {}", context, offset),
+    }
 }
 
 impl DecodeError {
     /// Returns the error code for this error.
     pub fn code(&self) -> ErrorCode {
         match self {
-            DecodeError::InvalidMagic { .. } | DecodeError::UnsupportedVersion { .. } => {
+            DecodeError::InvalidMagic { .. }
+            | DecodeError::UnsupportedVersion { .. }
+            | DecodeError::CompressionUnavailable { .. } => {
                 ErrorCode::InvalidMagicOrVersion
             }
             DecodeError::IndexOutOfBounds { .. } => ErrorCode::IndexOutOfBounds,
             DecodeError::InvalidUtf8 { .. } => ErrorCode::InvalidUtf8,
+            DecodeError::At { source, .. } => source.code(),
             _ => ErrorCode::MalformedEncoding,
         }
     }
+
+    /// Returns the underlying error, without its location.
+    pub fn kind(&self) -> &DecodeError {
+        match self {
+            DecodeError::At { source, .. } => source.kind(),
+            _ => self,
+        }
+    }
+
+    /// Returns the byte offset into the uncompressed edit where decoding
+    /// failed, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DecodeError::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the op that failed to decode, if the error is
+    /// in the ops section.
+    pub fn op_index(&self) -> Option<usize> {
+        match self {
+            DecodeError::At { op_index, source, .. } => op_index.or_else(|| source.op_index()),
+            _ => None,
+        }
+    }
+
+    /// Describes the error from the outermost location inwards, ending
+    /// with the underlying error, e.g. `["op 1234, byte 5678", "[E005]
+    /// unexpected end of input while reading varint"]`.
+    pub fn context_chain(&self) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut error = self;
+        while let DecodeError::At { context, offset, op_index, source } = error {
+            chain.push(location(context, op_index, offset));
+            error = source;
+        }
+        chain.push(error.to_string());
+        chain
+    }
+
+    /// Wraps the error with its location, keeping the innermost location
+    /// if it already has one.
+    pub(crate) fn at(self, context: &'static str, offset: usize, op_index: Option<usize>) -> Self {
+        match self {
+            DecodeError::At { .. } => self,
+            _ => DecodeError::At { context, offset, op_index, source: Box::new(self) },
+        }
+    }
 }
 
 /// Error during binary encoding.
@@ -159,12 +271,21 @@ pub enum EncodeError {
         data_len: usize,
     },
 
-    #[error("zstd compression failed: {0}")]
+    #[error("compression failed: {0}")]
     CompressionFailed(String),
 
+    #[error("compression backend {algorithm:?} is not compiled in")]
+    CompressionUnavailable { algorithm: CompressionAlgorithm },
+
+    #[error("zstd dictionary training failed: {0}")]
+    DictionaryTrainingFailed(String),
+
     #[error("DECIMAL value is not normalized (has trailing zeros)")]
     DecimalNotNormalized,
 
+    #[error("DECIMAL mantissa bytes are not minimal")]
+    DecimalMantissaNotMinimal,
+
     #[error("float value is NaN")]
     FloatIsNan,
 
@@ -183,6 +304,9 @@ pub enum EncodeError {
     #[error("DATE string is not valid ISO 8601: {reason}")]
     InvalidDate { reason: &'static str },
 
+    #[error("SCHEDULE is not valid RFC 5545 at line {line}: {reason}")]
+    InvalidSchedule { line: usize, reason: &'static str },
+
     #[error("batch entity has {actual} values but schema requires {expected}")]
     BatchEntityValueCountMismatch { expected: usize, actual: usize },
 
@@ -192,11 +316,34 @@ pub enum EncodeError {
     #[error("duplicate author ID in canonical mode: {id:?}")]
     DuplicateAuthor { id: Id },
 
+    #[error("duplicate parent edit ID in canonical mode: {id:?}")]
+    DuplicateParent { id: Id },
+
+    #[error("duplicate provenance metadata key in canonical mode: {key:?}")]
+    DuplicateMetadataKey { key: String },
+
     #[error("duplicate value (property={property:?}, language={language:?}) in canonical mode")]
     DuplicateValue { property: Id, language: Option<Id> },
 
     #[error("duplicate unset property (property={property:?}, language={language:?}) in canonical mode")]
     DuplicateUnset { property: Id, language: Option<Id> },
+
+    #[error("duplicate removed value ID {value_id:?} in canonical mode")]
+    DuplicateRemoveValue { value_id: Id },
+
+    #[error("value for shared property {property:?} is {actual:?} but the shared dictionary declares {shared:?}")]
+    SharedPropertyTypeMismatch {
+        property: Id,
+        shared: DataType,
+        actual: DataType,
+    },
+
+    #[error("value for property {property:?} is {actual:?} but the edit already uses it as {declared:?}")]
+    PropertyTypeConflict {
+        property: Id,
+        declared: DataType,
+        actual: DataType,
+    },
 }
 
 /// Error during semantic validation.
@@ -211,13 +358,392 @@ pub enum ValidationError {
     #[error("relation {relation:?} is dead (tombstoned)")]
     RelationIsDead { relation: Id },
 
+    #[error("{id:?} is created after being deleted in the same edit")]
+    CreateAfterDelete { id: Id },
+
+    #[error("{id:?} is restored but not deleted")]
+    NotDeleted { id: Id },
+
     #[error("property {property:?} not found in schema")]
     PropertyNotFound { property: Id },
 
+    #[error("property {property:?} is single-valued but entity {entity:?} is given multiple values")]
+    CardinalityViolation { entity: Id, property: Id },
+
+    #[error("required property {property:?} removed from entity {entity:?}")]
+    RequiredPropertyRemoved { entity: Id, property: Id },
+
+    #[error("relation {relation:?} {endpoint} entity {entity:?} does not have an allowed type")]
+    RelationEndpointTypeMismatch {
+        relation: Id,
+        endpoint: &'static str,
+        entity: Id,
+    },
+
+    #[error("relation {relation:?} {endpoint} entity {entity:?} has no known type to check")]
+    RelationEndpointTypeUnknown {
+        relation: Id,
+        endpoint: &'static str,
+        entity: Id,
+    },
+
+    #[error("property {property:?} is deprecated (replacement: {replacement:?})")]
+    DeprecatedProperty {
+        property: Id,
+        replacement: Option<Id>,
+    },
+
+    #[error("entity {entity:?} is assigned deprecated type {type_id:?} (replacement: {replacement:?})")]
+    DeprecatedType {
+        entity: Id,
+        type_id: Id,
+        replacement: Option<Id>,
+    },
+
+    #[error("relation {relation:?} of unique relation type {relation_type:?} does not use the unique-mode ID for its endpoints")]
+    RelationNotUnique { relation: Id, relation_type: Id },
+
     #[error("data type mismatch for property {property:?}: schema says {schema:?}, edit declares {declared:?}")]
     DataTypeInconsistent {
         property: Id,
         schema: DataType,
         declared: DataType,
     },
+    #[error("property {property:?} measures {expected:?} but value has no unit")]
+    MissingUnit { property: Id, expected: Dimension },
+
+    #[error("unit {unit:?} on property {property:?} is not registered")]
+    UnknownUnit { property: Id, unit: Id },
+
+    #[error("unit {unit:?} on property {property:?} measures {actual:?}, expected {expected:?}")]
+    UnitDimensionMismatch {
+        property: Id,
+        unit: Id,
+        expected: Dimension,
+        actual: Dimension,
+    },
+
+    #[error("language {language:?} on property {property:?} is not registered")]
+    UnknownLanguage { property: Id, language: Id },
+
+    #[error("space {space:?} is given an empty name")]
+    EmptySpaceName { space: Id },
+
+    #[error("{field} ID {id:?} is not a valid UUID: {reason}")]
+    InvalidId {
+        field: &'static str,
+        id: Id,
+        reason: &'static str,
+    },
+}
+
+/// Error evaluating a SCHEDULE value's recurrence.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum RecurrenceError {
+    #[error("invalid schedule at line {}: {}", .0.line, .0.reason)]
+    Invalid(ScheduleIssue),
+
+    #[error("recurrence did not finish within {max} periods")]
+    TooManyPeriods { max: usize },
+}
+
+#[cfg(feature = "std")]
+impl From<ScheduleIssue> for RecurrenceError {
+    fn from(issue: ScheduleIssue) -> Self {
+        RecurrenceError::Invalid(issue)
+    }
+}
+
+/// Error while extracting or restoring large values.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("blob {path:?} does not match its content hash")]
+    HashMismatch { path: PathBuf },
+
+    #[error("blob {path:?} referenced from a TEXT value is not valid UTF-8")]
+    InvalidUtf8 { path: PathBuf },
+}
+
+/// Error while compacting an edit log.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum CompactError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("compacted log does not reproduce the state: expected hash {expected:02x?}, got {actual:02x?}")]
+    VerificationFailed { expected: [u8; 32], actual: [u8; 32] },
+}
+
+/// Error while syncing a read replica.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum ReplicaError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("state at log position {position} does not match: expected hash {expected:02x?}, got {actual:02x?}")]
+    VerificationFailed { position: u64, expected: [u8; 32], actual: [u8; 32] },
+}
+
+/// Error unpacking an edit from a CAR stream.
+#[cfg(feature = "ipfs")]
+#[derive(Debug, Error)]
+pub enum IpfsError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error("invalid CAR stream: {reason}")]
+    InvalidCar { reason: &'static str },
+
+    #[error("block {cid} does not match its CID")]
+    HashMismatch { cid: Cid },
+
+    #[error("block {cid} is missing")]
+    MissingBlock { cid: Cid },
+}
+
+/// Error that gets an edit received over gossip rejected.
+#[cfg(feature = "p2p")]
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum P2pError {
+    #[error("malformed gossip message: {reason}")]
+    Malformed { reason: &'static str },
+
+    #[error("edit does not match its CID {cid}")]
+    CidMismatch { cid: Cid },
+
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    #[error("edit {cid} rejected by a validation hook")]
+    Rejected { cid: Cid },
+}
+
+/// Error encoding or decoding an edit over async I/O.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Error)]
+pub enum AioError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Error reading or appending to an edit journal.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("not a journal file")]
+    InvalidHeader,
+
+    #[error("journal record {index} is truncated")]
+    Truncated { index: u64 },
+
+    #[error("journal record {index} does not follow the previous record: expected {expected:02x?}, got {actual:02x?}")]
+    BrokenChain { index: u64, expected: [u8; 32], actual: [u8; 32] },
+
+    #[error("journal ends at {actual:02x?}, expected head {expected:02x?}")]
+    HeadMismatch { expected: [u8; 32], actual: [u8; 32] },
+}
+
+/// Error applying ops to or reading graph state from a key-value store.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum KvError<E> {
+    #[error("key-value store error: {0}")]
+    Store(E),
+
+    #[error("invalid record at key {key:02x?}: {source}")]
+    Corrupt { key: Vec<u8>, source: DecodeError },
+}
+
+/// Error parsing or converting a DECIMAL value.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecimalError {
+    #[error("invalid decimal at byte {position}: {reason}")]
+    Syntax { position: usize, reason: &'static str },
+
+    #[error("decimal has {digits} significant digits, max {max}")]
+    TooManyDigits { digits: usize, max: usize },
+
+    #[error("decimal exponent out of range")]
+    ExponentOutOfRange,
+
+    #[error("decimal does not fit in the target type")]
+    OutOfRange,
+
+    #[error("value is not a DECIMAL")]
+    NotDecimal,
+}
+
+/// Error converting between POINT values and GeoJSON or `geo-types`.
+#[cfg(feature = "geo")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GeoError {
+    #[error("GeoJSON geometry is not a Point")]
+    NotAPoint,
+
+    #[error("position has {len} ordinates, expected 2 or 3")]
+    InvalidPosition { len: usize },
+
+    #[error("invalid point: {reason}")]
+    InvalidPoint { reason: &'static str },
+
+    #[error("value is not a POINT")]
+    NotPointValue,
+}
+
+/// Error converting between DATE/DATETIME values and `chrono` types.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DateTimeError {
+    #[error("value is not a DATE")]
+    NotDateValue,
+
+    #[error("value is not a DATETIME")]
+    NotDatetimeValue,
+
+    #[error("invalid DATE: expected [sign]YYYY[-MM[-DD]] naming an existing day")]
+    InvalidDate,
+
+    #[error("invalid DATETIME")]
+    InvalidDatetime,
+
+    #[error("DATE has {precision:?} precision, expected a full date")]
+    ReducedPrecision { precision: DatePrecision },
+
+    #[error("date is outside the representable range")]
+    OutOfRange,
+}
+
+/// Error parsing a BCP 47 language tag.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LanguageTagError {
+    #[error("language tag is empty")]
+    Empty,
+
+    #[error("invalid language tag {tag:?}: {reason}")]
+    Invalid { tag: String, reason: &'static str },
+}
+
+/// Error parsing an author identity.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AuthorError {
+    #[error("invalid author identity {identity:?}: {reason}")]
+    Invalid { identity: String, reason: &'static str },
+
+    #[error("unsupported DID method {method:?}")]
+    UnsupportedMethod { method: String },
+}
+
+/// Error while generating a relation position.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PositionError {
+    #[error("invalid position: {reason}")]
+    Invalid { reason: &'static str },
+
+    #[error("positions must be given in ascending order")]
+    NotOrdered,
+
+    #[error("no position sorts between the given positions")]
+    NoRoom,
+
+    #[error("positions are too close: reorder the relations with evenly spaced positions")]
+    TooLong,
+}
+
+/// Error while building a CreateRelation op.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RelationBuildError {
+    #[error("relation has no {field}")]
+    MissingField { field: &'static str },
+
+    #[error(transparent)]
+    Position(#[from] PositionError),
+}
+
+/// Error parsing an ID from text.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseIdError {
+    #[error("invalid ID {input:?}: expected hex, base58, or multibase")]
+    Unrecognized { input: String },
+}
+
+/// Error while building or parsing an alias table.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AliasError {
+    #[error("invalid alias {alias:?}: {reason}")]
+    InvalidAlias { alias: String, reason: &'static str },
+
+    #[error("alias {alias:?} is already defined")]
+    DuplicateAlias { alias: String },
+
+    #[error("ID {id:?} already has an alias")]
+    DuplicateId { id: Id },
+
+    #[error("alias file line {line}: {reason}")]
+    Parse { line: usize, reason: &'static str },
+}
+
+/// Error while parsing a schema file.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SchemaFileError {
+    #[error("schema file line {line}: {reason}")]
+    Parse { line: usize, reason: &'static str },
+
+    #[error("schema file line {line}: invalid {key}: {reason}")]
+    InvalidField { line: usize, key: String, reason: &'static str },
+
+    #[error("schema file line {line}: ID {id:?} is declared more than once")]
+    DuplicateId { line: usize, id: Id },
+}
+
+/// Error while reordering an edit's ops.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OrderError {
+    /// The ops at these indices (original order) depend on each other in a
+    /// cycle, listed so that each op must come before the next.
+    #[error("ops {ops:?} depend on each other in a cycle")]
+    Cycle { ops: Vec<usize> },
 }
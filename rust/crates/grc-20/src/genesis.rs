@@ -2,8 +2,16 @@
 //!
 //! The Genesis Space provides well-known IDs for core properties, types,
 //! and relation types (spec Section 7).
+//!
+//! [`all`] lists every well-known ID as data, and [`genesis_edit`] emits
+//! the edit that creates them, for indexers bootstrapping a fresh space.
+
+use std::borrow::Cow;
 
-use crate::model::{derived_uuid, Id};
+use crate::model::id::unique_relation_id;
+use crate::model::{
+    derived_uuid, CreateEntity, CreateRelation, DataType, Edit, Id, Op, PropertyValue, Value,
+};
 
 // =============================================================================
 // ID DERIVATION
@@ -19,6 +27,44 @@ pub fn genesis_id(name: &str) -> Id {
     derived_uuid(input.as_bytes())
 }
 
+/// Derives a unit ID from a unit symbol or ISO 4217 currency code.
+///
+/// ```text
+/// id = derived_uuid("grc20:genesis:unit:" + symbol)
+/// ```
+pub fn unit_id(symbol: &str) -> Id {
+    let input = format!("grc20:genesis:unit:{}", symbol);
+    derived_uuid(input.as_bytes())
+}
+
+/// Derives the ID of a data type entity (spec Section 7.5).
+///
+/// ```text
+/// id = derived_uuid("grc20:genesis:datatype:" + type_name)
+/// ```
+pub fn data_type_id(data_type: DataType) -> Id {
+    let input = format!("grc20:genesis:datatype:{}", data_type_name(data_type));
+    derived_uuid(input.as_bytes())
+}
+
+/// Returns the lowercase name of a data type, as used in its entity ID.
+pub(crate) fn data_type_name(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "bool",
+        DataType::Int64 => "int64",
+        DataType::Float64 => "float64",
+        DataType::Decimal => "decimal",
+        DataType::Text => "text",
+        DataType::Bytes => "bytes",
+        DataType::Date => "date",
+        DataType::Time => "time",
+        DataType::Datetime => "datetime",
+        DataType::Schedule => "schedule",
+        DataType::Point => "point",
+        DataType::Embedding => "embedding",
+    }
+}
+
 /// Derives a language ID from an ISO language code.
 ///
 /// ```text
@@ -50,11 +96,40 @@ pub mod properties {
         /// URL property - external link (TEXT)
         pub static ref URL: Id = genesis_id("URL");
 
-        /// Created property - creation time (TIMESTAMP)
+        /// Created property - creation time (DATETIME)
         pub static ref CREATED: Id = genesis_id("Created");
 
-        /// Modified property - last modification (TIMESTAMP)
+        /// Modified property - last modification (DATETIME)
         pub static ref MODIFIED: Id = genesis_id("Modified");
+
+        /// Timezone property - original UTC offset or IANA zone of the
+        /// entity's timestamps (TEXT, e.g. "+09:00" or "Asia/Tokyo")
+        pub static ref TIMEZONE: Id = genesis_id("Timezone");
+
+        /// Deprecated property - marks a property or type entity as
+        /// deprecated (BOOL)
+        pub static ref DEPRECATED: Id = genesis_id("Deprecated");
+
+        /// Email property - email address (TEXT)
+        pub static ref EMAIL: Id = genesis_id("Email");
+
+        /// Phone property - phone number, preferably E.164 (TEXT)
+        pub static ref PHONE: Id = genesis_id("Phone");
+
+        /// Address property - postal address (TEXT)
+        pub static ref ADDRESS: Id = genesis_id("Address");
+
+        /// Website property - home page URL (TEXT)
+        pub static ref WEBSITE: Id = genesis_id("Website");
+
+        /// StartDate property - when an event, role, or project begins (DATE)
+        pub static ref START_DATE: Id = genesis_id("StartDate");
+
+        /// EndDate property - when it ends (DATE)
+        pub static ref END_DATE: Id = genesis_id("EndDate");
+
+        /// Location property - geographic coordinates (POINT)
+        pub static ref LOCATION: Id = genesis_id("Location");
     }
 
     /// Returns the Name property ID.
@@ -86,6 +161,51 @@ pub mod properties {
     pub fn modified() -> Id {
         *MODIFIED
     }
+
+    /// Returns the Timezone property ID.
+    pub fn timezone() -> Id {
+        *TIMEZONE
+    }
+
+    /// Returns the Deprecated property ID.
+    pub fn deprecated() -> Id {
+        *DEPRECATED
+    }
+
+    /// Returns the Email property ID.
+    pub fn email() -> Id {
+        *EMAIL
+    }
+
+    /// Returns the Phone property ID.
+    pub fn phone() -> Id {
+        *PHONE
+    }
+
+    /// Returns the Address property ID.
+    pub fn address() -> Id {
+        *ADDRESS
+    }
+
+    /// Returns the Website property ID.
+    pub fn website() -> Id {
+        *WEBSITE
+    }
+
+    /// Returns the StartDate property ID.
+    pub fn start_date() -> Id {
+        *START_DATE
+    }
+
+    /// Returns the EndDate property ID.
+    pub fn end_date() -> Id {
+        *END_DATE
+    }
+
+    /// Returns the Location property ID.
+    pub fn location() -> Id {
+        *LOCATION
+    }
 }
 
 // =============================================================================
@@ -108,6 +228,18 @@ pub mod types {
 
         /// Topic type - subject or concept
         pub static ref TOPIC: Id = genesis_id("Topic");
+
+        /// Event type - something that happens at a time and place
+        pub static ref EVENT: Id = genesis_id("Event");
+
+        /// Project type - an undertaking with a goal
+        pub static ref PROJECT: Id = genesis_id("Project");
+
+        /// Document type - article, paper, or file
+        pub static ref DOCUMENT: Id = genesis_id("Document");
+
+        /// Image type - picture or graphic
+        pub static ref IMAGE: Id = genesis_id("Image");
     }
 
     /// Returns the Person type ID.
@@ -129,6 +261,26 @@ pub mod types {
     pub fn topic() -> Id {
         *TOPIC
     }
+
+    /// Returns the Event type ID.
+    pub fn event() -> Id {
+        *EVENT
+    }
+
+    /// Returns the Project type ID.
+    pub fn project() -> Id {
+        *PROJECT
+    }
+
+    /// Returns the Document type ID.
+    pub fn document() -> Id {
+        *DOCUMENT
+    }
+
+    /// Returns the Image type ID.
+    pub fn image() -> Id {
+        *IMAGE
+    }
 }
 
 // =============================================================================
@@ -148,6 +300,17 @@ pub mod relation_types {
 
         /// RelatedTo relation - generic association
         pub static ref RELATED_TO: Id = genesis_id("RelatedTo");
+
+        /// ReplacedBy relation - from a deprecated property or type to
+        /// its replacement
+        pub static ref REPLACED_BY: Id = genesis_id("ReplacedBy");
+
+        /// DataType relation - from a property to its expected data type
+        /// entity
+        pub static ref DATA_TYPE: Id = genesis_id("DataType");
+
+        /// SubtypeOf relation - from a type to a type it specializes
+        pub static ref SUBTYPE_OF: Id = genesis_id("SubtypeOf");
     }
 
     /// Returns the Types relation type ID.
@@ -164,6 +327,21 @@ pub mod relation_types {
     pub fn related_to() -> Id {
         *RELATED_TO
     }
+
+    /// Returns the ReplacedBy relation type ID.
+    pub fn replaced_by() -> Id {
+        *REPLACED_BY
+    }
+
+    /// Returns the DataType relation type ID.
+    pub fn data_type() -> Id {
+        *DATA_TYPE
+    }
+
+    /// Returns the SubtypeOf relation type ID.
+    pub fn subtype_of() -> Id {
+        *SUBTYPE_OF
+    }
 }
 
 // =============================================================================
@@ -197,6 +375,19 @@ pub mod languages {
         language_id(code)
     }
 
+    /// Returns the codes of the genesis languages.
+    pub fn codes() -> impl Iterator<Item = &'static str> {
+        LANGUAGES.iter().map(|&(_, code)| code)
+    }
+
+    /// Returns the code of a genesis language ID.
+    ///
+    /// IDs are one-way hashes, so only the genesis languages can be looked
+    /// up here; see [`crate::lang::LanguageRegistry`] for others.
+    pub fn code_of(id: &Id) -> Option<&'static str> {
+        codes().find(|code| language_id(code) == *id)
+    }
+
     pub fn english() -> Id {
         *ENGLISH
     }
@@ -246,6 +437,284 @@ pub mod languages {
     }
 }
 
+// =============================================================================
+// UNITS
+// =============================================================================
+
+/// Well-known unit IDs from the Genesis Space.
+///
+/// Units are referenced by the `unit` field of INT64, FLOAT64, and DECIMAL
+/// values. [`UnitRegistry`](crate::validate::units::UnitRegistry) maps them
+/// to their dimensions.
+pub mod units {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        // Length
+        pub static ref METER: Id = unit_id("m");
+        pub static ref KILOMETER: Id = unit_id("km");
+        pub static ref CENTIMETER: Id = unit_id("cm");
+        pub static ref MILE: Id = unit_id("mi");
+        pub static ref FOOT: Id = unit_id("ft");
+
+        // Mass
+        pub static ref GRAM: Id = unit_id("g");
+        pub static ref KILOGRAM: Id = unit_id("kg");
+        pub static ref POUND: Id = unit_id("lb");
+
+        // Time
+        pub static ref SECOND: Id = unit_id("s");
+        pub static ref MINUTE: Id = unit_id("min");
+        pub static ref HOUR: Id = unit_id("h");
+        pub static ref DAY: Id = unit_id("d");
+
+        // Temperature
+        pub static ref KELVIN: Id = unit_id("K");
+        pub static ref CELSIUS: Id = unit_id("degC");
+        pub static ref FAHRENHEIT: Id = unit_id("degF");
+
+        // Information
+        pub static ref BYTE: Id = unit_id("B");
+
+        // Currency
+        pub static ref USD: Id = unit_id("USD");
+        pub static ref EUR: Id = unit_id("EUR");
+        pub static ref GBP: Id = unit_id("GBP");
+        pub static ref JPY: Id = unit_id("JPY");
+    }
+
+    /// Returns the unit ID for the given symbol or currency code.
+    ///
+    /// This dynamically derives the ID - for well-known units, use the
+    /// static constants instead.
+    pub fn from_symbol(symbol: &str) -> Id {
+        unit_id(symbol)
+    }
+
+    pub fn meter() -> Id {
+        *METER
+    }
+
+    pub fn kilometer() -> Id {
+        *KILOMETER
+    }
+
+    pub fn second() -> Id {
+        *SECOND
+    }
+
+    pub fn kilogram() -> Id {
+        *KILOGRAM
+    }
+
+    pub fn celsius() -> Id {
+        *CELSIUS
+    }
+
+    pub fn byte() -> Id {
+        *BYTE
+    }
+
+    pub fn usd() -> Id {
+        *USD
+    }
+
+    pub fn eur() -> Id {
+        *EUR
+    }
+}
+
+// =============================================================================
+// REGISTRY
+// =============================================================================
+
+/// What a well-known genesis entity is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenesisKind {
+    /// A property (Section 7.1).
+    Property,
+    /// An entity type (Section 7.2).
+    Type,
+    /// A relation type (Section 7.3).
+    RelationType,
+    /// A language for TEXT values (Section 7.4).
+    Language,
+    /// A unit for numeric values.
+    Unit,
+    /// A data type entity (Section 7.5).
+    DataType,
+}
+
+/// A well-known entity of the Genesis Space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenesisEntry {
+    /// What the entity is.
+    pub kind: GenesisKind,
+    /// Display name, set as the entity's Name by [`genesis_edit`].
+    pub name: &'static str,
+    /// The derived ID.
+    pub id: Id,
+    /// Expected data type, for properties.
+    pub data_type: Option<DataType>,
+}
+
+const PROPERTIES: &[(&str, DataType)] = &[
+    ("Name", DataType::Text),
+    ("Description", DataType::Text),
+    ("Avatar", DataType::Text),
+    ("URL", DataType::Text),
+    ("Created", DataType::Datetime),
+    ("Modified", DataType::Datetime),
+    ("Timezone", DataType::Text),
+    ("Deprecated", DataType::Bool),
+    ("Email", DataType::Text),
+    ("Phone", DataType::Text),
+    ("Address", DataType::Text),
+    ("Website", DataType::Text),
+    ("StartDate", DataType::Date),
+    ("EndDate", DataType::Date),
+    ("Location", DataType::Point),
+];
+
+const TYPES: &[&str] = &[
+    "Person",
+    "Organization",
+    "Place",
+    "Topic",
+    "Event",
+    "Project",
+    "Document",
+    "Image",
+];
+
+const RELATION_TYPES: &[&str] =
+    &["Types", "PartOf", "RelatedTo", "ReplacedBy", "DataType", "SubtypeOf"];
+
+const LANGUAGES: &[(&str, &str)] = &[
+    ("English", "en"),
+    ("Spanish", "es"),
+    ("French", "fr"),
+    ("German", "de"),
+    ("Chinese", "zh"),
+    ("Japanese", "ja"),
+    ("Korean", "ko"),
+    ("Portuguese", "pt"),
+    ("Italian", "it"),
+    ("Russian", "ru"),
+    ("Arabic", "ar"),
+    ("Hindi", "hi"),
+];
+
+const UNITS: &[(&str, &str)] = &[
+    ("Meter", "m"),
+    ("Kilometer", "km"),
+    ("Centimeter", "cm"),
+    ("Mile", "mi"),
+    ("Foot", "ft"),
+    ("Gram", "g"),
+    ("Kilogram", "kg"),
+    ("Pound", "lb"),
+    ("Second", "s"),
+    ("Minute", "min"),
+    ("Hour", "h"),
+    ("Day", "d"),
+    ("Kelvin", "K"),
+    ("Degree Celsius", "degC"),
+    ("Degree Fahrenheit", "degF"),
+    ("Byte", "B"),
+    ("US Dollar", "USD"),
+    ("Euro", "EUR"),
+    ("Pound Sterling", "GBP"),
+    ("Japanese Yen", "JPY"),
+];
+
+const DATA_TYPES: &[(&str, DataType)] = &[
+    ("Bool", DataType::Bool),
+    ("Int64", DataType::Int64),
+    ("Float64", DataType::Float64),
+    ("Decimal", DataType::Decimal),
+    ("Text", DataType::Text),
+    ("Bytes", DataType::Bytes),
+    ("Date", DataType::Date),
+    ("Time", DataType::Time),
+    ("Datetime", DataType::Datetime),
+    ("Schedule", DataType::Schedule),
+    ("Point", DataType::Point),
+    ("Embedding", DataType::Embedding),
+];
+
+/// Returns every well-known genesis entity, grouped by kind.
+pub fn all() -> Vec<GenesisEntry> {
+    let entry = |kind, name, id, data_type| GenesisEntry { kind, name, id, data_type };
+    let mut entries = Vec::new();
+    for &(name, data_type) in PROPERTIES {
+        entries.push(entry(GenesisKind::Property, name, genesis_id(name), Some(data_type)));
+    }
+    for &name in TYPES {
+        entries.push(entry(GenesisKind::Type, name, genesis_id(name), None));
+    }
+    for &name in RELATION_TYPES {
+        entries.push(entry(GenesisKind::RelationType, name, genesis_id(name), None));
+    }
+    for &(name, code) in LANGUAGES {
+        entries.push(entry(GenesisKind::Language, name, language_id(code), None));
+    }
+    for &(name, symbol) in UNITS {
+        entries.push(entry(GenesisKind::Unit, name, unit_id(symbol), None));
+    }
+    for &(name, data_type) in DATA_TYPES {
+        entries.push(entry(GenesisKind::DataType, name, data_type_id(data_type), None));
+    }
+    entries
+}
+
+/// Builds the edit that seeds a space with the genesis entities.
+///
+/// Each entry in [`all`] is created with its Name. Properties also get a
+/// DataType relation to their data type entity, with a unique-mode ID
+/// (see [`unique_relation_id`]). The edit is deterministic for a given
+/// `id` and `created_at`.
+pub fn genesis_edit(id: Id, created_at: i64) -> Edit<'static> {
+    let name = properties::name();
+    let data_type_rel = relation_types::data_type();
+    let mut ops = Vec::new();
+    for entry in all() {
+        ops.push(Op::CreateEntity(CreateEntity {
+            id: entry.id,
+            values: vec![PropertyValue {
+                property: name,
+                value: Value::Text { value: Cow::Borrowed(entry.name), language: None },
+                clock: None,
+            }],
+        }));
+        if let Some(data_type) = entry.data_type {
+            let to = data_type_id(data_type);
+            ops.push(Op::CreateRelation(CreateRelation {
+                id: unique_relation_id(&entry.id, &to, &data_type_rel),
+                relation_type: data_type_rel,
+                from: entry.id,
+                from_is_value_ref: false,
+                from_space: None,
+                from_version: None,
+                to,
+                to_is_value_ref: false,
+                to_space: None,
+                to_version: None,
+                entity: None,
+                position: None,
+            }));
+        }
+    }
+    Edit {
+        id,
+        name: Cow::Borrowed("Genesis"),
+        authors: Vec::new(),
+        created_at,
+        provenance: None,
+        ops,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,6 +760,10 @@ mod tests {
         assert_eq!(properties::url(), genesis_id("URL"));
         assert_eq!(properties::created(), genesis_id("Created"));
         assert_eq!(properties::modified(), genesis_id("Modified"));
+        assert_eq!(properties::timezone(), genesis_id("Timezone"));
+        assert_eq!(properties::email(), genesis_id("Email"));
+        assert_eq!(properties::start_date(), genesis_id("StartDate"));
+        assert_eq!(properties::location(), genesis_id("Location"));
     }
 
     #[test]
@@ -299,6 +772,8 @@ mod tests {
         assert_eq!(types::organization(), genesis_id("Organization"));
         assert_eq!(types::place(), genesis_id("Place"));
         assert_eq!(types::topic(), genesis_id("Topic"));
+        assert_eq!(types::event(), genesis_id("Event"));
+        assert_eq!(types::image(), genesis_id("Image"));
     }
 
     #[test]
@@ -306,6 +781,7 @@ mod tests {
         assert_eq!(relation_types::types(), genesis_id("Types"));
         assert_eq!(relation_types::part_of(), genesis_id("PartOf"));
         assert_eq!(relation_types::related_to(), genesis_id("RelatedTo"));
+        assert_eq!(relation_types::subtype_of(), genesis_id("SubtypeOf"));
     }
 
     #[test]
@@ -313,6 +789,33 @@ mod tests {
         assert_eq!(languages::english(), language_id("en"));
         assert_eq!(languages::spanish(), language_id("es"));
         assert_eq!(languages::from_code("en"), languages::english());
+        assert_eq!(languages::code_of(&languages::hindi()), Some("hi"));
+        assert_eq!(languages::code_of(&language_id("pt-br")), None);
+    }
+
+    #[test]
+    fn test_static_units() {
+        assert_eq!(units::meter(), unit_id("m"));
+        assert_eq!(units::usd(), unit_id("USD"));
+        assert_eq!(units::from_symbol("kg"), units::kilogram());
+        assert_ne!(unit_id("USD"), genesis_id("USD"));
+    }
+
+    #[test]
+    fn test_registry_and_seed_edit() {
+        let entries = all();
+        let ids: std::collections::HashSet<Id> = entries.iter().map(|e| e.id).collect();
+        assert_eq!(ids.len(), entries.len());
+        assert!(entries.iter().any(|e| e.id == properties::deprecated()
+            && e.data_type == Some(DataType::Bool)));
+        assert!(entries.iter().any(|e| e.id == units::usd() && e.kind == GenesisKind::Unit));
+        assert!(ids.contains(&relation_types::data_type()));
+        assert!(ids.contains(&data_type_id(DataType::Embedding)));
+
+        let edit = genesis_edit([1u8; 16], 0);
+        assert_eq!(edit.ops.len(), entries.len() + PROPERTIES.len());
+        let bytes = crate::codec::encode_edit(&edit).unwrap();
+        assert_eq!(crate::codec::decode_edit(&bytes).unwrap(), edit);
     }
 
     #[test]
@@ -325,6 +828,7 @@ mod tests {
         println!("URL: {}", format_id(&properties::url()));
         println!("Created: {}", format_id(&properties::created()));
         println!("Modified: {}", format_id(&properties::modified()));
+        println!("Timezone: {}", format_id(&properties::timezone()));
 
         println!("\n=== Core Types ===");
         println!("Person: {}", format_id(&types::person()));
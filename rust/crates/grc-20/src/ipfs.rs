@@ -0,0 +1,538 @@
+//! IPFS export of edits as CARv1 archives.
+//!
+//! Edits are published to IPFS as files: [`edit_to_car`] packs an encoded
+//! edit into a CAR (content-addressed archive, CARv1) stream that can be
+//! imported into any IPFS node, and returns the CID the edit will be
+//! addressed by. [`car_to_edit`] reverses it, checking every block against
+//! its CID.
+//!
+//! An edit of up to [`CHUNK_SIZE`] bytes is a single raw block. A larger
+//! edit is split into raw leaf blocks of [`CHUNK_SIZE`] bytes under one
+//! UnixFS file node (dag-pb). Up to 174 leaves (about 43 MiB), this is
+//! how IPFS lays out files added with raw leaves, so the CID matches
+//! `ipfs add --raw-leaves`. IPFS builds a balanced tree of file nodes for
+//! larger files, so larger edits get a different CID than IPFS would give
+//! them, though any node still reads the single wide node back. All CIDs
+//! are version 1 with SHA-256 multihashes.
+//!
+//! Stream layout:
+//!
+//! ```text
+//! header_length: varint
+//! header: DAG-CBOR {"roots": [root CID], "version": 1}
+//! block*:
+//!   length: varint (of CID + data)
+//!   cid: CID bytes
+//!   data
+//! ```
+//!
+//! The root block comes first, followed by the leaves in file order.
+
+use core::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::{decode_edit, encode_edit};
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, EncodeError, IpfsError};
+use crate::hash::FxHashMap;
+use crate::limits::MAX_EDIT_SIZE;
+use crate::model::Edit;
+
+/// Size of the leaf blocks a large edit is split into (256 KiB, the IPFS
+/// default).
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Multicodec of raw blocks.
+pub const CODEC_RAW: u64 = 0x55;
+
+/// Multicodec of dag-pb blocks.
+pub const CODEC_DAG_PB: u64 = 0x70;
+
+/// Multihash code of SHA-256.
+const SHA2_256: u64 = 0x12;
+
+/// CBOR tag for CIDs in DAG-CBOR.
+const CBOR_TAG_CID: u64 = 42;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A version 1 content identifier with a SHA-256 multihash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cid {
+    /// Multicodec of the block's content ([`CODEC_RAW`] or
+    /// [`CODEC_DAG_PB`]).
+    pub codec: u64,
+    /// SHA-256 of the block.
+    pub digest: [u8; 32],
+}
+
+impl Cid {
+    /// Returns the CID of a block.
+    pub fn of(codec: u64, block: &[u8]) -> Self {
+        Self { codec, digest: Sha256::digest(block).into() }
+    }
+
+    /// Returns the binary form of the CID.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::with_capacity(36);
+        writer.write_varint(1);
+        writer.write_varint(self.codec);
+        writer.write_varint(SHA2_256);
+        writer.write_varint(32);
+        writer.write_bytes(&self.digest);
+        writer.into_bytes()
+    }
+
+    /// Reads a binary CID.
+    ///
+    /// Version 0 CIDs (bare SHA-256 multihashes) are read as dag-pb.
+    pub(crate) fn read(reader: &mut Reader<'_>) -> Result<Self, IpfsError> {
+        let codec = if reader.remaining().starts_with(&[0x12, 0x20]) {
+            CODEC_DAG_PB
+        } else {
+            if reader.read_varint("cid version")? != 1 {
+                return Err(IpfsError::InvalidCar { reason: "unsupported CID version" });
+            }
+            reader.read_varint("cid codec")?
+        };
+        if reader.read_varint("multihash code")? != SHA2_256
+            || reader.read_varint("multihash length")? != 32
+        {
+            return Err(IpfsError::InvalidCar { reason: "CID hash is not SHA-256" });
+        }
+        let digest = reader.read_bytes(32, "multihash digest")?.try_into().expect("32 bytes");
+        Ok(Self { codec, digest })
+    }
+}
+
+/// Formats the CID as multibase base32, as IPFS displays version 1 CIDs
+/// (e.g. `bafkrei...`).
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes();
+        let mut out = String::with_capacity(1 + (bytes.len() * 8).div_ceil(5));
+        out.push('b');
+        let (mut buffer, mut bits) = (0u32, 0);
+        for &byte in &bytes {
+            buffer = (buffer << 8) | byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                out.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+            }
+        }
+        if bits > 0 {
+            out.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+        }
+        f.write_str(&out)
+    }
+}
+
+/// Encodes an edit and packs it into a CAR stream.
+///
+/// Returns the edit's CID and the stream.
+pub fn edit_to_car(edit: &Edit<'_>) -> Result<(Cid, Vec<u8>), EncodeError> {
+    Ok(encoded_to_car(&encode_edit(edit)?))
+}
+
+/// Packs an already-encoded edit (in any framing) into a CAR stream.
+///
+/// The bytes are stored unchanged, so the CID addresses exactly them.
+pub fn encoded_to_car(encoded: &[u8]) -> (Cid, Vec<u8>) {
+    if encoded.len() <= CHUNK_SIZE {
+        let cid = Cid::of(CODEC_RAW, encoded);
+        let mut car = car_header(&cid);
+        write_block(&mut car, &cid, encoded);
+        return (cid, car.into_bytes());
+    }
+
+    let leaves = leaves(encoded);
+    let node = file_node(&leaves, encoded.len());
+    let root = Cid::of(CODEC_DAG_PB, &node);
+    let mut car = car_header(&root);
+    write_block(&mut car, &root, &node);
+    for (cid, chunk) in &leaves {
+        write_block(&mut car, cid, chunk);
+    }
+    (root, car.into_bytes())
+}
+
+/// Returns the CID [`encoded_to_car`] would address an already-encoded
+/// edit by, without building the CAR stream.
+pub fn encoded_cid(encoded: &[u8]) -> Cid {
+    if encoded.len() <= CHUNK_SIZE {
+        return Cid::of(CODEC_RAW, encoded);
+    }
+    Cid::of(CODEC_DAG_PB, &file_node(&leaves(encoded), encoded.len()))
+}
+
+/// Splits a large encoded edit into raw leaf blocks.
+fn leaves(encoded: &[u8]) -> Vec<(Cid, &[u8])> {
+    encoded.chunks(CHUNK_SIZE).map(|chunk| (Cid::of(CODEC_RAW, chunk), chunk)).collect()
+}
+
+/// Unpacks an edit from a CAR stream written by [`edit_to_car`].
+///
+/// Returns the root CID and the encoded edit, which is checked to decode.
+/// Every block must match its CID; blocks not reachable from the root are
+/// ignored. The file node's declared size and block sizes must match its
+/// leaves, and the edit may not exceed [`MAX_EDIT_SIZE`], which is checked
+/// before the leaves are copied, so a small CAR whose root links the same
+/// leaf many times cannot force a large allocation.
+pub fn car_to_edit(car: &[u8]) -> Result<(Cid, Vec<u8>), IpfsError> {
+    let mut reader = Reader::new(car);
+    let header_len = reader.read_varint("car header length")? as usize;
+    let root = read_header(reader.read_bytes(header_len, "car header")?)?;
+
+    let mut blocks: FxHashMap<Cid, &[u8]> = FxHashMap::default();
+    while !reader.is_empty() {
+        let len = reader.read_varint("car block length")? as usize;
+        let mut section = Reader::new(reader.read_bytes(len, "car block")?);
+        let cid = Cid::read(&mut section)?;
+        let data = section.remaining();
+        if Cid::of(cid.codec, data) != cid {
+            return Err(IpfsError::HashMismatch { cid });
+        }
+        blocks.insert(cid, data);
+    }
+
+    let block = |cid: &Cid| blocks.get(cid).copied().ok_or(IpfsError::MissingBlock { cid: *cid });
+    let encoded = match root.codec {
+        CODEC_RAW => block(&root)?.to_vec(),
+        CODEC_DAG_PB => {
+            let node = read_file_node(block(&root)?)?;
+            check_edit_size(node.file_size)?;
+            if node.block_sizes.len() != node.links.len()
+                || node.block_sizes.iter().sum::<u64>() != node.file_size
+            {
+                let reason = "file node sizes do not match its links";
+                return Err(IpfsError::InvalidCar { reason });
+            }
+            let mut encoded = Vec::with_capacity(node.file_size as usize);
+            for (leaf, &size) in node.links.iter().zip(&node.block_sizes) {
+                if leaf.codec != CODEC_RAW {
+                    let reason = "file leaves must be raw blocks";
+                    return Err(IpfsError::InvalidCar { reason });
+                }
+                let data = block(leaf)?;
+                if data.len() as u64 != size {
+                    let reason = "leaf size does not match the file node";
+                    return Err(IpfsError::InvalidCar { reason });
+                }
+                check_edit_size((encoded.len() + data.len()) as u64)?;
+                encoded.extend_from_slice(data);
+            }
+            encoded
+        }
+        _ => return Err(IpfsError::InvalidCar { reason: "unsupported root codec" }),
+    };
+    decode_edit(&encoded)?;
+    Ok((root, encoded))
+}
+
+/// Writes the length-prefixed DAG-CBOR header
+/// `{"roots": [root], "version": 1}`.
+fn car_header(root: &Cid) -> Writer {
+    let cid = root.to_bytes();
+    let mut header = Writer::new();
+    header.write_byte(0xA2); // map(2), keys in DAG-CBOR order
+    header.write_byte(0x65); // text(5)
+    header.write_bytes(b"roots");
+    header.write_byte(0x81); // array(1)
+    header.write_bytes(&[0xD8, CBOR_TAG_CID as u8]);
+    cbor_head(&mut header, 2, cid.len() as u64 + 1);
+    header.write_byte(0x00); // multibase identity prefix
+    header.write_bytes(&cid);
+    header.write_byte(0x67); // text(7)
+    header.write_bytes(b"version");
+    header.write_byte(0x01);
+
+    let mut car = Writer::new();
+    car.write_varint(header.len() as u64);
+    car.write_bytes(header.as_bytes());
+    car
+}
+
+fn write_block(car: &mut Writer, cid: &Cid, data: &[u8]) {
+    let cid = cid.to_bytes();
+    car.write_varint((cid.len() + data.len()) as u64);
+    car.write_bytes(&cid);
+    car.write_bytes(data);
+}
+
+/// Reads the root CID from a CAR header, which must have version 1 and a
+/// single root.
+fn read_header(header: &[u8]) -> Result<Cid, IpfsError> {
+    let invalid = IpfsError::InvalidCar { reason: "malformed header" };
+    let mut reader = Reader::new(header);
+    let (major, entries) = read_cbor_head(&mut reader)?;
+    if major != 5 {
+        return Err(invalid);
+    }
+    let (mut root, mut version) = (None, None);
+    for _ in 0..entries {
+        let (major, len) = read_cbor_head(&mut reader)?;
+        if major != 3 {
+            return Err(invalid);
+        }
+        match reader.read_bytes(len as usize, "car header key")? {
+            b"roots" => {
+                if read_cbor_head(&mut reader)? != (4, 1) {
+                    return Err(IpfsError::InvalidCar { reason: "expected a single root" });
+                }
+                if read_cbor_head(&mut reader)? != (6, CBOR_TAG_CID) {
+                    return Err(invalid);
+                }
+                let (major, len) = read_cbor_head(&mut reader)?;
+                let cid = reader.read_bytes(len as usize, "car root")?;
+                if major != 2 || cid.first() != Some(&0x00) {
+                    return Err(invalid);
+                }
+                root = Some(Cid::read(&mut Reader::new(&cid[1..]))?);
+            }
+            b"version" => version = Some(read_cbor_head(&mut reader)?),
+            _ => return Err(invalid),
+        }
+    }
+    if version != Some((0, 1)) {
+        return Err(IpfsError::InvalidCar { reason: "unsupported CAR version" });
+    }
+    root.ok_or(invalid)
+}
+
+/// Writes a CBOR major type and argument.
+fn cbor_head(writer: &mut Writer, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => writer.write_byte(major | value as u8),
+        24..=0xFF => writer.write_bytes(&[major | 24, value as u8]),
+        0x100..=0xFFFF => {
+            writer.write_byte(major | 25);
+            writer.write_bytes(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            writer.write_byte(major | 26);
+            writer.write_bytes(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            writer.write_byte(major | 27);
+            writer.write_bytes(&value.to_be_bytes());
+        }
+    }
+}
+
+/// Reads a CBOR major type and argument.
+fn read_cbor_head(reader: &mut Reader<'_>) -> Result<(u8, u64), IpfsError> {
+    let byte = reader.read_byte("cbor")?;
+    let width = match byte & 0x1F {
+        info @ 0..=23 => return Ok((byte >> 5, info as u64)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err(IpfsError::InvalidCar { reason: "malformed header" }),
+    };
+    let bytes = reader.read_bytes(width, "cbor")?;
+    Ok((byte >> 5, bytes.iter().fold(0, |n, &b| (n << 8) | b as u64)))
+}
+
+/// Encodes a dag-pb UnixFS file node linking to `leaves` in order.
+fn file_node(leaves: &[(Cid, &[u8])], file_size: usize) -> Vec<u8> {
+    // UnixFS Data { Type = File, filesize, blocksizes }
+    let mut data = Writer::new();
+    data.write_bytes(&[0x08, 0x02]);
+    data.write_byte(0x18);
+    data.write_varint(file_size as u64);
+    for (_, chunk) in leaves {
+        data.write_byte(0x20);
+        data.write_varint(chunk.len() as u64);
+    }
+
+    // PBNode: Links (field 2) precede Data (field 1) in canonical dag-pb
+    let mut node = Writer::new();
+    for (cid, chunk) in leaves {
+        let cid = cid.to_bytes();
+        let mut link = Writer::new();
+        link.write_byte(0x0A); // Hash
+        link.write_bytes_prefixed(&cid);
+        link.write_bytes(&[0x12, 0x00]); // Name: ""
+        link.write_byte(0x18); // Tsize
+        link.write_varint(chunk.len() as u64);
+        node.write_byte(0x12);
+        node.write_bytes_prefixed(link.as_bytes());
+    }
+    node.write_byte(0x0A);
+    node.write_bytes_prefixed(data.as_bytes());
+    node.into_bytes()
+}
+
+/// Fails if an edit of `len` bytes would exceed [`MAX_EDIT_SIZE`].
+fn check_edit_size(len: u64) -> Result<(), IpfsError> {
+    if len > MAX_EDIT_SIZE as u64 {
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        let max = MAX_EDIT_SIZE;
+        return Err(DecodeError::LengthExceedsLimit { field: "edit", len, max }.into());
+    }
+    Ok(())
+}
+
+/// A UnixFS file node: its links in order, and the file size and leaf
+/// sizes it declares.
+struct FileNode {
+    links: Vec<Cid>,
+    file_size: u64,
+    block_sizes: Vec<u64>,
+}
+
+/// Reads a dag-pb UnixFS file node.
+fn read_file_node(node: &[u8]) -> Result<FileNode, IpfsError> {
+    let malformed = IpfsError::InvalidCar { reason: "malformed dag-pb node" };
+    let mut links = Vec::new();
+    let mut data = None;
+    let mut reader = Reader::new(node);
+    while !reader.is_empty() {
+        // Both PBNode fields are length-delimited: Data = 1, Links = 2
+        let key = reader.read_varint("dag-pb field")?;
+        let field = read_delimited(&mut reader)?;
+        match key {
+            0x0A => data = Some(read_unixfs_file(field)?),
+            0x12 => {
+                let mut link = Reader::new(field);
+                while !link.is_empty() {
+                    match link.read_varint("dag-pb link")? {
+                        0x0A => {
+                            let cid = read_delimited(&mut link)?;
+                            links.push(Cid::read(&mut Reader::new(cid))?);
+                        }
+                        0x12 => {
+                            read_delimited(&mut link)?;
+                        }
+                        0x18 => {
+                            link.read_varint("dag-pb link")?;
+                        }
+                        _ => return Err(malformed),
+                    }
+                }
+            }
+            _ => return Err(malformed),
+        }
+    }
+    let (file_size, block_sizes) =
+        data.ok_or(IpfsError::InvalidCar { reason: "file node has no UnixFS data" })?;
+    Ok(FileNode { links, file_size, block_sizes })
+}
+
+/// Reads the `filesize` and `blocksizes` of a UnixFS Data message, which
+/// must be of type File.
+fn read_unixfs_file(data: &[u8]) -> Result<(u64, Vec<u64>), IpfsError> {
+    let malformed = IpfsError::InvalidCar { reason: "malformed UnixFS data" };
+    let (mut file_type, mut file_size, mut block_sizes) = (None, None, Vec::new());
+    let mut reader = Reader::new(data);
+    while !reader.is_empty() {
+        match reader.read_varint("unixfs field")? {
+            0x08 => file_type = Some(reader.read_varint("unixfs type")?),
+            0x12 => {
+                read_delimited(&mut reader)?;
+            }
+            0x18 => file_size = Some(reader.read_varint("unixfs filesize")?),
+            0x20 => block_sizes.push(reader.read_varint("unixfs blocksize")?),
+            // Packed blocksizes
+            0x22 => {
+                let mut packed = Reader::new(read_delimited(&mut reader)?);
+                while !packed.is_empty() {
+                    block_sizes.push(packed.read_varint("unixfs blocksize")?);
+                }
+            }
+            _ => return Err(malformed),
+        }
+    }
+    if file_type != Some(2) {
+        return Err(IpfsError::InvalidCar { reason: "root is not a UnixFS file" });
+    }
+    Ok((file_size.ok_or(malformed)?, block_sizes))
+}
+
+/// Reads a length-delimited protobuf field.
+fn read_delimited<'a>(reader: &mut Reader<'a>) -> Result<&'a [u8], IpfsError> {
+    let len = reader.read_varint("dag-pb length")? as usize;
+    Ok(reader.read_bytes(len, "dag-pb field")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_cid_display() {
+        // Raw CID of the empty block, as printed by IPFS
+        let empty = Cid::of(CODEC_RAW, b"");
+        assert_eq!(
+            empty.to_string(),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+    }
+
+    #[test]
+    fn test_car_roundtrip() {
+        let small = EditBuilder::new([1u8; 16]).create_empty_entity([2u8; 16]).build();
+        let encoded = encode_edit(&small).unwrap();
+        let (cid, car) = edit_to_car(&small).unwrap();
+        assert_eq!(cid, Cid::of(CODEC_RAW, &encoded));
+        assert_eq!(encoded_cid(&encoded), cid);
+        assert_eq!(car_to_edit(&car).unwrap(), (cid, encoded));
+
+        // Over one chunk: a file node with raw leaves
+        let payload = vec![7u8; CHUNK_SIZE * 2 + 10];
+        let large = EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.bytes([3u8; 16], payload.as_slice()))
+            .build();
+        let encoded = encode_edit(&large).unwrap();
+        let (cid, mut car) = edit_to_car(&large).unwrap();
+        assert_eq!(cid.codec, CODEC_DAG_PB);
+        assert_eq!(encoded_cid(&encoded), cid);
+        assert_eq!(car_to_edit(&car).unwrap(), (cid, encoded));
+
+        // Corrupting a leaf is caught by its CID
+        let last = car.len() - 1;
+        car[last] ^= 1;
+        assert!(matches!(car_to_edit(&car), Err(IpfsError::HashMismatch { .. })));
+    }
+
+    #[test]
+    fn test_car_repeated_links() {
+        // Packs a file node over `links` with the single leaf block `leaf`
+        let car = |links: &[(Cid, &[u8])], file_size: usize, leaf: &[u8]| {
+            let node = file_node(links, file_size);
+            let root = Cid::of(CODEC_DAG_PB, &node);
+            let mut car = car_header(&root);
+            write_block(&mut car, &root, &node);
+            write_block(&mut car, &links[0].0, leaf);
+            car.into_bytes()
+        };
+        let chunk = vec![7u8; CHUNK_SIZE];
+        let leaf = (Cid::of(CODEC_RAW, &chunk), chunk.as_slice());
+
+        // A root linking one leaf over and over claims far more than the
+        // CAR holds; it is rejected before anything is copied
+        let links = vec![leaf; 2000];
+        let repeated = car(&links, 2000 * CHUNK_SIZE, &chunk);
+        assert!(repeated.len() < 1024 * 1024);
+        assert!(matches!(
+            car_to_edit(&repeated),
+            Err(IpfsError::Decode(DecodeError::LengthExceedsLimit { field: "edit", .. }))
+        ));
+
+        // Understating the file size does not get past the block sizes
+        let understated = car(&links, CHUNK_SIZE, &chunk);
+        assert!(matches!(car_to_edit(&understated), Err(IpfsError::InvalidCar { .. })));
+
+        // Nor do block sizes that understate the leaves
+        let short = (leaf.0, &chunk[..10]);
+        assert!(matches!(
+            car_to_edit(&car(&[short, short], 20, &chunk)),
+            Err(IpfsError::InvalidCar { reason: "leaf size does not match the file node" })
+        ));
+    }
+}
@@ -0,0 +1,311 @@
+//! Append-only, hash-chained journals of edits.
+//!
+//! Local-first clients keep edits they have not published yet in a
+//! journal. Each record embeds the hash of the record before it, so
+//! changing, dropping, or reordering a record breaks the chain from that
+//! point on, and [`Journal::verify`] reports it. Pinning the head hash
+//! somewhere else (e.g. next to a signature) also catches records dropped
+//! from or rewritten at the end.
+//!
+//! File layout:
+//!
+//! ```text
+//! "GRC2J" version
+//! record*:
+//!   length: u32 little-endian
+//!   prev_hash: 32 bytes
+//!   edit: length bytes (standalone edit in any framing)
+//! ```
+//!
+//! A record's hash is `SHA-256(prev_hash || edit)`. The first record's
+//! `prev_hash` is all zeros, which is also the head of an empty journal.
+//! Edits are stored exactly as encoded, so hashes and signatures computed
+//! over them stay valid.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::{decode_edit, encode_edit};
+use crate::error::{DecodeError, EncodeError, JournalError};
+use crate::limits::{FORMAT_VERSION, MAGIC_JOURNAL, MAX_EDIT_SIZE};
+use crate::model::Edit;
+
+const HEADER_LEN: u64 = MAGIC_JOURNAL.len() as u64 + 1;
+
+/// An edit journal over a file or any seekable byte store.
+#[derive(Debug)]
+pub struct Journal<F = File> {
+    io: F,
+    head: [u8; 32],
+    len: u64,
+}
+
+/// One record of a journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalRecord {
+    /// Position of the record, from 0.
+    pub index: u64,
+    /// Hash of the previous record (zeros for the first).
+    pub prev_hash: [u8; 32],
+    /// Hash of this record.
+    pub hash: [u8; 32],
+    /// The encoded edit.
+    pub bytes: Vec<u8>,
+}
+
+impl JournalRecord {
+    /// Decodes the record's edit.
+    pub fn edit(&self) -> Result<Edit<'_>, JournalError> {
+        Ok(decode_edit(&self.bytes)?)
+    }
+}
+
+impl Journal<File> {
+    /// Opens the journal at `path`, creating it if it doesn't exist.
+    ///
+    /// An existing journal is verified while opening. A crash during
+    /// [`Journal::append`] can leave a partial last record, which then
+    /// fails with [`JournalError::Truncated`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, JournalError> {
+        let file =
+            OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        Self::new(file)
+    }
+}
+
+impl<F: Read + Write + Seek> Journal<F> {
+    /// Starts a journal in `io`: writes the header if `io` is empty, and
+    /// verifies the existing records otherwise.
+    pub fn new(mut io: F) -> Result<Self, JournalError> {
+        if io.seek(SeekFrom::End(0))? == 0 {
+            io.write_all(MAGIC_JOURNAL)?;
+            io.write_all(&[FORMAT_VERSION])?;
+            io.flush()?;
+            return Ok(Self { io, head: [0; 32], len: 0 });
+        }
+        let mut journal = Self { io, head: [0; 32], len: 0 };
+        let (head, len) = journal.chain()?;
+        journal.head = head;
+        journal.len = len;
+        Ok(journal)
+    }
+
+    /// Returns the hash of the last record (zeros if empty).
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the journal has no records.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Encodes and appends an edit, returning the new head.
+    pub fn append(&mut self, edit: &Edit<'_>) -> Result<[u8; 32], JournalError> {
+        let bytes = encode_edit(edit)?;
+        self.append_encoded(&bytes)
+    }
+
+    /// Appends an already-encoded edit unchanged, returning the new head.
+    ///
+    /// The bytes must decode as a standalone edit.
+    pub fn append_encoded(&mut self, bytes: &[u8]) -> Result<[u8; 32], JournalError> {
+        if bytes.len() > MAX_EDIT_SIZE {
+            let len = bytes.len();
+            let error = EncodeError::LengthExceedsLimit { field: "edit", len, max: MAX_EDIT_SIZE };
+            return Err(error.into());
+        }
+        decode_edit(bytes)?;
+        let hash = record_hash(&self.head, bytes);
+        let mut record = Vec::with_capacity(36 + bytes.len());
+        record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        record.extend_from_slice(&self.head);
+        record.extend_from_slice(bytes);
+
+        // One write per record, so a crash leaves at most a truncated tail
+        self.io.seek(SeekFrom::End(0))?;
+        self.io.write_all(&record)?;
+        self.io.flush()?;
+        self.head = hash;
+        self.len += 1;
+        Ok(hash)
+    }
+
+    /// Re-reads the journal and checks that every record follows the one
+    /// before it and that the chain still ends at [`Journal::head`].
+    ///
+    /// Returns the head.
+    pub fn verify(&mut self) -> Result<[u8; 32], JournalError> {
+        let (actual, _) = self.chain()?;
+        if actual != self.head {
+            return Err(JournalError::HeadMismatch { expected: self.head, actual });
+        }
+        Ok(actual)
+    }
+
+    /// Iterates over the records from the start, checking the chain as it
+    /// goes. The first error ends the iteration.
+    pub fn iter(&mut self) -> Result<JournalIter<'_, F>, JournalError> {
+        let mut reader = BufReader::new(&mut self.io);
+        reader.seek(SeekFrom::Start(0))?;
+        let mut header = [0u8; HEADER_LEN as usize];
+        read_full(&mut reader, &mut header)?;
+        if !header.starts_with(MAGIC_JOURNAL) || header[MAGIC_JOURNAL.len()] != FORMAT_VERSION {
+            return Err(JournalError::InvalidHeader);
+        }
+        Ok(JournalIter { reader, prev_hash: [0; 32], index: 0, done: false })
+    }
+
+    /// Returns the unwrapped store.
+    pub fn into_inner(self) -> F {
+        self.io
+    }
+
+    /// Walks the chain, returning its head and length.
+    fn chain(&mut self) -> Result<([u8; 32], u64), JournalError> {
+        let mut head = [0; 32];
+        let mut len = 0;
+        for record in self.iter()? {
+            head = record?.hash;
+            len += 1;
+        }
+        Ok((head, len))
+    }
+}
+
+/// Iterator over a journal's records; see [`Journal::iter`].
+pub struct JournalIter<'j, F> {
+    reader: BufReader<&'j mut F>,
+    prev_hash: [u8; 32],
+    index: u64,
+    done: bool,
+}
+
+impl<F: Read> JournalIter<'_, F> {
+    fn read_record(&mut self) -> Result<Option<JournalRecord>, JournalError> {
+        let index = self.index;
+        let mut len = [0u8; 4];
+        match read_full(&mut self.reader, &mut len)? {
+            0 => return Ok(None),
+            4 => {}
+            _ => return Err(JournalError::Truncated { index }),
+        }
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_EDIT_SIZE {
+            let field = "journal record";
+            let error = DecodeError::LengthExceedsLimit { field, len, max: MAX_EDIT_SIZE };
+            return Err(error.into());
+        }
+
+        let mut prev_hash = [0u8; 32];
+        let mut bytes = vec![0u8; len];
+        if read_full(&mut self.reader, &mut prev_hash)? < 32
+            || read_full(&mut self.reader, &mut bytes)? < len
+        {
+            return Err(JournalError::Truncated { index });
+        }
+        if prev_hash != self.prev_hash {
+            let expected = self.prev_hash;
+            return Err(JournalError::BrokenChain { index, expected, actual: prev_hash });
+        }
+
+        let hash = record_hash(&prev_hash, &bytes);
+        self.prev_hash = hash;
+        self.index += 1;
+        Ok(Some(JournalRecord { index, prev_hash, hash, bytes }))
+    }
+}
+
+impl<F: Read> Iterator for JournalIter<'_, F> {
+    type Item = Result<JournalRecord, JournalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        self.done = !matches!(record, Some(Ok(_)));
+        record
+    }
+}
+
+fn record_hash(prev_hash: &[u8; 32], edit: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash);
+    hasher.update(edit);
+    hasher.finalize().into()
+}
+
+/// Reads until `buf` is full or the input ends, returning the bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+    use std::io::Cursor;
+
+    fn edit(n: u8) -> Edit<'static> {
+        EditBuilder::new([n; 16]).create_empty_entity([n + 1; 16]).build()
+    }
+
+    #[test]
+    fn test_journal_chain() {
+        let mut journal = Journal::new(Cursor::new(Vec::new())).unwrap();
+        assert_eq!(journal.head(), [0; 32]);
+        let first = journal.append(&edit(1)).unwrap();
+        let head = journal.append(&edit(2)).unwrap();
+        assert_eq!(journal.verify().unwrap(), head);
+
+        let records: Vec<_> = journal.iter().unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[1].prev_hash, records[1].hash), (first, head));
+        assert_eq!(records[1].edit().unwrap(), edit(2));
+
+        // Reopening recovers the head
+        let bytes = journal.into_inner().into_inner();
+        let reopened = Journal::new(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!((reopened.head(), reopened.len()), (head, 2));
+
+        // Rewriting the first record breaks the link from the second
+        let mut tampered = bytes.clone();
+        let first_len = u32::from_le_bytes(tampered[6..10].try_into().unwrap()) as usize;
+        tampered[6 + 4 + 32 + first_len - 1] ^= 1;
+        assert!(matches!(
+            Journal::new(Cursor::new(tampered)),
+            Err(JournalError::BrokenChain { index: 1, expected, actual })
+                if actual == first && expected != first
+        ));
+
+        // Rewriting the last record changes the head
+        let mut journal = Journal::new(Cursor::new(bytes.clone())).unwrap();
+        let last = bytes.len() - 1;
+        journal.io.get_mut()[last] ^= 1;
+        assert!(matches!(journal.verify(), Err(JournalError::HeadMismatch { .. })));
+
+        let truncated = bytes[..bytes.len() - 1].to_vec();
+        assert!(matches!(
+            Journal::new(Cursor::new(truncated)),
+            Err(JournalError::Truncated { index: 1 })
+        ));
+    }
+}
@@ -0,0 +1,303 @@
+//! BCP 47 language tags and their language IDs.
+//!
+//! TEXT values name their language by entity ID. Language IDs are derived
+//! from the lowercase BCP 47 tag (spec Section 7.4):
+//!
+//! ```text
+//! id = derived_uuid("grc20:genesis:language:" + lowercase(tag))
+//! ```
+//!
+//! so `pt-BR`, `pt-br`, and `pt_BR` must all map to the same ID. This
+//! module parses and canonicalizes tags ([`LanguageTag`]), derives their
+//! IDs ([`language_id_for_tag`]), and maps IDs back to tags
+//! ([`LanguageRegistry`]), which a hash cannot do on its own.
+//!
+//! ```
+//! use grc_20::genesis::languages;
+//! use grc_20::lang::{canonicalize, language_id_for_tag};
+//!
+//! assert_eq!(canonicalize("zh_hans_cn").unwrap(), "zh-Hans-CN");
+//! assert_eq!(language_id_for_tag("EN").unwrap(), languages::english());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::LanguageTagError;
+use crate::genesis::{language_id, languages};
+use crate::model::Id;
+
+/// Deprecated language subtags and their preferred values (IANA registry).
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("in", "id"),
+    ("iw", "he"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("mo", "ro"),
+];
+
+/// Deprecated region subtags and their preferred values (IANA registry).
+const REGION_ALIASES: &[(&str, &str)] = &[
+    ("BU", "MM"),
+    ("DD", "DE"),
+    ("FX", "FR"),
+    ("TP", "TL"),
+    ("YD", "YE"),
+    ("ZR", "CD"),
+];
+
+/// A well-formed BCP 47 language tag.
+///
+/// Supports the `language[-script][-region](-variant)*` form plus trailing
+/// extension and private-use subtags. Extended language subtags and
+/// grandfathered tags are rejected. Parsing canonicalizes case (`zh-Hans-CN`),
+/// accepts `_` as a separator, and replaces deprecated language and region
+/// subtags with their preferred values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageTag {
+    language: String,
+    script: Option<String>,
+    region: Option<String>,
+    variants: Vec<String>,
+    extensions: Vec<String>,
+}
+
+impl LanguageTag {
+    /// Parses and canonicalizes a tag.
+    pub fn parse(tag: &str) -> Result<Self, LanguageTagError> {
+        let invalid = |reason| LanguageTagError::Invalid { tag: tag.to_string(), reason };
+        if tag.is_empty() {
+            return Err(LanguageTagError::Empty);
+        }
+        let mut subtags = tag.split(['-', '_']).peekable();
+
+        let language = subtags.next().unwrap_or_default().to_ascii_lowercase();
+        if !matches!(language.len(), 2 | 3 | 5..=8) || !is_alpha(&language) {
+            return Err(invalid("language subtag must be 2-3 or 5-8 letters"));
+        }
+        let language = alias(LANGUAGE_ALIASES, &language).unwrap_or(language);
+
+        let script = subtags
+            .next_if(|s| s.len() == 4 && is_alpha(s))
+            .map(|s| s[..1].to_ascii_uppercase() + &s[1..].to_ascii_lowercase());
+
+        let region = subtags
+            .next_if(|s| (s.len() == 2 && is_alpha(s)) || (s.len() == 3 && is_digit(s)))
+            .map(|s| s.to_ascii_uppercase());
+        let region = region.map(|r| alias(REGION_ALIASES, &r).unwrap_or(r));
+
+        let mut variants = Vec::new();
+        while let Some(variant) = subtags.next_if(|s| is_variant(s)) {
+            let variant = variant.to_ascii_lowercase();
+            if variants.contains(&variant) {
+                return Err(invalid("duplicate variant subtag"));
+            }
+            variants.push(variant);
+        }
+
+        let mut extensions = Vec::new();
+        let mut private_use = false;
+        let mut last_singleton = false;
+        for subtag in subtags {
+            if subtag.is_empty() || subtag.len() > 8 || !is_alnum(subtag) {
+                return Err(invalid("subtags must be 1-8 letters or digits"));
+            }
+            let subtag = subtag.to_ascii_lowercase();
+            if private_use {
+                extensions.push(subtag);
+                continue;
+            }
+            if subtag.len() == 1 {
+                if last_singleton {
+                    return Err(invalid("extension singleton has no subtags"));
+                }
+                private_use = subtag == "x";
+                last_singleton = true;
+            } else if extensions.is_empty() {
+                return Err(invalid("subtag is not a valid script, region, or variant"));
+            } else {
+                last_singleton = false;
+            }
+            extensions.push(subtag);
+        }
+        if (last_singleton && !private_use) || extensions.last().is_some_and(|s| s == "x") {
+            return Err(invalid("extension singleton has no subtags"));
+        }
+
+        Ok(Self { language, script, region, variants, extensions })
+    }
+
+    /// The primary language subtag (lowercase).
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The script subtag (title case), if any.
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// The region subtag (uppercase letters or UN M.49 digits), if any.
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// The variant subtags (lowercase).
+    pub fn variants(&self) -> &[String] {
+        &self.variants
+    }
+
+    /// Returns the lowercase form used to derive the language ID.
+    pub fn id_key(&self) -> String {
+        self.to_string().to_ascii_lowercase()
+    }
+
+    /// Derives the language ID for this tag.
+    pub fn id(&self) -> Id {
+        language_id(&self.id_key())
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.language)?;
+        let rest = self.script.iter().chain(&self.region).chain(&self.variants);
+        for subtag in rest.chain(&self.extensions) {
+            write!(f, "-{}", subtag)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = LanguageTagError;
+
+    fn from_str(s: &str) -> Result<Self, LanguageTagError> {
+        Self::parse(s)
+    }
+}
+
+/// Canonicalizes a language tag (see [`LanguageTag`]).
+pub fn canonicalize(tag: &str) -> Result<String, LanguageTagError> {
+    LanguageTag::parse(tag).map(|t| t.to_string())
+}
+
+/// Derives the language ID for a tag, canonicalizing it first.
+pub fn language_id_for_tag(tag: &str) -> Result<Id, LanguageTagError> {
+    LanguageTag::parse(tag).map(|t| t.id())
+}
+
+/// Maps language IDs back to the tags they were derived from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageRegistry {
+    tags: HashMap<Id, String>,
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageRegistry {
+    /// Creates a registry holding the genesis languages.
+    pub fn new() -> Self {
+        Self {
+            tags: languages::codes().map(|code| (language_id(code), code.to_string())).collect(),
+        }
+    }
+
+    /// Creates a registry with no languages.
+    pub fn empty() -> Self {
+        Self { tags: HashMap::new() }
+    }
+
+    /// Registers a tag, returning its language ID.
+    pub fn add_tag(&mut self, tag: &str) -> Result<Id, LanguageTagError> {
+        let tag = LanguageTag::parse(tag)?;
+        let id = tag.id();
+        self.tags.insert(id, tag.to_string());
+        Ok(id)
+    }
+
+    /// Gets the canonical tag for a language ID, if registered.
+    pub fn tag(&self, id: &Id) -> Option<&str> {
+        self.tags.get(id).map(String::as_str)
+    }
+
+    /// Returns true if the language ID is registered.
+    pub fn contains(&self, id: &Id) -> bool {
+        self.tags.contains_key(id)
+    }
+
+    /// Returns the number of registered languages.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns true if no languages are registered.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+fn alias(table: &[(&str, &str)], subtag: &str) -> Option<String> {
+    table.iter().find(|(from, _)| *from == subtag).map(|(_, to)| to.to_string())
+}
+
+fn is_alpha(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+fn is_digit(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_alnum(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn is_variant(s: &str) -> bool {
+    is_alnum(s)
+        && match s.len() {
+            5..=8 => true,
+            4 => s.as_bytes()[0].is_ascii_digit(),
+            _ => false,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_canonicalize() {
+        assert_eq!(canonicalize("pt_br").unwrap(), "pt-BR");
+        assert_eq!(canonicalize("ZH-hans").unwrap(), "zh-Hans");
+        assert_eq!(canonicalize("sl-rozaj-biske").unwrap(), "sl-rozaj-biske");
+        assert_eq!(canonicalize("es-419").unwrap(), "es-419");
+        assert_eq!(canonicalize("iw-ZR").unwrap(), "he-CD");
+        assert_eq!(canonicalize("en-US-u-ca-gregory-x-Foo").unwrap(), "en-US-u-ca-gregory-x-foo");
+
+        let tag = LanguageTag::parse("zh-Hant-TW").unwrap();
+        assert_eq!((tag.language(), tag.script(), tag.region()), ("zh", Some("Hant"), Some("TW")));
+        assert_eq!(tag.id(), language_id("zh-hant-tw"));
+
+        for bad in ["", "e", "english-language-tag", "en--US", "en-u", "en-US-x", "en-a-b-cd"] {
+            assert!(LanguageTag::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        assert_eq!(language_id_for_tag("EN").unwrap(), languages::english());
+
+        let mut registry = LanguageRegistry::new();
+        assert_eq!(registry.tag(&languages::japanese()), Some("ja"));
+        let id = registry.add_tag("pt_BR").unwrap();
+        assert_eq!(id, language_id("pt-br"));
+        assert_eq!(registry.tag(&id), Some("pt-BR"));
+        assert!(LanguageRegistry::empty().is_empty());
+    }
+}
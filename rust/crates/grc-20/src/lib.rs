@@ -24,6 +24,7 @@
 //!     name: Cow::Owned("My Edit".to_string()),
 //!     authors: vec![[2u8; 16]],
 //!     created_at: 1234567890,
+//!     provenance: None,
 //!     ops: vec![
 //!         Op::CreateEntity(CreateEntity {
 //!             id: [3u8; 16],
@@ -33,6 +34,7 @@
 //!                     value: Cow::Owned("Alice".to_string()),
 //!                     language: None,
 //!                 },
+//!                 clock: None,
 //!             }],
 //!         }),
 //!     ],
@@ -49,9 +51,29 @@
 //! # Modules
 //!
 //! - [`model`]: Core data types (Entity, Relation, Value, Op, Edit)
-//! - [`codec`]: Binary encoding/decoding with compression support
+//! - [`codec`]: Binary encoding/decoding with compression support (async
+//!   I/O in `codec::aio` with the `tokio` feature)
+//! - [`compression`]: Compression backends and trained zstd dictionaries
+//! - [`checksum`]: CRC32C for edit integrity trailers
+//! - [`state`]: Resolved graph state and state hashing
+//! - [`query`]: Filtered entity lookups over resolved state
+//! - [`traverse`]: Neighbors, BFS/DFS, and shortest paths over resolved state
+//! - [`ordered`]: Position-ordered relation lists over resolved state
+//! - [`entity`]: Mapping structs to entities (`#[derive(Grc20Entity)]` with
+//!   the `derive` feature)
+//! - [`store`]: Graph state in key-value stores (`sled` backend with the
+//!   `sled` feature)
 //! - [`validate`]: Semantic validation
+//! - [`transform`]: Whole-edit rewrites (ID remapping, minimizing)
+//! - [`journal`]: Hash-chained local journals of edits
+//! - [`merge`]: Clock-based merging of concurrent updates
+//! - [`testgen`]: Reproducible synthetic edits for load testing
+//! - `ipfs`: CARv1 export for publishing edits to IPFS (`ipfs` feature)
+//! - `p2p`: Gossipsub framing and validation for edit propagation (`p2p`
+//!   feature)
 //! - [`genesis`]: Well-known IDs from the Genesis Space
+//! - [`lang`]: BCP 47 language tags and language IDs
+//! - [`authors`]: Author IDs for `did:key` and Ethereum identities
 //! - [`error`]: Error types
 //! - [`limits`]: Security limits for decoding
 //!
@@ -64,35 +86,215 @@
 //!
 //! # Wire Format
 //!
-//! Edits use a binary format with optional zstd compression:
+//! Edits use a binary format with optional compression:
 //! - Uncompressed: `GRC2` magic + version + data
-//! - Compressed: `GRC2Z` magic + uncompressed size + zstd data
+//! - Compressed: `GRC2Z` (zstd), `GRC2L` (LZ4), or `GRC2N` (passthrough)
+//!   magic + uncompressed size + data
+//! - Dictionary-compressed: `GRC2D` magic + dictionary ID + uncompressed size + zstd data
 //!
-//! The decoder automatically detects and handles both formats.
+//! The decoder automatically detects and handles every format whose backend
+//! is compiled in (zstd by default; LZ4 with the `lz4` feature).
+//!
+//! # `no_std`
+//!
+//! With default features disabled the crate builds on `core` + `alloc`:
+//! the data model, builders, value/op encoding, and encoding and decoding
+//! of uncompressed edits remain available. Compression backends, genesis
+//! IDs, schema validation, state replay, and log tooling require `std`.
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature, decoding, encoding, validation, and replay
+//! run in `debug`-level `tracing` spans (`decode_edit`, `encode_edit`,
+//! `validate_edit`, `apply_edit`, ...) carrying op and byte counts, and
+//! emit events with the dictionary sizes of each edit. The feature works
+//! without `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod authors;
+pub mod checksum;
 pub mod codec;
+pub mod compression;
+#[cfg(feature = "std")]
+pub mod conformance;
+#[cfg(feature = "std")]
+pub mod entity;
 pub mod error;
+#[cfg(feature = "std")]
 pub mod genesis;
+#[cfg(feature = "ipfs")]
+pub mod ipfs;
+#[cfg(feature = "p2p")]
+pub mod p2p;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "std")]
+pub mod lang;
 pub mod limits;
 pub mod model;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod ordered;
+#[cfg(feature = "std")]
+pub mod reduce;
+#[cfg(feature = "std")]
+pub mod replica;
+#[cfg(feature = "std")]
+pub mod state;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod testgen;
+pub mod transform;
+#[cfg(feature = "std")]
+pub mod traverse;
 pub mod validate;
 
+/// FxHash-keyed maps and sets: `rustc-hash`'s with `std`, `hashbrown`'s
+/// without.
+pub(crate) mod hash {
+    #[cfg(feature = "std")]
+    pub use rustc_hash::{FxHashMap, FxHashSet};
+
+    #[cfg(not(feature = "std"))]
+    pub type FxHashMap<K, V> = hashbrown::HashMap<K, V, rustc_hash::FxBuildHasher>;
+    #[cfg(not(feature = "std"))]
+    pub type FxHashSet<K> = hashbrown::HashSet<K, rustc_hash::FxBuildHasher>;
+}
+
 // Re-export commonly used types at crate root
 pub use codec::{
-    decode_edit, decompress, encode_edit, encode_edit_compressed,
-    encode_edit_compressed_with_options, encode_edit_profiled, encode_edit_with_options,
-    EncodeOptions,
+    decode_any, decode_edit, decode_edit_lossy, decode_edit_with_options, decompress, encode_edit,
+    encode_edit_canonical, encode_edit_compressed, encode_edit_compressed_with_options,
+    encode_edit_with_options, DecodeOptions, EncodeOptions, FormatVersion,
+};
+pub use codec::raw::{decode_edit_filtered, RawEditReader, RawOp};
+pub use codec::stats::{EditStats, OpSize, SizeStats};
+pub use codec::estimate::{estimate_size, SizeEstimate};
+#[cfg(feature = "std")]
+pub use codec::encode_edit_profiled;
+#[cfg(feature = "bumpalo")]
+pub use codec::decode_edit_in;
+#[cfg(feature = "parallel")]
+pub use codec::encode_edit_parallel;
+#[cfg(feature = "tokio")]
+pub use codec::aio::{decode_edit_from, encode_edit_to};
+#[cfg(feature = "std")]
+pub use codec::audit::{audit_trail, AuditAction, AuditEntry};
+#[cfg(feature = "std")]
+pub use codec::batch::{decode_batch, encode_batch, shared_dictionary};
+#[cfg(feature = "std")]
+pub use codec::compact::{compact_log, CompactionReport};
+#[cfg(feature = "std")]
+pub use codec::delta::{apply_delta, encode_edit_delta};
+#[cfg(feature = "std")]
+pub use codec::extract::{extract_large_values, restore_large_values};
+#[cfg(feature = "std")]
+pub use codec::transaction::{
+    decode_log_entry, decode_transaction, encode_transaction, encode_transaction_raw, Transaction,
 };
-pub use error::{DecodeError, EncodeError, ValidationError};
+#[cfg(feature = "std")]
+pub use codec::usage::{find_usages, PropertyUsage, UsageKind};
+pub use compression::{
+    encode_edit_with_compression, CompressionAlgorithm, CompressionOptions,
+};
+#[cfg(feature = "zstd")]
+pub use compression::{
+    decode_edit_with_dict, encode_edit_compressed_to_writer, encode_edit_compressed_with_dict,
+    train_dictionary, CompressionDictionary,
+};
+pub use error::{
+    AliasError, DecimalError, DecodeError, EncodeError, OrderError, ParseIdError, PositionError,
+    RelationBuildError, SchemaFileError, ValidationError,
+};
+#[cfg(feature = "std")]
+pub use entity::Grc20Entity;
+#[cfg(feature = "std")]
+pub use error::{
+    AuthorError, CompactError, ExtractError, JournalError, LanguageTagError, RecurrenceError,
+    ReplicaError,
+};
+#[cfg(feature = "tokio")]
+pub use error::AioError;
+#[cfg(feature = "chrono")]
+pub use error::DateTimeError;
+#[cfg(feature = "geo")]
+pub use error::GeoError;
+#[cfg(feature = "ipfs")]
+pub use error::IpfsError;
+#[cfg(feature = "p2p")]
+pub use error::P2pError;
+#[cfg(feature = "ipfs")]
+pub use ipfs::{car_to_edit, edit_to_car, Cid};
+#[cfg(feature = "p2p")]
+pub use p2p::{EditMessage, GossipValidator, Verdict};
+#[cfg(feature = "std")]
+pub use journal::{Journal, JournalRecord};
+#[cfg(feature = "std")]
+pub use lang::{LanguageRegistry, LanguageTag};
+#[cfg(feature = "std")]
+pub use authors::{AuthorIdentity, AuthorRegistry};
+#[cfg(feature = "std")]
+pub use model::AliasTable;
 pub use model::{
-    CreateEntity, CreateRelation, DataType, DecimalMantissa, DeleteEntity,
+    AddMember, CreateEntity, CreateRelation, CreateSpace, DataType, DecimalMantissa, DeleteEntity,
     DeleteRelation, DictionaryBuilder, Edit, EditBuilder, EmbeddingSubType, EntityBuilder, Id,
-    Op, Property, PropertyValue, RelationBuilder, UnsetLanguage, UnsetRelationField, UnsetValue,
-    UpdateEntity, UpdateEntityBuilder, UpdateRelation, Value, WireDictionaries,
+    MemberRole, Op, OpKind, OpVisitor, OpVisitorMut, Property, PropertyValue, RelationBuilder,
+    RelationIdMode, RemoveMember, Space, SpaceRegistry, UnknownOp, UnsetLanguage,
+    UnsetRelationField, UnsetValue, UpdateEntity, UpdateEntityBuilder, UpdateRelation,
+    UpdateSpaceMetadata, Value, WireDictionaries,
 };
 pub use model::builder::UpdateRelationBuilder;
-pub use model::id::{derived_uuid, format_id, parse_id, text_value_id, value_id, NIL_ID};
-pub use validate::{validate_edit, validate_position, validate_value, SchemaContext};
+#[cfg(feature = "std")]
+pub use model::cost::{edit_cost, CostModel, CostReport};
+#[cfg(feature = "std")]
+pub use model::recurrence::{next_occurrence, occurrences_between, Recurrence, ScheduleIndex};
+pub use model::id::{derived_uuid, format_id, parse_id, text_value_id, value_id, IdSet, NIL_ID};
+pub use model::id::{
+    format_id_base58, format_id_multibase, parse_id_any, parse_id_base58, parse_id_base64url,
+    parse_id_multibase, space_property_id, space_relation_type_id, space_scoped_id, space_type_id,
+    DisplayId, IdEncoding,
+};
+pub use model::decimal::{format_decimal, parse_decimal};
+pub use model::position::{position_between, MAX_POSITION_LEN};
+pub use model::typed_id::{
+    unique_relation_id_typed, EntityId, LanguageId, PropertyId, RelationTypeId, SpaceId,
+};
+#[cfg(feature = "getrandom")]
+pub use model::id::{random_id, time_ordered_id_at};
+#[cfg(all(feature = "getrandom", feature = "std"))]
+pub use model::id::time_ordered_id;
+#[cfg(feature = "std")]
+pub use ordered::ListInsert;
+#[cfg(feature = "std")]
+pub use reduce::{reduce, StateChange, StateDelta, StateView};
+#[cfg(feature = "std")]
+pub use replica::{Checkpoint, Replica, ReplicaSource, Snapshot, SyncReport};
+#[cfg(feature = "std")]
+pub use state::{
+    ApplyBudget, ApplyProgress, BudgetLimit, EntityState, EntityUpdate, GraphState, RelationState,
+    ValueSlot,
+};
+pub use transform::{remap_ids, IdMap, MinimizeReport};
+#[cfg(feature = "std")]
+pub use validate::{validate_edit, validate_edit_all, SchemaContext, UnitRegistry};
+pub use validate::{
+    validate_position, validate_value, Cardinality, Deprecation, Dimension, PropertySchema,
+    RelationMode, RelationTypeSchema, Severity, ValidationIssue, ValidationProfile,
+};
+pub use validate::ids::{validate_ids, IdPolicy};
+#[cfg(feature = "std")]
+pub use validate::lifecycle::validate_lifecycle;
+pub use validate::lint::{find_conflicts, Conflict};
+pub use validate::schedule::{validate_schedule, ScheduleIssue};
+pub use model::timestamp::{validate_iso8601_date, DatePrecision};
 
 /// Crate version.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -12,6 +12,9 @@ pub const MAX_STRING_LEN: usize = 16 * 1024 * 1024;
 /// Maximum length for bytes fields (64 MB).
 pub const MAX_BYTES_LEN: usize = 64 * 1024 * 1024;
 
+/// Maximum significant digits accepted when parsing a DECIMAL string.
+pub const MAX_DECIMAL_DIGITS: usize = 10_000;
+
 /// Maximum embedding dimensions.
 pub const MAX_EMBEDDING_DIMS: usize = 65536;
 
@@ -27,6 +30,12 @@ pub const MAX_VALUES_PER_ENTITY: usize = 10_000;
 /// Maximum authors per edit.
 pub const MAX_AUTHORS: usize = 1_000;
 
+/// Maximum parent edits in an edit's provenance.
+pub const MAX_PARENT_EDITS: usize = 1_000;
+
+/// Maximum metadata entries in an edit's provenance.
+pub const MAX_METADATA_ENTRIES: usize = 1_000;
+
 /// Maximum entries in any dictionary.
 pub const MAX_DICT_SIZE: usize = 1_000_000;
 
@@ -40,10 +49,53 @@ pub const MAX_POSITION_LEN: usize = 64;
 pub const MAGIC_UNCOMPRESSED: &[u8; 4] = b"GRC2";
 
 /// Magic bytes for zstd-compressed edits.
+///
+/// LZ4 (`GRC2L`) and passthrough (`GRC2N`) edits differ only in the fifth
+/// byte; see [`CompressionAlgorithm`](crate::compression::CompressionAlgorithm).
 pub const MAGIC_COMPRESSED: &[u8; 5] = b"GRC2Z";
 
-/// Current binary format version (for encoding).
+/// Magic bytes for zstd-compressed edits that need a trained dictionary.
+pub const MAGIC_COMPRESSED_DICT: &[u8; 5] = b"GRC2D";
+
+/// Maximum size of a trained compression dictionary (1 MB).
+pub const MAX_COMPRESSION_DICT_SIZE: usize = 1024 * 1024;
+
+/// Magic bytes for batch containers with a shared dictionary.
+pub const MAGIC_BATCH: &[u8; 5] = b"GRC2B";
+
+/// Maximum edits in a batch container.
+pub const MAX_EDITS_PER_BATCH: usize = 100_000;
+
+/// Magic bytes for transaction containers (all-or-nothing edit groups).
+pub const MAGIC_TRANSACTION: &[u8; 5] = b"GRC2T";
+
+/// Maximum edits in a transaction container.
+pub const MAX_EDITS_PER_TRANSACTION: usize = 10_000;
+
+/// Magic bytes for hash-chained edit journals.
+pub const MAGIC_JOURNAL: &[u8; 5] = b"GRC2J";
+
+/// Magic bytes for delta edits (patches against a base edit).
+pub const MAGIC_DELTA: &[u8; 5] = b"GRC2P";
+
+/// Maximum edit distance, in ops, searched when diffing a delta.
+///
+/// The search keeps O(distance²) state. Past this many inserted and removed
+/// ops the delta keeps only the common prefix and suffix of the base.
+pub const MAX_DELTA_DISTANCE: usize = 1024;
+
+/// Binary format version written for containers and for edits without
+/// provenance.
 pub const FORMAT_VERSION: u8 = 1;
 
+/// Edit format version that adds the provenance block to the header.
+pub const FORMAT_VERSION_PROVENANCE: u8 = 2;
+
+/// Edit format version that adds a logical clock to every property value.
+pub const FORMAT_VERSION_VALUE_CLOCKS: u8 = 3;
+
+/// Newest edit format version this crate decodes.
+pub const MAX_FORMAT_VERSION: u8 = FORMAT_VERSION_VALUE_CLOCKS;
+
 /// Minimum supported format version (for decoding).
 pub const MIN_FORMAT_VERSION: u8 = 0;
@@ -0,0 +1,360 @@
+//! Human-readable aliases for IDs in tool output.
+//!
+//! An [`AliasTable`] maps IDs to short names such as `props:name`, so
+//! printers, lints, and diffs can show `props:name` instead of 32 hex
+//! digits, and tools can accept the alias wherever an ID is expected.
+//!
+//! Tables are persisted as a flat TOML table or a flat JSON object:
+//!
+//! ```text
+//! [aliases]
+//! "props:name" = "a126ca530c8e48d5b88882c734c38935"
+//! ```
+//!
+//! ```text
+//! { "props:name": "a126ca530c8e48d5b88882c734c38935" }
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::error::AliasError;
+use crate::genesis::{languages, properties, relation_types, types};
+use crate::model::id::{format_id, parse_id};
+use crate::model::Id;
+
+/// Bidirectional map between IDs and short aliases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasTable {
+    by_id: HashMap<Id, String>,
+    by_alias: BTreeMap<String, Id>,
+}
+
+impl AliasTable {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table with aliases for the genesis properties, types,
+    /// relation types, and languages (`props:name`, `types:person`,
+    /// `rels:types`, `langs:en`, ...).
+    pub fn genesis() -> Self {
+        let mut table = Self::new();
+        let entries = [
+            ("props:name", properties::name()),
+            ("props:description", properties::description()),
+            ("props:avatar", properties::avatar()),
+            ("props:url", properties::url()),
+            ("props:created", properties::created()),
+            ("props:modified", properties::modified()),
+            ("props:timezone", properties::timezone()),
+            ("props:deprecated", properties::deprecated()),
+            ("types:person", types::person()),
+            ("types:organization", types::organization()),
+            ("types:place", types::place()),
+            ("types:topic", types::topic()),
+            ("rels:types", relation_types::types()),
+            ("rels:part_of", relation_types::part_of()),
+            ("rels:related_to", relation_types::related_to()),
+            ("rels:replaced_by", relation_types::replaced_by()),
+            ("rels:subtype_of", relation_types::subtype_of()),
+        ];
+        for (alias, id) in entries {
+            table.insert(id, alias).expect("genesis aliases are unique");
+        }
+        for code in ["en", "es", "fr", "de", "zh", "ja", "ko", "pt", "it", "ru", "ar", "hi"] {
+            table
+                .insert(languages::from_code(code), format!("langs:{}", code))
+                .expect("genesis aliases are unique");
+        }
+        table
+    }
+
+    /// Adds an alias for an ID.
+    ///
+    /// Aliases must be non-empty, use only ASCII letters, digits, and
+    /// `_ - . : /`, and must not themselves parse as an ID. Each ID and each
+    /// alias may appear only once.
+    pub fn insert(&mut self, id: Id, alias: impl Into<String>) -> Result<(), AliasError> {
+        let alias = alias.into();
+        if let Some(reason) = invalid_alias(&alias) {
+            return Err(AliasError::InvalidAlias { alias, reason });
+        }
+        if self.by_alias.contains_key(&alias) {
+            return Err(AliasError::DuplicateAlias { alias });
+        }
+        if self.by_id.contains_key(&id) {
+            return Err(AliasError::DuplicateId { id });
+        }
+        self.by_alias.insert(alias.clone(), id);
+        self.by_id.insert(id, alias);
+        Ok(())
+    }
+
+    /// Adds every entry of `other`, failing on the first conflict.
+    pub fn extend(&mut self, other: &AliasTable) -> Result<(), AliasError> {
+        for (alias, id) in other.iter() {
+            self.insert(id, alias)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the alias for an ID.
+    pub fn alias(&self, id: &Id) -> Option<&str> {
+        self.by_id.get(id).map(String::as_str)
+    }
+
+    /// Resolves an alias or a hex ID (with or without hyphens).
+    pub fn resolve(&self, s: &str) -> Option<Id> {
+        self.by_alias.get(s).copied().or_else(|| parse_id(s))
+    }
+
+    /// Formats an ID for display: its alias if known, otherwise hex.
+    pub fn display(&self, id: &Id) -> String {
+        match self.alias(id) {
+            Some(alias) => alias.to_string(),
+            None => format_id(id),
+        }
+    }
+
+    /// Returns the number of aliases.
+    pub fn len(&self) -> usize {
+        self.by_alias.len()
+    }
+
+    /// Returns true if the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.by_alias.is_empty()
+    }
+
+    /// Iterates over (alias, ID) pairs in alias order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Id)> {
+        self.by_alias.iter().map(|(alias, id)| (alias.as_str(), *id))
+    }
+
+    /// Parses a flat TOML table of `"alias" = "hex id"` entries.
+    ///
+    /// An optional `[aliases]` header and `#` comments are accepted; other
+    /// tables and non-string values are rejected.
+    pub fn from_toml_str(s: &str) -> Result<Self, AliasError> {
+        let mut table = Self::new();
+        for (i, raw) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let line = strip_toml_comment(raw).trim();
+            if line.is_empty() || line == "[aliases]" {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(parse_error(line_no, "only an [aliases] table is supported"));
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| parse_error(line_no, "expected `alias = \"id\"`"))?;
+            let key = key.trim();
+            let alias = match key.strip_prefix('"') {
+                Some(rest) => rest
+                    .strip_suffix('"')
+                    .ok_or_else(|| parse_error(line_no, "unterminated key"))?,
+                None => key,
+            };
+            let value = value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .ok_or_else(|| parse_error(line_no, "value must be a string"))?;
+            let id =
+                parse_id(value).ok_or_else(|| parse_error(line_no, "value is not a valid ID"))?;
+            table.insert(id, alias)?;
+        }
+        Ok(table)
+    }
+
+    /// Serializes the table as TOML (stable, alias-sorted output).
+    pub fn to_toml_string(&self) -> String {
+        let mut out = String::from("[aliases]\n");
+        for (alias, id) in self.iter() {
+            out.push_str(&format!("\"{}\" = \"{}\"\n", alias, format_id(&id)));
+        }
+        out
+    }
+
+    /// Parses a flat JSON object of `"alias": "hex id"` entries.
+    pub fn from_json_str(s: &str) -> Result<Self, AliasError> {
+        let mut parser = JsonParser { input: s.as_bytes(), pos: 0 };
+        let mut table = Self::new();
+
+        parser.expect(b'{')?;
+        if !parser.consume(b'}') {
+            loop {
+                let alias = parser.string()?;
+                parser.expect(b':')?;
+                let value = parser.string()?;
+                let id = parse_id(&value)
+                    .ok_or_else(|| parse_error(parser.line(), "value is not a valid ID"))?;
+                table.insert(id, alias)?;
+                if parser.consume(b'}') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(parse_error(parser.line(), "trailing characters after object"));
+        }
+        Ok(table)
+    }
+
+    /// Serializes the table as JSON (stable, alias-sorted output).
+    pub fn to_json_string(&self) -> String {
+        let entries: Vec<String> = self
+            .iter()
+            .map(|(alias, id)| format!("  \"{}\": \"{}\"", alias, format_id(&id)))
+            .collect();
+        if entries.is_empty() {
+            "{}\n".to_string()
+        } else {
+            format!("{{\n{}\n}}\n", entries.join(",\n"))
+        }
+    }
+}
+
+/// Returns why an alias is invalid, if it is.
+fn invalid_alias(alias: &str) -> Option<&'static str> {
+    if alias.is_empty() {
+        Some("alias is empty")
+    } else if !alias.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:/".contains(c)) {
+        Some("alias contains characters other than letters, digits, and _ - . : /")
+    } else if parse_id(alias).is_some() {
+        Some("alias would be ambiguous with a hex ID")
+    } else {
+        None
+    }
+}
+
+fn strip_toml_comment(line: &str) -> &str {
+    // Aliases and IDs cannot contain '#', so the first one starts a comment
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn parse_error(line: usize, reason: &'static str) -> AliasError {
+    AliasError::Parse { line, reason }
+}
+
+/// Minimal parser for a flat JSON object of strings.
+struct JsonParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn line(&self) -> usize {
+        1 + self.input[..self.pos].iter().filter(|&&b| b == b'\n').count()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), AliasError> {
+        if self.consume(byte) {
+            Ok(())
+        } else {
+            Err(parse_error(self.line(), "unexpected character"))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, AliasError> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while let Some(&b) = self.input.get(self.pos) {
+            match b {
+                b'"' => {
+                    let s = std::str::from_utf8(&self.input[start..self.pos])
+                        .map_err(|_| parse_error(self.line(), "invalid UTF-8"))?;
+                    self.pos += 1;
+                    return Ok(s.to_string());
+                }
+                // Aliases and IDs never need escapes
+                b'\\' => return Err(parse_error(self.line(), "escape sequences are not supported")),
+                b'\n' => return Err(parse_error(self.line(), "unterminated string")),
+                _ => self.pos += 1,
+            }
+        }
+        Err(parse_error(self.line(), "unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_aliases_and_resolve() {
+        let table = AliasTable::genesis();
+        assert_eq!(table.alias(&properties::name()), Some("props:name"));
+        assert_eq!(table.display(&languages::english()), "langs:en");
+        assert_eq!(table.display(&[7u8; 16]), format_id(&[7u8; 16]));
+        assert_eq!(table.resolve("rels:types"), Some(relation_types::types()));
+        assert_eq!(table.resolve(&format_id(&[7u8; 16])), Some([7u8; 16]));
+        assert_eq!(table.resolve("props:missing"), None);
+    }
+
+    #[test]
+    fn test_insert_rejects_conflicts() {
+        let mut table = AliasTable::new();
+        table.insert([1u8; 16], "a").unwrap();
+        assert!(matches!(table.insert([2u8; 16], "a"), Err(AliasError::DuplicateAlias { .. })));
+        assert!(matches!(table.insert([1u8; 16], "b"), Err(AliasError::DuplicateId { .. })));
+        assert!(matches!(
+            table.insert([3u8; 16], "has space"),
+            Err(AliasError::InvalidAlias { .. })
+        ));
+        assert!(matches!(
+            table.insert([3u8; 16], format_id(&[4u8; 16])),
+            Err(AliasError::InvalidAlias { .. })
+        ));
+    }
+
+    #[test]
+    fn test_toml_and_json_roundtrip() {
+        let table = AliasTable::genesis();
+
+        let toml = table.to_toml_string();
+        assert_eq!(AliasTable::from_toml_str(&toml).unwrap(), table);
+
+        let json = table.to_json_string();
+        assert_eq!(AliasTable::from_json_str(&json).unwrap(), table);
+        assert_eq!(AliasTable::from_json_str(" { } ").unwrap(), AliasTable::new());
+
+        let parsed = AliasTable::from_toml_str(
+            "# team aliases\n[aliases]\ncity = \"01010101-0101-0101-0101-010101010101\" # note\n",
+        );
+        assert_eq!(parsed.unwrap().resolve("city"), Some([1u8; 16]));
+
+        assert!(matches!(
+            AliasTable::from_toml_str("[other]\n"),
+            Err(AliasError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            AliasTable::from_json_str("{\n\"a\": \"zz\"\n}"),
+            Err(AliasError::Parse { line: 2, .. })
+        ));
+    }
+}
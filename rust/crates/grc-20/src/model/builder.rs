@@ -20,11 +20,17 @@
 //!     .build();
 //! ```
 
-use std::borrow::Cow;
-
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{PositionError, RelationBuildError};
+use crate::model::id::unique_relation_id;
+use crate::model::op::validate_position;
+use crate::model::position::position_between;
 use crate::model::{
     CreateEntity, CreateRelation, DeleteEntity, DeleteRelation,
-    Edit, Id, Op, PropertyValue, RestoreEntity, RestoreRelation, UnsetRelationField,
+    Edit, Id, Op, PropertyValue, Provenance, RestoreEntity, RestoreRelation, UnsetRelationField,
     UnsetLanguage, UnsetValue, UpdateEntity, UpdateRelation, Value,
 };
 
@@ -35,6 +41,7 @@ pub struct EditBuilder<'a> {
     name: Cow<'a, str>,
     authors: Vec<Id>,
     created_at: i64,
+    provenance: Option<Provenance<'a>>,
     ops: Vec<Op<'a>>,
 }
 
@@ -46,6 +53,7 @@ impl<'a> EditBuilder<'a> {
             name: Cow::Borrowed(""),
             authors: Vec::new(),
             created_at: 0,
+            provenance: None,
             ops: Vec::new(),
         }
     }
@@ -75,6 +83,7 @@ impl<'a> EditBuilder<'a> {
     }
 
     /// Sets the creation timestamp to now.
+    #[cfg(feature = "std")]
     pub fn created_now(mut self) -> Self {
         use std::time::{SystemTime, UNIX_EPOCH};
         let micros = SystemTime::now()
@@ -85,6 +94,29 @@ impl<'a> EditBuilder<'a> {
         self
     }
 
+    /// Sets the application that produced the edit (provenance).
+    pub fn source(mut self, source: Id) -> Self {
+        self.provenance.get_or_insert_with(Provenance::default).source = Some(source);
+        self
+    }
+
+    /// Adds a parent edit this edit was derived from (provenance).
+    pub fn parent(mut self, edit_id: Id) -> Self {
+        self.provenance.get_or_insert_with(Provenance::default).parents.push(edit_id);
+        self
+    }
+
+    /// Adds a provenance metadata entry.
+    pub fn metadata(
+        mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        let provenance = self.provenance.get_or_insert_with(Provenance::default);
+        provenance.metadata.push((key.into(), value.into()));
+        self
+    }
+
     // =========================================================================
     // Entity Operations
     // =========================================================================
@@ -121,6 +153,7 @@ impl<'a> EditBuilder<'a> {
             id: builder.id,
             set_properties: builder.set_properties,
             unset_values: builder.unset_values,
+            remove_values: builder.remove_values,
         }));
         self
     }
@@ -166,7 +199,16 @@ impl<'a> EditBuilder<'a> {
         self
     }
 
+    /// Adds a unique-mode CreateRelation operation, whose ID is derived
+    /// from the endpoints and type.
+    pub fn create_unique_relation(self, from: Id, to: Id, relation_type: Id) -> Self {
+        self.create_relation(|r| r.unique().from(from).to(to).relation_type(relation_type))
+    }
+
     /// Adds a CreateRelation operation with full control using a builder.
+    ///
+    /// The relation is skipped if the builder is incomplete or its position
+    /// is invalid; use [`RelationBuilder::try_build`] to see why.
     pub fn create_relation<F>(mut self, f: F) -> Self
     where
         F: FnOnce(RelationBuilder<'a>) -> RelationBuilder<'a>,
@@ -249,6 +291,7 @@ impl<'a> EditBuilder<'a> {
             name: self.name,
             authors: self.authors,
             created_at: self.created_at,
+            provenance: self.provenance,
             ops: self.ops,
         }
     }
@@ -273,7 +316,7 @@ impl<'a> EntityBuilder<'a> {
 
     /// Adds a property value.
     pub fn value(mut self, property: Id, value: Value<'a>) -> Self {
-        self.values.push(PropertyValue { property, value });
+        self.values.push(PropertyValue { property, value, clock: None });
         self
     }
 
@@ -290,6 +333,7 @@ impl<'a> EntityBuilder<'a> {
                 value: value.into(),
                 language,
             },
+            clock: None,
         });
         self
     }
@@ -299,6 +343,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Int64 { value, unit },
+            clock: None,
         });
         self
     }
@@ -308,6 +353,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Float64 { value, unit },
+            clock: None,
         });
         self
     }
@@ -317,6 +363,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Bool(value),
+            clock: None,
         });
         self
     }
@@ -326,6 +373,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Bytes(value.into()),
+            clock: None,
         });
         self
     }
@@ -335,6 +383,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Point { lon, lat, alt },
+            clock: None,
         });
         self
     }
@@ -344,6 +393,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Date(value.into()),
+            clock: None,
         });
         self
     }
@@ -353,6 +403,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Time(value.into()),
+            clock: None,
         });
         self
     }
@@ -362,6 +413,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Datetime(value.into()),
+            clock: None,
         });
         self
     }
@@ -371,6 +423,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Schedule(value.into()),
+            clock: None,
         });
         self
     }
@@ -386,6 +439,7 @@ impl<'a> EntityBuilder<'a> {
         self.values.push(PropertyValue {
             property,
             value: Value::Decimal { exponent, mantissa, unit },
+            clock: None,
         });
         self
     }
@@ -405,6 +459,17 @@ impl<'a> EntityBuilder<'a> {
                 dims,
                 data: data.into(),
             },
+            clock: None,
+        });
+        self
+    }
+
+    /// Adds a FLOAT32 EMBEDDING value from its dimensions.
+    pub fn embedding_f32(mut self, property: Id, values: &[f32]) -> Self {
+        self.values.push(PropertyValue {
+            property,
+            value: Value::embedding_from_f32(values),
+            clock: None,
         });
         self
     }
@@ -416,6 +481,7 @@ pub struct UpdateEntityBuilder<'a> {
     id: Id,
     set_properties: Vec<PropertyValue<'a>>,
     unset_values: Vec<UnsetValue>,
+    remove_values: Vec<Id>,
 }
 
 impl<'a> UpdateEntityBuilder<'a> {
@@ -425,12 +491,13 @@ impl<'a> UpdateEntityBuilder<'a> {
             id,
             set_properties: Vec::new(),
             unset_values: Vec::new(),
+            remove_values: Vec::new(),
         }
     }
 
     /// Sets a property value.
     pub fn set(mut self, property: Id, value: Value<'a>) -> Self {
-        self.set_properties.push(PropertyValue { property, value });
+        self.set_properties.push(PropertyValue { property, value, clock: None });
         self
     }
 
@@ -447,6 +514,7 @@ impl<'a> UpdateEntityBuilder<'a> {
                 value: value.into(),
                 language,
             },
+            clock: None,
         });
         self
     }
@@ -456,6 +524,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Int64 { value, unit },
+            clock: None,
         });
         self
     }
@@ -465,6 +534,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Float64 { value, unit },
+            clock: None,
         });
         self
     }
@@ -474,6 +544,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Bool(value),
+            clock: None,
         });
         self
     }
@@ -483,6 +554,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Point { lon, lat, alt },
+            clock: None,
         });
         self
     }
@@ -492,6 +564,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Date(value.into()),
+            clock: None,
         });
         self
     }
@@ -501,6 +574,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Time(value.into()),
+            clock: None,
         });
         self
     }
@@ -510,6 +584,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Datetime(value.into()),
+            clock: None,
         });
         self
     }
@@ -519,6 +594,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Schedule(value.into()),
+            clock: None,
         });
         self
     }
@@ -528,6 +604,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Bytes(value.into()),
+            clock: None,
         });
         self
     }
@@ -543,6 +620,7 @@ impl<'a> UpdateEntityBuilder<'a> {
         self.set_properties.push(PropertyValue {
             property,
             value: Value::Decimal { exponent, mantissa, unit },
+            clock: None,
         });
         self
     }
@@ -562,6 +640,17 @@ impl<'a> UpdateEntityBuilder<'a> {
                 dims,
                 data: data.into(),
             },
+            clock: None,
+        });
+        self
+    }
+
+    /// Sets a FLOAT32 EMBEDDING value from its dimensions.
+    pub fn set_embedding_f32(mut self, property: Id, values: &[f32]) -> Self {
+        self.set_properties.push(PropertyValue {
+            property,
+            value: Value::embedding_from_f32(values),
+            clock: None,
         });
         self
     }
@@ -598,12 +687,29 @@ impl<'a> UpdateEntityBuilder<'a> {
         });
         self
     }
+
+    /// Removes the value with the given value ID, if it is still current.
+    pub fn remove_value(mut self, value_id: Id) -> Self {
+        self.remove_values.push(value_id);
+        self
+    }
+}
+
+/// How a relation's ID is chosen (spec Section 4.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationIdMode {
+    /// An explicit ID, so several relations may link the same entities
+    /// with the same type.
+    Instance(Id),
+    /// The ID is `unique_relation_id(from, to, relation_type)`, so at most
+    /// one such relation exists and recreating it is idempotent.
+    Unique,
 }
 
 /// Builder for CreateRelation operations with full control.
 #[derive(Debug, Clone, Default)]
 pub struct RelationBuilder<'a> {
-    id: Option<Id>,
+    id_mode: Option<RelationIdMode>,
     relation_type: Option<Id>,
     from: Option<Id>,
     from_is_value_ref: bool,
@@ -611,6 +717,7 @@ pub struct RelationBuilder<'a> {
     to_is_value_ref: bool,
     entity: Option<Id>,
     position: Option<Cow<'a, str>>,
+    position_error: Option<PositionError>,
     from_space: Option<Id>,
     from_version: Option<Id>,
     to_space: Option<Id>,
@@ -623,9 +730,21 @@ impl<'a> RelationBuilder<'a> {
         Self::default()
     }
 
-    /// Sets the relation ID.
+    /// Sets an explicit relation ID (instance mode).
     pub fn id(mut self, id: Id) -> Self {
-        self.id = Some(id);
+        self.id_mode = Some(RelationIdMode::Instance(id));
+        self
+    }
+
+    /// Derives the relation ID from the endpoints and type (unique mode).
+    pub fn unique(mut self) -> Self {
+        self.id_mode = Some(RelationIdMode::Unique);
+        self
+    }
+
+    /// Sets how the relation ID is chosen.
+    pub fn id_mode(mut self, mode: RelationIdMode) -> Self {
+        self.id_mode = Some(mode);
         self
     }
 
@@ -656,6 +775,26 @@ impl<'a> RelationBuilder<'a> {
     /// Sets the position string for ordering.
     pub fn position(mut self, pos: impl Into<Cow<'a, str>>) -> Self {
         self.position = Some(pos.into());
+        self.position_error = None;
+        self
+    }
+
+    /// Sets a generated position that sorts between `before` and `after`,
+    /// where None is the start or end of the list.
+    ///
+    /// See [`position_between`] for the errors, which are reported by
+    /// [`try_build`](Self::try_build).
+    pub fn position_between(mut self, before: Option<&str>, after: Option<&str>) -> Self {
+        match position_between(before, after) {
+            Ok(pos) => {
+                self.position = Some(Cow::Owned(pos));
+                self.position_error = None;
+            }
+            Err(e) => {
+                self.position = None;
+                self.position_error = Some(e);
+            }
+        }
         self
     }
 
@@ -683,14 +822,36 @@ impl<'a> RelationBuilder<'a> {
         self
     }
 
-    /// Builds the CreateRelation, returning None if required fields are missing.
+    /// Builds the CreateRelation, returning None if required fields are
+    /// missing or the position is invalid.
     pub fn build(self) -> Option<CreateRelation<'a>> {
-        Some(CreateRelation {
-            id: self.id?,
-            relation_type: self.relation_type?,
-            from: self.from?,
+        self.try_build().ok()
+    }
+
+    /// Builds the CreateRelation, reporting missing fields and invalid
+    /// positions.
+    pub fn try_build(self) -> Result<CreateRelation<'a>, RelationBuildError> {
+        let missing = |field| RelationBuildError::MissingField { field };
+        let id_mode = self.id_mode.ok_or(missing("ID"))?;
+        let relation_type = self.relation_type.ok_or(missing("relation type"))?;
+        let from = self.from.ok_or(missing("source"))?;
+        let to = self.to.ok_or(missing("target"))?;
+        if let Some(e) = self.position_error {
+            return Err(e.into());
+        }
+        if let Some(pos) = &self.position {
+            validate_position(pos).map_err(|reason| PositionError::Invalid { reason })?;
+        }
+        let id = match id_mode {
+            RelationIdMode::Instance(id) => id,
+            RelationIdMode::Unique => unique_relation_id(&from, &to, &relation_type),
+        };
+        Ok(CreateRelation {
+            id,
+            relation_type,
+            from,
             from_is_value_ref: self.from_is_value_ref,
-            to: self.to?,
+            to,
             to_is_value_ref: self.to_is_value_ref,
             entity: self.entity,
             position: self.position,
@@ -915,6 +1076,53 @@ mod tests {
     }
 
     #[test]
+    fn test_relation_builder_id_modes() {
+        let (from, to, ty) = ([2u8; 16], [3u8; 16], [4u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_unique_relation(from, to, ty)
+            .create_relation(|r| r.id([1u8; 16]).from(from).to(to).relation_type(ty))
+            .build();
+
+        let ids: Vec<Id> = edit
+            .ops
+            .iter()
+            .map(|op| match op {
+                Op::CreateRelation(cr) => cr.id,
+                _ => panic!("Expected CreateRelation"),
+            })
+            .collect();
+        assert_eq!(ids, vec![unique_relation_id(&from, &to, &ty), [1u8; 16]]);
+
+        let missing = RelationBuilder::new().unique().from(from).to(to).try_build();
+        assert_eq!(missing, Err(RelationBuildError::MissingField { field: "relation type" }));
+    }
+
+    #[test]
+    fn test_relation_builder_positions() {
+        let relation = || {
+            RelationBuilder::new().unique().from([2u8; 16]).to([3u8; 16]).relation_type([4u8; 16])
+        };
+
+        let first = relation().position_between(None, None).try_build().unwrap();
+        assert_eq!(first.position.as_deref(), Some("a"));
+        let next = relation()
+            .position_between(first.position.as_deref(), Some("b"))
+            .try_build()
+            .unwrap();
+        assert_eq!(next.position.as_deref(), Some("aV"));
+
+        let unordered = relation().position_between(Some("b"), Some("a")).try_build();
+        assert_eq!(unordered, Err(RelationBuildError::Position(PositionError::NotOrdered)));
+        let invalid = relation().position("not valid");
+        assert!(matches!(
+            invalid.clone().try_build(),
+            Err(RelationBuildError::Position(PositionError::Invalid { .. }))
+        ));
+        assert_eq!(EditBuilder::new([0u8; 16]).create_relation(|_| invalid).build().ops.len(), 0);
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn test_entity_builder_all_types() {
         let edit = EditBuilder::new([0u8; 16])
             .create_entity([1u8; 16], |e| {
@@ -0,0 +1,211 @@
+//! Helpers for BYTES values: content hashing, MIME sniffing, and linting.
+//!
+//! These let attachment-heavy imports dedupe blobs by content and reject
+//! oversized or unexpected payloads consistently across tools.
+
+use sha2::{Digest, Sha256};
+
+use crate::model::Value;
+
+/// Hash algorithm for content addressing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashAlgorithm {
+    /// SHA-256 (always available).
+    #[default]
+    Sha256,
+    /// BLAKE3 (requires the `blake3` feature).
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+/// Computes the content hash of a byte payload.
+pub fn content_hash(data: &[u8], algorithm: HashAlgorithm) -> [u8; 32] {
+    match algorithm {
+        HashAlgorithm::Sha256 => {
+            let hash = Sha256::digest(data);
+            let mut out = [0u8; 32];
+            out.copy_from_slice(&hash);
+            out
+        }
+        #[cfg(feature = "blake3")]
+        HashAlgorithm::Blake3 => *blake3::hash(data).as_bytes(),
+    }
+}
+
+/// Formats a content hash as lowercase hex.
+pub fn format_hash(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Magic-byte signatures, checked in order.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"GRC2", "application/x-grc20"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"\x1a\x45\xdf\xa3", "video/webm"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\x00asm", "application/wasm"),
+];
+
+/// Guesses the MIME type of a byte payload from its leading bytes.
+///
+/// Recognizes common image, audio, video, document, and archive formats.
+/// Payloads that are valid UTF-8 without control characters are reported
+/// as `text/plain` (or `image/svg+xml` / `application/json` when they look
+/// like those). Returns None for unrecognized binary data.
+pub fn sniff_mime(data: &[u8]) -> Option<&'static str> {
+    for (magic, mime) in SIGNATURES {
+        if data.starts_with(magic) {
+            return Some(mime);
+        }
+    }
+
+    // RIFF containers: RIFF....WEBP / RIFF....WAVE
+    if data.len() >= 12 && data.starts_with(b"RIFF") {
+        match &data[8..12] {
+            b"WEBP" => return Some("image/webp"),
+            b"WAVE" => return Some("audio/wav"),
+            _ => {}
+        }
+    }
+    // ISO base media: ....ftyp
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        return Some(match &data[8..12] {
+            b"avif" => "image/avif",
+            b"heic" | b"heix" => "image/heic",
+            b"qt  " => "video/quicktime",
+            _ => "video/mp4",
+        });
+    }
+
+    let text = std::str::from_utf8(data).ok()?;
+    if text.chars().any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t')) {
+        return None;
+    }
+    let trimmed = text.trim_start();
+    if trimmed.starts_with("<svg") || (trimmed.starts_with("<?xml") && trimmed.contains("<svg")) {
+        Some("image/svg+xml")
+    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        Some("application/json")
+    } else {
+        Some("text/plain")
+    }
+}
+
+/// Size and type policy for BYTES values.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BytesPolicy {
+    /// Maximum payload size in bytes (None = no limit beyond the codec's).
+    pub max_size: Option<usize>,
+    /// Allowed MIME types (empty = any). Entries ending in `/*` match a
+    /// whole top-level type, e.g. `image/*`.
+    pub allowed_mime: Vec<&'static str>,
+}
+
+/// Checks a byte payload against a policy.
+///
+/// Returns a description of the first violation, if any.
+pub fn lint_bytes(data: &[u8], policy: &BytesPolicy) -> Option<&'static str> {
+    if policy.max_size.is_some_and(|max| data.len() > max) {
+        return Some("BYTES payload exceeds maximum size");
+    }
+    if policy.allowed_mime.is_empty() {
+        return None;
+    }
+    let Some(mime) = sniff_mime(data) else {
+        return Some("BYTES payload has an unrecognized type");
+    };
+    let allowed = policy.allowed_mime.iter().any(|pattern| match pattern.strip_suffix("/*") {
+        Some(top) => mime.split('/').next() == Some(top),
+        None => *pattern == mime,
+    });
+    if allowed {
+        None
+    } else {
+        Some("BYTES payload type is not allowed")
+    }
+}
+
+impl Value<'_> {
+    /// Returns the content hash of a BYTES value (None for other types).
+    pub fn content_hash(&self, algorithm: HashAlgorithm) -> Option<[u8; 32]> {
+        match self {
+            Value::Bytes(data) => Some(content_hash(data, algorithm)),
+            _ => None,
+        }
+    }
+
+    /// Guesses the MIME type of a BYTES value (None for other types or
+    /// unrecognized payloads).
+    pub fn sniff_mime(&self) -> Option<&'static str> {
+        match self {
+            Value::Bytes(data) => sniff_mime(data),
+            _ => None,
+        }
+    }
+
+    /// Checks a BYTES value against a policy (None for other types).
+    pub fn lint_bytes(&self, policy: &BytesPolicy) -> Option<&'static str> {
+        match self {
+            Value::Bytes(data) => lint_bytes(data, policy),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_sha256_hash() {
+        let hash = content_hash(b"abc", HashAlgorithm::Sha256);
+        assert_eq!(
+            format_hash(&hash),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let value = Value::Bytes(Cow::Borrowed(b"abc"));
+        assert_eq!(value.content_hash(HashAlgorithm::Sha256), Some(hash));
+        assert_eq!(Value::Bool(true).content_hash(HashAlgorithm::Sha256), None);
+    }
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff_mime(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_mime(b"\0\0\0\x18ftypmp42"), Some("video/mp4"));
+        assert_eq!(sniff_mime(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), Some("image/svg+xml"));
+        assert_eq!(sniff_mime(b"{\"a\": 1}"), Some("application/json"));
+        assert_eq!(sniff_mime(b"hello\nworld"), Some("text/plain"));
+        assert_eq!(sniff_mime(&[0x00, 0x01, 0x02, 0xff]), None);
+    }
+
+    #[test]
+    fn test_lint_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let policy = BytesPolicy {
+            max_size: Some(16),
+            allowed_mime: vec!["image/*", "application/pdf"],
+        };
+        assert_eq!(lint_bytes(png, &policy), None);
+        assert_eq!(lint_bytes(b"%PDF-1.7", &policy), None);
+        assert_eq!(lint_bytes(b"plain text", &policy), Some("BYTES payload type is not allowed"));
+        assert_eq!(lint_bytes(&[0u8; 17], &policy), Some("BYTES payload exceeds maximum size"));
+        assert_eq!(lint_bytes(&[0xffu8, 0x00], &policy), Some("BYTES payload has an unrecognized type"));
+        assert_eq!(lint_bytes(&[0u8; 64], &BytesPolicy::default()), None);
+    }
+}
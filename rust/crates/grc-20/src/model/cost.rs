@@ -0,0 +1,237 @@
+//! Cost model for pricing and capping edits.
+//!
+//! Gateways and governance need to price edits the same way across
+//! implementations, so the model is deliberately simple and independent of
+//! the wire encoding (dictionary reuse and compression do not change cost):
+//!
+//! ```text
+//! cost = per_op          * ops
+//!      + per_value       * (values set + values unset)
+//!      + per_value_byte  * payload bytes
+//!      + per_embedding_dim * embedding dimensions
+//! ```
+//!
+//! Payload bytes per value:
+//!
+//! | Type                                        | Bytes                           |
+//! |---------------------------------------------|---------------------------------|
+//! | BOOL                                        | 1                               |
+//! | INT64, FLOAT64                              | 8                               |
+//! | DECIMAL                                     | 4 + mantissa (8 if it fits i64) |
+//! | TEXT, BYTES, DATE, TIME, DATETIME, SCHEDULE | UTF-8 / raw length              |
+//! | POINT                                       | 16, or 24 with altitude         |
+//! | EMBEDDING                                   | raw data length                 |
+//!
+//! A relation position string counts as payload bytes of its op. All
+//! arithmetic saturates at `u64::MAX`.
+
+use crate::model::{DecimalMantissa, Edit, Op, Value};
+
+/// Weights of the cost model.
+///
+/// The [`Default`] weights are the reference values; implementations that
+/// want to agree on cost should use them unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    /// Cost of each op.
+    pub per_op: u64,
+    /// Cost of each value set or unset.
+    pub per_value: u64,
+    /// Cost of each payload byte.
+    pub per_value_byte: u64,
+    /// Cost of each embedding dimension (in addition to its bytes).
+    pub per_embedding_dim: u64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            per_op: 100,
+            per_value: 10,
+            per_value_byte: 1,
+            per_embedding_dim: 2,
+        }
+    }
+}
+
+/// Cost breakdown for an edit.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CostReport {
+    /// Total cost.
+    pub total: u64,
+    /// Cost of each op, in op order.
+    pub op_costs: Vec<u64>,
+    /// Number of ops.
+    pub ops: usize,
+    /// Number of values set or unset.
+    pub values: usize,
+    /// Total payload bytes.
+    pub value_bytes: u64,
+    /// Total embedding dimensions.
+    pub embedding_dims: u64,
+}
+
+impl CostReport {
+    /// Returns true if the total cost exceeds `limit`.
+    pub fn exceeds(&self, limit: u64) -> bool {
+        self.total > limit
+    }
+
+    /// Returns the index and cost of the most expensive op.
+    pub fn max_op(&self) -> Option<(usize, u64)> {
+        self.op_costs
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|&(i, cost)| (cost, std::cmp::Reverse(i)))
+    }
+}
+
+impl CostModel {
+    /// Computes the cost of an edit under this model.
+    pub fn edit_cost(&self, edit: &Edit) -> CostReport {
+        let mut report = CostReport {
+            op_costs: Vec::with_capacity(edit.ops.len()),
+            ops: edit.ops.len(),
+            ..CostReport::default()
+        };
+        for op in &edit.ops {
+            let cost = self.op_cost(op, &mut report);
+            report.op_costs.push(cost);
+            report.total = report.total.saturating_add(cost);
+        }
+        report
+    }
+
+    fn op_cost(&self, op: &Op, report: &mut CostReport) -> u64 {
+        let mut cost = self.per_op;
+        let mut values = 0usize;
+        let mut bytes = 0u64;
+        let mut dims = 0u64;
+
+        let mut add_value = |value: &Value| {
+            values += 1;
+            bytes = bytes.saturating_add(value_bytes(value));
+            if let Value::Embedding { dims: d, .. } = value {
+                dims = dims.saturating_add(*d as u64);
+            }
+        };
+
+        match op {
+            Op::CreateEntity(ce) => ce.values.iter().for_each(|pv| add_value(&pv.value)),
+            Op::UpdateEntity(ue) => {
+                ue.set_properties.iter().for_each(|pv| add_value(&pv.value));
+                values += ue.unset_values.len() + ue.remove_values.len();
+            }
+            Op::CreateRelation(cr) => {
+                bytes = cr.position.as_ref().map_or(0, |p| p.len() as u64);
+            }
+            Op::UpdateRelation(ur) => {
+                bytes = ur.position.as_ref().map_or(0, |p| p.len() as u64);
+            }
+            Op::DeleteEntity(_)
+            | Op::RestoreEntity(_)
+            | Op::DeleteRelation(_)
+            | Op::RestoreRelation(_)
+            | Op::CreateValueRef(_)
+            | Op::AddMember(_)
+            | Op::RemoveMember(_) => {}
+            Op::CreateSpace(cs) => bytes = cs.name.len() as u64,
+            Op::UpdateSpaceMetadata(usm) => {
+                bytes = usm.name.as_ref().map_or(0, |n| n.len() as u64)
+                    + usm.description.as_ref().map_or(0, |d| d.len() as u64);
+            }
+            Op::Unknown(op) => bytes = op.bytes.len() as u64,
+        }
+
+        cost = cost
+            .saturating_add(self.per_value.saturating_mul(values as u64))
+            .saturating_add(self.per_value_byte.saturating_mul(bytes))
+            .saturating_add(self.per_embedding_dim.saturating_mul(dims));
+
+        report.values += values;
+        report.value_bytes = report.value_bytes.saturating_add(bytes);
+        report.embedding_dims = report.embedding_dims.saturating_add(dims);
+        cost
+    }
+}
+
+/// Computes the cost of an edit under the reference [`CostModel`].
+pub fn edit_cost(edit: &Edit) -> CostReport {
+    CostModel::default().edit_cost(edit)
+}
+
+/// Returns the payload size of a value as defined by the cost model.
+pub fn value_bytes(value: &Value) -> u64 {
+    let len = match value {
+        Value::Bool(_) => 1,
+        Value::Int64 { .. } | Value::Float64 { .. } => 8,
+        Value::Decimal { mantissa, .. } => {
+            4 + match mantissa {
+                DecimalMantissa::I64(_) => 8,
+                DecimalMantissa::Big(bytes) => bytes.len(),
+            }
+        }
+        Value::Text { value, .. } => value.len(),
+        Value::Bytes(data) => data.len(),
+        Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => s.len(),
+        Value::Point { alt, .. } => if alt.is_some() { 24 } else { 16 },
+        Value::Embedding { data, .. } => data.len(),
+    };
+    len as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::model::{CreateEntity, DeleteEntity, EmbeddingSubType, PropertyValue};
+
+    #[test]
+    fn test_edit_cost_reference_model() {
+        let mut edit = Edit::new([0u8; 16]);
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: [1u8; 16],
+            values: vec![
+                PropertyValue {
+                    property: [2u8; 16],
+                    value: Value::Text { value: Cow::Borrowed("hello"), language: None },
+                    clock: None,
+                },
+                PropertyValue {
+                    property: [3u8; 16],
+                    value: Value::Embedding {
+                        sub_type: EmbeddingSubType::Float32,
+                        dims: 4,
+                        data: Cow::Owned(vec![0u8; 16]),
+                    },
+                    clock: None,
+                },
+            ],
+        }));
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [1u8; 16] }));
+
+        let report = edit_cost(&edit);
+        // op 0: 100 + 2 values * 10 + (5 + 16) bytes + 4 dims * 2
+        assert_eq!(report.op_costs, vec![149, 100]);
+        assert_eq!(report.total, 249);
+        assert_eq!(report.values, 2);
+        assert_eq!(report.value_bytes, 21);
+        assert_eq!(report.embedding_dims, 4);
+        assert_eq!(report.max_op(), Some((0, 149)));
+        assert!(report.exceeds(200));
+        assert!(!report.exceeds(249));
+    }
+
+    #[test]
+    fn test_custom_model_saturates() {
+        let mut edit = Edit::new([0u8; 16]);
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [1u8; 16] }));
+        edit.ops.push(Op::DeleteEntity(DeleteEntity { id: [2u8; 16] }));
+
+        let model = CostModel { per_op: u64::MAX, ..CostModel::default() };
+        assert_eq!(model.edit_cost(&edit).total, u64::MAX);
+        assert_eq!(edit_cost(&Edit::new([0u8; 16])), CostReport::default());
+    }
+}
@@ -0,0 +1,145 @@
+//! `chrono` conversions for DATE and DATETIME values (`chrono` feature).
+//!
+//! DATE strings are read and written with [`parse_date_value`] and
+//! [`format_date_value`], so a DATE accepted here is exactly one the spec
+//! grammar accepts (including leap days and astronomical years). DATE keeps
+//! its precision: converting a `NaiveDate` takes a [`DatePrecision`], and
+//! reading a year or year-month DATE back yields the first day of the
+//! period alongside its precision.
+//!
+//! DATETIME values and timestamps (epoch microseconds, e.g.
+//! `Edit::created_at`) convert to `DateTime<Utc>`. Both are microsecond
+//! precision; finer `chrono` values are truncated.
+//!
+//! ```ignore
+//! let value = Value::date_from_naive(birthday, DatePrecision::Month)?; // "1990-06"
+//! let (first_day, precision) = value.as_naive_date()?;
+//! let created = timestamp_to_utc(edit.created_at as i64);
+//! ```
+
+use alloc::borrow::Cow;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::error::DateTimeError;
+use crate::model::timestamp::{
+    format_date_value, format_timestamp, parse_date_value, parse_datetime, DatePrecision,
+};
+use crate::model::Value;
+
+impl Value<'static> {
+    /// Builds a DATE value from a `NaiveDate` at the given precision.
+    ///
+    /// Fails for years outside -9999..=9999, which DATE cannot represent.
+    pub fn date_from_naive(
+        date: NaiveDate,
+        precision: DatePrecision,
+    ) -> Result<Self, DateTimeError> {
+        format_date_value(date.year() as i64, date.month(), date.day(), precision)
+            .map(|s| Value::Date(Cow::Owned(s)))
+            .ok_or(DateTimeError::OutOfRange)
+    }
+}
+
+impl Value<'_> {
+    /// Reads a DATE value as a `NaiveDate` and its precision.
+    ///
+    /// Year and year-month dates map to the first day of the period.
+    pub fn as_naive_date(&self) -> Result<(NaiveDate, DatePrecision), DateTimeError> {
+        let Value::Date(s) = self else {
+            return Err(DateTimeError::NotDateValue);
+        };
+        let (year, month, day, precision) =
+            parse_date_value(s).ok_or(DateTimeError::InvalidDate)?;
+        let date =
+            NaiveDate::from_ymd_opt(year as i32, month, day).ok_or(DateTimeError::OutOfRange)?;
+        Ok((date, precision))
+    }
+}
+
+impl TryFrom<NaiveDate> for Value<'static> {
+    type Error = DateTimeError;
+
+    /// Converts to a full-precision (`YYYY-MM-DD`) DATE.
+    fn try_from(date: NaiveDate) -> Result<Self, DateTimeError> {
+        Value::date_from_naive(date, DatePrecision::Day)
+    }
+}
+
+impl TryFrom<&Value<'_>> for NaiveDate {
+    type Error = DateTimeError;
+
+    /// Converts a full-precision DATE; use [`Value::as_naive_date`] to
+    /// accept year and year-month dates.
+    fn try_from(value: &Value<'_>) -> Result<Self, DateTimeError> {
+        match value.as_naive_date()? {
+            (date, DatePrecision::Day) => Ok(date),
+            (_, precision) => Err(DateTimeError::ReducedPrecision { precision }),
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for Value<'static> {
+    /// Converts to a DATETIME in UTC (`...Z`).
+    fn from(dt: DateTime<Utc>) -> Self {
+        let s = format_timestamp(dt.timestamp_micros(), 0).expect("zero offset is in range");
+        Value::Datetime(Cow::Owned(s))
+    }
+}
+
+impl TryFrom<&Value<'_>> for DateTime<Utc> {
+    type Error = DateTimeError;
+
+    /// Converts a DATETIME, normalizing its offset to UTC.
+    fn try_from(value: &Value<'_>) -> Result<Self, DateTimeError> {
+        let Value::Datetime(s) = value else {
+            return Err(DateTimeError::NotDatetimeValue);
+        };
+        let (micros, _) = parse_datetime(s).ok_or(DateTimeError::InvalidDatetime)?;
+        timestamp_to_utc(micros).ok_or(DateTimeError::OutOfRange)
+    }
+}
+
+/// Converts a timestamp (epoch microseconds) to a `DateTime<Utc>`.
+///
+/// Returns None outside `chrono`'s supported range.
+pub fn timestamp_to_utc(micros: i64) -> Option<DateTime<Utc>> {
+    DateTime::<Utc>::from_timestamp_micros(micros)
+}
+
+/// Converts a `DateTime<Utc>` to a timestamp (epoch microseconds).
+pub fn utc_to_timestamp(dt: DateTime<Utc>) -> i64 {
+    dt.timestamp_micros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrono_roundtrip() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let value = Value::try_from(date).unwrap();
+        assert_eq!(value, Value::Date(Cow::Borrowed("2024-02-29")));
+        assert_eq!(NaiveDate::try_from(&value).unwrap(), date);
+
+        let month = Value::date_from_naive(date, DatePrecision::Month).unwrap();
+        assert_eq!(month, Value::Date(Cow::Borrowed("2024-02")));
+        let first = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        assert_eq!(month.as_naive_date().unwrap(), (first, DatePrecision::Month));
+        assert_eq!(
+            NaiveDate::try_from(&month),
+            Err(DateTimeError::ReducedPrecision { precision: DatePrecision::Month })
+        );
+        let leap = Value::Date(Cow::Borrowed("2023-02-29"));
+        assert_eq!(leap.as_naive_date(), Err(DateTimeError::InvalidDate));
+
+        let dt = timestamp_to_utc(1_710_460_800_500_000).unwrap();
+        let value = Value::from(dt);
+        assert_eq!(value, Value::Datetime(Cow::Borrowed("2024-03-15T00:00:00.5Z")));
+        assert_eq!(DateTime::<Utc>::try_from(&value).unwrap(), dt);
+        let tokyo = Value::Datetime(Cow::Borrowed("2024-03-15T09:00:00.5+09:00"));
+        assert_eq!(DateTime::<Utc>::try_from(&tokyo).unwrap(), dt);
+        assert_eq!(utc_to_timestamp(dt), 1_710_460_800_500_000);
+    }
+}
@@ -0,0 +1,585 @@
+//! DECIMAL parsing, formatting, and conversions.
+//!
+//! DECIMAL values must be normalized on the wire (spec Section 2.4): no
+//! trailing zeros in the mantissa, zero as `{0, 0}`, and mantissas that fit
+//! in `i64` stored as [`DecimalMantissa::I64`]. [`parse_decimal`] and the
+//! `rust_decimal`/`bigdecimal` conversions (behind the features of the same
+//! names) produce normalized values, so callers never build mantissas by
+//! hand.
+//!
+//! ```ignore
+//! let price = Value::decimal_from_str("12.340")?; // {exponent: -2, mantissa: 1234}
+//! assert_eq!(price.decimal_to_string().unwrap(), "12.34");
+//! ```
+//!
+//! Mantissas from elsewhere (e.g. a big-integer library's two's complement
+//! bytes) can be brought into normal form with [`DecimalMantissa::normalize`]
+//! or [`Value::normalize_decimal`] instead of being rejected by the encoder
+//! with `DecimalNotNormalized`.
+//!
+//! Big mantissas are handled as big-endian magnitudes with schoolbook
+//! arithmetic, which is quadratic in the digit count; inputs are capped at
+//! [`MAX_DECIMAL_DIGITS`].
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::DecimalError;
+use crate::limits::MAX_DECIMAL_DIGITS;
+use crate::model::{DecimalMantissa, Value};
+
+/// Parses a decimal string into a normalized `(exponent, mantissa)` pair.
+///
+/// Accepts an optional sign, digits with an optional decimal point, and an
+/// optional exponent: `12.34`, `-0.5`, `.5`, `1e6`, `1.5E-3`. Trailing zeros
+/// are folded into the exponent, so `"12.30"` and `"12.3"` parse the same.
+pub fn parse_decimal(s: &str) -> Result<(i32, DecimalMantissa<'static>), DecimalError> {
+    let bytes = s.as_bytes();
+    let mut pos = 0;
+    let negative = match bytes.first() {
+        Some(b'-') => {
+            pos += 1;
+            true
+        }
+        Some(b'+') => {
+            pos += 1;
+            false
+        }
+        _ => false,
+    };
+
+    // Significant digits, without leading zeros
+    let mut digits = Vec::new();
+    let mut fraction_digits: i64 = 0;
+    let mut seen_digit = false;
+    let mut seen_point = false;
+    while let Some(&c) = bytes.get(pos) {
+        match c {
+            b'0'..=b'9' => {
+                seen_digit = true;
+                if seen_point {
+                    fraction_digits += 1;
+                }
+                if c != b'0' || !digits.is_empty() {
+                    digits.push(c - b'0');
+                }
+            }
+            b'.' if !seen_point => seen_point = true,
+            b'e' | b'E' => break,
+            _ => return Err(syntax(pos, "unexpected character")),
+        }
+        pos += 1;
+    }
+    if !seen_digit {
+        return Err(syntax(pos, "expected a digit"));
+    }
+    if digits.len() > MAX_DECIMAL_DIGITS {
+        return Err(DecimalError::TooManyDigits {
+            digits: digits.len(),
+            max: MAX_DECIMAL_DIGITS,
+        });
+    }
+
+    let mut exponent: i64 = 0;
+    if pos < bytes.len() {
+        pos += 1;
+        let exp_negative = match bytes.get(pos) {
+            Some(b'-') => {
+                pos += 1;
+                true
+            }
+            Some(b'+') => {
+                pos += 1;
+                false
+            }
+            _ => false,
+        };
+        if pos == bytes.len() {
+            return Err(syntax(pos, "expected exponent digits"));
+        }
+        for (i, &c) in bytes[pos..].iter().enumerate() {
+            if !c.is_ascii_digit() {
+                return Err(syntax(pos + i, "unexpected character in exponent"));
+            }
+            exponent = exponent
+                .checked_mul(10)
+                .and_then(|e| e.checked_add((c - b'0') as i64))
+                .ok_or(DecimalError::ExponentOutOfRange)?;
+        }
+        if exp_negative {
+            exponent = -exponent;
+        }
+    }
+
+    while digits.last() == Some(&0) {
+        digits.pop();
+        exponent += 1;
+    }
+    if digits.is_empty() {
+        return Ok((0, DecimalMantissa::I64(0)));
+    }
+    let exponent = i32::try_from(exponent - fraction_digits)
+        .map_err(|_| DecimalError::ExponentOutOfRange)?;
+
+    let mut magnitude = Vec::new();
+    for digit in digits {
+        mul_add(&mut magnitude, 10, digit);
+    }
+    Ok((exponent, mantissa_from_magnitude(negative, magnitude)))
+}
+
+fn syntax(position: usize, reason: &'static str) -> DecimalError {
+    DecimalError::Syntax { position, reason }
+}
+
+/// Formats a decimal as a string that [`parse_decimal`] reads back.
+///
+/// Uses plain notation (`1234.5`, `0.001`, `1200`) unless that would need
+/// more than 21 integer digits or 5 leading fractional zeros, in which case
+/// it switches to scientific notation (`1.5e30`, `2e-9`).
+pub fn format_decimal(exponent: i32, mantissa: &DecimalMantissa<'_>) -> String {
+    let (negative, magnitude) = match mantissa {
+        DecimalMantissa::I64(v) => (*v < 0, v.unsigned_abs().to_be_bytes().to_vec()),
+        DecimalMantissa::Big(bytes) => magnitude_from_twos_complement(bytes),
+    };
+    let digits = decimal_digits(magnitude);
+    let mut out = String::with_capacity(digits.len() + 8);
+    if negative {
+        out.push('-');
+    }
+
+    let n = digits.len() as i64;
+    let exponent = exponent as i64;
+    let adjusted = n - 1 + exponent;
+    if digits == "0" {
+        out.push('0');
+    } else if exponent >= 0 && adjusted < 21 {
+        out.push_str(&digits);
+        out.extend(core::iter::repeat_n('0', exponent as usize));
+    } else if exponent < 0 && adjusted >= -6 {
+        let point = n + exponent;
+        if point > 0 {
+            out.push_str(&digits[..point as usize]);
+            out.push('.');
+            out.push_str(&digits[point as usize..]);
+        } else {
+            out.push_str("0.");
+            out.extend(core::iter::repeat_n('0', (-point) as usize));
+            out.push_str(&digits);
+        }
+    } else {
+        out.push_str(&digits[..1]);
+        if n > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push_str(&adjusted.to_string());
+    }
+    out
+}
+
+impl Value<'static> {
+    /// Parses a decimal string into a normalized DECIMAL value with no
+    /// unit (see [`parse_decimal`]).
+    pub fn decimal_from_str(s: &str) -> Result<Self, DecimalError> {
+        let (exponent, mantissa) = parse_decimal(s)?;
+        Ok(Value::Decimal { exponent, mantissa, unit: None })
+    }
+}
+
+impl Value<'_> {
+    /// Formats a DECIMAL value with [`format_decimal`], or returns None for
+    /// other types.
+    pub fn decimal_to_string(&self) -> Option<String> {
+        match self {
+            Value::Decimal { exponent, mantissa, .. } => Some(format_decimal(*exponent, mantissa)),
+            _ => None,
+        }
+    }
+}
+
+impl Value<'_> {
+    /// Normalizes a DECIMAL value in place (see [`DecimalMantissa::normalized`]).
+    /// Other values are left as they are.
+    pub fn normalize_decimal(&mut self) {
+        if let Value::Decimal { exponent, mantissa, .. } = self {
+            let (e, m) = mantissa.normalized(*exponent);
+            *exponent = e;
+            *mantissa = m;
+        }
+    }
+}
+
+impl DecimalMantissa<'_> {
+    /// Normalizes a decimal whose mantissa is given as big-endian two's
+    /// complement bytes of any length.
+    ///
+    /// Factors of ten are moved from the mantissa into the exponent, and
+    /// the result uses `I64` when it fits, or else minimal-length bytes.
+    /// Zero (including an empty byte string) becomes `(0, I64(0))`.
+    ///
+    /// Stripping stops if the exponent reaches `i32::MAX`; such a value is
+    /// not representable in normal form and still fails to encode.
+    pub fn normalize(exponent: i32, bytes: &[u8]) -> (i32, DecimalMantissa<'static>) {
+        let (negative, magnitude) = magnitude_from_twos_complement(bytes);
+        normalize_magnitude(exponent, negative, magnitude)
+    }
+
+    /// Returns this mantissa and `exponent` in normal form (see
+    /// [`normalize`](Self::normalize)).
+    pub fn normalized(&self, exponent: i32) -> (i32, DecimalMantissa<'static>) {
+        match self {
+            DecimalMantissa::I64(v) => {
+                normalize_magnitude(exponent, *v < 0, v.unsigned_abs().to_be_bytes().to_vec())
+            }
+            DecimalMantissa::Big(bytes) => Self::normalize(exponent, bytes),
+        }
+    }
+}
+
+fn normalize_magnitude(
+    mut exponent: i32,
+    negative: bool,
+    mut magnitude: Vec<u8>,
+) -> (i32, DecimalMantissa<'static>) {
+    let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+    magnitude.drain(..zeros);
+    if magnitude.is_empty() {
+        return (0, DecimalMantissa::I64(0));
+    }
+    while exponent < i32::MAX {
+        let mut quotient = magnitude.clone();
+        if div_rem(&mut quotient, 10) != 0 {
+            break;
+        }
+        magnitude = quotient;
+        exponent += 1;
+    }
+    (exponent, mantissa_from_magnitude(negative, magnitude))
+}
+
+// =============================================================================
+// MAGNITUDE ARITHMETIC
+// =============================================================================
+//
+// Magnitudes are unsigned big-endian byte strings without leading zeros;
+// zero is the empty string.
+
+/// Computes `magnitude * mul + add` in place.
+pub(crate) fn mul_add(magnitude: &mut Vec<u8>, mul: u8, add: u8) {
+    let mut carry = add as u32;
+    for byte in magnitude.iter_mut().rev() {
+        let v = *byte as u32 * mul as u32 + carry;
+        *byte = v as u8;
+        carry = v >> 8;
+    }
+    if carry > 0 {
+        magnitude.insert(0, carry as u8);
+    }
+}
+
+/// Divides in place by `div`, returning the remainder.
+pub(crate) fn div_rem(magnitude: &mut Vec<u8>, div: u8) -> u8 {
+    let mut rem = 0u32;
+    for byte in magnitude.iter_mut() {
+        let v = (rem << 8) | *byte as u32;
+        *byte = (v / div as u32) as u8;
+        rem = v % div as u32;
+    }
+    let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+    magnitude.drain(..zeros);
+    rem as u8
+}
+
+/// Renders a magnitude as decimal digits.
+fn decimal_digits(mut magnitude: Vec<u8>) -> String {
+    let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+    magnitude.drain(..zeros);
+    if magnitude.is_empty() {
+        return String::from("0");
+    }
+    let mut digits = Vec::new();
+    while !magnitude.is_empty() {
+        digits.push(b'0' + div_rem(&mut magnitude, 10));
+    }
+    digits.reverse();
+    // Only ASCII digits were pushed
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+/// Splits a two's complement mantissa into sign and magnitude.
+pub(crate) fn magnitude_from_twos_complement(bytes: &[u8]) -> (bool, Vec<u8>) {
+    let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+    let mut magnitude = bytes.to_vec();
+    if negative {
+        // Invert and add one
+        for byte in magnitude.iter_mut() {
+            *byte = !*byte;
+        }
+        for byte in magnitude.iter_mut().rev() {
+            let (v, overflow) = byte.overflowing_add(1);
+            *byte = v;
+            if !overflow {
+                break;
+            }
+        }
+    }
+    let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+    magnitude.drain(..zeros);
+    (negative, magnitude)
+}
+
+/// Encodes a sign and magnitude as minimal big-endian two's complement.
+pub(crate) fn twos_complement_from_magnitude(negative: bool, magnitude: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(magnitude.len() + 1);
+    bytes.push(0);
+    bytes.extend_from_slice(magnitude);
+    if negative {
+        for byte in bytes.iter_mut() {
+            *byte = !*byte;
+        }
+        for byte in bytes.iter_mut().rev() {
+            let (v, overflow) = byte.overflowing_add(1);
+            *byte = v;
+            if !overflow {
+                break;
+            }
+        }
+    }
+    // Drop redundant sign-extension bytes
+    let mut start = 0;
+    while start + 1 < bytes.len() {
+        let (first, second) = (bytes[start], bytes[start + 1]);
+        if (first == 0x00 && second & 0x80 == 0) || (first == 0xFF && second & 0x80 != 0) {
+            start += 1;
+        } else {
+            break;
+        }
+    }
+    bytes.drain(..start);
+    bytes
+}
+
+/// Builds a mantissa from a sign and magnitude, using `I64` when it fits.
+pub(crate) fn mantissa_from_magnitude(
+    negative: bool,
+    magnitude: Vec<u8>,
+) -> DecimalMantissa<'static> {
+    let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+    let significant = &magnitude[zeros..];
+    if significant.len() <= 8 {
+        let mut buf = [0u8; 8];
+        buf[8 - significant.len()..].copy_from_slice(significant);
+        let v = u64::from_be_bytes(buf) as i128;
+        let v = if negative { -v } else { v };
+        if let Ok(v) = i64::try_from(v) {
+            return DecimalMantissa::I64(v);
+        }
+    }
+    DecimalMantissa::Big(Cow::Owned(twos_complement_from_magnitude(negative, significant)))
+}
+
+// =============================================================================
+// INTEROP
+// =============================================================================
+
+#[cfg(feature = "rust_decimal")]
+impl From<rust_decimal::Decimal> for Value<'static> {
+    fn from(decimal: rust_decimal::Decimal) -> Self {
+        let mut mantissa = decimal.mantissa();
+        let mut exponent = -(decimal.scale() as i32);
+        if mantissa == 0 {
+            exponent = 0;
+        }
+        while mantissa != 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            exponent += 1;
+        }
+        let magnitude = mantissa.unsigned_abs().to_be_bytes().to_vec();
+        Value::Decimal {
+            exponent,
+            mantissa: mantissa_from_magnitude(mantissa < 0, magnitude),
+            unit: None,
+        }
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl TryFrom<&Value<'_>> for rust_decimal::Decimal {
+    type Error = DecimalError;
+
+    /// Converts a DECIMAL value, failing if it needs more than 96 bits of
+    /// mantissa or 28 fractional digits.
+    fn try_from(value: &Value<'_>) -> Result<Self, DecimalError> {
+        let Value::Decimal { exponent, mantissa, .. } = value else {
+            return Err(DecimalError::NotDecimal);
+        };
+        let mut mantissa: i128 = match mantissa {
+            DecimalMantissa::I64(v) => *v as i128,
+            DecimalMantissa::Big(bytes) => {
+                let (negative, magnitude) = magnitude_from_twos_complement(bytes);
+                if magnitude.len() > 16 {
+                    return Err(DecimalError::OutOfRange);
+                }
+                let mut buf = [0u8; 16];
+                buf[16 - magnitude.len()..].copy_from_slice(&magnitude);
+                let v = i128::try_from(u128::from_be_bytes(buf))
+                    .map_err(|_| DecimalError::OutOfRange)?;
+                if negative { -v } else { v }
+            }
+        };
+        let scale = if *exponent > 0 {
+            for _ in 0..*exponent {
+                mantissa = mantissa.checked_mul(10).ok_or(DecimalError::OutOfRange)?;
+            }
+            0
+        } else {
+            exponent.unsigned_abs()
+        };
+        rust_decimal::Decimal::try_from_i128_with_scale(mantissa, scale)
+            .map_err(|_| DecimalError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl TryFrom<&bigdecimal::BigDecimal> for Value<'static> {
+    type Error = DecimalError;
+
+    /// Converts and normalizes a `BigDecimal`, failing if its exponent does
+    /// not fit in `i32`.
+    fn try_from(decimal: &bigdecimal::BigDecimal) -> Result<Self, DecimalError> {
+        let (int, scale) = decimal.as_bigint_and_exponent();
+        let (negative, mut magnitude) = magnitude_from_twos_complement(&int.to_signed_bytes_be());
+        if magnitude.is_empty() {
+            let mantissa = DecimalMantissa::I64(0);
+            return Ok(Value::Decimal { exponent: 0, mantissa, unit: None });
+        }
+        let mut exponent = -scale;
+        loop {
+            let mut quotient = magnitude.clone();
+            if div_rem(&mut quotient, 10) != 0 {
+                break;
+            }
+            magnitude = quotient;
+            exponent += 1;
+        }
+        Ok(Value::Decimal {
+            exponent: i32::try_from(exponent).map_err(|_| DecimalError::ExponentOutOfRange)?,
+            mantissa: mantissa_from_magnitude(negative, magnitude),
+            unit: None,
+        })
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl TryFrom<&Value<'_>> for bigdecimal::BigDecimal {
+    type Error = DecimalError;
+
+    fn try_from(value: &Value<'_>) -> Result<Self, DecimalError> {
+        let Value::Decimal { exponent, mantissa, .. } = value else {
+            return Err(DecimalError::NotDecimal);
+        };
+
+        use bigdecimal::num_bigint::BigInt;
+
+        let int = match mantissa {
+            DecimalMantissa::I64(v) => BigInt::from(*v),
+            DecimalMantissa::Big(bytes) => BigInt::from_signed_bytes_be(bytes),
+        };
+        Ok(bigdecimal::BigDecimal::new(int, -(*exponent as i64)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> (i32, DecimalMantissa<'static>) {
+        parse_decimal(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        assert_eq!(parse("12.34"), (-2, DecimalMantissa::I64(1234)));
+        assert_eq!(parse("12.340"), (-2, DecimalMantissa::I64(1234)));
+        assert_eq!(parse("-0.000001"), (-6, DecimalMantissa::I64(-1)));
+        assert_eq!(parse("1200"), (2, DecimalMantissa::I64(12)));
+        assert_eq!(parse("+.5e3"), (2, DecimalMantissa::I64(5)));
+        assert_eq!(parse("-0.00"), (0, DecimalMantissa::I64(0)));
+        assert_eq!(parse("0e99999999999"), (0, DecimalMantissa::I64(0)));
+        assert_eq!(parse("-9223372036854775808"), (0, DecimalMantissa::I64(i64::MIN)));
+
+        // Beyond i64: minimal two's complement
+        let big = |bytes: &[u8]| DecimalMantissa::Big(Cow::Owned(bytes.to_vec()));
+        assert_eq!(parse("9223372036854775808").1, big(&[0x00, 0x80, 0, 0, 0, 0, 0, 0, 0]));
+        let mut min_minus_one = [0xFF; 9];
+        min_minus_one[1] = 0x7F;
+        assert_eq!(parse("-9223372036854775809").1, big(&min_minus_one));
+
+        for bad in ["", "-", ".", "1.2.3", "1e", "1e+", "12a", "1e5.0"] {
+            assert!(matches!(parse_decimal(bad), Err(DecimalError::Syntax { .. })), "{bad}");
+        }
+        assert_eq!(parse_decimal("1e3000000000"), Err(DecimalError::ExponentOutOfRange));
+    }
+
+    #[test]
+    fn test_format_roundtrip() {
+        let cases = [
+            ("12.34", "12.34"),
+            ("-0.001", "-0.001"),
+            ("1200", "1200"),
+            ("0", "0"),
+            ("1.5e30", "1.5e30"),
+            ("2e-9", "2e-9"),
+            ("0.000001", "0.000001"),
+            ("0.0000001", "1e-7"),
+            ("123456789012345678901234567890.5", "123456789012345678901234567890.5"),
+            ("-98765432109876543210e-40", "-9.876543210987654321e-21"),
+        ];
+        for (input, expected) in cases {
+            let value = Value::decimal_from_str(input).unwrap();
+            let formatted = value.decimal_to_string().unwrap();
+            assert_eq!(formatted, expected);
+            assert_eq!(Value::decimal_from_str(&formatted).unwrap(), value);
+        }
+        assert_eq!(Value::Bool(true).decimal_to_string(), None);
+    }
+
+    #[test]
+    fn test_normalize_big_mantissa() {
+        let big = |bytes: &[u8]| DecimalMantissa::Big(Cow::Owned(bytes.to_vec()));
+        // 10^19 with a redundant sign byte
+        let ten_pow_19 = [0x00, 0x00, 0x8A, 0xC7, 0x23, 0x04, 0x89, 0xE8, 0x00, 0x00];
+        assert_eq!(DecimalMantissa::normalize(-3, &ten_pow_19), (16, DecimalMantissa::I64(1)));
+        assert!(big(&ten_pow_19).has_trailing_zeros());
+
+        // -(10 * 2^64) with redundant sign bytes: stays big, loses the ten
+        let padded = [0xFF, 0xFF, 0xF6, 0, 0, 0, 0, 0, 0, 0, 0];
+        let minus_two_pow_64 = big(&[0xFF, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(DecimalMantissa::normalize(0, &padded), (1, minus_two_pow_64.clone()));
+        assert!(big(&padded[1..]).has_trailing_zeros());
+        assert!(!minus_two_pow_64.has_trailing_zeros());
+
+        assert_eq!(DecimalMantissa::normalize(7, &[]), (0, DecimalMantissa::I64(0)));
+        assert_eq!(DecimalMantissa::normalize(0, &[0xFF, 0xFF]), (0, DecimalMantissa::I64(-1)));
+
+        let mantissa = DecimalMantissa::I64(1500);
+        let mut value = Value::Decimal { exponent: -2, mantissa, unit: None };
+        value.normalize_decimal();
+        assert_eq!(value, Value::decimal_from_str("15").unwrap());
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal_conversion() {
+        let decimal = rust_decimal::Decimal::try_from_i128_with_scale(12340, 3).unwrap();
+        let value = Value::from(decimal);
+        assert_eq!(value, Value::decimal_from_str("12.34").unwrap());
+        assert_eq!(rust_decimal::Decimal::try_from(&value).unwrap(), decimal.normalize());
+
+        let huge = Value::decimal_from_str("1e40").unwrap();
+        assert_eq!(rust_decimal::Decimal::try_from(&huge), Err(DecimalError::OutOfRange));
+    }
+}
@@ -2,17 +2,22 @@
 //!
 //! Edits are standalone patches containing a batch of ops with metadata.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
 
-use rustc_hash::FxHashMap;
-
-use crate::codec::primitives::Writer;
+use crate::codec::primitives::{varint_len, Writer};
+use crate::error::DecodeError;
+use crate::hash::{FxHashMap, FxHashSet};
 use crate::model::{DataType, Id, Op};
+use crate::validate::ValidationProfile;
 
 /// A batch of operations with metadata (spec Section 4.1).
 ///
-/// Edits are standalone patches. They contain no parent references;
-/// ordering is provided by on-chain governance.
+/// Edits are standalone patches; ordering is provided by on-chain
+/// governance. Parent edits listed in [`Provenance`] record lineage only
+/// and do not affect ordering.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Edit<'a> {
     /// The edit's unique identifier.
@@ -23,10 +28,52 @@ pub struct Edit<'a> {
     pub authors: Vec<Id>,
     /// Creation timestamp (metadata only, not used for conflict resolution).
     pub created_at: i64,
+    /// Optional lineage metadata (format version 2).
+    pub provenance: Option<Provenance<'a>>,
     /// Operations in this edit.
     pub ops: Vec<Op<'a>>,
 }
 
+/// Lineage metadata carried in the edit header (spec Section 4.1).
+///
+/// Edits with provenance are encoded as format version 2; edits without it
+/// stay version 1 so older decoders can still read them. Empty provenance
+/// is encoded as none and decodes as `None`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Provenance<'a> {
+    /// The application that produced the edit (e.g. an importer's ID).
+    pub source: Option<Id>,
+    /// Edits this edit was derived from.
+    pub parents: Vec<Id>,
+    /// Free-form key/value metadata. Keys are unique in canonical mode.
+    pub metadata: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl Provenance<'_> {
+    /// Returns true if no provenance fields are set.
+    pub fn is_empty(&self) -> bool {
+        self.source.is_none() && self.parents.is_empty() && self.metadata.is_empty()
+    }
+
+    /// Gets the value of a metadata key (the first, if repeated).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_ref())
+    }
+
+    /// Converts to an owned provenance with `'static` lifetime.
+    pub fn into_owned(self) -> Provenance<'static> {
+        Provenance {
+            source: self.source,
+            parents: self.parents,
+            metadata: self
+                .metadata
+                .into_iter()
+                .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned())))
+                .collect(),
+        }
+    }
+}
+
 impl<'a> Edit<'a> {
     /// Creates a new empty edit with the given ID.
     pub fn new(id: Id) -> Self {
@@ -35,6 +82,7 @@ impl<'a> Edit<'a> {
             name: Cow::Borrowed(""),
             authors: Vec::new(),
             created_at: 0,
+            provenance: None,
             ops: Vec::new(),
         }
     }
@@ -46,9 +94,56 @@ impl<'a> Edit<'a> {
             name: name.into(),
             authors: Vec::new(),
             created_at: 0,
+            provenance: None,
             ops: Vec::new(),
         }
     }
+
+    /// Returns true if any property value in the edit carries a clock.
+    pub fn has_value_clocks(&self) -> bool {
+        self.ops.iter().any(|op| match op {
+            Op::CreateEntity(ce) => ce.values.iter().any(|pv| pv.clock.is_some()),
+            Op::UpdateEntity(ue) => ue.set_properties.iter().any(|pv| pv.clock.is_some()),
+            _ => false,
+        })
+    }
+
+    /// Returns true if any UpdateEntity op removes values by value ID.
+    pub fn has_remove_values(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, Op::UpdateEntity(ue) if !ue.remove_values.is_empty()))
+    }
+
+    /// Collects the dictionaries [`encode_edit`](crate::codec::encode_edit)
+    /// writes for this edit, in first-seen order.
+    ///
+    /// The builder's [`stats`](DictionaryBuilder::stats) count how often
+    /// each entry is referenced, which shows where index bytes go.
+    /// [`into_frequency_order`](DictionaryBuilder::into_frequency_order)
+    /// gives the index assignments `encode_edit` uses.
+    pub fn build_dictionaries(&self) -> DictionaryBuilder {
+        let mut builder = DictionaryBuilder::with_capacity(self.ops.len());
+        builder.set_value_clocks(self.has_value_clocks());
+        for op in &self.ops {
+            crate::codec::op::collect_op_ids(op, &mut builder);
+        }
+        builder
+    }
+
+    /// Returns true if both edits have the same canonical encoding.
+    ///
+    /// Unlike `==`, this ignores differences the wire format does not
+    /// preserve: author order, the order of values and unsets within an op,
+    /// and the order of `UpdateRelation` unset fields. If either edit cannot
+    /// be canonically encoded (e.g. it has duplicate authors or values), this
+    /// falls back to structural equality.
+    pub fn canonical_eq(&self, other: &Edit<'_>) -> bool {
+        use crate::codec::encode_edit_canonical;
+
+        match (encode_edit_canonical(self), encode_edit_canonical(other)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => self == other,
+        }
+    }
 }
 
 /// Wire-format dictionaries for encoding/decoding.
@@ -67,6 +162,18 @@ pub struct WireDictionaries {
     pub units: Vec<Id>,
     /// Object IDs (entities and relations).
     pub objects: Vec<Id>,
+    /// Whether property values carry a clock (format version 3).
+    pub value_clocks: bool,
+    /// Whether ops may be grouped into BatchCreateEntities records, as
+    /// announced by the edit header.
+    pub batch_records: bool,
+    /// Whether UpdateEntity ops may carry remove lists, as announced by the
+    /// edit header.
+    pub remove_values: bool,
+    /// Byte ranges of the string table entries, relative to the start of
+    /// the edit. None if the edit has no string table and TEXT values are
+    /// stored inline.
+    pub strings: Option<Vec<Range<usize>>>,
 }
 
 impl WireDictionaries {
@@ -122,6 +229,10 @@ impl WireDictionaries {
 pub struct DictionaryBuilder {
     properties: Vec<(Id, DataType)>,
     property_indices: FxHashMap<Id, usize>,
+    /// Properties whose type is a placeholder (see [`add_property_placeholder`]).
+    ///
+    /// [`add_property_placeholder`]: Self::add_property_placeholder
+    placeholder_properties: Vec<bool>,
     relation_types: Vec<Id>,
     relation_type_indices: FxHashMap<Id, usize>,
     languages: Vec<Id>,
@@ -130,6 +241,156 @@ pub struct DictionaryBuilder {
     unit_indices: FxHashMap<Id, usize>,
     objects: Vec<Id>,
     object_indices: FxHashMap<Id, usize>,
+    /// Number of leading entries in each dictionary that came from a seed.
+    seeded: SeedCounts,
+    /// Whether property values are written with a clock.
+    value_clocks: bool,
+    /// Which value checks are hard errors.
+    profile: ValidationProfile,
+    /// TEXT values stored once in the string table, in wire order.
+    strings: Vec<String>,
+    string_indices: FxHashMap<String, usize>,
+    /// How often each entry has been looked up, for [`stats`](Self::stats).
+    references: References,
+}
+
+/// Reference counts of each dictionary's entries, in index order.
+#[derive(Debug, Clone, Default)]
+struct References {
+    properties: RefCounts,
+    relation_types: RefCounts,
+    languages: RefCounts,
+    units: RefCounts,
+    objects: RefCounts,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RefCounts {
+    counts: Vec<u64>,
+    hits: u64,
+}
+
+impl RefCounts {
+    /// Counts a lookup that added entry `counts.len()`.
+    fn add(&mut self) {
+        self.counts.push(1);
+    }
+
+    /// Counts a lookup that found entry `idx`.
+    fn hit(&mut self, idx: usize) {
+        self.counts[idx] += 1;
+        self.hits += 1;
+    }
+
+    /// Counts for `len` entries that have not been looked up.
+    fn unused(len: usize) -> Self {
+        Self { counts: alloc::vec![0; len], hits: 0 }
+    }
+
+    /// Reorders the counts along with their entries, sorted by ID.
+    fn sorted_by(self, ids: &[Id]) -> Self {
+        let mut counts: Vec<(Id, u64)> = ids.iter().copied().zip(self.counts).collect();
+        counts.sort_by_key(|(id, _)| *id);
+        Self { counts: counts.into_iter().map(|(_, count)| count).collect(), hits: self.hits }
+    }
+}
+
+/// How often the entries of one dictionary are referenced, from
+/// [`DictionaryBuilder::stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DictionaryUsage {
+    /// Entry IDs, in index order.
+    pub ids: Vec<Id>,
+    /// References to each entry, in index order.
+    pub references: Vec<u64>,
+    /// References that found an existing entry rather than adding one.
+    pub hits: u64,
+    /// Wire index of the first entry (1 for languages and units, where 0
+    /// means none).
+    index_base: usize,
+}
+
+impl DictionaryUsage {
+    fn new(ids: Vec<Id>, refs: &RefCounts, index_base: usize) -> Self {
+        Self { ids, references: refs.counts.clone(), hits: refs.hits, index_base }
+    }
+
+    /// Returns the total number of references.
+    pub fn lookups(&self) -> u64 {
+        self.references.iter().sum()
+    }
+
+    /// Returns the bytes the references' index varints take.
+    pub fn index_bytes(&self) -> u64 {
+        self.index_bytes_in(self.references.iter().copied())
+    }
+
+    /// Returns the bytes the index varints would take if the most
+    /// referenced entries had the lowest indices.
+    pub fn packed_index_bytes(&self) -> u64 {
+        let mut references = self.references.clone();
+        references.sort_unstable_by(|a, b| b.cmp(a));
+        self.index_bytes_in(references.into_iter())
+    }
+
+    /// Returns the entries by descending reference count, ties in index
+    /// order: the order that packs indices best.
+    pub fn by_references(&self) -> Vec<(Id, u64)> {
+        let mut entries: Vec<(Id, u64)> =
+            self.ids.iter().copied().zip(self.references.iter().copied()).collect();
+        entries.sort_by_key(|(_, count)| core::cmp::Reverse(*count));
+        entries
+    }
+
+    fn index_bytes_in(&self, references: impl Iterator<Item = u64>) -> u64 {
+        references
+            .enumerate()
+            .map(|(i, count)| count * varint_len((i + self.index_base) as u64) as u64)
+            .sum()
+    }
+}
+
+/// Reference statistics of a [`DictionaryBuilder`], per dictionary.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DictionaryStats {
+    /// Properties dictionary.
+    pub properties: DictionaryUsage,
+    /// Relation types dictionary.
+    pub relation_types: DictionaryUsage,
+    /// Languages dictionary.
+    pub languages: DictionaryUsage,
+    /// Units dictionary.
+    pub units: DictionaryUsage,
+    /// Objects dictionary.
+    pub objects: DictionaryUsage,
+}
+
+impl DictionaryStats {
+    /// Returns the dictionaries in wire order.
+    pub fn all(&self) -> [&DictionaryUsage; 5] {
+        [&self.properties, &self.relation_types, &self.languages, &self.units, &self.objects]
+    }
+
+    /// Returns the bytes all index varints take.
+    pub fn index_bytes(&self) -> u64 {
+        self.all().iter().map(|usage| usage.index_bytes()).sum()
+    }
+
+    /// Returns the bytes all index varints would take with every
+    /// dictionary ordered by reference count.
+    pub fn packed_index_bytes(&self) -> u64 {
+        self.all().iter().map(|usage| usage.packed_index_bytes()).sum()
+    }
+}
+
+/// Entry counts of a seed dictionary, in wire order.
+#[derive(Debug, Clone, Copy, Default)]
+struct SeedCounts {
+    properties: usize,
+    relation_types: usize,
+    languages: usize,
+    units: usize,
+    objects: usize,
 }
 
 impl DictionaryBuilder {
@@ -156,6 +417,7 @@ impl DictionaryBuilder {
         Self {
             properties: Vec::with_capacity(prop_cap),
             property_indices: FxHashMap::with_capacity_and_hasher(prop_cap, Default::default()),
+            placeholder_properties: Vec::with_capacity(prop_cap),
             relation_types: Vec::with_capacity(rel_cap),
             relation_type_indices: FxHashMap::with_capacity_and_hasher(rel_cap, Default::default()),
             languages: Vec::with_capacity(lang_cap),
@@ -164,29 +426,109 @@ impl DictionaryBuilder {
             unit_indices: FxHashMap::with_capacity_and_hasher(unit_cap, Default::default()),
             objects: Vec::with_capacity(obj_cap),
             object_indices: FxHashMap::with_capacity_and_hasher(obj_cap, Default::default()),
+            seeded: SeedCounts::default(),
+            value_clocks: false,
+            profile: ValidationProfile::default(),
+            strings: Vec::new(),
+            string_indices: FxHashMap::default(),
+            references: References::default(),
+        }
+    }
+
+    /// Creates a builder pre-populated with shared dictionaries.
+    ///
+    /// Seeded entries keep their indices, and new entries are appended after
+    /// them. Use [`write_local_dictionaries`](Self::write_local_dictionaries)
+    /// to emit only the entries that are not part of the seed.
+    pub fn with_seed(dicts: &WireDictionaries) -> Self {
+        let mut builder = Self::default();
+        for (id, data_type) in &dicts.properties {
+            builder.add_property(*id, *data_type);
+        }
+        for id in &dicts.relation_types {
+            builder.add_relation_type(*id);
         }
+        for id in &dicts.languages {
+            builder.add_language(Some(*id));
+        }
+        for id in &dicts.units {
+            builder.add_unit(Some(*id));
+        }
+        for id in &dicts.objects {
+            builder.add_object(*id);
+        }
+        builder.seeded = SeedCounts {
+            properties: builder.properties.len(),
+            relation_types: builder.relation_types.len(),
+            languages: builder.languages.len(),
+            units: builder.units.len(),
+            objects: builder.objects.len(),
+        };
+        // Seeding is not a reference
+        builder.references = References {
+            properties: RefCounts::unused(builder.properties.len()),
+            relation_types: RefCounts::unused(builder.relation_types.len()),
+            languages: RefCounts::unused(builder.languages.len()),
+            units: RefCounts::unused(builder.units.len()),
+            objects: RefCounts::unused(builder.objects.len()),
+        };
+        builder
     }
 
     /// Adds or gets the index for a property.
+    ///
+    /// If the property was registered with a placeholder type, `data_type`
+    /// replaces it. Otherwise the first registered type is kept; use
+    /// [`property_type`](Self::property_type) to detect conflicts.
     pub fn add_property(&mut self, id: Id, data_type: DataType) -> usize {
         if let Some(&idx) = self.property_indices.get(&id) {
+            if self.placeholder_properties[idx] {
+                self.properties[idx].1 = data_type;
+                self.placeholder_properties[idx] = false;
+            }
+            self.references.properties.hit(idx);
             idx
         } else {
             let idx = self.properties.len();
             self.properties.push((id, data_type));
+            self.placeholder_properties.push(false);
             self.property_indices.insert(id, idx);
+            self.references.properties.add();
+            idx
+        }
+    }
+
+    /// Adds or gets the index for a property whose type is not known from
+    /// the referencing op (an unset, or a value ref without a language).
+    ///
+    /// The property is recorded as BOOL until a value of the property is
+    /// added, at which point that value's type is used instead.
+    pub fn add_property_placeholder(&mut self, id: Id) -> usize {
+        if let Some(&idx) = self.property_indices.get(&id) {
+            self.references.properties.hit(idx);
+            idx
+        } else {
+            let idx = self.add_property(id, DataType::Bool);
+            self.placeholder_properties[idx] = true;
             idx
         }
     }
 
+    /// Returns the type currently registered for the property at `index`.
+    pub fn property_type(&self, index: usize) -> Option<DataType> {
+        self.properties.get(index).map(|(_, data_type)| *data_type)
+    }
+
     /// Adds or gets the index for a relation type.
     pub fn add_relation_type(&mut self, id: Id) -> usize {
         if let Some(&idx) = self.relation_type_indices.get(&id) {
+            self.references.relation_types.hit(idx);
             idx
         } else {
             let idx = self.relation_types.len();
             self.relation_types.push(id);
             self.relation_type_indices.insert(id, idx);
+            self.references.relation_types.add();
             idx
         }
     }
@@ -199,11 +541,13 @@ impl DictionaryBuilder {
             None => 0,
             Some(lang_id) => {
                 if let Some(&idx) = self.language_indices.get(&lang_id) {
+                    self.references.languages.hit(idx);
                     idx + 1
                 } else {
                     let idx = self.languages.len();
                     self.languages.push(lang_id);
                     self.language_indices.insert(lang_id, idx);
+                    self.references.languages.add();
                     idx + 1
                 }
             }
@@ -218,11 +562,13 @@ impl DictionaryBuilder {
             None => 0,
             Some(unit_id) => {
                 if let Some(&idx) = self.unit_indices.get(&unit_id) {
+                    self.references.units.hit(idx);
                     idx + 1
                 } else {
                     let idx = self.units.len();
                     self.units.push(unit_id);
                     self.unit_indices.insert(unit_id, idx);
+                    self.references.units.add();
                     idx + 1
                 }
             }
@@ -232,16 +578,20 @@ impl DictionaryBuilder {
     /// Adds or gets the index for an object.
     pub fn add_object(&mut self, id: Id) -> usize {
         if let Some(&idx) = self.object_indices.get(&id) {
+            self.references.objects.hit(idx);
             idx
         } else {
             let idx = self.objects.len();
             self.objects.push(id);
             self.object_indices.insert(id, idx);
+            self.references.objects.add();
             idx
         }
     }
 
     /// Builds the final wire dictionaries (consumes the builder).
+    ///
+    /// The dictionaries accept every op record the encoders write.
     pub fn build(self) -> WireDictionaries {
         WireDictionaries {
             properties: self.properties,
@@ -249,6 +599,10 @@ impl DictionaryBuilder {
             languages: self.languages,
             units: self.units,
             objects: self.objects,
+            value_clocks: self.value_clocks,
+            batch_records: true,
+            remove_values: true,
+            strings: None,
         }
     }
 
@@ -261,9 +615,75 @@ impl DictionaryBuilder {
             languages: self.languages.clone(),
             units: self.units.clone(),
             objects: self.objects.clone(),
+            value_clocks: self.value_clocks,
+            batch_records: true,
+            remove_values: true,
+            strings: None,
         }
     }
 
+    /// Returns how often each entry has been referenced, and the index
+    /// assignments.
+    ///
+    /// Every `add_*` call counts as a reference, except those of
+    /// [`with_seed`](Self::with_seed) and lookups of no language or unit.
+    pub fn stats(&self) -> DictionaryStats {
+        let references = &self.references;
+        DictionaryStats {
+            properties: DictionaryUsage::new(
+                self.properties.iter().map(|(id, _)| *id).collect(),
+                &references.properties,
+                0,
+            ),
+            relation_types: DictionaryUsage::new(
+                self.relation_types.clone(),
+                &references.relation_types,
+                0,
+            ),
+            languages: DictionaryUsage::new(self.languages.clone(), &references.languages, 1),
+            units: DictionaryUsage::new(self.units.clone(), &references.units, 1),
+            objects: DictionaryUsage::new(self.objects.clone(), &references.objects, 0),
+        }
+    }
+
+    /// Sets whether property values are written with a clock. Encoders
+    /// turn this on for edits written as format version 3.
+    pub fn set_value_clocks(&mut self, value_clocks: bool) {
+        self.value_clocks = value_clocks;
+    }
+
+    /// Returns true if property values are written with a clock.
+    pub fn value_clocks(&self) -> bool {
+        self.value_clocks
+    }
+
+    /// Sets which value checks are hard errors when encoding.
+    pub fn set_profile(&mut self, profile: ValidationProfile) {
+        self.profile = profile;
+    }
+
+    /// Returns the validation profile values are encoded under.
+    pub fn profile(&self) -> ValidationProfile {
+        self.profile
+    }
+
+    /// Sets the string table. TEXT values equal to an entry are written as
+    /// a reference to it; encoders must set the header flag for the table.
+    pub fn set_strings(&mut self, strings: Vec<String>) {
+        self.string_indices = strings.iter().cloned().zip(0..).collect();
+        self.strings = strings;
+    }
+
+    /// Returns true if the builder has a string table.
+    pub fn has_strings(&self) -> bool {
+        !self.strings.is_empty()
+    }
+
+    /// Gets the index of a string table entry (for encoding).
+    pub fn get_string_index(&self, value: &str) -> Option<usize> {
+        self.string_indices.get(value).copied()
+    }
+
     /// Gets the index for an existing property (for encoding).
     pub fn get_property_index(&self, id: &Id) -> Option<usize> {
         self.property_indices.get(id).copied()
@@ -290,6 +710,16 @@ impl DictionaryBuilder {
 
     /// Writes the dictionaries directly to a writer (avoids cloning).
     pub fn write_dictionaries(&self, writer: &mut Writer) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            properties = self.properties.len(),
+            relation_types = self.relation_types.len(),
+            languages = self.languages.len(),
+            units = self.units.len(),
+            objects = self.objects.len(),
+            strings = self.strings.len(),
+            "encoded dictionaries"
+        );
         // Properties: count + (id, data_type) pairs
         writer.write_varint(self.properties.len() as u64);
         for (id, data_type) in &self.properties {
@@ -308,18 +738,120 @@ impl DictionaryBuilder {
 
         // Objects
         writer.write_id_vec(&self.objects);
+
+        // String table (only announced by the header flag)
+        if !self.strings.is_empty() {
+            writer.write_varint(self.strings.len() as u64);
+            for value in &self.strings {
+                writer.write_string(value);
+            }
+        }
+    }
+
+    /// Writes only the entries added after the seed (see [`with_seed`](Self::with_seed)).
+    ///
+    /// Without a seed this is the same as [`write_dictionaries`](Self::write_dictionaries).
+    pub fn write_local_dictionaries(&self, writer: &mut Writer) {
+        let properties = &self.properties[self.seeded.properties..];
+        writer.write_varint(properties.len() as u64);
+        for (id, data_type) in properties {
+            writer.write_id(id);
+            writer.write_byte(*data_type as u8);
+        }
+
+        writer.write_id_vec(&self.relation_types[self.seeded.relation_types..]);
+        writer.write_id_vec(&self.languages[self.seeded.languages..]);
+        writer.write_id_vec(&self.units[self.seeded.units..]);
+        writer.write_id_vec(&self.objects[self.seeded.objects..]);
+    }
+
+    /// Converts this builder into one whose entries are ordered by
+    /// reference count, most referenced first, so that they get the
+    /// shortest indices. Ties keep their order.
+    ///
+    /// Like [`into_sorted`](Self::into_sorted), this discards any seed.
+    pub fn into_frequency_order(self) -> Self {
+        /// Returns entry positions by descending reference count.
+        fn order(refs: &RefCounts) -> Vec<usize> {
+            let mut order: Vec<usize> = (0..refs.counts.len()).collect();
+            order.sort_by_key(|&i| core::cmp::Reverse(refs.counts[i]));
+            order
+        }
+        fn permute<T: Copy>(items: &[T], order: &[usize]) -> Vec<T> {
+            order.iter().map(|&i| items[i]).collect()
+        }
+        fn reorder(ids: &[Id], refs: &RefCounts) -> (Vec<Id>, FxHashMap<Id, usize>, RefCounts) {
+            let order = order(refs);
+            let ids = permute(ids, &order);
+            let indices = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+            (ids, indices, RefCounts { counts: permute(&refs.counts, &order), hits: refs.hits })
+        }
+
+        let refs = &self.references;
+        let property_order = order(&refs.properties);
+        let properties = permute(&self.properties, &property_order);
+        let property_indices = properties.iter().enumerate().map(|(i, (id, _))| (*id, i)).collect();
+        let property_refs = RefCounts {
+            counts: permute(&refs.properties.counts, &property_order),
+            hits: refs.properties.hits,
+        };
+        let (relation_types, relation_type_indices, relation_type_refs) =
+            reorder(&self.relation_types, &refs.relation_types);
+        let (languages, language_indices, language_refs) = reorder(&self.languages, &refs.languages);
+        let (units, unit_indices, unit_refs) = reorder(&self.units, &refs.units);
+        let (objects, object_indices, object_refs) = reorder(&self.objects, &refs.objects);
+
+        Self {
+            placeholder_properties: permute(&self.placeholder_properties, &property_order),
+            properties,
+            property_indices,
+            relation_types,
+            relation_type_indices,
+            languages,
+            language_indices,
+            units,
+            unit_indices,
+            objects,
+            object_indices,
+            seeded: SeedCounts::default(),
+            value_clocks: self.value_clocks,
+            profile: self.profile,
+            strings: self.strings,
+            string_indices: self.string_indices,
+            references: References {
+                properties: property_refs,
+                relation_types: relation_type_refs,
+                languages: language_refs,
+                units: unit_refs,
+                objects: object_refs,
+            },
+        }
     }
 
     /// Converts this builder into a sorted canonical form.
     ///
     /// All dictionaries are sorted by ID bytes (lexicographic order),
     /// and the index maps are rebuilt to reflect the new ordering.
+    /// Sorting discards any seed, since seeded indices would no longer hold.
     ///
     /// This is used for canonical encoding to ensure deterministic output.
     pub fn into_sorted(self) -> Self {
+        let references = References {
+            properties: self.references.properties.sorted_by(
+                &self.properties.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            ),
+            relation_types: self.references.relation_types.sorted_by(&self.relation_types),
+            languages: self.references.languages.sorted_by(&self.languages),
+            units: self.references.units.sorted_by(&self.units),
+            objects: self.references.objects.sorted_by(&self.objects),
+        };
+
         // Sort properties by ID
-        let mut properties = self.properties;
-        properties.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut properties: Vec<_> =
+            self.properties.into_iter().zip(self.placeholder_properties).collect();
+        properties.sort_by_key(|a| a.0 .0);
+        let (properties, placeholder_properties): (Vec<_>, Vec<_>) =
+            properties.into_iter().unzip();
         let property_indices: FxHashMap<Id, usize> = properties
             .iter()
             .enumerate()
@@ -365,6 +897,7 @@ impl DictionaryBuilder {
         Self {
             properties,
             property_indices,
+            placeholder_properties,
             relation_types,
             relation_type_indices,
             languages,
@@ -373,10 +906,58 @@ impl DictionaryBuilder {
             unit_indices,
             objects,
             object_indices,
+            seeded: SeedCounts::default(),
+            value_clocks: self.value_clocks,
+            profile: self.profile,
+            strings: self.strings,
+            string_indices: self.string_indices,
+            references,
         }
     }
 }
 
+impl WireDictionaries {
+    /// Returns these (shared) dictionaries followed by `local` entries.
+    ///
+    /// This is the inverse of [`DictionaryBuilder::write_local_dictionaries`]:
+    /// local indices continue after the shared ones. Fails if a local entry
+    /// repeats a shared one.
+    pub fn extended_with(&self, local: WireDictionaries) -> Result<WireDictionaries, DecodeError> {
+        fn extend(
+            shared: &[Id],
+            local: Vec<Id>,
+            dict: &'static str,
+        ) -> Result<Vec<Id>, DecodeError> {
+            let seen: FxHashSet<&Id> = shared.iter().collect();
+            if let Some(id) = local.iter().find(|id| seen.contains(id)) {
+                return Err(DecodeError::DuplicateDictionaryEntry { dict, id: *id });
+            }
+            let mut merged = shared.to_vec();
+            merged.extend(local);
+            Ok(merged)
+        }
+
+        let seen: FxHashSet<&Id> = self.properties.iter().map(|(id, _)| id).collect();
+        if let Some((id, _)) = local.properties.iter().find(|(id, _)| seen.contains(id)) {
+            return Err(DecodeError::DuplicateDictionaryEntry { dict: "properties", id: *id });
+        }
+        let mut properties = self.properties.clone();
+        properties.extend(local.properties);
+
+        Ok(WireDictionaries {
+            properties,
+            relation_types: extend(&self.relation_types, local.relation_types, "relation_types")?,
+            languages: extend(&self.languages, local.languages, "languages")?,
+            units: extend(&self.units, local.units, "units")?,
+            objects: extend(&self.objects, local.objects, "objects")?,
+            value_clocks: local.value_clocks,
+            batch_records: local.batch_records,
+            remove_values: local.remove_values,
+            strings: local.strings,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -391,6 +972,35 @@ mod tests {
         assert!(edit.ops.is_empty());
     }
 
+    #[test]
+    fn test_canonical_eq() {
+        use crate::model::{CreateEntity, PropertyValue, Value};
+
+        let value = |property: u8, value: i64| PropertyValue {
+            property: [property; 16],
+            value: Value::Int64 { value, unit: None },
+            clock: None,
+        };
+        let mut a = Edit::new([1u8; 16]);
+        a.authors = vec![[2u8; 16], [3u8; 16]];
+        a.ops.push(Op::CreateEntity(CreateEntity {
+            id: [4u8; 16],
+            values: vec![value(5, 1), value(6, 2)],
+        }));
+
+        let mut b = a.clone();
+        b.authors.reverse();
+        if let Op::CreateEntity(ce) = &mut b.ops[0] {
+            ce.values.reverse();
+        }
+        assert_ne!(a, b);
+        assert!(a.canonical_eq(&b));
+
+        let mut c = a.clone();
+        c.ops.push(Op::DeleteEntity(crate::model::DeleteEntity { id: [4u8; 16] }));
+        assert!(!a.canonical_eq(&c));
+    }
+
     #[test]
     fn test_dictionary_builder() {
         let mut builder = DictionaryBuilder::new();
@@ -411,6 +1021,55 @@ mod tests {
         assert_eq!(dicts.properties[1], (prop2, DataType::Int64));
     }
 
+    #[test]
+    fn test_dictionary_stats() {
+        use crate::model::EditBuilder;
+
+        let (name, age, knows) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_entity([10u8; 16], |e| e.text(name, "Alice", None).int64(age, 30, None))
+            .create_entity([11u8; 16], |e| e.text(name, "Bob", Some([20u8; 16])))
+            .update_entity([10u8; 16], |u| u.unset_all(age).unset_language(name, [20u8; 16]))
+            .create_relation_simple([12u8; 16], [10u8; 16], [11u8; 16], knows)
+            .build();
+        let builder = edit.build_dictionaries();
+        let stats = builder.stats();
+
+        assert_eq!(stats.properties.ids, vec![name, age]);
+        assert_eq!(stats.properties.references, vec![3, 2]);
+        assert_eq!(stats.properties.hits, 3);
+        assert_eq!(stats.languages.references, vec![2]);
+        assert_eq!(stats.objects.ids, vec![[10u8; 16], [11u8; 16]]);
+        assert_eq!(stats.objects.references, vec![2, 1]);
+        assert_eq!(stats.relation_types.lookups(), 1);
+        assert!(stats.units.ids.is_empty());
+        assert_eq!(stats.index_bytes(), 11);
+        assert_eq!(builder.get_object_index(&[11u8; 16]), Some(1));
+
+        // 200 objects referenced once, one referenced 10 times last
+        let mut builder = DictionaryBuilder::new();
+        for n in 0..200u8 {
+            builder.add_object([n; 16]);
+        }
+        for _ in 0..9 {
+            builder.add_object([199u8; 16]);
+        }
+        let objects = builder.stats().objects;
+        assert_eq!(objects.index_bytes(), 128 + 72 * 2 + 9 * 2);
+        assert_eq!(objects.packed_index_bytes(), 10 + 127 + 72 * 2);
+        assert_eq!(objects.by_references()[0], ([199u8; 16], 10));
+
+        // Sorting keeps counts with their entries; seeding is not counted
+        let sorted = builder.clone().into_sorted().stats().objects;
+        assert_eq!(sorted.by_references()[0], ([199u8; 16], 10));
+        let packed = builder.clone().into_frequency_order();
+        assert_eq!(packed.get_object_index(&[199u8; 16]), Some(0));
+        assert_eq!(packed.get_object_index(&[0u8; 16]), Some(1));
+        assert_eq!(packed.stats().objects.index_bytes(), objects.packed_index_bytes());
+        let seeded = DictionaryBuilder::with_seed(&builder.build()).stats();
+        assert_eq!(seeded.objects.lookups(), 0);
+    }
+
     #[test]
     fn test_language_indexing() {
         let mut builder = DictionaryBuilder::new();
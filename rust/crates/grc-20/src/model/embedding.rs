@@ -0,0 +1,140 @@
+//! EMBEDDING construction and sub-type conversion.
+//!
+//! Builds EMBEDDING values from `f32`/`i8` slices so the little-endian
+//! `data` buffer and `dims` always agree, and converts between sub-types:
+//!
+//! - FLOAT32 → INT8 scales by the largest magnitude so it maps to ±127.
+//!   The scale is not stored, so INT8 values are only comparable by angle
+//!   (cosine similarity), which scaling preserves.
+//! - Any → BINARY keeps the sign: bit `i` is set when dimension `i` is
+//!   positive (spec Section 2.4 bit order).
+//! - Reading back ([`Value::as_f32_vec`]) yields INT8 values as is and
+//!   BINARY bits as +1.0 / -1.0.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::model::{EmbeddingSubType, Value};
+
+impl Value<'static> {
+    /// Builds a FLOAT32 embedding.
+    pub fn embedding_from_f32(values: &[f32]) -> Self {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        Value::Embedding {
+            sub_type: EmbeddingSubType::Float32,
+            dims: values.len(),
+            data: Cow::Owned(data),
+        }
+    }
+
+    /// Builds an INT8 embedding.
+    pub fn embedding_from_i8(values: &[i8]) -> Self {
+        Value::Embedding {
+            sub_type: EmbeddingSubType::Int8,
+            dims: values.len(),
+            data: Cow::Owned(values.iter().map(|v| *v as u8).collect()),
+        }
+    }
+
+    /// Builds a BINARY embedding from one bit per dimension.
+    pub fn embedding_from_bits(bits: &[bool]) -> Self {
+        let mut data = alloc::vec![0u8; bits.len().div_ceil(8)];
+        for (i, _) in bits.iter().enumerate().filter(|(_, bit)| **bit) {
+            data[i / 8] |= 1 << (i % 8);
+        }
+        Value::Embedding {
+            sub_type: EmbeddingSubType::Binary,
+            dims: bits.len(),
+            data: Cow::Owned(data),
+        }
+    }
+}
+
+impl Value<'_> {
+    /// Returns an embedding's dimensions as `f32`.
+    ///
+    /// INT8 dimensions are returned unscaled; BINARY bits map to +1.0 (set)
+    /// and -1.0 (clear). Returns None for other values and for embeddings
+    /// whose data length does not match `dims`.
+    pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
+        let Value::Embedding { sub_type, dims, data } = self else {
+            return None;
+        };
+        if data.len() != sub_type.bytes_for_dims(*dims) {
+            return None;
+        }
+        let values = match sub_type {
+            EmbeddingSubType::Float32 => data
+                .chunks_exact(4)
+                .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+            EmbeddingSubType::Int8 => data.iter().map(|b| *b as i8 as f32).collect(),
+            EmbeddingSubType::Binary => (0..*dims)
+                .map(|i| if data[i / 8] >> (i % 8) & 1 == 1 { 1.0 } else { -1.0 })
+                .collect(),
+        };
+        Some(values)
+    }
+
+    /// Converts an embedding to another sub-type (see the module docs for
+    /// how each conversion quantizes).
+    ///
+    /// Returns None for other values and malformed embeddings.
+    pub fn convert_embedding(&self, target: EmbeddingSubType) -> Option<Value<'static>> {
+        let values = self.as_f32_vec()?;
+        let converted = match target {
+            EmbeddingSubType::Float32 => Value::embedding_from_f32(&values),
+            EmbeddingSubType::Int8 => {
+                let max = values.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+                let scale = if max > 0.0 { 127.0 / max } else { 0.0 };
+                let quantized: Vec<i8> =
+                    values.iter().map(|v| round(v * scale).clamp(-127.0, 127.0) as i8).collect();
+                Value::embedding_from_i8(&quantized)
+            }
+            EmbeddingSubType::Binary => {
+                let bits: Vec<bool> = values.iter().map(|v| *v > 0.0).collect();
+                Value::embedding_from_bits(&bits)
+            }
+        };
+        Some(converted)
+    }
+}
+
+/// Rounds half away from zero (`f32::round` needs `std`).
+fn round(v: f32) -> f32 {
+    let truncated = v as i32 as f32;
+    if (v - truncated).abs() >= 0.5 {
+        truncated + v.signum()
+    } else {
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_roundtrip_and_quantization() {
+        let floats = [0.5f32, -1.0, 0.25, 0.0, 2.0, -0.1, 0.3, 0.7, -2.0];
+        let value = Value::embedding_from_f32(&floats);
+        assert!(value.validate().is_none());
+        assert_eq!(value.as_f32_vec().unwrap(), floats);
+
+        let int8 = value.convert_embedding(EmbeddingSubType::Int8).unwrap();
+        assert!(int8.validate().is_none());
+        let expected = [32.0, -64.0, 16.0, 0.0, 127.0, -6.0, 19.0, 44.0, -127.0];
+        assert_eq!(int8.as_f32_vec().unwrap(), expected);
+
+        let binary = value.convert_embedding(EmbeddingSubType::Binary).unwrap();
+        assert!(binary.validate().is_none());
+        let Value::Embedding { dims, data, .. } = &binary else { unreachable!() };
+        // Dims 0, 2, 4, 6, 7 are positive; the second byte's padding is zero
+        assert_eq!((*dims, data.as_ref()), (9, [0b1101_0101u8, 0].as_slice()));
+        let signs = binary.as_f32_vec().unwrap();
+        assert_eq!(signs[..3], [1.0, -1.0, 1.0]);
+        assert_eq!(int8.convert_embedding(EmbeddingSubType::Binary).unwrap(), binary);
+
+        assert_eq!(Value::Bool(true).as_f32_vec(), None);
+    }
+}
@@ -0,0 +1,130 @@
+//! GeoJSON and `geo-types` conversions for POINT values (`geo` feature).
+//!
+//! GeoJSON positions and GRC-20 points share the `[lon, lat, alt]` order,
+//! and `geo-types` points use `x` for longitude and `y` for latitude, so
+//! the conversions are direct. Points coming in are checked with
+//! [`Value::validate`], so out-of-range coordinates fail here rather than
+//! at encode time.
+//!
+//! ```ignore
+//! let value = Value::try_from(&feature.geometry.unwrap())?;
+//! let geometry = geojson::Geometry::try_from(&value)?;
+//! ```
+
+use crate::error::GeoError;
+use crate::model::Value;
+
+fn point(lon: f64, lat: f64, alt: Option<f64>) -> Result<Value<'static>, GeoError> {
+    let value = Value::Point { lon, lat, alt };
+    match value.validate() {
+        Some(reason) => Err(GeoError::InvalidPoint { reason }),
+        None => Ok(value),
+    }
+}
+
+fn ordinates(value: &Value<'_>) -> Result<(f64, f64, Option<f64>), GeoError> {
+    match value {
+        Value::Point { lon, lat, alt } => Ok((*lon, *lat, *alt)),
+        _ => Err(GeoError::NotPointValue),
+    }
+}
+
+impl TryFrom<&geojson::Value> for Value<'static> {
+    type Error = GeoError;
+
+    /// Converts a GeoJSON Point with 2 or 3 ordinates.
+    fn try_from(geometry: &geojson::Value) -> Result<Self, GeoError> {
+        let geojson::Value::Point(position) = geometry else {
+            return Err(GeoError::NotAPoint);
+        };
+        match position[..] {
+            [lon, lat] => point(lon, lat, None),
+            [lon, lat, alt] => point(lon, lat, Some(alt)),
+            _ => Err(GeoError::InvalidPosition { len: position.len() }),
+        }
+    }
+}
+
+impl TryFrom<&geojson::Geometry> for Value<'static> {
+    type Error = GeoError;
+
+    fn try_from(geometry: &geojson::Geometry) -> Result<Self, GeoError> {
+        Value::try_from(&geometry.value)
+    }
+}
+
+impl TryFrom<&Value<'_>> for geojson::Value {
+    type Error = GeoError;
+
+    fn try_from(value: &Value<'_>) -> Result<Self, GeoError> {
+        let (lon, lat, alt) = ordinates(value)?;
+        let mut position = vec![lon, lat];
+        position.extend(alt);
+        Ok(geojson::Value::Point(position))
+    }
+}
+
+impl TryFrom<&Value<'_>> for geojson::Geometry {
+    type Error = GeoError;
+
+    fn try_from(value: &Value<'_>) -> Result<Self, GeoError> {
+        Ok(geojson::Geometry::new(geojson::Value::try_from(value)?))
+    }
+}
+
+impl TryFrom<geo_types::Point<f64>> for Value<'static> {
+    type Error = GeoError;
+
+    fn try_from(p: geo_types::Point<f64>) -> Result<Self, GeoError> {
+        point(p.x(), p.y(), None)
+    }
+}
+
+impl TryFrom<geo_types::Coord<f64>> for Value<'static> {
+    type Error = GeoError;
+
+    fn try_from(c: geo_types::Coord<f64>) -> Result<Self, GeoError> {
+        point(c.x, c.y, None)
+    }
+}
+
+impl TryFrom<&Value<'_>> for geo_types::Point<f64> {
+    type Error = GeoError;
+
+    /// Converts a POINT value, dropping its altitude.
+    fn try_from(value: &Value<'_>) -> Result<Self, GeoError> {
+        let (lon, lat, _) = ordinates(value)?;
+        Ok(geo_types::Point::new(lon, lat))
+    }
+}
+
+impl Value<'static> {
+    /// Builds a POINT value from a `geo-types` point with an altitude.
+    pub fn point_from_geo(p: geo_types::Point<f64>, alt: Option<f64>) -> Result<Self, GeoError> {
+        point(p.x(), p.y(), alt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geojson_roundtrip() {
+        let geometry = geojson::Geometry::new(geojson::Value::Point(vec![-122.42, 37.77, 16.0]));
+        let value = Value::try_from(&geometry).unwrap();
+        assert_eq!(value, Value::Point { lon: -122.42, lat: 37.77, alt: Some(16.0) });
+        assert_eq!(geojson::Geometry::try_from(&value).unwrap(), geometry);
+
+        let p: geo_types::Point<f64> = (&value).try_into().unwrap();
+        assert_eq!((p.x(), p.y()), (-122.42, 37.77));
+        assert_eq!(Value::point_from_geo(p, Some(16.0)).unwrap(), value);
+
+        let bad = geojson::Value::Point(vec![10.0, 95.0]);
+        assert!(matches!(Value::try_from(&bad), Err(GeoError::InvalidPoint { .. })));
+        let short = geojson::Value::Point(vec![10.0]);
+        assert_eq!(Value::try_from(&short), Err(GeoError::InvalidPosition { len: 1 }));
+        let line = geojson::Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]);
+        assert_eq!(Value::try_from(&line), Err(GeoError::NotAPoint));
+    }
+}
@@ -2,14 +2,25 @@
 //!
 //! All identifiers in GRC-20 are RFC 4122 UUIDs stored as 16 raw bytes.
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::str::FromStr;
+
 use sha2::{Digest, Sha256};
 
+use crate::error::ParseIdError;
+
 /// A 16-byte UUID identifier.
 ///
 /// This is the universal identifier type for entities, relations, properties,
 /// types, spaces, authors, and all other objects in GRC-20.
 pub type Id = [u8; 16];
 
+/// A set of IDs, e.g. the entities a partial indexer follows.
+pub type IdSet = crate::hash::FxHashSet<Id>;
+
 /// The zero/nil UUID.
 pub const NIL_ID: Id = [0u8; 16];
 
@@ -34,6 +45,74 @@ pub fn derived_uuid(input: &[u8]) -> Id {
     id
 }
 
+/// Generates a random UUIDv4.
+///
+/// Use this for new entities and relations whose IDs are not derived from
+/// their content.
+///
+/// # Panics
+///
+/// Panics if the platform's random source fails.
+#[cfg(feature = "getrandom")]
+pub fn random_id() -> Id {
+    let mut id = random_bytes();
+    // Set version 4 (bits 4-7 of byte 6)
+    id[6] = (id[6] & 0x0F) | 0x40;
+    // Set RFC 4122 variant (bits 6-7 of byte 8)
+    id[8] = (id[8] & 0x3F) | 0x80;
+    id
+}
+
+/// Generates a UUIDv7 for the current time.
+///
+/// IDs created in later milliseconds sort after earlier ones, which keeps
+/// freshly created entities clustered in ordered indexes. IDs created
+/// within the same millisecond are in random order.
+///
+/// # Panics
+///
+/// Panics if the platform's random source fails.
+#[cfg(all(feature = "getrandom", feature = "std"))]
+pub fn time_ordered_id() -> Id {
+    let unix_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    time_ordered_id_at(unix_ms)
+}
+
+/// Generates a UUIDv7 for a Unix timestamp in milliseconds.
+///
+/// ```text
+/// id[0..6] = unix_ms (48-bit big-endian)
+/// id[6]    = 0x70 | random
+/// id[8]    = 0x80 | random
+/// ```
+///
+/// Only the low 48 bits of `unix_ms` are used.
+///
+/// # Panics
+///
+/// Panics if the platform's random source fails.
+#[cfg(feature = "getrandom")]
+pub fn time_ordered_id_at(unix_ms: u64) -> Id {
+    let mut id = random_bytes();
+    id[0..6].copy_from_slice(&unix_ms.to_be_bytes()[2..]);
+    // Set version 7 (bits 4-7 of byte 6)
+    id[6] = (id[6] & 0x0F) | 0x70;
+    // Set RFC 4122 variant (bits 6-7 of byte 8)
+    id[8] = (id[8] & 0x3F) | 0x80;
+    id
+}
+
+#[cfg(feature = "getrandom")]
+fn random_bytes() -> Id {
+    let mut bytes = [0u8; 16];
+    if let Err(err) = getrandom::fill(&mut bytes) {
+        panic!("random source failed: {err}");
+    }
+    bytes
+}
+
 /// Computes the value identity hash for a non-TEXT value.
 ///
 /// ```text
@@ -100,13 +179,68 @@ pub fn relation_entity_id(relation_id: &Id) -> Id {
     derived_uuid(&input)
 }
 
+/// Domain separator prefix for space-scoped derivation.
+const SPACE_SCOPED_PREFIX: &[u8] = b"grc20:space:";
+
+/// Derives a deterministic ID within a space (spec Section 5.3).
+///
+/// ```text
+/// id = derived_uuid("grc20:space:" || space_id || ":" || name)
+/// ```
+///
+/// The same name in different spaces yields different IDs, so
+/// applications can mint stable IDs without coordinating with each other.
+pub fn space_scoped_id(space: &Id, name: &str) -> Id {
+    let mut input = Vec::with_capacity(SPACE_SCOPED_PREFIX.len() + 17 + name.len());
+    input.extend_from_slice(SPACE_SCOPED_PREFIX);
+    input.extend_from_slice(space);
+    input.push(b':');
+    input.extend_from_slice(name.as_bytes());
+    derived_uuid(&input)
+}
+
+/// Derives the ID of a property defined by a space.
+///
+/// ```text
+/// id = space_scoped_id(space_id, "property:" + name)
+/// ```
+pub fn space_property_id(space: &Id, name: &str) -> Id {
+    space_scoped_id(space, &format!("property:{}", name))
+}
+
+/// Derives the ID of a type defined by a space.
+///
+/// ```text
+/// id = space_scoped_id(space_id, "type:" + name)
+/// ```
+pub fn space_type_id(space: &Id, name: &str) -> Id {
+    space_scoped_id(space, &format!("type:{}", name))
+}
+
+/// Derives the ID of a relation type defined by a space.
+///
+/// ```text
+/// id = space_scoped_id(space_id, "relation-type:" + name)
+/// ```
+pub fn space_relation_type_id(space: &Id, name: &str) -> Id {
+    space_scoped_id(space, &format!("relation-type:{}", name))
+}
+
 /// Formats a UUID as non-hyphenated lowercase hex (recommended display format).
 pub fn format_id(id: &Id) -> String {
-    let mut s = String::with_capacity(32);
-    for byte in id {
-        s.push_str(&format!("{:02x}", byte));
-    }
-    s
+    DisplayId::new(*id).to_string()
+}
+
+/// Formats a UUID in base58 (Bitcoin alphabet), as most wallets and
+/// explorers in the surrounding ecosystem display IDs.
+pub fn format_id_base58(id: &Id) -> String {
+    DisplayId::new(*id).encoding(IdEncoding::Base58).to_string()
+}
+
+/// Formats a UUID as a multibase string: the encoding's prefix character
+/// followed by the encoded ID (e.g. `z` + base58).
+pub fn format_id_multibase(id: &Id, encoding: IdEncoding) -> String {
+    DisplayId::new(*id).encoding(encoding).multibase().to_string()
 }
 
 /// Parses a UUID from hex string (with or without hyphens).
@@ -119,16 +253,238 @@ pub fn parse_id(s: &str) -> Option<Id> {
 
     let mut id = [0u8; 16];
     for (i, chunk) in hex.as_bytes().chunks(2).enumerate() {
-        let byte_str = std::str::from_utf8(chunk).ok()?;
+        let byte_str = core::str::from_utf8(chunk).ok()?;
         id[i] = u8::from_str_radix(byte_str, 16).ok()?;
     }
     Some(id)
 }
 
+/// Parses a UUID from base58 (Bitcoin alphabet).
+///
+/// Only the canonical encoding of a 16-byte ID is accepted: one leading
+/// `1` per leading zero byte, and no value wider than 128 bits.
+pub fn parse_id_base58(s: &str) -> Option<Id> {
+    if s.len() > BASE58_MAX_LEN {
+        return None;
+    }
+    let mut n: u128 = 0;
+    for c in s.bytes() {
+        let digit = BASE58_ALPHABET.iter().position(|a| *a == c)? as u128;
+        n = n.checked_mul(58)?.checked_add(digit)?;
+    }
+    let id = n.to_be_bytes();
+    // Re-encoding rejects extra or missing leading '1's
+    (DisplayId::new(id).encoding(IdEncoding::Base58).to_string() == s).then_some(id)
+}
+
+/// Parses a UUID from unpadded base64url (RFC 4648 §5).
+pub fn parse_id_base64url(s: &str) -> Option<Id> {
+    if s.len() != BASE64_LEN {
+        return None;
+    }
+    let mut n: u128 = 0;
+    for (i, c) in s.bytes().enumerate() {
+        let digit = BASE64URL_ALPHABET.iter().position(|a| *a == c)? as u128;
+        if i == BASE64_LEN - 1 {
+            // 22 digits carry 132 bits; the last digit's low 4 must be zero
+            if digit & 0x0F != 0 {
+                return None;
+            }
+            n = (n << 2) | (digit >> 4);
+        } else {
+            n = (n << 6) | digit;
+        }
+    }
+    Some(n.to_be_bytes())
+}
+
+/// Parses a multibase-encoded UUID (`f`/`F` hex, `z` base58, `u` base64url).
+pub fn parse_id_multibase(s: &str) -> Option<Id> {
+    let mut chars = s.chars();
+    let prefix = chars.next()?;
+    let rest = chars.as_str();
+    match prefix {
+        'f' | 'F' if rest.len() == 32 => parse_id(rest),
+        'z' => parse_id_base58(rest),
+        'u' => parse_id_base64url(rest),
+        _ => None,
+    }
+}
+
+/// Parses a UUID in any supported form: hex (with or without hyphens),
+/// multibase, or bare base58.
+///
+/// Bare base64url is not accepted since it cannot be told apart from
+/// base58. A string that is valid multibase is read as multibase.
+pub fn parse_id_any(s: &str) -> Option<Id> {
+    if s.len() == 32 || s.len() == 36 {
+        if let Some(id) = parse_id(s) {
+            return Some(id);
+        }
+    }
+    parse_id_multibase(s).or_else(|| parse_id_base58(s))
+}
+
+pub(crate) const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// Longest base58 encoding of 16 bytes.
+const BASE58_MAX_LEN: usize = 22;
+/// Length of the unpadded base64 encoding of 16 bytes.
+const BASE64_LEN: usize = 22;
+
+/// Text encodings for IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IdEncoding {
+    /// Lowercase hex, 32 characters (multibase prefix `f`).
+    #[default]
+    Hex,
+    /// Base58 with the Bitcoin alphabet (multibase prefix `z`).
+    Base58,
+    /// Unpadded base64url, 22 characters (multibase prefix `u`).
+    Base64Url,
+}
+
+impl IdEncoding {
+    /// Returns the multibase prefix character.
+    pub fn multibase_prefix(self) -> char {
+        match self {
+            IdEncoding::Hex => 'f',
+            IdEncoding::Base58 => 'z',
+            IdEncoding::Base64Url => 'u',
+        }
+    }
+}
+
+/// An ID that formats without intermediate allocations.
+///
+/// Formats as lowercase hex by default; [`encoding`](Self::encoding) and
+/// [`multibase`](Self::multibase) pick another form. Parsing accepts
+/// anything [`parse_id_any`] does.
+///
+/// ```ignore
+/// println!("created {}", DisplayId::new(id).encoding(IdEncoding::Base58));
+/// let id: DisplayId = "zJ8sW...".parse()?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayId {
+    id: Id,
+    encoding: IdEncoding,
+    multibase: bool,
+}
+
+impl DisplayId {
+    /// Wraps an ID for display as hex.
+    pub fn new(id: Id) -> Self {
+        Self {
+            id,
+            encoding: IdEncoding::Hex,
+            multibase: false,
+        }
+    }
+
+    /// Sets the encoding.
+    pub fn encoding(mut self, encoding: IdEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Prefixes the output with the encoding's multibase character.
+    pub fn multibase(mut self) -> Self {
+        self.multibase = true;
+        self
+    }
+
+    /// Returns the wrapped ID.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+impl From<Id> for DisplayId {
+    fn from(id: Id) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<DisplayId> for Id {
+    fn from(display: DisplayId) -> Self {
+        display.id
+    }
+}
+
+impl fmt::Display for DisplayId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.multibase {
+            f.write_char(self.encoding.multibase_prefix())?;
+        }
+        match self.encoding {
+            IdEncoding::Hex => {
+                for byte in &self.id {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            IdEncoding::Base58 => {
+                let mut buf = [0u8; BASE58_MAX_LEN];
+                let mut start = buf.len();
+                let mut n = u128::from_be_bytes(self.id);
+                while n > 0 {
+                    start -= 1;
+                    buf[start] = BASE58_ALPHABET[(n % 58) as usize];
+                    n /= 58;
+                }
+                for _ in self.id.iter().take_while(|b| **b == 0) {
+                    f.write_char('1')?;
+                }
+                // The buffer holds only alphabet characters
+                f.write_str(core::str::from_utf8(&buf[start..]).map_err(|_| fmt::Error)?)
+            }
+            IdEncoding::Base64Url => {
+                let n = u128::from_be_bytes(self.id);
+                for i in 0..BASE64_LEN {
+                    // 21 full digits, then the last 2 bits shifted to the top
+                    let digit = if i < BASE64_LEN - 1 {
+                        (n >> (122 - 6 * i)) & 0x3F
+                    } else {
+                        (n & 0x03) << 4
+                    };
+                    f.write_char(BASE64URL_ALPHABET[digit as usize] as char)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for DisplayId {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_id_any(s)
+            .map(Self::new)
+            .ok_or_else(|| ParseIdError::Unrecognized { input: s.to_string() })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_random_and_time_ordered_ids() {
+        let (a, b) = (random_id(), random_id());
+        assert_ne!(a, b);
+        assert_eq!((a[6] & 0xF0, a[8] & 0xC0), (0x40, 0x80));
+
+        let early = time_ordered_id_at(1_700_000_000_000);
+        let late = time_ordered_id_at(1_700_000_000_001);
+        assert!(early < late);
+        assert_eq!((early[6] & 0xF0, early[8] & 0xC0), (0x70, 0x80));
+        assert_eq!(&early[0..6], &1_700_000_000_000u64.to_be_bytes()[2..]);
+    }
+
     #[test]
     fn test_derived_uuid_version_and_variant() {
         let id = derived_uuid(b"test");
@@ -156,6 +512,31 @@ mod tests {
         assert_eq!(id, parsed);
     }
 
+    #[test]
+    fn test_alternate_encodings() {
+        let id = derived_uuid(b"test");
+        let zero_led = [[0u8; 2].as_slice(), &id[2..]].concat().try_into().unwrap();
+        for id in [id, zero_led, NIL_ID, [0xFF; 16]] {
+            let base58 = format_id_base58(&id);
+            assert_eq!(parse_id_base58(&base58), Some(id));
+            for encoding in [IdEncoding::Hex, IdEncoding::Base58, IdEncoding::Base64Url] {
+                let multibase = format_id_multibase(&id, encoding);
+                assert_eq!(parse_id_any(&multibase), Some(id), "{multibase}");
+            }
+            assert_eq!(parse_id_any(&base58), Some(id));
+            assert_eq!(format_id(&id).parse::<DisplayId>().unwrap().id(), id);
+        }
+        assert_eq!(format_id_base58(&NIL_ID), "1".repeat(16));
+        let base64 = format_id_multibase(&[0xFF; 16], IdEncoding::Base64Url);
+        assert_eq!(base64, "u_____________________w");
+
+        // Non-canonical forms are rejected
+        assert_eq!(parse_id_base58(&format!("1{}", format_id_base58(&id))), None);
+        assert_eq!(parse_id_base64url("_____________________x"), None);
+        assert_eq!(parse_id_base58("0OIl"), None);
+        assert!("not an id".parse::<DisplayId>().is_err());
+    }
+
     #[test]
     fn test_parse_with_hyphens() {
         let hex = "550e8400e29b41d4a716446655440000";
@@ -202,4 +583,22 @@ mod tests {
         assert_eq!(entity1[6] & 0xF0, 0x80);
         assert_eq!(entity1[8] & 0xC0, 0x80);
     }
+
+    #[test]
+    fn test_space_scoped_ids() {
+        let (space_a, space_b) = ([1u8; 16], [2u8; 16]);
+        let mut input = b"grc20:space:".to_vec();
+        input.extend_from_slice(&space_a);
+        input.extend_from_slice(b":Person");
+        assert_eq!(space_scoped_id(&space_a, "Person"), derived_uuid(&input));
+        assert_ne!(space_scoped_id(&space_a, "Person"), space_scoped_id(&space_b, "Person"));
+
+        let ids = [
+            space_property_id(&space_a, "Person"),
+            space_type_id(&space_a, "Person"),
+            space_relation_type_id(&space_a, "Person"),
+        ];
+        assert_eq!(ids[1], space_scoped_id(&space_a, "type:Person"));
+        assert!(ids[0] != ids[1] && ids[1] != ids[2] && ids[0] != ids[2]);
+    }
 }
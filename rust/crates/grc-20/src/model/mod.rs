@@ -1,24 +1,81 @@
 //! Data model types for GRC-20.
 //!
 //! This module contains all the core types for representing GRC-20 data:
-//! - Identifiers (UUIDs)
-//! - Values (typed property instances)
+//! - Identifiers (UUIDs) and typed ID wrappers
+//! - Values (typed property instances) and DECIMAL parsing/formatting
+//! - EMBEDDING construction and quantization
 //! - Operations (state changes)
 //! - Edits (batched operations)
 //! - Builders (ergonomic construction)
+//! - ID aliases for human-facing output
+//! - Cost model (pricing and capping edits)
+//! - BYTES helpers (hashing, MIME sniffing, linting)
+//! - Timestamp and DATE helpers (timezone-annotation convention, `chrono`
+//!   conversions)
+//! - Recurrence evaluation for SCHEDULE values
+//! - Visitors over an edit's ops and values
+//! - Dependency ordering of ops
+//! - Fractional-indexing positions for ordered relations
+//! - Spaces and their membership
 
+#[cfg(feature = "std")]
+pub mod alias;
 pub mod builder;
+#[cfg(feature = "std")]
+pub mod bytes;
+#[cfg(feature = "std")]
+pub mod cost;
+#[cfg(feature = "chrono")]
+pub mod datetime;
+pub mod decimal;
 pub mod edit;
+pub mod embedding;
+#[cfg(feature = "geo")]
+pub mod geo;
 pub mod id;
 pub mod op;
+pub mod order;
+pub mod position;
+#[cfg(feature = "std")]
+pub mod recurrence;
+pub mod space;
+pub mod timestamp;
+pub mod typed_id;
 pub mod value;
+pub mod visit;
 
-pub use builder::{EditBuilder, EntityBuilder, RelationBuilder, UpdateEntityBuilder};
-pub use edit::{DictionaryBuilder, Edit, WireDictionaries};
-pub use id::{derived_uuid, format_id, parse_id, relation_entity_id, text_value_id, value_id, Id, NIL_ID};
+#[cfg(feature = "std")]
+pub use alias::AliasTable;
+pub use builder::{
+    EditBuilder, EntityBuilder, RelationBuilder, RelationIdMode, UpdateEntityBuilder,
+};
+pub use decimal::{format_decimal, parse_decimal};
+pub use edit::{
+    DictionaryBuilder, DictionaryStats, DictionaryUsage, Edit, Provenance, WireDictionaries,
+};
+pub use id::{
+    derived_uuid, format_id, format_id_base58, format_id_multibase, parse_id, parse_id_any,
+    parse_id_base58, parse_id_base64url, parse_id_multibase, relation_entity_id,
+    space_property_id, space_relation_type_id, space_scoped_id, space_type_id, text_value_id,
+    value_id, DisplayId, Id, IdEncoding, IdSet, NIL_ID,
+};
+#[cfg(feature = "getrandom")]
+pub use id::{random_id, time_ordered_id_at};
+#[cfg(all(feature = "getrandom", feature = "std"))]
+pub use id::time_ordered_id;
+pub use typed_id::{
+    unique_relation_id_typed, EntityId, LanguageId, PropertyId, RelationTypeId, SpaceId,
+};
+pub use timestamp::{format_date_value, parse_date_value, validate_iso8601_date, DatePrecision};
+#[cfg(feature = "chrono")]
+pub use datetime::{timestamp_to_utc, utc_to_timestamp};
 pub use op::{
-    validate_position, CreateEntity, CreateRelation, CreateValueRef, DeleteEntity, DeleteRelation,
-    Op, RestoreEntity, RestoreRelation, UnsetLanguage, UnsetRelationField, UnsetValue, UpdateEntity,
-    UpdateRelation,
+    validate_position, AddMember, CreateEntity, CreateRelation, CreateSpace, CreateValueRef,
+    DeleteEntity, DeleteRelation, MemberRole, Op, OpKind, RemoveMember, RestoreEntity,
+    RestoreRelation, UnknownOp, UnsetLanguage, UnsetRelationField, UnsetValue, UpdateEntity,
+    UpdateRelation, UpdateSpaceMetadata, MIN_EXTENSION_OP_TYPE,
 };
+pub use position::{evenly_spaced, position_between, rebalance_ops, MAX_POSITION_LEN};
+pub use space::{Space, SpaceRegistry};
 pub use value::{DataType, DecimalMantissa, EmbeddingSubType, Property, PropertyValue, Value};
+pub use visit::{OpVisitor, OpVisitorMut};
@@ -2,9 +2,11 @@
 //!
 //! All state changes in GRC-20 are expressed as operations (ops).
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 
-use crate::model::{Id, PropertyValue};
+use crate::model::{Id, PropertyValue, Value};
 
 /// An atomic operation that modifies graph state (spec Section 3.1).
 #[derive(Debug, Clone, PartialEq)]
@@ -18,9 +20,90 @@ pub enum Op<'a> {
     DeleteRelation(DeleteRelation),
     RestoreRelation(RestoreRelation),
     CreateValueRef(CreateValueRef),
+    CreateSpace(CreateSpace<'a>),
+    AddMember(AddMember),
+    RemoveMember(RemoveMember),
+    UpdateSpaceMetadata(UpdateSpaceMetadata<'a>),
+    /// An extension op this crate doesn't understand, kept only when
+    /// decoding with [`DecodeOptions::preserve_unknown_ops`].
+    ///
+    /// [`DecodeOptions::preserve_unknown_ops`]: crate::codec::DecodeOptions::preserve_unknown_ops
+    Unknown(UnknownOp<'a>),
+}
+
+/// Lowest op type of the extension range (spec Section 6.4). Extension ops
+/// are length-prefixed, so decoders that don't know them can skip them.
+pub const MIN_EXTENSION_OP_TYPE: u8 = 0x80;
+
+/// The kind of an op, identified by its wire type code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum OpKind {
+    CreateEntity = 1,
+    UpdateEntity = 2,
+    DeleteEntity = 3,
+    RestoreEntity = 4,
+    CreateRelation = 5,
+    UpdateRelation = 6,
+    DeleteRelation = 7,
+    RestoreRelation = 8,
+    CreateValueRef = 9,
+    CreateSpace = 10,
+    AddMember = 11,
+    RemoveMember = 12,
+    UpdateSpaceMetadata = 13,
+    /// Several CreateEntity ops stored column by column. Wire only: it
+    /// decodes to [`Op::CreateEntity`] ops.
+    BatchCreateEntities = 14,
+    /// Any extension op (types 128-255).
+    Extension = MIN_EXTENSION_OP_TYPE,
+}
+
+impl OpKind {
+    /// Creates an OpKind from its wire type code.
+    pub fn from_u8(v: u8) -> Option<OpKind> {
+        match v {
+            1 => Some(OpKind::CreateEntity),
+            2 => Some(OpKind::UpdateEntity),
+            3 => Some(OpKind::DeleteEntity),
+            4 => Some(OpKind::RestoreEntity),
+            5 => Some(OpKind::CreateRelation),
+            6 => Some(OpKind::UpdateRelation),
+            7 => Some(OpKind::DeleteRelation),
+            8 => Some(OpKind::RestoreRelation),
+            9 => Some(OpKind::CreateValueRef),
+            10 => Some(OpKind::CreateSpace),
+            11 => Some(OpKind::AddMember),
+            12 => Some(OpKind::RemoveMember),
+            13 => Some(OpKind::UpdateSpaceMetadata),
+            14 => Some(OpKind::BatchCreateEntities),
+            MIN_EXTENSION_OP_TYPE..=u8::MAX => Some(OpKind::Extension),
+            _ => None,
+        }
+    }
 }
 
 impl Op<'_> {
+    /// Returns the kind of this op.
+    pub fn kind(&self) -> OpKind {
+        match self {
+            Op::CreateEntity(_) => OpKind::CreateEntity,
+            Op::UpdateEntity(_) => OpKind::UpdateEntity,
+            Op::DeleteEntity(_) => OpKind::DeleteEntity,
+            Op::RestoreEntity(_) => OpKind::RestoreEntity,
+            Op::CreateRelation(_) => OpKind::CreateRelation,
+            Op::UpdateRelation(_) => OpKind::UpdateRelation,
+            Op::DeleteRelation(_) => OpKind::DeleteRelation,
+            Op::RestoreRelation(_) => OpKind::RestoreRelation,
+            Op::CreateValueRef(_) => OpKind::CreateValueRef,
+            Op::CreateSpace(_) => OpKind::CreateSpace,
+            Op::AddMember(_) => OpKind::AddMember,
+            Op::RemoveMember(_) => OpKind::RemoveMember,
+            Op::UpdateSpaceMetadata(_) => OpKind::UpdateSpaceMetadata,
+            Op::Unknown(_) => OpKind::Extension,
+        }
+    }
+
     /// Returns the op type code for wire encoding.
     pub fn op_type(&self) -> u8 {
         match self {
@@ -33,10 +116,27 @@ impl Op<'_> {
             Op::DeleteRelation(_) => 7,
             Op::RestoreRelation(_) => 8,
             Op::CreateValueRef(_) => 9,
+            Op::CreateSpace(_) => 10,
+            Op::AddMember(_) => 11,
+            Op::RemoveMember(_) => 12,
+            Op::UpdateSpaceMetadata(_) => 13,
+            Op::Unknown(op) => op.tag,
         }
     }
 }
 
+/// An extension op, kept as its undecoded payload (spec Section 6.4).
+///
+/// Extension payloads don't reference the edit's dictionaries, so the op is
+/// re-encoded byte for byte. It has no effect on state in this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownOp<'a> {
+    /// The op type, at least [`MIN_EXTENSION_OP_TYPE`].
+    pub tag: u8,
+    /// The payload, without the type and length prefix.
+    pub bytes: Cow<'a, [u8]>,
+}
+
 /// Creates a new entity (spec Section 3.2).
 ///
 /// If the entity does not exist, creates it. If it already exists,
@@ -53,7 +153,8 @@ pub struct CreateEntity<'a> {
 ///
 /// Application order within op:
 /// 1. unset_values
-/// 2. set_properties
+/// 2. remove_values
+/// 3. set_properties
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct UpdateEntity<'a> {
     /// The entity to update.
@@ -62,12 +163,17 @@ pub struct UpdateEntity<'a> {
     pub set_properties: Vec<PropertyValue<'a>>,
     /// Clear values for these properties (optionally specific language for TEXT).
     pub unset_values: Vec<UnsetValue>,
+    /// Clear the values with these value IDs (see
+    /// [`PropertyValue::value_id`]), without re-sending them. A slot is
+    /// only cleared if its current value has the ID.
+    pub remove_values: Vec<Id>,
 }
 
 /// Specifies which language slot to clear for an UnsetValue.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum UnsetLanguage {
     /// Clear all language slots (wire format: 0xFFFFFFFF).
+    #[default]
     All,
     /// Clear only the English slot (wire format: 0).
     English,
@@ -75,12 +181,6 @@ pub enum UnsetLanguage {
     Specific(Id),
 }
 
-impl Default for UnsetLanguage {
-    fn default() -> Self {
-        Self::All
-    }
-}
-
 /// Specifies a value to unset, with optional language targeting (TEXT only).
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct UnsetValue {
@@ -117,12 +217,60 @@ impl<'a> UpdateEntity<'a> {
             id,
             set_properties: Vec::new(),
             unset_values: Vec::new(),
+            remove_values: Vec::new(),
         }
     }
 
     /// Returns true if this update has no actual changes.
     pub fn is_empty(&self) -> bool {
-        self.set_properties.is_empty() && self.unset_values.is_empty()
+        self.set_properties.is_empty()
+            && self.unset_values.is_empty()
+            && self.remove_values.is_empty()
+    }
+
+    /// Computes the minimal update that turns `before` into `after`.
+    ///
+    /// Both maps hold an entity's values keyed by (property, language), as
+    /// in `EntityState::values`. Only new or changed slots are set. Removed
+    /// slots are unset individually, or with a single [`UnsetLanguage::All`]
+    /// when a property loses every slot. The result is empty if the maps
+    /// are equal.
+    pub fn patch(
+        id: Id,
+        before: &BTreeMap<(Id, Option<Id>), Value<'_>>,
+        after: &BTreeMap<(Id, Option<Id>), Value<'a>>,
+    ) -> Self {
+        let mut unset_values = Vec::new();
+        let mut cleared: Option<Id> = None;
+        for (property, language) in before.keys() {
+            if after.contains_key(&(*property, *language)) || cleared == Some(*property) {
+                continue;
+            }
+            let mut rest = after.range((*property, None)..);
+            let kept = rest.next().is_some_and(|((p, _), _)| p == property);
+            let language = if !kept {
+                cleared = Some(*property);
+                UnsetLanguage::All
+            } else {
+                match language {
+                    Some(language) => UnsetLanguage::Specific(*language),
+                    None => UnsetLanguage::English,
+                }
+            };
+            unset_values.push(UnsetValue { property: *property, language });
+        }
+
+        let set_properties = after
+            .iter()
+            .filter(|(key, value)| before.get(key) != Some(*value))
+            .map(|((property, _), value)| PropertyValue {
+                property: *property,
+                value: value.clone(),
+                clock: None,
+            })
+            .collect();
+
+        Self { id, set_properties, unset_values, remove_values: Vec::new() }
     }
 }
 
@@ -295,6 +443,86 @@ pub struct CreateValueRef {
     pub space: Option<Id>,
 }
 
+/// Creates a space (spec Section 5.4).
+///
+/// The edit's authors become the space's first editors. Creating a space
+/// that already exists is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateSpace<'a> {
+    /// The space's unique identifier.
+    pub id: Id,
+    /// Display name of the space.
+    pub name: Cow<'a, str>,
+}
+
+/// The role a member holds in a space (spec Section 5.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum MemberRole {
+    /// Can vote on proposals.
+    Member = 0,
+    /// Can also publish edits to the space.
+    Editor = 1,
+}
+
+impl MemberRole {
+    /// Creates a MemberRole from its wire code.
+    pub fn from_u8(v: u8) -> Option<MemberRole> {
+        match v {
+            0 => Some(MemberRole::Member),
+            1 => Some(MemberRole::Editor),
+            _ => None,
+        }
+    }
+}
+
+/// Grants a role in a space (spec Section 5.4).
+///
+/// Ignored if the space does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddMember {
+    /// The space.
+    pub space: Id,
+    /// The account gaining the role.
+    pub member: Id,
+    /// The role granted.
+    pub role: MemberRole,
+}
+
+/// Revokes a role in a space (spec Section 5.4).
+///
+/// Revoking a role the member doesn't hold is a no-op.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveMember {
+    /// The space.
+    pub space: Id,
+    /// The account losing the role.
+    pub member: Id,
+    /// The role revoked.
+    pub role: MemberRole,
+}
+
+/// Updates the metadata of a space (spec Section 5.4).
+///
+/// Fields that are `None` are left unchanged; an empty description clears
+/// it. Ignored if the space does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateSpaceMetadata<'a> {
+    /// The space.
+    pub space: Id,
+    /// New display name.
+    pub name: Option<Cow<'a, str>>,
+    /// New description.
+    pub description: Option<Cow<'a, str>>,
+}
+
+impl UpdateSpaceMetadata<'_> {
+    /// Returns true if this update has no actual changes.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none()
+    }
+}
+
 /// Validates a position string according to spec rules.
 ///
 /// Position strings must:
@@ -334,6 +562,39 @@ mod tests {
         assert_eq!(Op::DeleteEntity(DeleteEntity { id: [0; 16] }).op_type(), 3);
     }
 
+    #[test]
+    fn test_update_entity_patch() {
+        let (name, age, en, es) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]);
+        let text = |value: &'static str, language| Value::Text {
+            value: Cow::Borrowed(value),
+            language,
+        };
+        let int = |value| Value::Int64 { value, unit: None };
+
+        let before = BTreeMap::from([
+            ((name, Some(en)), text("Alice", Some(en))),
+            ((name, Some(es)), text("Alicia", Some(es))),
+            ((age, None), int(30)),
+        ]);
+        let mut after = before.clone();
+        after.remove(&(name, Some(es)));
+        assert_eq!(
+            UpdateEntity::patch([9u8; 16], &before, &after).unset_values,
+            vec![UnsetValue::language(name, es)]
+        );
+
+        after.insert((age, None), int(31));
+        after.remove(&(name, Some(en)));
+        let patch = UpdateEntity::patch([9u8; 16], &before, &after);
+        assert_eq!(
+            patch.set_properties,
+            vec![PropertyValue { property: age, value: int(31), clock: None }]
+        );
+        assert_eq!(patch.unset_values, vec![UnsetValue::all(name)]);
+
+        assert!(UpdateEntity::patch([9u8; 16], &before, &before).is_empty());
+    }
+
     #[test]
     fn test_validate_position() {
         assert!(validate_position("abc123").is_ok());
@@ -366,6 +627,7 @@ mod tests {
         update2.set_properties.push(PropertyValue {
             property: [1; 16],
             value: crate::model::Value::Bool(true),
+            clock: None,
         });
         assert!(!update2.is_empty());
     }
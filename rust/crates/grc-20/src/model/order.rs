@@ -0,0 +1,254 @@
+//! Dependency ordering of an edit's ops.
+//!
+//! Ops apply in sequence (spec Section 4.2), and validators expect an op to
+//! come after the ops it depends on. [`dependency_order`] computes such an
+//! order:
+//!
+//! - An op that references an ID (a property, relation type, relation
+//!   endpoint, unit, language, or value ref entity) comes after the op in
+//!   the edit that creates it, so properties are created before use and
+//!   entities before the relations that reference them.
+//! - Ops acting on the same entity, relation, or value ref keep their
+//!   relative order, since later ones override earlier ones.
+//! - A delete that leaves its target deleted comes after every op that
+//!   references the target.
+//!
+//! The sort is stable: ops are otherwise kept in their original order, so
+//! an edit that is already ordered is left unchanged.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::error::OrderError;
+use crate::hash::FxHashMap;
+use crate::model::{Edit, Id, Op, PropertyValue, UnsetLanguage, Value};
+
+impl Edit<'_> {
+    /// Reorders ops into dependency order (see [`dependency_order`]).
+    ///
+    /// On a cycle the ops are left unchanged.
+    pub fn sort_ops_dependency_order(&mut self) -> Result<(), OrderError> {
+        let order = dependency_order(&self.ops)?;
+        let mut ops: Vec<_> = core::mem::take(&mut self.ops).into_iter().map(Some).collect();
+        self.ops = order.into_iter().filter_map(|i| ops[i].take()).collect();
+        Ok(())
+    }
+}
+
+/// Returns the op indices in dependency order.
+///
+/// Fails with [`OrderError::Cycle`] if ops depend on each other, e.g. two
+/// properties created with values for each other.
+pub fn dependency_order(ops: &[Op<'_>]) -> Result<Vec<usize>, OrderError> {
+    let mut graph = Graph::new(ops.len());
+
+    let mut creators: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut last_by_subject: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut last_lifecycle: FxHashMap<Id, usize> = FxHashMap::default();
+    for (i, op) in ops.iter().enumerate() {
+        for subject in subjects(op).into_iter().flatten() {
+            if let Some(prev) = last_by_subject.insert(subject, i) {
+                graph.edge(prev, i);
+            }
+            if !matches!(op, Op::UpdateEntity(_) | Op::UpdateRelation(_)) {
+                last_lifecycle.insert(subject, i);
+            }
+        }
+        let created = match op {
+            Op::CreateEntity(_) | Op::CreateRelation(_) | Op::CreateValueRef(_) => subjects(op),
+            _ => [None, None],
+        };
+        for id in created.into_iter().flatten() {
+            creators.entry(id).or_insert(i);
+        }
+    }
+
+    let mut referencing: FxHashMap<Id, Vec<usize>> = FxHashMap::default();
+    let mut ids = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        ids.clear();
+        references(op, &mut ids);
+        for id in &ids {
+            if let Some(&creator) = creators.get(id) {
+                graph.edge(creator, i);
+            }
+            referencing.entry(*id).or_default().push(i);
+        }
+    }
+
+    // Deletes that leave their target deleted go after its dependents
+    for (i, op) in ops.iter().enumerate() {
+        let target = match op {
+            Op::DeleteEntity(op) => op.id,
+            Op::DeleteRelation(op) => op.id,
+            _ => continue,
+        };
+        if last_lifecycle.get(&target) == Some(&i) {
+            for &dependent in referencing.get(&target).into_iter().flatten() {
+                graph.edge(dependent, i);
+            }
+        }
+    }
+
+    graph.sort()
+}
+
+/// IDs whose state the op changes.
+fn subjects(op: &Op<'_>) -> [Option<Id>; 2] {
+    match op {
+        Op::CreateEntity(op) => [Some(op.id), None],
+        Op::UpdateEntity(op) => [Some(op.id), None],
+        Op::DeleteEntity(op) => [Some(op.id), None],
+        Op::RestoreEntity(op) => [Some(op.id), None],
+        Op::CreateRelation(op) => [Some(op.id), Some(op.entity_id())],
+        Op::UpdateRelation(op) => [Some(op.id), None],
+        Op::DeleteRelation(op) => [Some(op.id), None],
+        Op::RestoreRelation(op) => [Some(op.id), None],
+        Op::CreateValueRef(op) => [Some(op.id), None],
+        Op::CreateSpace(op) => [Some(op.id), None],
+        Op::AddMember(op) => [Some(op.space), None],
+        Op::RemoveMember(op) => [Some(op.space), None],
+        Op::UpdateSpaceMetadata(op) => [Some(op.space), None],
+        Op::Unknown(_) => [None, None],
+    }
+}
+
+/// IDs the op refers to without changing their state.
+fn references(op: &Op<'_>, out: &mut Vec<Id>) {
+    match op {
+        Op::CreateEntity(op) => op.values.iter().for_each(|pv| value_references(pv, out)),
+        Op::UpdateEntity(op) => {
+            op.set_properties.iter().for_each(|pv| value_references(pv, out));
+            for unset in &op.unset_values {
+                out.push(unset.property);
+                if let UnsetLanguage::Specific(language) = unset.language {
+                    out.push(language);
+                }
+            }
+        }
+        Op::CreateRelation(op) => out.extend([op.relation_type, op.from, op.to]),
+        Op::CreateValueRef(op) => {
+            out.extend([op.entity, op.property]);
+            out.extend(op.language);
+        }
+        _ => {}
+    }
+}
+
+fn value_references(pv: &PropertyValue<'_>, out: &mut Vec<Id>) {
+    out.push(pv.property);
+    match &pv.value {
+        Value::Int64 { unit, .. } | Value::Float64 { unit, .. } | Value::Decimal { unit, .. } => {
+            out.extend(*unit)
+        }
+        Value::Text { language, .. } => out.extend(*language),
+        _ => {}
+    }
+}
+
+/// Op dependency graph: an edge `a -> b` means op `a` must precede op `b`.
+struct Graph {
+    succs: Vec<Vec<usize>>,
+    preds: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new(len: usize) -> Self {
+        Self { succs: vec![Vec::new(); len], preds: vec![Vec::new(); len] }
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        if from != to {
+            self.succs[from].push(to);
+            self.preds[to].push(from);
+        }
+    }
+
+    /// Kahn's algorithm, always taking the lowest ready index.
+    fn sort(&self) -> Result<Vec<usize>, OrderError> {
+        let len = self.succs.len();
+        let mut in_degree: Vec<usize> = self.preds.iter().map(Vec::len).collect();
+        let mut ready: BinaryHeap<_> =
+            (0..len).filter(|&i| in_degree[i] == 0).map(Reverse).collect();
+        let mut order = Vec::with_capacity(len);
+        while let Some(Reverse(i)) = ready.pop() {
+            order.push(i);
+            for &next in &self.succs[i] {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    ready.push(Reverse(next));
+                }
+            }
+        }
+        if order.len() == len {
+            return Ok(order);
+        }
+
+        // Every unsorted op has an unsorted predecessor; walking back
+        // through them must revisit an op, closing a cycle
+        let mut path = Vec::new();
+        let mut seen = vec![None; len];
+        let mut op = (0..len).find(|&i| in_degree[i] > 0).expect("unsorted op");
+        while seen[op].is_none() {
+            seen[op] = Some(path.len());
+            path.push(op);
+            op = *self.preds[op].iter().find(|&&p| in_degree[p] > 0).expect("unsorted pred");
+        }
+        let mut ops = path.split_off(seen[op].unwrap_or_default());
+        ops.reverse();
+        let first = (0..ops.len()).min_by_key(|&i| ops[i]).unwrap_or_default();
+        ops.rotate_left(first);
+        Err(OrderError::Cycle { ops })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{relation_entity_id, EditBuilder};
+
+    #[test]
+    fn test_sort_ops_dependency_order() {
+        let (alice, bob, knows, age, rel) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16], [5u8; 16]);
+        let mut edit = EditBuilder::new([0u8; 16])
+            .delete_entity(bob)
+            .create_relation_simple(rel, alice, bob, knows)
+            .update_entity(relation_entity_id(&rel), |u| u.set_int64(age, 3, None))
+            .create_entity(alice, |e| e.int64(age, 30, None))
+            .update_entity(alice, |u| u.set_int64(age, 31, None))
+            .create_entity(age, |e| e)
+            .create_entity(knows, |e| e)
+            .build();
+        edit.sort_ops_dependency_order().unwrap();
+
+        let position = |pred: &dyn Fn(&Op<'_>) -> bool| edit.ops.iter().position(pred).unwrap();
+        let create = |id: Id| position(&|op| matches!(op, Op::CreateEntity(c) if c.id == id));
+        let relation = position(&|op| matches!(op, Op::CreateRelation(_)));
+        let delete = position(&|op| matches!(op, Op::DeleteEntity(_)));
+        let update = position(&|op| matches!(op, Op::UpdateEntity(u) if u.id == alice));
+        let rel_update =
+            position(&|op| matches!(op, Op::UpdateEntity(u) if u.id == relation_entity_id(&rel)));
+        assert!(create(age) < create(alice) && create(alice) < update);
+        assert!(create(knows) < relation && create(alice) < relation);
+        assert!(relation < rel_update && create(age) < rel_update);
+        // Bob ends deleted, so the delete follows the relation to him
+        assert!(relation < delete);
+
+        // Already ordered: unchanged
+        let sorted = edit.ops.clone();
+        edit.sort_ops_dependency_order().unwrap();
+        assert_eq!(edit.ops, sorted);
+
+        // Two properties created with values for each other
+        let mut cyclic = EditBuilder::new([0u8; 16])
+            .create_entity([9u8; 16], |e| e.text([6u8; 16], "a", None))
+            .create_entity([7u8; 16], |e| e)
+            .create_entity([6u8; 16], |e| e.text([9u8; 16], "b", None))
+            .build();
+        let before = cyclic.ops.clone();
+        assert_eq!(cyclic.sort_ops_dependency_order(), Err(OrderError::Cycle { ops: vec![0, 2] }));
+        assert_eq!(cyclic.ops, before);
+    }
+}
@@ -0,0 +1,267 @@
+//! Fractional-indexing positions for ordered relations.
+//!
+//! Positions are strings over `0-9A-Za-z` compared in ASCII order, so a new
+//! item can always be placed between two others by picking a string that
+//! sorts between them (spec Section 4.3):
+//!
+//! ```text
+//! position_between(None, None)             = "a"
+//! position_between(Some("a"), Some("z"))   = "n"
+//! position_between(Some("a"), Some("b"))   = "aV"
+//! ```
+//!
+//! Generated positions never end in `0`, which keeps room for inserting
+//! before them. When two positions are too close for a midpoint of at most
+//! 64 characters, the spec requires the client to reorder the relations
+//! explicitly with new, evenly spaced positions. [`needs_rebalance`] flags
+//! positions that are getting long, and [`evenly_spaced`] and
+//! [`rebalance_ops`] generate the replacement positions.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::PositionError;
+use crate::model::op::validate_position;
+use crate::model::{Id, UpdateRelation};
+
+/// The 62 position digits, in ASCII order.
+const DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Maximum position length (spec Section 4.3).
+pub const MAX_POSITION_LEN: usize = 64;
+
+/// Length from which [`needs_rebalance`] recommends reordering, leaving
+/// room for a few more inserts before [`MAX_POSITION_LEN`] is reached.
+pub const REBALANCE_POSITION_LEN: usize = 48;
+
+/// Returns a position that sorts strictly between `before` and `after`.
+///
+/// `None` stands for the start (`before`) or the end (`after`) of the
+/// list, so `(None, None)` gives the first item's position, `(Some(last),
+/// None)` appends, and `(None, Some(first))` prepends.
+pub fn position_between(
+    before: Option<&str>,
+    after: Option<&str>,
+) -> Result<String, PositionError> {
+    for position in [before, after].into_iter().flatten() {
+        validate_position(position).map_err(|reason| PositionError::Invalid { reason })?;
+    }
+    if let (Some(before), Some(after)) = (before, after) {
+        if before >= after {
+            return Err(PositionError::NotOrdered);
+        }
+    }
+    if before.is_none() && after.is_none() {
+        return Ok(String::from("a"));
+    }
+
+    let digits = |s: &str| s.bytes().map(digit_value).collect::<Vec<u8>>();
+    let before = before.map(digits).unwrap_or_default();
+    let after = after.map(digits);
+    let mid = midpoint(&before, after.as_deref()).ok_or(PositionError::NoRoom)?;
+    if mid.len() > MAX_POSITION_LEN {
+        return Err(PositionError::TooLong);
+    }
+    Ok(mid.into_iter().map(|d| DIGITS[d as usize] as char).collect())
+}
+
+/// Returns a position that sorts strictly between `a` and `b`.
+///
+/// Shorthand for [`position_between`] when the inputs are known to be
+/// valid and ordered.
+///
+/// # Panics
+///
+/// Panics if [`position_between`] fails: an input is invalid, `a >= b`, or
+/// the positions are too close. Check [`needs_rebalance`] on the neighbors
+/// first to reorder before the limit is reached.
+pub fn between(a: Option<&str>, b: Option<&str>) -> String {
+    match position_between(a, b) {
+        Ok(position) => position,
+        Err(e) => panic!("position::between({a:?}, {b:?}): {e}"),
+    }
+}
+
+/// Returns true if `position` is long enough that the list should be
+/// reordered with [`evenly_spaced`] positions.
+pub fn needs_rebalance(position: &str) -> bool {
+    position.len() >= REBALANCE_POSITION_LEN
+}
+
+/// Returns `count` ascending positions, evenly spaced over the whole
+/// range and as short as possible.
+pub fn evenly_spaced(count: usize) -> Vec<String> {
+    // Positions are `len`-digit numbers spaced by at least 1
+    let mut len = 1;
+    let mut range: u128 = 62;
+    while range <= count as u128 {
+        len += 1;
+        range *= 62;
+    }
+    (1..=count as u128)
+        .map(|i| {
+            let mut n = i * range / (count as u128 + 1);
+            let mut digits = vec![0u8; len];
+            for d in digits.iter_mut().rev() {
+                *d = DIGITS[(n % 62) as usize];
+                n /= 62;
+            }
+            while digits.last() == Some(&b'0') {
+                digits.pop();
+            }
+            digits.into_iter().map(char::from).collect()
+        })
+        .collect()
+}
+
+/// Returns UpdateRelation ops giving `relations`, in order, evenly spaced
+/// positions.
+///
+/// This is the explicit reordering the spec requires when positions get
+/// too close.
+pub fn rebalance_ops(relations: &[Id]) -> Vec<UpdateRelation<'static>> {
+    relations
+        .iter()
+        .zip(evenly_spaced(relations.len()))
+        .map(|(id, position)| UpdateRelation {
+            id: *id,
+            from_space: None,
+            from_version: None,
+            to_space: None,
+            to_version: None,
+            position: Some(position.into()),
+            unset: Vec::new(),
+        })
+        .collect()
+}
+
+fn digit_value(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' => byte - b'0',
+        b'A'..=b'Z' => byte - b'A' + 10,
+        _ => byte - b'a' + 36,
+    }
+}
+
+/// Returns digits that sort strictly between `a` and `b` (the end if
+/// None), given `a < b`. Returns None if no such string exists, which
+/// happens when `b` is `a` followed by zeros.
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Option<Vec<u8>> {
+    if let Some(b) = b {
+        let common = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        if common > 0 {
+            let mut out = b[..common].to_vec();
+            out.extend(midpoint(&a[common..], Some(&b[common..]))?);
+            return Some(out);
+        }
+    }
+    // The first digits differ, or `a` is empty
+    let high = b.and_then(|b| b.first()).map_or(62, |d| *d as usize);
+    match a.first() {
+        Some(&low) => {
+            let low = low as usize;
+            if high - low > 1 {
+                Some(vec![(low + high).div_ceil(2) as u8])
+            } else if b.is_some_and(|b| b.len() > 1) {
+                // `b`'s first digit alone sorts between them
+                Some(vec![high as u8])
+            } else {
+                let mut out = vec![low as u8];
+                out.extend(midpoint(&a[1..], None)?);
+                Some(out)
+            }
+        }
+        None if high > 1 => Some(vec![high.div_ceil(2) as u8]),
+        // Nothing sorts below "0", so go one digit deeper
+        None => {
+            let rest = match b?.split_first() {
+                Some((1, _)) => None,
+                Some((_, rest)) if !rest.is_empty() => Some(rest),
+                _ => return None,
+            };
+            let mut out = vec![0];
+            out.extend(midpoint(&[], rest)?);
+            Some(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_examples() {
+        assert_eq!(between(None, None), "a");
+        assert_eq!(between(Some("a"), Some("z")), "n");
+        assert_eq!(between(Some("a"), Some("b")), "aV");
+        assert_eq!(between(Some("a"), None), "n");
+        assert_eq!(between(None, Some("a")), "I");
+        assert_eq!(between(Some("z"), None), "zV");
+        assert_eq!(between(None, Some("1")), "0V");
+        assert_eq!(between(None, Some("01")), "00V");
+        assert_eq!(between(Some("a"), Some("a1")), "a0V");
+    }
+
+    #[test]
+    fn test_repeated_inserts_stay_ordered() {
+        // Always inserting right after the first item narrows the gap fastest
+        let mut positions = vec![between(None, None), between(Some("a"), None)];
+        for _ in 0..40 {
+            let mid = between(Some(&positions[0]), Some(&positions[1]));
+            assert!(positions[0] < mid && mid < positions[1]);
+            assert!(!mid.ends_with('0'));
+            positions.insert(1, mid);
+        }
+        let mut sorted = positions.clone();
+        sorted.sort();
+        assert_eq!(sorted, positions);
+    }
+
+    #[test]
+    fn test_evenly_spaced() {
+        assert_eq!(evenly_spaced(0), Vec::<String>::new());
+        assert_eq!(evenly_spaced(1), vec!["V"]);
+        assert_eq!(evenly_spaced(3), vec!["F", "V", "k"]);
+        for count in [61, 62, 1000] {
+            let positions = evenly_spaced(count);
+            assert_eq!(positions.len(), count);
+            assert!(positions.windows(2).all(|w| w[0] < w[1]));
+            assert!(positions.iter().all(|p| validate_position(p).is_ok() && !p.ends_with('0')));
+            assert!(positions.iter().all(|p| p.len() <= if count < 62 { 1 } else { 2 }));
+        }
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let crowded = format!("{}1", "a".repeat(REBALANCE_POSITION_LEN - 1));
+        assert!(needs_rebalance(&crowded));
+        assert!(!needs_rebalance(&between(None, Some(&crowded))));
+
+        let ops = rebalance_ops(&[[1u8; 16], [2u8; 16]]);
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].id, [1u8; 16]);
+        assert_eq!(ops[0].position.as_deref(), Some("K"));
+        assert_eq!(ops[1].position.as_deref(), Some("f"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ascending order")]
+    fn test_between_panics_on_unordered() {
+        between(Some("b"), Some("a"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(position_between(Some("b"), Some("a")), Err(PositionError::NotOrdered));
+        assert_eq!(position_between(Some("a"), Some("a")), Err(PositionError::NotOrdered));
+        assert!(matches!(position_between(Some("a-"), None), Err(PositionError::Invalid { .. })));
+        assert_eq!(position_between(Some("a"), Some("a0")), Err(PositionError::NoRoom));
+        assert_eq!(position_between(None, Some("0")), Err(PositionError::NoRoom));
+        let low = format!("{}1", "a".repeat(63));
+        let high = format!("{}2", "a".repeat(63));
+        assert_eq!(position_between(Some(&low), Some(&high)), Err(PositionError::TooLong));
+        assert_eq!(position_between(Some(&"z".repeat(64)), None), Err(PositionError::TooLong));
+    }
+}
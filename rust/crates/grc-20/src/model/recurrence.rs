@@ -0,0 +1,889 @@
+//! Recurrence evaluation for SCHEDULE values.
+//!
+//! Builds on the RFC 5545 validator in [`crate::validate::schedule`] to
+//! expand a schedule into concrete occurrence start times. Times are epoch
+//! microseconds (UTC), matching [`crate::model::timestamp`].
+//!
+//! Supported subset:
+//! - DTSTART as DATE or DATE-TIME
+//! - RRULE with FREQ, INTERVAL, COUNT, UNTIL, BYMONTH, BYMONTHDAY, BYDAY
+//!   (with ordinals for MONTHLY/YEARLY), BYHOUR, BYMINUTE, BYSECOND,
+//!   BYSETPOS, WKST
+//! - RDATE and EXDATE lists
+//!
+//! TZID parameters and floating times are evaluated as UTC since no zone
+//! database is available. Rules using BYYEARDAY or BYWEEKNO are rejected.
+
+use std::collections::HashMap;
+
+use crate::error::RecurrenceError;
+use crate::model::timestamp::{civil_from_days, days_from_civil, days_in_month};
+use crate::model::Id;
+use crate::validate::schedule::{parse_content_line, unfold, validate_schedule, ScheduleIssue};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Upper bound on recurrence periods visited per query.
+///
+/// Guards against rules that can never produce an occurrence
+/// (e.g. `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`). Rules without COUNT start
+/// at the first period that can reach the query, so the bound applies to
+/// the periods actually searched; queries that exceed it fail with
+/// [`RecurrenceError::TooManyPeriods`].
+pub const MAX_RECURRENCE_PERIODS: usize = 500_000;
+
+/// Recurrence frequency (RFC 5545 FREQ).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A parsed RRULE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<i64>,
+    pub by_month: Vec<u32>,
+    pub by_month_day: Vec<i32>,
+    /// (ordinal, weekday) pairs; ordinal 0 means every such weekday.
+    /// Weekdays are 0 = Monday through 6 = Sunday.
+    pub by_day: Vec<(i32, u32)>,
+    pub by_hour: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_second: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    /// Week start, 0 = Monday.
+    pub wkst: u32,
+}
+
+/// The recurrence described by a SCHEDULE value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    /// First occurrence (DTSTART).
+    pub start: i64,
+    /// Recurrence rule, if any.
+    pub rule: Option<RecurrenceRule>,
+    /// Additional occurrences (RDATE), sorted.
+    pub rdates: Vec<i64>,
+    /// Excluded occurrences (EXDATE), sorted.
+    pub exdates: Vec<i64>,
+}
+
+/// Returns occurrence start times in `[start, end)` for a SCHEDULE value.
+pub fn occurrences_between(schedule: &str, start: i64, end: i64) -> Result<Vec<i64>, RecurrenceError> {
+    Recurrence::parse(schedule)?.occurrences_between(start, end)
+}
+
+/// Returns the first occurrence strictly after `after` for a SCHEDULE value.
+pub fn next_occurrence(schedule: &str, after: i64) -> Result<Option<i64>, RecurrenceError> {
+    Recurrence::parse(schedule)?.next_occurrence(after)
+}
+
+impl Recurrence {
+    /// Parses a SCHEDULE value.
+    ///
+    /// The schedule is validated first. Only the first component with a
+    /// DTSTART is evaluated.
+    pub fn parse(schedule: &str) -> Result<Self, ScheduleIssue> {
+        validate_schedule(schedule, true)?;
+
+        let unfolded = unfold(schedule);
+        let mut start = None;
+        let mut rule = None;
+        let mut rdates = Vec::new();
+        let mut exdates = Vec::new();
+        let mut depth = 0usize;
+        let mut start_depth = None;
+
+        for (i, raw) in unfolded.split('\n').enumerate() {
+            let raw = raw.strip_suffix('\r').unwrap_or(raw);
+            if raw.is_empty() {
+                continue;
+            }
+            let line = parse_content_line(raw).map_err(|reason| ScheduleIssue { line: i + 1, reason })?;
+            let issue = |reason| ScheduleIssue { line: i + 1, reason };
+            let name = line.name.to_ascii_uppercase();
+            match name.as_str() {
+                "BEGIN" => depth += 1,
+                "END" => {
+                    if start_depth == Some(depth) {
+                        break;
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                _ if start_depth.is_some_and(|d| d != depth) => {}
+                "DTSTART" => {
+                    start = Some(parse_ical_time(line.value).ok_or(issue("malformed DTSTART"))?);
+                    start_depth = Some(depth);
+                }
+                "RRULE" if rule.is_none() => {
+                    rule = Some(parse_rule(line.value).map_err(issue)?);
+                }
+                "RDATE" | "EXDATE" => {
+                    let target = if name == "RDATE" { &mut rdates } else { &mut exdates };
+                    for item in line.value.split(',') {
+                        // For RDATE periods, the start of the period is the occurrence
+                        let item = item.split('/').next().unwrap_or(item);
+                        target.push(parse_ical_time(item).ok_or(issue("malformed date list"))?);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let start = start.ok_or(ScheduleIssue { line: 1, reason: "schedule has no DTSTART" })?;
+        rdates.sort_unstable();
+        exdates.sort_unstable();
+        Ok(Self { start, rule, rdates, exdates })
+    }
+
+    /// Returns occurrence start times in `[start, end)`.
+    pub fn occurrences_between(&self, start: i64, end: i64) -> Result<Vec<i64>, RecurrenceError> {
+        let mut out = Vec::new();
+        self.for_each(start, Some(end), |t| {
+            if t >= end {
+                return false;
+            }
+            if t >= start {
+                out.push(t);
+            }
+            true
+        })?;
+        Ok(out)
+    }
+
+    /// Returns the first occurrence strictly after `after`.
+    pub fn next_occurrence(&self, after: i64) -> Result<Option<i64>, RecurrenceError> {
+        let mut found = None;
+        self.for_each(after, None, |t| {
+            if t > after {
+                found = Some(t);
+                return false;
+            }
+            true
+        })?;
+        Ok(found)
+    }
+
+    /// Returns true if any occurrence falls in `[start, end)`, stopping at
+    /// the first one found.
+    pub fn occurs_between(&self, start: i64, end: i64) -> Result<bool, RecurrenceError> {
+        let mut found = false;
+        self.for_each(start, Some(end), |t| {
+            found = t >= start && t < end;
+            !found && t < end
+        })?;
+        Ok(found)
+    }
+
+    /// Returns the last possible occurrence, or None if unbounded.
+    ///
+    /// COUNT rules too long to expand within [`MAX_RECURRENCE_PERIODS`] are
+    /// reported as unbounded.
+    pub fn end_bound(&self) -> Option<i64> {
+        let rdate_max = self.rdates.last().copied().unwrap_or(self.start).max(self.start);
+        match &self.rule {
+            None => Some(rdate_max),
+            Some(rule) if rule.until.is_some() => rule.until.map(|u| u.max(rdate_max)),
+            Some(rule) if rule.count.is_some() => {
+                let mut last = rdate_max;
+                self.for_each(self.start, None, |t| {
+                    last = last.max(t);
+                    true
+                })
+                .ok()?;
+                Some(last)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Visits occurrences in ascending order until `visit` returns false.
+    ///
+    /// Occurrences before `from` may be skipped, and expansion ends once
+    /// the rule passes `to`.
+    fn for_each(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        mut visit: impl FnMut(i64) -> bool,
+    ) -> Result<(), RecurrenceError> {
+        let mut rdates = self.rdates.iter().copied().peekable();
+        let mut last = None;
+        let mut emit = |t: i64, visit: &mut dyn FnMut(i64) -> bool| -> bool {
+            if last == Some(t) || self.exdates.binary_search(&t).is_ok() {
+                return true;
+            }
+            last = Some(t);
+            visit(t)
+        };
+
+        let mut keep_going = true;
+        self.for_each_rule_occurrence(from, to, |t| {
+            while let Some(&r) = rdates.peek() {
+                if r > t {
+                    break;
+                }
+                rdates.next();
+                if !emit(r, &mut visit) {
+                    keep_going = false;
+                    return false;
+                }
+            }
+            keep_going = emit(t, &mut visit);
+            keep_going
+        })?;
+        if keep_going {
+            for r in rdates {
+                if !emit(r, &mut visit) {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Visits DTSTART and the RRULE expansion in ascending order.
+    ///
+    /// Without COUNT, expansion starts at the first period that can hold a
+    /// time at or after `from`. With `to`, it stops at the first period
+    /// starting after `to`.
+    fn for_each_rule_occurrence(
+        &self,
+        from: i64,
+        to: Option<i64>,
+        mut visit: impl FnMut(i64) -> bool,
+    ) -> Result<(), RecurrenceError> {
+        if !visit(self.start) {
+            return Ok(());
+        }
+        let Some(rule) = &self.rule else {
+            return Ok(());
+        };
+
+        // DTSTART counts as the first instance
+        let mut emitted = 1u32;
+        let start_seconds = self.start.div_euclid(MICROS_PER_SECOND);
+        let start_day = start_seconds.div_euclid(SECONDS_PER_DAY);
+        let start_frac = self.start.rem_euclid(MICROS_PER_SECOND);
+
+        // COUNT needs every earlier instance, so only open rules skip ahead
+        let first = match rule.count {
+            None => rule.first_period(start_seconds, start_day, from.div_euclid(MICROS_PER_SECOND)),
+            Some(_) => 0,
+        };
+        let to_seconds = to.map(|to| to.div_euclid(MICROS_PER_SECOND));
+
+        for period in (first..).take(MAX_RECURRENCE_PERIODS) {
+            if let Some(to_seconds) = to_seconds {
+                match rule.period_start(start_seconds, start_day, period) {
+                    Some(s) if s <= to_seconds => {}
+                    _ => return Ok(()),
+                }
+            }
+            let Some(candidates) = rule.period_candidates(start_seconds, start_day, period) else {
+                return Ok(());
+            };
+            for seconds in candidates {
+                let t = seconds * MICROS_PER_SECOND + start_frac;
+                if t <= self.start {
+                    continue;
+                }
+                if rule.until.is_some_and(|u| t > u) || rule.count.is_some_and(|c| emitted >= c) {
+                    return Ok(());
+                }
+                emitted += 1;
+                if !visit(t) {
+                    return Ok(());
+                }
+            }
+        }
+        Err(RecurrenceError::TooManyPeriods { max: MAX_RECURRENCE_PERIODS })
+    }
+}
+
+impl RecurrenceRule {
+    /// Returns the sorted candidate times (epoch seconds) for the nth period,
+    /// or None when the period is out of range.
+    fn period_candidates(&self, start_seconds: i64, start_day: i64, n: i64) -> Option<Vec<i64>> {
+        let step = n.checked_mul(self.interval as i64)?;
+        let (start_year, start_month, start_dom) = civil_from_days(start_day);
+        let start_tod = start_seconds.rem_euclid(SECONDS_PER_DAY);
+
+        let mut candidates: Vec<i64> = match self.freq {
+            Frequency::Secondly | Frequency::Minutely | Frequency::Hourly => {
+                let unit = match self.freq {
+                    Frequency::Secondly => 1,
+                    Frequency::Minutely => 60,
+                    _ => 3600,
+                };
+                let t = start_seconds.checked_add(step.checked_mul(unit)?)?;
+                let day = t.div_euclid(SECONDS_PER_DAY);
+                let tod = t.rem_euclid(SECONDS_PER_DAY);
+                if self.day_matches(day) && self.time_matches(tod) {
+                    vec![t]
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Daily => {
+                let day = start_day.checked_add(step)?;
+                if self.day_matches(day) {
+                    self.expand_times(&[day], start_tod)
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Weekly => {
+                let offset = (weekday(start_day) + 7 - self.wkst) % 7;
+                let week_start = start_day - offset as i64 + step.checked_mul(7)?;
+                let days: Vec<i64> = (week_start..week_start + 7)
+                    .filter(|&d| {
+                        let wd = weekday(d);
+                        let day_ok = if self.by_day.is_empty() {
+                            wd == weekday(start_day)
+                        } else {
+                            self.by_day.iter().any(|&(_, w)| w == wd)
+                        };
+                        day_ok && self.month_matches(d)
+                    })
+                    .collect();
+                self.expand_times(&days, start_tod)
+            }
+            Frequency::Monthly => {
+                let abs_month = start_year * 12 + start_month as i64 - 1 + step;
+                let (year, month) = (abs_month.div_euclid(12), abs_month.rem_euclid(12) as u32 + 1);
+                if !self.by_month.is_empty() && !self.by_month.contains(&month) {
+                    vec![]
+                } else {
+                    let days = self.month_days(year, month, start_dom);
+                    self.expand_times(&days, start_tod)
+                }
+            }
+            Frequency::Yearly => {
+                let year = start_year.checked_add(step)?;
+                let days = self.year_days(year, start_month, start_dom);
+                self.expand_times(&days, start_tod)
+            }
+        };
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        if !self.by_set_pos.is_empty() {
+            candidates = select_positions(&candidates, &self.by_set_pos);
+        }
+        if let Some(until) = self.until {
+            // Stop once a whole period starts past UNTIL
+            if candidates.is_empty() && self.period_start(start_seconds, start_day, n)? > until / MICROS_PER_SECOND {
+                return None;
+            }
+        }
+        Some(candidates)
+    }
+
+    /// Returns a period no later than the first one that can hold a time at
+    /// or after `from` (epoch seconds).
+    fn first_period(&self, start_seconds: i64, start_day: i64, from: i64) -> i64 {
+        let starts_by = |n| self.period_start(start_seconds, start_day, n).is_some_and(|s| s <= from);
+        if !starts_by(1) {
+            return 0;
+        }
+        // Bracket the last period starting by `from`, then bisect
+        let (mut lo, mut hi) = (1i64, 2i64);
+        while starts_by(hi) {
+            lo = hi;
+            match hi.checked_mul(2) {
+                Some(next) => hi = next,
+                None => return lo,
+            }
+        }
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if starts_by(mid) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        // Weekly period starts are a week early, so step back one more
+        lo - 1
+    }
+
+    /// Returns the earliest possible time (epoch seconds) in the nth period.
+    fn period_start(&self, start_seconds: i64, start_day: i64, n: i64) -> Option<i64> {
+        let step = n.checked_mul(self.interval as i64)?;
+        let (year, month, _) = civil_from_days(start_day);
+        let day = match self.freq {
+            Frequency::Secondly => return start_seconds.checked_add(step),
+            Frequency::Minutely => return start_seconds.checked_add(step.checked_mul(60)?),
+            Frequency::Hourly => return start_seconds.checked_add(step.checked_mul(3600)?),
+            Frequency::Daily => start_day.checked_add(step)?,
+            Frequency::Weekly => start_day.checked_add(step.checked_mul(7)?)? - 7,
+            Frequency::Monthly => {
+                let abs_month = (year * 12 + month as i64 - 1).checked_add(step)?;
+                days_from_civil(abs_month.div_euclid(12), abs_month.rem_euclid(12) as u32 + 1, 1)
+            }
+            Frequency::Yearly => days_from_civil(year.checked_add(step)?, 1, 1),
+        };
+        day.checked_mul(SECONDS_PER_DAY)
+    }
+
+    /// Days in a month selected by BYMONTHDAY/BYDAY (MONTHLY, or YEARLY with BYMONTH).
+    fn month_days(&self, year: i64, month: u32, default_dom: u32) -> Vec<i64> {
+        let len = days_in_month(year, month);
+        let first = days_from_civil(year, month, 1);
+        let all: Vec<i64> = (first..first + len as i64).collect();
+
+        let by_month_day: Vec<i64> = self
+            .by_month_day
+            .iter()
+            .filter_map(|&d| {
+                let dom = if d > 0 { d } else { len as i32 + d + 1 };
+                (1..=len as i32).contains(&dom).then(|| first + dom as i64 - 1)
+            })
+            .collect();
+
+        match (self.by_month_day.is_empty(), self.by_day.is_empty()) {
+            (true, true) => {
+                if default_dom <= len {
+                    vec![first + default_dom as i64 - 1]
+                } else {
+                    vec![]
+                }
+            }
+            (false, true) => by_month_day,
+            (true, false) => expand_by_day(&all, &self.by_day),
+            (false, false) => {
+                let by_day = expand_by_day(&all, &self.by_day);
+                by_month_day.into_iter().filter(|d| by_day.contains(d)).collect()
+            }
+        }
+    }
+
+    /// Days in a year for YEARLY rules.
+    fn year_days(&self, year: i64, default_month: u32, default_dom: u32) -> Vec<i64> {
+        if !self.by_month.is_empty() {
+            return self
+                .by_month
+                .iter()
+                .flat_map(|&m| self.month_days(year, m, default_dom))
+                .collect();
+        }
+        if !self.by_month_day.is_empty() {
+            return (1..=12).flat_map(|m| self.month_days(year, m, default_dom)).collect();
+        }
+        if !self.by_day.is_empty() {
+            let first = days_from_civil(year, 1, 1);
+            let last = days_from_civil(year + 1, 1, 1);
+            let all: Vec<i64> = (first..last).collect();
+            return expand_by_day(&all, &self.by_day);
+        }
+        if default_dom <= days_in_month(year, default_month) {
+            vec![days_from_civil(year, default_month, default_dom)]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Expands days into candidate times using BYHOUR/BYMINUTE/BYSECOND.
+    fn expand_times(&self, days: &[i64], start_tod: i64) -> Vec<i64> {
+        let pick = |by: &[u32], default: i64| -> Vec<i64> {
+            if by.is_empty() {
+                vec![default]
+            } else {
+                by.iter().map(|&v| v as i64).collect()
+            }
+        };
+        let hours = pick(&self.by_hour, start_tod / 3600);
+        let minutes = pick(&self.by_minute, (start_tod / 60) % 60);
+        let seconds = pick(&self.by_second, start_tod % 60);
+
+        let mut out = Vec::with_capacity(days.len() * hours.len() * minutes.len() * seconds.len());
+        for &day in days {
+            for &h in &hours {
+                for &m in &minutes {
+                    for &s in &seconds {
+                        out.push(day * SECONDS_PER_DAY + h * 3600 + m * 60 + s);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn month_matches(&self, day: i64) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&civil_from_days(day).1)
+    }
+
+    /// Filters a single day by BYMONTH, BYMONTHDAY, and BYDAY (weekday only).
+    fn day_matches(&self, day: i64) -> bool {
+        let (year, month, dom) = civil_from_days(day);
+        let len = days_in_month(year, month) as i32;
+        let dom_ok = self.by_month_day.is_empty()
+            || self
+                .by_month_day
+                .iter()
+                .any(|&d| d == dom as i32 || d == dom as i32 - len - 1);
+        let wd = weekday(day);
+        let day_ok = self.by_day.is_empty() || self.by_day.iter().any(|&(_, w)| w == wd);
+        self.month_matches(day) && dom_ok && day_ok
+    }
+
+    /// Filters a time of day by BYHOUR, BYMINUTE, and BYSECOND.
+    fn time_matches(&self, tod: i64) -> bool {
+        let matches = |by: &[u32], v: i64| by.is_empty() || by.contains(&(v as u32));
+        matches(&self.by_hour, tod / 3600)
+            && matches(&self.by_minute, (tod / 60) % 60)
+            && matches(&self.by_second, tod % 60)
+    }
+}
+
+/// Selects days matching BYDAY entries, with ordinals relative to `days`.
+fn expand_by_day(days: &[i64], by_day: &[(i32, u32)]) -> Vec<i64> {
+    let mut out = Vec::new();
+    for &(ordinal, wd) in by_day {
+        let matching: Vec<i64> = days.iter().copied().filter(|&d| weekday(d) == wd).collect();
+        match ordinal {
+            0 => out.extend(matching),
+            n if n > 0 => out.extend(matching.get(n as usize - 1)),
+            n => {
+                let idx = matching.len() as i64 + n as i64;
+                if idx >= 0 {
+                    out.push(matching[idx as usize]);
+                }
+            }
+        }
+    }
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Applies BYSETPOS to a sorted candidate list.
+fn select_positions(candidates: &[i64], positions: &[i32]) -> Vec<i64> {
+    let len = candidates.len() as i64;
+    let mut out: Vec<i64> = positions
+        .iter()
+        .filter_map(|&p| {
+            let idx = if p > 0 { p as i64 - 1 } else { len + p as i64 };
+            (0..len).contains(&idx).then(|| candidates[idx as usize])
+        })
+        .collect();
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Returns the weekday of a day number, 0 = Monday.
+fn weekday(day: i64) -> u32 {
+    // 1970-01-01 was a Thursday
+    (day + 3).rem_euclid(7) as u32
+}
+
+fn parse_weekday(s: &str) -> Option<u32> {
+    ["MO", "TU", "WE", "TH", "FR", "SA", "SU"]
+        .iter()
+        .position(|d| d.eq_ignore_ascii_case(s))
+        .map(|i| i as u32)
+}
+
+/// Parses an iCalendar DATE or DATE-TIME into epoch microseconds.
+fn parse_ical_time(value: &str) -> Option<i64> {
+    let (date, time) = match value.split_once('T') {
+        Some((d, t)) => (d, Some(t.strip_suffix('Z').unwrap_or(t))),
+        None => (value, None),
+    };
+    if date.len() != 8 {
+        return None;
+    }
+    let year: i64 = date[0..4].parse().ok()?;
+    let month: u32 = date[4..6].parse().ok()?;
+    let day: u32 = date[6..8].parse().ok()?;
+    let mut seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY;
+    if let Some(time) = time {
+        if time.len() != 6 {
+            return None;
+        }
+        let h: i64 = time[0..2].parse().ok()?;
+        let m: i64 = time[2..4].parse().ok()?;
+        let s: i64 = time[4..6].parse().ok()?;
+        seconds += h * 3600 + m * 60 + s;
+    }
+    Some(seconds * MICROS_PER_SECOND)
+}
+
+/// Parses an already-validated RRULE value.
+fn parse_rule(value: &str) -> Result<RecurrenceRule, &'static str> {
+    let mut rule = RecurrenceRule {
+        freq: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_month: Vec::new(),
+        by_month_day: Vec::new(),
+        by_day: Vec::new(),
+        by_hour: Vec::new(),
+        by_minute: Vec::new(),
+        by_second: Vec::new(),
+        by_set_pos: Vec::new(),
+        wkst: 0,
+    };
+
+    let ints = |v: &str| -> Result<Vec<i32>, &'static str> {
+        v.split(',').map(|x| x.parse().map_err(|_| "malformed RRULE list")).collect()
+    };
+    let uints = |v: &str| -> Result<Vec<u32>, &'static str> {
+        v.split(',').map(|x| x.parse().map_err(|_| "malformed RRULE list")).collect()
+    };
+
+    for part in value.split(';') {
+        let (key, val) = part.split_once('=').ok_or("RRULE part is missing '='")?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                rule.freq = match val.to_ascii_uppercase().as_str() {
+                    "SECONDLY" => Frequency::Secondly,
+                    "MINUTELY" => Frequency::Minutely,
+                    "HOURLY" => Frequency::Hourly,
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    _ => return Err("RRULE has an invalid FREQ"),
+                }
+            }
+            "INTERVAL" => rule.interval = val.parse().map_err(|_| "malformed RRULE INTERVAL")?,
+            "COUNT" => rule.count = Some(val.parse().map_err(|_| "malformed RRULE COUNT")?),
+            "UNTIL" => rule.until = Some(parse_ical_time(val).ok_or("malformed RRULE UNTIL")?),
+            "BYMONTH" => rule.by_month = uints(val)?,
+            "BYMONTHDAY" => rule.by_month_day = ints(val)?,
+            "BYHOUR" => rule.by_hour = uints(val)?,
+            "BYMINUTE" => rule.by_minute = uints(val)?,
+            "BYSECOND" => rule.by_second = uints(val)?,
+            "BYSETPOS" => rule.by_set_pos = ints(val)?,
+            "BYDAY" => {
+                for entry in val.split(',') {
+                    let (ord, wd) = entry.split_at(entry.len() - 2);
+                    let wd = parse_weekday(wd).ok_or("RRULE BYDAY entry must end in a weekday")?;
+                    let ord = if ord.is_empty() {
+                        0
+                    } else {
+                        ord.trim_start_matches('+').parse().map_err(|_| "malformed RRULE BYDAY")?
+                    };
+                    rule.by_day.push((ord, wd));
+                }
+            }
+            "WKST" => rule.wkst = parse_weekday(val).ok_or("RRULE WKST must be a weekday")?,
+            "BYYEARDAY" | "BYWEEKNO" => return Err("RRULE part is not supported for evaluation"),
+            _ => return Err("RRULE has an unknown part"),
+        }
+    }
+    Ok(rule)
+}
+
+/// Index of entities by schedule, for "occurrence in window" queries.
+///
+/// Each (entity, property) pair holds one parsed recurrence. Bounded
+/// recurrences are pre-filtered by their time span before evaluation.
+/// [`crate::GraphState`] keeps one of these up to date with its SCHEDULE
+/// values.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleIndex {
+    entities: HashMap<Id, Vec<(Id, IndexedRecurrence)>>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedRecurrence {
+    recurrence: Recurrence,
+    end_bound: Option<i64>,
+}
+
+impl ScheduleIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes a SCHEDULE value for an entity's property, replacing any
+    /// previous schedule for the same pair.
+    ///
+    /// If the schedule cannot be evaluated, the previous schedule is still
+    /// removed.
+    pub fn insert(&mut self, entity: Id, property: Id, schedule: &str) -> Result<(), ScheduleIssue> {
+        self.remove(&entity, &property);
+        let recurrence = Recurrence::parse(schedule)?;
+        let end_bound = recurrence.end_bound();
+        let entry = IndexedRecurrence { recurrence, end_bound };
+        self.entities.entry(entity).or_default().push((property, entry));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the schedule for an entity's property.
+    pub fn remove(&mut self, entity: &Id, property: &Id) {
+        let Some(schedules) = self.entities.get_mut(entity) else {
+            return;
+        };
+        if let Some(i) = schedules.iter().position(|(p, _)| p == property) {
+            schedules.swap_remove(i);
+            self.len -= 1;
+            if schedules.is_empty() {
+                self.entities.remove(entity);
+            }
+        }
+    }
+
+    /// Removes all schedules for an entity.
+    pub fn remove_entity(&mut self, entity: &Id) {
+        if let Some(schedules) = self.entities.remove(entity) {
+            self.len -= schedules.len();
+        }
+    }
+
+    /// Returns the number of indexed schedules.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no schedules are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entities with at least one occurrence in `[start, end)`,
+    /// sorted by ID.
+    pub fn entities_in_window(&self, start: i64, end: i64) -> Result<Vec<Id>, RecurrenceError> {
+        let mut out = Vec::new();
+        for (entity, schedules) in &self.entities {
+            for (_, entry) in schedules {
+                if entry.recurrence.start < end
+                    && entry.end_bound.is_none_or(|b| b >= start)
+                    && entry.recurrence.occurs_between(start, end)?
+                {
+                    out.push(*entity);
+                    break;
+                }
+            }
+        }
+        out.sort_unstable();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::timestamp::parse_datetime;
+
+    fn t(s: &str) -> i64 {
+        parse_datetime(s).unwrap().0
+    }
+
+    #[test]
+    fn test_single_event() {
+        let schedule = "BEGIN:VEVENT\r\nDTSTART:20240315T090000Z\r\nDTEND:20240315T100000Z\r\nEND:VEVENT";
+        let all = occurrences_between(schedule, i64::MIN, i64::MAX).unwrap();
+        assert_eq!(all, vec![t("2024-03-15T09:00:00Z")]);
+        assert_eq!(next_occurrence(schedule, t("2024-03-15T09:00:00Z")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_weekly_byday() {
+        let schedule = "DTSTART:20240304T090000Z\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=5";
+        let all = occurrences_between(schedule, i64::MIN, i64::MAX).unwrap();
+        let expected: Vec<i64> = ["04", "06", "08", "11", "13"]
+            .iter()
+            .map(|d| t(&format!("2024-03-{}T09:00:00Z", d)))
+            .collect();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn test_monthly_last_friday_until() {
+        let schedule = "DTSTART:20240126T170000Z\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20240430T000000Z";
+        let all = occurrences_between(schedule, i64::MIN, i64::MAX).unwrap();
+        let expected: Vec<i64> = ["01-26", "02-23", "03-29", "04-26"]
+            .iter()
+            .map(|d| t(&format!("2024-{}T17:00:00Z", d)))
+            .collect();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn test_yearly_leap_day_and_exdate() {
+        let schedule = "DTSTART;VALUE=DATE:20200229\nRRULE:FREQ=YEARLY\nEXDATE;VALUE=DATE:20280229";
+        let rec = Recurrence::parse(schedule).unwrap();
+        let all = rec.occurrences_between(t("2020-01-01T00:00:00Z"), t("2033-01-01T00:00:00Z")).unwrap();
+        assert_eq!(all, vec![t("2020-02-29T00:00:00Z"), t("2024-02-29T00:00:00Z"), t("2032-02-29T00:00:00Z")]);
+    }
+
+    #[test]
+    fn test_next_occurrence_and_rdate() {
+        let schedule = "DTSTART:20240101T120000Z\nRRULE:FREQ=DAILY;INTERVAL=10\nRDATE:20240105T080000Z";
+        assert_eq!(next_occurrence(schedule, t("2024-01-01T12:00:00Z")).unwrap(), Some(t("2024-01-05T08:00:00Z")));
+        assert_eq!(next_occurrence(schedule, t("2024-01-05T08:00:00Z")).unwrap(), Some(t("2024-01-11T12:00:00Z")));
+        assert_eq!(next_occurrence(schedule, t("2030-06-01T00:00:00Z")).unwrap(), Some(t("2030-06-08T12:00:00Z")));
+    }
+
+    #[test]
+    fn test_unsupported_and_impossible_rules() {
+        assert!(Recurrence::parse("DTSTART:20240101\nRRULE:FREQ=YEARLY;BYWEEKNO=1").is_err());
+        let impossible = "DTSTART:20240101\nRRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30";
+        assert_eq!(
+            next_occurrence(impossible, t("2024-01-01T00:00:00Z")),
+            Err(RecurrenceError::TooManyPeriods { max: MAX_RECURRENCE_PERIODS })
+        );
+        // A bounded window ends the search before the cap
+        let window = occurrences_between(impossible, t("2024-01-01T00:00:00Z"), t("2030-01-01T00:00:00Z"));
+        assert_eq!(window.unwrap(), vec![t("2024-01-01T00:00:00Z")]);
+    }
+
+    #[test]
+    fn test_open_rules_skip_to_window() {
+        let hourly = "DTSTART:19600101T000000Z\nRRULE:FREQ=HOURLY";
+        let all = occurrences_between(hourly, t("2024-05-01T10:00:00Z"), t("2024-05-01T13:00:00Z")).unwrap();
+        assert_eq!(all, vec![t("2024-05-01T10:00:00Z"), t("2024-05-01T11:00:00Z"), t("2024-05-01T12:00:00Z")]);
+
+        let minutely = "DTSTART:19600101T000030Z\nRRULE:FREQ=MINUTELY;INTERVAL=7";
+        let next = next_occurrence(minutely, t("2024-05-01T10:00:00Z")).unwrap().unwrap();
+        assert!(next > t("2024-05-01T10:00:00Z") && next <= t("2024-05-01T10:07:00Z"));
+        assert_eq!((next - t("1960-01-01T00:00:30Z")) % (7 * 60 * MICROS_PER_SECOND), 0);
+
+        let weekly = "DTSTART:19900103T090000Z\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE";
+        let all = occurrences_between(weekly, t("2024-03-04T00:00:00Z"), t("2024-03-11T00:00:00Z")).unwrap();
+        assert_eq!(all, vec![t("2024-03-04T09:00:00Z"), t("2024-03-06T09:00:00Z")]);
+
+        // COUNT rules expand from DTSTART, so long ones hit the cap
+        let counted = "DTSTART:19600101T000000Z\nRRULE:FREQ=SECONDLY;COUNT=2000000000";
+        assert!(occurrences_between(counted, t("2024-01-01T00:00:00Z"), t("2024-01-02T00:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn test_schedule_index_window() {
+        let mut index = ScheduleIndex::new();
+        let prop = [9u8; 16];
+        index.insert([1u8; 16], prop, "DTSTART:20240301T090000Z\nRRULE:FREQ=WEEKLY;COUNT=3").unwrap();
+        index.insert([2u8; 16], prop, "DTSTART:20240601T090000Z").unwrap();
+        index.insert([3u8; 16], prop, "DTSTART:20240101T090000Z\nRRULE:FREQ=MONTHLY").unwrap();
+
+        let march = index.entities_in_window(t("2024-03-10T00:00:00Z"), t("2024-03-20T00:00:00Z")).unwrap();
+        assert_eq!(march, vec![[1u8; 16]]);
+
+        let june = index.entities_in_window(t("2024-06-01T00:00:00Z"), t("2024-06-02T00:00:00Z")).unwrap();
+        assert_eq!(june, vec![[2u8; 16], [3u8; 16]]);
+
+        index.remove_entity(&[3u8; 16]);
+        assert_eq!(index.len(), 2);
+        assert!(index.insert([1u8; 16], prop, "RRULE:FREQ=DAILY").is_err());
+        assert_eq!(index.len(), 1);
+    }
+}
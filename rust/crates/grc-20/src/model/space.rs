@@ -0,0 +1,160 @@
+//! Spaces and their membership (spec Section 5.4).
+//!
+//! Space ops don't touch the entity graph, so [`GraphState`] ignores them.
+//! A [`SpaceRegistry`] replays them instead: which spaces exist, what they
+//! are called, and who holds which role. Whether an author was allowed to
+//! make a change is a governance decision (votes, thresholds) outside this
+//! crate; the registry records every space op it is given.
+//!
+//! [`GraphState`]: crate::state::GraphState
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+
+use crate::model::{Edit, Id, MemberRole, Op};
+
+/// A space's metadata and members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Space {
+    /// The space's unique identifier.
+    pub id: Id,
+    /// Display name.
+    pub name: String,
+    /// Description, if set.
+    pub description: Option<String>,
+    /// Accounts holding [`MemberRole::Member`].
+    pub members: BTreeSet<Id>,
+    /// Accounts holding [`MemberRole::Editor`].
+    pub editors: BTreeSet<Id>,
+}
+
+impl Space {
+    /// Returns the highest role `account` holds, if any.
+    pub fn role(&self, account: &Id) -> Option<MemberRole> {
+        if self.editors.contains(account) {
+            Some(MemberRole::Editor)
+        } else if self.members.contains(account) {
+            Some(MemberRole::Member)
+        } else {
+            None
+        }
+    }
+
+    fn holders(&mut self, role: MemberRole) -> &mut BTreeSet<Id> {
+        match role {
+            MemberRole::Member => &mut self.members,
+            MemberRole::Editor => &mut self.editors,
+        }
+    }
+}
+
+/// Spaces built up from space ops, keyed by ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpaceRegistry {
+    spaces: BTreeMap<Id, Space>,
+}
+
+impl SpaceRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the space ops of an edit, in order.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) {
+        for op in &edit.ops {
+            self.apply_op(op, &edit.authors);
+        }
+    }
+
+    /// Applies one op. `authors` are the authors of the op's edit, who
+    /// become the editors of a space it creates. Other ops are ignored.
+    pub fn apply_op(&mut self, op: &Op<'_>, authors: &[Id]) {
+        match op {
+            Op::CreateSpace(create) => {
+                self.spaces.entry(create.id).or_insert_with(|| Space {
+                    id: create.id,
+                    name: create.name.clone().into_owned(),
+                    description: None,
+                    members: BTreeSet::new(),
+                    editors: authors.iter().copied().collect(),
+                });
+            }
+            Op::AddMember(add) => {
+                if let Some(space) = self.spaces.get_mut(&add.space) {
+                    space.holders(add.role).insert(add.member);
+                }
+            }
+            Op::RemoveMember(remove) => {
+                if let Some(space) = self.spaces.get_mut(&remove.space) {
+                    space.holders(remove.role).remove(&remove.member);
+                }
+            }
+            Op::UpdateSpaceMetadata(update) => {
+                if let Some(space) = self.spaces.get_mut(&update.space) {
+                    if let Some(name) = &update.name {
+                        space.name = name.clone().into_owned();
+                    }
+                    if let Some(description) = &update.description {
+                        space.description =
+                            (!description.is_empty()).then(|| description.clone().into_owned());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Gets a space by ID.
+    pub fn get(&self, id: &Id) -> Option<&Space> {
+        self.spaces.get(id)
+    }
+
+    /// Iterates over the spaces in ID order.
+    pub fn iter(&self) -> impl Iterator<Item = &Space> {
+        self.spaces.values()
+    }
+
+    /// Returns the number of spaces.
+    pub fn len(&self) -> usize {
+        self.spaces.len()
+    }
+
+    /// Returns true if there are no spaces.
+    pub fn is_empty(&self) -> bool {
+        self.spaces.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AddMember, CreateSpace, RemoveMember, UpdateSpaceMetadata};
+
+    #[test]
+    fn test_space_registry() {
+        let (space, alice, bob) = ([1u8; 16], [2u8; 16], [3u8; 16]);
+        let mut edit = Edit::new([0u8; 16]);
+        edit.authors.push(alice);
+        edit.ops = vec![
+            Op::AddMember(AddMember { space, member: bob, role: MemberRole::Member }),
+            Op::CreateSpace(CreateSpace { id: space, name: "Crypto".into() }),
+            Op::AddMember(AddMember { space, member: bob, role: MemberRole::Editor }),
+            Op::RemoveMember(RemoveMember { space, member: alice, role: MemberRole::Editor }),
+            Op::UpdateSpaceMetadata(UpdateSpaceMetadata {
+                space,
+                name: None,
+                description: Some("News".into()),
+            }),
+        ];
+
+        let mut registry = SpaceRegistry::new();
+        registry.apply_edit(&edit);
+        let created = registry.get(&space).unwrap();
+        assert_eq!((created.name.as_str(), created.description.as_deref()), ("Crypto", Some("News")));
+        // The add before the create was ignored
+        assert_eq!(created.role(&bob), Some(MemberRole::Editor));
+        assert!(created.members.is_empty());
+        assert_eq!(created.role(&alice), None);
+    }
+}
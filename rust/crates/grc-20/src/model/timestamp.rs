@@ -0,0 +1,414 @@
+//! Timestamp helpers and the timezone-annotation convention.
+//!
+//! Timestamps (e.g. `Edit::created_at`, or the genesis Created/Modified
+//! properties) are bare epoch microseconds in UTC. That is enough to order
+//! events, but not to render them the way they were originally observed:
+//! "09:00 in Tokyo" and "01:00 in London" are the same instant.
+//!
+//! The convention is to record the original offset or zone alongside the
+//! timestamp using the genesis Timezone property (TEXT), whose value is
+//! either a fixed UTC offset (`+09:00`, `-05:30`, `Z`) or an IANA zone name
+//! (`Asia/Tokyo`). Fixed offsets can be rendered without a zone database via
+//! [`format_timestamp`]; IANA names are left to applications.
+
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::genesis::properties;
+#[cfg(feature = "std")]
+use crate::model::{PropertyValue, Value};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Maximum absolute UTC offset in minutes (±23:59).
+pub const MAX_OFFSET_MINUTES: i32 = 23 * 60 + 59;
+
+/// Formats a UTC offset in minutes as `±HH:MM` (`Z` for zero).
+pub fn format_offset(offset_minutes: i32) -> String {
+    if offset_minutes == 0 {
+        return "Z".to_string();
+    }
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+    let abs = offset_minutes.unsigned_abs();
+    format!("{}{:02}:{:02}", sign, abs / 60, abs % 60)
+}
+
+/// Parses a UTC offset (`Z`, `±HH:MM`, or `±HHMM`) into minutes.
+///
+/// Returns None for IANA zone names and malformed offsets.
+pub fn parse_offset(s: &str) -> Option<i32> {
+    if s == "Z" || s == "z" {
+        return Some(0);
+    }
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let digits: String = match rest.len() {
+        5 if rest.as_bytes()[2] == b':' => format!("{}{}", &rest[..2], &rest[3..]),
+        4 => rest.to_string(),
+        _ => return None,
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// Renders a timestamp (epoch microseconds) as an ISO 8601 DATETIME in the
+/// given UTC offset.
+///
+/// Fractional seconds are included only when non-zero. The result is a
+/// valid DATETIME value that preserves both the instant and the original
+/// wall-clock time.
+///
+/// Returns None if the offset exceeds ±23:59.
+pub fn format_timestamp(micros: i64, offset_minutes: i32) -> Option<String> {
+    if offset_minutes.abs() > MAX_OFFSET_MINUTES {
+        return None;
+    }
+    let local = micros.checked_add(offset_minutes as i64 * 60 * MICROS_PER_SECOND)?;
+    let seconds = local.div_euclid(MICROS_PER_SECOND);
+    let frac = local.rem_euclid(MICROS_PER_SECOND);
+    let days = seconds.div_euclid(SECONDS_PER_DAY);
+    let secs_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    let mut out = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day / 60) % 60,
+        secs_of_day % 60
+    );
+    if frac != 0 {
+        let digits = format!("{:06}", frac);
+        out.push('.');
+        out.push_str(digits.trim_end_matches('0'));
+    }
+    out.push_str(&format_offset(offset_minutes));
+    Some(out)
+}
+
+/// Parses an ISO 8601 DATETIME into epoch microseconds and its UTC offset.
+///
+/// This is the inverse of [`format_timestamp`]: the offset is returned so
+/// callers can record it with the Timezone property. Accepts
+/// `YYYY-MM-DDTHH:MM:SS[.frac](Z|±HH:MM)` with up to 6 fractional digits.
+pub fn parse_datetime(s: &str) -> Option<(i64, i32)> {
+    let (date, time) = s.split_once('T')?;
+    let (year, month, day) = parse_date(date)?;
+
+    let tz_start = time.find(['Z', 'z', '+', '-'])?;
+    let (clock, tz) = time.split_at(tz_start);
+    let offset = parse_offset(tz)?;
+
+    let (hms, frac) = match clock.split_once('.') {
+        Some((hms, frac)) => (hms, Some(frac)),
+        None => (clock, None),
+    };
+    let parts: Vec<&str> = hms.split(':').collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.len() != 2) {
+        return None;
+    }
+    let hour: i64 = parts[0].parse().ok()?;
+    let minute: i64 = parts[1].parse().ok()?;
+    let second: i64 = parts[2].parse().ok()?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let micros_frac = match frac {
+        Some(f) if !f.is_empty() && f.len() <= 6 && f.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<6}", f).parse::<i64>().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let local_seconds = days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second;
+    let utc_seconds = local_seconds - offset as i64 * 60;
+    Some((utc_seconds * MICROS_PER_SECOND + micros_frac, offset))
+}
+
+/// Creates a Timezone property value recording a fixed UTC offset.
+#[cfg(feature = "std")]
+pub fn timezone_offset_value(offset_minutes: i32) -> PropertyValue<'static> {
+    timezone_value(format_offset(offset_minutes))
+}
+
+/// Creates a Timezone property value from an offset or IANA zone name.
+#[cfg(feature = "std")]
+pub fn timezone_value<'a>(timezone: impl Into<Cow<'a, str>>) -> PropertyValue<'a> {
+    PropertyValue {
+        property: properties::timezone(),
+        value: Value::Text {
+            value: timezone.into(),
+            language: None,
+        },
+        clock: None,
+    }
+}
+
+/// The precision of a DATE value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DatePrecision {
+    /// `YYYY`
+    Year,
+    /// `YYYY-MM`
+    Month,
+    /// `YYYY-MM-DD`
+    Day,
+}
+
+/// Parses a DATE value per the spec grammar: `[sign] YYYY`, optionally
+/// followed by `-MM` and `-DD`, in the proleptic Gregorian calendar.
+///
+/// Returns `(year, month, day, precision)` with reduced-precision dates
+/// filled in as the first month/day of the period, or None if the string
+/// does not match the grammar or names a day that does not exist.
+pub fn parse_date_value(s: &str) -> Option<(i64, u32, u32, DatePrecision)> {
+    parse_date_parts(s, true).ok()
+}
+
+/// Validates a DATE string against the spec grammar, returning its
+/// precision.
+///
+/// In strict mode, days are checked against the month's length in the
+/// given year, so `2023-02-29` is rejected. This is the mode used by the
+/// encoder and decoder. Non-strict mode accepts February 29 in any year,
+/// for linting data from producers that never checked leap years.
+pub fn validate_iso8601_date(s: &str, strict: bool) -> Result<DatePrecision, &'static str> {
+    parse_date_parts(s, strict).map(|(_, _, _, precision)| precision)
+}
+
+fn parse_date_parts(
+    s: &str,
+    strict: bool,
+) -> Result<(i64, u32, u32, DatePrecision), &'static str> {
+    if s.contains('T') {
+        return Err("DATE should not contain 'T' separator (use DATETIME instead)");
+    }
+    let (negative, rest) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let mut parts = rest.split('-');
+    let year_str = parts.next().unwrap_or_default();
+    let month_str = parts.next();
+    let day_str = parts.next();
+    if parts.next().is_some() {
+        return Err("DATE has too many components (expected YYYY[-MM[-DD]])");
+    }
+    let year = match year_str.len() {
+        4 => parse_digits(year_str).ok_or("DATE year must be 4 digits")? as i64,
+        _ => return Err("DATE year must be 4 digits"),
+    };
+    let year = if negative { -year } else { year };
+    let month = match month_str {
+        Some(m) if m.len() == 2 => parse_digits(m).ok_or("DATE month must be 2 digits")?,
+        Some(_) => return Err("DATE month must be 2 digits"),
+        None => return Ok((year, 1, 1, DatePrecision::Year)),
+    };
+    if !(1..=12).contains(&month) {
+        return Err("DATE month must be between 01 and 12");
+    }
+    let day = match day_str {
+        Some(d) if d.len() == 2 => parse_digits(d).ok_or("DATE day must be 2 digits")?,
+        Some(_) => return Err("DATE day must be 2 digits"),
+        None => return Ok((year, month, 1, DatePrecision::Month)),
+    };
+    // February 29 is valid in a leap year; non-strict mode allows it in any year
+    let max_day = match month {
+        2 if !strict => 29,
+        _ => days_in_month(year, month),
+    };
+    if day < 1 || day > max_day {
+        return Err("DATE day is out of range for the month");
+    }
+    Ok((year, month, day, DatePrecision::Day))
+}
+
+/// Formats a DATE value at the given precision.
+///
+/// Returns None if the year needs more than 4 digits (the DATE grammar has
+/// no expanded years).
+pub fn format_date_value(
+    year: i64,
+    month: u32,
+    day: u32,
+    precision: DatePrecision,
+) -> Option<String> {
+    if year.unsigned_abs() > 9999 {
+        return None;
+    }
+    let sign = if year < 0 { "-" } else { "" };
+    let abs = year.unsigned_abs();
+    Some(match precision {
+        DatePrecision::Year => format!("{}{:04}", sign, abs),
+        DatePrecision::Month => format!("{}{:04}-{:02}", sign, abs, month),
+        DatePrecision::Day => format!("{}{:04}-{:02}-{:02}", sign, abs, month, day),
+    })
+}
+
+fn parse_digits(s: &str) -> Option<u32> {
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Returns the number of days in a month of the proleptic Gregorian calendar.
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Returns true for leap years in the proleptic Gregorian calendar.
+pub fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Parses `YYYY-MM-DD` (with optional leading `-` for BCE years).
+fn parse_date(s: &str) -> Option<(i64, u32, u32)> {
+    let (negative, rest) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let mut parts = rest.split('-');
+    let year_str = parts.next()?;
+    let month_str = parts.next()?;
+    let day_str = parts.next()?;
+    if parts.next().is_some() || year_str.len() < 4 || month_str.len() != 2 || day_str.len() != 2 {
+        return None;
+    }
+    let year: i64 = year_str.parse().ok()?;
+    let year = if negative { -year } else { year };
+    let month: u32 = month_str.parse().ok()?;
+    let day: u32 = day_str.parse().ok()?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some((year, month, day))
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) civil date.
+///
+/// Uses the algorithm from Howard Hinnant's "chrono-compatible low-level
+/// date algorithms".
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Converts a (year, month, day) civil date to days since 1970-01-01.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_roundtrip() {
+        assert_eq!(format_offset(0), "Z");
+        assert_eq!(format_offset(540), "+09:00");
+        assert_eq!(format_offset(-330), "-05:30");
+        assert_eq!(parse_offset("+09:00"), Some(540));
+        assert_eq!(parse_offset("-0530"), Some(-330));
+        assert_eq!(parse_offset("Z"), Some(0));
+        assert_eq!(parse_offset("Asia/Tokyo"), None);
+        assert_eq!(parse_offset("+24:00"), None);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0, 0).unwrap(), "1970-01-01T00:00:00Z");
+        // 2024-03-15T00:00:00Z rendered in Tokyo
+        let micros = 1_710_460_800 * MICROS_PER_SECOND;
+        assert_eq!(format_timestamp(micros, 540).unwrap(), "2024-03-15T09:00:00+09:00");
+        assert_eq!(format_timestamp(micros + 250_000, -300).unwrap(), "2024-03-14T19:00:00.25-05:00");
+        assert_eq!(format_timestamp(-1, 0).unwrap(), "1969-12-31T23:59:59.999999Z");
+        assert!(format_timestamp(0, 24 * 60).is_none());
+    }
+
+    #[test]
+    fn test_parse_datetime_roundtrip() {
+        let (micros, offset) = parse_datetime("2024-03-15T09:00:00+09:00").unwrap();
+        assert_eq!(micros, 1_710_460_800 * MICROS_PER_SECOND);
+        assert_eq!(offset, 540);
+        assert_eq!(format_timestamp(micros, offset).unwrap(), "2024-03-15T09:00:00+09:00");
+
+        let (micros, offset) = parse_datetime("2000-02-29T12:34:56.5Z").unwrap();
+        assert_eq!(format_timestamp(micros, offset).unwrap(), "2000-02-29T12:34:56.5Z");
+
+        assert!(parse_datetime("2023-02-29T00:00:00Z").is_none());
+        assert!(parse_datetime("2024-03-15T09:00:00").is_none());
+    }
+
+    #[test]
+    fn test_parse_date_value() {
+        assert_eq!(parse_date_value("2024"), Some((2024, 1, 1, DatePrecision::Year)));
+        assert_eq!(parse_date_value("2024-03"), Some((2024, 3, 1, DatePrecision::Month)));
+        assert_eq!(parse_date_value("-0100-03-15"), Some((-100, 3, 15, DatePrecision::Day)));
+        assert_eq!(parse_date_value("2024-02-29"), Some((2024, 2, 29, DatePrecision::Day)));
+        for bad in ["2023-02-29", "2024-13", "24-03-15", "2024-3-15", "2024-03-15T00:00:00Z", ""] {
+            assert_eq!(parse_date_value(bad), None, "{bad}");
+        }
+        assert_eq!(format_date_value(-100, 3, 15, DatePrecision::Day).unwrap(), "-0100-03-15");
+        assert_eq!(format_date_value(2024, 3, 15, DatePrecision::Month).unwrap(), "2024-03");
+        assert_eq!(format_date_value(10_000, 1, 1, DatePrecision::Year), None);
+
+        assert!(validate_iso8601_date("2023-02-29", true).is_err());
+        assert_eq!(validate_iso8601_date("2023-02-29", false), Ok(DatePrecision::Day));
+        let century = validate_iso8601_date("1900-02-29", true);
+        assert_eq!(century, Err("DATE day is out of range for the month"));
+        assert!(validate_iso8601_date("2000-02-29", true).is_ok());
+        assert!(validate_iso8601_date("2023-02-30", false).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_timezone_value() {
+        let pv = timezone_offset_value(-330);
+        assert_eq!(pv.property, properties::timezone());
+        assert_eq!(
+            pv.value,
+            Value::Text { value: Cow::Owned("-05:30".to_string()), language: None }
+        );
+    }
+}
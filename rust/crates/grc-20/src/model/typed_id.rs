@@ -0,0 +1,116 @@
+//! Typed ID wrappers.
+//!
+//! The wire format and the op structs use the bare [`Id`] for everything,
+//! so nothing stops a relation's `from` and `type` from being swapped.
+//! These newtypes name what an ID refers to and deref to the raw bytes, so
+//! they can be used wherever an `&Id` is expected while the typed helpers
+//! below ([`unique_relation_id_typed`]) catch argument mixups at compile
+//! time.
+//!
+//! ```ignore
+//! let alice = EntityId::from(random_id());
+//! let works_at = RelationTypeId::from(genesis_id("WorksAt"));
+//! let rel = unique_relation_id_typed(alice, acme, works_at);
+//! builder.create_relation_simple(rel, *alice, *acme, *works_at);
+//! ```
+
+use core::fmt;
+use core::ops::Deref;
+
+use crate::model::id::{unique_relation_id, DisplayId, Id};
+
+macro_rules! typed_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        #[repr(transparent)]
+        pub struct $name(pub Id);
+
+        impl Deref for $name {
+            type Target = Id;
+
+            fn deref(&self) -> &Id {
+                &self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl From<Id> for $name {
+            fn from(id: Id) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for Id {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                DisplayId::new(self.0).fmt(f)
+            }
+        }
+    };
+}
+
+typed_id! {
+    /// The ID of an entity (including relation entities).
+    EntityId
+}
+
+typed_id! {
+    /// The ID of a property entity.
+    PropertyId
+}
+
+typed_id! {
+    /// The ID of a relation type entity.
+    RelationTypeId
+}
+
+typed_id! {
+    /// The ID of a space.
+    SpaceId
+}
+
+typed_id! {
+    /// The ID of a language entity.
+    LanguageId
+}
+
+/// Derives a unique-mode relation ID from typed endpoints.
+///
+/// Same as [`unique_relation_id`], with the argument roles checked by the
+/// compiler.
+pub fn unique_relation_id_typed(
+    from: EntityId,
+    to: EntityId,
+    relation_type: RelationTypeId,
+) -> Id {
+    unique_relation_id(&from, &to, &relation_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::id::{derived_uuid, format_id};
+
+    #[test]
+    fn test_typed_ids() {
+        let (from, to, ty) = (derived_uuid(b"a"), derived_uuid(b"b"), derived_uuid(b"t"));
+        let typed = unique_relation_id_typed(from.into(), to.into(), ty.into());
+        assert_eq!(typed, unique_relation_id(&from, &to, &ty));
+
+        let entity = EntityId(from);
+        assert_eq!(*entity, from);
+        assert_eq!(Id::from(entity), from);
+        assert_eq!(entity.to_string(), format_id(&from));
+    }
+}
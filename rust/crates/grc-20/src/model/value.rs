@@ -2,12 +2,18 @@
 //!
 //! Values are typed attribute instances on entities and relations.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
 
-use crate::model::Id;
+use crate::codec::primitives::{has_f32_nan, Writer};
+use crate::model::id::{text_value_id, value_id};
+use crate::model::{decimal, Id};
+use crate::model::timestamp::validate_iso8601_date;
+use crate::validate::schedule::validate_schedule;
+use crate::validate::ValidationProfile;
 
 /// Data types for property values (spec Section 2.4).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum DataType {
     Bool = 1,
@@ -95,9 +101,8 @@ impl DecimalMantissa<'_> {
         match self {
             DecimalMantissa::I64(v) => *v != 0 && *v % 10 == 0,
             DecimalMantissa::Big(bytes) => {
-                // For big mantissas, we'd need to convert to check
-                // This is a simplification - full check would convert to decimal
-                !bytes.is_empty() && bytes[bytes.len() - 1] == 0
+                let (_, mut magnitude) = decimal::magnitude_from_twos_complement(bytes);
+                !magnitude.is_empty() && decimal::div_rem(&mut magnitude, 10) == 0
             }
         }
     }
@@ -109,6 +114,14 @@ impl DecimalMantissa<'_> {
             DecimalMantissa::Big(bytes) => bytes.iter().all(|b| *b == 0),
         }
     }
+
+    /// Converts to an owned mantissa that no longer borrows its input.
+    pub fn into_owned(self) -> DecimalMantissa<'static> {
+        match self {
+            DecimalMantissa::I64(v) => DecimalMantissa::I64(v),
+            DecimalMantissa::Big(bytes) => DecimalMantissa::Big(Cow::Owned(bytes.into_owned())),
+        }
+    }
 }
 
 /// A typed value that can be stored on an entity or relation.
@@ -181,6 +194,33 @@ pub enum Value<'a> {
 }
 
 impl Value<'_> {
+    /// Converts to an owned value that no longer borrows its input.
+    pub fn into_owned(self) -> Value<'static> {
+        let owned = |s: Cow<'_, str>| Cow::Owned(s.into_owned());
+        match self {
+            Value::Bool(v) => Value::Bool(v),
+            Value::Int64 { value, unit } => Value::Int64 { value, unit },
+            Value::Float64 { value, unit } => Value::Float64 { value, unit },
+            Value::Decimal { exponent, mantissa, unit } => Value::Decimal {
+                exponent,
+                mantissa: mantissa.into_owned(),
+                unit,
+            },
+            Value::Text { value, language } => Value::Text { value: owned(value), language },
+            Value::Bytes(data) => Value::Bytes(Cow::Owned(data.into_owned())),
+            Value::Date(s) => Value::Date(owned(s)),
+            Value::Time(s) => Value::Time(owned(s)),
+            Value::Datetime(s) => Value::Datetime(owned(s)),
+            Value::Schedule(s) => Value::Schedule(owned(s)),
+            Value::Point { lon, lat, alt } => Value::Point { lon, lat, alt },
+            Value::Embedding { sub_type, dims, data } => Value::Embedding {
+                sub_type,
+                dims,
+                data: Cow::Owned(data.into_owned()),
+            },
+        }
+    }
+
     /// Returns the data type of this value.
     pub fn data_type(&self) -> DataType {
         match self {
@@ -199,16 +239,81 @@ impl Value<'_> {
         }
     }
 
+    /// Returns the canonical payload of this value, the input to
+    /// [`value_id`] and [`text_value_id`].
+    ///
+    /// This is the payload as encoded on the wire (spec Section 6.5)
+    /// without the unit or language and without string table references,
+    /// with DECIMAL values normalized. Values are not validated.
+    pub fn canonical_payload(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            Value::Bool(v) => writer.write_byte(u8::from(*v)),
+            Value::Int64 { value, .. } => writer.write_signed_varint(*value),
+            Value::Float64 { value, .. } => writer.write_f64(*value),
+            Value::Decimal { exponent, mantissa, .. } => {
+                let (exponent, mantissa) = mantissa.normalized(*exponent);
+                writer.write_signed_varint(exponent as i64);
+                match mantissa {
+                    DecimalMantissa::I64(v) => {
+                        writer.write_byte(0x00);
+                        writer.write_signed_varint(v);
+                    }
+                    DecimalMantissa::Big(bytes) => {
+                        writer.write_byte(0x01);
+                        writer.write_bytes_prefixed(&bytes);
+                    }
+                }
+            }
+            Value::Text { value, .. } => writer.write_string(value),
+            Value::Bytes(bytes) => writer.write_bytes_prefixed(bytes),
+            Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => {
+                writer.write_string(s)
+            }
+            Value::Point { lon, lat, alt } => {
+                writer.write_byte(if alt.is_some() { 3 } else { 2 });
+                writer.write_f64(*lon);
+                writer.write_f64(*lat);
+                if let Some(alt) = alt {
+                    writer.write_f64(*alt);
+                }
+            }
+            Value::Embedding { sub_type, dims, data } => {
+                writer.write_byte(*sub_type as u8);
+                writer.write_varint(*dims as u64);
+                writer.write_bytes(data);
+            }
+        }
+        writer.into_bytes()
+    }
+
+    /// Returns the value identity hash of this value as a value of
+    /// `property` (see [`PropertyValue::value_id`]).
+    pub fn value_id(&self, property: &Id) -> Id {
+        let payload = self.canonical_payload();
+        match self {
+            Value::Text { language, .. } => text_value_id(property, &payload, language.as_ref()),
+            _ => value_id(property, &payload),
+        }
+    }
+
     /// Validates this value according to spec rules.
     ///
     /// Returns an error description if invalid, None if valid.
     pub fn validate(&self) -> Option<&'static str> {
+        self.validate_with(ValidationProfile::Strict)
+    }
+
+    /// Validates this value with the checks `profile` makes hard errors.
+    ///
+    /// DECIMAL values that the profile would normalize are valid.
+    pub fn validate_with(&self, profile: ValidationProfile) -> Option<&'static str> {
+        let nan_allowed = profile.allows_nan();
         match self {
-            Value::Float64 { value, .. } => {
-                if value.is_nan() {
-                    return Some("NaN is not allowed in Float64");
-                }
+            Value::Float64 { value, .. } if value.is_nan() && !nan_allowed => {
+                return Some("NaN is not allowed in Float64");
             }
+            Value::Decimal { .. } if profile.normalizes_decimals() => {}
             Value::Decimal { exponent, mantissa, .. } => {
                 // Zero must be {0, 0}
                 if mantissa.is_zero() && *exponent != 0 {
@@ -226,13 +331,11 @@ impl Value<'_> {
                 if *lat < -90.0 || *lat > 90.0 {
                     return Some("latitude out of range [-90, +90]");
                 }
-                if lon.is_nan() || lat.is_nan() {
+                if (lon.is_nan() || lat.is_nan()) && !nan_allowed {
                     return Some("NaN is not allowed in Point coordinates");
                 }
-                if let Some(a) = alt {
-                    if a.is_nan() {
-                        return Some("NaN is not allowed in Point altitude");
-                    }
+                if alt.is_some_and(f64::is_nan) && !nan_allowed {
+                    return Some("NaN is not allowed in Point altitude");
                 }
             }
             Value::Embedding {
@@ -245,13 +348,18 @@ impl Value<'_> {
                     return Some("embedding data length doesn't match dims");
                 }
                 // Check for NaN in float32 embeddings
-                if *sub_type == EmbeddingSubType::Float32 {
-                    for chunk in data.chunks_exact(4) {
-                        let f = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-                        if f.is_nan() {
-                            return Some("NaN is not allowed in float32 embedding");
-                        }
-                    }
+                if *sub_type == EmbeddingSubType::Float32 && !nan_allowed && has_f32_nan(data) {
+                    return Some("NaN is not allowed in float32 embedding");
+                }
+            }
+            Value::Date(s) => {
+                if let Err(reason) = validate_iso8601_date(s, profile.strict_dates()) {
+                    return Some(reason);
+                }
+            }
+            Value::Schedule(s) => {
+                if let Err(issue) = validate_schedule(s, true) {
+                    return Some(issue.reason);
                 }
             }
             _ => {}
@@ -267,6 +375,26 @@ pub struct PropertyValue<'a> {
     pub property: Id,
     /// The value.
     pub value: Value<'a>,
+    /// Logical clock of the write (e.g. a Lamport timestamp), for merging
+    /// concurrent writes before they are published (see [`lww_merge`]).
+    ///
+    /// Clocks start at 1. Indexers ignore them: published values resolve
+    /// by log position. An edit that carries any clock is written as
+    /// format version 3.
+    ///
+    /// [`lww_merge`]: crate::merge::lww_merge
+    pub clock: Option<u64>,
+}
+
+impl PropertyValue<'_> {
+    /// Returns the value identity hash of this value.
+    ///
+    /// TEXT values include their language, so the same text in two
+    /// languages has two IDs. Units and clocks are not part of the
+    /// identity.
+    pub fn value_id(&self) -> Id {
+        self.value.value_id(&self.property)
+    }
 }
 
 /// A property definition in the schema.
@@ -281,6 +409,63 @@ pub struct Property {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::value::encode_value;
+    use crate::model::DictionaryBuilder;
+
+    #[test]
+    fn test_canonical_payload_matches_wire_payload() {
+        use DataType::*;
+
+        // Without a unit or language, the wire encoding is the payload
+        // followed by a zero unit or language index
+        let values = [
+            Value::Bool(true),
+            Value::Int64 { value: -42, unit: None },
+            Value::Float64 { value: 1.5, unit: None },
+            Value::Decimal { exponent: -2, mantissa: DecimalMantissa::I64(1234), unit: None },
+            Value::Text { value: Cow::Borrowed("hello"), language: None },
+            Value::Bytes(Cow::Borrowed(&[1, 2, 3])),
+            Value::Date(Cow::Borrowed("2024-03-15")),
+            Value::Point { lon: -74.0, lat: 40.7, alt: Some(10.0) },
+            Value::Embedding {
+                sub_type: EmbeddingSubType::Int8,
+                dims: 3,
+                data: Cow::Borrowed(&[1, 2, 3]),
+            },
+        ];
+        for value in values {
+            let mut writer = Writer::new();
+            encode_value(&mut writer, &value, &mut DictionaryBuilder::new()).unwrap();
+            let mut wire = writer.into_bytes();
+            let data_type = value.data_type();
+            if matches!(data_type, Int64 | Float64 | Decimal | Text) {
+                assert_eq!(wire.pop(), Some(0));
+            }
+            assert_eq!(value.canonical_payload(), wire, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_property_value_id() {
+        let pv = |value| PropertyValue { property: [1u8; 16], value, clock: None };
+        let text = |language| Value::Text { value: Cow::Borrowed("Paris"), language };
+        let meters = Value::Int64 { value: 5, unit: Some([2u8; 16]) };
+
+        // Units and clocks don't count, languages and non-normal decimals do
+        let id = pv(Value::Int64 { value: 5, unit: None }).value_id();
+        assert_eq!(pv(meters.clone()).value_id(), id);
+        assert_eq!(PropertyValue { clock: Some(3), ..pv(meters) }.value_id(), id);
+        assert_ne!(pv(Value::Int64 { value: 6, unit: None }).value_id(), id);
+        assert_ne!(pv(text(None)).value_id(), pv(text(Some([3u8; 16]))).value_id());
+        assert_eq!(pv(text(None)).value_id(), text_value_id(&[1u8; 16], b"\x05Paris", None));
+
+        let decimal = |exponent, mantissa| Value::Decimal {
+            exponent,
+            mantissa: DecimalMantissa::I64(mantissa),
+            unit: None,
+        };
+        assert_eq!(pv(decimal(0, 150)).value_id(), pv(decimal(1, 15)).value_id());
+    }
 
     #[test]
     fn test_embedding_bytes_for_dims() {
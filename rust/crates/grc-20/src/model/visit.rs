@@ -0,0 +1,376 @@
+//! Visitors over the ops and values of an edit.
+//!
+//! [`OpVisitor`] has a method per op kind and per value type, each
+//! defaulting to a walk into its children (ops into their values) or to a
+//! no-op (leaves), so a pass overrides only what it cares about:
+//!
+//! ```
+//! use grc_20::model::visit::OpVisitor;
+//! use grc_20::{Edit, Id};
+//!
+//! #[derive(Default)]
+//! struct TextStats {
+//!     count: usize,
+//!     bytes: usize,
+//! }
+//!
+//! impl OpVisitor for TextStats {
+//!     fn visit_text(&mut self, _property: Id, value: &str, _language: Option<Id>) {
+//!         self.count += 1;
+//!         self.bytes += value.len();
+//!     }
+//! }
+//!
+//! let edit = Edit::new([1u8; 16]);
+//! let mut stats = TextStats::default();
+//! edit.visit(&mut stats);
+//! ```
+//!
+//! An override that still wants the children calls the matching `walk_*`
+//! function, e.g. to track the entity whose values are being visited.
+//!
+//! [`OpVisitorMut`] is the in-place counterpart for transformation passes;
+//! its hooks stop at whole values rather than per value type.
+
+use crate::model::{
+    AddMember, CreateEntity, CreateRelation, CreateSpace, CreateValueRef, DecimalMantissa,
+    DeleteEntity, DeleteRelation, Edit, EmbeddingSubType, Id, Op, PropertyValue, RemoveMember,
+    RestoreEntity, RestoreRelation, UnknownOp, UnsetValue, UpdateEntity, UpdateRelation,
+    UpdateSpaceMetadata, Value,
+};
+
+/// Read-only visitor over ops and values.
+///
+/// Op methods default to visiting their values; value methods default to
+/// doing nothing.
+#[allow(unused_variables)]
+pub trait OpVisitor {
+    /// Visits an op, dispatching on its kind.
+    fn visit_op(&mut self, op: &Op<'_>) {
+        walk_op(self, op);
+    }
+
+    fn visit_create_entity(&mut self, op: &CreateEntity<'_>) {
+        walk_create_entity(self, op);
+    }
+
+    fn visit_update_entity(&mut self, op: &UpdateEntity<'_>) {
+        walk_update_entity(self, op);
+    }
+
+    fn visit_delete_entity(&mut self, op: &DeleteEntity) {}
+
+    fn visit_restore_entity(&mut self, op: &RestoreEntity) {}
+
+    fn visit_create_relation(&mut self, op: &CreateRelation<'_>) {}
+
+    fn visit_update_relation(&mut self, op: &UpdateRelation<'_>) {}
+
+    fn visit_delete_relation(&mut self, op: &DeleteRelation) {}
+
+    fn visit_restore_relation(&mut self, op: &RestoreRelation) {}
+
+    fn visit_create_value_ref(&mut self, op: &CreateValueRef) {}
+
+    fn visit_create_space(&mut self, op: &CreateSpace<'_>) {}
+
+    fn visit_add_member(&mut self, op: &AddMember) {}
+
+    fn visit_remove_member(&mut self, op: &RemoveMember) {}
+
+    fn visit_update_space_metadata(&mut self, op: &UpdateSpaceMetadata<'_>) {}
+
+    fn visit_unknown(&mut self, op: &UnknownOp<'_>) {}
+
+    /// Visits a value being set, dispatching on its type.
+    fn visit_property_value(&mut self, pv: &PropertyValue<'_>) {
+        walk_value(self, pv.property, &pv.value);
+    }
+
+    /// Visits a value being cleared by `UpdateEntity`.
+    fn visit_unset_value(&mut self, unset: &UnsetValue) {}
+
+    fn visit_bool(&mut self, property: Id, value: bool) {}
+
+    fn visit_int64(&mut self, property: Id, value: i64, unit: Option<Id>) {}
+
+    fn visit_float64(&mut self, property: Id, value: f64, unit: Option<Id>) {}
+
+    fn visit_decimal(
+        &mut self,
+        property: Id,
+        exponent: i32,
+        mantissa: &DecimalMantissa<'_>,
+        unit: Option<Id>,
+    ) {
+    }
+
+    fn visit_text(&mut self, property: Id, value: &str, language: Option<Id>) {}
+
+    fn visit_bytes(&mut self, property: Id, value: &[u8]) {}
+
+    fn visit_date(&mut self, property: Id, value: &str) {}
+
+    fn visit_time(&mut self, property: Id, value: &str) {}
+
+    fn visit_datetime(&mut self, property: Id, value: &str) {}
+
+    fn visit_schedule(&mut self, property: Id, value: &str) {}
+
+    fn visit_point(&mut self, property: Id, lon: f64, lat: f64, alt: Option<f64>) {}
+
+    fn visit_embedding(
+        &mut self,
+        property: Id,
+        sub_type: EmbeddingSubType,
+        dims: usize,
+        data: &[u8],
+    ) {
+    }
+}
+
+/// Dispatches an op to the visitor method for its kind.
+pub fn walk_op<V: OpVisitor + ?Sized>(visitor: &mut V, op: &Op<'_>) {
+    match op {
+        Op::CreateEntity(op) => visitor.visit_create_entity(op),
+        Op::UpdateEntity(op) => visitor.visit_update_entity(op),
+        Op::DeleteEntity(op) => visitor.visit_delete_entity(op),
+        Op::RestoreEntity(op) => visitor.visit_restore_entity(op),
+        Op::CreateRelation(op) => visitor.visit_create_relation(op),
+        Op::UpdateRelation(op) => visitor.visit_update_relation(op),
+        Op::DeleteRelation(op) => visitor.visit_delete_relation(op),
+        Op::RestoreRelation(op) => visitor.visit_restore_relation(op),
+        Op::CreateValueRef(op) => visitor.visit_create_value_ref(op),
+        Op::CreateSpace(op) => visitor.visit_create_space(op),
+        Op::AddMember(op) => visitor.visit_add_member(op),
+        Op::RemoveMember(op) => visitor.visit_remove_member(op),
+        Op::UpdateSpaceMetadata(op) => visitor.visit_update_space_metadata(op),
+        Op::Unknown(op) => visitor.visit_unknown(op),
+    }
+}
+
+/// Visits the values of a `CreateEntity`.
+pub fn walk_create_entity<V: OpVisitor + ?Sized>(visitor: &mut V, op: &CreateEntity<'_>) {
+    for pv in &op.values {
+        visitor.visit_property_value(pv);
+    }
+}
+
+/// Visits the unset and set values of an `UpdateEntity`, in application
+/// order.
+pub fn walk_update_entity<V: OpVisitor + ?Sized>(visitor: &mut V, op: &UpdateEntity<'_>) {
+    for unset in &op.unset_values {
+        visitor.visit_unset_value(unset);
+    }
+    for pv in &op.set_properties {
+        visitor.visit_property_value(pv);
+    }
+}
+
+/// Dispatches a value to the visitor method for its type.
+pub fn walk_value<V: OpVisitor + ?Sized>(visitor: &mut V, property: Id, value: &Value<'_>) {
+    match value {
+        Value::Bool(v) => visitor.visit_bool(property, *v),
+        Value::Int64 { value, unit } => visitor.visit_int64(property, *value, *unit),
+        Value::Float64 { value, unit } => visitor.visit_float64(property, *value, *unit),
+        Value::Decimal { exponent, mantissa, unit } => {
+            visitor.visit_decimal(property, *exponent, mantissa, *unit)
+        }
+        Value::Text { value, language } => visitor.visit_text(property, value, *language),
+        Value::Bytes(v) => visitor.visit_bytes(property, v),
+        Value::Date(v) => visitor.visit_date(property, v),
+        Value::Time(v) => visitor.visit_time(property, v),
+        Value::Datetime(v) => visitor.visit_datetime(property, v),
+        Value::Schedule(v) => visitor.visit_schedule(property, v),
+        Value::Point { lon, lat, alt } => visitor.visit_point(property, *lon, *lat, *alt),
+        Value::Embedding { sub_type, dims, data } => {
+            visitor.visit_embedding(property, *sub_type, *dims, data)
+        }
+    }
+}
+
+/// In-place visitor over ops and values.
+///
+/// Op methods default to visiting their values; value methods default to
+/// doing nothing.
+#[allow(unused_variables)]
+pub trait OpVisitorMut {
+    /// Visits an op, dispatching on its kind.
+    fn visit_op_mut(&mut self, op: &mut Op<'_>) {
+        walk_op_mut(self, op);
+    }
+
+    fn visit_create_entity_mut(&mut self, op: &mut CreateEntity<'_>) {
+        walk_create_entity_mut(self, op);
+    }
+
+    fn visit_update_entity_mut(&mut self, op: &mut UpdateEntity<'_>) {
+        walk_update_entity_mut(self, op);
+    }
+
+    fn visit_delete_entity_mut(&mut self, op: &mut DeleteEntity) {}
+
+    fn visit_restore_entity_mut(&mut self, op: &mut RestoreEntity) {}
+
+    fn visit_create_relation_mut(&mut self, op: &mut CreateRelation<'_>) {}
+
+    fn visit_update_relation_mut(&mut self, op: &mut UpdateRelation<'_>) {}
+
+    fn visit_delete_relation_mut(&mut self, op: &mut DeleteRelation) {}
+
+    fn visit_restore_relation_mut(&mut self, op: &mut RestoreRelation) {}
+
+    fn visit_create_value_ref_mut(&mut self, op: &mut CreateValueRef) {}
+
+    fn visit_create_space_mut(&mut self, op: &mut CreateSpace<'_>) {}
+
+    fn visit_add_member_mut(&mut self, op: &mut AddMember) {}
+
+    fn visit_remove_member_mut(&mut self, op: &mut RemoveMember) {}
+
+    fn visit_update_space_metadata_mut(&mut self, op: &mut UpdateSpaceMetadata<'_>) {}
+
+    fn visit_unknown_mut(&mut self, op: &mut UnknownOp<'_>) {}
+
+    /// Visits a value being set.
+    fn visit_property_value_mut(&mut self, pv: &mut PropertyValue<'_>) {}
+
+    /// Visits a value being cleared by `UpdateEntity`.
+    fn visit_unset_value_mut(&mut self, unset: &mut UnsetValue) {}
+}
+
+/// Dispatches an op to the visitor method for its kind.
+pub fn walk_op_mut<V: OpVisitorMut + ?Sized>(visitor: &mut V, op: &mut Op<'_>) {
+    match op {
+        Op::CreateEntity(op) => visitor.visit_create_entity_mut(op),
+        Op::UpdateEntity(op) => visitor.visit_update_entity_mut(op),
+        Op::DeleteEntity(op) => visitor.visit_delete_entity_mut(op),
+        Op::RestoreEntity(op) => visitor.visit_restore_entity_mut(op),
+        Op::CreateRelation(op) => visitor.visit_create_relation_mut(op),
+        Op::UpdateRelation(op) => visitor.visit_update_relation_mut(op),
+        Op::DeleteRelation(op) => visitor.visit_delete_relation_mut(op),
+        Op::RestoreRelation(op) => visitor.visit_restore_relation_mut(op),
+        Op::CreateValueRef(op) => visitor.visit_create_value_ref_mut(op),
+        Op::CreateSpace(op) => visitor.visit_create_space_mut(op),
+        Op::AddMember(op) => visitor.visit_add_member_mut(op),
+        Op::RemoveMember(op) => visitor.visit_remove_member_mut(op),
+        Op::UpdateSpaceMetadata(op) => visitor.visit_update_space_metadata_mut(op),
+        Op::Unknown(op) => visitor.visit_unknown_mut(op),
+    }
+}
+
+/// Visits the values of a `CreateEntity`.
+pub fn walk_create_entity_mut<V: OpVisitorMut + ?Sized>(
+    visitor: &mut V,
+    op: &mut CreateEntity<'_>,
+) {
+    for pv in &mut op.values {
+        visitor.visit_property_value_mut(pv);
+    }
+}
+
+/// Visits the unset and set values of an `UpdateEntity`, in application
+/// order.
+pub fn walk_update_entity_mut<V: OpVisitorMut + ?Sized>(
+    visitor: &mut V,
+    op: &mut UpdateEntity<'_>,
+) {
+    for unset in &mut op.unset_values {
+        visitor.visit_unset_value_mut(unset);
+    }
+    for pv in &mut op.set_properties {
+        visitor.visit_property_value_mut(pv);
+    }
+}
+
+impl Edit<'_> {
+    /// Visits every op in order.
+    pub fn visit<V: OpVisitor + ?Sized>(&self, visitor: &mut V) {
+        for op in &self.ops {
+            visitor.visit_op(op);
+        }
+    }
+
+    /// Visits every op in order, allowing the visitor to modify them.
+    pub fn visit_mut<V: OpVisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        for op in &mut self.ops {
+            visitor.visit_op_mut(op);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[derive(Default)]
+    struct Counts {
+        entities: Vec<Id>,
+        texts: usize,
+        ints: i64,
+        relations: usize,
+        unsets: usize,
+    }
+
+    impl OpVisitor for Counts {
+        fn visit_create_entity(&mut self, op: &CreateEntity<'_>) {
+            self.entities.push(op.id);
+            walk_create_entity(self, op);
+        }
+
+        fn visit_create_relation(&mut self, _op: &CreateRelation<'_>) {
+            self.relations += 1;
+        }
+
+        fn visit_unset_value(&mut self, _unset: &UnsetValue) {
+            self.unsets += 1;
+        }
+
+        fn visit_text(&mut self, _property: Id, _value: &str, _language: Option<Id>) {
+            self.texts += 1;
+        }
+
+        fn visit_int64(&mut self, _property: Id, value: i64, _unit: Option<Id>) {
+            self.ints += value;
+        }
+    }
+
+    struct RenameProperty(Id, Id);
+
+    impl OpVisitorMut for RenameProperty {
+        fn visit_property_value_mut(&mut self, pv: &mut PropertyValue<'_>) {
+            if pv.property == self.0 {
+                pv.property = self.1;
+            }
+        }
+
+        fn visit_unset_value_mut(&mut self, unset: &mut UnsetValue) {
+            if unset.property == self.0 {
+                unset.property = self.1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_and_visit_mut() {
+        let mut edit = EditBuilder::new([0u8; 16])
+            .create_entity([1u8; 16], |e| e.text([10u8; 16], "a", None).int64([11u8; 16], 2, None))
+            .create_entity([2u8; 16], |e| e.text([10u8; 16], "b", None))
+            .update_entity([1u8; 16], |u| u.set_int64([11u8; 16], 3, None).unset_all([10u8; 16]))
+            .create_relation_simple([5u8; 16], [1u8; 16], [2u8; 16], [6u8; 16])
+            .build();
+
+        let mut counts = Counts::default();
+        edit.visit(&mut counts);
+        assert_eq!(counts.entities, vec![[1u8; 16], [2u8; 16]]);
+        assert_eq!((counts.texts, counts.ints), (2, 5));
+        assert_eq!((counts.relations, counts.unsets), (1, 1));
+
+        edit.visit_mut(&mut RenameProperty([10u8; 16], [12u8; 16]));
+        let Op::UpdateEntity(update) = &edit.ops[2] else { unreachable!() };
+        assert_eq!(update.unset_values[0].property, [12u8; 16]);
+        let Op::CreateEntity(create) = &edit.ops[1] else { unreachable!() };
+        assert_eq!(create.values[0].property, [12u8; 16]);
+    }
+}
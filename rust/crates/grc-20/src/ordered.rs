@@ -0,0 +1,211 @@
+//! Ordered relation lists in resolved state.
+//!
+//! The relations of one type from one entity form a list ordered by
+//! position (spec Section 2.6): relations with a position sort first, by
+//! position and then by relation ID, followed by relations without a
+//! position, by relation ID. [`GraphState::ordered_relations`] returns the
+//! list in that order, and [`GraphState::insert_at`] and
+//! [`GraphState::move_to`] compute the positions (and, when the list has to
+//! be rebalanced, the UpdateRelation ops) that put a relation at an index.
+
+use crate::model::position::{evenly_spaced, needs_rebalance, position_between};
+use crate::model::{Id, UpdateRelation};
+use crate::state::{GraphState, RelationState};
+
+/// Position for a new relation in an ordered list, from
+/// [`GraphState::insert_at`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListInsert {
+    /// Position to create the relation with.
+    pub position: String,
+    /// Ops repositioning existing relations, when there was no room for the
+    /// new one. Empty otherwise.
+    pub updates: Vec<UpdateRelation<'static>>,
+}
+
+impl GraphState {
+    /// Returns the live relations of `relation_type` from `from`, in list
+    /// order.
+    pub fn ordered_relations(&self, from: &Id, relation_type: &Id) -> Vec<(&Id, &RelationState)> {
+        let mut relations: Vec<_> = self
+            .relations()
+            .filter(|(_, r)| !r.deleted && r.from == *from && r.relation_type == *relation_type)
+            .collect();
+        relations.sort_by(|(a_id, a), (b_id, b)| {
+            let key_a = (a.position.is_none(), &a.position, a_id);
+            key_a.cmp(&(b.position.is_none(), &b.position, b_id))
+        });
+        relations
+    }
+
+    /// Returns the position that puts a new relation of `relation_type`
+    /// from `from` at `index` in the list. An index past the end appends.
+    ///
+    /// If the neighbors have no room in between, are unpositioned, or would
+    /// need a position long enough to [need
+    /// rebalancing](crate::model::position::needs_rebalance), the whole
+    /// list is respaced and `updates` holds the ops to apply along with the
+    /// new relation.
+    pub fn insert_at(&self, from: &Id, relation_type: &Id, index: usize) -> ListInsert {
+        let list: Vec<Id> =
+            self.ordered_relations(from, relation_type).into_iter().map(|(id, _)| *id).collect();
+        self.insert_into(&list, index)
+    }
+
+    /// Returns the ops that move a live relation to `index` in its list.
+    /// An index past the end moves it last.
+    ///
+    /// Returns None if the relation is unknown or tombstoned.
+    pub fn move_to(&self, relation: &Id, index: usize) -> Option<Vec<UpdateRelation<'static>>> {
+        let r = self.relation(relation).filter(|r| !r.deleted)?;
+        let list: Vec<Id> = self
+            .ordered_relations(&r.from, &r.relation_type)
+            .into_iter()
+            .map(|(id, _)| *id)
+            .filter(|id| id != relation)
+            .collect();
+        let ListInsert { position, mut updates } = self.insert_into(&list, index);
+        if r.position.as_deref() != Some(position.as_str()) {
+            updates.push(set_position(*relation, position));
+        }
+        Some(updates)
+    }
+
+    /// Computes the position for index `index` of `list`, a list of
+    /// relation IDs in order.
+    fn insert_into(&self, list: &[Id], index: usize) -> ListInsert {
+        let index = index.min(list.len());
+        let position_of = |id: &Id| self.relation(id).and_then(|r| r.position.as_deref());
+        let after = list.get(index).and_then(position_of);
+        // After an unpositioned relation, only a rebalance can place it
+        let before = match index.checked_sub(1).map(|i| position_of(&list[i])) {
+            None => Some(None),
+            Some(Some(position)) => Some(Some(position)),
+            Some(None) => None,
+        };
+        if let Some(Ok(position)) = before.map(|before| position_between(before, after)) {
+            if !needs_rebalance(&position) {
+                return ListInsert { position, updates: Vec::new() };
+            }
+        }
+
+        let mut positions = evenly_spaced(list.len() + 1);
+        let position = positions.remove(index);
+        let updates = list
+            .iter()
+            .zip(positions)
+            .filter(|(id, new)| position_of(id) != Some(new.as_str()))
+            .map(|(id, new)| set_position(*id, new))
+            .collect();
+        ListInsert { position, updates }
+    }
+}
+
+fn set_position(id: Id, position: String) -> UpdateRelation<'static> {
+    UpdateRelation {
+        id,
+        from_space: None,
+        from_version: None,
+        to_space: None,
+        to_version: None,
+        position: Some(position.into()),
+        unset: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EditBuilder, Op};
+
+    const LIST: Id = [1u8; 16];
+    const ITEM: Id = [2u8; 16];
+    const HAS: Id = [0xA1; 16];
+
+    fn relation_id(n: u8) -> Id {
+        [0x10 + n; 16]
+    }
+
+    fn state(positions: &[Option<&'static str>]) -> GraphState {
+        let mut edit = EditBuilder::new([0xE1; 16]);
+        for (n, position) in positions.iter().enumerate() {
+            edit = edit.create_relation(|r| {
+                let r = r.id(relation_id(n as u8)).from(LIST).to(ITEM).relation_type(HAS);
+                match position {
+                    Some(position) => r.position(*position),
+                    None => r,
+                }
+            });
+        }
+        let mut state = GraphState::new();
+        state.apply_edit(&edit.build());
+        state
+    }
+
+    fn order(state: &GraphState) -> Vec<Id> {
+        state.ordered_relations(&LIST, &HAS).into_iter().map(|(id, _)| *id).collect()
+    }
+
+    fn apply(state: &mut GraphState, updates: Vec<UpdateRelation<'static>>) {
+        for update in updates {
+            state.apply_op(&Op::UpdateRelation(update));
+        }
+    }
+
+    #[test]
+    fn test_ordered_relations() {
+        let state = state(&[Some("n"), None, Some("a"), Some("n"), None]);
+        let expected: Vec<Id> = [2, 0, 3, 1, 4].into_iter().map(relation_id).collect();
+        assert_eq!(order(&state), expected);
+        assert!(state.ordered_relations(&ITEM, &HAS).is_empty());
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let state = state(&[Some("a"), Some("b"), None]);
+        let insert = |index| state.insert_at(&LIST, &HAS, index);
+        assert_eq!(insert(0), ListInsert { position: "I".into(), updates: vec![] });
+        assert_eq!(insert(1), ListInsert { position: "aV".into(), updates: vec![] });
+        assert_eq!(insert(2).position, "o");
+        assert!(insert(2).updates.is_empty());
+
+        // After the unpositioned relation: the whole list is respaced
+        let ListInsert { position, updates } = insert(10);
+        assert_eq!(position, "n");
+        let updated: Vec<(Id, String)> =
+            updates.iter().map(|u| (u.id, u.position.as_deref().unwrap().into())).collect();
+        assert_eq!(
+            updated,
+            vec![
+                (relation_id(0), "C".to_string()),
+                (relation_id(1), "O".to_string()),
+                (relation_id(2), "b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_move_to() {
+        let mut state = state(&[Some("a"), Some("b"), Some("c")]);
+        let updates = state.move_to(&relation_id(2), 0).unwrap();
+        assert_eq!(updates.len(), 1);
+        apply(&mut state, updates);
+        assert_eq!(order(&state), vec![relation_id(2), relation_id(0), relation_id(1)]);
+
+        // Already in place
+        assert_eq!(state.move_to(&relation_id(2), 0), Some(vec![]));
+        assert_eq!(state.move_to(&[0xFF; 16], 0), None);
+
+        // Repeated moves between the same neighbors end with a rebalance
+        let mut rebalances = 0;
+        for n in 0..1000usize {
+            let moved = relation_id((n % 2) as u8);
+            let updates = state.move_to(&moved, 1).unwrap();
+            rebalances += usize::from(updates.len() > 1);
+            apply(&mut state, updates);
+            assert_eq!(order(&state)[1], moved);
+        }
+        assert!(rebalances > 0);
+        assert!(state.relations().all(|(_, r)| !needs_rebalance(r.position.as_deref().unwrap())));
+    }
+}
@@ -0,0 +1,352 @@
+//! Edit propagation over gossipsub.
+//!
+//! Peers that sync a space without a central publisher gossip its edits on
+//! a per-space topic ([`topic`]). Each message carries one compressed edit
+//! and the CID it is addressed by on IPFS:
+//!
+//! ```text
+//! cid: CID bytes (CIDv1, SHA-256)
+//! edit: encoded edit (GRC2Z from EditMessage::new)
+//! ```
+//!
+//! The CID makes messages content-addressed: [`message_id`] uses it as the
+//! gossipsub message ID, so the mesh drops copies of an edit however many
+//! peers publish it, and [`GossipValidator`] checks it and skips edits it
+//! has already seen before decoding them. Edits are encoded canonically,
+//! so every peer publishing the same edit produces the same CID.
+//!
+//! The module is transport-agnostic. With `libp2p-gossipsub`, wire it up
+//! with validation mode on and manual propagation:
+//!
+//! ```text
+//! let config = gossipsub::ConfigBuilder::default()
+//!     .validate_messages()
+//!     .message_id_fn(|m| MessageId::new(&grc_20::p2p::message_id(&m.data)))
+//!     .build()?;
+//! gossipsub.subscribe(&IdentTopic::new(grc_20::p2p::topic(&space)))?;
+//!
+//! // On gossipsub::Event::Message { propagation_source, message_id, message }:
+//! let verdict = validator.validate(&message.data);
+//! let acceptance = match verdict.acceptance() {
+//!     Acceptance::Accept => MessageAcceptance::Accept,
+//!     Acceptance::Ignore => MessageAcceptance::Ignore,
+//!     Acceptance::Reject => MessageAcceptance::Reject,
+//! };
+//! gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance);
+//! ```
+//!
+//! Gossipsub limits message size (64 KiB by default); larger edits should
+//! be published to IPFS and announced by CID instead.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::codec::edit::{decode_edit, EncodeOptions};
+use crate::codec::primitives::Reader;
+use crate::compression::{encode_edit_with_compression, CompressionOptions};
+use crate::error::{DecodeError, EncodeError, P2pError};
+use crate::ipfs::{encoded_cid, Cid};
+use crate::model::id::format_id;
+use crate::model::{Edit, Id};
+use crate::validate::{validate_edit, SchemaContext};
+
+/// Number of CIDs a [`GossipValidator`] remembers by default.
+pub const DEFAULT_SEEN_CAPACITY: usize = 16 * 1024;
+
+/// Returns the gossipsub topic for a space's edits.
+pub fn topic(space: &Id) -> String {
+    format!("/grc20/space/{}/edits", format_id(space))
+}
+
+/// Returns the gossipsub message ID for a message: its CID bytes, or the
+/// SHA-256 of the data if it doesn't start with a CID.
+pub fn message_id(data: &[u8]) -> Vec<u8> {
+    let mut reader = Reader::new(data);
+    match Cid::read(&mut reader) {
+        Ok(cid) => cid.to_bytes(),
+        Err(_) => Sha256::digest(data).to_vec(),
+    }
+}
+
+/// A compressed edit and its CID, as gossiped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditMessage {
+    /// CID of `edit`, as [`encoded_to_car`](crate::ipfs::encoded_to_car)
+    /// addresses it.
+    pub cid: Cid,
+    /// Encoded edit.
+    pub edit: Vec<u8>,
+}
+
+impl EditMessage {
+    /// Encodes an edit canonically with zstd compression.
+    pub fn new(edit: &Edit) -> Result<Self, EncodeError> {
+        let options = CompressionOptions::zstd(crate::compression::DEFAULT_ZSTD_LEVEL)
+            .with_encode_options(EncodeOptions::canonical());
+        Ok(Self::from_encoded(encode_edit_with_compression(edit, options)?))
+    }
+
+    /// Wraps an already-encoded edit (in any framing).
+    pub fn from_encoded(edit: Vec<u8>) -> Self {
+        Self { cid: encoded_cid(&edit), edit }
+    }
+
+    /// Returns the message bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.cid.to_bytes();
+        bytes.extend_from_slice(&self.edit);
+        bytes
+    }
+
+    /// Reads a message, checking the edit against its CID.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, P2pError> {
+        let mut reader = Reader::new(data);
+        let cid = Cid::read(&mut reader)
+            .map_err(|_| P2pError::Malformed { reason: "message does not start with a CID" })?;
+        let edit = reader.remaining().to_vec();
+        if encoded_cid(&edit) != cid {
+            return Err(P2pError::CidMismatch { cid });
+        }
+        Ok(Self { cid, edit })
+    }
+
+    /// Decodes the edit.
+    pub fn decode_edit(&self) -> Result<Edit<'_>, DecodeError> {
+        decode_edit(&self.edit)
+    }
+}
+
+/// What to tell gossipsub about a message; the same as gossipsub's
+/// `MessageAcceptance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acceptance {
+    /// Deliver and forward the message.
+    Accept,
+    /// Drop the message without penalizing the sender.
+    Ignore,
+    /// Drop the message and penalize the sender.
+    Reject,
+}
+
+/// Outcome of validating a gossiped message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// A new, valid edit.
+    Accept { cid: Cid, edit: Edit<'static> },
+    /// An edit already seen, or one a hook ignored.
+    Ignore { cid: Cid },
+    /// A malformed or invalid message.
+    Reject(P2pError),
+}
+
+impl Verdict {
+    /// Returns the acceptance to report to gossipsub.
+    pub fn acceptance(&self) -> Acceptance {
+        match self {
+            Verdict::Accept { .. } => Acceptance::Accept,
+            Verdict::Ignore { .. } => Acceptance::Ignore,
+            Verdict::Reject(_) => Acceptance::Reject,
+        }
+    }
+}
+
+/// A check run on each new, schema-valid edit.
+pub type ValidationHook = Box<dyn Fn(&Cid, &Edit<'_>) -> Acceptance + Send + Sync>;
+
+/// Validates gossiped edits and drops those already seen.
+///
+/// A message is rejected if it is malformed, its edit doesn't match its
+/// CID, or the edit doesn't decode or fails [`validate_edit`]; hooks can
+/// then reject or ignore it on other grounds (say, an author who may not
+/// write to the space). The CIDs of the most recent messages are
+/// remembered, and a message with one of them is ignored before decoding.
+pub struct GossipValidator {
+    schema: SchemaContext,
+    hooks: Vec<ValidationHook>,
+    seen: HashSet<Cid>,
+    order: VecDeque<Cid>,
+    capacity: usize,
+}
+
+impl GossipValidator {
+    /// Creates a validator checking edits against `schema`.
+    pub fn new(schema: SchemaContext) -> Self {
+        Self {
+            schema,
+            hooks: Vec::new(),
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity: DEFAULT_SEEN_CAPACITY,
+        }
+    }
+
+    /// Sets how many CIDs are remembered (at least 1).
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Adds a hook, run in order after the schema check. The first that
+    /// doesn't accept decides the verdict.
+    pub fn with_hook(
+        mut self,
+        hook: impl Fn(&Cid, &Edit<'_>) -> Acceptance + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Returns true if a message with this CID was seen recently.
+    pub fn has_seen(&self, cid: &Cid) -> bool {
+        self.seen.contains(cid)
+    }
+
+    /// Validates a message.
+    pub fn validate(&mut self, data: &[u8]) -> Verdict {
+        let message = match EditMessage::from_bytes(data) {
+            Ok(message) => message,
+            Err(e) => return Verdict::Reject(e),
+        };
+        let cid = message.cid;
+        if !self.remember(cid) {
+            return Verdict::Ignore { cid };
+        }
+        let edit = match decode_edit(&message.edit) {
+            Ok(edit) => owned(edit),
+            Err(e) => return Verdict::Reject(e.into()),
+        };
+        if let Err(e) = validate_edit(&edit, &self.schema) {
+            return Verdict::Reject(e.into());
+        }
+        match self.hooks.iter().map(|hook| hook(&cid, &edit)).find(|a| *a != Acceptance::Accept) {
+            Some(Acceptance::Reject) => Verdict::Reject(P2pError::Rejected { cid }),
+            Some(_) => Verdict::Ignore { cid },
+            None => Verdict::Accept { cid, edit },
+        }
+    }
+
+    /// Records a CID, evicting the oldest past capacity. Returns false if
+    /// it was already recorded.
+    fn remember(&mut self, cid: Cid) -> bool {
+        if !self.seen.insert(cid) {
+            return false;
+        }
+        self.order.push_back(cid);
+        if self.order.len() > self.capacity {
+            let oldest = self.order.pop_front().expect("over capacity");
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+}
+
+impl fmt::Debug for GossipValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GossipValidator")
+            .field("hooks", &self.hooks.len())
+            .field("seen", &self.seen.len())
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Detaches a decoded edit from the message bytes.
+fn owned(edit: Edit<'_>) -> Edit<'static> {
+    Edit {
+        id: edit.id,
+        name: edit.name.into_owned().into(),
+        authors: edit.authors,
+        created_at: edit.created_at,
+        provenance: edit.provenance.map(|p| p.into_owned()),
+        ops: edit.ops.into_iter().map(crate::codec::edit::op_to_owned).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{DataType, EditBuilder};
+    use crate::ValidationError;
+
+    const NAME: Id = [1u8; 16];
+
+    fn edit(n: u8) -> Edit<'static> {
+        EditBuilder::new([n; 16])
+            .author([0xAA; 16])
+            .create_entity([0x10 + n; 16], |e| e.text(NAME, format!("Entity {}", n), None))
+            .build()
+    }
+
+    fn schema() -> SchemaContext {
+        let mut schema = SchemaContext::new();
+        schema.add_property(NAME, DataType::Text);
+        schema
+    }
+
+    #[test]
+    fn test_message_roundtrip() {
+        let message = EditMessage::new(&edit(1)).unwrap();
+        let bytes = message.to_bytes();
+        assert_eq!(EditMessage::from_bytes(&bytes).unwrap(), message);
+        assert_eq!(message.decode_edit().unwrap(), edit(1));
+        assert_eq!(message_id(&bytes), message.cid.to_bytes());
+        assert_eq!(message_id(b"junk"), Sha256::digest(b"junk").to_vec());
+        assert_eq!(topic(&[0xAB; 16]), format!("/grc20/space/{}/edits", "ab".repeat(16)));
+
+        // Canonical encoding: value order doesn't change the CID
+        let a = EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.text(NAME, "a", None).int64([3u8; 16], 1, None))
+            .build();
+        let b = EditBuilder::new([1u8; 16])
+            .create_entity([2u8; 16], |e| e.int64([3u8; 16], 1, None).text(NAME, "a", None))
+            .build();
+        assert_eq!(EditMessage::new(&a).unwrap().cid, EditMessage::new(&b).unwrap().cid);
+    }
+
+    #[test]
+    fn test_validator_verdicts() {
+        let mut validator =
+            GossipValidator::new(schema()).with_capacity(2).with_hook(|_, edit| match edit.id {
+                [3u8, ..] => Acceptance::Ignore,
+                [4u8, ..] => Acceptance::Reject,
+                _ => Acceptance::Accept,
+            });
+        let bytes = |n| EditMessage::new(&edit(n)).unwrap().to_bytes();
+
+        let Verdict::Accept { cid, edit: accepted } = validator.validate(&bytes(1)) else {
+            panic!("expected accept");
+        };
+        assert_eq!(accepted, edit(1));
+        assert_eq!(validator.validate(&bytes(1)), Verdict::Ignore { cid });
+        assert_eq!(validator.validate(&bytes(3)).acceptance(), Acceptance::Ignore);
+        assert!(matches!(
+            validator.validate(&bytes(4)),
+            Verdict::Reject(P2pError::Rejected { .. })
+        ));
+        // Capacity 2: edit 1 was forgotten
+        assert!(!validator.has_seen(&cid));
+        assert_eq!(validator.validate(&bytes(1)).acceptance(), Acceptance::Accept);
+
+        // Tampered edit bytes
+        let mut tampered = bytes(5);
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(
+            validator.validate(&tampered),
+            Verdict::Reject(P2pError::CidMismatch { .. })
+        ));
+        assert!(matches!(validator.validate(b"junk"), Verdict::Reject(P2pError::Malformed { .. })));
+
+        // Schema violation: NAME is TEXT
+        let wrong = EditBuilder::new([6u8; 16])
+            .create_entity([0x16; 16], |e| e.int64(NAME, 1, None))
+            .build();
+        let verdict = validator.validate(&EditMessage::new(&wrong).unwrap().to_bytes());
+        assert!(matches!(
+            verdict,
+            Verdict::Reject(P2pError::Validation(ValidationError::TypeMismatch { .. }))
+        ));
+    }
+}
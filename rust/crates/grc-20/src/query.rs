@@ -0,0 +1,381 @@
+//! Filtered entity lookups over resolved state.
+//!
+//! [`GraphState::query`] starts a [`Query`]; each filter narrows it, and
+//! [`Query::ids`] yields the IDs of the live entities that pass every
+//! filter:
+//!
+//! ```ignore
+//! let people_in_paris: Vec<Id> = state
+//!     .query()
+//!     .of_type(person)
+//!     .related_to(lives_in, paris)
+//!     .value_range(age, Value::Int64 { value: 18, unit: None }..)
+//!     .ids()
+//!     .collect();
+//! ```
+//!
+//! Only live objects count: tombstoned entities are never returned, and
+//! tombstoned relations match no relation filter. [`Query::of_type`] also
+//! matches instances of subtypes, declared with SubtypeOf relations. There
+//! are no indexes, so a query scans the relations once when relation
+//! filters are present and then the entities, lazily.
+//!
+//! Value filters match if any value of the property passes (a property
+//! may have a value per language). TEXT values compare by their text,
+//! whatever their language. Ranges order values of the same data type:
+//! INT64 and FLOAT64 numerically (also against each other, when their
+//! units are equal), TEXT, DATE, and TIME lexically, DATETIME by instant,
+//! and BOOL with false first. Other data types, and values of different
+//! data types, fall outside every range.
+
+use core::cmp::Ordering;
+use core::ops::{Bound, RangeBounds};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::genesis::relation_types;
+use crate::model::timestamp::parse_datetime;
+use crate::model::{Id, Value};
+use crate::state::{EntityState, GraphState};
+
+/// A filtered lookup of entities, from [`GraphState::query`].
+#[derive(Debug, Clone)]
+pub struct Query<'s> {
+    state: &'s GraphState,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    /// A live Types relation from the entity to the type or a subtype.
+    OfType(Id),
+    /// A live relation of this type from the entity to `to`.
+    RelatedTo { relation_type: Id, to: Id },
+    /// A live relation of this type from `from` to the entity.
+    RelatedFrom { relation_type: Id, from: Id },
+    /// A value of the property equal to `value`.
+    ValueEq { property: Id, value: Value<'static> },
+    /// A value of the property within the bounds.
+    ValueRange { property: Id, start: Bound<Value<'static>>, end: Bound<Value<'static>> },
+}
+
+impl<'s> Query<'s> {
+    pub(crate) fn new(state: &'s GraphState) -> Self {
+        Self { state, filters: Vec::new() }
+    }
+
+    /// Keeps instances of `type_id`: entities with a Types relation to it
+    /// or to one of its subtypes (see [`GraphState::is_instance_of`]).
+    pub fn of_type(mut self, type_id: Id) -> Self {
+        self.filters.push(Filter::OfType(type_id));
+        self
+    }
+
+    /// Keeps entities with a relation of `relation_type` to `to`.
+    pub fn related_to(mut self, relation_type: Id, to: Id) -> Self {
+        self.filters.push(Filter::RelatedTo { relation_type, to });
+        self
+    }
+
+    /// Keeps entities with a relation of `relation_type` from `from`.
+    pub fn related_from(mut self, relation_type: Id, from: Id) -> Self {
+        self.filters.push(Filter::RelatedFrom { relation_type, from });
+        self
+    }
+
+    /// Keeps entities with a value of `property` equal to `value`.
+    pub fn value_eq(mut self, property: Id, value: Value<'_>) -> Self {
+        self.filters.push(Filter::ValueEq { property, value: value.into_owned() });
+        self
+    }
+
+    /// Keeps entities with a value of `property` within `range`.
+    pub fn value_range<'v>(mut self, property: Id, range: impl RangeBounds<Value<'v>>) -> Self {
+        let owned = |bound: Bound<&Value<'v>>| bound.map(|value| value.clone().into_owned());
+        self.filters.push(Filter::ValueRange {
+            property,
+            start: owned(range.start_bound()),
+            end: owned(range.end_bound()),
+        });
+        self
+    }
+
+    /// Iterates over the IDs of the matching entities, in no particular
+    /// order.
+    pub fn ids(self) -> impl Iterator<Item = Id> + 's {
+        let related = self.related_sets();
+        let Query { state, filters } = self;
+        state.entities().filter_map(move |(id, entity)| {
+            let matches = !entity.deleted
+                && related.iter().all(|set| set.contains(id))
+                && filters.iter().all(|filter| value_matches(filter, entity));
+            matches.then_some(*id)
+        })
+    }
+
+    /// Collects, for each relation filter, the entities it admits.
+    fn related_sets(&self) -> Vec<FxHashSet<Id>> {
+        let relation_filters: Vec<&Filter> =
+            self.filters.iter().filter(|f| !is_value_filter(f)).collect();
+        let mut sets = vec![FxHashSet::default(); relation_filters.len()];
+        if relation_filters.is_empty() {
+            return sets;
+        }
+        let types = relation_types::types();
+        let subtypes: Vec<Option<FxHashSet<Id>>> = relation_filters
+            .iter()
+            .map(|f| match f {
+                Filter::OfType(type_id) => Some(self.state.subtypes(type_id)),
+                _ => None,
+            })
+            .collect();
+        for (_, relation) in self.state.relations().filter(|(_, r)| !r.deleted) {
+            for ((filter, set), subtypes) in relation_filters.iter().zip(&mut sets).zip(&subtypes) {
+                match filter {
+                    Filter::OfType(_)
+                        if relation.relation_type == types
+                            && !relation.from_is_value_ref
+                            && subtypes.as_ref().is_some_and(|s| s.contains(&relation.to)) =>
+                    {
+                        set.insert(relation.from);
+                    }
+                    Filter::RelatedTo { relation_type, to }
+                        if relation.relation_type == *relation_type
+                            && relation.to == *to
+                            && !relation.from_is_value_ref =>
+                    {
+                        set.insert(relation.from);
+                    }
+                    Filter::RelatedFrom { relation_type, from }
+                        if relation.relation_type == *relation_type
+                            && relation.from == *from
+                            && !relation.to_is_value_ref =>
+                    {
+                        set.insert(relation.to);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        sets
+    }
+}
+
+impl GraphState {
+    /// Starts a filtered lookup of entities. See [`Query`].
+    pub fn query(&self) -> Query<'_> {
+        Query::new(self)
+    }
+
+    /// Returns true if a live Types relation gives `entity` the type
+    /// `type_id` or one of its subtypes.
+    ///
+    /// Subtypes are declared with live SubtypeOf relations from a type to
+    /// the type it specializes, and are resolved transitively.
+    pub fn is_instance_of(&self, entity: &Id, type_id: &Id) -> bool {
+        let types = relation_types::types();
+        let subtypes = self.subtypes(type_id);
+        self.relations().any(|(_, r)| {
+            !r.deleted
+                && r.relation_type == types
+                && r.from == *entity
+                && !r.from_is_value_ref
+                && subtypes.contains(&r.to)
+        })
+    }
+
+    /// Returns `type_id` and every type that is, transitively, a SubtypeOf
+    /// it. Cycles in the hierarchy are tolerated.
+    pub fn subtypes(&self, type_id: &Id) -> FxHashSet<Id> {
+        let subtype_of = relation_types::subtype_of();
+        let mut children: FxHashMap<Id, Vec<Id>> = FxHashMap::default();
+        for (_, r) in self.relations() {
+            if !r.deleted
+                && r.relation_type == subtype_of
+                && !r.from_is_value_ref
+                && !r.to_is_value_ref
+            {
+                children.entry(r.to).or_default().push(r.from);
+            }
+        }
+        let mut subtypes = FxHashSet::from_iter([*type_id]);
+        let mut stack = vec![*type_id];
+        while let Some(current) = stack.pop() {
+            for child in children.get(&current).into_iter().flatten() {
+                if subtypes.insert(*child) {
+                    stack.push(*child);
+                }
+            }
+        }
+        subtypes
+    }
+}
+
+fn is_value_filter(filter: &Filter) -> bool {
+    matches!(filter, Filter::ValueEq { .. } | Filter::ValueRange { .. })
+}
+
+/// Checks a value filter; relation filters are checked separately.
+fn value_matches(filter: &Filter, entity: &EntityState) -> bool {
+    match filter {
+        Filter::OfType(_) | Filter::RelatedTo { .. } | Filter::RelatedFrom { .. } => true,
+        Filter::ValueEq { property, value } => {
+            entity.values_of(property).any(|v| values_equal(v, value))
+        }
+        Filter::ValueRange { property, start, end } => entity.values_of(property).any(|v| {
+            let above = match start {
+                Bound::Included(start) => compare_values(v, start).is_some_and(|o| o.is_ge()),
+                Bound::Excluded(start) => compare_values(v, start).is_some_and(|o| o.is_gt()),
+                Bound::Unbounded => compare_values(v, v).is_some(),
+            };
+            let below = match end {
+                Bound::Included(end) => compare_values(v, end).is_some_and(|o| o.is_le()),
+                Bound::Excluded(end) => compare_values(v, end).is_some_and(|o| o.is_lt()),
+                Bound::Unbounded => true,
+            };
+            above && below
+        }),
+    }
+}
+
+fn values_equal(a: &Value<'_>, b: &Value<'_>) -> bool {
+    match (a, b) {
+        (Value::Text { value: a, .. }, Value::Text { value: b, .. }) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Orders two values for range filters; None if they are not comparable.
+fn compare_values(a: &Value<'_>, b: &Value<'_>) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Int64 { value: a, unit: ua }, Value::Int64 { value: b, unit: ub }) if ua == ub => {
+            Some(a.cmp(b))
+        }
+        (Value::Float64 { value: a, unit: ua }, Value::Float64 { value: b, unit: ub })
+            if ua == ub =>
+        {
+            a.partial_cmp(b)
+        }
+        (Value::Int64 { value: a, unit: ua }, Value::Float64 { value: b, unit: ub })
+            if ua == ub =>
+        {
+            (*a as f64).partial_cmp(b)
+        }
+        (Value::Float64 { value: a, unit: ua }, Value::Int64 { value: b, unit: ub })
+            if ua == ub =>
+        {
+            a.partial_cmp(&(*b as f64))
+        }
+        (Value::Text { value: a, .. }, Value::Text { value: b, .. }) => Some(a.cmp(b)),
+        (Value::Date(a), Value::Date(b)) | (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+        (Value::Datetime(a), Value::Datetime(b)) => {
+            let (a, _) = parse_datetime(a)?;
+            let (b, _) = parse_datetime(b)?;
+            Some(a.cmp(&b))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const A: Id = [1u8; 16];
+    const B: Id = [2u8; 16];
+    const C: Id = [3u8; 16];
+    const PERSON: Id = [4u8; 16];
+    const AGE: Id = [5u8; 16];
+    const NAME: Id = [6u8; 16];
+    const KNOWS: Id = [7u8; 16];
+    const LANG: Id = [8u8; 16];
+    const AGENT: Id = [9u8; 16];
+
+    fn sorted(query: Query<'_>) -> Vec<Id> {
+        let mut ids: Vec<Id> = query.ids().collect();
+        ids.sort();
+        ids
+    }
+
+    fn int(value: i64) -> Value<'static> {
+        Value::Int64 { value, unit: None }
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let types = relation_types::types();
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_entity(A, |e| e.int64(AGE, 30, None).text(NAME, "Ada", Some(LANG)))
+            .create_entity(B, |e| e.float64(AGE, 17.5, None).text(NAME, "Bob", None))
+            .create_entity(C, |e| e.text(AGE, "unknown", None))
+            .create_relation_simple([0x11; 16], A, PERSON, types)
+            .create_relation_simple([0x12; 16], B, PERSON, types)
+            .create_relation_simple([0x13; 16], A, B, KNOWS)
+            .create_relation_simple([0x14; 16], C, B, KNOWS)
+            .build();
+        let mut state = GraphState::new();
+        state.apply_edit(&edit);
+
+        assert_eq!(sorted(state.query().of_type(PERSON)), vec![A, B]);
+        assert!(sorted(state.query().of_type(AGENT)).is_empty());
+        assert_eq!(sorted(state.query().related_to(KNOWS, B)), vec![A, C]);
+        assert_eq!(sorted(state.query().related_from(KNOWS, A)), vec![B]);
+        assert_eq!(sorted(state.query().of_type(PERSON).related_to(KNOWS, B)), vec![A]);
+
+        // TEXT equality ignores the language
+        let ada = Value::Text { value: "Ada".into(), language: None };
+        assert_eq!(sorted(state.query().value_eq(NAME, ada)), vec![A]);
+        assert_eq!(sorted(state.query().value_eq(AGE, int(30))), vec![A]);
+
+        // INT64 and FLOAT64 compare numerically; TEXT is outside numeric ranges
+        assert_eq!(sorted(state.query().value_range(AGE, int(18)..)), vec![A]);
+        assert_eq!(sorted(state.query().value_range(AGE, ..int(30))), vec![B]);
+        assert_eq!(sorted(state.query().value_range(AGE, ..=int(30))), vec![A, B]);
+        assert_eq!(sorted(state.query().value_range(AGE, ..)), vec![A, B, C]);
+
+        // Tombstones hide entities and relations
+        let edit = EditBuilder::new([0xE2; 16]).delete_entity(A).delete_relation([0x14; 16]).build();
+        state.apply_edit(&edit);
+        assert_eq!(sorted(state.query().of_type(PERSON)), vec![B]);
+        assert!(sorted(state.query().related_to(KNOWS, B)).is_empty());
+    }
+
+    #[test]
+    fn test_subtypes() {
+        let types = relation_types::types();
+        let subtype_of = relation_types::subtype_of();
+        const EMPLOYEE: Id = [0x20; 16];
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_empty_entity(A)
+            .create_empty_entity(B)
+            .create_relation_simple([0x11; 16], A, EMPLOYEE, types)
+            .create_relation_simple([0x12; 16], B, PERSON, types)
+            .create_relation_simple([0x13; 16], EMPLOYEE, PERSON, subtype_of)
+            .create_relation_simple([0x14; 16], PERSON, AGENT, subtype_of)
+            .create_relation_simple([0x15; 16], AGENT, PERSON, subtype_of)
+            .build();
+        let mut state = GraphState::new();
+        state.apply_edit(&edit);
+
+        assert!(state.is_instance_of(&A, &AGENT));
+        assert!(state.is_instance_of(&A, &EMPLOYEE));
+        assert!(!state.is_instance_of(&B, &EMPLOYEE));
+        assert_eq!(sorted(state.query().of_type(AGENT)), vec![A, B]);
+        assert_eq!(sorted(state.query().of_type(EMPLOYEE)), vec![A]);
+
+        // Tombstoned SubtypeOf relations no longer count
+        state.apply_edit(&EditBuilder::new([0xE2; 16]).delete_relation([0x13; 16]).build());
+        assert!(!state.is_instance_of(&A, &PERSON));
+        assert_eq!(sorted(state.query().of_type(PERSON)), vec![B]);
+    }
+
+    #[test]
+    fn test_compare_datetimes() {
+        let utc = Value::Datetime("2024-01-01T09:00:00Z".into());
+        let tokyo = Value::Datetime("2024-01-01T18:00:00+09:00".into());
+        assert_eq!(compare_values(&utc, &tokyo), Some(Ordering::Equal));
+        assert_eq!(compare_values(&utc, &Value::Date("2024-01-01".into())), None);
+    }
+}
@@ -0,0 +1,479 @@
+//! Op application as a pure function.
+//!
+//! [`reduce`] states what a single op does to resolved state, without
+//! owning any state itself: it reads the objects the op touches through a
+//! [`StateView`] and returns the resulting [`StateDelta`]. [`GraphState`]
+//! applies every op this way, so an indexer with its own storage that
+//! implements [`StateView`] and applies the deltas converges on the same
+//! state.
+//!
+//! The rules (spec Sections 3 and 8.2):
+//!
+//! - Values are last-writer-wins per (property, language); only TEXT
+//!   values have a language. An UpdateEntity applies its unsets first,
+//!   then its removals by value ID, then its sets, and a later set of a
+//!   slot in the same op wins.
+//! - CreateEntity of an existing entity acts as an update; of a deleted
+//!   entity it is ignored. UpdateEntity of a missing or deleted entity is
+//!   ignored.
+//! - Delete and restore flip the tombstone of an existing entity or
+//!   relation, and are ignored otherwise. A tombstoned entity keeps its
+//!   values, so a restore reveals them again.
+//! - Relations are immutable apart from their pins and position. A
+//!   CreateRelation of an existing relation is ignored; otherwise the
+//!   relation's reified entity is created if it does not exist yet.
+//! - Entities, relations, and value refs share one ID namespace: a create
+//!   that collides with an object of another kind is ignored.
+//! - A value ref binds its slot last-writer-wins.
+//! - Space ops and extension ops ([`Op::Unknown`]) have no effect on the
+//!   graph; see [`SpaceRegistry`] for the former.
+//!
+//! Deltas only carry actual changes, so an ignored op, or one that sets
+//! values to what they already are, reduces to an empty delta.
+//!
+//! [`SpaceRegistry`]: crate::model::SpaceRegistry
+
+use crate::model::id::relation_entity_id;
+use crate::model::{
+    CreateRelation, Id, Op, PropertyValue, UnsetLanguage, UnsetRelationField, Value,
+};
+use crate::state::{EntityState, GraphState, RelationState, ValueKey, ValueSlot};
+
+/// Read access to resolved state, as needed by [`reduce`].
+pub trait StateView {
+    /// Returns an entity's state, including tombstoned entities.
+    fn entity(&self, id: &Id) -> Option<&EntityState>;
+
+    /// Returns a relation's state, including tombstoned relations.
+    fn relation(&self, id: &Id) -> Option<&RelationState>;
+
+    /// Returns the value ref ID bound to a slot.
+    fn value_ref(&self, slot: &ValueSlot) -> Option<Id>;
+
+    /// Returns true if `id` is bound to at least one value slot.
+    fn is_value_ref(&self, id: &Id) -> bool;
+}
+
+impl StateView for GraphState {
+    fn entity(&self, id: &Id) -> Option<&EntityState> {
+        GraphState::entity(self, id)
+    }
+
+    fn relation(&self, id: &Id) -> Option<&RelationState> {
+        GraphState::relation(self, id)
+    }
+
+    fn value_ref(&self, slot: &ValueSlot) -> Option<Id> {
+        GraphState::value_ref(self, slot)
+    }
+
+    fn is_value_ref(&self, id: &Id) -> bool {
+        GraphState::is_value_ref(self, id)
+    }
+}
+
+/// A single change to resolved state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    /// Adds an entity with no values.
+    CreateEntity(Id),
+    /// Sets a value slot of an entity.
+    SetValue { entity: Id, key: ValueKey, value: Value<'static> },
+    /// Clears a value slot of an entity.
+    RemoveValue { entity: Id, key: ValueKey },
+    /// Sets or clears an entity's tombstone.
+    SetEntityDeleted { entity: Id, deleted: bool },
+    /// Adds a relation, or replaces its state.
+    PutRelation { id: Id, relation: RelationState },
+    /// Sets or clears a relation's tombstone.
+    SetRelationDeleted { id: Id, deleted: bool },
+    /// Binds a value slot to a value ref, replacing any previous binding.
+    BindValueRef { slot: ValueSlot, id: Id },
+}
+
+/// The changes an op makes, in application order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateDelta {
+    /// The changes.
+    pub changes: Vec<StateChange>,
+}
+
+impl StateDelta {
+    /// Returns true if the op changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Returns the changes `op` makes to `state`.
+///
+/// See the module docs for the rules.
+pub fn reduce(op: &Op<'_>, state: &impl StateView) -> StateDelta {
+    let mut changes = Vec::new();
+    match op {
+        Op::CreateEntity(ce) => {
+            if state.relation(&ce.id).is_some() || state.is_value_ref(&ce.id) {
+                return StateDelta::default();
+            }
+            match state.entity(&ce.id) {
+                None => {
+                    changes.push(StateChange::CreateEntity(ce.id));
+                    set_values(ce.id, None, &[], &ce.values, &mut changes);
+                }
+                Some(entity) if !entity.deleted => {
+                    set_values(ce.id, Some(entity), &[], &ce.values, &mut changes);
+                }
+                Some(_) => {}
+            }
+        }
+        Op::UpdateEntity(ue) => {
+            let Some(entity) = state.entity(&ue.id).filter(|e| !e.deleted) else {
+                return StateDelta::default();
+            };
+            let mut removed: Vec<ValueKey> = Vec::new();
+            for unset in &ue.unset_values {
+                let keys = entity.values.keys().filter(|(property, language)| {
+                    *property == unset.property
+                        && match unset.language {
+                            UnsetLanguage::All => true,
+                            UnsetLanguage::English => language.is_none(),
+                            UnsetLanguage::Specific(id) => *language == Some(id),
+                        }
+                });
+                for key in keys {
+                    if !removed.contains(key) {
+                        removed.push(*key);
+                    }
+                }
+            }
+            if !ue.remove_values.is_empty() {
+                for (key, value) in &entity.values {
+                    if !removed.contains(key) && ue.remove_values.contains(&value.value_id(&key.0)) {
+                        removed.push(*key);
+                    }
+                }
+            }
+            set_values(ue.id, Some(entity), &removed, &ue.set_properties, &mut changes);
+        }
+        Op::DeleteEntity(de) => entity_deleted(state, de.id, true, &mut changes),
+        Op::RestoreEntity(re) => entity_deleted(state, re.id, false, &mut changes),
+        Op::CreateRelation(cr) => create_relation(state, cr, &mut changes),
+        Op::UpdateRelation(ur) => {
+            let Some(current) = state.relation(&ur.id).filter(|r| !r.deleted) else {
+                return StateDelta::default();
+            };
+            let mut relation = current.clone();
+            for field in &ur.unset {
+                match field {
+                    UnsetRelationField::FromSpace => relation.from_space = None,
+                    UnsetRelationField::FromVersion => relation.from_version = None,
+                    UnsetRelationField::ToSpace => relation.to_space = None,
+                    UnsetRelationField::ToVersion => relation.to_version = None,
+                    UnsetRelationField::Position => relation.position = None,
+                }
+            }
+            if ur.from_space.is_some() {
+                relation.from_space = ur.from_space;
+            }
+            if ur.from_version.is_some() {
+                relation.from_version = ur.from_version;
+            }
+            if ur.to_space.is_some() {
+                relation.to_space = ur.to_space;
+            }
+            if ur.to_version.is_some() {
+                relation.to_version = ur.to_version;
+            }
+            if let Some(position) = &ur.position {
+                relation.position = Some(position.to_string());
+            }
+            if relation != *current {
+                changes.push(StateChange::PutRelation { id: ur.id, relation });
+            }
+        }
+        Op::DeleteRelation(dr) => relation_deleted(state, dr.id, true, &mut changes),
+        Op::RestoreRelation(rr) => relation_deleted(state, rr.id, false, &mut changes),
+        Op::CreateValueRef(cvr) => {
+            if state.entity(&cvr.id).is_some() || state.relation(&cvr.id).is_some() {
+                return StateDelta::default();
+            }
+            let slot = ValueSlot {
+                entity: cvr.entity,
+                property: cvr.property,
+                language: cvr.language,
+                space: cvr.space,
+            };
+            if state.value_ref(&slot) != Some(cvr.id) {
+                changes.push(StateChange::BindValueRef { slot, id: cvr.id });
+            }
+        }
+        Op::CreateSpace(_)
+        | Op::AddMember(_)
+        | Op::RemoveMember(_)
+        | Op::UpdateSpaceMetadata(_)
+        | Op::Unknown(_) => {}
+    }
+    StateDelta { changes }
+}
+
+/// Emits the net changes of clearing `removed` and then applying `values`
+/// to an entity's current values.
+fn set_values(
+    entity_id: Id,
+    entity: Option<&EntityState>,
+    removed: &[ValueKey],
+    values: &[PropertyValue<'_>],
+    changes: &mut Vec<StateChange>,
+) {
+    // Last set of each slot wins
+    let mut sets: Vec<(ValueKey, &Value<'_>)> = Vec::new();
+    for pv in values {
+        let language = match &pv.value {
+            Value::Text { language, .. } => *language,
+            _ => None,
+        };
+        let key = (pv.property, language);
+        match sets.iter_mut().find(|(k, _)| *k == key) {
+            Some(set) => set.1 = &pv.value,
+            None => sets.push((key, &pv.value)),
+        }
+    }
+
+    let current = |key: &ValueKey| entity.and_then(|e| e.values.get(key));
+    for key in removed {
+        if !sets.iter().any(|(k, _)| k == key) {
+            changes.push(StateChange::RemoveValue { entity: entity_id, key: *key });
+        }
+    }
+    for (key, value) in sets {
+        if current(&key) != Some(value) {
+            let value = value.clone().into_owned();
+            changes.push(StateChange::SetValue { entity: entity_id, key, value });
+        }
+    }
+}
+
+fn entity_deleted(state: &impl StateView, id: Id, deleted: bool, changes: &mut Vec<StateChange>) {
+    if state.entity(&id).is_some_and(|e| e.deleted != deleted) {
+        changes.push(StateChange::SetEntityDeleted { entity: id, deleted });
+    }
+}
+
+fn relation_deleted(
+    state: &impl StateView,
+    id: Id,
+    deleted: bool,
+    changes: &mut Vec<StateChange>,
+) {
+    if state.relation(&id).is_some_and(|r| r.deleted != deleted) {
+        changes.push(StateChange::SetRelationDeleted { id, deleted });
+    }
+}
+
+fn create_relation(
+    state: &impl StateView,
+    cr: &CreateRelation<'_>,
+    changes: &mut Vec<StateChange>,
+) {
+    if state.relation(&cr.id).is_some()
+        || state.entity(&cr.id).is_some()
+        || state.is_value_ref(&cr.id)
+    {
+        return;
+    }
+    let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+    let relation = RelationState {
+        relation_type: cr.relation_type,
+        from: cr.from,
+        from_is_value_ref: cr.from_is_value_ref,
+        from_space: cr.from_space,
+        from_version: cr.from_version,
+        to: cr.to,
+        to_is_value_ref: cr.to_is_value_ref,
+        to_space: cr.to_space,
+        to_version: cr.to_version,
+        entity,
+        explicit_entity: cr.entity.is_some(),
+        position: cr.position.as_ref().map(|p| p.to_string()),
+        deleted: false,
+    };
+    changes.push(StateChange::PutRelation { id: cr.id, relation });
+    // The reified entity is created implicitly; an existing (possibly
+    // deleted) entity is reused as-is.
+    if entity != cr.id
+        && state.entity(&entity).is_none()
+        && state.relation(&entity).is_none()
+        && !state.is_value_ref(&entity)
+    {
+        changes.push(StateChange::CreateEntity(entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Edit, EditBuilder};
+
+    const E: Id = [1u8; 16];
+    const P: Id = [2u8; 16];
+    const R: Id = [3u8; 16];
+    const T: Id = [4u8; 16];
+    const L: Id = [5u8; 16];
+    const V: Id = [6u8; 16];
+
+    fn int(value: i64) -> Value<'static> {
+        Value::Int64 { value, unit: None }
+    }
+
+    fn text(value: &'static str, language: Option<Id>) -> Value<'static> {
+        Value::Text { value: value.into(), language }
+    }
+
+    fn relation(deleted: bool, position: Option<&str>) -> RelationState {
+        RelationState {
+            relation_type: T,
+            from: E,
+            from_is_value_ref: false,
+            from_space: None,
+            from_version: None,
+            to: P,
+            to_is_value_ref: false,
+            to_space: None,
+            to_version: None,
+            entity: relation_entity_id(&R),
+            explicit_entity: false,
+            position: position.map(str::to_string),
+            deleted,
+        }
+    }
+
+    /// Golden vectors: (name, prior ops, op, expected changes).
+    fn vectors() -> Vec<(&'static str, Edit<'static>, Op<'static>, Vec<StateChange>)> {
+        let edit = || EditBuilder::new([0u8; 16]);
+        let with_entity =
+            || edit().create_entity(E, |e| e.int64(P, 1, None).text(P, "hi", Some(L)));
+        let with_relation = || edit().create_relation_simple(R, E, P, T);
+        let op = |b: EditBuilder<'static>| b.build().ops.remove(0);
+        use StateChange::*;
+        vec![
+            (
+                "create new entity",
+                edit().build(),
+                op(edit().create_entity(E, |e| e.int64(P, 1, None).int64(P, 2, None))),
+                vec![CreateEntity(E), SetValue { entity: E, key: (P, None), value: int(2) }],
+            ),
+            (
+                "create existing entity sets changed values only",
+                with_entity().build(),
+                op(edit().create_entity(E, |e| e.int64(P, 1, None).text(P, "yo", Some(L)))),
+                vec![SetValue { entity: E, key: (P, Some(L)), value: text("yo", Some(L)) }],
+            ),
+            (
+                "create deleted entity is ignored",
+                with_entity().delete_entity(E).build(),
+                op(edit().create_entity(E, |e| e.int64(P, 5, None))),
+                vec![],
+            ),
+            (
+                "unset all languages, then set",
+                with_entity().build(),
+                op(edit().update_entity(E, |u| u.unset_all(P).set_int64(P, 3, None))),
+                vec![
+                    RemoveValue { entity: E, key: (P, Some(L)) },
+                    SetValue { entity: E, key: (P, None), value: int(3) },
+                ],
+            ),
+            (
+                "remove by value ID only matches the current value",
+                with_entity().build(),
+                op(edit().update_entity(E, |u| {
+                    u.remove_value(text("hi", Some(L)).value_id(&P))
+                        .remove_value(int(2).value_id(&P))
+                })),
+                vec![RemoveValue { entity: E, key: (P, Some(L)) }],
+            ),
+            (
+                "unset and reset to the same value is a no-op",
+                with_entity().build(),
+                op(edit().update_entity(E, |u| u.unset_english(P).set_int64(P, 1, None))),
+                vec![],
+            ),
+            (
+                "update missing entity is ignored",
+                edit().build(),
+                op(edit().update_entity(E, |u| u.set_int64(P, 1, None))),
+                vec![],
+            ),
+            (
+                "delete and restore flip the tombstone",
+                with_entity().delete_entity(E).build(),
+                op(edit().restore_entity(E)),
+                vec![SetEntityDeleted { entity: E, deleted: false }],
+            ),
+            (
+                "create relation creates its entity",
+                edit().build(),
+                op(edit().create_relation_simple(R, E, P, T)),
+                vec![
+                    PutRelation { id: R, relation: relation(false, None) },
+                    CreateEntity(relation_entity_id(&R)),
+                ],
+            ),
+            (
+                "create relation over an entity is ignored",
+                with_entity().build(),
+                op(edit().create_relation_simple(E, E, P, T)),
+                vec![],
+            ),
+            (
+                "update relation position",
+                with_relation().build(),
+                op(edit().update_relation_position(R, Some("a0".into()))),
+                vec![PutRelation { id: R, relation: relation(false, Some("a0")) }],
+            ),
+            (
+                "update deleted relation is ignored",
+                with_relation().delete_relation(R).build(),
+                op(edit().update_relation_position(R, Some("a0".into()))),
+                vec![],
+            ),
+            (
+                "delete relation",
+                with_relation().build(),
+                op(edit().delete_relation(R)),
+                vec![SetRelationDeleted { id: R, deleted: true }],
+            ),
+            (
+                "bind value ref",
+                with_entity().build(),
+                Op::CreateValueRef(crate::model::CreateValueRef {
+                    id: V,
+                    entity: E,
+                    property: P,
+                    language: None,
+                    space: None,
+                }),
+                vec![BindValueRef {
+                    slot: ValueSlot { entity: E, property: P, language: None, space: None },
+                    id: V,
+                }],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_reduce_golden_vectors() {
+        for (name, prior, op, expected) in vectors() {
+            let mut state = GraphState::new();
+            state.apply_edit(&prior);
+            let delta = reduce(&op, &state);
+            assert_eq!(delta.changes, expected, "{name}");
+
+            // Applying the delta is applying the op
+            let mut applied = state.clone();
+            applied.apply_delta(&delta);
+            state.apply_op(&op);
+            assert_eq!(applied.state_hash(), state.state_hash(), "{name}");
+        }
+    }
+}
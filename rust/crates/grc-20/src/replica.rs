@@ -0,0 +1,327 @@
+//! Read replicas that catch up from a snapshot and a log.
+//!
+//! A publisher compacts its log with [`compact_log`], serves the snapshot
+//! container and the remaining log entries, and publishes the
+//! [`GraphState::state_hash`] at some log positions. A [`Replica`] drives
+//! the reader side through a [`ReplicaSource`]:
+//!
+//! 1. A fresh replica downloads the snapshot, replays it, and checks the
+//!    replayed state against the hash published with it.
+//! 2. It then tails the log from the snapshot's position in batches.
+//! 3. Whenever the source publishes a hash for the position reached, the
+//!    state is checked against it, and the position becomes the replica's
+//!    [`Checkpoint`].
+//!
+//! Changes since the last checkpoint are journaled. If a check fails, they
+//! are rolled back, so the replica always resumes from a verified position
+//! on the next [`Replica::sync`]. [`Replica::save`] and [`Replica::resume`]
+//! carry the verified state across restarts.
+//!
+//! Positions count log entries from the start of the original, uncompacted
+//! log: a snapshot covering the first `n` entries has position `n`, and the
+//! entry kept at `log_out[0]` by [`compact_log`] has position `n`.
+//!
+//! [`compact_log`]: crate::codec::compact::compact_log
+
+use std::io::{self, Write};
+
+use crate::codec::batch::{decode_batch, encode_batch};
+use crate::codec::transaction::decode_log_entry;
+use crate::error::ReplicaError;
+use crate::model::{derived_uuid, Edit, WireDictionaries};
+use crate::state::GraphState;
+
+/// Default number of log entries requested per [`ReplicaSource::entries`] call.
+pub const DEFAULT_REPLICA_BATCH: usize = 256;
+
+/// A published snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Batch container that rebuilds the state (see [`decode_batch`]).
+    pub container: Vec<u8>,
+    /// Number of log entries the snapshot covers.
+    pub position: u64,
+    /// State hash after replaying the snapshot.
+    pub state_hash: [u8; 32],
+}
+
+/// A verified log position and the state hash at that position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// Number of log entries applied.
+    pub position: u64,
+    /// State hash after applying them.
+    pub state_hash: [u8; 32],
+}
+
+/// Where a replica reads snapshots, log entries, and state hashes from.
+///
+/// A source should publish hashes at least every few batches: changes past
+/// the last checkpoint are journaled until a hash is available.
+pub trait ReplicaSource {
+    /// Returns the latest snapshot, if any.
+    fn snapshot(&mut self) -> io::Result<Option<Snapshot>>;
+
+    /// Returns up to `limit` log entries starting at position `from`.
+    ///
+    /// Entries may be standalone edits (compressed or not) or transaction
+    /// containers. An empty result means the replica is caught up.
+    fn entries(&mut self, from: u64, limit: usize) -> io::Result<Vec<Vec<u8>>>;
+
+    /// Returns the published state hash at `position`, if any.
+    fn state_hash(&mut self, position: u64) -> io::Result<Option<[u8; 32]>>;
+}
+
+/// Summary of a [`Replica::sync`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Whether the replica was bootstrapped from a snapshot.
+    pub snapshot_loaded: bool,
+    /// Number of log entries applied.
+    pub entries_applied: u64,
+    /// Position reached, which may be past the last checkpoint.
+    pub position: u64,
+    /// Last verified position.
+    pub checkpoint: Checkpoint,
+}
+
+/// A read replica of a space's state.
+#[derive(Debug, Clone)]
+pub struct Replica {
+    state: GraphState,
+    position: u64,
+    checkpoint: Checkpoint,
+    batch_size: usize,
+}
+
+impl Default for Replica {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Replica {
+    /// Creates an empty replica at position 0.
+    pub fn new() -> Self {
+        Self::with_state(GraphState::new(), 0)
+    }
+
+    /// Rebuilds a replica saved with [`Replica::save`].
+    ///
+    /// Fails with [`ReplicaError::VerificationFailed`] if the container does
+    /// not reproduce `checkpoint.state_hash`.
+    pub fn resume(container: &[u8], checkpoint: Checkpoint) -> Result<Self, ReplicaError> {
+        let state = replay(container, checkpoint.position, checkpoint.state_hash)?;
+        Ok(Self::with_state(state, checkpoint.position))
+    }
+
+    /// Sets the number of log entries requested per batch.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn with_state(mut state: GraphState, position: u64) -> Self {
+        let state_hash = state.state_hash();
+        state.begin_journal();
+        Self {
+            state,
+            position,
+            checkpoint: Checkpoint { position, state_hash },
+            batch_size: DEFAULT_REPLICA_BATCH,
+        }
+    }
+
+    /// Returns the current state, including changes past the checkpoint.
+    pub fn state(&self) -> &GraphState {
+        &self.state
+    }
+
+    /// Returns the number of log entries applied.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the last verified position.
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint
+    }
+
+    /// Catches up with `source`.
+    ///
+    /// A replica at position 0 first loads the source's snapshot, if any.
+    /// Log entries are then applied in batches until the source has none
+    /// left. On error the replica keeps what it has verified: a failed
+    /// check rolls back to the last checkpoint, and any other error leaves
+    /// the position at the last fully applied batch.
+    pub fn sync<S: ReplicaSource>(&mut self, source: &mut S) -> Result<SyncReport, ReplicaError> {
+        let mut report = SyncReport {
+            snapshot_loaded: false,
+            entries_applied: 0,
+            position: self.position,
+            checkpoint: self.checkpoint,
+        };
+
+        if self.position == 0 {
+            if let Some(snapshot) = source.snapshot()? {
+                let state = replay(&snapshot.container, snapshot.position, snapshot.state_hash)?;
+                *self = Self::with_state(state, snapshot.position).with_batch_size(self.batch_size);
+                report.snapshot_loaded = true;
+            }
+        }
+
+        loop {
+            let entries = source.entries(self.position, self.batch_size)?;
+            if entries.is_empty() {
+                break;
+            }
+            // Decode the whole batch first so a bad entry applies nothing
+            let edits = entries
+                .iter()
+                .map(|bytes| decode_log_entry(bytes))
+                .collect::<Result<Vec<_>, _>>()?;
+            edits.iter().flatten().for_each(|edit| self.state.apply_edit(edit));
+            self.position += entries.len() as u64;
+            report.entries_applied += entries.len() as u64;
+
+            if let Some(expected) = source.state_hash(self.position)? {
+                self.verify(expected)?;
+            }
+        }
+
+        report.position = self.position;
+        report.checkpoint = self.checkpoint;
+        Ok(report)
+    }
+
+    /// Writes the state at the last checkpoint as a batch container.
+    ///
+    /// Pass the container and [`Replica::checkpoint`] to [`Replica::resume`]
+    /// to pick up from the same position later.
+    pub fn save<W: Write>(&self, out: &mut W) -> Result<Checkpoint, ReplicaError> {
+        let mut verified = self.state.clone();
+        verified.rollback_journal();
+        let container = encode_state(&verified, &self.checkpoint)?;
+        out.write_all(&container)?;
+        Ok(self.checkpoint)
+    }
+
+    fn verify(&mut self, expected: [u8; 32]) -> Result<(), ReplicaError> {
+        let actual = self.state.state_hash();
+        if actual != expected {
+            let position = self.position;
+            self.state.rollback_journal();
+            self.state.begin_journal();
+            self.position = self.checkpoint.position;
+            return Err(ReplicaError::VerificationFailed { position, expected, actual });
+        }
+        self.state.commit_journal();
+        self.state.begin_journal();
+        self.checkpoint = Checkpoint { position: self.position, state_hash: actual };
+        Ok(())
+    }
+}
+
+/// Replays a batch container and checks the result against `expected`.
+fn replay(container: &[u8], position: u64, expected: [u8; 32]) -> Result<GraphState, ReplicaError> {
+    let mut state = GraphState::new();
+    decode_batch(container)?.iter().for_each(|edit| state.apply_edit(edit));
+    let actual = state.state_hash();
+    if actual != expected {
+        return Err(ReplicaError::VerificationFailed { position, expected, actual });
+    }
+    Ok(state)
+}
+
+/// Encodes a state as a batch container, with edit IDs derived from the
+/// checkpoint.
+fn encode_state(state: &GraphState, checkpoint: &Checkpoint) -> Result<Vec<u8>, ReplicaError> {
+    let mut id_input = b"grc20:replica:".to_vec();
+    id_input.extend_from_slice(&checkpoint.position.to_be_bytes());
+    id_input.extend_from_slice(&checkpoint.state_hash);
+    let edits: Vec<Edit<'static>> = state.snapshot_edits(derived_uuid(&id_input), 0);
+    Ok(encode_batch(&edits, &WireDictionaries::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::compact::compact_log;
+    use crate::codec::edit::encode_edit;
+    use crate::model::EditBuilder;
+
+    /// A publisher that compacted its log at `base` and publishes hashes
+    /// for the positions in `hashes`.
+    struct Publisher {
+        snapshot: Snapshot,
+        log: Vec<Vec<u8>>,
+        hashes: Vec<(u64, [u8; 32])>,
+    }
+
+    impl ReplicaSource for Publisher {
+        fn snapshot(&mut self) -> io::Result<Option<Snapshot>> {
+            Ok(Some(self.snapshot.clone()))
+        }
+
+        fn entries(&mut self, from: u64, limit: usize) -> io::Result<Vec<Vec<u8>>> {
+            let start = (from - self.snapshot.position) as usize;
+            Ok(self.log.iter().skip(start).take(limit).cloned().collect())
+        }
+
+        fn state_hash(&mut self, position: u64) -> io::Result<Option<[u8; 32]>> {
+            Ok(self.hashes.iter().find(|(p, _)| *p == position).map(|(_, hash)| *hash))
+        }
+    }
+
+    fn log() -> Vec<Vec<u8>> {
+        let name = [1u8; 16];
+        (0u8..6)
+            .map(|i| {
+                let edit = EditBuilder::new([100 + i; 16])
+                    .create_entity([10 + i; 16], |e| e.text(name, "entity", None))
+                    .build();
+                encode_edit(&edit).unwrap()
+            })
+            .collect()
+    }
+
+    fn hash_at(log: &[Vec<u8>], position: usize) -> [u8; 32] {
+        let mut state = GraphState::new();
+        for bytes in &log[..position] {
+            decode_log_entry(bytes).unwrap().iter().for_each(|e| state.apply_edit(e));
+        }
+        state.state_hash()
+    }
+
+    #[test]
+    fn test_sync_verifies_and_resumes() {
+        let full = log();
+        let mut container = Vec::new();
+        let mut kept = Vec::new();
+        let report = compact_log(&full, &mut container, &mut kept, 2).unwrap();
+        let mut publisher = Publisher {
+            snapshot: Snapshot { container, position: 2, state_hash: hash_at(&full, 2) },
+            log: kept,
+            hashes: vec![(4, hash_at(&full, 4)), (6, [0u8; 32])],
+        };
+
+        // The bogus hash at 6 rolls the replica back to the checkpoint at 4
+        let mut replica = Replica::new().with_batch_size(2);
+        let err = replica.sync(&mut publisher).unwrap_err();
+        assert!(matches!(err, ReplicaError::VerificationFailed { position: 6, .. }));
+        assert_eq!(replica.position(), 4);
+        assert_eq!(replica.checkpoint().state_hash, hash_at(&full, 4));
+        assert_eq!(replica.state().state_hash(), hash_at(&full, 4));
+
+        // Saved replicas resume from the checkpoint
+        let mut saved = Vec::new();
+        let checkpoint = replica.save(&mut saved).unwrap();
+        let mut resumed = Replica::resume(&saved, checkpoint).unwrap();
+
+        publisher.hashes[1].1 = report.state_hash;
+        let sync = resumed.sync(&mut publisher).unwrap();
+        assert!(!sync.snapshot_loaded);
+        assert_eq!(sync.entries_applied, 2);
+        assert_eq!(sync.checkpoint, Checkpoint { position: 6, state_hash: report.state_hash });
+    }
+}
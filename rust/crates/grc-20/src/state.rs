@@ -0,0 +1,1268 @@
+//! Resolved graph state for a single space.
+//!
+//! [`GraphState`] replays ops in log order and applies the resolution rules
+//! of spec Sections 3 and 8.2: LWW values per (property, language),
+//! tombstone dominance for entities and relations, relation immutability
+//! apart from pins and position, a shared ID namespace for entities,
+//! relations, and value refs, and LWW slot bindings for value refs.
+//!
+//! Two indexers that replay the same log reach states with the same
+//! [`GraphState::state_hash`], which makes the hash suitable for checking
+//! snapshots and replicas against each other.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+use sha2::{Digest, Sha256};
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::DecodeError;
+use crate::limits::{MAX_BYTES_LEN, MAX_EMBEDDING_BYTES, MAX_STRING_LEN};
+use crate::model::id::relation_entity_id;
+use crate::model::recurrence::ScheduleIndex;
+use crate::model::{
+    CreateEntity, CreateRelation, CreateValueRef, DataType, DecimalMantissa, DeleteEntity,
+    DeleteRelation, Edit, EmbeddingSubType, Id, Op, PropertyValue, RestoreEntity, RestoreRelation,
+    UpdateEntity, UpdateRelation, Value, NIL_ID,
+};
+use crate::reduce::{reduce, StateChange, StateDelta};
+
+/// Maximum ops per edit produced by [`GraphState::snapshot_edits`].
+pub const SNAPSHOT_MAX_OPS: usize = 100_000;
+
+/// Domain separator for [`GraphState::state_hash`].
+const STATE_HASH_DOMAIN: &[u8] = b"grc20:state:v1";
+
+/// Key of a value slot on an entity: property, and language for TEXT.
+pub type ValueKey = (Id, Option<Id>);
+
+/// Resolved state of an entity.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityState {
+    /// Current values, keyed by (property, language).
+    pub values: BTreeMap<ValueKey, Value<'static>>,
+    /// True if the entity is tombstoned. Values are kept so that a restore
+    /// reveals them again.
+    pub deleted: bool,
+}
+
+impl EntityState {
+    /// Returns the value of a property (and language, for TEXT).
+    pub fn value(&self, property: &Id, language: Option<&Id>) -> Option<&Value<'static>> {
+        self.values.get(&(*property, language.copied()))
+    }
+
+    /// Iterates over every value of a property, across languages.
+    pub fn values_of(&self, property: &Id) -> impl Iterator<Item = &Value<'static>> {
+        self.values
+            .range((*property, None)..)
+            .take_while(move |((p, _), _)| p == property)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Resolved state of a relation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationState {
+    /// Relation type ID.
+    pub relation_type: Id,
+    /// Source entity or value ref.
+    pub from: Id,
+    /// True if `from` is a value ref ID.
+    pub from_is_value_ref: bool,
+    /// Optional space pin for the source.
+    pub from_space: Option<Id>,
+    /// Optional version pin for the source.
+    pub from_version: Option<Id>,
+    /// Target entity or value ref.
+    pub to: Id,
+    /// True if `to` is a value ref ID.
+    pub to_is_value_ref: bool,
+    /// Optional space pin for the target.
+    pub to_space: Option<Id>,
+    /// Optional version pin for the target.
+    pub to_version: Option<Id>,
+    /// Reified entity ID (explicit or auto-derived).
+    pub entity: Id,
+    /// True if the reified entity was given explicitly.
+    pub explicit_entity: bool,
+    /// Optional ordering position.
+    pub position: Option<String>,
+    /// True if the relation is tombstoned.
+    pub deleted: bool,
+}
+
+/// A value slot that a value ref can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ValueSlot {
+    /// Entity holding the value.
+    pub entity: Id,
+    /// Property of the value.
+    pub property: Id,
+    /// Language (TEXT values only).
+    pub language: Option<Id>,
+    /// Space containing the value (None = the current space).
+    pub space: Option<Id>,
+}
+
+/// Limits on the work done by one [`GraphState::apply_edit_budgeted`] call.
+///
+/// Decode limits bound the size of an edit; a budget bounds how much of it
+/// is replayed at a time, so an indexer can interleave a large untrusted
+/// edit with other work. Every limit defaults to unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyBudget {
+    /// Maximum ops applied.
+    pub max_ops: usize,
+    /// Maximum values set or unset (UpdateEntity removals included).
+    pub max_values: usize,
+    /// Maximum payload bytes of variable-length values and positions.
+    pub max_bytes: usize,
+}
+
+impl Default for ApplyBudget {
+    fn default() -> Self {
+        Self { max_ops: usize::MAX, max_values: usize::MAX, max_bytes: usize::MAX }
+    }
+}
+
+impl ApplyBudget {
+    /// Creates an unlimited budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the ops applied.
+    pub fn with_max_ops(mut self, max_ops: usize) -> Self {
+        self.max_ops = max_ops;
+        self
+    }
+
+    /// Limits the values set or unset.
+    pub fn with_max_values(mut self, max_values: usize) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    /// Limits the payload bytes of variable-length values and positions.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+}
+
+/// The [`ApplyBudget`] limit that stopped a budgeted apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetLimit {
+    /// [`ApplyBudget::max_ops`].
+    Ops,
+    /// [`ApplyBudget::max_values`].
+    Values,
+    /// [`ApplyBudget::max_bytes`].
+    Bytes,
+}
+
+/// How far a [`GraphState::apply_edit_budgeted`] call got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyProgress {
+    /// Index of the first op not yet applied; `ops.len()` once complete.
+    pub next_op: usize,
+    /// Ops applied by this call.
+    pub ops: usize,
+    /// Values set or unset by this call.
+    pub values: usize,
+    /// Payload bytes applied by this call.
+    pub bytes: usize,
+    /// The limit that stopped the call, or `None` if the edit is complete.
+    pub exhausted: Option<BudgetLimit>,
+}
+
+impl ApplyProgress {
+    /// Returns true if every op of the edit has been applied.
+    pub fn is_complete(&self) -> bool {
+        self.exhausted.is_none()
+    }
+}
+
+/// Resolved state of a space, built by replaying edits in log order.
+#[derive(Debug, Clone, Default)]
+pub struct GraphState {
+    entities: FxHashMap<Id, EntityState>,
+    relations: FxHashMap<Id, RelationState>,
+    /// Authoritative slot → value ref binding (LWW).
+    value_refs: FxHashMap<ValueSlot, Id>,
+    /// Number of slots each value ref ID currently wins.
+    value_ref_slots: FxHashMap<Id, usize>,
+    /// SCHEDULE values of live entities, kept in step with `entities`.
+    schedules: ScheduleIndex,
+    /// Prior contents of everything touched while a transaction is open.
+    journal: Option<Vec<Undo>>,
+}
+
+/// A prior map entry, restored when a transaction rolls back.
+#[derive(Debug, Clone)]
+enum Undo {
+    Entity(Id, Option<EntityState>),
+    Relation(Id, Option<RelationState>),
+    ValueRef(ValueSlot, Option<Id>),
+    ValueRefSlots(Id, Option<usize>),
+}
+
+impl GraphState {
+    /// Creates an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies every op of an edit in order.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(ops = edit.ops.len()))
+    )]
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) {
+        for op in &edit.ops {
+            self.apply_op(op);
+        }
+    }
+
+    /// Applies the ops of an edit from `start_op` until the edit ends or
+    /// the next op would exceed `budget`.
+    ///
+    /// Resume by calling again with the returned
+    /// [`next_op`](ApplyProgress::next_op). The first op of a call is
+    /// applied even if it alone exceeds the budget, so every call makes
+    /// progress; single ops are bounded by the decode limits instead.
+    /// Replaying an edit in budgeted steps reaches the same state as
+    /// [`apply_edit`](Self::apply_edit).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                start_op = start_op,
+                applied = tracing::field::Empty,
+                exhausted = tracing::field::Empty
+            )
+        )
+    )]
+    pub fn apply_edit_budgeted(
+        &mut self,
+        edit: &Edit<'_>,
+        start_op: usize,
+        budget: ApplyBudget,
+    ) -> ApplyProgress {
+        let mut progress =
+            ApplyProgress { next_op: start_op, ops: 0, values: 0, bytes: 0, exhausted: None };
+        for op in edit.ops.iter().skip(start_op) {
+            let (values, bytes) = op_cost(op);
+            if progress.ops > 0 {
+                let exhausted = if progress.ops >= budget.max_ops {
+                    Some(BudgetLimit::Ops)
+                } else if values > budget.max_values.saturating_sub(progress.values) {
+                    Some(BudgetLimit::Values)
+                } else if bytes > budget.max_bytes.saturating_sub(progress.bytes) {
+                    Some(BudgetLimit::Bytes)
+                } else {
+                    None
+                };
+                if exhausted.is_some() {
+                    progress.exhausted = exhausted;
+                    break;
+                }
+            }
+            self.apply_op(op);
+            progress.next_op += 1;
+            progress.ops += 1;
+            progress.values += values;
+            progress.bytes += bytes;
+        }
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            span.record("applied", progress.ops);
+            if let Some(limit) = progress.exhausted {
+                span.record("exhausted", tracing::field::debug(limit));
+            }
+        }
+        progress
+    }
+
+    /// Applies the edits of a transaction atomically.
+    ///
+    /// Before each edit is applied, `check` is called with the state as left
+    /// by the transaction's earlier edits (e.g. to validate a data edit
+    /// against a schema edit in the same transaction). If any check fails,
+    /// every edit already applied is rolled back and the error is returned,
+    /// so the state is never left partway through a transaction.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(edits = edits.len()))
+    )]
+    pub fn apply_transaction<E>(
+        &mut self,
+        edits: &[Edit<'_>],
+        mut check: impl FnMut(&GraphState, &Edit<'_>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.begin_journal();
+        let result = edits.iter().try_for_each(|edit| {
+            check(self, edit)?;
+            self.apply_edit(edit);
+            Ok(())
+        });
+        if result.is_err() {
+            self.rollback_journal();
+        }
+        self.commit_journal();
+        result
+    }
+
+    /// Starts journaling changes so that they can be undone.
+    pub(crate) fn begin_journal(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+
+    /// Keeps every change made since [`begin_journal`] and stops journaling.
+    ///
+    /// [`begin_journal`]: Self::begin_journal
+    pub(crate) fn commit_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Undoes every change made since [`begin_journal`] and stops journaling.
+    ///
+    /// [`begin_journal`]: Self::begin_journal
+    pub(crate) fn rollback_journal(&mut self) {
+        if let Some(journal) = self.journal.take() {
+            self.rollback(journal);
+        }
+    }
+
+    /// Applies a single op.
+    ///
+    /// Ops that the spec says are ignored (updates to missing or deleted
+    /// objects, creates that collide with another kind of object, and so
+    /// on) leave the state unchanged. See [`reduce`](crate::reduce) for the
+    /// rules.
+    pub fn apply_op(&mut self, op: &Op<'_>) {
+        if self.journal.is_some() {
+            self.record(op);
+        }
+        let delta = reduce(op, self);
+        self.apply_delta(&delta);
+    }
+
+    /// Applies the changes computed by [`reduce`](crate::reduce::reduce).
+    ///
+    /// Changes are not journaled; use [`apply_op`](Self::apply_op) within
+    /// transactions.
+    pub fn apply_delta(&mut self, delta: &StateDelta) {
+        for change in &delta.changes {
+            match change {
+                StateChange::CreateEntity(id) => {
+                    self.entities.entry(*id).or_default();
+                }
+                StateChange::SetValue { entity, key, value } => {
+                    self.entities.entry(*entity).or_default().values.insert(*key, value.clone());
+                    self.index_schedule(*entity, key);
+                }
+                StateChange::RemoveValue { entity, key } => {
+                    if let Some(state) = self.entities.get_mut(entity) {
+                        state.values.remove(key);
+                    }
+                    self.index_schedule(*entity, key);
+                }
+                StateChange::SetEntityDeleted { entity, deleted } => {
+                    self.entities.entry(*entity).or_default().deleted = *deleted;
+                    self.index_entity_schedules(*entity);
+                }
+                StateChange::PutRelation { id, relation } => {
+                    self.relations.insert(*id, relation.clone());
+                }
+                StateChange::SetRelationDeleted { id, deleted } => {
+                    if let Some(relation) = self.relations.get_mut(id) {
+                        relation.deleted = *deleted;
+                    }
+                }
+                StateChange::BindValueRef { slot, id } => {
+                    if let Some(previous) = self.value_refs.insert(*slot, *id) {
+                        if let Some(count) = self.value_ref_slots.get_mut(&previous) {
+                            *count -= 1;
+                            if *count == 0 {
+                                self.value_ref_slots.remove(&previous);
+                            }
+                        }
+                    }
+                    *self.value_ref_slots.entry(*id).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    /// Updates the schedule index for the value stored under `key`.
+    fn index_schedule(&mut self, entity: Id, key: &ValueKey) {
+        if key.1.is_some() {
+            return;
+        }
+        let state = self.entities.get(&entity).filter(|state| !state.deleted);
+        match state.and_then(|state| state.values.get(key)) {
+            // Schedules that cannot be evaluated are left out of the index
+            Some(Value::Schedule(schedule)) => {
+                let _ = self.schedules.insert(entity, key.0, schedule);
+            }
+            _ => self.schedules.remove(&entity, &key.0),
+        }
+    }
+
+    /// Re-indexes every SCHEDULE value of an entity.
+    fn index_entity_schedules(&mut self, entity: Id) {
+        self.schedules.remove_entity(&entity);
+        let Some(state) = self.entities.get(&entity).filter(|state| !state.deleted) else {
+            return;
+        };
+        for (key, value) in &state.values {
+            if let (Value::Schedule(schedule), None) = (value, key.1) {
+                let _ = self.schedules.insert(entity, key.0, schedule);
+            }
+        }
+    }
+
+    /// Journals the prior contents of every entry `op` may touch.
+    fn record(&mut self, op: &Op<'_>) {
+        let mut undo = Vec::new();
+        match op {
+            Op::CreateEntity(CreateEntity { id, .. })
+            | Op::UpdateEntity(UpdateEntity { id, .. })
+            | Op::DeleteEntity(DeleteEntity { id })
+            | Op::RestoreEntity(RestoreEntity { id }) => {
+                undo.push(Undo::Entity(*id, self.entities.get(id).cloned()));
+            }
+            Op::CreateRelation(cr) => {
+                let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+                undo.push(Undo::Relation(cr.id, self.relations.get(&cr.id).cloned()));
+                undo.push(Undo::Entity(entity, self.entities.get(&entity).cloned()));
+            }
+            Op::UpdateRelation(UpdateRelation { id, .. })
+            | Op::DeleteRelation(DeleteRelation { id })
+            | Op::RestoreRelation(RestoreRelation { id }) => {
+                undo.push(Undo::Relation(*id, self.relations.get(id).cloned()));
+            }
+            Op::CreateValueRef(cvr) => {
+                let slot = ValueSlot {
+                    entity: cvr.entity,
+                    property: cvr.property,
+                    language: cvr.language,
+                    space: cvr.space,
+                };
+                let previous = self.value_refs.get(&slot).copied();
+                undo.push(Undo::ValueRef(slot, previous));
+                for id in previous.into_iter().chain([cvr.id]) {
+                    undo.push(Undo::ValueRefSlots(id, self.value_ref_slots.get(&id).copied()));
+                }
+            }
+            Op::CreateSpace(_)
+            | Op::AddMember(_)
+            | Op::RemoveMember(_)
+            | Op::UpdateSpaceMetadata(_)
+            | Op::Unknown(_) => {}
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.extend(undo);
+        }
+    }
+
+    /// Restores journaled entries, newest first.
+    fn rollback(&mut self, journal: Vec<Undo>) {
+        fn restore<K, V>(map: &mut FxHashMap<K, V>, key: K, prior: Option<V>)
+        where
+            K: Eq + std::hash::Hash,
+        {
+            match prior {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+        for undo in journal.into_iter().rev() {
+            match undo {
+                Undo::Entity(id, prior) => {
+                    restore(&mut self.entities, id, prior);
+                    self.index_entity_schedules(id);
+                }
+                Undo::Relation(id, prior) => restore(&mut self.relations, id, prior),
+                Undo::ValueRef(slot, prior) => restore(&mut self.value_refs, slot, prior),
+                Undo::ValueRefSlots(id, prior) => restore(&mut self.value_ref_slots, id, prior),
+            }
+        }
+    }
+
+    pub(crate) fn is_value_ref(&self, id: &Id) -> bool {
+        self.value_ref_slots.contains_key(id)
+    }
+
+    /// Returns the index of SCHEDULE values on live entities.
+    ///
+    /// Values in a language slot and schedules that cannot be evaluated are
+    /// not indexed.
+    pub fn schedules(&self) -> &ScheduleIndex {
+        &self.schedules
+    }
+
+    /// Returns an entity's state, including tombstoned entities.
+    pub fn entity(&self, id: &Id) -> Option<&EntityState> {
+        self.entities.get(id)
+    }
+
+    /// Starts a read-modify-write update of an entity.
+    ///
+    /// The returned builder starts from the entity's current values (none if
+    /// the entity is unknown). Changes are diffed against them when the
+    /// builder is finished, so the resulting op carries only the slots that
+    /// actually changed. Updates to missing or deleted entities are ignored
+    /// when applied, as with any UpdateEntity.
+    pub fn update(&self, id: Id) -> EntityUpdate<'_> {
+        let current = self.entities.get(&id).map(|entity| &entity.values);
+        EntityUpdate { id, values: current.cloned().unwrap_or_default(), current }
+    }
+
+    /// Returns a relation's state, including tombstoned relations.
+    pub fn relation(&self, id: &Id) -> Option<&RelationState> {
+        self.relations.get(id)
+    }
+
+    /// Returns the value ref ID bound to a slot.
+    pub fn value_ref(&self, slot: &ValueSlot) -> Option<Id> {
+        self.value_refs.get(slot).copied()
+    }
+
+    /// Iterates over all entities (in no particular order).
+    pub fn entities(&self) -> impl Iterator<Item = (&Id, &EntityState)> {
+        self.entities.iter()
+    }
+
+    /// Iterates over all relations (in no particular order).
+    pub fn relations(&self) -> impl Iterator<Item = (&Id, &RelationState)> {
+        self.relations.iter()
+    }
+
+    /// Iterates over all value ref bindings (in no particular order).
+    pub fn value_refs(&self) -> impl Iterator<Item = (&ValueSlot, &Id)> {
+        self.value_refs.iter()
+    }
+
+    /// Returns the number of entities, including tombstoned ones.
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns the number of relations, including tombstoned ones.
+    pub fn relation_count(&self) -> usize {
+        self.relations.len()
+    }
+
+    /// Computes a SHA-256 hash of the resolved state.
+    ///
+    /// The hash covers every entity (with its tombstone flag and values),
+    /// relation, and value ref binding, serialized in ID order, so it does
+    /// not depend on how the state was reached or on map iteration order.
+    pub fn state_hash(&self) -> [u8; 32] {
+        let mut writer = Writer::new();
+        writer.write_bytes(STATE_HASH_DOMAIN);
+
+        let entities = sorted(&self.entities);
+        writer.write_varint(entities.len() as u64);
+        for (id, entity) in entities {
+            writer.write_id(id);
+            writer.write_byte(entity.deleted as u8);
+            writer.write_varint(entity.values.len() as u64);
+            for ((property, language), value) in &entity.values {
+                writer.write_id(property);
+                write_optional_id(&mut writer, language);
+                write_value(&mut writer, value);
+            }
+        }
+
+        let relations = sorted(&self.relations);
+        writer.write_varint(relations.len() as u64);
+        for (id, r) in relations {
+            writer.write_id(id);
+            writer.write_id(&r.relation_type);
+            writer.write_id(&r.from);
+            writer.write_byte(r.from_is_value_ref as u8);
+            write_optional_id(&mut writer, &r.from_space);
+            write_optional_id(&mut writer, &r.from_version);
+            writer.write_id(&r.to);
+            writer.write_byte(r.to_is_value_ref as u8);
+            write_optional_id(&mut writer, &r.to_space);
+            write_optional_id(&mut writer, &r.to_version);
+            writer.write_id(&r.entity);
+            match &r.position {
+                Some(position) => {
+                    writer.write_byte(1);
+                    writer.write_string(position);
+                }
+                None => writer.write_byte(0),
+            }
+            writer.write_byte(r.deleted as u8);
+        }
+
+        let mut value_refs: Vec<_> = self.value_refs.iter().collect();
+        value_refs.sort_unstable_by_key(|(slot, _)| **slot);
+        writer.write_varint(value_refs.len() as u64);
+        for (slot, id) in value_refs {
+            writer.write_id(&slot.entity);
+            writer.write_id(&slot.property);
+            write_optional_id(&mut writer, &slot.language);
+            write_optional_id(&mut writer, &slot.space);
+            writer.write_id(id);
+        }
+
+        Sha256::digest(writer.as_bytes()).into()
+    }
+
+    /// Expresses the state as a sequence of edits that rebuild it when
+    /// replayed into an empty state.
+    ///
+    /// Entity values come first, split across edits wherever a property is
+    /// used with more than one data type (an edit declares one type per
+    /// property) and every [`SNAPSHOT_MAX_OPS`] ops. Tombstones, relations,
+    /// and value refs follow. Edit IDs are derived from `id`; every edit
+    /// carries `created_at`.
+    pub fn snapshot_edits(&self, id: Id, created_at: i64) -> Vec<Edit<'static>> {
+        let mut ops: Vec<Op<'static>> = Vec::new();
+        let mut edits: Vec<Edit<'static>> = Vec::new();
+        let mut types: FxHashMap<Id, DataType> = FxHashMap::default();
+
+        let flush = |ops: &mut Vec<Op<'static>>, edits: &mut Vec<Edit<'static>>| {
+            if !ops.is_empty() {
+                let mut edit = Edit::new(snapshot_edit_id(&id, edits.len()));
+                edit.name = Cow::Borrowed("snapshot");
+                edit.created_at = created_at;
+                edit.ops = std::mem::take(ops);
+                edits.push(edit);
+            }
+        };
+
+        // Entities and their values, layered so each property keeps one
+        // data type per edit. Every entity is created in the first layer,
+        // even without values, so that empty and reified entities survive.
+        let entities = sorted(&self.entities);
+        let mut pending: Vec<(Id, Vec<PropertyValue<'static>>)> = entities
+            .iter()
+            .map(|(id, entity)| {
+                let values = entity
+                    .values
+                    .iter()
+                    .map(|((property, _), value)| PropertyValue {
+                        property: *property,
+                        value: value.clone(),
+                        clock: None,
+                    })
+                    .collect();
+                (**id, values)
+            })
+            .collect();
+        let mut first_layer = true;
+        while first_layer || !pending.is_empty() {
+            let mut next = Vec::new();
+            for (entity, values) in pending {
+                let (fits, rest): (Vec<_>, Vec<_>) = values.into_iter().partition(|pv| {
+                    let data_type = pv.value.data_type();
+                    *types.entry(pv.property).or_insert(data_type) == data_type
+                });
+                if first_layer {
+                    ops.push(Op::CreateEntity(CreateEntity { id: entity, values: fits }));
+                } else if !fits.is_empty() {
+                    ops.push(Op::UpdateEntity(UpdateEntity {
+                        id: entity,
+                        set_properties: fits,
+                        unset_values: Vec::new(),
+                        remove_values: Vec::new(),
+                    }));
+                }
+                if !rest.is_empty() {
+                    next.push((entity, rest));
+                }
+                if ops.len() >= SNAPSHOT_MAX_OPS {
+                    flush(&mut ops, &mut edits);
+                    types.clear();
+                }
+            }
+            flush(&mut ops, &mut edits);
+            types.clear();
+            pending = next;
+            first_layer = false;
+        }
+
+        // Tombstones must follow every value they hide
+        for (id, entity) in &entities {
+            if entity.deleted {
+                ops.push(Op::DeleteEntity(DeleteEntity { id: **id }));
+            }
+        }
+
+        for (id, r) in sorted(&self.relations) {
+            ops.push(Op::CreateRelation(CreateRelation {
+                id: *id,
+                relation_type: r.relation_type,
+                from: r.from,
+                from_is_value_ref: r.from_is_value_ref,
+                from_space: r.from_space,
+                from_version: r.from_version,
+                to: r.to,
+                to_is_value_ref: r.to_is_value_ref,
+                to_space: r.to_space,
+                to_version: r.to_version,
+                entity: r.explicit_entity.then_some(r.entity),
+                position: r.position.clone().map(Cow::Owned),
+            }));
+            if r.deleted {
+                ops.push(Op::DeleteRelation(DeleteRelation { id: *id }));
+            }
+            if ops.len() >= SNAPSHOT_MAX_OPS {
+                flush(&mut ops, &mut edits);
+            }
+        }
+
+        // Refs with a language first, so the edit declares those properties
+        // as TEXT before any language-less ref registers them.
+        let mut value_refs: Vec<_> = self.value_refs.iter().collect();
+        value_refs.sort_unstable_by_key(|(slot, _)| (slot.language.is_none(), **slot));
+        for (slot, id) in value_refs {
+            ops.push(Op::CreateValueRef(CreateValueRef {
+                id: *id,
+                entity: slot.entity,
+                property: slot.property,
+                language: slot.language,
+                space: slot.space,
+            }));
+            if ops.len() >= SNAPSHOT_MAX_OPS {
+                flush(&mut ops, &mut edits);
+            }
+        }
+        flush(&mut ops, &mut edits);
+
+        edits
+    }
+}
+
+/// Returns map entries sorted by ID.
+fn sorted<V>(map: &FxHashMap<Id, V>) -> Vec<(&Id, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|(id, _)| **id);
+    entries
+}
+
+fn snapshot_edit_id(id: &Id, index: usize) -> Id {
+    if index == 0 {
+        return *id;
+    }
+    let mut input = b"grc20:snapshot:".to_vec();
+    input.extend_from_slice(id);
+    input.extend_from_slice(&(index as u64).to_be_bytes());
+    crate::model::derived_uuid(&input)
+}
+
+pub(crate) fn write_optional_id(writer: &mut Writer, id: &Option<Id>) {
+    writer.write_id(id.as_ref().unwrap_or(&NIL_ID));
+}
+
+/// Writes a self-describing value payload for hashing.
+pub(crate) fn write_value(writer: &mut Writer, value: &Value<'_>) {
+    writer.write_byte(value.data_type() as u8);
+    match value {
+        Value::Bool(v) => writer.write_byte(*v as u8),
+        Value::Int64 { value, unit } => {
+            writer.write_signed_varint(*value);
+            write_optional_id(writer, unit);
+        }
+        Value::Float64 { value, unit } => {
+            writer.write_f64(*value);
+            write_optional_id(writer, unit);
+        }
+        Value::Decimal { exponent, mantissa, unit } => {
+            writer.write_signed_varint(*exponent as i64);
+            match mantissa {
+                DecimalMantissa::I64(v) => {
+                    writer.write_byte(0);
+                    writer.write_signed_varint(*v);
+                }
+                DecimalMantissa::Big(bytes) => {
+                    writer.write_byte(1);
+                    writer.write_bytes_prefixed(bytes);
+                }
+            }
+            write_optional_id(writer, unit);
+        }
+        Value::Text { value, .. } => writer.write_string(value),
+        Value::Bytes(data) => writer.write_bytes_prefixed(data),
+        Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => {
+            writer.write_string(s)
+        }
+        Value::Point { lon, lat, alt } => {
+            writer.write_f64(*lon);
+            writer.write_f64(*lat);
+            match alt {
+                Some(alt) => {
+                    writer.write_byte(1);
+                    writer.write_f64(*alt);
+                }
+                None => writer.write_byte(0),
+            }
+        }
+        Value::Embedding { sub_type, dims, data } => {
+            writer.write_byte(*sub_type as u8);
+            writer.write_varint(*dims as u64);
+            writer.write_bytes_prefixed(data);
+        }
+    }
+}
+
+pub(crate) fn read_optional_id(
+    reader: &mut Reader<'_>,
+    field: &'static str,
+) -> Result<Option<Id>, DecodeError> {
+    let id = reader.read_id(field)?;
+    Ok((id != NIL_ID).then_some(id))
+}
+
+/// Reads a payload written by [`write_value`]. The language of a TEXT value
+/// is not part of the payload and is passed in.
+pub(crate) fn read_value(
+    reader: &mut Reader<'_>,
+    language: Option<Id>,
+) -> Result<Value<'static>, DecodeError> {
+    let data_type = reader.read_byte("value.type")?;
+    let data_type =
+        DataType::from_u8(data_type).ok_or(DecodeError::InvalidDataType { data_type })?;
+    let read_text =
+        |reader: &mut Reader<'_>| reader.read_string(MAX_STRING_LEN, "value.text").map(Cow::Owned);
+    Ok(match data_type {
+        DataType::Bool => match reader.read_byte("value.bool")? {
+            0 => Value::Bool(false),
+            1 => Value::Bool(true),
+            value => return Err(DecodeError::InvalidBool { value }),
+        },
+        DataType::Int64 => Value::Int64 {
+            value: reader.read_signed_varint("value.int64")?,
+            unit: read_optional_id(reader, "value.unit")?,
+        },
+        DataType::Float64 => Value::Float64 {
+            value: reader.read_f64("value.float64")?,
+            unit: read_optional_id(reader, "value.unit")?,
+        },
+        DataType::Decimal => {
+            let exponent = reader.read_signed_varint("value.decimal.exponent")? as i32;
+            let mantissa = match reader.read_byte("value.decimal.mantissa")? {
+                0 => DecimalMantissa::I64(reader.read_signed_varint("value.decimal.mantissa")?),
+                1 => DecimalMantissa::Big(Cow::Owned(
+                    reader.read_bytes_prefixed(MAX_BYTES_LEN, "value.decimal.mantissa")?,
+                )),
+                _ => {
+                    return Err(DecodeError::MalformedEncoding {
+                        context: "value.decimal.mantissa",
+                    })
+                }
+            };
+            Value::Decimal { exponent, mantissa, unit: read_optional_id(reader, "value.unit")? }
+        }
+        DataType::Text => Value::Text { value: read_text(reader)?, language },
+        DataType::Bytes => {
+            Value::Bytes(Cow::Owned(reader.read_bytes_prefixed(MAX_BYTES_LEN, "value.bytes")?))
+        }
+        DataType::Date => Value::Date(read_text(reader)?),
+        DataType::Time => Value::Time(read_text(reader)?),
+        DataType::Datetime => Value::Datetime(read_text(reader)?),
+        DataType::Schedule => Value::Schedule(read_text(reader)?),
+        DataType::Point => {
+            let lon = reader.read_f64("value.point")?;
+            let lat = reader.read_f64("value.point")?;
+            let alt = match reader.read_byte("value.point")? {
+                0 => None,
+                1 => Some(reader.read_f64("value.point")?),
+                value => return Err(DecodeError::InvalidBool { value }),
+            };
+            Value::Point { lon, lat, alt }
+        }
+        DataType::Embedding => {
+            let sub_type = reader.read_byte("value.embedding")?;
+            let sub_type = EmbeddingSubType::from_u8(sub_type)
+                .ok_or(DecodeError::InvalidEmbeddingSubType { sub_type })?;
+            let dims = reader.read_varint("value.embedding")? as usize;
+            let data = reader.read_bytes_prefixed(MAX_EMBEDDING_BYTES, "value.embedding")?;
+            Value::Embedding { sub_type, dims, data: Cow::Owned(data) }
+        }
+    })
+}
+
+/// Returns the values an op sets or unsets and the payload bytes it
+/// carries, as counted by [`ApplyBudget`].
+fn op_cost(op: &Op<'_>) -> (usize, usize) {
+    let values_bytes = |values: &[PropertyValue<'_>]| -> usize {
+        values.iter().map(|pv| value_bytes(&pv.value)).sum()
+    };
+    match op {
+        Op::CreateEntity(ce) => (ce.values.len(), values_bytes(&ce.values)),
+        Op::UpdateEntity(ue) => (
+            ue.set_properties.len() + ue.unset_values.len() + ue.remove_values.len(),
+            values_bytes(&ue.set_properties),
+        ),
+        Op::CreateRelation(cr) => (0, cr.position.as_ref().map_or(0, |p| p.len())),
+        Op::UpdateRelation(ur) => (0, ur.position.as_ref().map_or(0, |p| p.len())),
+        _ => (0, 0),
+    }
+}
+
+/// Returns the payload bytes of a variable-length value; fixed-width values
+/// count as zero.
+fn value_bytes(value: &Value<'_>) -> usize {
+    match value {
+        Value::Text { value, .. } => value.len(),
+        Value::Bytes(bytes) => bytes.len(),
+        Value::Date(s) | Value::Time(s) | Value::Datetime(s) | Value::Schedule(s) => s.len(),
+        Value::Decimal { mantissa: DecimalMantissa::Big(bytes), .. } => bytes.len(),
+        Value::Embedding { data, .. } => data.len(),
+        _ => 0,
+    }
+}
+
+/// Read-modify-write builder for one entity, from [`GraphState::update`].
+///
+/// Holds the desired values for the entity. [`build`](Self::build) compares
+/// them with the state the builder was created from and emits a minimal
+/// UpdateEntity op (see [`UpdateEntity::patch`]).
+#[derive(Debug, Clone)]
+pub struct EntityUpdate<'s> {
+    id: Id,
+    current: Option<&'s BTreeMap<ValueKey, Value<'static>>>,
+    values: BTreeMap<ValueKey, Value<'static>>,
+}
+
+impl EntityUpdate<'_> {
+    /// Returns the entity ID.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Returns the desired value of a property (and language, for TEXT).
+    pub fn value(&self, property: &Id, language: Option<&Id>) -> Option<&Value<'static>> {
+        self.values.get(&(*property, language.copied()))
+    }
+
+    /// Returns all desired values, keyed by (property, language).
+    pub fn values(&self) -> &BTreeMap<ValueKey, Value<'static>> {
+        &self.values
+    }
+
+    /// Sets a value, adding the slot if it is not present.
+    ///
+    /// The slot's language is taken from the value (TEXT only). Setting a
+    /// slot to its current value records no change.
+    pub fn set(mut self, property: Id, value: Value<'_>) -> Self {
+        let language = match &value {
+            Value::Text { language, .. } => *language,
+            _ => None,
+        };
+        self.values.insert((property, language), value.into_owned());
+        self
+    }
+
+    /// Removes one slot of a property: `None` for the non-localized slot,
+    /// or a specific language.
+    pub fn remove(mut self, property: Id, language: Option<Id>) -> Self {
+        self.values.remove(&(property, language));
+        self
+    }
+
+    /// Removes every slot of a property, across languages.
+    pub fn unset(mut self, property: Id) -> Self {
+        self.values.retain(|(p, _), _| *p != property);
+        self
+    }
+
+    /// Returns true if the desired values differ from the current ones.
+    pub fn is_changed(&self) -> bool {
+        match self.current {
+            Some(current) => *current != self.values,
+            None => !self.values.is_empty(),
+        }
+    }
+
+    /// Emits the minimal UpdateEntity op, or `None` if nothing changed.
+    pub fn build(self) -> Option<Op<'static>> {
+        if !self.is_changed() {
+            return None;
+        }
+        let empty = BTreeMap::new();
+        let current = self.current.unwrap_or(&empty);
+        Some(Op::UpdateEntity(UpdateEntity::patch(self.id, current, &self.values)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{EditBuilder, UnsetLanguage, UnsetValue};
+
+    const NAME: Id = [1u8; 16];
+    const AGE: Id = [2u8; 16];
+    const KNOWS: Id = [3u8; 16];
+
+    fn replay(edits: &[Edit<'_>]) -> GraphState {
+        let mut state = GraphState::new();
+        edits.iter().for_each(|edit| state.apply_edit(edit));
+        state
+    }
+
+    #[test]
+    fn test_lww_unset_and_tombstones() {
+        let alice = [10u8; 16];
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| {
+                e.text(NAME, "Alice", None)
+                    .text(NAME, "Alicia", Some([9u8; 16]))
+                    .int64(AGE, 30, None)
+            })
+            .update_entity(alice, |u| u.set_int64(AGE, 31, None).unset_all(NAME))
+            .delete_entity(alice)
+            .update_entity(alice, |u| u.set_int64(AGE, 99, None))
+            .create_entity(alice, |e| e.int64(AGE, 100, None))
+            .update_entity([11u8; 16], |u| u.set_int64(AGE, 1, None))
+            .build();
+        let mut state = replay(&[edit]);
+
+        let entity = state.entity(&alice).unwrap();
+        assert!(entity.deleted);
+        assert_eq!(entity.value(&NAME, None), None);
+        assert_eq!(entity.value(&AGE, None), Some(&Value::Int64 { value: 31, unit: None }));
+        // Updates to missing entities do not create them
+        assert!(state.entity(&[11u8; 16]).is_none());
+
+        let restore = EditBuilder::new([1u8; 16]).restore_entity(alice).build();
+        state.apply_edit(&restore);
+        assert!(!state.entity(&alice).unwrap().deleted);
+    }
+
+    #[test]
+    fn test_update_emits_minimal_op() {
+        let alice = [10u8; 16];
+        let fr = [9u8; 16];
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| {
+                e.text(NAME, "Alice", None)
+                    .text(NAME, "Alice", Some(fr))
+                    .int64(AGE, 30, None)
+                    .bool(KNOWS, true)
+            })
+            .build();
+        let mut state = replay(&[edit]);
+
+        let unchanged = state.update(alice).set(AGE, Value::Int64 { value: 30, unit: None });
+        assert!(unchanged.build().is_none());
+
+        let op = state
+            .update(alice)
+            .set(AGE, Value::Int64 { value: 31, unit: None })
+            .remove(NAME, Some(fr))
+            .unset(KNOWS)
+            .build()
+            .unwrap();
+        let Op::UpdateEntity(ue) = &op else { panic!("expected UpdateEntity") };
+        assert_eq!(ue.set_properties.len(), 1);
+        assert_eq!(
+            ue.unset_values,
+            vec![
+                UnsetValue { property: NAME, language: UnsetLanguage::Specific(fr) },
+                UnsetValue { property: KNOWS, language: UnsetLanguage::All },
+            ]
+        );
+
+        state.apply_op(&op);
+        let entity = state.entity(&alice).unwrap();
+        assert_eq!(entity.values.len(), 2);
+        assert_eq!(entity.value(&AGE, None), Some(&Value::Int64 { value: 31, unit: None }));
+        assert!(entity.value(&NAME, None).is_some());
+    }
+
+    #[test]
+    fn test_transaction_rolls_back() {
+        let (alice, rel, vref) = ([10u8; 16], [20u8; 16], [30u8; 16]);
+        let mut state = replay(&[EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| e.int64(AGE, 30, None))
+            .build()]);
+        let before = state.state_hash();
+
+        let edits = [
+            EditBuilder::new([1u8; 16])
+                .update_entity(alice, |u| u.set_int64(AGE, 31, None))
+                .create_relation_simple(rel, alice, alice, KNOWS)
+                .build(),
+            EditBuilder::new([2u8; 16])
+                .op(Op::CreateValueRef(CreateValueRef {
+                    id: vref,
+                    entity: alice,
+                    property: AGE,
+                    language: None,
+                    space: None,
+                }))
+                .delete_entity(alice)
+                .build(),
+        ];
+
+        // The second check fails after the first edit was applied
+        let mut seen = 0;
+        let result = state.apply_transaction(&edits, |state, _| {
+            seen += 1;
+            if state.relation(&rel).is_some() { Err("rejected") } else { Ok(()) }
+        });
+        assert_eq!((result, seen), (Err("rejected"), 2));
+        assert_eq!(state.state_hash(), before);
+        assert!(state.entity(&relation_entity_id(&rel)).is_none());
+
+        assert_eq!(state.apply_transaction(&edits, |_, _| Ok::<_, ()>(())), Ok(()));
+        assert!(state.entity(&alice).unwrap().deleted);
+        assert_eq!(state.value_refs().count(), 1);
+    }
+
+    #[test]
+    fn test_schedule_index_follows_state() {
+        const WHEN: Id = [4u8; 16];
+        let (alice, bob) = ([10u8; 16], [11u8; 16]);
+        let day = |d: u32| crate::model::timestamp::days_from_civil(2024, 3, d) * 86_400_000_000;
+        let window = |state: &GraphState, d: u32| {
+            state.schedules().entities_in_window(day(d), day(d + 1)).unwrap()
+        };
+
+        let daily = "DTSTART:20240301T090000Z\nRRULE:FREQ=DAILY";
+        let mut state = replay(&[EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| e.schedule(WHEN, daily))
+            .create_entity(bob, |e| e.schedule(WHEN, "DTSTART:20240305T090000Z"))
+            .build()]);
+        assert_eq!(state.schedules().len(), 2);
+        assert_eq!(window(&state, 5), vec![alice, bob]);
+
+        // Replacing, unsetting, and deleting update the index
+        state.apply_edit(
+            &EditBuilder::new([1u8; 16])
+                .update_entity(alice, |u| u.set_schedule(WHEN, "DTSTART:20240310T090000Z"))
+                .update_entity(bob, |u| u.unset_all(WHEN))
+                .build(),
+        );
+        assert_eq!(window(&state, 5), Vec::<Id>::new());
+        assert_eq!(window(&state, 10), vec![alice]);
+        state.apply_edit(&EditBuilder::new([2u8; 16]).delete_entity(alice).build());
+        assert!(state.schedules().is_empty());
+        state.apply_edit(&EditBuilder::new([3u8; 16]).restore_entity(alice).build());
+        assert_eq!(window(&state, 10), vec![alice]);
+
+        // A rolled-back transaction leaves the index as it was
+        let edits = [EditBuilder::new([4u8; 16])
+            .update_entity(bob, |u| u.set_schedule(WHEN, "DTSTART:20240310T120000Z"))
+            .delete_entity(alice)
+            .build()];
+        let result = state.apply_transaction(&edits, |_, _| Ok::<_, ()>(()));
+        assert_eq!(result, Ok(()));
+        assert_eq!(window(&state, 10), vec![bob]);
+        state.begin_journal();
+        state.apply_edit(&EditBuilder::new([5u8; 16]).restore_entity(alice).build());
+        state.apply_edit(&EditBuilder::new([6u8; 16]).delete_entity(bob).build());
+        assert_eq!(window(&state, 10), vec![alice]);
+        state.rollback_journal();
+        assert_eq!(window(&state, 10), vec![bob]);
+    }
+
+    #[test]
+    fn test_budgeted_apply_resumes() {
+        let (alice, bob, rel) = ([10u8; 16], [11u8; 16], [20u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| e.text(NAME, "Alice", None).int64(AGE, 30, None))
+            .create_entity(bob, |e| e.text(NAME, "b".repeat(100), None))
+            .update_entity(alice, |u| u.set_int64(AGE, 31, None).unset_all(NAME))
+            .create_relation_simple(rel, alice, bob, KNOWS)
+            .delete_entity(bob)
+            .build();
+        let expected = replay(std::slice::from_ref(&edit)).state_hash();
+
+        let budget = ApplyBudget::new().with_max_values(2).with_max_bytes(50);
+        let mut state = GraphState::new();
+        let progress = state.apply_edit_budgeted(&edit, 0, budget);
+        assert_eq!(
+            progress,
+            ApplyProgress {
+                next_op: 1,
+                ops: 1,
+                values: 2,
+                bytes: 5,
+                exhausted: Some(BudgetLimit::Values)
+            }
+        );
+        // The first op of a call is applied even over budget
+        let progress = state.apply_edit_budgeted(&edit, 1, budget);
+        assert_eq!((progress.next_op, progress.bytes), (2, 100));
+        let progress = state.apply_edit_budgeted(&edit, 2, budget);
+        assert_eq!((progress.next_op, progress.ops), (5, 3));
+        assert!(progress.is_complete());
+        assert_eq!(state.state_hash(), expected);
+
+        let mut state = GraphState::new();
+        let (mut next_op, mut calls) = (0, 0);
+        loop {
+            let progress =
+                state.apply_edit_budgeted(&edit, next_op, ApplyBudget::new().with_max_ops(2));
+            next_op = progress.next_op;
+            calls += 1;
+            if progress.is_complete() {
+                break;
+            }
+            assert_eq!(progress.exhausted, Some(BudgetLimit::Ops));
+        }
+        assert_eq!(calls, 3);
+        assert_eq!(state.state_hash(), expected);
+    }
+
+    #[test]
+    fn test_relations_and_namespace() {
+        let (alice, bob, rel) = ([10u8; 16], [11u8; 16], [20u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_relation_simple(rel, alice, bob, KNOWS)
+            // Collides with the relation ID: ignored
+            .create_entity(rel, |e| e.int64(AGE, 1, None))
+            .update_relation(rel, |r| r.set_position("a"))
+            .delete_relation(rel)
+            .update_relation(rel, |r| r.set_position("b"))
+            .build();
+        let state = replay(&[edit]);
+
+        let relation = state.relation(&rel).unwrap();
+        assert!(relation.deleted);
+        assert_eq!(relation.position.as_deref(), Some("a"));
+        assert!(state.entity(&rel).is_none());
+        // Reified entity is created implicitly
+        assert!(state.entity(&relation_entity_id(&rel)).is_some());
+    }
+
+    #[test]
+    fn test_snapshot_reproduces_state_hash() {
+        let (alice, bob, rel) = ([10u8; 16], [11u8; 16], [20u8; 16]);
+        let first = EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| e.text(NAME, "Alice", None).int64(AGE, 30, None))
+            .create_relation_simple(rel, alice, bob, KNOWS)
+            .build();
+        // A later edit uses AGE with a different type
+        let second = EditBuilder::new([1u8; 16])
+            .create_entity(bob, |e| e.text(AGE, "forty", None))
+            .delete_entity(alice)
+            .build();
+        let state = replay(&[first, second]);
+
+        let snapshot = state.snapshot_edits([7u8; 16], 42);
+        assert_eq!(snapshot.len(), 3);
+        assert_eq!(snapshot[0].id, [7u8; 16]);
+        for edit in &snapshot {
+            let bytes = crate::codec::encode_edit(edit).unwrap();
+            assert_eq!(&crate::codec::decode_edit(&bytes).unwrap(), edit);
+        }
+        assert_eq!(replay(&snapshot).state_hash(), state.state_hash());
+        assert_ne!(GraphState::new().state_hash(), state.state_hash());
+    }
+}
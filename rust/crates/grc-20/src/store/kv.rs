@@ -0,0 +1,836 @@
+//! Graph state in an ordered key-value store.
+//!
+//! [`KvGraph`] applies ops to resolved state kept in any store behind
+//! [`KvStore`] and reads it back by ID. It loads the objects each op
+//! touches and runs [`reduce`] over them, so it resolves ops exactly as
+//! [`GraphState`] does; indexers sharing a store therefore also share one
+//! key layout instead of each inventing their own. [`MemoryStore`] keeps
+//! the entries in memory, and with the `sled` feature [`sled::Tree`] is a
+//! store too.
+//!
+//! Key layout (IDs are 16 raw bytes; an absent language, space, unit, or
+//! pin is written as [`NIL_ID`]):
+//!
+//! ```text
+//! "e" entity                          -> deleted: u8
+//! "v" entity property language        -> value
+//! "r" relation                        -> relation record
+//! "o" from relation_type relation     -> (empty)
+//! "i" to relation_type relation       -> (empty)
+//! "s" entity property language space  -> value ref ID
+//! "c" value_ref                       -> bound slot count: u64 big-endian
+//!
+//! value: data_type: u8, then the payload hashed by GraphState::state_hash
+//! relation record:
+//!   relation_type from from_space from_version to to_space to_version entity
+//!   flags: u8 (bit 0 from_is_value_ref, bit 1 to_is_value_ref,
+//!              bit 2 explicit_entity, bit 3 deleted, bit 4 has_position)
+//!   position: varint length + UTF-8 (if has_position)
+//! ```
+//!
+//! The `o` and `i` adjacency keys list only live relations: tombstoning a
+//! relation removes them and restoring it adds them back. Tombstoned
+//! entities keep their values, as in [`GraphState`].
+//!
+//! [`GraphState`]: crate::state::GraphState
+
+use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use rustc_hash::FxHashMap;
+
+use crate::codec::primitives::{Reader, Writer};
+use crate::error::{DecodeError, KvError};
+use crate::limits::MAX_POSITION_LEN;
+use crate::model::id::relation_entity_id;
+use crate::model::{
+    CreateEntity, DeleteEntity, DeleteRelation, Edit, Id, Op, RestoreEntity, RestoreRelation,
+    UpdateEntity, UpdateRelation, Value, NIL_ID,
+};
+use crate::reduce::{reduce, StateChange, StateView};
+use crate::state::{
+    read_optional_id, read_value, write_optional_id, write_value, EntityState, RelationState,
+    ValueKey, ValueSlot,
+};
+
+const ENTITY: u8 = b'e';
+const VALUE: u8 = b'v';
+const RELATION: u8 = b'r';
+const OUTGOING: u8 = b'o';
+const INCOMING: u8 = b'i';
+const VALUE_REF: u8 = b's';
+const VALUE_REF_SLOTS: u8 = b'c';
+
+const FROM_IS_VALUE_REF: u8 = 0x01;
+const TO_IS_VALUE_REF: u8 = 0x02;
+const EXPLICIT_ENTITY: u8 = 0x04;
+const DELETED: u8 = 0x08;
+const HAS_POSITION: u8 = 0x10;
+
+/// Entries yielded by [`KvStore::scan_prefix`].
+pub type KvEntries<'s, E> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>), E>> + 's>;
+
+/// An ordered key-value store that can hold graph state.
+pub trait KvStore {
+    /// Error returned by the store.
+    type Error;
+
+    /// Returns the value stored under `key`.
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Iterates in key order over the entries whose key starts with `prefix`.
+    fn scan_prefix(&self, prefix: &[u8]) -> KvEntries<'_, Self::Error>;
+
+    /// Applies a batch of writes atomically.
+    fn write(&mut self, batch: WriteBatch) -> Result<(), Self::Error>;
+}
+
+/// Puts and deletes applied together by [`KvStore::write`].
+///
+/// Writes apply in order, so a later write to a key wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+    /// Each key with the value to put, or None to delete it.
+    pub writes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a put.
+    pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.writes.push((key, Some(value)));
+    }
+
+    /// Adds a delete.
+    pub fn delete(&mut self, key: Vec<u8>) {
+        self.writes.push((key, None));
+    }
+
+    /// Returns true if the batch has no writes.
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+}
+
+/// A [`KvStore`] in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStore {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Iterates over every entry in key order.
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.entries.iter().map(|(key, value)| (key.as_slice(), value.as_slice()))
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the store has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl KvStore for MemoryStore {
+    type Error = Infallible;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Infallible> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> KvEntries<'_, Infallible> {
+        let prefix = prefix.to_vec();
+        Box::new(
+            self.entries
+                .range(prefix.clone()..)
+                .take_while(move |(key, _)| key.starts_with(&prefix))
+                .map(|(key, value)| Ok((key.clone(), value.clone()))),
+        )
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), Infallible> {
+        for (key, value) in batch.writes {
+            match value {
+                Some(value) => self.entries.insert(key, value),
+                None => self.entries.remove(&key),
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+impl KvStore for sled::Tree {
+    type Error = sled::Error;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, sled::Error> {
+        Ok(sled::Tree::get(self, key)?.map(|value| value.to_vec()))
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> KvEntries<'_, sled::Error> {
+        Box::new(
+            sled::Tree::scan_prefix(self, prefix)
+                .map(|entry| entry.map(|(key, value)| (key.to_vec(), value.to_vec()))),
+        )
+    }
+
+    fn write(&mut self, batch: WriteBatch) -> Result<(), sled::Error> {
+        let mut sled_batch = sled::Batch::default();
+        for (key, value) in batch.writes {
+            match value {
+                Some(value) => sled_batch.insert(key, value),
+                None => sled_batch.remove(key),
+            }
+        }
+        self.apply_batch(sled_batch)
+    }
+}
+
+fn key(tag: u8, ids: &[&Id]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + 16 * ids.len());
+    key.push(tag);
+    for id in ids {
+        key.extend_from_slice(&id[..]);
+    }
+    key
+}
+
+/// Returns the key of an entity record.
+pub fn entity_key(id: &Id) -> Vec<u8> {
+    key(ENTITY, &[id])
+}
+
+/// Returns the key of a value slot of an entity.
+pub fn value_key(entity: &Id, property: &Id, language: Option<&Id>) -> Vec<u8> {
+    key(VALUE, &[entity, property, language.unwrap_or(&NIL_ID)])
+}
+
+/// Returns the prefix of every value key of an entity.
+pub fn value_prefix(entity: &Id) -> Vec<u8> {
+    key(VALUE, &[entity])
+}
+
+/// Returns the key of a relation record.
+pub fn relation_key(id: &Id) -> Vec<u8> {
+    key(RELATION, &[id])
+}
+
+/// Returns the prefix of the outgoing adjacency keys of `from`, optionally
+/// narrowed to one relation type.
+pub fn outgoing_prefix(from: &Id, relation_type: Option<&Id>) -> Vec<u8> {
+    match relation_type {
+        Some(relation_type) => key(OUTGOING, &[from, relation_type]),
+        None => key(OUTGOING, &[from]),
+    }
+}
+
+/// Returns the prefix of the incoming adjacency keys of `to`, optionally
+/// narrowed to one relation type.
+pub fn incoming_prefix(to: &Id, relation_type: Option<&Id>) -> Vec<u8> {
+    match relation_type {
+        Some(relation_type) => key(INCOMING, &[to, relation_type]),
+        None => key(INCOMING, &[to]),
+    }
+}
+
+/// Returns the key of a value ref slot binding.
+pub fn value_ref_key(slot: &ValueSlot) -> Vec<u8> {
+    key(
+        VALUE_REF,
+        &[
+            &slot.entity,
+            &slot.property,
+            slot.language.as_ref().unwrap_or(&NIL_ID),
+            slot.space.as_ref().unwrap_or(&NIL_ID),
+        ],
+    )
+}
+
+/// Returns the key of the number of slots a value ref is bound to.
+pub fn value_ref_slots_key(id: &Id) -> Vec<u8> {
+    key(VALUE_REF_SLOTS, &[id])
+}
+
+fn adjacency_keys(id: &Id, relation: &RelationState) -> [Vec<u8>; 2] {
+    [
+        key(OUTGOING, &[&relation.from, &relation.relation_type, id]),
+        key(INCOMING, &[&relation.to, &relation.relation_type, id]),
+    ]
+}
+
+fn encode_value(value: &Value<'_>) -> Vec<u8> {
+    let mut writer = Writer::new();
+    write_value(&mut writer, value);
+    writer.into_bytes()
+}
+
+fn encode_relation(relation: &RelationState) -> Vec<u8> {
+    let mut writer = Writer::with_capacity(8 * 16 + 1);
+    writer.write_id(&relation.relation_type);
+    writer.write_id(&relation.from);
+    write_optional_id(&mut writer, &relation.from_space);
+    write_optional_id(&mut writer, &relation.from_version);
+    writer.write_id(&relation.to);
+    write_optional_id(&mut writer, &relation.to_space);
+    write_optional_id(&mut writer, &relation.to_version);
+    writer.write_id(&relation.entity);
+    let mut flags = 0;
+    if relation.from_is_value_ref {
+        flags |= FROM_IS_VALUE_REF;
+    }
+    if relation.to_is_value_ref {
+        flags |= TO_IS_VALUE_REF;
+    }
+    if relation.explicit_entity {
+        flags |= EXPLICIT_ENTITY;
+    }
+    if relation.deleted {
+        flags |= DELETED;
+    }
+    if relation.position.is_some() {
+        flags |= HAS_POSITION;
+    }
+    writer.write_byte(flags);
+    if let Some(position) = &relation.position {
+        writer.write_string(position);
+    }
+    writer.into_bytes()
+}
+
+fn decode_relation(bytes: &[u8]) -> Result<RelationState, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    let relation_type = reader.read_id("relation.type")?;
+    let from = reader.read_id("relation.from")?;
+    let from_space = read_optional_id(&mut reader, "relation.from_space")?;
+    let from_version = read_optional_id(&mut reader, "relation.from_version")?;
+    let to = reader.read_id("relation.to")?;
+    let to_space = read_optional_id(&mut reader, "relation.to_space")?;
+    let to_version = read_optional_id(&mut reader, "relation.to_version")?;
+    let entity = reader.read_id("relation.entity")?;
+    let flags = reader.read_byte("relation.flags")?;
+    if flags & !(FROM_IS_VALUE_REF | TO_IS_VALUE_REF | EXPLICIT_ENTITY | DELETED | HAS_POSITION)
+        != 0
+    {
+        return Err(DecodeError::ReservedBitsSet { context: "relation.flags" });
+    }
+    let position = if flags & HAS_POSITION != 0 {
+        Some(reader.read_string(MAX_POSITION_LEN, "relation.position")?)
+    } else {
+        None
+    };
+    expect_end(&reader)?;
+    Ok(RelationState {
+        relation_type,
+        from,
+        from_is_value_ref: flags & FROM_IS_VALUE_REF != 0,
+        from_space,
+        from_version,
+        to,
+        to_is_value_ref: flags & TO_IS_VALUE_REF != 0,
+        to_space,
+        to_version,
+        entity,
+        explicit_entity: flags & EXPLICIT_ENTITY != 0,
+        position,
+        deleted: flags & DELETED != 0,
+    })
+}
+
+fn decode_deleted(bytes: &[u8]) -> Result<bool, DecodeError> {
+    match bytes {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        [value] => Err(DecodeError::InvalidBool { value: *value }),
+        _ => Err(DecodeError::MalformedEncoding { context: "entity record" }),
+    }
+}
+
+fn decode_id(bytes: &[u8], context: &'static str) -> Result<Id, DecodeError> {
+    bytes.try_into().map_err(|_| DecodeError::MalformedEncoding { context })
+}
+
+/// Returns the IDs that follow the `skip`-byte prefix of a fixed-width key.
+fn key_ids<const N: usize>(key: &[u8], skip: usize) -> Result<[Id; N], DecodeError> {
+    let ids = key
+        .get(skip..)
+        .filter(|rest| rest.len() == 16 * N)
+        .ok_or(DecodeError::MalformedEncoding { context: "key" })?;
+    Ok(core::array::from_fn(|i| ids[16 * i..16 * (i + 1)].try_into().unwrap()))
+}
+
+fn expect_end(reader: &Reader<'_>) -> Result<(), DecodeError> {
+    if reader.is_empty() {
+        Ok(())
+    } else {
+        Err(DecodeError::MalformedEncoding { context: "trailing bytes in record" })
+    }
+}
+
+fn corrupt<E>(key: &[u8]) -> impl FnOnce(DecodeError) -> KvError<E> + '_ {
+    move |source| KvError::Corrupt { key: key.to_vec(), source }
+}
+
+/// Resolved graph state in a [`KvStore`].
+#[derive(Debug, Clone, Default)]
+pub struct KvGraph<S> {
+    store: S,
+}
+
+impl<S: KvStore> KvGraph<S> {
+    /// Wraps a store, which may already hold state.
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Returns the underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the underlying store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Applies every op of an edit in order, in one write batch.
+    pub fn apply_edit(&mut self, edit: &Edit<'_>) -> Result<(), KvError<S::Error>> {
+        self.apply_ops(&edit.ops)
+    }
+
+    /// Applies a single op.
+    pub fn apply_op(&mut self, op: &Op<'_>) -> Result<(), KvError<S::Error>> {
+        self.apply_ops(core::slice::from_ref(op))
+    }
+
+    /// Applies ops in order, in one write batch, so either all of them or
+    /// none of them reach the store.
+    pub fn apply_ops(&mut self, ops: &[Op<'_>]) -> Result<(), KvError<S::Error>> {
+        let mut loaded = Loaded::default();
+        let mut batch = WriteBatch::new();
+        for op in ops {
+            loaded.load(self, op)?;
+            for change in reduce(op, &loaded).changes {
+                loaded.apply(change, &mut batch);
+            }
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.store.write(batch).map_err(KvError::Store)
+    }
+
+    /// Returns an entity's state, including tombstoned entities.
+    pub fn entity(&self, id: &Id) -> Result<Option<EntityState>, KvError<S::Error>> {
+        let key = entity_key(id);
+        let Some(record) = self.store.get(&key).map_err(KvError::Store)? else {
+            return Ok(None);
+        };
+        let deleted = decode_deleted(&record).map_err(corrupt(&key))?;
+        let mut values = BTreeMap::new();
+        for entry in self.store.scan_prefix(&value_prefix(id)) {
+            let (key, bytes) = entry.map_err(KvError::Store)?;
+            let (value_key, value) = decode_value_entry(&key, &bytes).map_err(corrupt(&key))?;
+            values.insert(value_key, value);
+        }
+        Ok(Some(EntityState { values, deleted }))
+    }
+
+    /// Returns a relation's state, including tombstoned relations.
+    pub fn relation(&self, id: &Id) -> Result<Option<RelationState>, KvError<S::Error>> {
+        let key = relation_key(id);
+        match self.store.get(&key).map_err(KvError::Store)? {
+            Some(record) => decode_relation(&record).map(Some).map_err(corrupt(&key)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the value ref ID bound to a slot.
+    pub fn value_ref(&self, slot: &ValueSlot) -> Result<Option<Id>, KvError<S::Error>> {
+        let key = value_ref_key(slot);
+        match self.store.get(&key).map_err(KvError::Store)? {
+            Some(id) => decode_id(&id, "value ref").map(Some).map_err(corrupt(&key)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns true if `id` is bound to at least one value slot.
+    pub fn is_value_ref(&self, id: &Id) -> Result<bool, KvError<S::Error>> {
+        Ok(self.value_ref_slots(id)? > 0)
+    }
+
+    /// Returns the IDs of the live relations from `from`, optionally of one
+    /// relation type, ordered by relation type and then ID.
+    pub fn outgoing(
+        &self,
+        from: &Id,
+        relation_type: Option<&Id>,
+    ) -> Result<Vec<Id>, KvError<S::Error>> {
+        self.adjacent(&outgoing_prefix(from, None), relation_type)
+    }
+
+    /// Returns the IDs of the live relations to `to`, optionally of one
+    /// relation type, ordered by relation type and then ID.
+    pub fn incoming(
+        &self,
+        to: &Id,
+        relation_type: Option<&Id>,
+    ) -> Result<Vec<Id>, KvError<S::Error>> {
+        self.adjacent(&incoming_prefix(to, None), relation_type)
+    }
+
+    fn adjacent(
+        &self,
+        prefix: &[u8],
+        relation_type: Option<&Id>,
+    ) -> Result<Vec<Id>, KvError<S::Error>> {
+        let mut scan_prefix = prefix.to_vec();
+        if let Some(relation_type) = relation_type {
+            scan_prefix.extend_from_slice(relation_type);
+        }
+        let mut ids = Vec::new();
+        for entry in self.store.scan_prefix(&scan_prefix) {
+            let (key, _) = entry.map_err(KvError::Store)?;
+            let [_, id] = key_ids::<2>(&key, prefix.len()).map_err(corrupt(&key))?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    fn value_ref_slots(&self, id: &Id) -> Result<u64, KvError<S::Error>> {
+        let key = value_ref_slots_key(id);
+        match self.store.get(&key).map_err(KvError::Store)? {
+            Some(count) => <[u8; 8]>::try_from(count.as_slice())
+                .map(u64::from_be_bytes)
+                .map_err(|_| DecodeError::MalformedEncoding { context: "value ref slot count" })
+                .map_err(corrupt(&key)),
+            None => Ok(0),
+        }
+    }
+}
+
+fn decode_value_entry(
+    key: &[u8],
+    bytes: &[u8],
+) -> Result<(ValueKey, Value<'static>), DecodeError> {
+    let [_, property, language] = key_ids::<3>(key, 1)?;
+    let language = (language != NIL_ID).then_some(language);
+    let mut reader = Reader::new(bytes);
+    let value = read_value(&mut reader, language)?;
+    expect_end(&reader)?;
+    Ok(((property, language), value))
+}
+
+/// The objects loaded for the ops of one [`KvGraph::apply_ops`] call, as
+/// changed by the ops reduced so far. None records a missing object.
+#[derive(Debug, Default)]
+struct Loaded {
+    entities: FxHashMap<Id, Option<EntityState>>,
+    relations: FxHashMap<Id, Option<RelationState>>,
+    value_refs: FxHashMap<ValueSlot, Option<Id>>,
+    value_ref_slots: FxHashMap<Id, u64>,
+}
+
+impl Loaded {
+    /// Loads everything [`reduce`] may read for `op`.
+    fn load<S: KvStore>(
+        &mut self,
+        graph: &KvGraph<S>,
+        op: &Op<'_>,
+    ) -> Result<(), KvError<S::Error>> {
+        match op {
+            Op::CreateEntity(CreateEntity { id, .. })
+            | Op::UpdateEntity(UpdateEntity { id, .. })
+            | Op::DeleteEntity(DeleteEntity { id })
+            | Op::RestoreEntity(RestoreEntity { id })
+            | Op::UpdateRelation(UpdateRelation { id, .. })
+            | Op::DeleteRelation(DeleteRelation { id })
+            | Op::RestoreRelation(RestoreRelation { id }) => self.load_object(graph, id),
+            Op::CreateRelation(cr) => {
+                let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+                self.load_object(graph, &cr.id)?;
+                self.load_object(graph, &entity)
+            }
+            Op::CreateValueRef(cvr) => {
+                self.load_object(graph, &cvr.id)?;
+                let slot = ValueSlot {
+                    entity: cvr.entity,
+                    property: cvr.property,
+                    language: cvr.language,
+                    space: cvr.space,
+                };
+                let bound = match self.value_refs.entry(slot) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => *entry.insert(graph.value_ref(&slot)?),
+                };
+                if let Some(bound) = bound {
+                    self.load_value_ref_slots(graph, &bound)?;
+                }
+                Ok(())
+            }
+            Op::CreateSpace(_)
+            | Op::AddMember(_)
+            | Op::RemoveMember(_)
+            | Op::UpdateSpaceMetadata(_)
+            | Op::Unknown(_) => Ok(()),
+        }
+    }
+
+    /// Loads whatever entity, relation, or value ref has this ID.
+    fn load_object<S: KvStore>(
+        &mut self,
+        graph: &KvGraph<S>,
+        id: &Id,
+    ) -> Result<(), KvError<S::Error>> {
+        if !self.entities.contains_key(id) {
+            self.entities.insert(*id, graph.entity(id)?);
+        }
+        if !self.relations.contains_key(id) {
+            self.relations.insert(*id, graph.relation(id)?);
+        }
+        self.load_value_ref_slots(graph, id)
+    }
+
+    fn load_value_ref_slots<S: KvStore>(
+        &mut self,
+        graph: &KvGraph<S>,
+        id: &Id,
+    ) -> Result<(), KvError<S::Error>> {
+        if !self.value_ref_slots.contains_key(id) {
+            self.value_ref_slots.insert(*id, graph.value_ref_slots(id)?);
+        }
+        Ok(())
+    }
+
+    /// Applies a change to the loaded objects and adds its writes to `batch`.
+    fn apply(&mut self, change: StateChange, batch: &mut WriteBatch) {
+        match change {
+            StateChange::CreateEntity(id) => {
+                self.entity_mut(id, batch);
+            }
+            StateChange::SetValue { entity, key, value } => {
+                batch.put(value_key(&entity, &key.0, key.1.as_ref()), encode_value(&value));
+                self.entity_mut(entity, batch).values.insert(key, value);
+            }
+            StateChange::RemoveValue { entity, key } => {
+                if let Some(Some(entity_state)) = self.entities.get_mut(&entity) {
+                    entity_state.values.remove(&key);
+                    batch.delete(value_key(&entity, &key.0, key.1.as_ref()));
+                }
+            }
+            StateChange::SetEntityDeleted { entity, deleted } => {
+                self.entity_mut(entity, batch).deleted = deleted;
+                batch.put(entity_key(&entity), vec![deleted as u8]);
+            }
+            StateChange::PutRelation { id, relation } => {
+                if let Some(Some(previous)) = self.relations.get(&id) {
+                    for key in adjacency_keys(&id, previous) {
+                        batch.delete(key);
+                    }
+                }
+                put_relation(&id, &relation, batch);
+                self.relations.insert(id, Some(relation));
+            }
+            StateChange::SetRelationDeleted { id, deleted } => {
+                if let Some(Some(relation)) = self.relations.get_mut(&id) {
+                    relation.deleted = deleted;
+                    if deleted {
+                        for key in adjacency_keys(&id, relation) {
+                            batch.delete(key);
+                        }
+                    }
+                    put_relation(&id, relation, batch);
+                }
+            }
+            StateChange::BindValueRef { slot, id } => {
+                batch.put(value_ref_key(&slot), id.to_vec());
+                if let Some(previous) = self.value_refs.insert(slot, Some(id)).flatten() {
+                    self.add_value_ref_slots(previous, -1, batch);
+                }
+                self.add_value_ref_slots(id, 1, batch);
+            }
+        }
+    }
+
+    /// Returns a loaded entity, creating it if it does not exist.
+    fn entity_mut(&mut self, id: Id, batch: &mut WriteBatch) -> &mut EntityState {
+        let entity = self.entities.entry(id).or_default();
+        if entity.is_none() {
+            batch.put(entity_key(&id), vec![0]);
+        }
+        entity.get_or_insert_with(EntityState::default)
+    }
+
+    fn add_value_ref_slots(&mut self, id: Id, delta: i64, batch: &mut WriteBatch) {
+        let count = self.value_ref_slots.entry(id).or_default();
+        *count = count.saturating_add_signed(delta);
+        match *count {
+            0 => batch.delete(value_ref_slots_key(&id)),
+            count => batch.put(value_ref_slots_key(&id), count.to_be_bytes().to_vec()),
+        }
+    }
+}
+
+/// Writes a relation record, and its adjacency keys if it is live.
+fn put_relation(id: &Id, relation: &RelationState, batch: &mut WriteBatch) {
+    batch.put(relation_key(id), encode_relation(relation));
+    if !relation.deleted {
+        for key in adjacency_keys(id, relation) {
+            batch.put(key, Vec::new());
+        }
+    }
+}
+
+impl StateView for Loaded {
+    fn entity(&self, id: &Id) -> Option<&EntityState> {
+        self.entities.get(id).and_then(Option::as_ref)
+    }
+
+    fn relation(&self, id: &Id) -> Option<&RelationState> {
+        self.relations.get(id).and_then(Option::as_ref)
+    }
+
+    fn value_ref(&self, slot: &ValueSlot) -> Option<Id> {
+        self.value_refs.get(slot).copied().flatten()
+    }
+
+    fn is_value_ref(&self, id: &Id) -> bool {
+        self.value_ref_slots.get(id).is_some_and(|count| *count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CreateValueRef, EditBuilder};
+    use crate::state::GraphState;
+
+    const A: Id = [1u8; 16];
+    const B: Id = [2u8; 16];
+    const P: Id = [3u8; 16];
+    const T: Id = [4u8; 16];
+    const R: Id = [5u8; 16];
+    const L: Id = [6u8; 16];
+    const V: Id = [7u8; 16];
+    const V2: Id = [8u8; 16];
+
+    fn value_ref(id: Id) -> Op<'static> {
+        Op::CreateValueRef(CreateValueRef {
+            id,
+            entity: A,
+            property: P,
+            language: None,
+            space: None,
+        })
+    }
+
+    fn assert_matches(graph: &KvGraph<MemoryStore>, state: &GraphState) {
+        for (id, entity) in state.entities() {
+            assert_eq!(graph.entity(id).unwrap().as_ref(), Some(entity));
+        }
+        for (id, relation) in state.relations() {
+            assert_eq!(graph.relation(id).unwrap().as_ref(), Some(relation));
+        }
+        for (slot, id) in state.value_refs() {
+            assert_eq!(graph.value_ref(slot).unwrap(), Some(*id));
+        }
+        let records = |tag| graph.store().entries().filter(|(key, _)| key[0] == tag).count();
+        assert_eq!(records(ENTITY), state.entity_count());
+        assert_eq!(records(RELATION), state.relation_count());
+        assert_eq!(records(VALUE_REF), state.value_refs().count());
+    }
+
+    #[test]
+    fn test_kv_graph_matches_graph_state() {
+        let edits = [
+            EditBuilder::new([0xE1; 16])
+                .create_entity(A, |e| {
+                    e.text(P, "hello", None).text(P, "hallo", Some(L)).float64(T, 1.5, None)
+                })
+                .create_entity(B, |e| e.int64(P, 7, None))
+                .create_relation_simple(R, A, B, T)
+                .update_relation_position(R, Some("a0".into()))
+                .op(value_ref(V))
+                .build(),
+            EditBuilder::new([0xE2; 16])
+                .update_entity(A, |u| u.unset_all(P).set_int64(T, 3, None))
+                .delete_relation(R)
+                .op(value_ref(V2))
+                .delete_entity(B)
+                .create_empty_entity(V2)
+                .build(),
+            EditBuilder::new([0xE3; 16]).restore_relation(R).build(),
+        ];
+
+        let mut graph = KvGraph::new(MemoryStore::new());
+        let mut state = GraphState::new();
+        for edit in &edits {
+            graph.apply_edit(edit).unwrap();
+            state.apply_edit(edit);
+            assert_matches(&graph, &state);
+        }
+
+        // The relation's reified entity and the rebound value ref
+        let relation = graph.relation(&R).unwrap().unwrap();
+        assert!(graph.entity(&relation.entity).unwrap().is_some());
+        assert!(!graph.is_value_ref(&V).unwrap());
+        assert!(graph.is_value_ref(&V2).unwrap());
+        assert!(graph.entity(&V2).unwrap().is_none());
+        assert_eq!(
+            graph.entity(&A).unwrap().unwrap().value(&T, None),
+            Some(&Value::Int64 { value: 3, unit: None })
+        );
+
+        // Adjacency follows the tombstone
+        assert_eq!(graph.outgoing(&A, None).unwrap(), vec![R]);
+        assert_eq!(graph.incoming(&B, Some(&T)).unwrap(), vec![R]);
+        assert!(graph.outgoing(&A, Some(&P)).unwrap().is_empty());
+        graph.apply_op(&Op::DeleteRelation(DeleteRelation { id: R })).unwrap();
+        assert!(graph.outgoing(&A, None).unwrap().is_empty());
+        assert!(graph.incoming(&B, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_kv_graph_corrupt_record() {
+        let mut store = MemoryStore::new();
+        let mut batch = WriteBatch::new();
+        batch.put(entity_key(&A), vec![2]);
+        store.write(batch).unwrap();
+        let graph = KvGraph::new(store);
+        assert!(matches!(
+            graph.entity(&A),
+            Err(KvError::Corrupt { source: DecodeError::InvalidBool { value: 2 }, .. })
+        ));
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_kv_graph_sled() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let mut graph = KvGraph::new(db.open_tree("grc20").unwrap());
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_entity(A, |e| e.text(P, "hello", Some(L)))
+            .create_relation_simple(R, A, B, T)
+            .build();
+        graph.apply_edit(&edit).unwrap();
+
+        let mut state = GraphState::new();
+        state.apply_edit(&edit);
+        assert_eq!(graph.entity(&A).unwrap().as_ref(), state.entity(&A));
+        assert_eq!(graph.relation(&R).unwrap().as_ref(), state.relation(&R));
+        assert_eq!(graph.outgoing(&A, Some(&T)).unwrap(), vec![R]);
+    }
+}
@@ -0,0 +1,6 @@
+//! Persistent storage for resolved graph state.
+//!
+//! - [`kv`]: a shared key layout for ordered key-value stores, with an
+//!   in-memory backend and a `sled` backend (`sled` feature)
+
+pub mod kv;
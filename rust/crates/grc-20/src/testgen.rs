@@ -0,0 +1,520 @@
+//! Synthetic edits for load testing and benchmarks.
+//!
+//! [`EditGenerator`] produces a reproducible stream of edits shaped like
+//! an import: entities with a weighted mix of property types, float32
+//! embeddings, and relations to entities from earlier in the stream. Every
+//! choice is drawn from a seeded generator, so the same
+//! [`GeneratorConfig`] always yields the same bytes, and
+//! [`GeneratorConfig::schema`] registers the generated properties for
+//! validation.
+//!
+//! [`sample_edit`] goes the other way: it thins an existing edit down to
+//! a reproducible fraction of its ops.
+//!
+//! ```
+//! use grc_20::testgen::{Distribution, EditGenerator, GeneratorConfig};
+//! use grc_20::{encode_edit, validate_edit, DataType};
+//!
+//! let config = GeneratorConfig::new(42)
+//!     .with_entities(100)
+//!     .with_property_mix(vec![(DataType::Text, 3), (DataType::Int64, 1)])
+//!     .with_text_length(Distribution::Geometric { mean: 40, max: 1_000 });
+//! let schema = config.schema();
+//! for edit in EditGenerator::new(config).take(3) {
+//!     validate_edit(&edit, &schema).unwrap();
+//!     encode_edit(&edit).unwrap();
+//! }
+//! ```
+
+use crate::model::id::derived_uuid;
+use crate::model::{DataType, DecimalMantissa, Edit, EditBuilder, EntityBuilder, Id, Op};
+use crate::validate::SchemaContext;
+
+/// A distribution of sizes or counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    /// Always the same value.
+    Fixed(usize),
+    /// Uniform between `min` and `max`, inclusive.
+    Uniform { min: usize, max: usize },
+    /// Geometric with the given mean, capped at `max`: mostly small
+    /// values with a long tail, like real-world text lengths.
+    Geometric { mean: usize, max: usize },
+}
+
+impl Distribution {
+    fn sample(self, rng: &mut Rng) -> usize {
+        match self {
+            Distribution::Fixed(n) => n,
+            Distribution::Uniform { min, max } => rng.range(min, max),
+            Distribution::Geometric { mean: 0, .. } => 0,
+            Distribution::Geometric { mean, max } => {
+                let p = 1.0 / (mean as f64 + 1.0);
+                let n = ((1.0 - rng.unit()).ln() / (1.0 - p).ln()) as usize;
+                n.min(max)
+            }
+        }
+    }
+}
+
+/// What [`EditGenerator`] generates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    /// Seed for every random choice and every generated ID.
+    pub seed: u64,
+    /// Entities created per edit.
+    pub entities: usize,
+    /// Data types of the generated properties, with relative weights.
+    pub property_mix: Vec<(DataType, u32)>,
+    /// Distinct properties of each data type in the mix.
+    pub properties_per_type: usize,
+    /// Values per entity, each for a distinct property.
+    pub values_per_entity: Distribution,
+    /// Characters per TEXT value, and bytes per BYTES value.
+    pub text_length: Distribution,
+    /// Dimensions of EMBEDDING values (float32).
+    pub embedding_dims: usize,
+    /// Outgoing relations per entity.
+    pub relation_fan_out: Distribution,
+    /// Distinct relation types.
+    pub relation_types: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl GeneratorConfig {
+    /// Creates a configuration with a typical import shape: 1,000 entities
+    /// per edit, mostly TEXT and numeric values, and a few relations each.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            entities: 1_000,
+            property_mix: vec![
+                (DataType::Text, 6),
+                (DataType::Int64, 2),
+                (DataType::Float64, 1),
+                (DataType::Decimal, 1),
+                (DataType::Bool, 1),
+                (DataType::Date, 1),
+                (DataType::Datetime, 1),
+                (DataType::Point, 1),
+            ],
+            properties_per_type: 2,
+            values_per_entity: Distribution::Uniform { min: 2, max: 6 },
+            text_length: Distribution::Geometric { mean: 24, max: 4_096 },
+            embedding_dims: 384,
+            relation_fan_out: Distribution::Uniform { min: 0, max: 3 },
+            relation_types: 4,
+        }
+    }
+
+    /// Sets the entities created per edit.
+    pub fn with_entities(mut self, entities: usize) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Sets the data types of the generated properties and their weights.
+    pub fn with_property_mix(mut self, mix: Vec<(DataType, u32)>) -> Self {
+        self.property_mix = mix;
+        self
+    }
+
+    /// Sets the distinct properties of each data type.
+    pub fn with_properties_per_type(mut self, properties: usize) -> Self {
+        self.properties_per_type = properties;
+        self
+    }
+
+    /// Sets the distribution of values per entity.
+    pub fn with_values_per_entity(mut self, values: Distribution) -> Self {
+        self.values_per_entity = values;
+        self
+    }
+
+    /// Sets the distribution of TEXT and BYTES value lengths.
+    pub fn with_text_length(mut self, length: Distribution) -> Self {
+        self.text_length = length;
+        self
+    }
+
+    /// Sets the dimensions of EMBEDDING values.
+    pub fn with_embedding_dims(mut self, dims: usize) -> Self {
+        self.embedding_dims = dims;
+        self
+    }
+
+    /// Sets the distribution of outgoing relations per entity.
+    pub fn with_relation_fan_out(mut self, fan_out: Distribution) -> Self {
+        self.relation_fan_out = fan_out;
+        self
+    }
+
+    /// Sets the distinct relation types.
+    pub fn with_relation_types(mut self, relation_types: usize) -> Self {
+        self.relation_types = relation_types;
+        self
+    }
+
+    /// Returns a schema with every generated property and its data type.
+    pub fn schema(&self) -> SchemaContext {
+        let mut schema = SchemaContext::new();
+        for property in self.properties() {
+            schema.add_property(property.id, property.data_type);
+        }
+        schema
+    }
+
+    fn properties(&self) -> Vec<GeneratedProperty> {
+        let mut properties = Vec::new();
+        for &(data_type, weight) in &self.property_mix {
+            for n in 0..self.properties_per_type {
+                properties.push(GeneratedProperty {
+                    id: self.id(&format!("property:{:?}:{}", data_type, n)),
+                    data_type,
+                    weight,
+                });
+            }
+        }
+        properties
+    }
+
+    fn id(&self, name: &str) -> Id {
+        derived_uuid(format!("testgen:{}:{}", self.seed, name).as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GeneratedProperty {
+    id: Id,
+    data_type: DataType,
+    weight: u32,
+}
+
+/// An endless, reproducible stream of synthetic edits.
+///
+/// Each edit creates [`GeneratorConfig::entities`] new entities. Relations
+/// point to entities created earlier in the same edit or in previous ones,
+/// so replaying the stream in order never references a missing entity.
+#[derive(Debug, Clone)]
+pub struct EditGenerator {
+    config: GeneratorConfig,
+    properties: Vec<GeneratedProperty>,
+    relation_types: Vec<Id>,
+    rng: Rng,
+    edits: u64,
+    entities: u64,
+}
+
+impl EditGenerator {
+    /// Creates a generator for the given configuration.
+    pub fn new(config: GeneratorConfig) -> Self {
+        let properties = config.properties();
+        let relation_types = (0..config.relation_types)
+            .map(|n| config.id(&format!("relation_type:{}", n)))
+            .collect();
+        let rng = Rng::new(config.seed);
+        Self { config, properties, relation_types, rng, edits: 0, entities: 0 }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> &GeneratorConfig {
+        &self.config
+    }
+
+    /// Returns the ID of the `n`th entity in the stream.
+    pub fn entity_id(&self, n: u64) -> Id {
+        self.config.id(&format!("entity:{}", n))
+    }
+
+    /// Generates the next edit.
+    pub fn next_edit(&mut self) -> Edit<'static> {
+        let edit_id = self.config.id(&format!("edit:{}", self.edits));
+        let mut builder = EditBuilder::new(edit_id)
+            .name(format!("Synthetic edit {}", self.edits))
+            .author(self.config.id("author"))
+            .created_at(1_704_067_200_000_000 + self.edits as i64 * 1_000_000);
+        self.edits += 1;
+
+        for _ in 0..self.config.entities {
+            let n = self.entities;
+            self.entities += 1;
+            let id = self.entity_id(n);
+            let values = self.pick_properties();
+            let rng = &mut self.rng;
+            let config = &self.config;
+            builder = builder.create_entity(id, |mut e| {
+                for property in values {
+                    e = generate_value(e, property, config, rng);
+                }
+                e
+            });
+
+            if self.relation_types.is_empty() || n == 0 {
+                continue;
+            }
+            for r in 0..self.config.relation_fan_out.sample(&mut self.rng) {
+                let to = self.rng.below(n);
+                let to = self.entity_id(to);
+                let relation_type =
+                    self.relation_types[self.rng.below(self.relation_types.len() as u64) as usize];
+                let relation = self.config.id(&format!("relation:{}:{}", n, r));
+                builder = builder.create_relation_simple(relation, id, to, relation_type);
+            }
+        }
+        builder.build()
+    }
+
+    /// Picks distinct properties for an entity, weighted by data type.
+    fn pick_properties(&mut self) -> Vec<GeneratedProperty> {
+        let count = self.config.values_per_entity.sample(&mut self.rng);
+        let mut remaining: Vec<_> =
+            self.properties.iter().copied().filter(|p| p.weight > 0).collect();
+        let mut picked = Vec::with_capacity(count.min(remaining.len()));
+        while picked.len() < count && !remaining.is_empty() {
+            let total: u64 = remaining.iter().map(|p| p.weight as u64).sum();
+            let mut target = self.rng.below(total);
+            let index = remaining
+                .iter()
+                .position(|p| {
+                    let hit = target < p.weight as u64;
+                    target = target.saturating_sub(p.weight as u64);
+                    hit
+                })
+                .expect("target is below the total weight");
+            picked.push(remaining.swap_remove(index));
+        }
+        picked
+    }
+}
+
+impl Iterator for EditGenerator {
+    type Item = Edit<'static>;
+
+    fn next(&mut self) -> Option<Edit<'static>> {
+        Some(self.next_edit())
+    }
+}
+
+/// Adds a random valid value for `property` to an entity.
+fn generate_value<'a>(
+    e: EntityBuilder<'a>,
+    property: GeneratedProperty,
+    config: &GeneratorConfig,
+    rng: &mut Rng,
+) -> EntityBuilder<'a> {
+    let id = property.id;
+    match property.data_type {
+        DataType::Bool => e.bool(id, rng.below(2) == 1),
+        DataType::Int64 => e.int64(id, rng.range(0, 10_000_000) as i64 - 5_000_000, None),
+        DataType::Float64 => e.float64(id, (rng.unit() - 0.5) * 1e6, None),
+        DataType::Decimal => {
+            // A last digit of 1-9 keeps the mantissa normalized
+            let mantissa = rng.range(0, 99_999) as i64 * 10 + rng.range(1, 9) as i64;
+            e.decimal(id, -2, DecimalMantissa::I64(mantissa), None)
+        }
+        DataType::Text => {
+            let len = config.text_length.sample(rng);
+            e.text(id, text(rng, len), None)
+        }
+        DataType::Bytes => {
+            let len = config.text_length.sample(rng);
+            e.bytes(id, (0..len).map(|_| rng.next_u64() as u8).collect::<Vec<_>>())
+        }
+        DataType::Date => e.date(id, date(rng)),
+        DataType::Time => e.time(id, format!("{}Z", time(rng))),
+        DataType::Datetime => e.datetime(id, format!("{}T{}Z", date(rng), time(rng))),
+        DataType::Schedule => e.schedule(
+            id,
+            format!("BEGIN:VEVENT\r\nDTSTART:{}T090000Z\r\nEND:VEVENT", date(rng).replace('-', "")),
+        ),
+        DataType::Point => e.point(id, rng.unit() * 360.0 - 180.0, rng.unit() * 180.0 - 90.0, None),
+        DataType::Embedding => {
+            let values: Vec<f32> =
+                (0..config.embedding_dims).map(|_| rng.unit() as f32 * 2.0 - 1.0).collect();
+            e.embedding_f32(id, &values)
+        }
+    }
+}
+
+const SYLLABLES: &[&str] = &[
+    "an", "bel", "cor", "da", "el", "fin", "gra", "hal", "is", "jor", "ka", "lum", "mer", "no",
+    "or", "pra", "qui", "ros", "sa", "tor", "ul", "ven", "wy", "zan",
+];
+
+/// Generates word-like ASCII text of exactly `len` characters.
+fn text(rng: &mut Rng, len: usize) -> String {
+    let mut text = String::with_capacity(len + 4);
+    let mut word = 0;
+    while text.len() < len {
+        if word > 1 && rng.below(3) == 0 {
+            text.push(' ');
+            word = 0;
+        } else {
+            text.push_str(SYLLABLES[rng.below(SYLLABLES.len() as u64) as usize]);
+            word += 1;
+        }
+    }
+    text.truncate(len);
+    text
+}
+
+fn date(rng: &mut Rng) -> String {
+    format!("{:04}-{:02}-{:02}", rng.range(1900, 2030), rng.range(1, 12), rng.range(1, 28))
+}
+
+fn time(rng: &mut Rng) -> String {
+    format!("{:02}:{:02}:{:02}", rng.range(0, 23), rng.range(0, 59), rng.range(0, 59))
+}
+
+/// Keeps a reproducible random `fraction` of an edit's ops, in order.
+///
+/// Ops are sampled independently, so a relation may be kept while the
+/// entities it connects are dropped; the result is meant for exercising
+/// decoders and indexers, not as a consistent subset of the graph.
+pub fn sample_edit<'a>(edit: &Edit<'a>, fraction: f64, seed: u64) -> Edit<'a> {
+    let mut rng = Rng::new(seed);
+    let mut sampled = edit.clone();
+    sampled.ops.retain(|_: &Op<'a>| rng.unit() < fraction);
+    sampled
+}
+
+/// SplitMix64: small, fast, and stable across platforms and releases, so
+/// a seed reproduces the same edits everywhere.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`; `n` must be nonzero.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Returns a value in `min..=max`, or `min` if the range is empty.
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        if max <= min {
+            return min;
+        }
+        min + self.below((max - min) as u64 + 1) as usize
+    }
+
+    /// Returns a value in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit, encode_edit};
+    use crate::validate::validate_edit;
+
+    #[test]
+    fn test_generated_edits_are_valid_and_reproducible() {
+        let all_types = vec![
+            (DataType::Bool, 1),
+            (DataType::Int64, 1),
+            (DataType::Float64, 1),
+            (DataType::Decimal, 1),
+            (DataType::Text, 1),
+            (DataType::Bytes, 1),
+            (DataType::Date, 1),
+            (DataType::Time, 1),
+            (DataType::Datetime, 1),
+            (DataType::Schedule, 1),
+            (DataType::Point, 1),
+            (DataType::Embedding, 1),
+        ];
+        let config = GeneratorConfig::new(7)
+            .with_entities(200)
+            .with_property_mix(all_types)
+            .with_embedding_dims(16)
+            .with_values_per_entity(Distribution::Fixed(8));
+        let schema = config.schema();
+
+        let edits: Vec<_> = EditGenerator::new(config.clone()).take(2).collect();
+        assert_ne!(edits[0].id, edits[1].id);
+        for edit in &edits {
+            validate_edit(edit, &schema).unwrap();
+            let bytes = encode_edit(edit).unwrap();
+            assert_eq!(&decode_edit(&bytes).unwrap(), edit);
+            for op in &edit.ops {
+                if let Op::CreateEntity(ce) = op {
+                    assert_eq!(ce.values.len(), 8);
+                }
+            }
+        }
+        assert_eq!(EditGenerator::new(config).next_edit(), edits[0]);
+        assert_ne!(EditGenerator::new(GeneratorConfig::new(8)).next_edit(), edits[0]);
+    }
+
+    #[test]
+    fn test_relations_point_backwards() {
+        let config = GeneratorConfig::new(1)
+            .with_entities(50)
+            .with_relation_fan_out(Distribution::Fixed(2))
+            .with_relation_types(1);
+        let mut generator = EditGenerator::new(config);
+        let first = generator.next_edit();
+        let second = generator.next_edit();
+        let known: Vec<Id> = (0..100).map(|n| generator.entity_id(n)).collect();
+
+        // Every entity but the very first has two relations
+        let relations =
+            |edit: &Edit| edit.ops.iter().filter(|op| matches!(op, Op::CreateRelation(_))).count();
+        assert_eq!(relations(&first), 98);
+        assert_eq!(relations(&second), 100);
+        for op in first.ops.iter().chain(&second.ops) {
+            if let Op::CreateRelation(r) = op {
+                let from = known.iter().position(|id| *id == r.from).unwrap();
+                let to = known.iter().position(|id| *id == r.to).unwrap();
+                assert!(to < from);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distributions() {
+        let mut rng = Rng::new(3);
+        let samples: Vec<_> = (0..10_000)
+            .map(|_| Distribution::Geometric { mean: 20, max: 100 }.sample(&mut rng))
+            .collect();
+        let mean = samples.iter().sum::<usize>() as f64 / samples.len() as f64;
+        assert!((18.0..22.0).contains(&mean), "mean {}", mean);
+        assert!(samples.iter().all(|&n| n <= 100));
+        assert!((0..1_000)
+            .map(|_| Distribution::Uniform { min: 3, max: 5 }.sample(&mut rng))
+            .all(|n| (3..=5).contains(&n)));
+        assert_eq!(text(&mut rng, 37).len(), 37);
+    }
+
+    #[test]
+    fn test_sample_edit() {
+        let edit = EditGenerator::new(GeneratorConfig::new(2).with_entities(500)).next_edit();
+        let sampled = sample_edit(&edit, 0.25, 9);
+        assert_eq!(sampled.id, edit.id);
+        let ratio = sampled.ops.len() as f64 / edit.ops.len() as f64;
+        assert!((0.2..0.3).contains(&ratio), "ratio {}", ratio);
+        assert!(sampled.ops.iter().all(|op| edit.ops.contains(op)));
+        assert_eq!(sample_edit(&edit, 0.25, 9), sampled);
+        assert_eq!(sample_edit(&edit, 1.0, 9), edit);
+        assert!(sample_edit(&edit, 0.0, 9).ops.is_empty());
+    }
+}
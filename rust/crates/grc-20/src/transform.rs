@@ -0,0 +1,423 @@
+//! Whole-edit rewrites.
+//!
+//! [`remap_ids`] moves an edit into another ID namespace, e.g. to import
+//! data authored against staging IDs into production. Every ID an op
+//! carries is looked up in an [`IdMap`]: entities, properties, relations
+//! and their types, value refs, languages, units, and space and version
+//! pins. IDs missing from the map are kept. The edit's own header (ID,
+//! authors, provenance) is left as is. Removed value IDs are hashes of a
+//! property and a value, not IDs in the namespace, so they are kept too and
+//! no longer match if their property is remapped.
+//!
+//! Relations without an explicit entity keep a derived one
+//! (`relation_entity_id(relation)`, spec Section 3.3). When such a relation
+//! is remapped, its derived entity follows the new relation ID, and other
+//! ops that reference the old derived entity are rewritten to match. Map
+//! the old derived entity explicitly to pin it elsewhere instead; the
+//! relation then gets an explicit entity.
+//!
+//! [`Edit::minimize`] drops ops that can't have an effect, which automated
+//! pipelines tend to emit in bulk.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::codec::encode_edit;
+use crate::error::EncodeError;
+use crate::hash::FxHashMap;
+use crate::model::visit::{walk_create_entity_mut, walk_update_entity_mut, OpVisitorMut};
+use crate::model::{
+    relation_entity_id, CreateEntity, CreateRelation, CreateValueRef, DeleteEntity,
+    DeleteRelation, Edit, Id, Op, PropertyValue, RestoreEntity, RestoreRelation, UnsetLanguage,
+    UnsetValue, UpdateEntity, UpdateRelation, Value,
+};
+use crate::validate::ids::visit_op_ids;
+
+/// A mapping from old IDs to new IDs.
+#[derive(Debug, Clone, Default)]
+pub struct IdMap {
+    ids: FxHashMap<Id, Id>,
+}
+
+impl IdMap {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `from` to `to`, returning the previous target of `from`.
+    pub fn insert(&mut self, from: Id, to: Id) -> Option<Id> {
+        self.ids.insert(from, to)
+    }
+
+    /// Gets the target of an ID, if mapped.
+    pub fn get(&self, from: &Id) -> Option<Id> {
+        self.ids.get(from).copied()
+    }
+
+    /// Returns the target of an ID, or the ID itself if unmapped.
+    pub fn map(&self, id: Id) -> Id {
+        self.get(&id).unwrap_or(id)
+    }
+
+    /// Returns the number of mapped IDs.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if no IDs are mapped.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+impl FromIterator<(Id, Id)> for IdMap {
+    fn from_iter<I: IntoIterator<Item = (Id, Id)>>(iter: I) -> Self {
+        Self { ids: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<(Id, Id)> for IdMap {
+    fn extend<I: IntoIterator<Item = (Id, Id)>>(&mut self, iter: I) {
+        self.ids.extend(iter);
+    }
+}
+
+/// Returns a copy of `edit` with its op IDs rewritten through `map`.
+///
+/// See the module docs for which IDs are rewritten and how derived relation
+/// entities are handled.
+pub fn remap_ids<'a>(edit: &Edit<'a>, map: &IdMap) -> Edit<'a> {
+    // Derived entities of remapped relations follow their relation, unless
+    // the caller mapped them explicitly
+    let mut effective = map.clone();
+    for op in &edit.ops {
+        if let Op::CreateRelation(CreateRelation { id, entity: None, .. }) = op {
+            let derived = relation_entity_id(id);
+            if let (Some(to), None) = (map.get(id), map.get(&derived)) {
+                effective.insert(derived, relation_entity_id(&to));
+            }
+        }
+    }
+
+    let mut edit = edit.clone();
+    edit.visit_mut(&mut Remapper { map: &effective });
+    edit
+}
+
+struct Remapper<'m> {
+    map: &'m IdMap,
+}
+
+impl Remapper<'_> {
+    fn id(&self, id: &mut Id) {
+        *id = self.map.map(*id);
+    }
+
+    fn opt(&self, id: &mut Option<Id>) {
+        if let Some(id) = id {
+            self.id(id);
+        }
+    }
+}
+
+impl OpVisitorMut for Remapper<'_> {
+    fn visit_create_entity_mut(&mut self, op: &mut CreateEntity<'_>) {
+        self.id(&mut op.id);
+        walk_create_entity_mut(self, op);
+    }
+
+    fn visit_update_entity_mut(&mut self, op: &mut UpdateEntity<'_>) {
+        self.id(&mut op.id);
+        walk_update_entity_mut(self, op);
+    }
+
+    fn visit_delete_entity_mut(&mut self, op: &mut DeleteEntity) {
+        self.id(&mut op.id);
+    }
+
+    fn visit_restore_entity_mut(&mut self, op: &mut RestoreEntity) {
+        self.id(&mut op.id);
+    }
+
+    fn visit_create_relation_mut(&mut self, op: &mut CreateRelation<'_>) {
+        if op.entity.is_none() {
+            let new_id = self.map.map(op.id);
+            let entity = self.map.map(relation_entity_id(&op.id));
+            if entity != relation_entity_id(&new_id) {
+                op.entity = Some(entity);
+            }
+        } else {
+            self.opt(&mut op.entity);
+        }
+        self.id(&mut op.id);
+        self.id(&mut op.relation_type);
+        self.id(&mut op.from);
+        self.id(&mut op.to);
+        self.opt(&mut op.from_space);
+        self.opt(&mut op.from_version);
+        self.opt(&mut op.to_space);
+        self.opt(&mut op.to_version);
+    }
+
+    fn visit_update_relation_mut(&mut self, op: &mut UpdateRelation<'_>) {
+        self.id(&mut op.id);
+        self.opt(&mut op.from_space);
+        self.opt(&mut op.from_version);
+        self.opt(&mut op.to_space);
+        self.opt(&mut op.to_version);
+    }
+
+    fn visit_delete_relation_mut(&mut self, op: &mut DeleteRelation) {
+        self.id(&mut op.id);
+    }
+
+    fn visit_restore_relation_mut(&mut self, op: &mut RestoreRelation) {
+        self.id(&mut op.id);
+    }
+
+    fn visit_create_value_ref_mut(&mut self, op: &mut CreateValueRef) {
+        self.id(&mut op.id);
+        self.id(&mut op.entity);
+        self.id(&mut op.property);
+        self.opt(&mut op.language);
+        self.opt(&mut op.space);
+    }
+
+    fn visit_property_value_mut(&mut self, pv: &mut PropertyValue<'_>) {
+        self.id(&mut pv.property);
+        match &mut pv.value {
+            Value::Int64 { unit, .. }
+            | Value::Float64 { unit, .. }
+            | Value::Decimal { unit, .. } => self.opt(unit),
+            Value::Text { language, .. } => self.opt(language),
+            _ => {}
+        }
+    }
+
+    fn visit_unset_value_mut(&mut self, unset: &mut UnsetValue) {
+        self.id(&mut unset.property);
+        if let UnsetLanguage::Specific(language) = &mut unset.language {
+            self.id(language);
+        }
+    }
+}
+
+/// What [`Edit::minimize`] removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MinimizeReport {
+    /// Ops removed from the edit.
+    pub ops_removed: usize,
+    /// Unsets removed from UpdateEntity ops.
+    pub unsets_removed: usize,
+    /// Uncompressed encoded size before, minus after.
+    pub bytes_saved: usize,
+}
+
+impl Edit<'_> {
+    /// Removes ops and unsets that have no effect:
+    ///
+    /// - unsets of a value slot that an earlier op in the edit already
+    ///   unset, with no set of the property in between;
+    /// - UpdateEntity and UpdateRelation ops left with nothing to do;
+    /// - relations created and then deleted, with any updates in between,
+    ///   when no other op references the relation or its entity.
+    ///
+    /// Unsets of values the edit never set are kept, since they remove
+    /// values set by earlier edits. Dropping a created-and-deleted relation
+    /// assumes it did not exist before the edit; its reified entity, which
+    /// would have been left behind empty, is not created either.
+    ///
+    /// Fails, leaving the edit unchanged, if it can't be encoded.
+    pub fn minimize(&mut self) -> Result<MinimizeReport, EncodeError> {
+        let before = encode_edit(self)?.len();
+        let keep = transient_relations(&self.ops);
+        let unsets_removed = drop_repeated_unsets(&mut self.ops);
+
+        let len = self.ops.len();
+        let mut keep = keep.into_iter();
+        self.ops.retain(|op| {
+            let keep = keep.next().unwrap_or(true);
+            keep && !is_empty_update(op)
+        });
+        let after = encode_edit(self)?.len();
+        Ok(MinimizeReport {
+            ops_removed: len - self.ops.len(),
+            unsets_removed,
+            bytes_saved: before.saturating_sub(after),
+        })
+    }
+}
+
+/// Marks the ops of relations that are created, optionally updated, and
+/// then deleted, and that nothing else references, as not kept.
+fn transient_relations(ops: &[Op<'_>]) -> Vec<bool> {
+    let mut mentions: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut lifecycle: FxHashMap<Id, Vec<usize>> = FxHashMap::default();
+    for (i, op) in ops.iter().enumerate() {
+        visit_op_ids(op, &mut |_, id| *mentions.entry(*id).or_default() += 1);
+        let relation = match op {
+            Op::CreateRelation(cr) => cr.id,
+            Op::UpdateRelation(ur) => ur.id,
+            Op::DeleteRelation(dr) => dr.id,
+            Op::RestoreRelation(rr) => rr.id,
+            _ => continue,
+        };
+        lifecycle.entry(relation).or_default().push(i);
+    }
+
+    let mut keep = vec![true; ops.len()];
+    for op in ops {
+        let Op::CreateRelation(cr) = op else { continue };
+        let positions = &lifecycle[&cr.id];
+        let (&first, rest) = positions.split_first().expect("the create is recorded");
+        let Some((&last, updates)) = rest.split_last() else { continue };
+        let transient = matches!(ops[first], Op::CreateRelation(_))
+            && matches!(ops[last], Op::DeleteRelation(_))
+            && updates.iter().all(|&i| matches!(ops[i], Op::UpdateRelation(_)))
+            && mentions[&cr.id] == positions.len();
+        // An explicit entity is mentioned by the create itself
+        let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+        let entity_mentions = mentions.get(&entity).copied().unwrap_or(0);
+        if transient && entity_mentions == usize::from(cr.entity.is_some()) {
+            for &i in positions {
+                keep[i] = false;
+            }
+        }
+    }
+    keep
+}
+
+/// Removes unsets of value slots already cleared earlier in the edit and
+/// not set since. Returns how many were removed.
+fn drop_repeated_unsets(ops: &mut [Op<'_>]) -> usize {
+    // Per entity: (property, language) unsets since the property was last set
+    let mut cleared: FxHashMap<Id, Vec<(Id, UnsetLanguage)>> = FxHashMap::default();
+    let mut removed = 0;
+    for op in ops {
+        match op {
+            Op::CreateEntity(ce) => forget_sets(&mut cleared, ce.id, &ce.values),
+            Op::UpdateEntity(ue) => {
+                let slots = cleared.entry(ue.id).or_default();
+                let len = ue.unset_values.len();
+                ue.unset_values.retain(|unset| {
+                    let repeated = slots.iter().any(|&(property, language)| {
+                        property == unset.property
+                            && (language == UnsetLanguage::All || language == unset.language)
+                    });
+                    if !repeated {
+                        slots.push((unset.property, unset.language));
+                    }
+                    !repeated
+                });
+                removed += len - ue.unset_values.len();
+                forget_sets(&mut cleared, ue.id, &ue.set_properties);
+            }
+            // Unsets of a deleted entity are ignored, so a restore makes
+            // them effective again
+            Op::DeleteEntity(DeleteEntity { id }) | Op::RestoreEntity(RestoreEntity { id }) => {
+                cleared.remove(id);
+            }
+            _ => {}
+        }
+    }
+    removed
+}
+
+fn forget_sets(
+    cleared: &mut FxHashMap<Id, Vec<(Id, UnsetLanguage)>>,
+    entity: Id,
+    values: &[PropertyValue<'_>],
+) {
+    if let Some(slots) = cleared.get_mut(&entity) {
+        slots.retain(|(property, _)| values.iter().all(|pv| pv.property != *property));
+    }
+}
+
+fn is_empty_update(op: &Op<'_>) -> bool {
+    match op {
+        Op::UpdateEntity(ue) => ue.is_empty(),
+        Op::UpdateRelation(ur) => ur.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_remap_ids() {
+        let (entity, other, property, rel, rel_type, lang) =
+            ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16], [5u8; 16], [6u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity(entity, |e| e.text(property, "hi", Some(lang)))
+            .create_relation_simple(rel, entity, other, rel_type)
+            .update_entity(relation_entity_id(&rel), |u| u.set_int64(property, 1, None))
+            .build();
+
+        let map: IdMap = [
+            (entity, [11u8; 16]),
+            (property, [13u8; 16]),
+            (rel, [14u8; 16]),
+            (lang, [16u8; 16]),
+        ]
+        .into_iter()
+        .collect();
+        let remapped = remap_ids(&edit, &map);
+
+        let Op::CreateEntity(create) = &remapped.ops[0] else { unreachable!() };
+        assert_eq!((create.id, create.values[0].property), ([11u8; 16], [13u8; 16]));
+        assert_eq!(
+            create.values[0].value,
+            Value::Text { value: "hi".into(), language: Some([16u8; 16]) }
+        );
+        let Op::CreateRelation(relation) = &remapped.ops[1] else { unreachable!() };
+        assert_eq!((relation.id, relation.from, relation.to), ([14u8; 16], [11u8; 16], other));
+        assert_eq!(relation.entity, None);
+        // The update on the old derived entity follows it
+        let Op::UpdateEntity(update) = &remapped.ops[2] else { unreachable!() };
+        assert_eq!(update.id, relation_entity_id(&[14u8; 16]));
+
+        // Pinning the derived entity elsewhere makes it explicit
+        let mut pinned = map.clone();
+        pinned.insert(relation_entity_id(&rel), [20u8; 16]);
+        let remapped = remap_ids(&edit, &pinned);
+        let Op::CreateRelation(relation) = &remapped.ops[1] else { unreachable!() };
+        assert_eq!(relation.entity, Some([20u8; 16]));
+        let Op::UpdateEntity(update) = &remapped.ops[2] else { unreachable!() };
+        assert_eq!(update.id, [20u8; 16]);
+    }
+
+    #[test]
+    fn test_minimize() {
+        let (alice, bob, name, rel, rel_type) =
+            ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16], [5u8; 16]);
+        let mut edit = EditBuilder::new([0u8; 16])
+            .update_entity(alice, |u| u.unset_all(name))
+            .update_entity(alice, |u| u.unset_all(name))
+            .update_entity(bob, |u| u)
+            .create_relation_simple(rel, alice, bob, rel_type)
+            .delete_relation(rel)
+            .update_entity(alice, |u| u.set_text(name, "Alice", None))
+            .update_entity(alice, |u| u.unset_all(name))
+            .build();
+
+        let report = edit.minimize().unwrap();
+        assert_eq!((report.ops_removed, report.unsets_removed), (4, 1));
+        assert!(report.bytes_saved > 0);
+        // The first unset may clear an earlier edit's value, and the last
+        // one clears the set before it
+        assert_eq!(edit.ops.len(), 3);
+        assert!(matches!(&edit.ops[2], Op::UpdateEntity(u) if u.unset_values.len() == 1));
+
+        // A relation referenced elsewhere is kept
+        let mut edit = EditBuilder::new([0u8; 16])
+            .create_relation_simple(rel, alice, bob, rel_type)
+            .update_entity(relation_entity_id(&rel), |u| u.set_text(name, "x", None))
+            .delete_relation(rel)
+            .build();
+        assert_eq!(edit.minimize().unwrap().ops_removed, 0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Traversal of the relation graph in resolved state.
+//!
+//! [`GraphState::neighbors`], [`GraphState::bfs`], [`GraphState::dfs`], and
+//! [`GraphState::shortest_path`] follow relations from their source to
+//! their target. Only live relations between entities are followed:
+//! tombstoned relations, relations to or from value refs, and relations to
+//! tombstoned entities are skipped. Targets that are not in the state
+//! (e.g. entities in another space) are reached but have no neighbors.
+//!
+//! The state keeps no adjacency index, so each call scans the relations
+//! once. Neighbors are visited in ID order, which makes every result
+//! deterministic.
+
+use std::collections::VecDeque;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::model::Id;
+use crate::state::{GraphState, RelationState};
+
+impl GraphState {
+    /// Returns the targets of the live relations from `id`, optionally of
+    /// one relation type, in ID order.
+    pub fn neighbors(&self, id: &Id, relation_type: Option<&Id>) -> Vec<Id> {
+        let mut targets: Vec<Id> = self
+            .relations()
+            .filter(|(_, r)| r.from == *id && relation_type.is_none_or(|t| r.relation_type == *t))
+            .filter(|(_, r)| self.is_followed(r))
+            .map(|(_, r)| r.to)
+            .collect();
+        targets.sort_unstable();
+        targets.dedup();
+        targets
+    }
+
+    /// Visits the entities reachable from `start` breadth-first, following
+    /// the relations `follow` accepts, up to `max_depth` hops.
+    ///
+    /// Returns each entity once with its depth, starting with `start` at
+    /// depth 0, in visiting order.
+    pub fn bfs(
+        &self,
+        start: Id,
+        max_depth: usize,
+        follow: impl FnMut(&RelationState) -> bool,
+    ) -> Vec<(Id, usize)> {
+        let adjacency = self.adjacency(follow);
+        let mut visited = FxHashSet::from_iter([start]);
+        let mut order = vec![(start, 0)];
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((id, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for next in adjacency.get(&id).into_iter().flatten() {
+                if visited.insert(*next) {
+                    order.push((*next, depth + 1));
+                    queue.push_back((*next, depth + 1));
+                }
+            }
+        }
+        order
+    }
+
+    /// Visits the entities reachable from `start` depth-first (preorder),
+    /// following the relations `follow` accepts, up to `max_depth` hops.
+    ///
+    /// Returns each entity once with the depth it was first reached at,
+    /// starting with `start` at depth 0, in visiting order. As in any
+    /// depth-limited search, an entity first reached along a long path is
+    /// not revisited along a shorter one; use [`bfs`](Self::bfs) for
+    /// everything within `max_depth` hops.
+    pub fn dfs(
+        &self,
+        start: Id,
+        max_depth: usize,
+        follow: impl FnMut(&RelationState) -> bool,
+    ) -> Vec<(Id, usize)> {
+        let adjacency = self.adjacency(follow);
+        let mut visited = FxHashSet::default();
+        let mut order = Vec::new();
+        let mut stack = vec![(start, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            order.push((id, depth));
+            if depth == max_depth {
+                continue;
+            }
+            // Reversed, so that the smallest neighbor is visited first
+            for next in adjacency.get(&id).into_iter().flatten().rev() {
+                if !visited.contains(next) {
+                    stack.push((*next, depth + 1));
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns a shortest path of entities from `from` to `to`, both
+    /// included, over relations of the given types (any type if empty).
+    ///
+    /// Among paths of the same length, the one through the smallest IDs
+    /// wins. Returns None if `to` is not reachable.
+    pub fn shortest_path(&self, from: Id, to: Id, relation_types: &[Id]) -> Option<Vec<Id>> {
+        let adjacency = self.adjacency(|r| {
+            relation_types.is_empty() || relation_types.contains(&r.relation_type)
+        });
+        let mut parents: FxHashMap<Id, Id> = FxHashMap::default();
+        let mut queue = VecDeque::from([from]);
+        let mut found = from == to;
+        while let Some(id) = queue.pop_front().filter(|_| !found) {
+            for next in adjacency.get(&id).into_iter().flatten() {
+                if *next != from && !parents.contains_key(next) {
+                    parents.insert(*next, id);
+                    queue.push_back(*next);
+                    found |= *next == to;
+                }
+            }
+        }
+        if !found {
+            return None;
+        }
+        let mut path = vec![to];
+        while let Some(parent) = path.last().and_then(|id| parents.get(id)) {
+            path.push(*parent);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns true if traversals follow `relation`, before any filter.
+    fn is_followed(&self, relation: &RelationState) -> bool {
+        !relation.deleted
+            && !relation.from_is_value_ref
+            && !relation.to_is_value_ref
+            && !self.entity(&relation.to).is_some_and(|e| e.deleted)
+    }
+
+    /// Builds the sorted outgoing neighbor lists of the followed relations.
+    fn adjacency(&self, mut follow: impl FnMut(&RelationState) -> bool) -> FxHashMap<Id, Vec<Id>> {
+        let mut adjacency: FxHashMap<Id, Vec<Id>> = FxHashMap::default();
+        for (_, relation) in self.relations() {
+            if self.is_followed(relation) && follow(relation) {
+                adjacency.entry(relation.from).or_default().push(relation.to);
+            }
+        }
+        for targets in adjacency.values_mut() {
+            targets.sort_unstable();
+            targets.dedup();
+        }
+        adjacency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const A: Id = [1u8; 16];
+    const B: Id = [2u8; 16];
+    const C: Id = [3u8; 16];
+    const D: Id = [4u8; 16];
+    const E: Id = [5u8; 16];
+    const KNOWS: Id = [0xA1; 16];
+    const LIKES: Id = [0xA2; 16];
+
+    fn relation_id(n: u8) -> Id {
+        [0x10 + n; 16]
+    }
+
+    // A -knows-> B -knows-> C -knows-> D, A -likes-> C, B -knows-> E (deleted)
+    fn state() -> GraphState {
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_empty_entity(A)
+            .create_empty_entity(B)
+            .create_empty_entity(C)
+            .create_empty_entity(D)
+            .create_empty_entity(E)
+            .create_relation_simple(relation_id(1), A, B, KNOWS)
+            .create_relation_simple(relation_id(2), B, C, KNOWS)
+            .create_relation_simple(relation_id(3), C, D, KNOWS)
+            .create_relation_simple(relation_id(4), A, C, LIKES)
+            .create_relation_simple(relation_id(5), B, E, KNOWS)
+            .delete_entity(E)
+            .build();
+        let mut state = GraphState::new();
+        state.apply_edit(&edit);
+        state
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let state = state();
+        assert_eq!(state.neighbors(&A, None), vec![B, C]);
+        assert_eq!(state.neighbors(&A, Some(&LIKES)), vec![C]);
+        assert_eq!(state.neighbors(&B, None), vec![C]);
+        assert!(state.neighbors(&D, None).is_empty());
+    }
+
+    #[test]
+    fn test_bfs_and_dfs() {
+        let state = state();
+        assert_eq!(state.bfs(A, 10, |_| true), vec![(A, 0), (B, 1), (C, 1), (D, 2)]);
+        assert_eq!(state.bfs(A, 1, |_| true), vec![(A, 0), (B, 1), (C, 1)]);
+        assert_eq!(
+            state.bfs(A, 10, |r| r.relation_type == KNOWS),
+            vec![(A, 0), (B, 1), (C, 2), (D, 3)]
+        );
+        assert_eq!(state.dfs(A, 10, |_| true), vec![(A, 0), (B, 1), (C, 2), (D, 3)]);
+        assert_eq!(state.dfs(A, 0, |_| true), vec![(A, 0)]);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let state = state();
+        assert_eq!(state.shortest_path(A, D, &[]), Some(vec![A, C, D]));
+        assert_eq!(state.shortest_path(A, D, &[KNOWS]), Some(vec![A, B, C, D]));
+        assert_eq!(state.shortest_path(A, A, &[]), Some(vec![A]));
+        assert_eq!(state.shortest_path(D, A, &[]), None);
+        assert_eq!(state.shortest_path(A, E, &[]), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! UUID format checks for the IDs referenced by an edit's ops.
+//!
+//! The wire format accepts any 16 bytes as an ID, but the spec requires
+//! RFC 4122 UUIDs (Section 2.1). These checks verify the variant bits
+//! (`10xx`) and version nibble (1–8), and reject NIL, which never names a
+//! real object: entities, relations, endpoints, and dictionary entries all
+//! need one.
+//!
+//! Header IDs (edit ID, authors) are not checked; only IDs inside ops.
+//!
+//! Which way a problem is surfaced is set by an [`IdPolicy`]: ignored,
+//! reported as warnings while producers migrate, or rejected.
+
+use alloc::vec::Vec;
+
+use crate::error::ValidationError;
+use crate::model::{
+    AddMember, Edit, Id, Op, PropertyValue, RemoveMember, UnsetLanguage, Value, NIL_ID,
+};
+use crate::validate::{Severity, ValidationIssue};
+
+/// How malformed IDs are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdPolicy {
+    /// Accept any 16 bytes (the wire format's own rule).
+    #[default]
+    Permissive,
+    /// Report malformed IDs as warnings.
+    Warn,
+    /// Reject malformed IDs.
+    Strict,
+}
+
+/// Returns why `id` is not a valid UUID, or `None` if it is.
+pub fn id_problem(id: &Id) -> Option<&'static str> {
+    if *id == NIL_ID {
+        return Some("NIL is not allowed");
+    }
+    if id[8] & 0xC0 != 0x80 {
+        return Some("variant bits are not RFC 4122");
+    }
+    match id[6] >> 4 {
+        1..=8 => None,
+        _ => Some("unknown UUID version"),
+    }
+}
+
+/// Checks every ID in the edit's ops under `policy`.
+///
+/// Returns one issue per malformed ID, in op order: warnings under
+/// [`IdPolicy::Warn`], errors under [`IdPolicy::Strict`], and nothing
+/// under [`IdPolicy::Permissive`].
+pub fn validate_ids(edit: &Edit, policy: IdPolicy) -> Vec<ValidationIssue> {
+    let severity = match policy {
+        IdPolicy::Permissive => return Vec::new(),
+        IdPolicy::Warn => Severity::Warning,
+        IdPolicy::Strict => Severity::Error,
+    };
+    let mut issues = Vec::new();
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        visit_op_ids(op, &mut |field, id| {
+            if let Some(reason) = id_problem(id) {
+                issues.push(ValidationIssue {
+                    op_index,
+                    severity,
+                    error: ValidationError::InvalidId { field, id: *id, reason },
+                });
+            }
+        });
+    }
+    issues
+}
+
+/// Returns the first malformed ID in the edit's ops, as
+/// `(field, id, reason)`.
+pub(crate) fn first_invalid_id(edit: &Edit) -> Option<(&'static str, Id, &'static str)> {
+    let mut found = None;
+    for op in &edit.ops {
+        visit_op_ids(op, &mut |field, id| {
+            if found.is_none() {
+                found = id_problem(id).map(|reason| (field, *id, reason));
+            }
+        });
+        if found.is_some() {
+            break;
+        }
+    }
+    found
+}
+
+/// Calls `f` with every ID an op references, labeled by its role.
+pub(crate) fn visit_op_ids(op: &Op, f: &mut impl FnMut(&'static str, &Id)) {
+    match op {
+        Op::CreateEntity(ce) => {
+            f("entity", &ce.id);
+            visit_values(&ce.values, f);
+        }
+        Op::UpdateEntity(ue) => {
+            f("entity", &ue.id);
+            visit_values(&ue.set_properties, f);
+            for unset in &ue.unset_values {
+                f("property", &unset.property);
+                if let UnsetLanguage::Specific(language) = &unset.language {
+                    f("language", language);
+                }
+            }
+        }
+        Op::DeleteEntity(de) => f("entity", &de.id),
+        Op::RestoreEntity(re) => f("entity", &re.id),
+        Op::CreateRelation(cr) => {
+            f("relation", &cr.id);
+            f("relation_type", &cr.relation_type);
+            f("from", &cr.from);
+            f("to", &cr.to);
+            optional("from_space", &cr.from_space, f);
+            optional("from_version", &cr.from_version, f);
+            optional("to_space", &cr.to_space, f);
+            optional("to_version", &cr.to_version, f);
+            optional("relation_entity", &cr.entity, f);
+        }
+        Op::UpdateRelation(ur) => {
+            f("relation", &ur.id);
+            optional("from_space", &ur.from_space, f);
+            optional("from_version", &ur.from_version, f);
+            optional("to_space", &ur.to_space, f);
+            optional("to_version", &ur.to_version, f);
+        }
+        Op::DeleteRelation(dr) => f("relation", &dr.id),
+        Op::RestoreRelation(rr) => f("relation", &rr.id),
+        Op::CreateValueRef(vr) => {
+            f("value_ref", &vr.id);
+            f("entity", &vr.entity);
+            f("property", &vr.property);
+            optional("language", &vr.language, f);
+            optional("space", &vr.space, f);
+        }
+        Op::CreateSpace(cs) => f("space", &cs.id),
+        Op::AddMember(AddMember { space, member, .. })
+        | Op::RemoveMember(RemoveMember { space, member, .. }) => {
+            f("space", space);
+            f("member", member);
+        }
+        Op::UpdateSpaceMetadata(usm) => f("space", &usm.space),
+        Op::Unknown(_) => {}
+    }
+}
+
+fn optional(field: &'static str, id: &Option<Id>, f: &mut impl FnMut(&'static str, &Id)) {
+    if let Some(id) = id {
+        f(field, id);
+    }
+}
+
+fn visit_values(values: &[PropertyValue], f: &mut impl FnMut(&'static str, &Id)) {
+    for pv in values {
+        f("property", &pv.property);
+        match &pv.value {
+            Value::Text { language, .. } => optional("language", language, f),
+            Value::Int64 { unit, .. }
+            | Value::Float64 { unit, .. }
+            | Value::Decimal { unit, .. } => optional("unit", unit, f),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{derived_uuid, EditBuilder};
+
+    #[test]
+    fn test_id_policies() {
+        let entity = derived_uuid(b"entity");
+        let name = derived_uuid(b"name");
+        let knows = derived_uuid(b"knows");
+        let edit = EditBuilder::new(NIL_ID)
+            .create_entity(entity, |e| e.text(name, "Alice", None))
+            .create_relation_simple(derived_uuid(b"rel"), entity, NIL_ID, knows)
+            .create_entity([0x11; 16], |e| e)
+            .build();
+
+        assert!(validate_ids(&edit, IdPolicy::Permissive).is_empty());
+
+        let issues = validate_ids(&edit, IdPolicy::Warn);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].op_index, 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(
+            issues[0].error,
+            ValidationError::InvalidId { field: "to", id: NIL_ID, reason: "NIL is not allowed" }
+        );
+        assert_eq!(issues[1].op_index, 2);
+
+        let issues = validate_ids(&edit, IdPolicy::Strict);
+        assert!(issues.iter().all(|i| i.severity == Severity::Error));
+        assert_eq!(first_invalid_id(&edit).map(|(field, ..)| field), Some("to"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Tombstone checks for an edit against resolved state.
+//!
+//! Deleted objects absorb later writes (spec Section 8.2): creating,
+//! updating, or re-creating a tombstoned entity or relation is silently
+//! ignored, and only an explicit RestoreEntity or RestoreRelation brings it
+//! back. Indexers agree on this, but a client that expected its write to
+//! resurrect the object loses it without notice. [`validate_lifecycle`]
+//! replays an edit's deletes and restores over a [`GraphState`] and reports
+//! the ops that would be absorbed, so they can be fixed before publishing.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::ValidationError;
+use crate::model::{relation_entity_id, Edit, Id, Op};
+use crate::state::GraphState;
+use crate::validate::{Severity, ValidationIssue};
+
+/// Checks the edit's ops against the lifecycle of the objects they target.
+///
+/// Reported, in op order:
+/// - Errors for a CreateEntity or CreateRelation of an object deleted
+///   earlier in the same edit, which the spec forbids (Section 3.6).
+/// - Warnings for writes that a tombstone absorbs: CreateEntity and
+///   UpdateEntity of a deleted entity, CreateRelation and UpdateRelation of
+///   a deleted relation, and CreateRelation whose reified entity is
+///   deleted (the relation is created, its entity stays deleted).
+/// - Warnings for restores of objects that are not deleted.
+pub fn validate_lifecycle(edit: &Edit, state: &GraphState) -> Vec<ValidationIssue> {
+    let mut lifecycle = Lifecycle { state, entities: HashMap::new(), relations: HashMap::new() };
+    let mut deleted_in_edit: HashSet<Id> = HashSet::new();
+    let mut issues = Vec::new();
+    let mut report = |op_index, severity, error| {
+        issues.push(ValidationIssue { op_index, severity, error });
+    };
+
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        match op {
+            Op::CreateEntity(ce) => {
+                if deleted_in_edit.contains(&ce.id) {
+                    report(
+                        op_index,
+                        Severity::Error,
+                        ValidationError::CreateAfterDelete { id: ce.id },
+                    );
+                } else if lifecycle.entity(&ce.id) == Some(true) {
+                    report(
+                        op_index,
+                        Severity::Warning,
+                        ValidationError::EntityIsDead { entity: ce.id },
+                    );
+                }
+                lifecycle.create_entity(ce.id);
+            }
+            Op::UpdateEntity(ue) if lifecycle.entity(&ue.id) == Some(true) => {
+                report(
+                    op_index,
+                    Severity::Warning,
+                    ValidationError::EntityIsDead { entity: ue.id },
+                );
+            }
+            Op::DeleteEntity(de) => {
+                deleted_in_edit.insert(de.id);
+                lifecycle.set_entity(de.id, true);
+            }
+            Op::RestoreEntity(re) => {
+                if lifecycle.entity(&re.id) != Some(true) {
+                    report(op_index, Severity::Warning, ValidationError::NotDeleted { id: re.id });
+                }
+                lifecycle.set_entity(re.id, false);
+            }
+            Op::CreateRelation(cr) => {
+                if deleted_in_edit.contains(&cr.id) {
+                    report(
+                        op_index,
+                        Severity::Error,
+                        ValidationError::CreateAfterDelete { id: cr.id },
+                    );
+                    continue;
+                }
+                match lifecycle.relation(&cr.id) {
+                    Some(true) => {
+                        report(
+                            op_index,
+                            Severity::Warning,
+                            ValidationError::RelationIsDead { relation: cr.id },
+                        );
+                        continue;
+                    }
+                    Some(false) => continue,
+                    None => {}
+                }
+                let entity = cr.entity.unwrap_or_else(|| relation_entity_id(&cr.id));
+                if lifecycle.entity(&entity) == Some(true) {
+                    report(op_index, Severity::Warning, ValidationError::EntityIsDead { entity });
+                }
+                lifecycle.relations.insert(cr.id, false);
+                lifecycle.create_entity(entity);
+            }
+            Op::UpdateRelation(ur) if lifecycle.relation(&ur.id) == Some(true) => {
+                report(
+                    op_index,
+                    Severity::Warning,
+                    ValidationError::RelationIsDead { relation: ur.id },
+                );
+            }
+            Op::DeleteRelation(dr) => {
+                deleted_in_edit.insert(dr.id);
+                lifecycle.set_relation(dr.id, true);
+            }
+            Op::RestoreRelation(rr) => {
+                if lifecycle.relation(&rr.id) != Some(true) {
+                    report(op_index, Severity::Warning, ValidationError::NotDeleted { id: rr.id });
+                }
+                lifecycle.set_relation(rr.id, false);
+            }
+            _ => {}
+        }
+    }
+    issues
+}
+
+/// Tombstones as of the current op: the state's, overridden by the edit's
+/// earlier ops.
+struct Lifecycle<'s> {
+    state: &'s GraphState,
+    entities: HashMap<Id, bool>,
+    relations: HashMap<Id, bool>,
+}
+
+impl Lifecycle<'_> {
+    /// Returns whether the entity is deleted, or None if it doesn't exist.
+    fn entity(&self, id: &Id) -> Option<bool> {
+        self.entities.get(id).copied().or_else(|| self.state.entity(id).map(|e| e.deleted))
+    }
+
+    /// Returns whether the relation is deleted, or None if it doesn't exist.
+    fn relation(&self, id: &Id) -> Option<bool> {
+        self.relations.get(id).copied().or_else(|| self.state.relation(id).map(|r| r.deleted))
+    }
+
+    /// Creates an entity if it doesn't exist; tombstones absorb the create.
+    fn create_entity(&mut self, id: Id) {
+        if self.entity(&id).is_none() {
+            self.entities.insert(id, false);
+        }
+    }
+
+    /// Deletes or restores an entity; missing entities are left missing.
+    fn set_entity(&mut self, id: Id, deleted: bool) {
+        if self.entity(&id).is_some() {
+            self.entities.insert(id, deleted);
+        }
+    }
+
+    /// Deletes or restores a relation; missing relations are left missing.
+    fn set_relation(&mut self, id: Id, deleted: bool) {
+        if self.relation(&id).is_some() {
+            self.relations.insert(id, deleted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    const ALICE: Id = [1u8; 16];
+    const BOB: Id = [2u8; 16];
+    const NAME: Id = [3u8; 16];
+    const KNOWS: Id = [4u8; 16];
+    const REL: Id = [5u8; 16];
+
+    fn state() -> GraphState {
+        let edit = EditBuilder::new([0xE0; 16])
+            .create_entity(ALICE, |e| e.text(NAME, "Alice", None))
+            .create_entity(BOB, |e| e.text(NAME, "Bob", None))
+            .create_relation_simple(REL, ALICE, BOB, KNOWS)
+            .delete_entity(BOB)
+            .delete_relation(REL)
+            .build();
+        let mut state = GraphState::new();
+        state.apply_edit(&edit);
+        state
+    }
+
+    fn issues(edit: EditBuilder<'_>) -> Vec<(usize, Severity, ValidationError)> {
+        validate_lifecycle(&edit.build(), &state())
+            .into_iter()
+            .map(|issue| (issue.op_index, issue.severity, issue.error))
+            .collect()
+    }
+
+    #[test]
+    fn test_writes_to_tombstones() {
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_entity(BOB, |e| e.text(NAME, "Bob again", None))
+            .update_entity(BOB, |u| u.set_text(NAME, "Bob", None))
+            .update_entity(ALICE, |u| u.set_text(NAME, "Alice B.", None))
+            .update_relation_position(REL, Some("a".into()))
+            .create_relation_simple(REL, ALICE, BOB, KNOWS);
+        assert_eq!(
+            issues(edit),
+            vec![
+                (0, Severity::Warning, ValidationError::EntityIsDead { entity: BOB }),
+                (1, Severity::Warning, ValidationError::EntityIsDead { entity: BOB }),
+                (3, Severity::Warning, ValidationError::RelationIsDead { relation: REL }),
+                (4, Severity::Warning, ValidationError::RelationIsDead { relation: REL }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restore_then_write() {
+        let edit = EditBuilder::new([0xE1; 16])
+            .restore_entity(BOB)
+            .update_entity(BOB, |u| u.set_text(NAME, "Bob", None))
+            .restore_relation(REL)
+            .restore_entity(ALICE)
+            .restore_relation([9u8; 16]);
+        assert_eq!(
+            issues(edit),
+            vec![
+                (3, Severity::Warning, ValidationError::NotDeleted { id: ALICE }),
+                (4, Severity::Warning, ValidationError::NotDeleted { id: [9u8; 16] }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_then_create_in_one_edit() {
+        let edit = EditBuilder::new([0xE1; 16])
+            .delete_entity(ALICE)
+            .update_entity(ALICE, |u| u.set_text(NAME, "Alice", None))
+            .create_entity(ALICE, |e| e.text(NAME, "Alice", None));
+        assert_eq!(
+            issues(edit),
+            vec![
+                (1, Severity::Warning, ValidationError::EntityIsDead { entity: ALICE }),
+                (2, Severity::Error, ValidationError::CreateAfterDelete { id: ALICE }),
+            ]
+        );
+
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_relation_simple([8u8; 16], ALICE, BOB, KNOWS)
+            .delete_relation([8u8; 16])
+            .create_relation_simple([8u8; 16], ALICE, BOB, KNOWS);
+        assert_eq!(
+            issues(edit),
+            vec![(2, Severity::Error, ValidationError::CreateAfterDelete { id: [8u8; 16] })]
+        );
+
+        // A relation's reified entity stays deleted
+        let edit = EditBuilder::new([0xE1; 16])
+            .delete_entity(relation_entity_id(&[8u8; 16]))
+            .create_relation_simple([8u8; 16], ALICE, BOB, KNOWS);
+        assert!(issues(edit).is_empty());
+        let edit = EditBuilder::new([0xE1; 16])
+            .create_empty_entity(relation_entity_id(&[8u8; 16]))
+            .delete_entity(relation_entity_id(&[8u8; 16]))
+            .create_relation_simple([8u8; 16], ALICE, BOB, KNOWS);
+        assert_eq!(
+            issues(edit),
+            vec![(
+                2,
+                Severity::Warning,
+                ValidationError::EntityIsDead { entity: relation_entity_id(&[8u8; 16]) }
+            )]
+        );
+    }
+}
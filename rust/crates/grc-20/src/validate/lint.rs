@@ -0,0 +1,167 @@
+//! Redundant and contradictory ops within a single edit.
+//!
+//! None of these are errors: ops apply in order and the result is well
+//! defined. They usually point at an authoring mistake, though, and cost
+//! bytes for no effect, so tools can warn before publishing.
+
+use alloc::vec::Vec;
+
+use crate::hash::FxHashMap;
+use crate::model::{Edit, Id, Op, PropertyValue, UnsetLanguage, Value};
+
+/// A pair of ops that conflict or repeat each other. Op positions are
+/// indices into `Edit::ops`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// The same entity is created twice; the second create acts as an
+    /// update.
+    DuplicateCreate { entity: Id, first: usize, second: usize },
+    /// A value is set in one op and unset in another, so one of them is
+    /// overridden.
+    SetAndUnset { entity: Id, property: Id, set: usize, unset: usize },
+    /// A relation is created and deleted in the same edit.
+    CreateAndDelete { relation: Id, create: usize, delete: usize },
+    /// A value is set to what it was already set to earlier in the edit.
+    RepeatedValue { entity: Id, property: Id, first: usize, second: usize },
+}
+
+/// Finds conflicting and redundant ops, in op order.
+///
+/// Setting and unsetting a property within one `UpdateEntity` is the
+/// documented way to replace a value and is not reported.
+pub fn find_conflicts(edit: &Edit<'_>) -> Vec<Conflict> {
+    let mut lint = Lint::default();
+    let mut created: FxHashMap<Id, usize> = FxHashMap::default();
+    let mut relations: FxHashMap<Id, usize> = FxHashMap::default();
+
+    for (i, op) in edit.ops.iter().enumerate() {
+        match op {
+            Op::CreateEntity(create) => {
+                if let Some(&first) = created.get(&create.id) {
+                    let entity = create.id;
+                    lint.conflicts.push(Conflict::DuplicateCreate { entity, first, second: i });
+                } else {
+                    created.insert(create.id, i);
+                }
+                for pv in &create.values {
+                    lint.set(create.id, pv, i);
+                }
+            }
+            Op::UpdateEntity(update) => {
+                for unset in &update.unset_values {
+                    lint.unset(update.id, unset.property, unset.language, i);
+                }
+                for pv in &update.set_properties {
+                    lint.set(update.id, pv, i);
+                }
+            }
+            Op::CreateRelation(relation) => {
+                relations.entry(relation.id).or_insert(i);
+            }
+            Op::DeleteRelation(delete) => {
+                if let Some(&create) = relations.get(&delete.id) {
+                    let relation = delete.id;
+                    lint.conflicts.push(Conflict::CreateAndDelete { relation, create, delete: i });
+                }
+            }
+            _ => {}
+        }
+    }
+    lint.conflicts
+}
+
+/// A live set: language slot, op position, value.
+type SetSlot<'e, 'a> = (Option<Id>, usize, &'e Value<'a>);
+
+/// Value slots seen so far, keyed by (entity, property).
+#[derive(Default)]
+struct Lint<'e, 'a> {
+    sets: FxHashMap<(Id, Id), Vec<SetSlot<'e, 'a>>>,
+    /// Unsets: language, op position.
+    unsets: FxHashMap<(Id, Id), Vec<(UnsetLanguage, usize)>>,
+    conflicts: Vec<Conflict>,
+}
+
+impl<'e, 'a> Lint<'e, 'a> {
+    fn set(&mut self, entity: Id, pv: &'e PropertyValue<'a>, op: usize) {
+        let property = pv.property;
+        let language = match &pv.value {
+            Value::Text { language, .. } => *language,
+            _ => None,
+        };
+        let unset = self.unsets.get(&(entity, property)).and_then(|unsets| {
+            unsets.iter().rev().find(|&&(l, u)| u != op && covers(l, language))
+        });
+        if let Some(&(_, unset)) = unset {
+            self.conflicts.push(Conflict::SetAndUnset { entity, property, set: op, unset });
+        }
+
+        let slots = self.sets.entry((entity, property)).or_default();
+        match slots.iter_mut().find(|(l, ..)| *l == language) {
+            Some(slot) => {
+                if *slot.2 == pv.value {
+                    let first = slot.1;
+                    let repeated = Conflict::RepeatedValue { entity, property, first, second: op };
+                    self.conflicts.push(repeated);
+                }
+                *slot = (language, op, &pv.value);
+            }
+            None => slots.push((language, op, &pv.value)),
+        }
+    }
+
+    fn unset(&mut self, entity: Id, property: Id, language: UnsetLanguage, op: usize) {
+        if let Some(slots) = self.sets.get_mut(&(entity, property)) {
+            for &(l, set, _) in slots.iter() {
+                if set != op && covers(language, l) {
+                    self.conflicts.push(Conflict::SetAndUnset { entity, property, set, unset: op });
+                }
+            }
+            slots.retain(|&(l, ..)| !covers(language, l));
+        }
+        self.unsets.entry((entity, property)).or_default().push((language, op));
+    }
+}
+
+/// Returns true if an unset of `unset` clears the slot for `language`.
+fn covers(unset: UnsetLanguage, language: Option<Id>) -> bool {
+    match unset {
+        UnsetLanguage::All => true,
+        UnsetLanguage::English => language.is_none(),
+        UnsetLanguage::Specific(id) => language == Some(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EditBuilder;
+
+    #[test]
+    fn test_find_conflicts() {
+        let (alice, name, age, rel) = ([1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]);
+        let edit = EditBuilder::new([0u8; 16])
+            .create_entity(alice, |e| e.text(name, "Alice", None).int64(age, 30, None))
+            .create_entity(alice, |e| e.int64(age, 30, None))
+            .update_entity(alice, |u| u.unset_all(name).set_text(name, "Al", None))
+            .create_relation_simple(rel, alice, [5u8; 16], [6u8; 16])
+            .delete_relation(rel)
+            .build();
+
+        assert_eq!(
+            find_conflicts(&edit),
+            vec![
+                Conflict::DuplicateCreate { entity: alice, first: 0, second: 1 },
+                Conflict::RepeatedValue { entity: alice, property: age, first: 0, second: 1 },
+                Conflict::SetAndUnset { entity: alice, property: name, set: 0, unset: 2 },
+                Conflict::CreateAndDelete { relation: rel, create: 3, delete: 4 },
+            ]
+        );
+
+        // Unset and set within one update replaces the value
+        let replace = EditBuilder::new([0u8; 16])
+            .update_entity(alice, |u| u.unset_all(name).set_text(name, "Al", None))
+            .build();
+        assert!(find_conflicts(&replace).is_empty());
+    }
+}
@@ -8,22 +8,186 @@
 //! The protocol does not enforce that a property always uses the same type
 //! across edits. Applications can use SchemaContext to opt-in to type checking.
 
-use std::collections::HashMap;
+pub mod ids;
+#[cfg(feature = "std")]
+pub mod lifecycle;
+pub mod lint;
+pub mod profile;
+pub mod schedule;
+#[cfg(feature = "std")]
+mod schema_file;
+pub mod units;
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+use alloc::vec::Vec;
 
 use crate::error::ValidationError;
-use crate::model::{DataType, Edit, Id, Op, PropertyValue, Value};
+#[cfg(feature = "std")]
+use crate::genesis::{properties, relation_types};
+#[cfg(feature = "std")]
+use crate::lang::LanguageRegistry;
+use crate::model::{DataType, Id, Value};
+pub use profile::ValidationProfile;
+pub use units::Dimension;
+#[cfg(feature = "std")]
+pub use units::UnitRegistry;
+#[cfg(feature = "std")]
+use crate::model::id::unique_relation_id;
+#[cfg(feature = "std")]
+use crate::model::{CreateRelation, Edit, Op, PropertyValue, UnsetLanguage, UnsetValue};
+
+/// How many values a property may carry on a single object.
+///
+/// Values are unique per (entity, property), or per (entity, property,
+/// language) for TEXT (spec Section 2.2), so a property holds one value
+/// per slot whatever its cardinality. Cardinality only governs whether an
+/// op may list the same slot more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cardinality {
+    /// At most one value per slot in an op. TEXT values in distinct
+    /// languages count as separate slots of the same value.
+    #[default]
+    Single,
+    /// Several values for the same slot in one op, where the last one
+    /// listed wins. Use relations for properties with several values.
+    Multi,
+}
+
+/// Constraints registered for a property in a [`SchemaContext`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertySchema {
+    /// Expected data type.
+    pub data_type: DataType,
+    /// Single vs multi-valued.
+    pub cardinality: Cardinality,
+    /// Whether the property may be removed once set.
+    pub required: bool,
+    /// Dimension that numeric values' units must measure, if any.
+    pub dimension: Option<Dimension>,
+}
+
+impl PropertySchema {
+    /// Creates a single-valued, optional property schema.
+    pub fn new(data_type: DataType) -> Self {
+        Self {
+            data_type,
+            cardinality: Cardinality::Single,
+            required: false,
+            dimension: None,
+        }
+    }
+
+    /// Allows an op to list the same slot more than once.
+    pub fn multi(mut self) -> Self {
+        self.cardinality = Cardinality::Multi;
+        self
+    }
+
+    /// Marks the property as required.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Requires INT64, FLOAT64, and DECIMAL values to carry a unit of the
+    /// given dimension.
+    pub fn dimension(mut self, dimension: Dimension) -> Self {
+        self.dimension = Some(dimension);
+        self
+    }
+}
+
+/// How relations of a type are identified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelationMode {
+    /// Relations use unique-mode IDs (see [`unique_relation_id`]), so there
+    /// is at most one relation of this type per (from, to) pair. Because a
+    /// repeated create reuses the same ID, this also holds across edits.
+    ///
+    /// [`unique_relation_id`]: crate::model::id::unique_relation_id
+    Unique,
+    /// Any number of relations.
+    #[default]
+    Multi,
+}
+
+/// Constraints registered for a relation type in a [`SchemaContext`].
+///
+/// Empty `from_types`/`to_types` lists allow any entity type.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RelationTypeSchema {
+    /// Types the source entity may have (any one of them).
+    pub from_types: Vec<Id>,
+    /// Types the target entity may have (any one of them).
+    pub to_types: Vec<Id>,
+    /// Unique vs multi mode.
+    pub mode: RelationMode,
+}
+
+impl RelationTypeSchema {
+    /// Creates an unconstrained, multi-mode relation type schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an allowed source entity type.
+    pub fn from_type(mut self, type_id: Id) -> Self {
+        self.from_types.push(type_id);
+        self
+    }
+
+    /// Adds an allowed target entity type.
+    pub fn to_type(mut self, type_id: Id) -> Self {
+        self.to_types.push(type_id);
+        self
+    }
+
+    /// Requires unique-mode relation IDs for this type.
+    pub fn unique(mut self) -> Self {
+        self.mode = RelationMode::Unique;
+        self
+    }
+}
+
+/// Deprecation marker for a property or type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Deprecation {
+    /// The property or type that should be used instead, if any.
+    pub replacement: Option<Id>,
+}
 
 /// Schema context for semantic validation.
 ///
 /// Applications can use this to register expected types for properties
 /// and validate that values match those types. This is advisory—the
 /// protocol does not enforce global type consistency.
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Default)]
 pub struct SchemaContext {
-    /// Known property data types (advisory).
-    properties: HashMap<Id, DataType>,
+    /// Known property constraints (advisory).
+    properties: HashMap<Id, PropertySchema>,
+    /// Known relation type constraints (advisory).
+    relation_types: HashMap<Id, RelationTypeSchema>,
+    /// Known entity types, keyed by entity ID.
+    entity_types: HashMap<Id, HashSet<Id>>,
+    /// Known direct supertypes, keyed by type ID.
+    supertypes: HashMap<Id, HashSet<Id>>,
+    /// Properties that instances of a type are expected to have, in
+    /// declaration order.
+    type_properties: HashMap<Id, Vec<Id>>,
+    /// Human-readable names of properties, relation types, and types.
+    names: HashMap<Id, String>,
+    /// Deprecated properties and types.
+    deprecations: HashMap<Id, Deprecation>,
+    /// Known units, starting with the genesis units.
+    units: UnitRegistry,
+    /// Known languages; TEXT languages are only checked when set.
+    languages: Option<LanguageRegistry>,
 }
 
+#[cfg(feature = "std")]
 impl SchemaContext {
     /// Creates a new empty schema context.
     pub fn new() -> Self {
@@ -31,58 +195,678 @@ impl SchemaContext {
     }
 
     /// Registers a property with its expected data type.
+    ///
+    /// The property is single-valued and optional. Use
+    /// [`add_property_schema`](Self::add_property_schema) to declare
+    /// cardinality or required-ness.
     pub fn add_property(&mut self, id: Id, data_type: DataType) {
-        self.properties.insert(id, data_type);
+        self.properties.insert(id, PropertySchema::new(data_type));
+    }
+
+    /// Registers a property with full constraints.
+    pub fn add_property_schema(&mut self, id: Id, schema: PropertySchema) {
+        self.properties.insert(id, schema);
     }
 
     /// Gets the expected data type for a property, if registered.
     pub fn get_property_type(&self, id: &Id) -> Option<DataType> {
-        self.properties.get(id).copied()
+        self.properties.get(id).map(|p| p.data_type)
+    }
+
+    /// Gets the constraints for a property, if registered.
+    pub fn get_property_schema(&self, id: &Id) -> Option<&PropertySchema> {
+        self.properties.get(id)
+    }
+
+    /// Registers constraints for a relation type.
+    pub fn add_relation_type(&mut self, id: Id, schema: RelationTypeSchema) {
+        self.relation_types.insert(id, schema);
+    }
+
+    /// Gets the constraints for a relation type, if registered.
+    pub fn get_relation_type(&self, id: &Id) -> Option<&RelationTypeSchema> {
+        self.relation_types.get(id)
+    }
+
+    /// Records that an entity has a type.
+    ///
+    /// Types assigned within an edit (via the genesis Types relation) are
+    /// picked up automatically by [`validate_edit`]; this is for entities
+    /// whose types come from earlier edits.
+    pub fn add_entity_type(&mut self, entity: Id, type_id: Id) {
+        self.entity_types.entry(entity).or_default().insert(type_id);
+    }
+
+    /// Gets the known types of an entity, if any.
+    pub fn get_entity_types(&self, entity: &Id) -> Option<&HashSet<Id>> {
+        self.entity_types.get(entity)
+    }
+
+    /// Records that a type is a subtype of another.
+    ///
+    /// SubtypeOf relations within an edit (via the genesis SubtypeOf
+    /// relation) are picked up automatically by [`validate_edit`]; this is
+    /// for type hierarchies from earlier edits.
+    pub fn add_subtype(&mut self, subtype: Id, supertype: Id) {
+        self.supertypes.entry(subtype).or_default().insert(supertype);
+    }
+
+    /// Returns true if `subtype` is `supertype` or, transitively, a subtype
+    /// of it. Cycles in the hierarchy are tolerated.
+    pub fn is_subtype_of(&self, subtype: &Id, supertype: &Id) -> bool {
+        is_subtype(&[&self.supertypes], subtype, supertype)
+    }
+
+    /// Returns true if one of the known types of `entity` is `type_id` or
+    /// a subtype of it.
+    pub fn is_instance_of(&self, entity: &Id, type_id: &Id) -> bool {
+        self.entity_types
+            .get(entity)
+            .is_some_and(|types| types.iter().any(|t| self.is_subtype_of(t, type_id)))
+    }
+
+    /// Declares that instances of a type are expected to have a property.
+    ///
+    /// This is descriptive only: validation does not require the property,
+    /// but code generators use the list to build one field per property.
+    pub fn add_type_property(&mut self, type_id: Id, property: Id) {
+        let properties = self.type_properties.entry(type_id).or_default();
+        if !properties.contains(&property) {
+            properties.push(property);
+        }
+    }
+
+    /// Gets the properties declared for a type, in declaration order.
+    pub fn get_type_properties(&self, type_id: &Id) -> &[Id] {
+        self.type_properties.get(type_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterates over the types that have declared properties, in no
+    /// particular order.
+    pub fn type_properties(&self) -> impl Iterator<Item = (&Id, &[Id])> {
+        self.type_properties.iter().map(|(id, properties)| (id, properties.as_slice()))
+    }
+
+    /// Sets the human-readable name of a property, relation type, or type.
+    pub fn set_name(&mut self, id: Id, name: impl Into<String>) {
+        self.names.insert(id, name.into());
+    }
+
+    /// Gets the name of a property, relation type, or type, if set.
+    pub fn get_name(&self, id: &Id) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+
+    /// Registers a unit with the dimension it measures.
+    pub fn add_unit(&mut self, unit: Id, dimension: Dimension) {
+        self.units.add_unit(unit, dimension);
+    }
+
+    /// Returns the known units.
+    pub fn units(&self) -> &UnitRegistry {
+        &self.units
+    }
+
+    /// Sets the known languages.
+    ///
+    /// Once set, TEXT values whose language is not in the registry are
+    /// reported as warnings.
+    pub fn set_languages(&mut self, languages: LanguageRegistry) {
+        self.languages = Some(languages);
+    }
+
+    /// Returns the known languages, if set.
+    pub fn languages(&self) -> Option<&LanguageRegistry> {
+        self.languages.as_ref()
+    }
+
+    /// Marks a property or type as deprecated, optionally naming its
+    /// replacement.
+    pub fn deprecate(&mut self, id: Id, replacement: Option<Id>) {
+        self.deprecations.insert(id, Deprecation { replacement });
+    }
+
+    /// Gets the deprecation marker for a property or type, if any.
+    pub fn get_deprecation(&self, id: &Id) -> Option<&Deprecation> {
+        self.deprecations.get(id)
+    }
+
+    /// Follows replacement hints from a deprecated property or type to the
+    /// final non-deprecated replacement.
+    ///
+    /// Returns None if `id` is not deprecated, has no replacement, or the
+    /// hints form a cycle. Migration tooling uses this to rewrite writes to
+    /// deprecated properties automatically.
+    pub fn resolve_replacement(&self, id: &Id) -> Option<Id> {
+        let mut current = *id;
+        let mut seen = HashSet::new();
+        while let Some(deprecation) = self.deprecations.get(&current) {
+            if !seen.insert(current) {
+                return None;
+            }
+            current = deprecation.replacement?;
+        }
+        (current != *id).then_some(current)
+    }
+
+    /// Records deprecations declared by an edit.
+    ///
+    /// An entity is deprecated by setting the genesis Deprecated property
+    /// to true; a genesis ReplacedBy relation from it names the
+    /// replacement. Setting Deprecated to false lifts the marker. Use this
+    /// to carry markers from earlier edits; markers within the edit being
+    /// validated are picked up automatically.
+    pub fn apply_deprecations(&mut self, edit: &Edit) {
+        for (id, deprecation) in collect_deprecations(edit) {
+            match deprecation {
+                Some(deprecation) => {
+                    let entry = self.deprecations.entry(id).or_default();
+                    entry.replacement = deprecation.replacement.or(entry.replacement);
+                }
+                None => {
+                    self.deprecations.remove(&id);
+                }
+            }
+        }
     }
 }
 
+/// How serious a validation issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The edit can be applied, but something could not be verified.
+    Warning,
+    /// The edit violates the schema.
+    Error,
+}
+
+/// A validation problem found by [`validate_edit_all`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// Index of the offending op within `edit.ops`.
+    pub op_index: usize,
+    /// Error or warning.
+    pub severity: Severity,
+    /// The problem.
+    pub error: ValidationError,
+}
+
 /// Validates an edit against a schema context.
 ///
 /// This performs semantic validation that requires context:
 /// - Value types match property data types (when registered in schema)
+/// - Single-valued properties are not given multiple values in one op
+/// - Numeric values carry a unit of their property's declared dimension
+/// - TEXT languages are registered, once a language registry is set
+///   (warnings only)
+/// - Required properties are not unset without being replaced in the same
+///   op and slot
+/// - Relation endpoints have an allowed type, and relations of unique
+///   relation types use unique-mode IDs
+/// - Deprecated properties are not written and deprecated types are not
+///   assigned (warnings only)
+/// - Spaces are not created with or renamed to an empty name
+///
+/// Returns the first error. Use [`validate_edit_all`] to collect every
+/// issue, including warnings.
 ///
-/// Note: Type checking is advisory. Unknown properties are allowed.
-/// Entity lifecycle (DELETED/ACTIVE) validation requires state context
-/// and is not performed here.
+/// Note: Type checking is advisory. Unknown properties and relation types
+/// are allowed. Relation endpoints with no known types cannot be checked and
+/// are reported as warnings by [`validate_edit_all`].
+/// Entity lifecycle (DELETED/ACTIVE) validation requires state context;
+/// see [`lifecycle::validate_lifecycle`].
+#[cfg(feature = "std")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(ops = edit.ops.len()))
+)]
 pub fn validate_edit(edit: &Edit, schema: &SchemaContext) -> Result<(), ValidationError> {
-    for op in &edit.ops {
+    let mut validator = EditValidator::new(edit, schema);
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        validator.validate_op(op_index, op);
+        if let Some(issue) = validator.issues.iter().find(|i| i.severity == Severity::Error) {
+            return Err(issue.error.clone());
+        }
+        validator.issues.clear();
+    }
+    Ok(())
+}
+
+/// Validates an edit against a schema context, collecting every issue.
+///
+/// Performs the same checks as [`validate_edit`] but does not stop at the
+/// first error. Issues are returned in op order. Warnings are reported for
+/// constrained relation endpoints whose entity types are unknown and for
+/// writes to deprecated properties or types.
+#[cfg(feature = "std")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(ops = edit.ops.len(), issues = tracing::field::Empty)
+    )
+)]
+pub fn validate_edit_all(edit: &Edit, schema: &SchemaContext) -> Vec<ValidationIssue> {
+    let mut validator = EditValidator::new(edit, schema);
+    for (op_index, op) in edit.ops.iter().enumerate() {
+        validator.validate_op(op_index, op);
+    }
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("issues", validator.issues.len());
+    validator.issues
+}
+
+/// Shared state for validating the ops of one edit.
+#[cfg(feature = "std")]
+struct EditValidator<'s> {
+    schema: &'s SchemaContext,
+    edit_types: HashMap<Id, HashSet<Id>>,
+    edit_supertypes: HashMap<Id, HashSet<Id>>,
+    edit_deprecations: HashMap<Id, Option<Deprecation>>,
+    op_index: usize,
+    issues: Vec<ValidationIssue>,
+}
+
+#[cfg(feature = "std")]
+impl<'s> EditValidator<'s> {
+    fn new(edit: &Edit, schema: &'s SchemaContext) -> Self {
+        Self {
+            schema,
+            edit_types: collect_related(edit, relation_types::types()),
+            edit_supertypes: collect_related(edit, relation_types::subtype_of()),
+            edit_deprecations: collect_deprecations(edit),
+            op_index: 0,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Checks subtyping against the schema and the edit's SubtypeOf relations.
+    fn is_subtype(&self, subtype: &Id, supertype: &Id) -> bool {
+        is_subtype(&[&self.schema.supertypes, &self.edit_supertypes], subtype, supertype)
+    }
+
+    fn report(&mut self, severity: Severity, error: ValidationError) {
+        self.issues.push(ValidationIssue {
+            op_index: self.op_index,
+            severity,
+            error,
+        });
+    }
+
+    fn validate_op(&mut self, op_index: usize, op: &Op) {
+        self.op_index = op_index;
         match op {
             Op::CreateEntity(ce) => {
-                validate_property_values(&ce.values, schema)?;
+                self.validate_property_values(&ce.values);
+                self.validate_units(&ce.values);
+                self.validate_languages(&ce.values);
+                self.validate_deprecated_properties(&ce.values);
+                self.validate_cardinality(ce.id, &ce.values);
             }
             Op::UpdateEntity(ue) => {
-                validate_property_values(&ue.set_properties, schema)?;
+                self.validate_property_values(&ue.set_properties);
+                self.validate_units(&ue.set_properties);
+                self.validate_languages(&ue.set_properties);
+                self.validate_deprecated_properties(&ue.set_properties);
+                self.validate_cardinality(ue.id, &ue.set_properties);
+                self.validate_required(ue.id, &ue.unset_values, &ue.set_properties);
+            }
+            Op::CreateRelation(cr) => {
+                self.validate_relation(cr);
+                self.validate_deprecated_type(cr);
+            }
+            Op::CreateSpace(cs) if cs.name.is_empty() => {
+                self.report(Severity::Error, ValidationError::EmptySpaceName { space: cs.id });
+            }
+            Op::UpdateSpaceMetadata(usm) if usm.name.as_deref() == Some("") => {
+                self.report(Severity::Error, ValidationError::EmptySpaceName { space: usm.space });
             }
             _ => {}
         }
     }
 
-    Ok(())
+    /// Returns the deprecation marker for an ID, preferring markers
+    /// declared in the edit over those in the schema.
+    fn deprecation(&self, id: &Id) -> Option<Deprecation> {
+        match self.edit_deprecations.get(id) {
+            Some(marker) => *marker,
+            None => self.schema.get_deprecation(id).copied(),
+        }
+    }
+
+    /// Warns about values written to deprecated properties.
+    fn validate_deprecated_properties(&mut self, values: &[PropertyValue]) {
+        for pv in values {
+            if let Some(deprecation) = self.deprecation(&pv.property) {
+                self.report(
+                    Severity::Warning,
+                    ValidationError::DeprecatedProperty {
+                        property: pv.property,
+                        replacement: deprecation.replacement,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Warns about entities being assigned a deprecated type.
+    fn validate_deprecated_type(&mut self, relation: &CreateRelation) {
+        if relation.relation_type != relation_types::types() || relation.to_is_value_ref {
+            return;
+        }
+        if let Some(deprecation) = self.deprecation(&relation.to) {
+            self.report(
+                Severity::Warning,
+                ValidationError::DeprecatedType {
+                    entity: relation.from,
+                    type_id: relation.to,
+                    replacement: deprecation.replacement,
+                },
+            );
+        }
+    }
+
+    /// Validates that property values match their declared types.
+    fn validate_property_values(&mut self, values: &[PropertyValue]) {
+        for pv in values {
+            if let Some(expected_type) = self.schema.get_property_type(&pv.property) {
+                let actual_type = pv.value.data_type();
+                if expected_type != actual_type {
+                    self.report(
+                        Severity::Error,
+                        ValidationError::TypeMismatch {
+                            property: pv.property,
+                            expected: expected_type,
+                        },
+                    );
+                }
+            }
+            // Note: If property is not in schema, we allow it (might be defined elsewhere)
+        }
+    }
+
+    /// Validates that numeric values carry units of their property's
+    /// declared dimension.
+    ///
+    /// Units missing from the registry are reported as warnings, since
+    /// they may be defined elsewhere.
+    fn validate_units(&mut self, values: &[PropertyValue]) {
+        for pv in values {
+            let schema = self.schema.get_property_schema(&pv.property);
+            let Some(expected) = schema.and_then(|p| p.dimension) else {
+                continue;
+            };
+            let unit = match &pv.value {
+                Value::Int64 { unit, .. }
+                | Value::Float64 { unit, .. }
+                | Value::Decimal { unit, .. } => *unit,
+                _ => continue,
+            };
+            let Some(unit) = unit else {
+                self.report(
+                    Severity::Error,
+                    ValidationError::MissingUnit { property: pv.property, expected },
+                );
+                continue;
+            };
+            match self.schema.units.dimension(&unit) {
+                None => self.report(
+                    Severity::Warning,
+                    ValidationError::UnknownUnit { property: pv.property, unit },
+                ),
+                Some(actual) if actual != expected => self.report(
+                    Severity::Error,
+                    ValidationError::UnitDimensionMismatch {
+                        property: pv.property,
+                        unit,
+                        expected,
+                        actual,
+                    },
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Validates that TEXT languages are registered, when the schema has
+    /// a language registry.
+    fn validate_languages(&mut self, values: &[PropertyValue]) {
+        let Some(languages) = &self.schema.languages else {
+            return;
+        };
+        for pv in values {
+            if let Value::Text { language: Some(language), .. } = &pv.value {
+                if !languages.contains(language) {
+                    self.report(
+                        Severity::Warning,
+                        ValidationError::UnknownLanguage {
+                            property: pv.property,
+                            language: *language,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Validates that single-valued properties receive at most one value.
+    fn validate_cardinality(&mut self, entity: Id, values: &[PropertyValue]) {
+        for (i, pv) in values.iter().enumerate() {
+            let Some(prop) = self.schema.get_property_schema(&pv.property) else {
+                continue;
+            };
+            if prop.cardinality != Cardinality::Single {
+                continue;
+            }
+            let slot = text_language(&pv.value);
+            let duplicate = values[..i]
+                .iter()
+                .any(|other| other.property == pv.property && text_language(&other.value) == slot);
+            if duplicate {
+                self.report(
+                    Severity::Error,
+                    ValidationError::CardinalityViolation {
+                        entity,
+                        property: pv.property,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Validates that required properties are not removed.
+    ///
+    /// Unsetting a required property is allowed when the same op sets a
+    /// replacement value in the cleared slot (unsets are applied before
+    /// sets): a TEXT value in the same language for a language unset, a
+    /// non-TEXT or language-less value for an English unset. Clearing all
+    /// slots of a TEXT property needs a replacement for every language the
+    /// entity had, which cannot be seen without state, so a replacement in
+    /// the same op is only reported as a warning.
+    fn validate_required(
+        &mut self,
+        entity: Id,
+        unset_values: &[UnsetValue],
+        set_properties: &[PropertyValue],
+    ) {
+        for unset in unset_values {
+            let Some(prop) = self.schema.get_property_schema(&unset.property) else {
+                continue;
+            };
+            if !prop.required {
+                continue;
+            }
+            let mut slots = set_properties
+                .iter()
+                .filter(|pv| pv.property == unset.property)
+                .map(|pv| text_language(&pv.value).flatten());
+            let severity = match unset.language {
+                UnsetLanguage::All if prop.data_type == DataType::Text => {
+                    if slots.next().is_some() {
+                        Severity::Warning
+                    } else {
+                        Severity::Error
+                    }
+                }
+                UnsetLanguage::All | UnsetLanguage::English => {
+                    if slots.any(|slot| slot.is_none()) {
+                        continue;
+                    }
+                    Severity::Error
+                }
+                UnsetLanguage::Specific(language) => {
+                    if slots.any(|slot| slot == Some(language)) {
+                        continue;
+                    }
+                    Severity::Error
+                }
+            };
+            self.report(
+                severity,
+                ValidationError::RequiredPropertyRemoved {
+                    entity,
+                    property: unset.property,
+                },
+            );
+        }
+    }
+
+    /// Validates a relation against its relation type constraints.
+    fn validate_relation(&mut self, relation: &CreateRelation) {
+        let Some(rel_schema) = self.schema.get_relation_type(&relation.relation_type) else {
+            return;
+        };
+
+        let endpoints = [
+            ("from", relation.from, relation.from_is_value_ref, &rel_schema.from_types),
+            ("to", relation.to, relation.to_is_value_ref, &rel_schema.to_types),
+        ];
+        for (endpoint, entity, is_value_ref, allowed) in endpoints {
+            if is_value_ref || allowed.is_empty() {
+                continue;
+            }
+            let known = self
+                .schema
+                .get_entity_types(&entity)
+                .into_iter()
+                .chain(self.edit_types.get(&entity));
+            let mut has_types = false;
+            let mut matches = false;
+            for types in known {
+                has_types |= !types.is_empty();
+                matches |= types.iter().any(|ty| allowed.iter().any(|t| self.is_subtype(ty, t)));
+            }
+            if !has_types {
+                self.report(
+                    Severity::Warning,
+                    ValidationError::RelationEndpointTypeUnknown {
+                        relation: relation.id,
+                        endpoint,
+                        entity,
+                    },
+                );
+            } else if !matches {
+                self.report(
+                    Severity::Error,
+                    ValidationError::RelationEndpointTypeMismatch {
+                        relation: relation.id,
+                        endpoint,
+                        entity,
+                    },
+                );
+            }
+        }
+
+        if rel_schema.mode == RelationMode::Unique
+            && relation.id
+                != unique_relation_id(&relation.from, &relation.to, &relation.relation_type)
+        {
+            self.report(
+                Severity::Error,
+                ValidationError::RelationNotUnique {
+                    relation: relation.id,
+                    relation_type: relation.relation_type,
+                },
+            );
+        }
+    }
 }
 
-/// Validates that property values match their declared types.
-fn validate_property_values(
-    values: &[PropertyValue],
-    schema: &SchemaContext,
-) -> Result<(), ValidationError> {
-    for pv in values {
-        if let Some(expected_type) = schema.get_property_type(&pv.property) {
-            let actual_type = pv.value.data_type();
-            if expected_type != actual_type {
-                return Err(ValidationError::TypeMismatch {
-                    property: pv.property,
-                    expected: expected_type,
-                });
+/// Collects the targets of the relations of one type within the edit, by
+/// source: entity types for Types, supertypes for SubtypeOf.
+#[cfg(feature = "std")]
+fn collect_related(edit: &Edit, relation_type: Id) -> HashMap<Id, HashSet<Id>> {
+    let mut related: HashMap<Id, HashSet<Id>> = HashMap::new();
+    for op in &edit.ops {
+        if let Op::CreateRelation(cr) = op {
+            if cr.relation_type == relation_type && !cr.from_is_value_ref && !cr.to_is_value_ref {
+                related.entry(cr.from).or_default().insert(cr.to);
             }
         }
-        // Note: If property is not in schema, we allow it (might be defined elsewhere)
     }
-    Ok(())
+    related
+}
+
+/// Returns true if `supertype` is `subtype` or reachable from it through
+/// the supertype maps.
+#[cfg(feature = "std")]
+fn is_subtype(supertypes: &[&HashMap<Id, HashSet<Id>>], subtype: &Id, supertype: &Id) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![*subtype];
+    while let Some(current) = stack.pop() {
+        if current == *supertype {
+            return true;
+        }
+        if seen.insert(current) {
+            for map in supertypes {
+                stack.extend(map.get(&current).into_iter().flatten());
+            }
+        }
+    }
+    false
+}
+
+/// Collects deprecation markers declared within the edit.
+///
+/// Maps each entity whose Deprecated property is set to Some (true) or None
+/// (false); ReplacedBy relations fill in replacements.
+#[cfg(feature = "std")]
+fn collect_deprecations(edit: &Edit) -> HashMap<Id, Option<Deprecation>> {
+    let deprecated = properties::deprecated();
+    let replaced_by = relation_types::replaced_by();
+    let mut markers: HashMap<Id, Option<Deprecation>> = HashMap::new();
+    for op in &edit.ops {
+        let (id, values) = match op {
+            Op::CreateEntity(ce) => (ce.id, &ce.values),
+            Op::UpdateEntity(ue) => (ue.id, &ue.set_properties),
+            _ => continue,
+        };
+        for pv in values.iter().filter(|pv| pv.property == deprecated) {
+            if let Value::Bool(flag) = pv.value {
+                markers.insert(id, flag.then(Deprecation::default));
+            }
+        }
+    }
+    for op in &edit.ops {
+        if let Op::CreateRelation(cr) = op {
+            if cr.relation_type == replaced_by && !cr.to_is_value_ref {
+                if let Some(Some(marker)) = markers.get_mut(&cr.from) {
+                    marker.replacement = Some(cr.to);
+                }
+            }
+        }
+    }
+    markers
+}
+
+/// Returns the language slot of a TEXT value (None for other types).
+#[cfg(feature = "std")]
+fn text_language(value: &Value) -> Option<Option<Id>> {
+    match value {
+        Value::Text { language, .. } => Some(*language),
+        _ => None,
+    }
 }
 
 /// Validates a single value (independent of property context).
@@ -105,12 +889,29 @@ pub fn validate_position(pos: &str) -> Result<(), &'static str> {
     crate::model::validate_position(pos)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::borrow::Cow;
 
     use super::*;
-    use crate::model::CreateEntity;
+    use crate::model::{CreateEntity, CreateSpace, UpdateEntity, UpdateSpaceMetadata};
+
+    fn relation(id: u8, relation_type: Id, from: Id, to: Id) -> Op<'static> {
+        Op::CreateRelation(CreateRelation {
+            id: [id; 16],
+            relation_type,
+            from,
+            from_is_value_ref: false,
+            from_space: None,
+            from_version: None,
+            to,
+            to_is_value_ref: false,
+            to_space: None,
+            to_version: None,
+            entity: None,
+            position: None,
+        })
+    }
 
     #[test]
     fn test_validate_type_mismatch() {
@@ -122,6 +923,7 @@ mod tests {
             name: Cow::Borrowed(""),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
                 values: vec![PropertyValue {
@@ -130,6 +932,7 @@ mod tests {
                         value: Cow::Owned("not an int".to_string()),
                         language: None,
                     },
+                    clock: None,
                 }],
             })],
         };
@@ -148,11 +951,13 @@ mod tests {
             name: Cow::Borrowed(""),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
                 values: vec![PropertyValue {
                     property: [1u8; 16],
                     value: Value::Int64 { value: 42, unit: None },
+                    clock: None,
                 }],
             })],
         };
@@ -170,6 +975,7 @@ mod tests {
             name: Cow::Borrowed(""),
             authors: vec![],
             created_at: 0,
+            provenance: None,
             ops: vec![Op::CreateEntity(CreateEntity {
                 id: [2u8; 16],
                 values: vec![PropertyValue {
@@ -178,6 +984,7 @@ mod tests {
                         value: Cow::Owned("test".to_string()),
                         language: None,
                     },
+                    clock: None,
                 }],
             })],
         };
@@ -186,4 +993,446 @@ mod tests {
         let result = validate_edit(&edit, &schema);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_validate_cardinality() {
+        let mut schema = SchemaContext::new();
+        schema.add_property_schema([1u8; 16], PropertySchema::new(DataType::Int64));
+        schema.add_property_schema([3u8; 16], PropertySchema::new(DataType::Text));
+
+        let int = |value| PropertyValue {
+            property: [1u8; 16],
+            value: Value::Int64 { value, unit: None },
+            clock: None,
+        };
+        let text = |language| PropertyValue {
+            property: [3u8; 16],
+            value: Value::Text { value: Cow::Borrowed("name"), language },
+            clock: None,
+        };
+        let edit_with = |values| Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![Op::CreateEntity(CreateEntity { id: [2u8; 16], values })],
+        };
+
+        let result = validate_edit(&edit_with(vec![int(1), int(2)]), &schema);
+        assert!(matches!(result, Err(ValidationError::CardinalityViolation { .. })));
+
+        // Different language slots are not duplicates
+        let result = validate_edit(&edit_with(vec![int(1), text(None), text(Some([4u8; 16]))]), &schema);
+        assert!(result.is_ok());
+
+        let result = validate_edit(&edit_with(vec![text(Some([4u8; 16])), text(Some([4u8; 16]))]), &schema);
+        assert!(matches!(result, Err(ValidationError::CardinalityViolation { .. })));
+
+        // Multi-valued properties may list a slot more than once
+        schema.add_property_schema([1u8; 16], PropertySchema::new(DataType::Int64).multi());
+        assert!(validate_edit(&edit_with(vec![int(1), int(2)]), &schema).is_ok());
+    }
+
+    #[test]
+    fn test_validate_required_removal() {
+        let mut schema = SchemaContext::new();
+        schema.add_property_schema([1u8; 16], PropertySchema::new(DataType::Int64).required());
+
+        let mut update = UpdateEntity::new([2u8; 16]);
+        update.unset_values.push(UnsetValue::all([1u8; 16]));
+        let mut edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![Op::UpdateEntity(update.clone())],
+        };
+
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(result, Err(ValidationError::RequiredPropertyRemoved { .. })));
+
+        // Replacing the value in the same op is allowed
+        update.set_properties.push(PropertyValue {
+            property: [1u8; 16],
+            value: Value::Int64 { value: 7, unit: None },
+            clock: None,
+        });
+        edit.ops = vec![Op::UpdateEntity(update)];
+        assert!(validate_edit(&edit, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_validate_required_removal_language() {
+        let name = [1u8; 16];
+        let en = [5u8; 16];
+        let es = [6u8; 16];
+        let mut schema = SchemaContext::new();
+        schema.add_property_schema(name, PropertySchema::new(DataType::Text).required());
+
+        let text = |language| PropertyValue {
+            property: name,
+            value: Value::Text { value: Cow::Borrowed("name"), language },
+            clock: None,
+        };
+        let edit_with = |unset, set| {
+            let mut update = UpdateEntity::new([2u8; 16]);
+            update.unset_values.push(unset);
+            update.set_properties = set;
+            Edit {
+                id: [0u8; 16],
+                name: Cow::Borrowed(""),
+                authors: vec![],
+                created_at: 0,
+                provenance: None,
+                ops: vec![Op::UpdateEntity(update)],
+            }
+        };
+
+        // A value in another language does not replace the cleared slot
+        let edit = edit_with(UnsetValue::language(name, es), vec![text(Some(en))]);
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(result, Err(ValidationError::RequiredPropertyRemoved { .. })));
+
+        let edit = edit_with(UnsetValue::english(name), vec![text(Some(en))]);
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(result, Err(ValidationError::RequiredPropertyRemoved { .. })));
+
+        let edit = edit_with(UnsetValue::language(name, es), vec![text(Some(es))]);
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        let edit = edit_with(UnsetValue::english(name), vec![text(None)]);
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        // Clearing every language cannot be checked without state
+        let edit = edit_with(UnsetValue::all(name), vec![text(Some(en))]);
+        let issues = validate_edit_all(&edit, &schema);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(matches!(issues[0].error, ValidationError::RequiredPropertyRemoved { .. }));
+
+        let edit = edit_with(UnsetValue::all(name), vec![]);
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(result, Err(ValidationError::RequiredPropertyRemoved { .. })));
+    }
+
+    #[test]
+    fn test_validate_relation_endpoint_types() {
+        let works_at = [10u8; 16];
+        let person = [20u8; 16];
+        let org = [21u8; 16];
+        let alice = [30u8; 16];
+        let acme = [31u8; 16];
+
+        let mut schema = SchemaContext::new();
+        schema.add_relation_type(works_at, RelationTypeSchema::new().from_type(person).to_type(org));
+        schema.add_entity_type(acme, org);
+
+        let types = relation_types::types();
+        let mut edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![relation(1, types, alice, person), relation(2, works_at, alice, acme)],
+        };
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        // Reversed endpoints: alice is not an organization
+        edit.ops[1] = relation(2, works_at, acme, alice);
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(
+            result,
+            Err(ValidationError::RelationEndpointTypeMismatch { endpoint: "from", .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_relation_endpoint_subtypes() {
+        let works_at = [10u8; 16];
+        let org = [21u8; 16];
+        let company = [22u8; 16];
+        let startup = [23u8; 16];
+        let alice = [30u8; 16];
+        let acme = [31u8; 16];
+
+        let mut schema = SchemaContext::new();
+        schema.add_relation_type(works_at, RelationTypeSchema::new().to_type(org));
+        schema.add_subtype(startup, company);
+        schema.add_entity_type(acme, startup);
+        assert!(!schema.is_instance_of(&acme, &org));
+        assert!(schema.is_instance_of(&acme, &company));
+
+        // company SubtypeOf org comes from the edit
+        let mut edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![relation(2, works_at, alice, acme)],
+        };
+        let result = validate_edit(&edit, &schema);
+        assert!(matches!(
+            result,
+            Err(ValidationError::RelationEndpointTypeMismatch { endpoint: "to", .. })
+        ));
+        edit.ops.insert(0, relation(1, relation_types::subtype_of(), company, org));
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        // Cycles terminate
+        schema.add_subtype(company, startup);
+        assert!(!schema.is_subtype_of(&startup, &org));
+    }
+
+    #[test]
+    fn test_validate_relation_unique() {
+        let spouse = [10u8; 16];
+        let mut schema = SchemaContext::new();
+        schema.add_relation_type(spouse, RelationTypeSchema::new().unique());
+
+        let unique = |from: Id, to: Id| {
+            let Op::CreateRelation(mut relation) = relation(0, spouse, from, to) else {
+                unreachable!()
+            };
+            relation.id = unique_relation_id(&from, &to, &spouse);
+            Op::CreateRelation(relation)
+        };
+        let mut edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![
+                unique([30u8; 16], [31u8; 16]),
+                unique([31u8; 16], [30u8; 16]),
+                unique([30u8; 16], [32u8; 16]),
+            ],
+        };
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        // A many-mode ID would allow a second (from, to) relation
+        edit.ops.push(relation(3, spouse, [30u8; 16], [31u8; 16]));
+        let result = validate_edit(&edit, &schema);
+        assert_eq!(
+            result,
+            Err(ValidationError::RelationNotUnique { relation: [3u8; 16], relation_type: spouse })
+        );
+    }
+
+    #[test]
+    fn test_validate_edit_all_collects_issues() {
+        let works_at = [10u8; 16];
+        let mut schema = SchemaContext::new();
+        schema.add_property([1u8; 16], DataType::Int64);
+        schema.add_relation_type(works_at, RelationTypeSchema::new().to_type([21u8; 16]));
+
+        let bad_value = |id: u8| {
+            Op::CreateEntity(CreateEntity {
+                id: [id; 16],
+                values: vec![PropertyValue {
+                    property: [1u8; 16],
+                    value: Value::Bool(true),
+                    clock: None,
+                }],
+            })
+        };
+        let edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![bad_value(2), relation(1, works_at, [2u8; 16], [3u8; 16]), bad_value(4)],
+        };
+
+        let issues = validate_edit_all(&edit, &schema);
+        assert_eq!(issues.len(), 3);
+        assert_eq!((issues[0].op_index, issues[0].severity), (0, Severity::Error));
+        assert_eq!((issues[1].op_index, issues[1].severity), (1, Severity::Warning));
+        assert_eq!((issues[2].op_index, issues[2].severity), (2, Severity::Error));
+
+        // validate_edit stops at the first error and ignores warnings
+        assert!(matches!(validate_edit(&edit, &schema), Err(ValidationError::TypeMismatch { .. })));
+        let warnings_only = Edit { ops: vec![edit.ops[1].clone()], ..edit };
+        assert!(validate_edit(&warnings_only, &schema).is_ok());
+    }
+
+    #[test]
+    fn test_validate_unit_dimensions() {
+        use crate::genesis::units;
+
+        let height = [1u8; 16];
+        let mut schema = SchemaContext::new();
+        schema.add_property_schema(
+            height,
+            PropertySchema::new(DataType::Float64).multi().dimension(Dimension::Length),
+        );
+
+        let value = |unit: Option<Id>| PropertyValue {
+            property: height,
+            value: Value::Float64 { value: 1.8, unit },
+            clock: None,
+        };
+        let edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![Op::CreateEntity(CreateEntity {
+                id: [2u8; 16],
+                values: vec![
+                    value(Some(units::meter())),
+                    value(Some(units::second())),
+                    value(None),
+                    value(Some(units::from_symbol("furlong"))),
+                ],
+            })],
+        };
+
+        let issues = validate_edit_all(&edit, &schema);
+        let errors: Vec<_> = issues.iter().map(|i| (i.severity, i.error.clone())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    Severity::Error,
+                    ValidationError::UnitDimensionMismatch {
+                        property: height,
+                        unit: units::second(),
+                        expected: Dimension::Length,
+                        actual: Dimension::Time,
+                    }
+                ),
+                (
+                    Severity::Error,
+                    ValidationError::MissingUnit { property: height, expected: Dimension::Length }
+                ),
+                (
+                    Severity::Warning,
+                    ValidationError::UnknownUnit {
+                        property: height,
+                        unit: units::from_symbol("furlong"),
+                    }
+                ),
+            ]
+        );
+
+        // Registering the unit makes it acceptable
+        schema.add_unit(units::from_symbol("furlong"), Dimension::Length);
+        assert_eq!(validate_edit_all(&edit, &schema).len(), 2);
+    }
+
+    #[test]
+    fn test_validate_languages() {
+        use crate::genesis::languages;
+
+        let text = |language: Id| PropertyValue {
+            property: [1u8; 16],
+            value: Value::Text { value: Cow::Borrowed("olá"), language: Some(language) },
+            clock: None,
+        };
+        let pt_br = crate::lang::language_id_for_tag("pt-BR").unwrap();
+        let edit = Edit {
+            id: [0u8; 16],
+            name: Cow::Borrowed(""),
+            authors: vec![],
+            created_at: 0,
+            provenance: None,
+            ops: vec![Op::CreateEntity(CreateEntity {
+                id: [2u8; 16],
+                values: vec![text(languages::portuguese()), text(pt_br)],
+            })],
+        };
+
+        let mut schema = SchemaContext::new();
+        assert!(validate_edit_all(&edit, &schema).is_empty());
+
+        let mut registry = LanguageRegistry::new();
+        schema.set_languages(registry.clone());
+        let issues = validate_edit_all(&edit, &schema);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].error,
+            ValidationError::UnknownLanguage { property: [1u8; 16], language: pt_br }
+        );
+
+        registry.add_tag("pt-BR").unwrap();
+        schema.set_languages(registry);
+        assert!(validate_edit_all(&edit, &schema).is_empty());
+    }
+
+    #[test]
+    fn test_validate_deprecated_property_and_type() {
+        let old_name = [1u8; 16];
+        let new_name = [2u8; 16];
+        let old_type = [3u8; 16];
+
+        let mut schema = SchemaContext::new();
+        schema.deprecate(old_type, None);
+
+        // The edit itself deprecates old_name in favor of new_name
+        let mut edit = Edit::new([0u8; 16]);
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: old_name,
+            values: vec![PropertyValue {
+                property: properties::deprecated(),
+                value: Value::Bool(true),
+                clock: None,
+            }],
+        }));
+        edit.ops.push(relation(1, relation_types::replaced_by(), old_name, new_name));
+        edit.ops.push(Op::CreateEntity(CreateEntity {
+            id: [10u8; 16],
+            values: vec![PropertyValue {
+                property: old_name,
+                value: Value::Text { value: Cow::Borrowed("x"), language: None },
+                clock: None,
+            }],
+        }));
+        edit.ops.push(relation(2, relation_types::types(), [10u8; 16], old_type));
+
+        let issues = validate_edit_all(&edit, &schema);
+        assert_eq!(issues.len(), 2);
+        assert!(issues.iter().all(|i| i.severity == Severity::Warning));
+        assert_eq!(
+            issues[0].error,
+            ValidationError::DeprecatedProperty { property: old_name, replacement: Some(new_name) }
+        );
+        assert_eq!(issues[1].op_index, 3);
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        schema.apply_deprecations(&edit);
+        assert_eq!(schema.resolve_replacement(&old_name), Some(new_name));
+        schema.deprecate(new_name, Some([4u8; 16]));
+        assert_eq!(schema.resolve_replacement(&old_name), Some([4u8; 16]));
+        schema.deprecate([4u8; 16], Some(old_name));
+        assert_eq!(schema.resolve_replacement(&old_name), None);
+        assert_eq!(schema.resolve_replacement(&old_type), None);
+    }
+
+    #[test]
+    fn test_validate_space_names() {
+        let space = [1u8; 16];
+        let mut edit = Edit::new([0u8; 16]);
+        edit.ops.push(Op::CreateSpace(CreateSpace { id: space, name: Cow::Borrowed("Crypto") }));
+        edit.ops.push(Op::UpdateSpaceMetadata(UpdateSpaceMetadata {
+            space,
+            name: None,
+            description: Some(Cow::Borrowed("")),
+        }));
+        let schema = SchemaContext::new();
+        assert!(validate_edit(&edit, &schema).is_ok());
+
+        edit.ops.push(Op::UpdateSpaceMetadata(UpdateSpaceMetadata {
+            space,
+            name: Some(Cow::Borrowed("")),
+            description: None,
+        }));
+        assert_eq!(validate_edit(&edit, &schema), Err(ValidationError::EmptySpaceName { space }));
+    }
 }
@@ -0,0 +1,154 @@
+//! Validation profiles: which value checks are hard errors.
+//!
+//! The spec's value rules are all enforced by default. Deployments that
+//! index legacy data written by lax producers can relax some of them by
+//! configuring a [`ValidationProfile`] on [`EncodeOptions`],
+//! [`DecodeOptions`], and [`Value::validate_with`]:
+//!
+//! | Check                          | Strict | Standard   | Permissive |
+//! |--------------------------------|--------|------------|------------|
+//! | DECIMAL normalization          | error  | normalized | normalized |
+//! | DATE day in a non-leap year    | error  | accepted   | accepted   |
+//! | position characters            | error  | error      | accepted   |
+//! | NaN in FLOAT64, POINT, float32 | error  | error      | accepted   |
+//!
+//! "Normalized" means unnormalized or non-minimal DECIMAL values are
+//! accepted and converted to normal form, so they never leave the codec
+//! unnormalized. Length limits apply under every profile. Edits encoded
+//! under Standard or Permissive may be rejected by Strict decoders.
+//!
+//! [`EncodeOptions`]: crate::codec::EncodeOptions
+//! [`DecodeOptions`]: crate::codec::DecodeOptions
+//! [`Value::validate_with`]: crate::model::Value::validate_with
+
+/// How strictly value checks are enforced. See the [module
+/// docs](self) for the checks each profile relaxes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ValidationProfile {
+    /// Every check is a hard error, as the spec requires. Edits encoded
+    /// under it are accepted by every profile.
+    #[default]
+    Strict,
+    /// Tolerates representation issues: unnormalized DECIMAL values are
+    /// normalized, and February 29 is accepted in any year.
+    Standard,
+    /// Also accepts any characters in positions and NaN floats.
+    Permissive,
+}
+
+impl ValidationProfile {
+    /// Returns true if unnormalized DECIMAL values are normalized instead
+    /// of rejected.
+    pub fn normalizes_decimals(self) -> bool {
+        self != ValidationProfile::Strict
+    }
+
+    /// Returns true if DATE days are checked against leap years (see
+    /// [`validate_iso8601_date`](crate::model::timestamp::validate_iso8601_date)).
+    pub fn strict_dates(self) -> bool {
+        self == ValidationProfile::Strict
+    }
+
+    /// Returns true if positions may contain characters outside
+    /// `0-9A-Za-z`.
+    pub fn allows_any_position_chars(self) -> bool {
+        self == ValidationProfile::Permissive
+    }
+
+    /// Returns true if NaN is accepted in FLOAT64 values, POINT
+    /// coordinates, and float32 embeddings.
+    pub fn allows_nan(self) -> bool {
+        self == ValidationProfile::Permissive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{decode_edit_with_options, encode_edit_with_options};
+    use crate::codec::{DecodeOptions, EncodeOptions};
+    use crate::error::{DecodeError, EncodeError};
+    use crate::model::{DecimalMantissa, Edit, EditBuilder, Op, Value};
+
+    const PROFILES: [ValidationProfile; 3] =
+        [ValidationProfile::Strict, ValidationProfile::Standard, ValidationProfile::Permissive];
+
+    /// Encodes and decodes under `profile`, returning the first decoded
+    /// value of a CreateEntity op.
+    fn roundtrip(
+        edit: &Edit,
+        profile: ValidationProfile,
+    ) -> Result<Option<Value<'static>>, EncodeError> {
+        let bytes = encode_edit_with_options(edit, EncodeOptions::new().with_profile(profile))?;
+        let options = DecodeOptions::new().with_profile(profile);
+        let decoded = decode_edit_with_options(&bytes, options).unwrap();
+        Ok(match &decoded.ops[0] {
+            Op::CreateEntity(ce) => Some(ce.values[0].value.clone().into_owned()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_profiles_across_encode_and_decode() {
+        let p = [1u8; 16];
+        let entity =
+            |f: fn(_) -> _| EditBuilder::new([0u8; 16]).create_entity([2u8; 16], f).build();
+
+        let decimal = entity(|e| e.decimal([1u8; 16], 0, DecimalMantissa::I64(1200), None));
+        let leap_day = entity(|e| e.date([1u8; 16], "2023-02-29"));
+        let nan = entity(|e| e.float64([1u8; 16], f64::NAN, None));
+        let position = EditBuilder::new([0u8; 16])
+            .create_relation_simple([3u8; 16], [2u8; 16], [4u8; 16], p)
+            .update_relation([3u8; 16], |r| r.set_position("a-b"))
+            .build();
+
+        for profile in PROFILES {
+            let decimal = roundtrip(&decimal, profile);
+            match profile {
+                ValidationProfile::Strict => {
+                    assert_eq!(decimal.unwrap_err(), EncodeError::DecimalNotNormalized)
+                }
+                _ => assert_eq!(
+                    decimal.unwrap(),
+                    Some(Value::Decimal {
+                        exponent: 2,
+                        mantissa: DecimalMantissa::I64(12),
+                        unit: None
+                    })
+                ),
+            }
+            assert_eq!(roundtrip(&leap_day, profile).is_ok(), !profile.strict_dates());
+            assert_eq!(roundtrip(&position, profile).is_ok(), profile.allows_any_position_chars());
+            let nan = roundtrip(&nan, profile);
+            assert_eq!(nan.is_ok(), profile.allows_nan());
+            if let Ok(value) = nan {
+                assert!(matches!(value, Some(Value::Float64 { value, .. }) if value.is_nan()));
+            }
+        }
+
+        // Decoding under a stricter profile than the encoder's
+        let options = EncodeOptions::new().with_profile(ValidationProfile::Permissive);
+        let bytes = encode_edit_with_options(&position, options).unwrap();
+        let standard = DecodeOptions::new().with_profile(ValidationProfile::Standard);
+        assert!(matches!(
+            decode_edit_with_options(&bytes, standard).unwrap_err().kind(),
+            DecodeError::InvalidPositionChar { char: '-' }
+        ));
+    }
+
+    #[test]
+    fn test_validate_with_profile() {
+        let unnormalized =
+            Value::Decimal { exponent: 0, mantissa: DecimalMantissa::I64(10), unit: None };
+        let leap_day = Value::Date("2023-02-29".into());
+        let nan = Value::Float64 { value: f64::NAN, unit: None };
+        for value in [&unnormalized, &leap_day, &nan] {
+            assert!(value.validate().is_some());
+            assert_eq!(value.validate(), value.validate_with(ValidationProfile::Strict));
+        }
+        assert_eq!(unnormalized.validate_with(ValidationProfile::Standard), None);
+        assert_eq!(leap_day.validate_with(ValidationProfile::Standard), None);
+        assert!(nan.validate_with(ValidationProfile::Standard).is_some());
+        assert_eq!(nan.validate_with(ValidationProfile::Permissive), None);
+    }
+}
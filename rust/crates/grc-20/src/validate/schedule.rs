@@ -0,0 +1,686 @@
+//! RFC 5545 validation for SCHEDULE values.
+//!
+//! SCHEDULE values are sequences of iCalendar content lines (spec Section 2.4).
+//! This module checks the subset of RFC 5545 that matters for GRC-20:
+//! - Content line syntax (names, parameters, line folding)
+//! - BEGIN/END component structure, including VEVENT requirements
+//! - DTSTART/DTEND/DUE/RECURRENCE-ID date-time syntax
+//! - RDATE/EXDATE/FREEBUSY lists, DURATION values
+//! - RRULE syntax
+//!
+//! Unknown properties are accepted as long as they are syntactically valid,
+//! since RFC 5545 allows IANA and experimental (`X-`) extensions.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::model::timestamp::days_in_month;
+
+/// A problem found while validating a SCHEDULE value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleIssue {
+    /// 1-based content line number (after unfolding) where the problem was found.
+    pub line: usize,
+    /// Description of the problem.
+    pub reason: &'static str,
+}
+
+/// Validates a SCHEDULE string according to RFC 5545.
+///
+/// In strict mode, the first problem is returned as an error. This is the
+/// mode used by the encoder and decoder, since the spec requires invalid
+/// schedules to be rejected (E005).
+///
+/// In non-strict mode, every problem is downgraded to a warning and returned
+/// in the `Ok` vector, which is useful for linting data before an import.
+pub fn validate_schedule(
+    schedule: &str,
+    strict: bool,
+) -> Result<Vec<ScheduleIssue>, ScheduleIssue> {
+    let mut issues = Issues {
+        strict,
+        found: Vec::new(),
+    };
+
+    let unfolded = unfold(schedule);
+    let mut lines: Vec<&str> = unfolded
+        .split('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .collect();
+    // A single trailing line break is allowed
+    if lines.len() > 1 && lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    if lines.iter().all(|l| l.is_empty()) {
+        issues.report(1, "schedule contains no content lines")?;
+        return Ok(issues.found);
+    }
+
+    let mut stack: Vec<Component<'_>> = vec![Component::new("", 0)];
+
+    for (i, raw) in lines.iter().enumerate() {
+        let line_no = i + 1;
+        if raw.is_empty() {
+            issues.report(line_no, "empty content line")?;
+            continue;
+        }
+
+        let line = match parse_content_line(raw) {
+            Ok(line) => line,
+            Err(reason) => {
+                issues.report(line_no, reason)?;
+                continue;
+            }
+        };
+
+        let name = line.name.to_ascii_uppercase();
+        match name.as_str() {
+            "BEGIN" => {
+                if !is_valid_name(line.value) {
+                    issues.report(line_no, "BEGIN has an invalid component name")?;
+                }
+                stack.push(Component::new(line.value, line_no));
+            }
+            "END" => {
+                let matches_top = stack.len() > 1
+                    && stack
+                        .last()
+                        .is_some_and(|c| c.name.eq_ignore_ascii_case(line.value));
+                if matches_top {
+                    let component = stack.pop().expect("stack has an open component");
+                    component.finish(&mut issues)?;
+                } else {
+                    issues.report(line_no, "END does not match the open BEGIN")?;
+                }
+            }
+            _ => {
+                let component = stack.last_mut().expect("stack always has a root");
+                check_property(&name, &line, line_no, component, &mut issues)?;
+            }
+        }
+    }
+
+    let last_line = lines.len();
+    while stack.len() > 1 {
+        stack.pop();
+        issues.report(last_line, "component is missing its END line")?;
+    }
+    stack.pop().expect("root component").finish(&mut issues)?;
+
+    Ok(issues.found)
+}
+
+/// Accumulates issues, or fails fast in strict mode.
+struct Issues {
+    strict: bool,
+    found: Vec<ScheduleIssue>,
+}
+
+impl Issues {
+    fn report(&mut self, line: usize, reason: &'static str) -> Result<(), ScheduleIssue> {
+        let issue = ScheduleIssue { line, reason };
+        if self.strict {
+            return Err(issue);
+        }
+        self.found.push(issue);
+        Ok(())
+    }
+}
+
+/// Per-component state used for structural checks.
+struct Component<'a> {
+    name: &'a str,
+    begin_line: usize,
+    dtstart: Option<DateProperty<'a>>,
+    dtend: Option<DateProperty<'a>>,
+    has_duration: bool,
+    rrule_line: Option<usize>,
+}
+
+/// A DTSTART/DTEND value with its TZID parameter, for ordering checks.
+struct DateProperty<'a> {
+    value: &'a str,
+    tzid: Option<&'a str>,
+    line: usize,
+}
+
+impl<'a> Component<'a> {
+    fn new(name: &'a str, begin_line: usize) -> Self {
+        Self {
+            name,
+            begin_line,
+            dtstart: None,
+            dtend: None,
+            has_duration: false,
+            rrule_line: None,
+        }
+    }
+
+    /// Runs the checks that need the whole component.
+    fn finish(self, issues: &mut Issues) -> Result<(), ScheduleIssue> {
+        let is_event = self.name.eq_ignore_ascii_case("VEVENT");
+        if is_event && self.dtstart.is_none() {
+            issues.report(self.begin_line, "VEVENT is missing DTSTART")?;
+        }
+        if let Some(line) = self.rrule_line {
+            if self.dtstart.is_none() && !is_event {
+                issues.report(line, "RRULE requires DTSTART")?;
+            }
+        }
+        if let (Some(start), Some(end)) = (&self.dtstart, &self.dtend) {
+            if self.has_duration {
+                issues.report(end.line, "DTEND and DURATION are mutually exclusive")?;
+            }
+            // Only compare values in the same form and zone
+            if start.tzid == end.tzid
+                && start.value.len() == end.value.len()
+                && end.value < start.value
+            {
+                issues.report(end.line, "DTEND is before DTSTART")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed content line: `name *(";" param) ":" value`.
+pub(crate) struct ContentLine<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) params: Vec<(&'a str, &'a str)>,
+    pub(crate) value: &'a str,
+}
+
+impl<'a> ContentLine<'a> {
+    /// Returns the raw value of a parameter (case-insensitive name match).
+    pub(crate) fn param(&self, name: &str) -> Option<&'a str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+}
+
+/// Removes RFC 5545 line folding (a line break followed by a space or tab).
+pub(crate) fn unfold(s: &str) -> Cow<'_, str> {
+    if !s.contains("\n ") && !s.contains("\n\t") {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let is_break = c == '\n' || (c == '\r' && chars.peek() == Some(&'\n'));
+        if is_break {
+            let mut lookahead = chars.clone();
+            if c == '\r' {
+                lookahead.next();
+            }
+            if matches!(lookahead.peek(), Some(' ') | Some('\t')) {
+                lookahead.next();
+                chars = lookahead;
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    Cow::Owned(out)
+}
+
+pub(crate) fn parse_content_line(line: &str) -> Result<ContentLine<'_>, &'static str> {
+    let name_end = line
+        .find([';', ':'])
+        .ok_or("content line is missing ':' separator")?;
+    let name = &line[..name_end];
+    if !is_valid_name(name) {
+        return Err("invalid property name");
+    }
+
+    let mut params = Vec::new();
+    let bytes = line.as_bytes();
+    let mut pos = name_end;
+    while bytes[pos] == b';' {
+        pos += 1;
+        let eq = line[pos..]
+            .find('=')
+            .map(|p| pos + p)
+            .ok_or("parameter is missing '='")?;
+        let param_name = &line[pos..eq];
+        if !is_valid_name(param_name) {
+            return Err("invalid parameter name");
+        }
+
+        // Parameter values are comma-separated, optionally double-quoted
+        let value_start = eq + 1;
+        pos = value_start;
+        loop {
+            if bytes.get(pos) == Some(&b'"') {
+                let close = line[pos + 1..]
+                    .find('"')
+                    .ok_or("unterminated quoted parameter value")?;
+                pos += close + 2;
+            } else {
+                while pos < bytes.len() && !matches!(bytes[pos], b',' | b';' | b':' | b'"') {
+                    pos += 1;
+                }
+            }
+            match bytes.get(pos) {
+                Some(b',') => pos += 1,
+                Some(b';') | Some(b':') => break,
+                Some(_) => return Err("invalid character in parameter value"),
+                None => return Err("content line is missing ':' separator"),
+            }
+        }
+        params.push((param_name, &line[value_start..pos]));
+    }
+
+    Ok(ContentLine {
+        name,
+        params,
+        value: &line[pos + 1..],
+    })
+}
+
+/// Returns true for an iana-token or x-name: 1*(ALPHA / DIGIT / "-").
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+fn check_property<'a>(
+    name: &str,
+    line: &ContentLine<'a>,
+    line_no: usize,
+    component: &mut Component<'a>,
+    issues: &mut Issues,
+) -> Result<(), ScheduleIssue> {
+    let value_type = line.param("VALUE");
+    let tzid = line.param("TZID");
+
+    match name {
+        "DTSTART" | "DTEND" | "DUE" | "RECURRENCE-ID" => {
+            if let Err(reason) = check_date_or_date_time(line.value, value_type, tzid) {
+                issues.report(line_no, reason)?;
+            }
+            let date_property = DateProperty {
+                value: line.value,
+                tzid,
+                line: line_no,
+            };
+            if name == "DTSTART" {
+                if component.dtstart.is_some() {
+                    issues.report(line_no, "DTSTART appears more than once")?;
+                }
+                component.dtstart = Some(date_property);
+            } else if name == "DTEND" {
+                if component.dtend.is_some() {
+                    issues.report(line_no, "DTEND appears more than once")?;
+                }
+                component.dtend = Some(date_property);
+            }
+        }
+        "DURATION" => {
+            if !is_valid_duration(line.value) {
+                issues.report(line_no, "malformed DURATION value")?;
+            }
+            component.has_duration = true;
+        }
+        "RRULE" | "EXRULE" => {
+            if let Err(reason) = check_rrule(line.value) {
+                issues.report(line_no, reason)?;
+            }
+            if name == "RRULE" {
+                component.rrule_line = Some(line_no);
+            }
+        }
+        "EXDATE" | "RDATE" => {
+            for item in line.value.split(',') {
+                let result = if value_type.is_some_and(|v| v.eq_ignore_ascii_case("PERIOD")) {
+                    check_period(item)
+                } else {
+                    check_date_or_date_time(item, value_type, tzid)
+                };
+                if let Err(reason) = result {
+                    issues.report(line_no, reason)?;
+                }
+            }
+        }
+        "FREEBUSY" => {
+            for item in line.value.split(',') {
+                if let Err(reason) = check_period(item) {
+                    issues.report(line_no, reason)?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Checks a DATE or DATE-TIME value, honoring the VALUE parameter.
+///
+/// Without a VALUE parameter either form is accepted, since GRC-20
+/// schedules commonly use bare dates (e.g. `DTSTART:20240101`).
+fn check_date_or_date_time(
+    value: &str,
+    value_type: Option<&str>,
+    tzid: Option<&str>,
+) -> Result<(), &'static str> {
+    let wants_date = value_type.is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+    let wants_date_time = value_type.is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME"));
+
+    if value.contains('T') {
+        if wants_date {
+            return Err("VALUE=DATE but value is a DATE-TIME");
+        }
+        check_date_time(value)?;
+        if tzid.is_some() && value.ends_with('Z') {
+            return Err("UTC DATE-TIME must not have a TZID parameter");
+        }
+        Ok(())
+    } else {
+        if wants_date_time {
+            return Err("VALUE=DATE-TIME but value is a DATE");
+        }
+        check_date(value)
+    }
+}
+
+/// Checks an iCalendar DATE: `YYYYMMDD`.
+fn check_date(value: &str) -> Result<(), &'static str> {
+    if value.len() != 8 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("malformed DATE (expected YYYYMMDD)");
+    }
+    let year = digits(&value[0..4]);
+    let month = digits(&value[4..6]);
+    let day = digits(&value[6..8]);
+    if !(1..=12).contains(&month) {
+        return Err("DATE month out of range");
+    }
+    if day < 1 || day > days_in_month(year as i64, month) {
+        return Err("DATE day out of range for month");
+    }
+    Ok(())
+}
+
+/// Checks an iCalendar DATE-TIME: `YYYYMMDD "T" HHMMSS ["Z"]`.
+fn check_date_time(value: &str) -> Result<(), &'static str> {
+    let (date, time) = value
+        .split_once('T')
+        .ok_or("malformed DATE-TIME (expected YYYYMMDDTHHMMSS)")?;
+    check_date(date)?;
+    let time = time.strip_suffix('Z').unwrap_or(time);
+    if time.len() != 6 || !time.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("malformed DATE-TIME (expected YYYYMMDDTHHMMSS)");
+    }
+    if digits(&time[0..2]) > 23 || digits(&time[2..4]) > 59 || digits(&time[4..6]) > 60 {
+        return Err("DATE-TIME time component out of range");
+    }
+    Ok(())
+}
+
+/// Checks a PERIOD: `date-time "/" (date-time / duration)`.
+fn check_period(value: &str) -> Result<(), &'static str> {
+    let (start, end) = value
+        .split_once('/')
+        .ok_or("malformed PERIOD (expected start/end or start/duration)")?;
+    check_date_time(start)?;
+    if end.starts_with(['P', '+', '-']) {
+        if !is_valid_duration(end) {
+            return Err("malformed DURATION in PERIOD");
+        }
+    } else {
+        check_date_time(end)?;
+    }
+    Ok(())
+}
+
+/// Checks a DURATION: `["+" / "-"] "P" (dur-date / dur-time / dur-week)`.
+fn is_valid_duration(value: &str) -> bool {
+    let value = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let Some(rest) = value.strip_prefix('P') else {
+        return false;
+    };
+
+    // dur-week = 1*DIGIT "W"
+    if let Some(weeks) = rest.strip_suffix('W') {
+        return !weeks.is_empty() && weeks.bytes().all(|b| b.is_ascii_digit());
+    }
+
+    let (date_part, time_part) = match rest.split_once('T') {
+        Some((d, t)) => (d, Some(t)),
+        None => (rest, None),
+    };
+
+    // dur-day = 1*DIGIT "D"
+    if !date_part.is_empty() {
+        let Some(days) = date_part.strip_suffix('D') else {
+            return false;
+        };
+        if days.is_empty() || !days.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+    }
+
+    match time_part {
+        // dur-time = "T" (dur-hour / dur-minute / dur-second), designators in order
+        Some(time) => {
+            let mut remaining = time;
+            let mut seen_any = false;
+            for designator in ['H', 'M', 'S'] {
+                let digit_count = remaining.bytes().take_while(|b| b.is_ascii_digit()).count();
+                if digit_count > 0 && remaining[digit_count..].starts_with(designator) {
+                    remaining = &remaining[digit_count + 1..];
+                    seen_any = true;
+                }
+            }
+            seen_any && remaining.is_empty()
+        }
+        None => !date_part.is_empty(),
+    }
+}
+
+/// Checks an RRULE/EXRULE recurrence rule (RFC 5545 Section 3.3.10).
+fn check_rrule(value: &str) -> Result<(), &'static str> {
+    let mut seen: Vec<&str> = Vec::new();
+    let mut has_freq = false;
+    let mut has_until = false;
+    let mut has_count = false;
+
+    for part in value.split(';') {
+        let (key, val) = part
+            .split_once('=')
+            .ok_or("RRULE part is missing '='")?;
+        let key_upper = key.to_ascii_uppercase();
+        if seen.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+            return Err("RRULE part appears more than once");
+        }
+        seen.push(key);
+        if val.is_empty() {
+            return Err("RRULE part has an empty value");
+        }
+
+        match key_upper.as_str() {
+            "FREQ" => {
+                has_freq = true;
+                let valid = [
+                    "SECONDLY", "MINUTELY", "HOURLY", "DAILY", "WEEKLY", "MONTHLY", "YEARLY",
+                ];
+                if !valid.iter().any(|f| f.eq_ignore_ascii_case(val)) {
+                    return Err("RRULE has an invalid FREQ");
+                }
+            }
+            "UNTIL" => {
+                has_until = true;
+                check_date_or_date_time(val, None, None)?;
+            }
+            "COUNT" => {
+                has_count = true;
+                if parse_unsigned(val).is_none_or(|n| n == 0) {
+                    return Err("RRULE COUNT must be a positive integer");
+                }
+            }
+            "INTERVAL" => {
+                if parse_unsigned(val).is_none_or(|n| n == 0) {
+                    return Err("RRULE INTERVAL must be a positive integer");
+                }
+            }
+            "BYSECOND" => check_int_list(val, 0, 60, false)?,
+            "BYMINUTE" => check_int_list(val, 0, 59, false)?,
+            "BYHOUR" => check_int_list(val, 0, 23, false)?,
+            "BYMONTHDAY" => check_int_list(val, 1, 31, true)?,
+            "BYYEARDAY" => check_int_list(val, 1, 366, true)?,
+            "BYWEEKNO" => check_int_list(val, 1, 53, true)?,
+            "BYMONTH" => check_int_list(val, 1, 12, false)?,
+            "BYSETPOS" => check_int_list(val, 1, 366, true)?,
+            "BYDAY" => {
+                for day in val.split(',') {
+                    check_weekday_num(day)?;
+                }
+            }
+            "WKST" => {
+                if !is_weekday(val) {
+                    return Err("RRULE WKST must be a weekday");
+                }
+            }
+            _ => return Err("RRULE has an unknown part"),
+        }
+    }
+
+    if !has_freq {
+        return Err("RRULE is missing FREQ");
+    }
+    if has_until && has_count {
+        return Err("RRULE UNTIL and COUNT are mutually exclusive");
+    }
+    Ok(())
+}
+
+/// Checks a comma-separated list of integers in `min..=max`.
+///
+/// When `signed` is true, negative values in `-max..=-min` are also allowed.
+fn check_int_list(list: &str, min: u32, max: u32, signed: bool) -> Result<(), &'static str> {
+    for item in list.split(',') {
+        let (negative, digits) = match item.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, item.strip_prefix('+').unwrap_or(item)),
+        };
+        if negative && !signed {
+            return Err("RRULE BY* value must not be negative");
+        }
+        match parse_unsigned(digits) {
+            Some(n) if (min..=max).contains(&n) => {}
+            _ => return Err("RRULE BY* value out of range"),
+        }
+    }
+    Ok(())
+}
+
+/// Checks a BYDAY entry: `[["+" / "-"] 1*2DIGIT] weekday`.
+fn check_weekday_num(value: &str) -> Result<(), &'static str> {
+    if value.len() < 2 || !is_weekday(&value[value.len() - 2..]) {
+        return Err("RRULE BYDAY entry must end in a weekday");
+    }
+    let ordinal = &value[..value.len() - 2];
+    if ordinal.is_empty() {
+        return Ok(());
+    }
+    let ordinal = ordinal.strip_prefix(['+', '-']).unwrap_or(ordinal);
+    match parse_unsigned(ordinal) {
+        Some(n) if (1..=53).contains(&n) => Ok(()),
+        _ => Err("RRULE BYDAY ordinal out of range"),
+    }
+}
+
+fn is_weekday(value: &str) -> bool {
+    ["SU", "MO", "TU", "WE", "TH", "FR", "SA"]
+        .iter()
+        .any(|d| d.eq_ignore_ascii_case(value))
+}
+
+fn parse_unsigned(value: &str) -> Option<u32> {
+    if value.is_empty() || value.len() > 9 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+/// Parses a run of ASCII digits that has already been checked.
+fn digits(s: &str) -> u32 {
+    s.bytes().fold(0, |acc, b| acc * 10 + (b - b'0') as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict(s: &str) -> Result<Vec<ScheduleIssue>, ScheduleIssue> {
+        validate_schedule(s, true)
+    }
+
+    #[test]
+    fn test_valid_schedules() {
+        assert!(strict("BEGIN:VEVENT\r\nDTSTART:20240315T090000Z\r\nDTEND:20240315T100000Z\r\nEND:VEVENT").is_ok());
+        assert!(strict("DTSTART:20240315T090000Z\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR").is_ok());
+        assert!(strict("DTSTART:20240101\nRRULE:FREQ=YEARLY").is_ok());
+        assert!(strict("FREEBUSY:20240315T090000Z/20240315T170000Z").is_ok());
+        assert!(strict("FREEBUSY:20240315T090000Z/PT8H,20240316T090000Z/PT1H30M").is_ok());
+        assert!(strict("DTSTART;TZID=America/New_York:20240315T090000\nDURATION:PT1H").is_ok());
+        assert!(strict("DTSTART:20240229T090000Z\nRRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=10").is_ok());
+        assert!(strict("DTSTART;VALUE=DATE:20240101\nEXDATE;VALUE=DATE:20250101,20260101").is_ok());
+    }
+
+    #[test]
+    fn test_line_folding() {
+        let folded = "DTSTART:20240315T090000Z\r\nRRULE:FREQ=WEEKLY;\r\n BYDAY=MO,WE,FR";
+        assert!(strict(folded).is_ok());
+    }
+
+    #[test]
+    fn test_structure_errors() {
+        assert_eq!(strict("").unwrap_err().reason, "schedule contains no content lines");
+        assert!(strict("BEGIN:VEVENT\nDTSTART:20240315T090000Z").is_err());
+        assert!(strict("BEGIN:VEVENT\nDTSTART:20240315T090000Z\nEND:VTODO").is_err());
+        assert!(strict("BEGIN:VEVENT\nSUMMARY:Meeting\nEND:VEVENT").is_err());
+        assert!(strict("not a content line").is_err());
+        assert!(strict("BAD NAME:value").is_err());
+    }
+
+    #[test]
+    fn test_date_time_errors() {
+        assert!(strict("DTSTART:20241315T090000Z").is_err()); // month 13
+        assert!(strict("DTSTART:20230229").is_err()); // not a leap year
+        assert!(strict("DTSTART:20240315T250000Z").is_err()); // hour 25
+        assert!(strict("DTSTART:2024-03-15").is_err());
+        assert!(strict("DTSTART;VALUE=DATE:20240315T090000Z").is_err());
+        assert!(strict("DTSTART;TZID=Europe/Paris:20240315T090000Z").is_err());
+        assert!(strict("DTSTART:20240315T100000Z\nDTEND:20240315T090000Z").is_err());
+        assert!(strict("DTSTART:20240315T090000Z\nDTEND:20240315T100000Z\nDURATION:PT1H").is_err());
+    }
+
+    #[test]
+    fn test_rrule_errors() {
+        let with_rule = |rule: &str| strict(&format!("DTSTART:20240315T090000Z\nRRULE:{}", rule));
+        assert!(with_rule("BYDAY=MO").is_err()); // missing FREQ
+        assert!(with_rule("FREQ=FORTNIGHTLY").is_err());
+        assert!(with_rule("FREQ=DAILY;COUNT=5;UNTIL=20240401T000000Z").is_err());
+        assert!(with_rule("FREQ=DAILY;COUNT=0").is_err());
+        assert!(with_rule("FREQ=DAILY;FREQ=WEEKLY").is_err());
+        assert!(with_rule("FREQ=MONTHLY;BYMONTHDAY=32").is_err());
+        assert!(with_rule("FREQ=WEEKLY;BYDAY=XX").is_err());
+        assert!(with_rule("FREQ=WEEKLY;BYHOUR=-1").is_err());
+        assert!(with_rule("FREQ=MONTHLY;BYMONTHDAY=-31;BYDAY=+2TU").is_ok());
+        assert!(strict("RRULE:FREQ=DAILY").is_err()); // no DTSTART
+    }
+
+    #[test]
+    fn test_non_strict_collects_warnings() {
+        let schedule = "BEGIN:VEVENT\nDTSTART:20241315\nRRULE:FREQ=NEVER\nEND:VEVENT";
+        let warnings = validate_schedule(schedule, false).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].line, 2);
+        assert_eq!(warnings[1].line, 3);
+
+        assert!(validate_schedule("DTSTART:20240315T090000Z", false).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,802 @@
+//! Declarative schema files for [`SchemaContext`].
+//!
+//! A schema file lists properties, relation types, and types by ID, so a
+//! schema can be version-controlled and loaded for [`validate_edit`] or
+//! code generation. The TOML form uses one array-of-tables per section:
+//!
+//! ```text
+//! [[properties]]
+//! id = "7f1c2e3d4b5a69788796a5b4c3d2e1f0"
+//! name = "Age"
+//! data_type = "int64"
+//! cardinality = "multi"    # or "single" (default)
+//! required = true          # default false
+//! dimension = "time"       # length, mass, time, temperature,
+//!                          # information, currency, or an ID
+//!
+//! [[relation_types]]
+//! id = "..."
+//! from_types = ["..."]     # default: any type
+//! to_types = ["..."]
+//! mode = "unique"          # or "multi" (default)
+//!
+//! [[types]]
+//! id = "..."
+//! subtype_of = ["..."]
+//! properties = ["..."]     # fields for code generation
+//! deprecated = true        # any entry may be deprecated
+//! replaced_by = "..."
+//! ```
+//!
+//! The JSON form has the same shape: an object with `properties`,
+//! `relation_types`, and `types` arrays of objects with the same keys.
+//!
+//! Unknown keys, duplicate keys, and IDs declared twice are errors. Entity
+//! types, units, and languages are not part of the schema file. Output is
+//! deterministic: entries are sorted by ID and default values are omitted.
+//!
+//! [`validate_edit`]: super::validate_edit
+
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+
+use super::{
+    Cardinality, Dimension, PropertySchema, RelationMode, RelationTypeSchema, SchemaContext,
+};
+use crate::error::SchemaFileError;
+use crate::genesis::data_type_name;
+use crate::model::id::{format_id, parse_id};
+use crate::model::{DataType, Id};
+
+impl SchemaContext {
+    /// Parses a TOML schema file.
+    ///
+    /// Only the subset of TOML the format needs is supported: the
+    /// `[[properties]]`, `[[relation_types]]`, and `[[types]]` headers,
+    /// and `key = value` lines whose value is a string, a boolean, or a
+    /// single-line array of strings.
+    pub fn from_toml_str(s: &str) -> Result<Self, SchemaFileError> {
+        build(parse_toml(s)?)
+    }
+
+    /// Serializes the schema as a TOML schema file.
+    pub fn to_toml_string(&self) -> String {
+        let mut out = String::new();
+        for (section, fields) in self.declarations() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[[{}]]\n", section.name()));
+            for (key, value) in fields {
+                out.push_str(&format!("{} = {}\n", key, value.to_text()));
+            }
+        }
+        out
+    }
+
+    /// Parses a JSON schema file.
+    pub fn from_json_str(s: &str) -> Result<Self, SchemaFileError> {
+        build(parse_json(s)?)
+    }
+
+    /// Serializes the schema as a JSON schema file.
+    pub fn to_json_string(&self) -> String {
+        let declarations = self.declarations();
+        let sections: Vec<String> = Section::ALL
+            .iter()
+            .map(|section| {
+                let entries: Vec<String> = declarations
+                    .iter()
+                    .filter(|(s, _)| s == section)
+                    .map(|(_, fields)| {
+                        let fields: Vec<String> = fields
+                            .iter()
+                            .map(|(key, value)| format!("\"{}\": {}", key, value.to_text()))
+                            .collect();
+                        format!("    {{{}}}", fields.join(", "))
+                    })
+                    .collect();
+                if entries.is_empty() {
+                    format!("  \"{}\": []", section.name())
+                } else {
+                    format!("  \"{}\": [\n{}\n  ]", section.name(), entries.join(",\n"))
+                }
+            })
+            .collect();
+        format!("{{\n{}\n}}\n", sections.join(",\n"))
+    }
+
+    /// Lists every declared ID with its fields, in file order.
+    fn declarations(&self) -> Vec<(Section, Vec<(&'static str, Field)>)> {
+        let mut out = Vec::new();
+
+        let properties: BTreeSet<Id> = self.properties.keys().copied().collect();
+        for id in &properties {
+            let schema = &self.properties[id];
+            let mut fields = self.common_fields(id);
+            fields.push(("data_type", Field::Str(data_type_name(schema.data_type).to_string())));
+            if schema.cardinality == Cardinality::Multi {
+                fields.push(("cardinality", Field::Str("multi".to_string())));
+            }
+            if schema.required {
+                fields.push(("required", Field::Bool(true)));
+            }
+            if let Some(dimension) = schema.dimension {
+                fields.push(("dimension", Field::Str(dimension_name(dimension))));
+            }
+            out.push((Section::Properties, self.with_deprecation(id, fields)));
+        }
+
+        let relation_types: BTreeSet<Id> = self.relation_types.keys().copied().collect();
+        for id in &relation_types {
+            let schema = &self.relation_types[id];
+            let mut fields = self.common_fields(id);
+            if !schema.from_types.is_empty() {
+                fields.push(("from_types", id_list(&schema.from_types)));
+            }
+            if !schema.to_types.is_empty() {
+                fields.push(("to_types", id_list(&schema.to_types)));
+            }
+            if schema.mode == RelationMode::Unique {
+                fields.push(("mode", Field::Str("unique".to_string())));
+            }
+            out.push((Section::RelationTypes, self.with_deprecation(id, fields)));
+        }
+
+        let types: BTreeSet<Id> = self
+            .names
+            .keys()
+            .chain(self.supertypes.keys())
+            .chain(self.type_properties.keys())
+            .chain(self.deprecations.keys())
+            .copied()
+            .filter(|id| !properties.contains(id) && !relation_types.contains(id))
+            .collect();
+        for id in &types {
+            let mut fields = self.common_fields(id);
+            if let Some(supertypes) = self.supertypes.get(id).filter(|s| !s.is_empty()) {
+                let mut supertypes: Vec<Id> = supertypes.iter().copied().collect();
+                supertypes.sort_unstable();
+                fields.push(("subtype_of", id_list(&supertypes)));
+            }
+            let type_properties = self.get_type_properties(id);
+            if !type_properties.is_empty() {
+                fields.push(("properties", id_list(type_properties)));
+            }
+            out.push((Section::Types, self.with_deprecation(id, fields)));
+        }
+
+        out
+    }
+
+    fn common_fields(&self, id: &Id) -> Vec<(&'static str, Field)> {
+        let mut fields = vec![("id", Field::Str(format_id(id)))];
+        if let Some(name) = self.get_name(id) {
+            fields.push(("name", Field::Str(name.to_string())));
+        }
+        fields
+    }
+
+    fn with_deprecation(
+        &self,
+        id: &Id,
+        mut fields: Vec<(&'static str, Field)>,
+    ) -> Vec<(&'static str, Field)> {
+        if let Some(deprecation) = self.get_deprecation(id) {
+            fields.push(("deprecated", Field::Bool(true)));
+            if let Some(replacement) = deprecation.replacement {
+                fields.push(("replaced_by", Field::Str(format_id(&replacement))));
+            }
+        }
+        fields
+    }
+}
+
+/// A top-level section of a schema file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Properties,
+    RelationTypes,
+    Types,
+}
+
+impl Section {
+    const ALL: [Section; 3] = [Section::Properties, Section::RelationTypes, Section::Types];
+
+    fn name(self) -> &'static str {
+        match self {
+            Section::Properties => "properties",
+            Section::RelationTypes => "relation_types",
+            Section::Types => "types",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// A value in a schema file entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Str(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Field {
+    /// Formats the value; TOML and JSON share the syntax for these values.
+    fn to_text(&self) -> String {
+        match self {
+            Field::Str(s) => quote(s),
+            Field::Bool(b) => b.to_string(),
+            Field::List(items) => {
+                let items: Vec<String> = items.iter().map(|s| quote(s)).collect();
+                format!("[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+/// One declaration: a TOML table or a JSON object in a section.
+struct Entry {
+    section: Section,
+    line: usize,
+    /// Key, line, and value of each field, in file order.
+    fields: Vec<(String, usize, Field)>,
+}
+
+impl Entry {
+    fn new(section: Section, line: usize) -> Self {
+        Self { section, line, fields: Vec::new() }
+    }
+
+    fn insert(&mut self, key: String, line: usize, value: Field) -> Result<(), SchemaFileError> {
+        if self.fields.iter().any(|(k, _, _)| *k == key) {
+            return Err(invalid_field(line, &key, "key is set more than once"));
+        }
+        self.fields.push((key, line, value));
+        Ok(())
+    }
+
+    fn take(&mut self, key: &str) -> Option<(usize, Field)> {
+        let index = self.fields.iter().position(|(k, _, _)| k == key)?;
+        let (_, line, value) = self.fields.remove(index);
+        Some((line, value))
+    }
+
+    fn take_str(&mut self, key: &str) -> Result<Option<(usize, String)>, SchemaFileError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((line, Field::Str(s))) => Ok(Some((line, s))),
+            Some((line, _)) => Err(invalid_field(line, key, "expected a string")),
+        }
+    }
+
+    fn take_bool(&mut self, key: &str) -> Result<Option<bool>, SchemaFileError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((_, Field::Bool(b))) => Ok(Some(b)),
+            Some((line, _)) => Err(invalid_field(line, key, "expected a boolean")),
+        }
+    }
+
+    fn take_id(&mut self, key: &str) -> Result<Option<Id>, SchemaFileError> {
+        match self.take_str(key)? {
+            None => Ok(None),
+            Some((line, s)) => {
+                parse_id(&s).map(Some).ok_or_else(|| invalid_field(line, key, "not a valid ID"))
+            }
+        }
+    }
+
+    fn take_ids(&mut self, key: &str) -> Result<Vec<Id>, SchemaFileError> {
+        match self.take(key) {
+            None => Ok(Vec::new()),
+            Some((line, Field::List(items))) => items
+                .iter()
+                .map(|s| parse_id(s).ok_or_else(|| invalid_field(line, key, "not a valid ID")))
+                .collect(),
+            Some((line, _)) => Err(invalid_field(line, key, "expected a list of IDs")),
+        }
+    }
+
+    /// Fails on the first key that was not taken.
+    fn finish(self) -> Result<(), SchemaFileError> {
+        match self.fields.into_iter().next() {
+            Some((key, line, _)) => Err(invalid_field(line, &key, "unknown key")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Builds a schema context from parsed entries.
+fn build(entries: Vec<Entry>) -> Result<SchemaContext, SchemaFileError> {
+    let mut schema = SchemaContext::new();
+    let mut declared = HashSet::new();
+    for mut entry in entries {
+        let line = entry.line;
+        let id = entry.take_id("id")?.ok_or_else(|| parse_error(line, "entry has no id"))?;
+        if !declared.insert(id) {
+            return Err(SchemaFileError::DuplicateId { line, id });
+        }
+        if let Some((_, name)) = entry.take_str("name")? {
+            schema.set_name(id, name);
+        }
+        match (entry.take_bool("deprecated")?, entry.take_id("replaced_by")?) {
+            (Some(false), Some(_)) => {
+                return Err(invalid_field(line, "replaced_by", "requires deprecated = true"));
+            }
+            (Some(true), replacement) | (None, replacement @ Some(_)) => {
+                schema.deprecate(id, replacement)
+            }
+            _ => {}
+        }
+
+        match entry.section {
+            Section::Properties => {
+                let (type_line, data_type) = entry
+                    .take_str("data_type")?
+                    .ok_or_else(|| parse_error(line, "property has no data_type"))?;
+                let data_type = parse_data_type(&data_type)
+                    .ok_or_else(|| invalid_field(type_line, "data_type", "unknown data type"))?;
+                let mut property = PropertySchema::new(data_type);
+                if let Some((line, cardinality)) = entry.take_str("cardinality")? {
+                    property.cardinality = match cardinality.as_str() {
+                        "single" => Cardinality::Single,
+                        "multi" => Cardinality::Multi,
+                        _ => {
+                            return Err(invalid_field(
+                                line,
+                                "cardinality",
+                                "expected single or multi",
+                            ))
+                        }
+                    };
+                }
+                property.required = entry.take_bool("required")?.unwrap_or(false);
+                if let Some((line, dimension)) = entry.take_str("dimension")? {
+                    property.dimension =
+                        Some(parse_dimension(&dimension).ok_or_else(|| {
+                            invalid_field(line, "dimension", "unknown dimension")
+                        })?);
+                }
+                schema.add_property_schema(id, property);
+            }
+            Section::RelationTypes => {
+                let mut relation_type = RelationTypeSchema::new();
+                relation_type.from_types = entry.take_ids("from_types")?;
+                relation_type.to_types = entry.take_ids("to_types")?;
+                if let Some((line, mode)) = entry.take_str("mode")? {
+                    relation_type.mode = match mode.as_str() {
+                        "unique" => RelationMode::Unique,
+                        "multi" => RelationMode::Multi,
+                        _ => return Err(invalid_field(line, "mode", "expected unique or multi")),
+                    };
+                }
+                schema.add_relation_type(id, relation_type);
+            }
+            Section::Types => {
+                for supertype in entry.take_ids("subtype_of")? {
+                    schema.add_subtype(id, supertype);
+                }
+                for property in entry.take_ids("properties")? {
+                    schema.add_type_property(id, property);
+                }
+            }
+        }
+        entry.finish()?;
+    }
+    Ok(schema)
+}
+
+fn parse_data_type(name: &str) -> Option<DataType> {
+    (1..=12).filter_map(DataType::from_u8).find(|t| data_type_name(*t).eq_ignore_ascii_case(name))
+}
+
+const DIMENSIONS: [(&str, Dimension); 6] = [
+    ("length", Dimension::Length),
+    ("mass", Dimension::Mass),
+    ("time", Dimension::Time),
+    ("temperature", Dimension::Temperature),
+    ("information", Dimension::Information),
+    ("currency", Dimension::Currency),
+];
+
+fn parse_dimension(name: &str) -> Option<Dimension> {
+    match DIMENSIONS.iter().find(|(n, _)| *n == name) {
+        Some((_, dimension)) => Some(*dimension),
+        None => parse_id(name).map(Dimension::Custom),
+    }
+}
+
+fn dimension_name(dimension: Dimension) -> String {
+    match dimension {
+        Dimension::Custom(id) => format_id(&id),
+        _ => DIMENSIONS.iter().find(|(_, d)| *d == dimension).unwrap().0.to_string(),
+    }
+}
+
+fn id_list(ids: &[Id]) -> Field {
+    Field::List(ids.iter().map(format_id).collect())
+}
+
+/// Quotes a string with the escapes TOML and JSON have in common.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn parse_error(line: usize, reason: &'static str) -> SchemaFileError {
+    SchemaFileError::Parse { line, reason }
+}
+
+fn invalid_field(line: usize, key: &str, reason: &'static str) -> SchemaFileError {
+    SchemaFileError::InvalidField { line, key: key.to_string(), reason }
+}
+
+fn parse_toml(s: &str) -> Result<Vec<Entry>, SchemaFileError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for (i, raw) in s.lines().enumerate() {
+        let line_no = i + 1;
+        let mut scanner = Scanner { input: raw.as_bytes(), pos: 0, first_line: line_no };
+        if scanner.at_comment_or_end() {
+            continue;
+        }
+        if scanner.consume(b'[') {
+            if !scanner.consume(b'[') {
+                return Err(parse_error(line_no, "only arrays of tables are supported"));
+            }
+            let section = Section::from_name(scanner.bare_key())
+                .ok_or_else(|| parse_error(line_no, "unknown section"))?;
+            scanner.expect(b']')?;
+            scanner.expect(b']')?;
+            entries.push(Entry::new(section, line_no));
+        } else {
+            let entry = entries
+                .last_mut()
+                .ok_or_else(|| parse_error(line_no, "key outside of a section"))?;
+            let key = match scanner.peek() {
+                Some(b'"') => scanner.string()?,
+                _ => scanner.bare_key().to_string(),
+            };
+            if key.is_empty() {
+                return Err(parse_error(line_no, "expected `key = value`"));
+            }
+            scanner.expect(b'=')?;
+            let value = scanner.value()?;
+            entry.insert(key, line_no, value)?;
+        }
+        if !scanner.at_comment_or_end() {
+            return Err(parse_error(line_no, "trailing characters"));
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_json(s: &str) -> Result<Vec<Entry>, SchemaFileError> {
+    let mut scanner = Scanner { input: s.as_bytes(), pos: 0, first_line: 1 };
+    let mut entries = Vec::new();
+    let mut seen = Vec::new();
+
+    scanner.expect(b'{')?;
+    if !scanner.consume(b'}') {
+        loop {
+            let line = scanner.line();
+            let section = Section::from_name(&scanner.string()?)
+                .ok_or_else(|| parse_error(line, "unknown section"))?;
+            if seen.contains(&section) {
+                return Err(parse_error(line, "section is set more than once"));
+            }
+            seen.push(section);
+            scanner.expect(b':')?;
+            scanner.expect(b'[')?;
+            if !scanner.consume(b']') {
+                loop {
+                    entries.push(scanner.object(section)?);
+                    if scanner.consume(b']') {
+                        break;
+                    }
+                    scanner.expect(b',')?;
+                }
+            }
+            if scanner.consume(b'}') {
+                break;
+            }
+            scanner.expect(b',')?;
+        }
+    }
+    scanner.skip_whitespace();
+    if scanner.pos != scanner.input.len() {
+        return Err(parse_error(scanner.line(), "trailing characters after object"));
+    }
+    Ok(entries)
+}
+
+/// Minimal tokenizer shared by the TOML (one line at a time) and JSON
+/// parsers.
+struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+    first_line: usize,
+}
+
+impl Scanner<'_> {
+    fn line(&self) -> usize {
+        self.first_line + self.input[..self.pos].iter().filter(|&&b| b == b'\n').count()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.pos).copied()
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SchemaFileError> {
+        if self.consume(byte) {
+            Ok(())
+        } else {
+            Err(parse_error(self.line(), "unexpected character"))
+        }
+    }
+
+    fn at_comment_or_end(&mut self) -> bool {
+        matches!(self.peek(), None | Some(b'#'))
+    }
+
+    /// Reads a run of letters, digits, `_`, and `-`.
+    fn bare_key(&mut self) -> &str {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .input
+            .get(self.pos)
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b"_-".contains(b))
+        {
+            self.pos += 1;
+        }
+        // Only ASCII was consumed
+        std::str::from_utf8(&self.input[start..self.pos]).unwrap_or_default()
+    }
+
+    fn string(&mut self) -> Result<String, SchemaFileError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        while let Some(&b) = self.input.get(self.pos) {
+            self.pos += 1;
+            match b {
+                b'"' => {
+                    return String::from_utf8(bytes)
+                        .map_err(|_| parse_error(self.line(), "invalid UTF-8"));
+                }
+                b'\\' => {
+                    let c = match self.input.get(self.pos) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self.input.get(self.pos + 1..self.pos + 5);
+                            let c = hex
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| parse_error(self.line(), "invalid \\u escape"))?;
+                            self.pos += 4;
+                            c
+                        }
+                        _ => return Err(parse_error(self.line(), "invalid escape sequence")),
+                    };
+                    self.pos += 1;
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                b'\n' => return Err(parse_error(self.line(), "unterminated string")),
+                _ => bytes.push(b),
+            }
+        }
+        Err(parse_error(self.line(), "unterminated string"))
+    }
+
+    /// Reads a string, a boolean, or an array of strings.
+    fn value(&mut self) -> Result<Field, SchemaFileError> {
+        match self.peek() {
+            Some(b'"') => Ok(Field::Str(self.string()?)),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                // A trailing comma is allowed, as in TOML
+                while !self.consume(b']') {
+                    items.push(self.string()?);
+                    if !self.consume(b',') {
+                        self.expect(b']')?;
+                        break;
+                    }
+                }
+                Ok(Field::List(items))
+            }
+            _ => {
+                let line = self.line();
+                match self.bare_key() {
+                    "true" => Ok(Field::Bool(true)),
+                    "false" => Ok(Field::Bool(false)),
+                    _ => Err(parse_error(line, "expected a string, boolean, or list")),
+                }
+            }
+        }
+    }
+
+    /// Reads a JSON object of fields as an entry of `section`.
+    fn object(&mut self, section: Section) -> Result<Entry, SchemaFileError> {
+        self.skip_whitespace();
+        let mut entry = Entry::new(section, self.line());
+        self.expect(b'{')?;
+        if !self.consume(b'}') {
+            loop {
+                let key = self.string()?;
+                let line = self.line();
+                self.expect(b':')?;
+                let value = self.value()?;
+                entry.insert(key, line, value)?;
+                if self.consume(b'}') {
+                    break;
+                }
+                self.expect(b',')?;
+            }
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGE: Id = [1u8; 16];
+    const EMAIL: Id = [2u8; 16];
+    const WORKS_AT: Id = [3u8; 16];
+    const PERSON: Id = [4u8; 16];
+    const EMPLOYEE: Id = [5u8; 16];
+    const COMPANY: Id = [6u8; 16];
+    const OLD_EMAIL: Id = [7u8; 16];
+
+    fn schema() -> SchemaContext {
+        let mut schema = SchemaContext::new();
+        schema.add_property_schema(
+            AGE,
+            PropertySchema::new(DataType::Int64).multi().required().dimension(Dimension::Time),
+        );
+        schema.add_property(EMAIL, DataType::Text);
+        schema.add_property(OLD_EMAIL, DataType::Text);
+        schema.deprecate(OLD_EMAIL, Some(EMAIL));
+        schema.add_relation_type(
+            WORKS_AT,
+            RelationTypeSchema::new().from_type(EMPLOYEE).to_type(COMPANY).unique(),
+        );
+        schema.add_subtype(EMPLOYEE, PERSON);
+        schema.add_type_property(PERSON, AGE);
+        schema.add_type_property(PERSON, EMAIL);
+        schema.set_name(AGE, "Age");
+        schema.set_name(PERSON, "Person \"quoted\"\n");
+        schema.set_name(COMPANY, "Company");
+        schema
+    }
+
+    #[test]
+    fn test_toml_and_json_roundtrip() {
+        let schema = schema();
+
+        let toml = schema.to_toml_string();
+        let parsed = SchemaContext::from_toml_str(&toml).unwrap();
+        assert_eq!(parsed.to_toml_string(), toml);
+        assert_eq!(parsed.get_property_schema(&AGE), schema.get_property_schema(&AGE));
+        assert_eq!(parsed.get_relation_type(&WORKS_AT), schema.get_relation_type(&WORKS_AT));
+        assert_eq!(parsed.resolve_replacement(&OLD_EMAIL), Some(EMAIL));
+        assert!(parsed.is_subtype_of(&EMPLOYEE, &PERSON));
+        assert_eq!(parsed.get_type_properties(&PERSON), [AGE, EMAIL]);
+        assert_eq!(parsed.get_name(&PERSON), Some("Person \"quoted\"\n"));
+
+        let json = schema.to_json_string();
+        let parsed = SchemaContext::from_json_str(&json).unwrap();
+        assert_eq!(parsed.to_json_string(), json);
+        assert_eq!(parsed.to_toml_string(), toml);
+
+        assert_eq!(SchemaContext::new().to_toml_string(), "");
+        let empty = SchemaContext::from_json_str(&SchemaContext::new().to_json_string()).unwrap();
+        assert_eq!(empty.to_toml_string(), "");
+    }
+
+    #[test]
+    fn test_parse_hand_written_toml() {
+        let schema = SchemaContext::from_toml_str(
+            "# Team schema\n\
+             [[properties]]\n\
+             id = \"01010101-0101-0101-0101-010101010101\" # age\n\
+             data_type = \"INT64\"\n\
+             \n\
+             [[types]]\n\
+             id = \"05050505050505050505050505050505\"\n\
+             subtype_of = [\n",
+        );
+        assert!(matches!(schema, Err(SchemaFileError::Parse { line: 8, .. })));
+
+        let schema = SchemaContext::from_toml_str(
+            "[[types]]\n\
+             \"id\" = \"05050505050505050505050505050505\"\n\
+             subtype_of = [\"04040404040404040404040404040404\",]\n",
+        )
+        .unwrap();
+        assert!(schema.is_subtype_of(&EMPLOYEE, &PERSON));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let id = format_id(&AGE);
+        let cases = [
+            ("[properties]\n", 1),
+            ("[[entities]]\n", 1),
+            ("id = \"x\"\n", 1),
+            ("[[properties]]\nid = \"x\"\ndata_type = \"text\"\n", 2),
+            (&format!("[[properties]]\nid = \"{id}\"\n"), 1),
+            (&format!("[[properties]]\nid = \"{id}\"\ndata_type = \"int\"\n"), 3),
+            (&format!("[[properties]]\nid = \"{id}\"\ndata_type = \"text\"\nunit = \"m\"\n"), 4),
+            (&format!("[[types]]\nid = \"{id}\"\nname = \"a\"\nname = \"b\"\n"), 4),
+            (&format!("[[types]]\nid = \"{id}\"\nname = \"a\" b\n"), 3),
+            (&format!("[[types]]\nid = \"{id}\"\ndeprecated = false\nreplaced_by = \"{id}\"\n"), 1),
+        ];
+        for (toml, line) in cases {
+            let err = SchemaContext::from_toml_str(toml).unwrap_err();
+            let err_line = match err {
+                SchemaFileError::Parse { line, .. }
+                | SchemaFileError::InvalidField { line, .. }
+                | SchemaFileError::DuplicateId { line, .. } => line,
+            };
+            assert_eq!(err_line, line, "{toml:?}: {err}");
+        }
+
+        let duplicate = format!("[[types]]\nid = \"{id}\"\n\n[[properties]]\nid = \"{id}\"\n");
+        assert_eq!(
+            SchemaContext::from_toml_str(&duplicate).unwrap_err(),
+            SchemaFileError::DuplicateId { line: 4, id: AGE }
+        );
+
+        assert!(matches!(
+            SchemaContext::from_json_str("{\"types\": [\n{\"id\": \"zz\"}\n]}"),
+            Err(SchemaFileError::InvalidField { line: 2, .. })
+        ));
+        assert!(matches!(
+            SchemaContext::from_json_str("{\"types\": [], \"types\": []}"),
+            Err(SchemaFileError::Parse { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! Units of measure and their dimensions.
+//!
+//! INT64, FLOAT64, and DECIMAL values may name a unit entity. The protocol
+//! does not interpret units, but a [`SchemaContext`] can declare the
+//! [`Dimension`] a property measures (see [`PropertySchema::dimension`]);
+//! validation then checks each value's unit against it using the context's
+//! [`UnitRegistry`].
+//!
+//! [`SchemaContext`]: crate::validate::SchemaContext
+//! [`PropertySchema::dimension`]: crate::validate::PropertySchema::dimension
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(feature = "std")]
+use crate::genesis::units;
+use crate::model::Id;
+
+/// The physical or economic quantity a unit measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    /// Distance (meters, miles, ...).
+    Length,
+    /// Mass (grams, pounds, ...).
+    Mass,
+    /// Duration (seconds, hours, ...).
+    Time,
+    /// Temperature (kelvin, degrees Celsius, ...).
+    Temperature,
+    /// Amounts of data (bytes).
+    Information,
+    /// Money, in any currency.
+    Currency,
+    /// An application-defined dimension, named by an entity ID.
+    Custom(Id),
+}
+
+/// Maps unit IDs to the dimension they measure.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitRegistry {
+    units: HashMap<Id, Dimension>,
+}
+
+#[cfg(feature = "std")]
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl UnitRegistry {
+    /// Creates a registry holding the genesis units.
+    pub fn new() -> Self {
+        let genesis = [
+            (*units::METER, Dimension::Length),
+            (*units::KILOMETER, Dimension::Length),
+            (*units::CENTIMETER, Dimension::Length),
+            (*units::MILE, Dimension::Length),
+            (*units::FOOT, Dimension::Length),
+            (*units::GRAM, Dimension::Mass),
+            (*units::KILOGRAM, Dimension::Mass),
+            (*units::POUND, Dimension::Mass),
+            (*units::SECOND, Dimension::Time),
+            (*units::MINUTE, Dimension::Time),
+            (*units::HOUR, Dimension::Time),
+            (*units::DAY, Dimension::Time),
+            (*units::KELVIN, Dimension::Temperature),
+            (*units::CELSIUS, Dimension::Temperature),
+            (*units::FAHRENHEIT, Dimension::Temperature),
+            (*units::BYTE, Dimension::Information),
+            (*units::USD, Dimension::Currency),
+            (*units::EUR, Dimension::Currency),
+            (*units::GBP, Dimension::Currency),
+            (*units::JPY, Dimension::Currency),
+        ];
+        Self {
+            units: genesis.into_iter().collect(),
+        }
+    }
+
+    /// Creates a registry with no units.
+    pub fn empty() -> Self {
+        Self { units: HashMap::new() }
+    }
+
+    /// Registers a unit, replacing any previous dimension for it.
+    pub fn add_unit(&mut self, unit: Id, dimension: Dimension) {
+        self.units.insert(unit, dimension);
+    }
+
+    /// Gets the dimension of a unit, if registered.
+    pub fn dimension(&self, unit: &Id) -> Option<Dimension> {
+        self.units.get(unit).copied()
+    }
+
+    /// Returns the number of registered units.
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    /// Returns true if no units are registered.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_units() {
+        let mut registry = UnitRegistry::new();
+        assert_eq!(registry.dimension(&units::meter()), Some(Dimension::Length));
+        assert_eq!(registry.dimension(&units::usd()), Some(Dimension::Currency));
+        assert_eq!(registry.dimension(&units::from_symbol("parsec")), None);
+
+        let parsec = units::from_symbol("parsec");
+        registry.add_unit(parsec, Dimension::Length);
+        assert_eq!(registry.dimension(&parsec), Some(Dimension::Length));
+        assert!(UnitRegistry::empty().is_empty());
+    }
+}
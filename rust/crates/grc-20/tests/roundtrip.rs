@@ -0,0 +1,405 @@
+//! Property-based roundtrip tests: `decode(encode(x)) == x` for generated
+//! values, ops, and edits.
+//!
+//! Generators only produce input the encoder accepts (normalized decimals,
+//! in-range points, zero-padded binary embeddings, and so on), so every
+//! case must roundtrip. Each property ID has a fixed data type, derived from
+//! its ID byte, because one edit cannot use a property with two types.
+
+use std::borrow::Cow;
+
+use grc_20::codec::primitives::{Reader, Writer};
+use grc_20::codec::value::{decode_value, encode_value};
+use grc_20::limits::MAX_EMBEDDING_DIMS;
+use grc_20::model::{
+    CreateEntity, CreateRelation, CreateValueRef, DataType, DecimalMantissa, DeleteEntity,
+    DeleteRelation, DictionaryBuilder, Edit, EmbeddingSubType, Id, Op, PropertyValue, Provenance,
+    RestoreEntity, RestoreRelation, UnsetLanguage, UnsetRelationField, UnsetValue, UpdateEntity,
+    UpdateRelation, Value,
+};
+use grc_20::{decode_edit, encode_edit, encode_edit_with_options, EncodeOptions};
+use proptest::prelude::*;
+
+const DATA_TYPES: [DataType; 12] = [
+    DataType::Bool,
+    DataType::Int64,
+    DataType::Float64,
+    DataType::Decimal,
+    DataType::Text,
+    DataType::Bytes,
+    DataType::Date,
+    DataType::Time,
+    DataType::Datetime,
+    DataType::Schedule,
+    DataType::Point,
+    DataType::Embedding,
+];
+
+/// Object, language, and unit IDs, drawn from a small pool so that
+/// dictionary entries are shared across ops.
+fn id() -> impl Strategy<Value = Id> {
+    (0u8..8).prop_map(|b| [b; 16])
+}
+
+/// Property IDs, disjoint from [`id`]; the data type follows from the ID.
+fn property() -> impl Strategy<Value = Id> {
+    (0u8..24).prop_map(|b| [0x80 + b; 16])
+}
+
+fn property_type(property: &Id) -> DataType {
+    DATA_TYPES[(property[0] - 0x80) as usize % DATA_TYPES.len()]
+}
+
+fn text_property() -> impl Strategy<Value = Id> {
+    property().prop_filter("TEXT property", |p| property_type(p) == DataType::Text)
+}
+
+fn decimal() -> impl Strategy<Value = Value<'static>> {
+    let small = any::<i64>().prop_map(DecimalMantissa::I64);
+    // Two's complement big-endian bytes, up to 40 long
+    let big = prop::collection::vec(any::<u8>(), 1..40)
+        .prop_map(|bytes| DecimalMantissa::Big(Cow::Owned(minimal_mantissa(bytes))));
+    (prop_oneof![small, big], any::<i32>(), prop::option::of(id())).prop_map(
+        |(mantissa, exponent, unit)| {
+            let (mantissa, zero) = normalize_mantissa(mantissa);
+            let exponent = if zero { 0 } else { exponent };
+            Value::Decimal { exponent, mantissa, unit }
+        },
+    )
+}
+
+/// Strips redundant sign-extension bytes.
+fn minimal_mantissa(mut bytes: Vec<u8>) -> Vec<u8> {
+    while let [first, second, ..] = bytes[..] {
+        if !((first == 0x00 && second & 0x80 == 0) || (first == 0xFF && second & 0x80 != 0)) {
+            break;
+        }
+        bytes.remove(0);
+    }
+    bytes
+}
+
+/// Removes trailing decimal zeros; returns the mantissa and whether it is 0.
+fn normalize_mantissa(mantissa: DecimalMantissa<'static>) -> (DecimalMantissa<'static>, bool) {
+    match mantissa {
+        DecimalMantissa::I64(mut m) => {
+            while m != 0 && m % 10 == 0 {
+                m /= 10;
+            }
+            (DecimalMantissa::I64(m), m == 0)
+        }
+        DecimalMantissa::Big(bytes) => {
+            let mut bytes = bytes.into_owned();
+            if bytes.iter().all(|&b| b == 0) {
+                return (DecimalMantissa::Big(Cow::Owned(vec![0])), true);
+            }
+            // Flipping the low bit moves the value by one, off a multiple of 10
+            if big_mod_10(&bytes) == 0 {
+                *bytes.last_mut().unwrap() ^= 1;
+            }
+            (DecimalMantissa::Big(Cow::Owned(minimal_mantissa(bytes))), false)
+        }
+    }
+}
+
+fn big_mod_10(bytes: &[u8]) -> u32 {
+    let unsigned = bytes.iter().fold(0, |r, &b| (r * 256 + b as u32) % 10);
+    if bytes[0] & 0x80 == 0 {
+        return unsigned;
+    }
+    let modulus = bytes.iter().fold(1, |r, _| r * 256 % 10);
+    (unsigned + 10 - modulus) % 10
+}
+
+fn date() -> impl Strategy<Value = String> {
+    // Negative years are BCE (astronomical numbering)
+    (-9999i32..=9999, 1u32..=12, 1u32..=28, 0usize..3).prop_map(|(y, m, d, precision)| {
+        let year = if y < 0 { format!("-{:04}", -y) } else { format!("{:04}", y) };
+        match precision {
+            0 => year,
+            1 => format!("{}-{:02}", year, m),
+            _ => format!("{}-{:02}-{:02}", year, m, d),
+        }
+    })
+}
+
+fn time() -> impl Strategy<Value = String> {
+    let offset = prop_oneof![
+        Just("Z".to_string()),
+        (-12i32..=14, prop::sample::select(vec![0u32, 30, 45])).prop_map(|(h, m)| {
+            format!("{}{:02}:{:02}", if h < 0 { '-' } else { '+' }, h.abs(), m)
+        }),
+    ];
+    (0u32..24, 0u32..60, 0u32..60, prop::option::of(0u32..1000), offset).prop_map(
+        |(h, m, s, millis, offset)| match millis {
+            Some(ms) => format!("{:02}:{:02}:{:02}.{:03}{}", h, m, s, ms, offset),
+            None => format!("{:02}:{:02}:{:02}{}", h, m, s, offset),
+        },
+    )
+}
+
+fn schedule() -> impl Strategy<Value = String> {
+    let freq = prop::sample::select(vec!["DAILY", "WEEKLY", "MONTHLY", "YEARLY"]);
+    (1970u32..2100, 1u32..=12, 1u32..=28, 0u32..24, freq, 1u32..50).prop_map(
+        |(y, m, d, h, freq, count)| {
+            format!(
+                "DTSTART:{:04}{:02}{:02}T{:02}0000Z\r\nRRULE:FREQ={};COUNT={}",
+                y, m, d, h, freq, count
+            )
+        },
+    )
+}
+
+fn embedding() -> impl Strategy<Value = Value<'static>> {
+    let dims = prop_oneof![4 => 0usize..64, 1 => Just(MAX_EMBEDDING_DIMS)];
+    let sub_type = prop::sample::select(vec![
+        EmbeddingSubType::Float32,
+        EmbeddingSubType::Int8,
+        EmbeddingSubType::Binary,
+    ]);
+    (sub_type, dims, any::<u64>()).prop_map(|(sub_type, dims, seed)| {
+        // Bytes from a cheap generator: max-dims vectors are too large to
+        // draw element by element
+        let mut state = seed | 1;
+        let mut data: Vec<u8> = (0..sub_type.bytes_for_dims(dims))
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        match sub_type {
+            EmbeddingSubType::Float32 => {
+                for chunk in data.chunks_exact_mut(4) {
+                    let f = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                    if f.is_nan() {
+                        chunk.copy_from_slice(&0f32.to_le_bytes());
+                    }
+                }
+            }
+            EmbeddingSubType::Binary if dims % 8 != 0 => {
+                *data.last_mut().unwrap() &= (1u8 << (dims % 8)) - 1;
+            }
+            _ => {}
+        }
+        Value::Embedding { sub_type, dims, data: Cow::Owned(data) }
+    })
+}
+
+fn finite() -> impl Strategy<Value = f64> {
+    any::<f64>().prop_filter("not NaN", |f| !f.is_nan())
+}
+
+fn value_of(data_type: DataType) -> BoxedStrategy<Value<'static>> {
+    match data_type {
+        DataType::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+        DataType::Int64 => (any::<i64>(), prop::option::of(id()))
+            .prop_map(|(value, unit)| Value::Int64 { value, unit })
+            .boxed(),
+        DataType::Float64 => (finite(), prop::option::of(id()))
+            .prop_map(|(value, unit)| Value::Float64 { value, unit })
+            .boxed(),
+        DataType::Decimal => decimal().boxed(),
+        DataType::Text => (any::<String>(), prop::option::of(id()))
+            .prop_map(|(value, language)| Value::Text { value: Cow::Owned(value), language })
+            .boxed(),
+        DataType::Bytes => prop::collection::vec(any::<u8>(), 0..64)
+            .prop_map(|bytes| Value::Bytes(Cow::Owned(bytes)))
+            .boxed(),
+        DataType::Date => date().prop_map(|s| Value::Date(Cow::Owned(s))).boxed(),
+        DataType::Time => time().prop_map(|s| Value::Time(Cow::Owned(s))).boxed(),
+        DataType::Datetime => (date(), time())
+            .prop_map(|(date, time)| Value::Datetime(Cow::Owned(format!("{}T{}", date, time))))
+            .boxed(),
+        DataType::Schedule => schedule().prop_map(|s| Value::Schedule(Cow::Owned(s))).boxed(),
+        DataType::Point => (-180.0f64..=180.0, -90.0f64..=90.0, prop::option::of(finite()))
+            .prop_map(|(lon, lat, alt)| Value::Point { lon, lat, alt })
+            .boxed(),
+        DataType::Embedding => embedding().boxed(),
+    }
+}
+
+fn property_value() -> impl Strategy<Value = PropertyValue<'static>> {
+    property().prop_flat_map(|property| {
+        (value_of(property_type(&property)), prop::option::of(1..=u64::MAX))
+            .prop_map(move |(value, clock)| PropertyValue { property, value, clock })
+    })
+}
+
+fn position() -> impl Strategy<Value = Option<Cow<'static, str>>> {
+    let chars: Vec<char> = ('0'..='9').chain('A'..='Z').chain('a'..='z').collect();
+    prop::option::of(
+        prop::collection::vec(prop::sample::select(chars), 1..=64)
+            .prop_map(|chars| Cow::Owned(chars.into_iter().collect())),
+    )
+}
+
+fn unset_value() -> impl Strategy<Value = UnsetValue> {
+    let language = prop_oneof![
+        Just(UnsetLanguage::All),
+        Just(UnsetLanguage::English),
+        id().prop_map(UnsetLanguage::Specific),
+    ];
+    (property(), language).prop_map(|(property, language)| UnsetValue { property, language })
+}
+
+fn op() -> impl Strategy<Value = Op<'static>> {
+    let values = || prop::collection::vec(property_value(), 0..6);
+    let pins = || (prop::option::of(id()), prop::option::of(id()));
+    prop_oneof![
+        (id(), values()).prop_map(|(id, values)| Op::CreateEntity(CreateEntity { id, values })),
+        (
+            id(),
+            values(),
+            prop::collection::vec(unset_value(), 0..4),
+            prop::collection::vec(id(), 0..3),
+        )
+            .prop_map(|(id, set_properties, unset_values, remove_values)| {
+                Op::UpdateEntity(UpdateEntity { id, set_properties, unset_values, remove_values })
+            }),
+        id().prop_map(|id| Op::DeleteEntity(DeleteEntity { id })),
+        id().prop_map(|id| Op::RestoreEntity(RestoreEntity { id })),
+        (
+            (id(), id(), id(), id()),
+            (any::<bool>(), any::<bool>()),
+            (pins(), pins()),
+            (prop::option::of(id()), position()),
+        )
+            .prop_map(
+                |(
+                    (id, relation_type, from, to),
+                    (from_is_value_ref, to_is_value_ref),
+                    ((from_space, from_version), (to_space, to_version)),
+                    (entity, position),
+                )| {
+                    Op::CreateRelation(CreateRelation {
+                        id,
+                        relation_type,
+                        from,
+                        from_is_value_ref,
+                        from_space,
+                        from_version,
+                        to,
+                        to_is_value_ref,
+                        to_space,
+                        to_version,
+                        entity,
+                        position,
+                    })
+                }
+            ),
+        (id(), pins(), pins(), position(), prop::collection::vec(any::<bool>(), 5)).prop_map(
+            |(id, (from_space, from_version), (to_space, to_version), position, flags)| {
+                // Unset fields travel as a bitmask: no duplicates, wire order
+                let fields = [
+                    UnsetRelationField::FromSpace,
+                    UnsetRelationField::FromVersion,
+                    UnsetRelationField::ToSpace,
+                    UnsetRelationField::ToVersion,
+                    UnsetRelationField::Position,
+                ];
+                let unset = fields.into_iter().zip(flags).filter(|(_, f)| *f).map(|(u, _)| u);
+                Op::UpdateRelation(UpdateRelation {
+                    id,
+                    from_space,
+                    from_version,
+                    to_space,
+                    to_version,
+                    position,
+                    unset: unset.collect(),
+                })
+            }
+        ),
+        id().prop_map(|id| Op::DeleteRelation(DeleteRelation { id })),
+        id().prop_map(|id| Op::RestoreRelation(RestoreRelation { id })),
+        (id(), id(), property(), prop::option::of(id())).prop_map(
+            |(id, entity, property, space)| {
+                Op::CreateValueRef(CreateValueRef { id, entity, property, language: None, space })
+            }
+        ),
+        (id(), id(), text_property(), id(), prop::option::of(id())).prop_map(
+            |(id, entity, property, language, space)| {
+                let language = Some(language);
+                Op::CreateValueRef(CreateValueRef { id, entity, property, language, space })
+            }
+        ),
+    ]
+}
+
+fn provenance() -> impl Strategy<Value = Provenance<'static>> {
+    (
+        prop::option::of(id()),
+        prop::collection::btree_set(id(), 0..4),
+        prop::collection::vec((any::<String>(), any::<String>()), 0..4),
+    )
+        .prop_map(|(source, parents, metadata)| Provenance {
+            source,
+            parents: parents.into_iter().collect(),
+            metadata: metadata.into_iter().map(|(k, v)| (Cow::Owned(k), Cow::Owned(v))).collect(),
+        })
+}
+
+fn edit() -> impl Strategy<Value = Edit<'static>> {
+    (
+        any::<[u8; 16]>(),
+        any::<String>(),
+        prop::collection::btree_set(any::<[u8; 16]>(), 0..4),
+        any::<i64>(),
+        // Empty provenance is written as none, so it doesn't roundtrip
+        prop::option::of(provenance().prop_filter("empty", |p| !p.is_empty())),
+        prop::collection::vec(op(), 0..24),
+    )
+        .prop_map(|(id, name, authors, created_at, provenance, ops)| Edit {
+            id,
+            name: Cow::Owned(name),
+            authors: authors.into_iter().collect(),
+            created_at,
+            provenance,
+            ops,
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn value_roundtrip(pv in property_value()) {
+        let mut dict_builder = DictionaryBuilder::new();
+        let mut writer = Writer::new();
+        encode_value(&mut writer, &pv.value, &mut dict_builder).unwrap();
+        let bytes = writer.into_bytes();
+
+        let dicts = dict_builder.build();
+        let mut reader = Reader::new(&bytes);
+        let decoded = decode_value(&mut reader, pv.value.data_type(), &dicts).unwrap();
+        prop_assert!(reader.is_empty());
+        prop_assert_eq!(decoded, pv.value);
+    }
+
+    #[test]
+    fn op_roundtrip(op in op()) {
+        let mut edit = Edit::new([0xEE; 16]);
+        edit.ops.push(op);
+        let bytes = encode_edit(&edit).unwrap();
+        prop_assert_eq!(decode_edit(&bytes).unwrap(), edit);
+    }
+
+    #[test]
+    fn edit_roundtrip(edit in edit()) {
+        let bytes = encode_edit(&edit).unwrap();
+        prop_assert_eq!(&decode_edit(&bytes).unwrap(), &edit);
+
+        // The string table and columnar records decode transparently
+        let compact = EncodeOptions::new().with_interned_strings().with_columnar();
+        let bytes = encode_edit_with_options(&edit, compact).unwrap();
+        prop_assert_eq!(&decode_edit(&bytes).unwrap(), &edit);
+
+        // Canonical encoding may reorder and dedupe, but is a fixed point
+        if let Ok(canonical) = encode_edit_with_options(&edit, EncodeOptions::canonical()) {
+            let decoded = decode_edit(&canonical).unwrap();
+            let again = encode_edit_with_options(&decoded, EncodeOptions::canonical()).unwrap();
+            prop_assert_eq!(again, canonical);
+        }
+    }
+}